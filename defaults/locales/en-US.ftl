@@ -0,0 +1,92 @@
+# The English text of the UI, which is used for anything a catalog of another
+# language leaves out. Catalogs of other languages go in the locales folder of
+# Lapce, named after their language like `de-DE.ftl`, and are reloaded when
+# they change.
+#
+# Commands are named `command-` and the name of the command, like
+# `command-toggle_terminal_focus`, and are shown with their English description
+# when they aren't in the catalog.
+
+panel-open-editors = Open Editors
+panel-file-explorer = File Explorer
+panel-installed = Installed
+panel-available = Available
+panel-undo-tree = Undo Tree
+panel-processes = Processes
+panel-variables = Variables
+panel-stack-frames = Stack Frames
+panel-breakpoints = Breakpoints
+panel-errors = Errors
+panel-warnings = Warnings
+panel-changes = Changes
+
+find-no-results = No Results
+find-match-count = { $current } of { $all } matches
+
+mode-normal = Normal
+mode-insert = Insert
+mode-visual = Visual
+mode-visual-line = Visual Line
+mode-visual-block = Visual Block
+mode-terminal = Terminal
+
+menu-file = File
+menu-edit = Edit
+menu-settings = Settings...
+menu-about-lapce = About Lapce
+menu-quit-lapce = Quit Lapce
+menu-hide-lapce = Hide Lapce
+menu-hide-others = Hide Others
+menu-show-all = Show All
+menu-new-file = New File
+menu-open = Open
+menu-open-folder = Open Folder
+menu-open-recent-workspace = Open Recent Workspace
+menu-save = Save
+menu-save-all = Save All
+menu-close-folder = Close Folder
+menu-close-window = Close Window
+menu-cut = Cut
+menu-copy = Copy
+menu-paste = Paste
+menu-paste-without-formatting = Paste without Formatting
+menu-duplicate = Duplicate
+menu-copy-path = Copy Path
+menu-copy-relative-path = Copy Relative Path
+menu-undo = Undo
+menu-redo = Redo
+menu-find = Find
+menu-refactor = Refactor
+menu-code-actions = Code Actions
+menu-command-palette = Command Palette
+menu-open-settings = Open Settings
+menu-open-keyboard-shortcuts = Open Keyboard Shortcuts
+menu-open-theme-color-settings = Open Theme Color Settings
+menu-update-in-progress = Update in progress ({ $version })
+menu-restart-to-update = Restart to update ({ $version })
+menu-no-update-available = No update available
+menu-connect-to-ssh-host = Connect to SSH Host
+menu-attach-to-running-container = Attach to Running Container
+menu-connect-to-wsl = Connect to WSL
+menu-upgrade-plugin = Upgrade Plugin
+menu-reload-plugin = Reload Plugin
+menu-enable = Enable
+menu-disable = Disable
+menu-enable-for-workspace = Enable For Workspace
+menu-disable-for-workspace = Disable For Workspace
+menu-revoke-permissions = Revoke Permissions
+menu-uninstall = Uninstall
+menu-discard-changes = Discard Changes
+
+notification-center = Notifications
+notification-clear-all = Clear All
+notification-none = No notifications
+notification-save-failed = Save Failed
+notification-save-failed-message = Couldn't save { $path }: { $error }
+notification-compare-with-file = Compare with File
+notification-compare-with-file-remote = Picking a file to compare with isn't supported in remote workspaces yet.
+notification-performance-trace = Performance Trace
+notification-performance-trace-empty = Nothing has been recorded yet, show the performance overlay while reproducing the slowness first.
+notification-performance-trace-written = Wrote { $count } spans to { $path }
+notification-performance-trace-failed = Couldn't write the trace: { $error }
+notification-open-logs-directory = Open Logs Directory
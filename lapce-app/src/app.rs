@@ -32,9 +32,9 @@
     view::View,
     views::{
         clip, container, container_box, drag_resize_window_area, drag_window_area,
-        empty, label, list, rich_text, scroll::scroll, stack, svg, tab, text,
-        virtual_list, Decorators, VirtualListDirection, VirtualListItemSize,
-        VirtualListVector,
+        dyn_container, empty, label, list, rich_text, scroll::scroll, stack, svg,
+        tab, text, virtual_list, Decorators, VirtualListDirection,
+        VirtualListItemSize, VirtualListVector,
     },
     window::{ResizeDirection, WindowConfig, WindowId},
     EventPropagation,
@@ -49,7 +49,7 @@
     file::PathObject,
     RpcMessage,
 };
-use lsp_types::{CompletionItemKind, MessageType, ShowMessageParams};
+use lsp_types::CompletionItemKind;
 use notify::Watcher;
 use serde::{Deserialize, Serialize};
 use tracing::{error, metadata::LevelFilter, trace};
@@ -58,18 +58,22 @@
 
 use crate::{
     about, alert,
-    code_action::CodeActionStatus,
+    code_action::{CodeActionPreviewLine, CodeActionStatus},
+    color_picker,
     command::{
         CommandKind, InternalCommand, LapceCommand, LapceWorkbenchCommand,
         WindowCommand,
     },
     config::{
-        color::LapceColor, icon::LapceIcons, watcher::ConfigWatcher, LapceConfig,
+        color::LapceColor, icon::LapceIcons, os_accessibility, os_theme,
+        watcher::ConfigWatcher, LapceConfig,
     },
+    crash,
     db::LapceDb,
     debug::RunDebugMode,
     editor::{
         diff::diff_show_more_section_view,
+        image_preview::{image_preview_view, is_image_path},
         location::{EditorLocation, EditorPosition},
         view::editor_container_view,
     },
@@ -79,19 +83,23 @@
     keymap::keymap_view,
     keypress::keymap::KeyMap,
     listener::Listener,
+    localization::tr,
     main_split::{SplitContent, SplitData, SplitDirection, SplitMoveDirection},
     markdown::MarkdownContent,
+    notification::notification_view,
     palette::{
         item::{PaletteItem, PaletteItemContent},
         PaletteStatus,
     },
     panel::{position::PanelContainerPosition, view::panel_container_view},
+    perf,
     plugin::{plugin_info_view, PluginData},
     settings::{settings_view, theme_color_settings_view},
     status::status,
     text_input::text_input,
     title::{title, window_controls_view},
     update::ReleaseInfo,
+    welcome::welcome_view,
     window::{TabsInfo, WindowData, WindowInfo},
     window_tab::{Focus, WindowTabData},
     workspace::{LapceWorkspace, LapceWorkspaceType},
@@ -108,14 +116,42 @@ struct Cli {
     /// Don't return instantly when opened in a terminal
     #[clap(short, long, action)]
     wait: bool,
+    /// Always read `:line:column` at the end of a path as the position to go
+    /// to, even when a file is named like that
+    #[clap(short, long, action)]
+    goto: bool,
 
     /// Paths to file(s) and/or folder(s) to open.
     /// When path is a file (that exists or not),
     /// it accepts `path:line:column` syntax
-    /// to specify line and column at which it should open the file
-    #[clap(value_parser = lapce_proxy::cli::parse_file_line_column)]
+    /// to specify line and column at which it should open the file.
+    /// `-` reads standard input into a new untitled file
     #[clap(value_hint = clap::ValueHint::AnyPath)]
-    paths: Vec<PathObject>,
+    paths: Vec<String>,
+}
+
+impl Cli {
+    /// The paths to open, and whether standard input is read into a new file.
+    fn paths(&self) -> (Vec<PathObject>, bool) {
+        let mut stdin = false;
+        let mut paths = Vec::new();
+        for path in &self.paths {
+            if path == "-" {
+                stdin = true;
+                continue;
+            }
+            let path = if self.goto {
+                lapce_proxy::cli::parse_position(path)
+            } else {
+                lapce_proxy::cli::parse_file_line_column(path)
+            };
+            match path {
+                Ok(path) => paths.push(path),
+                Err(e) => error!("invalid path: {e}"),
+            }
+        }
+        (paths, stdin)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -127,9 +163,14 @@ pub struct AppInfo {
 pub enum AppCommand {
     SaveApp,
     NewWindow,
+    /// Open a new window with the workspace in it, like when moving a window
+    /// tab out of its window.
+    NewWindowWithWorkspace(LapceWorkspace),
     CloseWindow(WindowId),
     WindowGotFocus(WindowId),
     WindowClosed(WindowId),
+    /// Quit, once the unsaved changes of every window were asked about.
+    Quit,
 }
 
 #[derive(Clone)]
@@ -177,6 +218,10 @@ fn default_window_config(&self) -> WindowConfig {
     }
 
     pub fn new_window(&self) {
+        self.new_window_with_workspace(LapceWorkspace::default());
+    }
+
+    pub fn new_window_with_workspace(&self, workspace: LapceWorkspace) {
         let config = self
             .active_window()
             .map(|window| {
@@ -213,7 +258,7 @@ pub fn new_window(&self) {
                         maximised: false,
                         tabs: TabsInfo {
                             active_tab: 0,
-                            workspaces: vec![LapceWorkspace::default()],
+                            workspaces: vec![workspace.clone()],
                         },
                     },
                 )
@@ -241,6 +286,7 @@ pub fn run_app_command(&self, cmd: AppCommand) {
                     .try_update(|windows| windows.remove(&window_id))
                     .unwrap();
                 if let Some(window_data) = window_data {
+                    window_data.back_up_unsaved();
                     window_data.scope.dispose();
                 }
                 let _ = db.save_app(self);
@@ -251,9 +297,18 @@ pub fn run_app_command(&self, cmd: AppCommand) {
             AppCommand::NewWindow => {
                 self.new_window();
             }
+            AppCommand::NewWindowWithWorkspace(workspace) => {
+                self.new_window_with_workspace(workspace);
+            }
             AppCommand::WindowGotFocus(window_id) => {
                 self.active_window.set(window_id);
             }
+            AppCommand::Quit => {
+                let windows = self
+                    .windows
+                    .with_untracked(|windows| windows.values().cloned().collect());
+                confirm_quit(windows);
+            }
         }
     }
 
@@ -261,6 +316,7 @@ fn create_windows(
         &self,
         db: Arc<LapceDb>,
         paths: Vec<PathObject>,
+        stdin: Option<String>,
     ) -> floem::Application {
         let mut app = floem::Application::new();
 
@@ -280,14 +336,25 @@ fn create_windows(
 
             for dir in dirs {
                 #[cfg(windows)]
-                let workspace_type = if !std::env::var("WSL_DISTRO_NAME")
-                    .unwrap_or_default()
-                    .is_empty()
-                    || !std::env::var("WSL_INTEROP").unwrap_or_default().is_empty()
-                {
-                    LapceWorkspaceType::RemoteWSL
-                } else {
-                    LapceWorkspaceType::Local
+                let workspace_type = {
+                    let distro =
+                        std::env::var("WSL_DISTRO_NAME").unwrap_or_default();
+                    let distro = if !distro.is_empty() {
+                        Some(distro)
+                    } else if !std::env::var("WSL_INTEROP")
+                        .unwrap_or_default()
+                        .is_empty()
+                    {
+                        crate::proxy::wsl::WslDistro::default_name()
+                    } else {
+                        None
+                    };
+                    match distro {
+                        Some(distro) => LapceWorkspaceType::RemoteWSL(
+                            crate::workspace::WslHost { distro },
+                        ),
+                        None => LapceWorkspaceType::Local,
+                    }
                 };
                 #[cfg(not(windows))]
                 let workspace_type = LapceWorkspaceType::Local;
@@ -301,6 +368,7 @@ fn create_windows(
                         workspaces: vec![LapceWorkspace {
                             kind: workspace_type,
                             path: Some(dir.path.to_owned()),
+                            folders: Vec::new(),
                             last_open: 0,
                         }],
                     },
@@ -411,6 +479,9 @@ fn create_windows(
                     },
                 });
             }
+            if let Some(content) = stdin {
+                window_tab.main_split.new_file_with_content(content);
+            }
         }
 
         app
@@ -557,6 +628,20 @@ fn app_view(&self, window_id: WindowId, info: WindowInfo) -> impl View {
     }
 }
 
+/// Ask about the unsaved changes of the windows one after the other, and quit
+/// once that's done for all of them.
+fn confirm_quit(mut windows: Vec<WindowData>) {
+    let Some(window) = windows.pop() else {
+        floem::quit_app();
+        return;
+    };
+    let closing = window.closing;
+    window.confirm_close(Rc::new(move || {
+        closing.set(true);
+        confirm_quit(windows.clone());
+    }));
+}
+
 fn editor_tab_header(
     window_tab_data: Rc<WindowTabData>,
     active_editor_tab: ReadSignal<Option<EditorTabId>>,
@@ -1051,11 +1136,31 @@ fn editor_tab_content(
                         }
                     };
                     let editor_data = create_rw_signal(editor_data);
-                    container_box(editor_container_view(
-                        window_tab_data.clone(),
-                        workspace.clone(),
-                        is_active,
-                        editor_data,
+                    let image_path = create_memo(move |_| {
+                        editor_data.with(|editor| {
+                            editor.view.doc.get().content.with(|content| {
+                                content
+                                    .path()
+                                    .filter(|path| is_image_path(path))
+                                    .cloned()
+                            })
+                        })
+                    });
+                    let window_tab_data = window_tab_data.clone();
+                    let workspace = workspace.clone();
+                    container_box(dyn_container(
+                        move || image_path.get(),
+                        move |image_path| match image_path {
+                            Some(path) => {
+                                Box::new(image_preview_view(path, common.clone()))
+                            }
+                            None => Box::new(editor_container_view(
+                                window_tab_data.clone(),
+                                workspace.clone(),
+                                is_active,
+                                editor_data,
+                            )),
+                        },
                     ))
                 } else {
                     container_box(text("emtpy editor"))
@@ -1219,6 +1324,7 @@ fn editor_tab(
     let internal_command = main_split.common.internal_command;
     let tab_size = create_rw_signal(Size::ZERO);
     let drag_over: RwSignal<Option<DragOverPosition>> = create_rw_signal(None);
+    let dragging_file = window_tab_data.file_explorer.dragging;
     stack((
         editor_tab_header(
             window_tab_data.clone(),
@@ -1289,7 +1395,9 @@ fn editor_tab(
             }),
             empty()
                 .on_event_stop(EventListener::DragOver, move |event| {
-                    if dragging.with_untracked(|dragging| dragging.is_some()) {
+                    if dragging.with_untracked(|dragging| dragging.is_some())
+                        || dragging_file.with_untracked(|path| path.is_some())
+                    {
                         if let Event::PointerMove(pointer_event) = event {
                             let size = tab_size.get_untracked();
                             let pos = pointer_event.pos;
@@ -1314,7 +1422,33 @@ fn editor_tab(
                     drag_over.set(None);
                 })
                 .on_event(EventListener::Drop, move |_| {
-                    if let Some((from_index, from_editor_tab_id)) =
+                    if let Some(path) = dragging_file.get_untracked() {
+                        if let Some(pos) = drag_over.get_untracked() {
+                            let split = match pos {
+                                DragOverPosition::Top => {
+                                    Some(SplitMoveDirection::Up)
+                                }
+                                DragOverPosition::Bottom => {
+                                    Some(SplitMoveDirection::Down)
+                                }
+                                DragOverPosition::Left => {
+                                    Some(SplitMoveDirection::Left)
+                                }
+                                DragOverPosition::Right => {
+                                    Some(SplitMoveDirection::Right)
+                                }
+                                DragOverPosition::Middle => None,
+                            };
+                            main_split.open_file_in_editor_tab(
+                                path,
+                                editor_tab_id,
+                                split,
+                            );
+                        }
+                        dragging_file.set(None);
+                        drag_over.set(None);
+                        EventPropagation::Stop
+                    } else if let Some((from_index, from_editor_tab_id)) =
                         dragging.get_untracked()
                     {
                         if let Some(pos) = drag_over.get_untracked() {
@@ -1791,12 +1925,16 @@ fn main_split(window_tab_data: Rc<WindowTabData>) -> impl View {
     let plugin = window_tab_data.plugin.clone();
     let dragging: RwSignal<Option<(RwSignal<usize>, EditorTabId)>> =
         create_rw_signal(None);
-    split_list(
-        root_split,
-        window_tab_data.clone(),
-        plugin.clone(),
-        dragging,
-    )
+    stack((
+        split_list(
+            root_split,
+            window_tab_data.clone(),
+            plugin.clone(),
+            dragging,
+        )
+        .style(|s| s.size_full()),
+        welcome_view(window_tab_data.clone()),
+    ))
     .style(move |s| {
         let config = config.get();
         let is_hidden = panel.panel_bottom_maximized(true)
@@ -1885,7 +2023,11 @@ fn workbench(window_tab_data: Rc<WindowTabData>) -> impl View {
             .style(|s| s.flex_col().size_full())
         },
         panel_container_view(window_tab_data.clone(), PanelContainerPosition::Right),
-        window_message_view(window_tab_data.messages, window_tab_data.common.config),
+        notification_view(
+            window_tab_data.notification.clone(),
+            window_tab_data.progresses,
+            window_tab_data.common.config,
+        ),
     ))
     .on_resize(move |rect| {
         let size = rect.size();
@@ -2047,6 +2189,26 @@ fn palette_item(
                 .style(|s| s.align_items(Some(AlignItems::Center)).max_width_full()),
             )
         }
+        PaletteItemContent::Workspace { pinned, .. } => {
+            let pinned = *pinned;
+            let text = item.filter_text;
+            let indices = item.indices;
+            container_box(
+                stack((
+                    focus_text(
+                        move || text.clone(),
+                        move || indices.clone(),
+                        move || *config.get().get_color(LapceColor::EDITOR_FOCUS),
+                    )
+                    .style(|s| s.margin_right(6.0).max_width_full()),
+                    label(|| "pinned".to_string()).style(move |s| {
+                        s.color(*config.get().get_color(LapceColor::EDITOR_DIM))
+                            .apply_if(!pinned, |s| s.hide())
+                    }),
+                ))
+                .style(|s| s.align_items(Some(AlignItems::Center)).max_width_full()),
+            )
+        }
         PaletteItemContent::WorkspaceSymbol {
             kind,
             name,
@@ -2250,12 +2412,23 @@ fn palette_item(
             )
         }
         PaletteItemContent::Line { .. }
-        | PaletteItemContent::Workspace { .. }
+        | PaletteItemContent::GoToLine { .. }
         | PaletteItemContent::SshHost { .. }
+        | PaletteItemContent::WslDistro { .. }
+        | PaletteItemContent::Container { .. }
         | PaletteItemContent::Language { .. }
+        | PaletteItemContent::LineEnding { .. }
+        | PaletteItemContent::Indentation { .. }
+        | PaletteItemContent::ClipboardHistory { .. }
+        | PaletteItemContent::FilterSelections { .. }
         | PaletteItemContent::ColorTheme { .. }
         | PaletteItemContent::SCMReference { .. }
+        | PaletteItemContent::SCMCreateBranch { .. }
+        | PaletteItemContent::FileHistory { .. }
+        | PaletteItemContent::Task { .. }
         | PaletteItemContent::TerminalProfile { .. }
+        | PaletteItemContent::PluginCommand { .. }
+        | PaletteItemContent::PluginQuickPickItem { .. }
         | PaletteItemContent::IconTheme { .. } => {
             let text = item.filter_text;
             let indices = item.indices;
@@ -2288,19 +2461,19 @@ fn palette_input(window_tab_data: Rc<WindowTabData>) -> impl View {
     let config = window_tab_data.common.config;
     let focus = window_tab_data.common.focus;
     let is_focused = move || focus.get() == Focus::Palette;
-    container(
-        container(text_input(editor, is_focused).style(|s| s.width_full())).style(
-            move |s| {
-                let config = config.get();
-                s.width_full()
-                    .height(25.0)
-                    .items_center()
-                    .border_bottom(1.0)
-                    .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
-                    .background(*config.get_color(LapceColor::EDITOR_BACKGROUND))
-            },
-        ),
-    )
+    let palette = window_tab_data.palette.clone();
+    let input = text_input(editor, is_focused)
+        .placeholder(move || palette.placeholder())
+        .style(|s| s.width_full());
+    container(container(input).style(move |s| {
+        let config = config.get();
+        s.width_full()
+            .height(25.0)
+            .items_center()
+            .border_bottom(1.0)
+            .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+            .background(*config.get_color(LapceColor::EDITOR_BACKGROUND))
+    }))
     .style(|s| s.padding_bottom(5.0))
 }
 
@@ -2505,101 +2678,6 @@ fn palette(window_tab_data: Rc<WindowTabData>) -> impl View {
     })
 }
 
-fn window_message_view(
-    messages: RwSignal<Vec<(String, ShowMessageParams)>>,
-    config: ReadSignal<Arc<LapceConfig>>,
-) -> impl View {
-    let view_fn =
-        move |(i, (title, message)): (usize, (String, ShowMessageParams))| {
-            stack((
-                svg(move || {
-                    if let MessageType::ERROR = message.typ {
-                        config.get().ui_svg(LapceIcons::ERROR)
-                    } else {
-                        config.get().ui_svg(LapceIcons::WARNING)
-                    }
-                })
-                .style(move |s| {
-                    let config = config.get();
-                    let size = config.ui.icon_size() as f32;
-                    let color = if let MessageType::ERROR = message.typ {
-                        config.get_color(LapceColor::LAPCE_ERROR)
-                    } else {
-                        config.get_color(LapceColor::LAPCE_WARN)
-                    };
-                    s.min_width(size)
-                        .size(size, size)
-                        .margin_right(10.0)
-                        .margin_top(4.0)
-                        .color(*color)
-                }),
-                stack((
-                    text(title.clone()).style(|s| {
-                        s.min_width(0.0).line_height(1.6).font_weight(Weight::BOLD)
-                    }),
-                    text(message.message.clone()).style(|s| {
-                        s.min_width(0.0).line_height(1.6).margin_top(5.0)
-                    }),
-                ))
-                .style(move |s| {
-                    s.flex_col().min_width(0.0).flex_basis(0.0).flex_grow(1.0)
-                }),
-                clickable_icon(
-                    || LapceIcons::CLOSE,
-                    move || {
-                        messages.update(|messages| {
-                            messages.remove(i);
-                        });
-                    },
-                    || false,
-                    || false,
-                    config,
-                )
-                .style(|s| s.margin_left(6.0)),
-            ))
-            .style(move |s| {
-                let config = config.get();
-                s.width_full()
-                    .items_start()
-                    .padding(10.0)
-                    .border(1.0)
-                    .border_radius(6.0)
-                    .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
-                    .background(*config.get_color(LapceColor::PANEL_BACKGROUND))
-                    .apply_if(i > 0, |s| s.margin_top(10.0))
-            })
-        };
-
-    let id = AtomicU64::new(0);
-    container(
-        container(
-            container(
-                scroll(
-                    list(
-                        move || messages.get().into_iter().enumerate(),
-                        move |_| {
-                            id.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
-                        },
-                        view_fn,
-                    )
-                    .style(|s| s.flex_col().width_full()),
-                )
-                .style(|s| {
-                    s.absolute().width_full().min_height(0.0).max_height_full()
-                }),
-            )
-            .style(|s| s.size_full()),
-        )
-        .style(|s| {
-            s.width(360.0)
-                .max_width_pct(80.0)
-                .padding(10.0)
-                .height_full()
-        }),
-    )
-    .style(|s| s.absolute().size_full().justify_end())
-}
-
 struct VectorItems<V>(im::Vector<V>);
 
 impl<V: Clone + 'static> VirtualListVector<(usize, V)> for VectorItems<V> {
@@ -2710,16 +2788,32 @@ fn completion(window_tab_data: Rc<WindowTabData>) -> impl View {
             move || completion_data.with(|c| VectorItems(c.filtered_items.clone())),
             move |(i, _item)| (request_id(), *i),
             move |(i, item)| {
+                let kind = item.item.kind;
+                let has_svg = move || config.get().completion_svg(kind).is_some();
+                let detail = item
+                    .item
+                    .detail
+                    .as_deref()
+                    .and_then(|detail| detail.lines().next())
+                    .unwrap_or("")
+                    .to_string();
                 stack((
-                    container(
-                        text(
-                            item.item.kind.map(completion_kind_to_str).unwrap_or(""),
-                        )
+                    container(stack((
+                        svg(move || {
+                            config.get().completion_svg(kind).unwrap_or_default()
+                        })
                         .style(move |s| {
-                            s.width_full()
-                                .justify_content(Some(JustifyContent::Center))
+                            let config = config.get();
+                            let size = config.ui.icon_size() as f32;
+                            s.size(size, size)
+                                .apply_if(!has_svg(), |s| s.hide())
+                                .apply_opt(config.completion_color(kind), |s, c| {
+                                    s.color(c)
+                                })
                         }),
-                    )
+                        text(kind.map(completion_kind_to_str).unwrap_or(""))
+                            .style(move |s| s.apply_if(has_svg(), |s| s.hide())),
+                    )))
                     .style(move |s| {
                         let config = config.get();
                         let width = config.editor.line_height() as f32;
@@ -2727,19 +2821,35 @@ fn completion(window_tab_data: Rc<WindowTabData>) -> impl View {
                             .min_width(width)
                             .height_full()
                             .align_items(Some(AlignItems::Center))
+                            .justify_content(Some(JustifyContent::Center))
                             .font_weight(Weight::BOLD)
-                            .apply_opt(
-                                config.completion_color(item.item.kind),
-                                |s, c| {
-                                    s.color(c).background(c.with_alpha_factor(0.3))
-                                },
-                            )
+                            .apply_opt(config.completion_color(kind), |s, c| {
+                                s.color(c).background(c.with_alpha_factor(0.3))
+                            })
                     }),
-                    focus_text(
-                        move || item.item.label.clone(),
-                        move || item.indices.clone(),
-                        move || *config.get().get_color(LapceColor::EDITOR_FOCUS),
-                    )
+                    stack((
+                        focus_text(
+                            move || item.item.label.clone(),
+                            move || item.indices.clone(),
+                            move || {
+                                *config.get().get_color(LapceColor::EDITOR_FOCUS)
+                            },
+                        )
+                        .style(|s| s.min_width(0.0)),
+                        container(text(detail).style(move |s| {
+                            let config = config.get();
+                            s.min_width(0.0)
+                                .text_ellipsis()
+                                .color(*config.get_color(LapceColor::EDITOR_DIM))
+                        }))
+                        .style(|s| {
+                            s.min_width(0.0)
+                                .flex_grow(1.0)
+                                .flex_basis(0.0)
+                                .padding_left(10.0)
+                                .justify_content(Some(JustifyContent::FlexEnd))
+                        }),
+                    ))
                     .style(move |s| {
                         let config = config.get();
                         s.padding_horiz(5.0)
@@ -2792,7 +2902,7 @@ fn completion(window_tab_data: Rc<WindowTabData>) -> impl View {
             .margin_left(origin.x as f32)
             .margin_top(origin.y as f32)
             .background(*config.get_color(LapceColor::COMPLETION_BACKGROUND))
-            .font_family(config.editor.font_family.clone())
+            .font_family(config.editor.font_family_list())
             .font_size(config.editor.font_size() as f32)
             .border_radius(6.0)
     })
@@ -2805,50 +2915,133 @@ fn code_action(window_tab_data: Rc<WindowTabData>) -> impl View {
         .with_untracked(|code_action| (code_action.status, code_action.active));
     let request_id =
         move || code_action.with_untracked(|code_action| code_action.request_id);
-    scroll(
-        container(
+    let preview = move || {
+        code_action.with(|code_action| {
+            code_action
+                .filtered_items
+                .get(active.get())
+                .map(|item| item.preview.clone())
+                .unwrap_or_default()
+        })
+    };
+    stack((
+        scroll(
+            container(
+                list(
+                    move || {
+                        code_action.with(|code_action| {
+                            code_action
+                                .filtered_items
+                                .clone()
+                                .into_iter()
+                                .enumerate()
+                        })
+                    },
+                    move |(i, _item)| (request_id(), *i),
+                    move |(i, item)| {
+                        let fixes = item.fixes().unwrap_or_default();
+                        stack((
+                            text(item.group.title()).style(move |s| {
+                                let config = config.get();
+                                s.padding_horiz(10.0)
+                                    .line_height(1.6)
+                                    .font_size((config.ui.font_size() - 1) as f32)
+                                    .color(*config.get_color(LapceColor::EDITOR_DIM))
+                                    .apply_if(!item.first_of_group, |s| s.hide())
+                            }),
+                            stack((
+                                text(item.title().replace('\n', " "))
+                                    .style(|s| s.text_ellipsis().min_width(0.0)),
+                                text(fixes).style(move |s| {
+                                    let dim = *config
+                                        .get()
+                                        .get_color(LapceColor::EDITOR_DIM);
+                                    s.text_ellipsis()
+                                        .min_width(0.0)
+                                        .flex_grow(1.0)
+                                        .flex_basis(0.0)
+                                        .margin_left(10.0)
+                                        .color(dim)
+                                }),
+                            ))
+                            .style(move |s| {
+                                let config = config.get();
+                                s.padding_horiz(10.0)
+                                    .align_items(Some(AlignItems::Center))
+                                    .min_width(0.0)
+                                    .width_full()
+                                    .line_height(1.6)
+                                    .apply_if(active.get() == i, |s| {
+                                        s.border_radius(6.0).background(
+                                            *config.get_color(
+                                                LapceColor::COMPLETION_CURRENT,
+                                            ),
+                                        )
+                                    })
+                            }),
+                        ))
+                        .style(|s| s.flex_col().min_width(0.0).width_full())
+                    },
+                )
+                .style(|s| s.width_full().flex_col()),
+            )
+            .style(|s| s.width_full().padding_vert(4.0)),
+        )
+        .on_ensure_visible(move || {
+            let config = config.get();
+            let active = active.get();
+            // The titles of the groups up to the active item are rows too
+            let titles = code_action.with_untracked(|code_action| {
+                code_action
+                    .filtered_items
+                    .iter()
+                    .take(active + 1)
+                    .filter(|item| item.first_of_group)
+                    .count()
+            });
+            Size::new(1.0, config.editor.line_height() as f64)
+                .to_rect()
+                .with_origin(Point::new(
+                    0.0,
+                    (active + titles) as f64 * config.editor.line_height() as f64,
+                ))
+        })
+        .style(|s| s.width_full().min_height(0.0).flex_shrink(1.0)),
+        scroll(
             list(
-                move || {
-                    code_action.with(|code_action| {
-                        code_action.filtered_items.clone().into_iter().enumerate()
-                    })
-                },
-                move |(i, _item)| (request_id(), *i),
-                move |(i, item)| {
-                    container(
-                        text(item.title().replace('\n', " "))
-                            .style(|s| s.text_ellipsis().min_width(0.0)),
-                    )
-                    .style(move |s| {
-                        let config = config.get();
-                        s.padding_horiz(10.0)
-                            .align_items(Some(AlignItems::Center))
-                            .min_width(0.0)
-                            .width_full()
-                            .line_height(1.6)
-                            .apply_if(active.get() == i, |s| {
-                                s.border_radius(6.0).background(
-                                    *config
-                                        .get_color(LapceColor::COMPLETION_CURRENT),
-                                )
-                            })
+                move || preview().into_iter().enumerate(),
+                move |(i, _)| (request_id(), active.get_untracked(), *i),
+                move |(_, line)| {
+                    let (line, color) = match line {
+                        CodeActionPreviewLine::File(name) => {
+                            (name, LapceColor::EDITOR_DIM)
+                        }
+                        CodeActionPreviewLine::Removed(line) => {
+                            (format!("- {line}"), LapceColor::SOURCE_CONTROL_REMOVED)
+                        }
+                        CodeActionPreviewLine::Added(line) => {
+                            (format!("+ {line}"), LapceColor::SOURCE_CONTROL_ADDED)
+                        }
+                    };
+                    text(line).style(move |s| {
+                        s.color(*config.get().get_color(color)).line_height(1.6)
                     })
                 },
             )
-            .style(|s| s.width_full().flex_col()),
+            .style(|s| s.flex_col().padding(10.0)),
         )
-        .style(|s| s.width_full().padding_vert(4.0)),
-    )
-    .on_ensure_visible(move || {
-        let config = config.get();
-        let active = active.get();
-        Size::new(1.0, config.editor.line_height() as f64)
-            .to_rect()
-            .with_origin(Point::new(
-                0.0,
-                active as f64 * config.editor.line_height() as f64,
-            ))
-    })
+        .style(move |s| {
+            let config = config.get();
+            s.width_full()
+                .max_height(200.0)
+                .flex_shrink(0.0)
+                .border_top(1.0)
+                .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+                .font_family(config.editor.font_family_list())
+                .font_size(config.editor.font_size() as f32)
+                .apply_if(preview().is_empty(), |s| s.hide())
+        }),
+    ))
     .on_resize(move |rect| {
         code_action.update(|c| {
             c.layout_rect = rect;
@@ -2862,8 +3055,9 @@ fn code_action(window_tab_data: Rc<WindowTabData>) -> impl View {
             CodeActionStatus::Active => Display::Flex,
         })
         .position(Position::Absolute)
+        .flex_col()
         .width(400.0)
-        .max_height(400.0)
+        .max_height(600.0)
         .margin_left(origin.x as f32)
         .margin_top(origin.y as f32)
         .background(*config.get().get_color(LapceColor::COMPLETION_BACKGROUND))
@@ -2883,7 +3077,7 @@ fn rename(window_tab_data: Rc<WindowTabData>) -> impl View {
         )
         .style(move |s| {
             let config = config.get();
-            s.font_family(config.editor.font_family.clone())
+            s.font_family(config.editor.font_family_list())
                 .font_size(config.editor.font_size() as f32)
                 .border(1.0)
                 .border_radius(6.0)
@@ -2908,6 +3102,60 @@ fn rename(window_tab_data: Rc<WindowTabData>) -> impl View {
     })
 }
 
+fn peek(window_tab_data: Rc<WindowTabData>) -> impl View {
+    let peek_data = window_tab_data.peek.clone();
+    let active = peek_data.active;
+    let source = peek_data.source;
+    let layout_rect = peek_data.layout_rect;
+    let editor = peek_data.editor;
+    let workspace = window_tab_data.workspace.clone();
+    let config = window_tab_data.common.config;
+
+    container(
+        stack((
+            label(move || peek_data.title()).style(move |s| {
+                let config = config.get();
+                s.padding_horiz(10.0)
+                    .padding_vert(4.0)
+                    .width_full()
+                    .border_bottom(1.0)
+                    .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+                    .background(*config.get_color(LapceColor::PANEL_BACKGROUND))
+            }),
+            container(editor_container_view(
+                window_tab_data.clone(),
+                workspace,
+                |_tracked: bool| true,
+                editor,
+            ))
+            .style(|s| s.width_full().flex_grow(1.0).flex_basis(0.0)),
+        ))
+        .style(|s| s.flex_col().size_full()),
+    )
+    .on_resize(move |rect| {
+        layout_rect.set(rect);
+    })
+    .on_event_stop(EventListener::PointerMove, |_| {})
+    .style(move |s| {
+        let origin = window_tab_data.peek_origin();
+        let config = config.get();
+        let width = source
+            .get()
+            .map(|editor| editor.viewport.get().width())
+            .unwrap_or(0.0);
+        s.position(Position::Absolute)
+            .apply_if(!active.get(), |s| s.hide())
+            .margin_left(origin.x as f32)
+            .margin_top(origin.y as f32)
+            .width(width as f32)
+            .height((config.editor.line_height() * 12) as f32 + 28.0)
+            .border_top(1.0)
+            .border_bottom(1.0)
+            .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+            .background(*config.get_color(LapceColor::EDITOR_BACKGROUND))
+    })
+}
+
 fn window_tab(window_tab_data: Rc<WindowTabData>) -> impl View {
     let source_control = window_tab_data.source_control.clone();
     let window_origin = window_tab_data.common.window_origin;
@@ -2915,6 +3163,7 @@ fn window_tab(window_tab_data: Rc<WindowTabData>) -> impl View {
     let config = window_tab_data.common.config;
     let workbench_command = window_tab_data.common.workbench_command;
     let window_tab_scope = window_tab_data.scope;
+    let window_tab_id = window_tab_data.window_tab_id;
     let hover_active = window_tab_data.common.hover.active;
     let status_height = window_tab_data.status_height;
 
@@ -2941,11 +3190,15 @@ fn window_tab(window_tab_data: Rc<WindowTabData>) -> impl View {
         hover(window_tab_data.clone()),
         code_action(window_tab_data.clone()),
         rename(window_tab_data.clone()),
+        peek(window_tab_data.clone()),
+        color_picker::color_picker_popup(window_tab_data.clone()),
         palette(window_tab_data.clone()),
         about::about_popup(window_tab_data.clone()),
+        perf::perf_overlay(window_tab_data.clone()),
         alert::alert_box(window_tab_data.alert_data.clone()),
     ))
     .on_cleanup(move || {
+        crash::set_open_files(window_tab_id, Vec::new());
         window_tab_scope.dispose();
     })
     .on_event_cont(EventListener::PointerMove, move |_| {
@@ -2979,7 +3232,12 @@ fn workspace_title(workspace: &LapceWorkspace) -> Option<String> {
         LapceWorkspaceType::Local => format!("{dir}"),
         LapceWorkspaceType::RemoteSSH(ssh) => format!("{dir} [{ssh}]"),
         #[cfg(windows)]
-        LapceWorkspaceType::RemoteWSL => format!("{dir} [wsl]"),
+        LapceWorkspaceType::RemoteWSL(wsl) => {
+            format!("{dir} [wsl: {}]", wsl.distro)
+        }
+        LapceWorkspaceType::RemoteContainer(host) => {
+            format!("{dir} [container: {}]", host.container)
+        }
     })
 }
 
@@ -3299,6 +3557,16 @@ fn window(window_data: WindowData) -> impl View {
     .on_event_cont(EventListener::WindowGotFocus, move |_| {
         window_focus.set(true);
     })
+    .on_event_cont(EventListener::WindowLostFocus, move |_| {
+        for (_, window_tab) in window_tabs.get_untracked() {
+            let config = window_tab.common.config.get_untracked();
+            if config.editor.save_on_focus_lost {
+                window_tab
+                    .main_split
+                    .save_all(config.editor.format_on_autosave, || {});
+            }
+        }
+    })
     .on_event_cont(EventListener::WindowMaximizeChanged, move |event| {
         if let Event::WindowMaximizeChanged(maximized) = event {
             window_maximized.set(*maximized);
@@ -3373,6 +3641,7 @@ fn logging() -> (Handle<Targets>, Option<WorkerGuard>) {
 
 pub fn launch() {
     let (reload_handle, _guard) = logging();
+    crash::install_panic_hook();
     tracing::info!("Starting up Lapce..");
 
     // if PWD is not set, then we are not being launched via a terminal
@@ -3402,12 +3671,17 @@ pub fn launch() {
         return;
     }
 
+    let (paths, read_stdin) = cli.paths();
+    let stdin = read_stdin.then(stdin_content).flatten();
+
     if !cli.new {
         if let Ok(socket) = get_socket() {
-            if let Err(e) = try_open_in_existing_process(socket, &cli.paths) {
-                error!("failed to open path(s): {e}");
-            };
-            return;
+            match try_open_in_existing_process(socket, &paths, stdin.clone()) {
+                Ok(()) => return,
+                // The instance that left the socket behind doesn't answer, so
+                // this one takes over
+                Err(e) => error!("failed to open path(s): {e}"),
+            }
         }
     }
 
@@ -3430,6 +3704,8 @@ pub fn launch() {
     let app_command = Listener::new_empty(scope);
 
     let (tx, rx) = crossbeam_channel::bounded(1);
+    os_theme::watch(tx.clone());
+    os_accessibility::watch(tx.clone());
     let mut watcher = notify::recommended_watcher(ConfigWatcher::new(tx)).unwrap();
     if let Some(path) = LapceConfig::settings_file() {
         let _ = watcher.watch(&path, notify::RecursiveMode::Recursive);
@@ -3437,6 +3713,9 @@ pub fn launch() {
     if let Some(path) = Directory::themes_directory() {
         let _ = watcher.watch(&path, notify::RecursiveMode::Recursive);
     }
+    if let Some(path) = Directory::locales_directory() {
+        let _ = watcher.watch(&path, notify::RecursiveMode::Recursive);
+    }
     if let Some(path) = LapceConfig::keymaps_file() {
         let _ = watcher.watch(&path, notify::RecursiveMode::Recursive);
     }
@@ -3459,7 +3738,7 @@ pub fn launch() {
         config,
     };
 
-    let app = app_data.create_windows(db.clone(), cli.paths);
+    let app = app_data.create_windows(db.clone(), paths, stdin);
 
     {
         let app_data = app_data.clone();
@@ -3494,11 +3773,21 @@ pub fn launch() {
         let notification = create_signal_from_channel(rx);
         let app_data = app_data.clone();
         create_effect(move |_| {
-            if let Some(CoreNotification::OpenPaths { paths }) = notification.get() {
-                if let Some(window_tab) = app_data.active_window_tab() {
+            let notification = notification.get();
+            let Some(window_tab) = app_data.active_window_tab() else {
+                return;
+            };
+            match notification {
+                Some(CoreNotification::OpenPaths { paths }) => {
                     window_tab.open_paths(&paths);
                 }
+                Some(CoreNotification::OpenUntitled { content }) => {
+                    window_tab.main_split.new_file_with_content(content);
+                }
+                _ => return,
             }
+            // Opened from a terminal, the window should come to the front
+            floem::action::focus_window();
         });
         std::thread::spawn(move || {
             let _ = listen_local_socket(tx);
@@ -3515,6 +3804,11 @@ pub fn launch() {
     app.on_event(move |event| match event {
         floem::AppEvent::WillTerminate => {
             app_data.app_terminated.set(true);
+            app_data.windows.with_untracked(|windows| {
+                for window in windows.values() {
+                    window.back_up_unsaved();
+                }
+            });
             let _ = db.insert_app(app_data.clone());
         }
         floem::AppEvent::Reopen {
@@ -3570,24 +3864,45 @@ pub fn get_socket() -> Result<interprocess::local_socket::LocalSocketStream> {
     Ok(socket)
 }
 
+/// What was piped to `lapce -`, for a new untitled file.
+fn stdin_content() -> Option<String> {
+    let mut content = String::new();
+    if let Err(e) = std::io::stdin().read_to_string(&mut content) {
+        error!("failed to read standard input: {e}");
+        return None;
+    }
+    Some(content)
+}
+
 pub fn try_open_in_existing_process(
     mut socket: interprocess::local_socket::LocalSocketStream,
     paths: &[PathObject],
+    stdin: Option<String>,
 ) -> Result<()> {
-    let msg: CoreMessage = RpcMessage::Notification(CoreNotification::OpenPaths {
+    let mut msgs = vec![CoreNotification::OpenPaths {
         paths: paths.to_vec(),
-    });
-    lapce_rpc::stdio::write_msg(&mut socket, msg)?;
+    }];
+    if let Some(content) = stdin {
+        msgs.push(CoreNotification::OpenUntitled { content });
+    }
+    // Every message is answered on its own
+    let expected = b"received".repeat(msgs.len());
+    for msg in msgs {
+        let msg: CoreMessage = RpcMessage::Notification(msg);
+        lapce_rpc::stdio::write_msg(&mut socket, msg)?;
+    }
 
     let (tx, rx) = crossbeam_channel::bounded(1);
     std::thread::spawn(move || {
         let mut buf = [0; 100];
-        let received = if let Ok(n) = socket.read(&mut buf) {
-            &buf[..n] == b"received"
-        } else {
-            false
-        };
-        tx.send(received)
+        let mut answers = Vec::new();
+        while answers.len() < expected.len() {
+            match socket.read(&mut buf) {
+                Ok(n) if n > 0 => answers.extend_from_slice(&buf[..n]),
+                _ => break,
+            }
+        }
+        tx.send(answers == expected)
     });
 
     let received = rx.recv_timeout(std::time::Duration::from_millis(500))?;
@@ -3634,103 +3949,116 @@ pub fn window_menu(
     Menu::new("Lapce")
         .entry({
             let mut menu = Menu::new("Lapce")
-                .entry(MenuItem::new("About Lapce").action(move || {
+                .entry(MenuItem::new(tr("menu-about-lapce")).action(move || {
                     workbench_command.send(LapceWorkbenchCommand::ShowAbout)
                 }))
                 .separator()
                 .entry(
-                    Menu::new("Settings...")
-                        .entry(MenuItem::new("Open Settings").action(move || {
-                            workbench_command
-                                .send(LapceWorkbenchCommand::OpenSettings);
-                        }))
-                        .entry(MenuItem::new("Open Keyboard Shortcuts").action(
+                    Menu::new(tr("menu-settings"))
+                        .entry(MenuItem::new(tr("menu-open-settings")).action(
                             move || {
-                                workbench_command.send(
-                                    LapceWorkbenchCommand::OpenKeyboardShortcuts,
-                                );
+                                workbench_command
+                                    .send(LapceWorkbenchCommand::OpenSettings);
                             },
-                        )),
+                        ))
+                        .entry(
+                            MenuItem::new(tr("menu-open-keyboard-shortcuts"))
+                                .action(move || {
+                                    workbench_command.send(
+                                        LapceWorkbenchCommand::OpenKeyboardShortcuts,
+                                    );
+                                }),
+                        ),
                 )
                 .separator()
-                .entry(MenuItem::new("Quit Lapce").action(move || {
+                .entry(MenuItem::new(tr("menu-quit-lapce")).action(move || {
                     workbench_command.send(LapceWorkbenchCommand::Quit);
                 }));
             if cfg!(target_os = "macos") {
                 menu = menu
                     .separator()
-                    .entry(MenuItem::new("Hide Lapce"))
-                    .entry(MenuItem::new("Hide Others"))
-                    .entry(MenuItem::new("Show All"))
+                    .entry(MenuItem::new(tr("menu-hide-lapce")))
+                    .entry(MenuItem::new(tr("menu-hide-others")))
+                    .entry(MenuItem::new(tr("menu-show-all")))
             }
             menu
         })
         .separator()
         .entry(
-            Menu::new("File")
-                .entry(MenuItem::new("New File").action(move || {
+            Menu::new(tr("menu-file"))
+                .entry(MenuItem::new(tr("menu-new-file")).action(move || {
                     workbench_command.send(LapceWorkbenchCommand::NewFile);
                 }))
                 .separator()
-                .entry(MenuItem::new("Open").action(move || {
+                .entry(MenuItem::new(tr("menu-open")).action(move || {
                     workbench_command.send(LapceWorkbenchCommand::OpenFile);
                 }))
-                .entry(MenuItem::new("Open Folder").action(move || {
+                .entry(MenuItem::new(tr("menu-open-folder")).action(move || {
                     workbench_command.send(LapceWorkbenchCommand::OpenFolder);
                 }))
                 .separator()
-                .entry(MenuItem::new("Save").action(move || {
+                .entry(MenuItem::new(tr("menu-save")).action(move || {
                     lapce_command.send(LapceCommand {
                         kind: CommandKind::Focus(FocusCommand::Save),
                         data: None,
                     });
                 }))
-                .entry(MenuItem::new("Save All").action(move || {
+                .entry(MenuItem::new(tr("menu-save-all")).action(move || {
                     workbench_command.send(LapceWorkbenchCommand::SaveAll);
                 }))
                 .separator()
-                .entry(MenuItem::new("Close Folder").action(move || {
+                .entry(MenuItem::new(tr("menu-close-folder")).action(move || {
                     workbench_command.send(LapceWorkbenchCommand::CloseFolder);
                 }))
-                .entry(MenuItem::new("Close Window").action(move || {
+                .entry(MenuItem::new(tr("menu-close-window")).action(move || {
                     workbench_command.send(LapceWorkbenchCommand::CloseWindow);
                 })),
         )
         .entry(
-            Menu::new("Edit")
-                .entry(MenuItem::new("Cut").action(move || {
+            Menu::new(tr("menu-edit"))
+                .entry(MenuItem::new(tr("menu-cut")).action(move || {
                     lapce_command.send(LapceCommand {
                         kind: CommandKind::Edit(EditCommand::ClipboardCut),
                         data: None,
                     });
                 }))
-                .entry(MenuItem::new("Copy").action(move || {
+                .entry(MenuItem::new(tr("menu-copy")).action(move || {
                     lapce_command.send(LapceCommand {
                         kind: CommandKind::Edit(EditCommand::ClipboardCopy),
                         data: None,
                     });
                 }))
-                .entry(MenuItem::new("Paste").action(move || {
+                .entry(MenuItem::new(tr("menu-paste")).action(move || {
                     lapce_command.send(LapceCommand {
                         kind: CommandKind::Edit(EditCommand::ClipboardPaste),
                         data: None,
                     });
                 }))
+                .entry(MenuItem::new(tr("menu-paste-without-formatting")).action(
+                    move || {
+                        lapce_command.send(LapceCommand {
+                            kind: CommandKind::Edit(
+                                EditCommand::ClipboardPasteWithoutFormatting,
+                            ),
+                            data: None,
+                        });
+                    },
+                ))
                 .separator()
-                .entry(MenuItem::new("Undo").action(move || {
+                .entry(MenuItem::new(tr("menu-undo")).action(move || {
                     lapce_command.send(LapceCommand {
                         kind: CommandKind::Edit(EditCommand::Undo),
                         data: None,
                     });
                 }))
-                .entry(MenuItem::new("Redo").action(move || {
+                .entry(MenuItem::new(tr("menu-redo")).action(move || {
                     lapce_command.send(LapceCommand {
                         kind: CommandKind::Edit(EditCommand::Redo),
                         data: None,
                     });
                 }))
                 .separator()
-                .entry(MenuItem::new("Find").action(move || {
+                .entry(MenuItem::new(tr("menu-find")).action(move || {
                     lapce_command.send(LapceCommand {
                         kind: CommandKind::Focus(FocusCommand::Search),
                         data: None,
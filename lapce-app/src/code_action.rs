@@ -1,17 +1,24 @@
-use std::{rc::Rc, sync::Arc};
+use std::{path::Path, rc::Rc, sync::Arc};
 
 use floem::{
     keyboard::ModifiersState,
     peniko::kurbo::Rect,
     reactive::{RwSignal, Scope},
 };
-use lapce_core::{command::FocusCommand, mode::Mode, movement::Movement};
+use lapce_core::{
+    buffer::{rope_text::RopeText, Buffer},
+    command::FocusCommand,
+    mode::Mode,
+    movement::Movement,
+};
 use lapce_rpc::plugin::PluginId;
-use lsp_types::CodeActionOrCommand;
+use lsp_types::{CodeActionKind, CodeActionOrCommand, WorkspaceEdit};
 
 use crate::{
     command::{CommandExecuted, CommandKind, InternalCommand},
+    doc::Document,
     keypress::{condition::Condition, KeyPressFocus},
+    main_split::workspace_edits,
     window_tab::{CommonData, Focus},
 };
 
@@ -21,12 +28,68 @@ pub enum CodeActionStatus {
     Active,
 }
 
+/// The kinds of code actions the menu lists together, in the order it lists
+/// them.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CodeActionGroup {
+    QuickFix,
+    Refactor,
+    Source,
+    Other,
+}
+
+impl CodeActionGroup {
+    fn of(item: &CodeActionOrCommand) -> Self {
+        let kind = match item {
+            CodeActionOrCommand::CodeAction(action) => action.kind.as_ref(),
+            CodeActionOrCommand::Command(_) => None,
+        };
+        let Some(kind) = kind else {
+            return CodeActionGroup::Other;
+        };
+        let is = |group: CodeActionKind| {
+            kind.as_str() == group.as_str()
+                || kind.as_str().starts_with(&format!("{}.", group.as_str()))
+        };
+        if is(CodeActionKind::QUICKFIX) {
+            CodeActionGroup::QuickFix
+        } else if is(CodeActionKind::REFACTOR) {
+            CodeActionGroup::Refactor
+        } else if is(CodeActionKind::SOURCE) {
+            CodeActionGroup::Source
+        } else {
+            CodeActionGroup::Other
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            CodeActionGroup::QuickFix => "Quick Fix",
+            CodeActionGroup::Refactor => "Refactor",
+            CodeActionGroup::Source => "Source Action",
+            CodeActionGroup::Other => "More Actions",
+        }
+    }
+}
+
+/// A line of the preview of the changes a code action makes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CodeActionPreviewLine {
+    File(String),
+    Removed(String),
+    Added(String),
+}
+
 #[derive(Clone, PartialEq)]
 pub struct ScoredCodeActionItem {
     pub item: CodeActionOrCommand,
     pub plugin_id: PluginId,
     pub score: i64,
     pub indices: Vec<usize>,
+    pub group: CodeActionGroup,
+    /// Whether this is the first item of its group, under the group's title.
+    pub first_of_group: bool,
+    pub preview: Vec<CodeActionPreviewLine>,
 }
 
 impl ScoredCodeActionItem {
@@ -36,6 +99,20 @@ pub fn title(&self) -> &str {
             CodeActionOrCommand::CodeAction(c) => &c.title,
         }
     }
+
+    /// The first line of the message of the diagnostic the action fixes, with
+    /// how many more it fixes.
+    pub fn fixes(&self) -> Option<String> {
+        let CodeActionOrCommand::CodeAction(action) = &self.item else {
+            return None;
+        };
+        let diagnostics = action.diagnostics.as_ref()?;
+        let message = diagnostics.first()?.message.lines().next().unwrap_or("");
+        Some(match diagnostics.len() {
+            1 => message.to_string(),
+            n => format!("{message} (+{} more)", n - 1),
+        })
+    }
 }
 
 #[derive(Clone)]
@@ -168,13 +245,18 @@ pub fn show(
         code_actions: Arc<(PluginId, Vec<CodeActionOrCommand>)>,
         offset: usize,
         mouse_click: bool,
+        doc: Option<Rc<Document>>,
     ) {
         self.active.set(0);
         self.status.set(CodeActionStatus::Active);
         self.offset = offset;
         self.mouse_click = mouse_click;
         self.request_id += 1;
-        self.items = code_actions
+        let path = doc
+            .as_ref()
+            .and_then(|doc| doc.content.with_untracked(|c| c.path().cloned()));
+        let buffer = doc.map(|doc| doc.buffer.get_untracked());
+        let mut items = code_actions
             .1
             .iter()
             .map(|code_action| ScoredCodeActionItem {
@@ -182,8 +264,25 @@ pub fn show(
                 plugin_id: code_actions.0,
                 score: 0,
                 indices: Vec::new(),
+                group: CodeActionGroup::of(code_action),
+                first_of_group: false,
+                preview: match (code_action, buffer.as_ref()) {
+                    (CodeActionOrCommand::CodeAction(action), Some(buffer)) => {
+                        action
+                            .edit
+                            .as_ref()
+                            .map(|edit| edit_preview(edit, path.as_deref(), buffer))
+                            .unwrap_or_default()
+                    }
+                    _ => Vec::new(),
+                },
             })
-            .collect();
+            .collect::<Vec<_>>();
+        items.sort_by_key(|item| item.group);
+        for i in 0..items.len() {
+            items[i].first_of_group = i == 0 || items[i - 1].group != items[i].group;
+        }
+        self.items = items.into();
         self.filtered_items = self.items.clone();
         self.common.focus.set(Focus::CodeAction);
     }
@@ -230,3 +329,100 @@ fn run_focus_command(&self, cmd: &FocusCommand) -> CommandExecuted {
         CommandExecuted::Yes
     }
 }
+
+/// The lines of the document the edit changes, before and after, with how many
+/// changes it makes to other files.
+fn edit_preview(
+    edit: &WorkspaceEdit,
+    path: Option<&Path>,
+    buffer: &Buffer,
+) -> Vec<CodeActionPreviewLine> {
+    let Some(edits) = workspace_edits(edit) else {
+        return Vec::new();
+    };
+    let mut files = edits.into_iter().collect::<Vec<_>>();
+    files.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+
+    let mut lines = Vec::new();
+    for (url, mut edits) in files {
+        let file_path = url.to_file_path().ok();
+        let name = file_path
+            .as_ref()
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| url.to_string());
+        if path.is_none() || file_path.as_deref() != path {
+            lines.push(CodeActionPreviewLine::File(match edits.len() {
+                1 => format!("{name}: 1 change"),
+                n => format!("{name}: {n} changes"),
+            }));
+            continue;
+        }
+
+        lines.push(CodeActionPreviewLine::File(name));
+        edits.sort_by_key(|edit| edit.range.start);
+        for edit in edits {
+            let start = buffer.offset_of_position(&edit.range.start);
+            let end = buffer.offset_of_position(&edit.range.end);
+            let line_start = buffer.offset_of_line(buffer.line_of_offset(start));
+            let line_end = buffer.line_end_offset(buffer.line_of_offset(end), true);
+            let old = buffer.slice_to_cow(line_start..line_end);
+            let new = format!(
+                "{}{}{}",
+                buffer.slice_to_cow(line_start..start),
+                edit.new_text,
+                buffer.slice_to_cow(end..line_end)
+            );
+            lines.extend(
+                old.lines()
+                    .map(|line| CodeActionPreviewLine::Removed(line.to_string())),
+            );
+            lines.extend(
+                new.lines()
+                    .map(|line| CodeActionPreviewLine::Added(line.to_string())),
+            );
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, path::Path};
+
+    use lapce_core::buffer::Buffer;
+    use lsp_types::{Position, Range, TextEdit, Url, WorkspaceEdit};
+
+    use super::{edit_preview, CodeActionPreviewLine};
+
+    #[test]
+    fn test_edit_preview() {
+        let path = Path::new("/project/src/main.rs");
+        let buffer = Buffer::new("use b;\nuse a;\n\nfn main() {}\n");
+        let edit = |line, new_text: &str| TextEdit {
+            range: Range::new(Position::new(line, 4), Position::new(line, 5)),
+            new_text: new_text.to_string(),
+        };
+        let edit = WorkspaceEdit::new(HashMap::from([
+            (
+                Url::from_file_path(path).unwrap(),
+                vec![edit(1, "b"), edit(0, "a")],
+            ),
+            (
+                Url::from_file_path("/project/src/lib.rs").unwrap(),
+                vec![edit(0, "c")],
+            ),
+        ]));
+        assert_eq!(
+            edit_preview(&edit, Some(path), &buffer),
+            vec![
+                CodeActionPreviewLine::File("lib.rs: 1 change".to_string()),
+                CodeActionPreviewLine::File("main.rs".to_string()),
+                CodeActionPreviewLine::Removed("use b;".to_string()),
+                CodeActionPreviewLine::Added("use a;".to_string()),
+                CodeActionPreviewLine::Removed("use a;".to_string()),
+                CodeActionPreviewLine::Added("use b;".to_string()),
+            ]
+        );
+    }
+}
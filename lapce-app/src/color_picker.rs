@@ -0,0 +1,328 @@
+//! The colors of a document, shown with a swatch before them, and the popup that
+//! picks a new color for one when its swatch is clicked.
+
+use std::rc::Rc;
+
+use floem::{
+    event::{Event, EventListener},
+    ext_event::create_ext_action,
+    keyboard::ModifiersState,
+    peniko::{kurbo::Rect, Color},
+    reactive::{create_rw_signal, RwSignal, Scope},
+    style::Position,
+    view::View,
+    views::{container, empty, label, stack, Decorators},
+};
+use lapce_core::{
+    buffer::rope_text::RopeText,
+    color::{format_color, ColorNotation},
+    command::FocusCommand,
+    mode::Mode,
+};
+use lapce_rpc::proxy::ProxyResponse;
+use lsp_types::{ColorPresentation, Range, TextEdit};
+
+use crate::{
+    command::{CommandExecuted, CommandKind, LapceCommand},
+    config::color::LapceColor,
+    editor::EditorData,
+    keypress::{condition::Condition, KeyPressFocus},
+    window_tab::{CommonData, Focus, WindowTabData},
+};
+
+/// The width of the bar a channel of the color is picked on.
+const CHANNEL_WIDTH: f64 = 200.0;
+
+/// A color in a document, found by its language server or in the text.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DocumentColor {
+    pub start: usize,
+    pub end: usize,
+    pub rgba: [u8; 4],
+    /// How the color is written, if it's a notation the editor can write itself.
+    /// Otherwise the language server is asked how to write a new color.
+    pub notation: Option<ColorNotation>,
+}
+
+impl DocumentColor {
+    pub fn swatch_color(&self) -> Color {
+        let [r, g, b, a] = self.rgba;
+        Color::rgba8(r, g, b, a)
+    }
+}
+
+#[derive(Clone)]
+pub struct ColorPickerData {
+    pub active: RwSignal<bool>,
+    /// The editor of the document the color is in
+    pub editor: RwSignal<Option<EditorData>>,
+    /// The color being changed, with the range it is written in now
+    pub color: RwSignal<DocumentColor>,
+    /// The color picked so far, which is written to the document when a channel
+    /// is let go of
+    pub rgba: RwSignal<[u8; 4]>,
+    pub layout_rect: RwSignal<Rect>,
+    pub common: Rc<CommonData>,
+}
+
+impl KeyPressFocus for ColorPickerData {
+    fn get_mode(&self) -> Mode {
+        Mode::Insert
+    }
+
+    fn check_condition(&self, condition: Condition) -> bool {
+        matches!(condition, Condition::ModalFocus)
+    }
+
+    fn run_command(
+        &self,
+        command: &LapceCommand,
+        _count: Option<usize>,
+        _mods: ModifiersState,
+    ) -> CommandExecuted {
+        match &command.kind {
+            CommandKind::Focus(FocusCommand::ModalClose) => {
+                self.close();
+                CommandExecuted::Yes
+            }
+            _ => CommandExecuted::No,
+        }
+    }
+
+    fn receive_char(&self, _c: &str) {}
+
+    fn focus_only(&self) -> bool {
+        true
+    }
+}
+
+impl ColorPickerData {
+    pub fn new(cx: Scope, common: Rc<CommonData>) -> Self {
+        Self {
+            active: cx.create_rw_signal(false),
+            editor: cx.create_rw_signal(None),
+            color: cx.create_rw_signal(DocumentColor {
+                start: 0,
+                end: 0,
+                rgba: [0, 0, 0, 255],
+                notation: None,
+            }),
+            rgba: cx.create_rw_signal([0, 0, 0, 255]),
+            layout_rect: cx.create_rw_signal(Rect::ZERO),
+            common,
+        }
+    }
+
+    pub fn start(&self, editor: EditorData, color: DocumentColor) {
+        self.editor.set(Some(editor));
+        self.color.set(color);
+        self.rgba.set(color.rgba);
+        self.active.set(true);
+        self.common.focus.set(Focus::ColorPicker);
+    }
+
+    pub fn close(&self) {
+        self.apply();
+        self.active.set(false);
+        if let Focus::ColorPicker = self.common.focus.get_untracked() {
+            self.common.focus.set(Focus::Workbench);
+        }
+    }
+
+    /// Write the picked color over the color it replaces, in the same notation.
+    pub fn apply(&self) {
+        let rgba = self.rgba.get_untracked();
+        let color = self.color.get_untracked();
+        let Some(editor) = self.editor.get_untracked() else {
+            return;
+        };
+        if rgba == color.rgba {
+            return;
+        }
+        if let Some(notation) = color.notation {
+            self.replace(&editor, color, rgba, format_color(rgba, notation));
+            return;
+        }
+
+        let doc = editor.view.doc.get_untracked();
+        let Some(path) = doc.content.with_untracked(|c| c.path().cloned()) else {
+            return;
+        };
+        let (range, rev) = doc.buffer.with_untracked(|buffer| {
+            let range = Range {
+                start: buffer.offset_to_position(color.start),
+                end: buffer.offset_to_position(color.end),
+            };
+            (range, buffer.rev())
+        });
+        let picker = self.clone();
+        let send = create_ext_action(
+            self.common.scope,
+            move |presentations: Vec<ColorPresentation>| {
+                // The server lists the ways to write the color, most fitting first
+                let Some(presentation) = presentations.into_iter().next() else {
+                    return;
+                };
+                if doc.rev() != rev {
+                    return;
+                }
+                let text = presentation
+                    .text_edit
+                    .map_or(presentation.label, |edit| edit.new_text);
+                picker.replace(&editor, color, rgba, text);
+            },
+        );
+        let [red, green, blue, alpha] = rgba.map(|c| c as f32 / 255.0);
+        let lsp_color = lsp_types::Color {
+            red,
+            green,
+            blue,
+            alpha,
+        };
+        self.common.proxy.get_color_presentations(
+            path,
+            lsp_color,
+            range,
+            move |result| {
+                if let Ok(ProxyResponse::GetColorPresentations { presentations }) =
+                    result
+                {
+                    send(presentations);
+                }
+            },
+        );
+    }
+
+    fn replace(
+        &self,
+        editor: &EditorData,
+        color: DocumentColor,
+        rgba: [u8; 4],
+        text: String,
+    ) {
+        let doc = editor.view.doc.get_untracked();
+        let range = doc.buffer.with_untracked(|buffer| Range {
+            start: buffer.offset_to_position(color.start),
+            end: buffer.offset_to_position(color.end),
+        });
+        let end = color.start + text.len();
+        editor.do_text_edit(&[TextEdit {
+            range,
+            new_text: text,
+        }]);
+        self.color.set(DocumentColor { end, rgba, ..color });
+    }
+}
+
+/// The popup to pick a new color for the color a swatch was clicked for, with a
+/// bar for each channel of it.
+pub fn color_picker_popup(window_tab_data: Rc<WindowTabData>) -> impl View {
+    let color_picker = window_tab_data.color_picker.clone();
+    let active = color_picker.active;
+    let rgba = color_picker.rgba;
+    let layout_rect = color_picker.layout_rect;
+    let config = window_tab_data.common.config;
+
+    container(
+        stack((
+            stack((
+                empty().style(move |s| {
+                    let [r, g, b, a] = rgba.get();
+                    s.size(24.0, 24.0)
+                        .border(1.0)
+                        .border_radius(4.0)
+                        .border_color(
+                            *config.get().get_color(LapceColor::LAPCE_BORDER),
+                        )
+                        .background(Color::rgba8(r, g, b, a))
+                }),
+                label(move || format_color(rgba.get(), ColorNotation::Hex))
+                    .style(|s| s.margin_left(8.0)),
+            ))
+            .style(|s| s.items_center().margin_bottom(4.0)),
+            color_channel(color_picker.clone(), 0, "R"),
+            color_channel(color_picker.clone(), 1, "G"),
+            color_channel(color_picker.clone(), 2, "B"),
+            color_channel(color_picker, 3, "A"),
+        ))
+        .style(|s| s.flex_col()),
+    )
+    .on_resize(move |rect| {
+        layout_rect.set(rect);
+    })
+    .on_event_stop(EventListener::PointerMove, |_| {})
+    .on_event_stop(EventListener::PointerDown, |_| {})
+    .style(move |s| {
+        let config = config.get();
+        let origin = window_tab_data.color_picker_origin();
+        s.position(Position::Absolute)
+            .apply_if(!active.get(), |s| s.hide())
+            .margin_left(origin.x as f32)
+            .margin_top(origin.y as f32)
+            .font_family(config.editor.font_family_list())
+            .font_size(config.editor.font_size() as f32)
+            .color(*config.get_color(LapceColor::EDITOR_FOREGROUND))
+            .background(*config.get_color(LapceColor::PANEL_BACKGROUND))
+            .border(1.0)
+            .border_radius(6.0)
+            .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+            .padding(8.0)
+    })
+}
+
+/// A bar to drag across to pick the value of a channel of the color, filled up
+/// to the value in the color of the channel.
+fn color_channel(
+    color_picker: ColorPickerData,
+    channel: usize,
+    name: &'static str,
+) -> impl View {
+    let rgba = color_picker.rgba;
+    let config = color_picker.common.config;
+    let dragging = create_rw_signal(false);
+    let set_value = move |x: f64| {
+        let value = (x / CHANNEL_WIDTH * 255.0).round().clamp(0.0, 255.0) as u8;
+        rgba.update(|rgba| rgba[channel] = value);
+    };
+
+    stack((
+        label(move || name.to_string()).style(|s| s.width(16.0)),
+        container(empty().style(move |s| {
+            let value = rgba.get()[channel];
+            let mut fill = [0, 0, 0, 255];
+            fill[channel] = 255;
+            let [r, g, b, a] = fill;
+            s.height_full()
+                .width((value as f64 / 255.0 * CHANNEL_WIDTH) as f32)
+                .background(Color::rgba8(r, g, b, a))
+        }))
+        .on_event_stop(EventListener::PointerDown, move |event| {
+            if let Event::PointerDown(pointer_event) = event {
+                dragging.set(true);
+                set_value(pointer_event.pos.x);
+            }
+        })
+        .on_event_stop(EventListener::PointerMove, move |event| {
+            if let Event::PointerMove(pointer_event) = event {
+                if dragging.get_untracked() {
+                    set_value(pointer_event.pos.x);
+                }
+            }
+        })
+        .on_event_stop(EventListener::PointerUp, move |_| {
+            dragging.set(false);
+            color_picker.apply();
+        })
+        .style(move |s| {
+            let config = config.get();
+            s.width(CHANNEL_WIDTH as f32)
+                .height(12.0)
+                .margin_horiz(6.0)
+                .border(1.0)
+                .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+                .background(*config.get_color(LapceColor::EDITOR_BACKGROUND))
+        }),
+        label(move || rgba.get()[channel].to_string()).style(|s| s.width(32.0)),
+    ))
+    .style(|s| s.items_center().margin_top(4.0))
+}
@@ -8,10 +8,10 @@
 use lapce_rpc::{
     dap_types::{DapId, RunDebugConfig},
     plugin::{PluginId, VoltID},
-    proxy::ProxyStatus,
+    proxy::{ProxyStatus, Runnable},
     terminal::{TermId, TerminalProfile},
 };
-use lsp_types::{CodeActionOrCommand, Position, WorkspaceEdit};
+use lsp_types::{CodeActionOrCommand, MessageType, Position, WorkspaceEdit};
 use serde_json::Value;
 use strum::{EnumMessage, IntoEnumIterator};
 use strum_macros::{Display, EnumIter, EnumMessage, EnumString, IntoStaticStr};
@@ -23,7 +23,9 @@
     editor::location::EditorLocation,
     editor_tab::EditorTabChild,
     id::EditorTabId,
+    localization,
     main_split::{SplitDirection, SplitMoveDirection},
+    task::TaskConfig,
     workspace::LapceWorkspace,
 };
 
@@ -55,6 +57,30 @@ pub fn desc(&self) -> Option<&'static str> {
         }
     }
 
+    /// The name the command is shown with, from the catalog of the language in
+    /// use, or else its description. It's made from the command itself for the
+    /// commands without a description.
+    pub fn name(&self) -> String {
+        if let Some(name) = localization::get(&format!("command-{}", self.str())) {
+            return name;
+        }
+        if let Some(desc) = self.desc() {
+            return desc.to_string();
+        }
+        self.str()
+            .split(['_', '.'])
+            .filter(|word| !word.is_empty())
+            .map(|word| {
+                let mut chars = word.chars();
+                match chars.next() {
+                    Some(c) => c.to_uppercase().chain(chars).collect(),
+                    None => String::new(),
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
     pub fn str(&self) -> &'static str {
         match &self {
             CommandKind::Workbench(cmd) => cmd.into(),
@@ -151,6 +177,10 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Open Folder")]
     OpenFolder,
 
+    #[strum(serialize = "add_folder_to_workspace")]
+    #[strum(message = "Add Folder to Workspace")]
+    AddFolderToWorkspace,
+
     #[strum(serialize = "close_folder")]
     #[strum(message = "Close Folder")]
     CloseFolder,
@@ -163,6 +193,18 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Reveal Active File in File Explorer")]
     RevealActiveFileInFileExplorer,
 
+    #[strum(serialize = "compare_with_file")]
+    #[strum(message = "Compare Active File With...")]
+    CompareWithFile,
+
+    #[strum(serialize = "compare_with_saved")]
+    #[strum(message = "Compare Active File With Saved")]
+    CompareWithSaved,
+
+    #[strum(serialize = "compare_with_head")]
+    #[strum(message = "Compare Active File With HEAD")]
+    CompareWithHead,
+
     #[strum(serialize = "open_ui_inspector")]
     #[strum(message = "Open Internal UI Inspector")]
     OpenUIInspector,
@@ -171,6 +213,10 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Change Color Theme")]
     ChangeColorTheme,
 
+    #[strum(serialize = "toggle_dark_light_theme")]
+    #[strum(message = "Toggle Between Dark and Light Theme")]
+    ToggleDarkLightTheme,
+
     #[strum(serialize = "change_icon_theme")]
     #[strum(message = "Change Icon Theme")]
     ChangeIconTheme,
@@ -207,6 +253,22 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Open Logs Directory")]
     OpenLogsDirectory,
 
+    #[strum(serialize = "toggle_performance_overlay")]
+    #[strum(message = "Toggle Performance Overlay")]
+    TogglePerformanceOverlay,
+
+    #[strum(serialize = "dump_performance_trace")]
+    #[strum(message = "Dump Performance Trace")]
+    DumpPerformanceTrace,
+
+    #[strum(serialize = "toggle_notifications")]
+    #[strum(message = "Toggle Notifications")]
+    ToggleNotifications,
+
+    #[strum(serialize = "clear_notifications")]
+    #[strum(message = "Clear Notifications")]
+    ClearNotifications,
+
     #[strum(serialize = "open_proxy_directory")]
     #[strum(message = "Open Proxy Directory")]
     OpenProxyDirectory,
@@ -219,6 +281,10 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Open Plugins Directory")]
     OpenPluginsDirectory,
 
+    #[strum(serialize = "update_plugins")]
+    #[strum(message = "Update All Plugins")]
+    UpdatePlugins,
+
     #[strum(serialize = "zoom_in")]
     #[strum(message = "Zoom In")]
     ZoomIn,
@@ -231,6 +297,50 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Reset Zoom")]
     ZoomReset,
 
+    #[strum(serialize = "editor_zoom_in")]
+    #[strum(message = "Editor Zoom In")]
+    EditorZoomIn,
+
+    #[strum(serialize = "editor_zoom_out")]
+    #[strum(message = "Editor Zoom Out")]
+    EditorZoomOut,
+
+    #[strum(serialize = "editor_zoom_reset")]
+    #[strum(message = "Reset Editor Zoom")]
+    EditorZoomReset,
+
+    #[strum(serialize = "close_split")]
+    #[strum(message = "Close Split")]
+    CloseSplit,
+
+    #[strum(serialize = "split_rotate")]
+    #[strum(message = "Rotate Splits")]
+    SplitRotate,
+
+    #[strum(serialize = "split_equalize")]
+    #[strum(message = "Equalize Split Sizes")]
+    SplitEqualize,
+
+    #[strum(serialize = "split_toggle_maximize")]
+    #[strum(message = "Toggle Maximize Split")]
+    SplitToggleMaximize,
+
+    #[strum(serialize = "split_increase_width")]
+    #[strum(message = "Increase Split Width")]
+    SplitIncreaseWidth,
+
+    #[strum(serialize = "split_decrease_width")]
+    #[strum(message = "Decrease Split Width")]
+    SplitDecreaseWidth,
+
+    #[strum(serialize = "split_increase_height")]
+    #[strum(message = "Increase Split Height")]
+    SplitIncreaseHeight,
+
+    #[strum(serialize = "split_decrease_height")]
+    #[strum(message = "Decrease Split Height")]
+    SplitDecreaseHeight,
+
     #[strum(serialize = "close_window_tab")]
     #[strum(message = "Close Current Window Tab")]
     CloseWindowTab,
@@ -255,6 +365,10 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Previous Terminal Tab")]
     PreviousTerminalTab,
 
+    #[strum(serialize = "move_window_tab_to_new_window")]
+    #[strum(message = "Move Window Tab to New Window")]
+    MoveWindowTabToNewWindow,
+
     #[strum(serialize = "next_window_tab")]
     #[strum(message = "Go To Next Window Tab")]
     NextWindowTab,
@@ -287,6 +401,10 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Connect to WSL")]
     ConnectWsl,
 
+    #[strum(serialize = "connect_container")]
+    #[strum(message = "Attach to Running Container")]
+    ConnectContainer,
+
     #[strum(serialize = "disconnect_remote")]
     #[strum(message = "Disconnect From Remote")]
     DisconnectRemote,
@@ -295,10 +413,38 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "palette.line")]
     PaletteLine,
 
+    #[strum(message = "Go To Line/Column")]
+    #[strum(serialize = "palette.go_to_line")]
+    PaletteGoToLine,
+
     #[strum(serialize = "palette")]
     #[strum(message = "Go to File")]
     Palette,
 
+    #[strum(serialize = "palette.recent_files")]
+    #[strum(message = "Switch to Recent File")]
+    PaletteRecentFiles,
+
+    #[strum(serialize = "palette.clipboard_history")]
+    #[strum(message = "Paste from Clipboard History")]
+    PaletteClipboardHistory,
+
+    #[strum(serialize = "keep_selections")]
+    #[strum(message = "Keep Selections Matching Regex")]
+    KeepSelections,
+
+    #[strum(serialize = "remove_selections")]
+    #[strum(message = "Remove Selections Matching Regex")]
+    RemoveSelections,
+
+    #[strum(serialize = "reopen_closed_editor")]
+    #[strum(message = "Reopen Closed Editor")]
+    ReopenClosedEditor,
+
+    #[strum(serialize = "clear_input_history")]
+    #[strum(message = "Clear Search and Palette History")]
+    ClearInputHistory,
+
     #[strum(message = "Go To Symbol In File")]
     #[strum(serialize = "palette.symbol")]
     PaletteSymbol,
@@ -315,10 +461,34 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "palette.workspace")]
     PaletteWorkspace,
 
+    #[strum(message = "Remove Workspace From Recent List")]
+    #[strum(serialize = "palette.remove_recent_workspace")]
+    PaletteRemoveRecentWorkspace,
+
+    #[strum(message = "Pin or Unpin Recent Workspace")]
+    #[strum(serialize = "palette.toggle_pin_recent_workspace")]
+    PaletteTogglePinRecentWorkspace,
+
+    #[strum(message = "Pin Current Workspace")]
+    #[strum(serialize = "pin_workspace")]
+    PinWorkspace,
+
+    #[strum(message = "Unpin Current Workspace")]
+    #[strum(serialize = "unpin_workspace")]
+    UnpinWorkspace,
+
     #[strum(message = "Run and Debug")]
     #[strum(serialize = "palette.run_and_debug")]
     PaletteRunAndDebug,
 
+    #[strum(message = "Run Task")]
+    #[strum(serialize = "palette.run_task")]
+    PaletteRunTask,
+
+    #[strum(message = "Run File")]
+    #[strum(serialize = "run_file")]
+    RunFile,
+
     #[strum(message = "Source Control: Checkout")]
     #[strum(serialize = "palette.scm_references")]
     PaletteSCMReferences,
@@ -410,6 +580,10 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "toggle_debug_visual")]
     ToggleDebugVisual,
 
+    #[strum(message = "Toggle Undo Tree")]
+    #[strum(serialize = "toggle_undo_tree_visual")]
+    ToggleUndoTreeVisual,
+
     #[strum(serialize = "toggle_search_visual")]
     ToggleSearchVisual,
 
@@ -426,6 +600,18 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "source_control_commit")]
     SourceControlCommit,
 
+    #[strum(message = "Source Control: Show File History")]
+    #[strum(serialize = "source_control_file_history")]
+    SourceControlFileHistory,
+
+    #[strum(message = "Source Control: Pull")]
+    #[strum(serialize = "source_control_pull")]
+    SourceControlPull,
+
+    #[strum(message = "Source Control: Push")]
+    #[strum(serialize = "source_control_push")]
+    SourceControlPush,
+
     #[strum(message = "Source Control: Copy Remote File Url")]
     #[strum(serialize = "source_control_copy_active_file_remote_url")]
     SourceControlCopyActiveFileRemoteUrl,
@@ -453,6 +639,18 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Change current file language")]
     ChangeFileLanguage,
 
+    #[strum(serialize = "change_file_line_ending")]
+    #[strum(message = "Change current file line ending")]
+    ChangeFileLineEnding,
+
+    #[strum(serialize = "change_file_indentation")]
+    #[strum(message = "Change current file indentation")]
+    ChangeFileIndentation,
+
+    #[strum(serialize = "toggle_read_only")]
+    #[strum(message = "Toggle Read Only")]
+    ToggleReadOnly,
+
     #[strum(serialize = "next_editor_tab")]
     #[strum(message = "Next Editor Tab")]
     NextEditorTab,
@@ -524,6 +722,10 @@ pub enum InternalCommand {
     OpenFileChanges {
         path: PathBuf,
     },
+    OpenDiffFiles {
+        left_path: PathBuf,
+        right_path: PathBuf,
+    },
     GoToLocation {
         location: EditorLocation,
     },
@@ -587,12 +789,29 @@ pub enum InternalCommand {
         mode: RunDebugMode,
         config: RunDebugConfig,
     },
+    RunTask {
+        task: TaskConfig,
+    },
+    /// Run or debug a test or binary the language server found.
+    RunRunnable {
+        mode: RunDebugMode,
+        runnable: Runnable,
+    },
     StartRename {
         path: PathBuf,
         placeholder: String,
         start: usize,
         position: Position,
     },
+    /// Open the peek window below the line of the offset in the active editor.
+    ShowPeek {
+        offset: usize,
+        locations: Vec<EditorLocation>,
+    },
+    /// Open the color picker for the color at the offset in the active editor.
+    StartColorPicker {
+        offset: usize,
+    },
     Search {
         pattern: Option<String>,
     },
@@ -641,7 +860,12 @@ pub enum InternalCommand {
         buttons: Vec<AlertButton>,
     },
     HideAlert,
-    SaveScratchDoc {
+    ShowNotification {
+        title: String,
+        level: MessageType,
+        message: String,
+    },
+    SaveDocAs {
         doc: Rc<Document>,
     },
     UpdateProxyStatus {
@@ -671,6 +895,11 @@ pub enum WindowCommand {
     },
     NextWorkspaceTab,
     PreviousWorkspaceTab,
+    MoveWorkspaceTabToNewWindow {
+        index: Option<usize>,
+    },
     NewWindow,
     CloseWindow,
+    /// Quit the app, once the unsaved changes of every window were asked about.
+    Quit,
 }
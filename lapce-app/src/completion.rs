@@ -13,8 +13,12 @@
 use nucleo::Utf32Str;
 
 use crate::{
-    config::LapceConfig, doc::Document, editor::view_data::EditorViewData,
-    id::EditorId, snippet::Snippet,
+    config::LapceConfig,
+    doc::Document,
+    editor::view_data::EditorViewData,
+    id::EditorId,
+    perf::{self, Metric},
+    snippet::Snippet,
 };
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -61,6 +65,9 @@ pub struct CompletionData {
     pub layout_rect: Rect,
     /// The editor id that was most recently used to trigger a completion.
     pub latest_editor_id: Option<EditorId>,
+    /// Whether the only match is inserted as soon as the items arrive, for
+    /// completions asked for rather than shown while typing.
+    pub auto_insert: bool,
     /// Matcher for filtering the completion items
     matcher: RwSignal<nucleo::Matcher>,
     config: ReadSignal<Arc<LapceConfig>>,
@@ -83,6 +90,7 @@ pub fn new(cx: Scope, config: ReadSignal<Arc<LapceConfig>>) -> Self {
             matcher: cx
                 .create_rw_signal(nucleo::Matcher::new(nucleo::Config::DEFAULT)),
             latest_editor_id: None,
+            auto_insert: false,
             config,
         }
     }
@@ -106,7 +114,7 @@ pub fn receive(
             // TODO: Possibly handle the 'is_incomplete' field on List.
             CompletionResponse::List(list) => &list.items,
         };
-        let items: im::Vector<ScoredCompletionItem> = items
+        let mut items: im::Vector<ScoredCompletionItem> = items
             .iter()
             .map(|i| ScoredCompletionItem {
                 item: i.to_owned(),
@@ -116,6 +124,9 @@ pub fn receive(
                 indices: Vec::new(),
             })
             .collect();
+        // Without any input to match, the items are shown in the order the
+        // language server asks for.
+        items.sort_by(|a, b| sort_text(&a.item).cmp(sort_text(&b.item)));
         self.input_items.insert(input.to_string(), items);
         self.filter_items();
     }
@@ -142,6 +153,7 @@ pub fn cancel(&mut self) {
         self.status = CompletionStatus::Inactive;
         self.input_id = 0;
         self.latest_editor_id = None;
+        self.auto_insert = false;
         self.active.set(0);
         self.input.clear();
         self.input_items.clear();
@@ -153,6 +165,7 @@ pub fn update_input(&mut self, input: String) {
             return;
         }
         self.input = input;
+        self.auto_insert = false;
         // TODO: If the user types a letter that continues the current active item, we should
         // try keeping that item active. Possibly give this a setting.
         // ex: `p` has `print!` and `println!` has options. If you select the second, then type
@@ -172,6 +185,7 @@ fn all_items(&self) -> im::Vector<ScoredCompletionItem> {
     }
 
     pub fn filter_items(&mut self) {
+        let _timer = perf::time(Metric::Completion);
         self.input_id += 1;
         if self.input.is_empty() {
             self.filtered_items = self.all_items();
@@ -191,50 +205,45 @@ pub fn filter_items(&mut self) {
                     .filter_map(|i| {
                         let filter_text =
                             i.item.filter_text.as_ref().unwrap_or(&i.item.label);
-                        let shift = i
-                            .item
-                            .label
-                            .match_indices(filter_text)
-                            .next()
-                            .map(|(shift, _)| shift)
-                            .unwrap_or(0);
                         let mut indices = Vec::new();
                         let mut filter_text_buf = Vec::new();
-                        let filter_text =
-                            Utf32Str::new(filter_text, &mut filter_text_buf);
+                        let score = pattern.indices(
+                            Utf32Str::new(filter_text, &mut filter_text_buf),
+                            matcher,
+                            &mut indices,
+                        )?;
+                        let mut item = i.clone();
+                        item.score = score;
+                        item.label_score = score;
+
+                        let mut label_buf = Vec::new();
+                        let label_text =
+                            Utf32Str::new(&i.item.label, &mut label_buf);
+                        let mut label_indices = Vec::new();
                         if let Some(score) =
-                            pattern.indices(filter_text, matcher, &mut indices)
+                            pattern.indices(label_text, matcher, &mut label_indices)
                         {
-                            if shift > 0 {
-                                for idx in indices.iter_mut() {
-                                    *idx += shift as u32;
-                                }
-                            }
-                            let mut item = i.clone();
-                            item.score = score;
                             item.label_score = score;
-                            item.indices =
-                                indices.into_iter().map(|i| i as usize).collect();
-
-                            let mut label_buf = Vec::new();
-                            let label_text =
-                                Utf32Str::new(&i.item.label, &mut label_buf);
-                            if let Some(score) = pattern.score(label_text, matcher) {
-                                item.label_score = score;
-                            }
-                            Some(item)
-                        } else {
-                            None
                         }
+                        // Highlight where the filter text matched if the label
+                        // shows it, otherwise where the label itself matches.
+                        item.indices = match i.item.label.find(filter_text.as_str())
+                        {
+                            Some(shift) => byte_indices(filter_text, indices, shift),
+                            None => byte_indices(&i.item.label, label_indices, 0),
+                        };
+                        Some(item)
                     })
                     .collect()
             })
             .unwrap();
-        // Sort all the items by their score, then their label score, then their length.
+        // Sort all the items by their score, then their label score, then the order
+        // the language server gave them, then their length.
         items.sort_by(|a, b| {
             b.score
                 .cmp(&a.score)
                 .then_with(|| b.label_score.cmp(&a.label_score))
+                .then_with(|| sort_text(&a.item).cmp(sort_text(&b.item)))
                 .then_with(|| a.item.label.len().cmp(&b.item.label.len()))
         });
         self.filtered_items = items;
@@ -338,6 +347,30 @@ pub fn update_document_completion(
     }
 }
 
+/// The text the language server wants the item sorted by, when it scores the
+/// same as another.
+fn sort_text(item: &CompletionItem) -> &str {
+    item.sort_text.as_deref().unwrap_or(&item.label)
+}
+
+/// Turn the char indices of the matched characters of `text` into the sorted
+/// byte offsets in the label, where `text` starts at `shift`.
+fn byte_indices(text: &str, mut indices: Vec<u32>, shift: usize) -> Vec<usize> {
+    indices.sort_unstable();
+    indices.dedup();
+    let mut indices = indices.into_iter().peekable();
+    let mut offsets = Vec::new();
+    for (i, (offset, _)) in text.char_indices().enumerate() {
+        if indices.peek().is_none() {
+            break;
+        }
+        if indices.next_if_eq(&(i as u32)).is_some() {
+            offsets.push(offset + shift);
+        }
+    }
+    offsets
+}
+
 /// Clear the current completion lens. Only `update`s if there is a completion lens.
 pub fn clear_completion_lens(doc: Rc<Document>) {
     let has_completion = doc.completion_lens.with_untracked(|lens| lens.is_some());
@@ -422,3 +455,14 @@ fn completion_lens_text(
         Some(Some(item.to_string()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::byte_indices;
+
+    #[test]
+    fn test_byte_indices() {
+        assert_eq!(byte_indices("äbc", vec![2, 0, 2], 0), vec![0, 3]);
+        assert_eq!(byte_indices("bar", vec![0, 2], 4), vec![4, 6]);
+    }
+}
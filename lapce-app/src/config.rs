@@ -14,19 +14,26 @@
 use parking_lot::RwLock;
 use serde::Deserialize;
 use strum::VariantNames;
+use tracing::error;
 
 use self::{
     color::LapceColor,
-    color_theme::{ColorThemeConfig, ThemeColor, ThemeColorPreference},
-    core::CoreConfig,
+    color_theme::{
+        ColorThemeConfig, ScopeFontStyle, ThemeColor, ThemeColorPreference,
+    },
+    core::{CoreConfig, KeymapPreset},
     editor::{EditorConfig, SCALE_OR_SIZE_LIMIT},
     icon::LapceIcons,
     icon_theme::IconThemeConfig,
+    os_theme::OsTheme,
     svg::SvgStore,
     terminal::TerminalConfig,
     ui::UIConfig,
 };
-use crate::workspace::{LapceWorkspace, LapceWorkspaceType};
+use crate::{
+    localization,
+    workspace::{LapceWorkspace, LapceWorkspaceType},
+};
 
 pub mod color;
 pub mod color_theme;
@@ -34,6 +41,9 @@
 pub mod editor;
 pub mod icon;
 pub mod icon_theme;
+pub mod os_accessibility;
+mod os_settings;
+pub mod os_theme;
 pub mod svg;
 pub mod terminal;
 pub mod ui;
@@ -43,8 +53,13 @@
 const DEFAULT_SETTINGS: &str = include_str!("../../defaults/settings.toml");
 const DEFAULT_LIGHT_THEME: &str = include_str!("../../defaults/light-theme.toml");
 const DEFAULT_DARK_THEME: &str = include_str!("../../defaults/dark-theme.toml");
+const DEFAULT_HIGH_CONTRAST_THEME: &str =
+    include_str!("../../defaults/high-contrast-theme.toml");
 const DEFAULT_ICON_THEME: &str = include_str!("../../defaults/icon-theme.toml");
 
+/// Bounds the scope lookup, in case the theme maps scopes to each other in a loop.
+const MAX_SCOPE_LOOKUPS: usize = 16;
+
 static DEFAULT_CONFIG: Lazy<config::Config> = Lazy::new(LapceConfig::default_config);
 static DEFAULT_LAPCE_CONFIG: Lazy<LapceConfig> =
     Lazy::new(LapceConfig::default_lapce_config);
@@ -103,7 +118,11 @@ pub fn load(workspace: &LapceWorkspace, disabled_volts: &[VoltID]) -> Self {
         lapce_config.available_color_themes =
             Self::load_color_themes(disabled_volts);
         lapce_config.available_icon_themes = Self::load_icon_themes(disabled_volts);
+        os_accessibility::set_followed(lapce_config.core.follow_os_accessibility);
+        os_theme::set_followed(lapce_config.core.follow_os_theme);
+        lapce_config.follow_os_theme();
         lapce_config.resolve_theme(workspace);
+        localization::load(&lapce_config.core.locale);
 
         lapce_config.color_theme_list = lapce_config
             .available_color_themes
@@ -171,7 +190,8 @@ fn merge_config(
             }
             LapceWorkspaceType::RemoteSSH(_) => {}
             #[cfg(windows)]
-            LapceWorkspaceType::RemoteWSL => {}
+            LapceWorkspaceType::RemoteWSL(_) => {}
+            LapceWorkspaceType::RemoteContainer(_) => {}
         }
 
         config
@@ -201,6 +221,34 @@ fn default_lapce_config() -> LapceConfig {
         default_lapce_config
     }
 
+    /// The settings as they are when the settings file doesn't change any.
+    pub fn default_settings() -> &'static LapceConfig {
+        &DEFAULT_LAPCE_CONFIG
+    }
+
+    /// Use the color theme set for the dark or light mode of the operating system,
+    /// or the high contrast one while the operating system asks for that.
+    fn follow_os_theme(&mut self) {
+        if self.core.follow_os_accessibility
+            && os_accessibility::current().high_contrast
+            && !self.core.high_contrast_color_theme.is_empty()
+        {
+            self.core.color_theme = self.core.high_contrast_color_theme.clone();
+            return;
+        }
+        if !self.core.follow_os_theme {
+            return;
+        }
+        let theme = match os_theme::current() {
+            Some(OsTheme::Dark) => &self.core.dark_color_theme,
+            Some(OsTheme::Light) => &self.core.light_color_theme,
+            None => return,
+        };
+        if !theme.is_empty() {
+            self.core.color_theme = theme.clone();
+        }
+    }
+
     fn resolve_theme(&mut self, workspace: &LapceWorkspace) {
         let mut default_lapce_config = DEFAULT_LAPCE_CONFIG.clone();
         if let Some((_, color_theme_config)) = self
@@ -241,7 +289,11 @@ fn resolve_theme(&mut self, workspace: &LapceWorkspace) {
             )
             .try_deserialize::<LapceConfig>()
             {
+                // The color theme isn't the one in the settings while it follows
+                // the OS or is being previewed
+                let color_theme = std::mem::take(&mut self.core.color_theme);
                 self.core = new.core;
+                self.core.color_theme = color_theme;
                 self.ui = new.ui;
                 self.editor = new.editor;
                 self.terminal = new.terminal;
@@ -277,10 +329,24 @@ fn load_color_themes(
         let (name, theme) =
             Self::load_color_theme_from_str(DEFAULT_DARK_THEME).unwrap();
         themes.insert(name.to_lowercase(), (name, theme));
+        let (name, theme) =
+            Self::load_color_theme_from_str(DEFAULT_HIGH_CONTRAST_THEME).unwrap();
+        themes.insert(name.to_lowercase(), (name, theme));
 
         themes
     }
 
+    /// The interval the cursor blinks at, which is 0 for it not to blink while
+    /// the operating system asks for reduced motion.
+    pub fn cursor_blink_interval(&self) -> u64 {
+        if self.core.follow_os_accessibility
+            && os_accessibility::current().reduced_motion
+        {
+            return 0;
+        }
+        self.editor.blink_interval()
+    }
+
     /// Set the active color theme.
     /// Note that this does not save the config.
     pub fn set_color_theme(&mut self, workspace: &LapceWorkspace, theme: &str) {
@@ -295,6 +361,14 @@ pub fn set_icon_theme(&mut self, workspace: &LapceWorkspace, theme: &str) {
         self.resolve_theme(workspace);
     }
 
+    /// Set how much the editor font is zoomed in, which invalidates the text
+    /// layouts made with the previous font size.
+    /// Note that this does not save the config.
+    pub fn set_editor_zoom_level(&mut self, zoom_level: isize) {
+        self.editor.zoom_level = zoom_level;
+        self.update_id();
+    }
+
     pub fn set_modal(&mut self, _workspace: &LapceWorkspace, modal: bool) {
         self.core.modal = modal;
     }
@@ -313,7 +387,33 @@ pub fn get_color(&self, name: &str) -> &Color {
 
     /// Retrieve a color value whose key starts with "style."
     pub fn get_style_color(&self, name: &str) -> Option<&Color> {
-        self.color.syntax.get(name)
+        self.style_scope(name, |scope| self.color.syntax.get(scope))
+    }
+
+    pub fn get_style_font(&self, name: &str) -> Option<&ScopeFontStyle> {
+        self.style_scope(name, |scope| self.color.font_style.get(scope))
+    }
+
+    /// Look up a syntax scope such as `function.macro` or `variable.mutable`.
+    /// When the theme has nothing for it, the scope it is mapped to is tried, and
+    /// then its parent scope with the last segment removed.
+    fn style_scope<'a, T>(
+        &'a self,
+        name: &str,
+        get: impl Fn(&str) -> Option<&'a T>,
+    ) -> Option<&'a T> {
+        let mut scope = name;
+        for _ in 0..MAX_SCOPE_LOOKUPS {
+            if let Some(value) = get(scope) {
+                return Some(value);
+            }
+            scope = if let Some(mapped) = self.color.scopes.get(scope) {
+                mapped
+            } else {
+                scope.rsplit_once('.')?.0
+            };
+        }
+        None
     }
 
     pub fn completion_color(
@@ -354,6 +454,8 @@ fn resolve_colors(&mut self, default_config: Option<&LapceConfig>) {
             &self.color.base,
             default_config.map(|c| &c.color.syntax),
         );
+        self.color.scopes = self.color_theme.scopes.clone().into_iter().collect();
+        self.color.font_style = self.color_theme.resolve_font_style();
 
         let fg = self.get_color(LapceColor::EDITOR_FOREGROUND);
         let bg = self.get_color(LapceColor::EDITOR_BACKGROUND);
@@ -386,12 +488,25 @@ fn load_color_theme(path: &Path) -> Option<(String, (String, config::Config))> {
         if !path.is_file() {
             return None;
         }
-        let config = config::Config::builder()
+        // A theme with a mistake in it is missing from the list, so say why
+        let config = match config::Config::builder()
             .add_source(config::File::from(path))
             .build()
-            .ok()?;
-        let table = config.get_table("color-theme").ok()?;
-        let name = table.get("name")?.to_string();
+        {
+            Ok(config) => config,
+            Err(err) => {
+                error!("Failed to load color theme {path:?}: {err}");
+                return None;
+            }
+        };
+        let Some(name) = config
+            .get_table("color-theme")
+            .ok()
+            .and_then(|table| Some(table.get("name")?.to_string()))
+        else {
+            error!("Color theme {path:?} has no color-theme.name");
+            return None;
+        };
         Some((name.to_lowercase(), (name, config)))
     }
 
@@ -595,6 +710,44 @@ pub fn symbol_svg(&self, kind: &SymbolKind) -> Option<String> {
         Some(self.ui_svg(kind_str))
     }
 
+    pub fn completion_svg(
+        &self,
+        kind: Option<CompletionItemKind>,
+    ) -> Option<String> {
+        let kind_str = match kind? {
+            CompletionItemKind::CLASS => LapceIcons::COMPLETION_ITEM_KIND_CLASS,
+            CompletionItemKind::CONSTANT => {
+                LapceIcons::COMPLETION_ITEM_KIND_CONSTANT
+            }
+            CompletionItemKind::ENUM_MEMBER => {
+                LapceIcons::COMPLETION_ITEM_KIND_ENUM_MEMBER
+            }
+            CompletionItemKind::ENUM => LapceIcons::COMPLETION_ITEM_KIND_ENUM,
+            CompletionItemKind::FIELD => LapceIcons::COMPLETION_ITEM_KIND_FIELD,
+            CompletionItemKind::FUNCTION => {
+                LapceIcons::COMPLETION_ITEM_KIND_FUNCTION
+            }
+            CompletionItemKind::INTERFACE => {
+                LapceIcons::COMPLETION_ITEM_KIND_INTERFACE
+            }
+            CompletionItemKind::KEYWORD => LapceIcons::COMPLETION_ITEM_KIND_KEYWORD,
+            CompletionItemKind::METHOD => LapceIcons::COMPLETION_ITEM_KIND_METHOD,
+            CompletionItemKind::MODULE => LapceIcons::COMPLETION_ITEM_KIND_MODULE,
+            CompletionItemKind::PROPERTY => {
+                LapceIcons::COMPLETION_ITEM_KIND_PROPERTY
+            }
+            CompletionItemKind::SNIPPET => LapceIcons::COMPLETION_ITEM_KIND_SNIPPET,
+            CompletionItemKind::TEXT => LapceIcons::COMPLETION_ITEM_KIND_STRING,
+            CompletionItemKind::STRUCT => LapceIcons::COMPLETION_ITEM_KIND_STRUCT,
+            CompletionItemKind::VARIABLE => {
+                LapceIcons::COMPLETION_ITEM_KIND_VARIABLE
+            }
+            _ => return None,
+        };
+
+        Some(self.ui_svg(kind_str))
+    }
+
     pub fn logo_svg(&self) -> String {
         self.svg_store.read().logo_svg()
     }
@@ -609,11 +762,18 @@ pub fn icon_theme_list(&self) -> im::Vector<String> {
         self.icon_theme_list.clone()
     }
 
-    pub fn terminal_font_family(&self) -> &str {
+    /// The terminal font family, or the editor's when it isn't set, followed by
+    /// the editor's fallback fonts.
+    pub fn terminal_font_family(&self) -> String {
         if self.terminal.font_family.is_empty() {
-            self.editor.font_family.as_str()
+            self.editor.font_family_list()
+        } else if self.editor.font_fallback.trim().is_empty() {
+            self.terminal.font_family.clone()
         } else {
-            self.terminal.font_family.as_str()
+            format!(
+                "{}, {}",
+                self.terminal.font_family, self.editor.font_fallback
+            )
         }
     }
 
@@ -794,6 +954,30 @@ pub fn get_dropdown_info(&self, kind: &str, key: &str) -> Option<DropdownInfo> {
                     .unwrap_or(0),
                 items: self.color_theme_list.clone(),
             }),
+            ("core", "dark-color-theme") => Some(DropdownInfo {
+                active_index: self
+                    .color_theme_list
+                    .iter()
+                    .position(|s| s == &self.core.dark_color_theme)
+                    .unwrap_or(0),
+                items: self.color_theme_list.clone(),
+            }),
+            ("core", "light-color-theme") => Some(DropdownInfo {
+                active_index: self
+                    .color_theme_list
+                    .iter()
+                    .position(|s| s == &self.core.light_color_theme)
+                    .unwrap_or(0),
+                items: self.color_theme_list.clone(),
+            }),
+            ("core", "high-contrast-color-theme") => Some(DropdownInfo {
+                active_index: self
+                    .color_theme_list
+                    .iter()
+                    .position(|s| s == &self.core.high_contrast_color_theme)
+                    .unwrap_or(0),
+                items: self.color_theme_list.clone(),
+            }),
             ("core", "icon-theme") => Some(DropdownInfo {
                 active_index: self
                     .icon_theme_list
@@ -802,6 +986,13 @@ pub fn get_dropdown_info(&self, kind: &str, key: &str) -> Option<DropdownInfo> {
                     .unwrap_or(0),
                 items: self.icon_theme_list.clone(),
             }),
+            ("core", "keymap-preset") => Some(DropdownInfo {
+                active_index: self.core.keymap_preset as usize,
+                items: KeymapPreset::VARIANTS
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect(),
+            }),
             ("ui", "tab-close-button") => Some(DropdownInfo {
                 active_index: self.ui.tab_close_button as usize,
                 items: ui::TabCloseButton::VARIANTS
@@ -69,6 +69,15 @@ impl LapceColor {
     pub const EDITOR_LINK: &'static str = "editor.link";
     pub const EDITOR_VISIBLE_WHITESPACE: &'static str = "editor.visible_whitespace";
     pub const EDITOR_INDENT_GUIDE: &'static str = "editor.indent_guide";
+    pub const EDITOR_INDENT_GUIDE_ACTIVE: &'static str =
+        "editor.indent_guide_active";
+    pub const EDITOR_RULER: &'static str = "editor.ruler";
+    pub const EDITOR_SPELLING_ERROR: &'static str = "editor.spelling_error";
+    pub const EDITOR_SYMBOL_HIGHLIGHT: &'static str = "editor.symbol_highlight";
+    pub const EDITOR_SYMBOL_HIGHLIGHT_WRITE: &'static str =
+        "editor.symbol_highlight_write";
+    pub const EDITOR_SELECTION_OCCURRENCE: &'static str =
+        "editor.selection_occurrence";
     pub const EDITOR_DRAG_DROP_BACKGROUND: &'static str =
         "editor.drag_drop_background";
     pub const EDITOR_STICKY_HEADER_BACKGROUND: &'static str =
@@ -80,6 +80,32 @@ pub struct ThemeColor {
     pub base: ThemeBaseColor,
     pub syntax: HashMap<String, Color>,
     pub ui: HashMap<String, Color>,
+    pub scopes: HashMap<String, String>,
+    pub font_style: HashMap<String, ScopeFontStyle>,
+}
+
+/// How the text of a syntax scope is drawn, on top of its color.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScopeFontStyle {
+    pub italic: bool,
+    pub bold: bool,
+    pub underline: bool,
+}
+
+impl ScopeFontStyle {
+    /// Parse a list of styles such as `"bold italic"`. Unknown words are ignored.
+    pub fn parse(s: &str) -> Self {
+        let mut style = ScopeFontStyle::default();
+        for word in s.split(|c: char| c.is_whitespace() || c == ',') {
+            match word.to_lowercase().as_str() {
+                "italic" => style.italic = true,
+                "bold" => style.bold = true,
+                "underline" => style.underline = true,
+                _ => {}
+            }
+        }
+        style
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
@@ -160,6 +186,12 @@ pub struct ColorThemeConfig {
     pub base: ThemeBaseConfig,
     pub syntax: BTreeMap<String, String>,
     pub ui: BTreeMap<String, String>,
+    /// Scopes that are styled like another one, e.g. `"selfKeyword" = "keyword"`.
+    #[serde(default)]
+    pub scopes: BTreeMap<String, String>,
+    /// The font styles of scopes, e.g. `"comment" = "italic"`.
+    #[serde(default)]
+    pub font_style: BTreeMap<String, String>,
 }
 
 impl ColorThemeConfig {
@@ -203,4 +235,11 @@ pub(super) fn resolve_syntax_color(
     ) -> HashMap<String, Color> {
         Self::resolve_color(&self.syntax, base, default)
     }
+
+    pub(super) fn resolve_font_style(&self) -> HashMap<String, ScopeFontStyle> {
+        self.font_style
+            .iter()
+            .map(|(name, style)| (name.to_string(), ScopeFontStyle::parse(style)))
+            .collect()
+    }
 }
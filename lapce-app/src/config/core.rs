@@ -1,6 +1,10 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 use structdesc::FieldNames;
 
+use crate::workspace::SshHost;
+
 #[derive(FieldNames, Debug, Clone, Deserialize, Serialize, Default)]
 #[serde(rename_all = "kebab-case")]
 pub struct CoreConfig {
@@ -8,10 +12,62 @@ pub struct CoreConfig {
     pub modal: bool,
     #[field_names(desc = "Set the color theme of Lapce")]
     pub color_theme: String,
+    #[field_names(
+        desc = "Switch between the dark and light color theme when the operating system switches between dark and light mode"
+    )]
+    pub follow_os_theme: bool,
+    #[field_names(desc = "Set the color theme used in dark mode")]
+    pub dark_color_theme: String,
+    #[field_names(desc = "Set the color theme used in light mode")]
+    pub light_color_theme: String,
+    #[field_names(
+        desc = "Follow the reduced motion and high contrast settings of the operating system"
+    )]
+    pub follow_os_accessibility: bool,
+    #[field_names(
+        desc = "Set the color theme used while the operating system is in high contrast mode"
+    )]
+    pub high_contrast_color_theme: String,
+    #[field_names(
+        desc = "Set the language of the UI, like de-DE, from the catalogs in the locales folder. If empty, it uses the one of the operating system."
+    )]
+    pub locale: String,
     #[field_names(desc = "Set the icon theme of Lapce")]
     pub icon_theme: String,
     #[field_names(
         desc = "Enable customised titlebar and disable OS native one (Linux, BSD, Windows)"
     )]
     pub custom_titlebar: bool,
+    #[field_names(
+        desc = "Set the key bindings the user's keymaps are added to. Emacs adds Emacs style editing keys to the defaults"
+    )]
+    pub keymap_preset: KeymapPreset,
+    #[field_names(
+        desc = "How many searches, replacements and palette queries are remembered for each workspace"
+    )]
+    pub input_history_size: usize,
+    #[field_names(
+        desc = "Remote hosts to connect to over SSH, by name. Each one has a host, and optionally a user, a port and how to log in"
+    )]
+    #[serde(default)]
+    pub ssh_hosts: HashMap<String, SshHost>,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Deserialize,
+    Serialize,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    strum_macros::EnumVariantNames,
+)]
+pub enum KeymapPreset {
+    #[default]
+    Default,
+    Emacs,
 }
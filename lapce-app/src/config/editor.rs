@@ -1,3 +1,7 @@
+use std::{collections::HashMap, time::Duration};
+
+use floem::cosmic_text::FamilyOwned;
+use lapce_core::language::LapceLanguage;
 use serde::{Deserialize, Serialize};
 use structdesc::FieldNames;
 
@@ -19,8 +23,16 @@ pub enum ClickMode {
 pub struct EditorConfig {
     #[field_names(desc = "Set the editor font family")]
     pub font_family: String,
+    #[field_names(
+        desc = "Set the fonts to use for the characters the editor font doesn't have, like CJK or emoji, in the order they are tried"
+    )]
+    pub font_fallback: String,
     #[field_names(desc = "Set the editor font size")]
     font_size: usize,
+    #[field_names(
+        desc = "Set how many points larger, or smaller when negative, the editor font is shown than its font size. This is changed by zooming the editor in and out"
+    )]
+    pub zoom_level: isize,
     #[field_names(desc = "Set the font size in the code lens")]
     pub code_lens_font_size: usize,
     #[field_names(
@@ -51,6 +63,34 @@ pub struct EditorConfig {
         desc = "If the editor should show the documentation of the current completion item"
     )]
     pub completion_show_documentation: bool,
+    #[field_names(
+        desc = "Show completions as you type. If disabled, they are only shown when asked for with Get Completion"
+    )]
+    pub completion_on_typing: bool,
+    #[field_names(
+        desc = "How many characters of a word have to be typed before completions are shown, unless it follows a trigger character"
+    )]
+    pub completion_min_prefix_length: usize,
+    #[field_names(
+        desc = "How long (in ms) typing has to pause before completions are shown. Set to 0 to show them right away"
+    )]
+    pub completion_debounce: u64,
+    #[field_names(
+        desc = "Insert the completion right away when it is the only match of completions asked for with Get Completion"
+    )]
+    pub completion_auto_insert_single: bool,
+    #[field_names(
+        desc = "Whether Enter accepts the current completion item. If disabled, only Tab does"
+    )]
+    pub completion_accept_on_enter: bool,
+    #[field_names(
+        desc = "The characters that show completions when typed, e.g. [\".\", \":\"]"
+    )]
+    pub completion_trigger_characters: Vec<String>,
+    #[field_names(
+        desc = "The completion trigger characters of a language, used instead of the trigger characters setting for its files"
+    )]
+    pub language_completion_trigger_characters: HashMap<String, Vec<String>>,
     #[field_names(
         desc = "If the editor should show the signature of the function as the parameters are being typed"
     )]
@@ -79,10 +119,45 @@ pub struct EditorConfig {
         desc = "Whether it should format the document on save (if there is an available formatter)"
     )]
     pub format_on_save: bool,
+    #[field_names(
+        desc = "The kinds of code actions to run on save, before formatting, e.g. [\"source.fixAll\", \"source.organizeImports\"]"
+    )]
+    pub code_actions_on_save: Vec<String>,
+    #[field_names(
+        desc = "The code actions of a language to run on save, used instead of the code actions on save setting for its files"
+    )]
+    pub language_code_actions_on_save: HashMap<String, Vec<String>>,
+    #[field_names(desc = "Remove trailing whitespace from every line on save")]
+    pub trim_trailing_whitespace: bool,
+    #[field_names(
+        desc = "Keep the trailing whitespace of the line the cursor is on when trimming it on save"
+    )]
+    pub trim_trailing_whitespace_skip_cursor_line: bool,
+    #[field_names(desc = "Make sure the file ends with a newline on save")]
+    pub insert_final_newline: bool,
+    #[field_names(
+        desc = "Remove the blank lines at the end of the file on save, leaving a single newline"
+    )]
+    pub trim_final_newlines: bool,
 
     #[field_names(desc = "If matching brackets are highlighted")]
     pub highlight_matching_brackets: bool,
 
+    #[field_names(
+        desc = "If the other occurrences of the symbol under the cursor are highlighted"
+    )]
+    pub highlight_symbol: bool,
+
+    #[field_names(
+        desc = "If the other occurrences of the selected text are highlighted"
+    )]
+    pub highlight_selection_occurrences: bool,
+
+    #[field_names(
+        desc = "How many characters the selection needs before its other occurrences are highlighted"
+    )]
+    pub highlight_selection_min_length: usize,
+
     #[field_names(desc = "If scope lines are highlighted")]
     pub highlight_scope_lines: bool,
 
@@ -123,6 +198,20 @@ pub struct EditorConfig {
         desc = "Set the completion lens font size. If 0 it uses the inlay hint font size."
     )]
     pub completion_lens_font_size: usize,
+    #[field_names(desc = "Underline misspelled words in comments and strings")]
+    pub spell_check: bool,
+    #[field_names(
+        desc = "The word list to check spelling against, with one word on each line. If empty, the system word list at /usr/share/dict/words is used. Words added from the editor go in .lapce/dictionary.txt of the workspace"
+    )]
+    pub spell_check_dictionary: String,
+    #[field_names(
+        desc = "Show a swatch before the colors written in the code, which opens a color picker when clicked"
+    )]
+    pub color_swatches: bool,
+    #[field_names(
+        desc = "Show buttons to run or debug the tests and main functions the language server finds"
+    )]
+    pub show_runnables: bool,
     #[field_names(
         desc = "Set the cursor blink interval (in milliseconds). Set to 0 to completely disable."
     )]
@@ -136,11 +225,19 @@ pub struct EditorConfig {
     )]
     pub multicursor_whole_words: bool,
     #[field_names(
-        desc = "How the editor should render whitespace characters.\nOptions: none, all, boundary, trailing."
+        desc = "How the editor should render whitespace characters.\nOptions: none, all, boundary, trailing, selection."
     )]
     pub render_whitespace: String,
     #[field_names(desc = "Whether the editor show indent guide.")]
     pub show_indent_guide: bool,
+    #[field_names(
+        desc = "The columns vertical rulers are shown at, e.g. [80, 100]"
+    )]
+    pub rulers: Vec<usize>,
+    #[field_names(
+        desc = "The rulers of a language, used instead of the rulers setting for its files"
+    )]
+    pub language_rulers: HashMap<String, Vec<usize>>,
     #[field_names(
         desc = "Set the auto save delay (in milliseconds), Set to 0 to completely disable"
     )]
@@ -149,10 +246,38 @@ pub struct EditorConfig {
         desc = "Whether the document should be formatted when an autosave is triggered (required Format on Save)"
     )]
     pub format_on_autosave: bool,
+    #[field_names(
+        desc = "Save all the files with unsaved changes when the window loses focus (formatted if Format on Autosave is set)"
+    )]
+    pub save_on_focus_lost: bool,
+    #[field_names(
+        desc = "Save all the files with unsaved changes before running a task or starting a debug session"
+    )]
+    pub save_before_run: bool,
+    #[field_names(
+        desc = "Set how often (in milliseconds) unsaved changes are backed up so they can be restored after a crash. Set to 0 to completely disable"
+    )]
+    pub recovery_interval: u64,
+    #[field_names(
+        desc = "When saving a symlinked file, write to the file it points to instead of replacing the link"
+    )]
+    pub write_through_symlink: bool,
     #[field_names(
         desc = "If enabled the cursor treats leading soft tabs as if they are hard tabs."
     )]
     pub atomic_soft_tabs: bool,
+    #[field_names(
+        desc = "Move the cursor the way the arrow key points in right-to-left text, like Arabic and Hebrew, instead of through the text in the order it is written."
+    )]
+    pub visual_cursor_movement: bool,
+    #[field_names(
+        desc = "Characters that are part of words besides letters, digits and `_`, for double-click selection, word motions and selecting the next occurrence, e.g. \"-\" for kebab-case"
+    )]
+    pub word_characters: String,
+    #[field_names(
+        desc = "The word characters of a language, used instead of the word characters setting for its files"
+    )]
+    pub language_word_characters: HashMap<String, String>,
     #[field_names(
         desc = "Use a double click to interact with the file explorer.\nOptions: single (default), file or all."
     )]
@@ -163,27 +288,93 @@ pub struct EditorConfig {
         desc = "Set the default number of visible lines above and below the diff block (-1 for infinite)"
     )]
     pub diff_context_lines: i32,
+    #[field_names(
+        desc = "How long (in milliseconds) typing can pause and still be undone together with what was typed before. Set to 0 to never split typing on pauses"
+    )]
+    pub undo_group_timeout: u64,
 }
 
 impl EditorConfig {
+    pub fn rulers(&self, language: LapceLanguage) -> &[usize] {
+        self.language_rulers
+            .iter()
+            .find(|(name, _)| LapceLanguage::from_name(name) == Some(language))
+            .map(|(_, rulers)| rulers)
+            .unwrap_or(&self.rulers)
+    }
+
+    pub fn word_characters(&self, language: LapceLanguage) -> &str {
+        self.language_word_characters
+            .iter()
+            .find(|(name, _)| LapceLanguage::from_name(name) == Some(language))
+            .map(|(_, characters)| characters.as_str())
+            .unwrap_or(&self.word_characters)
+    }
+
+    pub fn completion_trigger_characters(
+        &self,
+        language: LapceLanguage,
+    ) -> &[String] {
+        self.language_completion_trigger_characters
+            .iter()
+            .find(|(name, _)| LapceLanguage::from_name(name) == Some(language))
+            .map(|(_, characters)| characters)
+            .unwrap_or(&self.completion_trigger_characters)
+    }
+
+    pub fn code_actions_on_save(&self, language: LapceLanguage) -> &[String] {
+        self.language_code_actions_on_save
+            .iter()
+            .find(|(name, _)| LapceLanguage::from_name(name) == Some(language))
+            .map(|(_, kinds)| kinds)
+            .unwrap_or(&self.code_actions_on_save)
+    }
+
+    pub fn completion_debounce(&self) -> Option<Duration> {
+        (self.completion_debounce > 0)
+            .then(|| Duration::from_millis(self.completion_debounce))
+    }
+
+    pub fn undo_group_timeout(&self) -> Option<Duration> {
+        (self.undo_group_timeout > 0)
+            .then(|| Duration::from_millis(self.undo_group_timeout))
+    }
+
+    /// The editor font family followed by the fallback fonts, as a list that
+    /// can be given to a view's style.
+    pub fn font_family_list(&self) -> String {
+        if self.font_fallback.trim().is_empty() {
+            self.font_family.clone()
+        } else {
+            format!("{}, {}", self.font_family, self.font_fallback)
+        }
+    }
+
+    pub fn font_family(&self) -> Vec<FamilyOwned> {
+        FamilyOwned::parse_list(&self.font_family_list()).collect()
+    }
+
+    /// The font size with the zoom level applied.
     pub fn font_size(&self) -> usize {
-        self.font_size.max(6).min(32)
+        (self.font_size as isize + self.zoom_level).clamp(6, 32) as usize
     }
 
     pub fn line_height(&self) -> usize {
+        let font_size = self.font_size();
         let line_height = if self.line_height < SCALE_OR_SIZE_LIMIT {
-            self.line_height * self.font_size as f64
+            self.line_height * font_size as f64
         } else {
-            self.line_height
+            // A line height in pixels grows and shrinks along with the zoom
+            self.line_height * font_size as f64 / self.font_size.max(1) as f64
         };
 
         // Prevent overlapping lines
-        (line_height.round() as usize).max(self.font_size)
+        (line_height.round() as usize).max(font_size)
     }
 
     pub fn inlay_hint_font_size(&self) -> usize {
         if self.inlay_hint_font_size < 5
-            || self.inlay_hint_font_size > self.font_size
+            || self.inlay_hint_font_size > self.font_size()
         {
             self.font_size()
         } else {
@@ -27,6 +27,9 @@ impl LapceIcons {
     pub const DEBUG_STOP: &'static str = "debug_stop";
     pub const DEBUG_CONSOLE: &'static str = "debug_console";
     pub const DEBUG_DISCONNECT: &'static str = "debug_disconnect";
+    pub const UNDO_TREE: &'static str = "undo_tree";
+    pub const INFO: &'static str = "info";
+    pub const NOTIFICATIONS: &'static str = "notifications";
     pub const START: &'static str = "start";
     pub const RUN_ERRORS: &'static str = "run_errors";
     pub const UNSAVED: &'static str = "unsaved";
@@ -94,6 +97,7 @@ impl LapceIcons {
     pub const SEARCH_CASE_SENSITIVE: &'static str = "search.case_sensitive";
     pub const SEARCH_WHOLE_WORD: &'static str = "search.whole_word";
     pub const SEARCH_REGEX: &'static str = "search.regex";
+    pub const SEARCH_IN_SELECTION: &'static str = "search.in_selection";
     pub const SEARCH_REPLACE: &'static str = "search.replace";
     pub const SEARCH_REPLACE_ALL: &'static str = "search.replace_all";
 
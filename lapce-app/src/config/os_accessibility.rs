@@ -0,0 +1,133 @@
+//! The accessibility settings of the operating system that the UI follows: to
+//! reduce motion, and to use colors of high contrast.
+
+use std::{
+    process::Command,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crossbeam_channel::Sender;
+
+use super::os_settings::OsSettingsWatcher;
+
+static REDUCED_MOTION: AtomicBool = AtomicBool::new(false);
+static HIGH_CONTRAST: AtomicBool = AtomicBool::new(false);
+static WATCHER: OsSettingsWatcher = OsSettingsWatcher::new();
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OsAccessibility {
+    /// Animations, like the blinking of the cursor, should be left out
+    pub reduced_motion: bool,
+    pub high_contrast: bool,
+}
+
+/// The accessibility settings of the operating system, as last detected.
+pub fn current() -> OsAccessibility {
+    OsAccessibility {
+        reduced_motion: REDUCED_MOTION.load(Ordering::Relaxed),
+        high_contrast: HIGH_CONTRAST.load(Ordering::Relaxed),
+    }
+}
+
+/// Remember the settings, giving back the ones they replace.
+fn swap(settings: OsAccessibility) -> OsAccessibility {
+    OsAccessibility {
+        reduced_motion: REDUCED_MOTION
+            .swap(settings.reduced_motion, Ordering::Relaxed),
+        high_contrast: HIGH_CONTRAST.swap(settings.high_contrast, Ordering::Relaxed),
+    }
+}
+
+/// Keep checking the accessibility settings in the background while they are
+/// followed. Every time they change the config is reloaded through `reload`.
+pub fn watch(reload: Sender<()>) {
+    WATCHER.spawn(
+        &[
+            "org.gnome.desktop.interface",
+            "org.gnome.desktop.a11y.interface",
+        ],
+        move || {
+            let settings = detect();
+            if swap(settings) != settings {
+                let _ = reload.send(());
+            }
+        },
+    );
+}
+
+/// Whether the config follows the accessibility settings, which are only
+/// checked while it does.
+pub fn set_followed(followed: bool) {
+    WATCHER.set_followed(followed);
+}
+
+#[cfg(target_os = "macos")]
+fn detect() -> OsAccessibility {
+    let enabled = |key: &str| {
+        Command::new("defaults")
+            .args(["read", "com.apple.universalaccess", key])
+            .output()
+            .is_ok_and(|output| {
+                String::from_utf8_lossy(&output.stdout).trim() == "1"
+            })
+    };
+    OsAccessibility {
+        reduced_motion: enabled("reduceMotion"),
+        high_contrast: enabled("increaseContrast"),
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn detect() -> OsAccessibility {
+    use std::os::windows::process::CommandExt;
+
+    let value = |key: &str, name: &str| {
+        let output = Command::new("reg")
+            .args(["query", key, "/v", name])
+            .creation_flags(0x08000000) // CREATE_NO_WINDOW
+            .output()
+            .ok()?;
+        let value = String::from_utf8_lossy(&output.stdout);
+        value.split_whitespace().last().map(|v| v.to_string())
+    };
+    // Windows stops animating windows as they are minimized when animations
+    // are turned off
+    let reduced_motion =
+        value(r"HKCU\Control Panel\Desktop\WindowMetrics", "MinAnimate")
+            .is_some_and(|value| value == "0");
+    // The first bit of the flags is whether high contrast is on
+    let high_contrast =
+        value(r"HKCU\Control Panel\Accessibility\HighContrast", "Flags")
+            .and_then(|flags| flags.parse::<u32>().ok())
+            .is_some_and(|flags| flags & 1 != 0);
+    OsAccessibility {
+        reduced_motion,
+        high_contrast,
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn detect() -> OsAccessibility {
+    let gsettings = |schema: &str, key: &str| {
+        let output = Command::new("gsettings")
+            .args(["get", schema, key])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+    OsAccessibility {
+        reduced_motion: gsettings(
+            "org.gnome.desktop.interface",
+            "enable-animations",
+        )
+        .is_some_and(|value| value == "false"),
+        high_contrast: gsettings(
+            "org.gnome.desktop.a11y.interface",
+            "high-contrast",
+        )
+        .is_some_and(|value| value == "true"),
+    }
+}
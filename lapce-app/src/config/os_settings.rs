@@ -0,0 +1,124 @@
+//! Watching settings of the operating system that the UI follows, like dark mode.
+//! They are only checked while following them is turned on in the config.
+
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use crossbeam_channel::{Receiver, Sender};
+use once_cell::sync::OnceCell;
+
+/// How often the operating system is asked for the settings, where it can't tell
+/// when they change.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+pub struct OsSettingsWatcher {
+    followed: AtomicBool,
+    /// Wakes the thread that checks the settings.
+    wake: OnceCell<Sender<()>>,
+}
+
+impl OsSettingsWatcher {
+    pub const fn new() -> Self {
+        Self {
+            followed: AtomicBool::new(false),
+            wake: OnceCell::new(),
+        }
+    }
+
+    /// Turn following the settings on or off. Turning it on checks them right
+    /// away.
+    pub fn set_followed(&self, followed: bool) {
+        let was_followed = self.followed.swap(followed, Ordering::Relaxed);
+        if followed && !was_followed {
+            if let Some(wake) = self.wake.get() {
+                let _ = wake.try_send(());
+            }
+        }
+    }
+
+    /// Check the settings with `check` on a thread of its own whenever they may
+    /// have changed, while they are followed. On Linux the desktop tells when a
+    /// key of one of the gsettings `schemas` changes, elsewhere the settings are
+    /// polled.
+    pub fn spawn(
+        &'static self,
+        schemas: &'static [&'static str],
+        mut check: impl FnMut() + Send + 'static,
+    ) {
+        let (wake, woken) = crossbeam_channel::bounded(1);
+        if self.wake.set(wake.clone()).is_err() {
+            return;
+        }
+        std::thread::spawn(move || {
+            let mut notified = false;
+            loop {
+                if self.followed.load(Ordering::Relaxed) {
+                    if !notified {
+                        notified = monitor_gsettings(schemas, &wake);
+                    }
+                    check();
+                }
+                if !wait(&woken, self.followed.load(Ordering::Relaxed) && !notified)
+                {
+                    return;
+                }
+            }
+        });
+    }
+}
+
+/// Wait to be woken, or until it's time to poll. Returns `false` when nothing can
+/// wake the thread anymore.
+fn wait(woken: &Receiver<()>, poll: bool) -> bool {
+    if poll {
+        !matches!(
+            woken.recv_timeout(POLL_INTERVAL),
+            Err(crossbeam_channel::RecvTimeoutError::Disconnected)
+        )
+    } else {
+        woken.recv().is_ok()
+    }
+}
+
+/// Have `gsettings monitor` wake the thread every time a key of the schemas
+/// changes. Returns whether it could be started.
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn monitor_gsettings(schemas: &[&str], wake: &Sender<()>) -> bool {
+    use std::{
+        io::{BufRead, BufReader},
+        process::{Command, Stdio},
+    };
+
+    let mut started = false;
+    for schema in schemas {
+        let Ok(mut child) = Command::new("gsettings")
+            .args(["monitor", schema])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+        let Some(stdout) = child.stdout.take() else {
+            continue;
+        };
+        started = true;
+        let wake = wake.clone();
+        std::thread::spawn(move || {
+            // A line is printed for every change
+            for _ in BufReader::new(stdout).lines() {
+                let _ = wake.try_send(());
+            }
+            let _ = child.wait();
+        });
+    }
+    started
+}
+
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn monitor_gsettings(_schemas: &[&str], _wake: &Sender<()>) -> bool {
+    false
+}
@@ -0,0 +1,152 @@
+use std::{
+    process::Command,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use crossbeam_channel::Sender;
+use once_cell::sync::OnceCell;
+
+use super::os_settings::OsSettingsWatcher;
+
+const UNKNOWN: u8 = 0;
+const LIGHT: u8 = 1;
+const DARK: u8 = 2;
+
+/// The appearance the operating system is set to, as last detected.
+static OS_THEME: AtomicU8 = AtomicU8::new(UNKNOWN);
+/// The appearance picked from the status bar, until the OS one changes.
+static OVERRIDE: AtomicU8 = AtomicU8::new(UNKNOWN);
+/// Reloads the config of every window.
+static RELOAD: OnceCell<Sender<()>> = OnceCell::new();
+static WATCHER: OsSettingsWatcher = OsSettingsWatcher::new();
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OsTheme {
+    Light,
+    Dark,
+}
+
+impl OsTheme {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            LIGHT => Some(OsTheme::Light),
+            DARK => Some(OsTheme::Dark),
+            _ => None,
+        }
+    }
+
+    fn to_u8(theme: Option<Self>) -> u8 {
+        match theme {
+            Some(OsTheme::Light) => LIGHT,
+            Some(OsTheme::Dark) => DARK,
+            None => UNKNOWN,
+        }
+    }
+}
+
+/// The appearance the color theme should follow, which is the one picked in the
+/// status bar if there is one, and otherwise the one of the operating system.
+pub fn current() -> Option<OsTheme> {
+    OsTheme::from_u8(OVERRIDE.load(Ordering::Relaxed))
+        .or_else(|| OsTheme::from_u8(OS_THEME.load(Ordering::Relaxed)))
+}
+
+/// Switch to the other appearance until the operating system changes its own.
+pub fn toggle_override() {
+    let theme = match current() {
+        Some(OsTheme::Dark) => OsTheme::Light,
+        Some(OsTheme::Light) | None => OsTheme::Dark,
+    };
+    OVERRIDE.store(OsTheme::to_u8(Some(theme)), Ordering::Relaxed);
+    if let Some(reload) = RELOAD.get() {
+        // A reload that is already waiting picks up the change too
+        let _ = reload.try_send(());
+    }
+}
+
+/// Keep checking the appearance of the operating system in the background while
+/// it is followed. Every time it changes the config is reloaded through `reload`.
+pub fn watch(reload: Sender<()>) {
+    let _ = RELOAD.set(reload.clone());
+    WATCHER.spawn(&["org.gnome.desktop.interface"], move || {
+        let theme = OsTheme::to_u8(detect());
+        if OS_THEME.swap(theme, Ordering::Relaxed) != theme {
+            OVERRIDE.store(UNKNOWN, Ordering::Relaxed);
+            let _ = reload.send(());
+        }
+    });
+}
+
+/// Whether the config follows the appearance of the operating system, which is
+/// only checked while it does.
+pub fn set_followed(followed: bool) {
+    WATCHER.set_followed(followed);
+}
+
+#[cfg(target_os = "macos")]
+fn detect() -> Option<OsTheme> {
+    // The key only exists while dark mode is on
+    let output = Command::new("defaults")
+        .args(["read", "-g", "AppleInterfaceStyle"])
+        .output()
+        .ok()?;
+    let style = String::from_utf8_lossy(&output.stdout);
+    Some(if style.trim().eq_ignore_ascii_case("dark") {
+        OsTheme::Dark
+    } else {
+        OsTheme::Light
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn detect() -> Option<OsTheme> {
+    use std::os::windows::process::CommandExt;
+
+    let output = Command::new("reg")
+        .args([
+            "query",
+            r"HKCU\Software\Microsoft\Windows\CurrentVersion\Themes\Personalize",
+            "/v",
+            "AppsUseLightTheme",
+        ])
+        .creation_flags(0x08000000) // CREATE_NO_WINDOW
+        .output()
+        .ok()?;
+    let value = String::from_utf8_lossy(&output.stdout);
+    let value = value.split_whitespace().last()?;
+    Some(if value == "0x0" {
+        OsTheme::Dark
+    } else {
+        OsTheme::Light
+    })
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn detect() -> Option<OsTheme> {
+    let gsettings = |key: &str| {
+        let output = Command::new("gsettings")
+            .args(["get", "org.gnome.desktop.interface", key])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&output.stdout).to_lowercase())
+    };
+    // Newer desktops have a color scheme, older ones only a GTK theme that
+    // usually has dark in its name
+    if let Some(scheme) = gsettings("color-scheme") {
+        if scheme.contains("dark") {
+            return Some(OsTheme::Dark);
+        }
+        if scheme.contains("light") {
+            return Some(OsTheme::Light);
+        }
+    }
+    let theme = gsettings("gtk-theme")?;
+    Some(if theme.contains("dark") {
+        OsTheme::Dark
+    } else {
+        OsTheme::Light
+    })
+}
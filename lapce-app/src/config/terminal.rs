@@ -23,6 +23,8 @@ pub struct TerminalConfig {
     pub profiles: HashMap<String, TerminalProfile>,
     #[field_names(desc = "Default profile for each platform")]
     pub default_profile: HashMap<String, String>,
+    #[field_names(desc = "The interpreter Run File uses for each language")]
+    pub run_file: HashMap<String, String>,
 
     #[serde(skip)]
     #[field_names(skip)]
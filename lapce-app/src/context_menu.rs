@@ -0,0 +1,101 @@
+//! The menu shown on right-click, made of commands and of actions of the view
+//! that shows it.
+
+use floem::{
+    action::show_context_menu,
+    menu::{Menu, MenuItem},
+};
+
+use crate::{
+    command::{CommandKind, LapceCommand},
+    listener::Listener,
+};
+
+pub struct ContextMenu {
+    menu: Menu,
+    lapce_command: Listener<LapceCommand>,
+    empty: bool,
+    /// Separators are only added before the next entry, so that sections that
+    /// end up with no entries don't leave separators in a row or at the end.
+    separator: bool,
+}
+
+impl ContextMenu {
+    pub fn new(lapce_command: Listener<LapceCommand>) -> Self {
+        Self::with_title("", lapce_command)
+    }
+
+    fn with_title(title: &str, lapce_command: Listener<LapceCommand>) -> Self {
+        Self {
+            menu: Menu::new(title),
+            lapce_command,
+            empty: true,
+            separator: false,
+        }
+    }
+
+    /// An entry that runs the command, with the name it is shown with.
+    pub fn command(self, cmd: CommandKind) -> Self {
+        let text = cmd.name();
+        let lapce_command = self.lapce_command;
+        self.action(text, move || {
+            lapce_command.send(LapceCommand {
+                kind: cmd.clone(),
+                data: None,
+            })
+        })
+    }
+
+    pub fn action(
+        self,
+        text: impl Into<String>,
+        action: impl Fn() + 'static,
+    ) -> Self {
+        self.action_enabled(text, true, action)
+    }
+
+    /// An entry that is greyed out and does nothing unless `enabled`.
+    pub fn action_enabled(
+        self,
+        text: impl Into<String>,
+        enabled: bool,
+        action: impl Fn() + 'static,
+    ) -> Self {
+        let item = MenuItem::new(text).enabled(enabled).action(action);
+        self.entry(|menu| menu.entry(item))
+    }
+
+    /// A submenu with the entries `build` adds, which is left out if it adds
+    /// none.
+    pub fn submenu(
+        self,
+        text: &str,
+        build: impl FnOnce(ContextMenu) -> ContextMenu,
+    ) -> Self {
+        let submenu = build(Self::with_title(text, self.lapce_command));
+        if submenu.empty {
+            return self;
+        }
+        self.entry(|menu| menu.entry(submenu.menu))
+    }
+
+    pub fn separator(mut self) -> Self {
+        self.separator = !self.empty;
+        self
+    }
+
+    fn entry(mut self, add: impl FnOnce(Menu) -> Menu) -> Self {
+        if self.separator {
+            self.menu = self.menu.separator();
+            self.separator = false;
+        }
+        self.menu = add(self.menu);
+        self.empty = false;
+        self
+    }
+
+    /// Show the menu where the pointer is.
+    pub fn show(self) {
+        show_context_menu(self.menu, None);
+    }
+}
@@ -0,0 +1,85 @@
+use std::{
+    backtrace::Backtrace,
+    collections::{HashMap, VecDeque},
+    fmt::{Display, Write},
+    path::PathBuf,
+};
+
+use lapce_core::{directory::Directory, meta};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use crate::id::WindowTabId;
+
+/// How many of the last commands a crash report lists.
+const RECENT_COMMANDS: usize = 20;
+
+/// What Lapce was doing, for the crash report if it panics.
+#[derive(Default)]
+struct CrashContext {
+    commands: VecDeque<String>,
+    open_files: HashMap<WindowTabId, Vec<PathBuf>>,
+}
+
+static CONTEXT: Lazy<Mutex<CrashContext>> = Lazy::new(Default::default);
+
+/// Remember a command that was run, from a key or from the palette.
+pub fn record_command(command: &str) {
+    let mut context = CONTEXT.lock();
+    if context.commands.len() == RECENT_COMMANDS {
+        context.commands.pop_front();
+    }
+    context.commands.push_back(command.to_string());
+}
+
+/// Remember the files that are open in a window tab, which is forgotten with an
+/// empty list.
+pub fn set_open_files(window_tab_id: WindowTabId, files: Vec<PathBuf>) {
+    let mut context = CONTEXT.lock();
+    if files.is_empty() {
+        context.open_files.remove(&window_tab_id);
+    } else {
+        context.open_files.insert(window_tab_id, files);
+    }
+}
+
+/// Write a crash report to the logs directory when Lapce panics, before the
+/// usual message is printed.
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        match write_report(info) {
+            Some(path) => {
+                tracing::error!("{info}, crash report at {}", path.display());
+            }
+            None => tracing::error!("{info}"),
+        }
+        default_hook(info);
+    }));
+}
+
+fn write_report(info: &dyn Display) -> Option<PathBuf> {
+    let now = chrono::Local::now();
+    let path = Directory::logs_directory()?
+        .join(format!("crash.{}.log", now.format("%Y-%m-%d-%H%M%S")));
+
+    let mut report = String::new();
+    let _ = writeln!(report, "Lapce {} crashed at {now}", meta::VERSION);
+    let _ = writeln!(report, "{info}");
+
+    // The panic may have happened while the context was locked
+    if let Some(context) = CONTEXT.try_lock() {
+        let _ = writeln!(report, "\nOpen files:");
+        for path in context.open_files.values().flatten() {
+            let _ = writeln!(report, "  {}", path.display());
+        }
+        let _ = writeln!(report, "\nLast commands:");
+        for command in &context.commands {
+            let _ = writeln!(report, "  {command}");
+        }
+    }
+
+    let _ = writeln!(report, "\nBacktrace:\n{}", Backtrace::force_capture());
+    std::fs::write(&path, report).ok()?;
+    Some(path)
+}
@@ -8,11 +8,12 @@
 use crossbeam_channel::{unbounded, Sender};
 use floem::peniko::kurbo::Vec2;
 use lapce_core::directory::Directory;
-use lapce_rpc::plugin::VoltID;
+use lapce_rpc::plugin::{VoltID, VoltPermissions};
 
 use crate::{
     app::{AppData, AppInfo},
     doc::DocInfo,
+    input_history::InputHistoryKind,
     panel::{data::PanelOrder, kind::PanelKind, position::PanelPosition},
     window::{WindowData, WindowInfo},
     window_tab::WindowTabData,
@@ -26,6 +27,9 @@ pub enum SaveEvent {
     Doc(DocInfo),
     DisabledVolts(Vec<VoltID>),
     WorkspaceDisabledVolts(Arc<LapceWorkspace>, Vec<VoltID>),
+    GrantedVoltPermissions(Vec<(VoltID, VoltPermissions)>),
+    ClosedFiles(Arc<LapceWorkspace>, Vec<PathBuf>),
+    InputHistory(Arc<LapceWorkspace>, InputHistoryKind, Vec<String>),
     PanelOrder(PanelOrder),
 }
 
@@ -73,6 +77,16 @@ pub fn new() -> Result<Self> {
                         let _ = local_db
                             .insert_workspace_disabled_volts(workspace, volts);
                     }
+                    SaveEvent::GrantedVoltPermissions(granted) => {
+                        let _ = local_db.insert_granted_volt_permissions(granted);
+                    }
+                    SaveEvent::ClosedFiles(workspace, paths) => {
+                        let _ = local_db.insert_closed_files(&workspace, &paths);
+                    }
+                    SaveEvent::InputHistory(workspace, kind, entries) => {
+                        let _ = local_db
+                            .insert_input_history(&workspace, kind, &entries);
+                    }
                     SaveEvent::PanelOrder(order) => {
                         let _ = local_db.insert_panel_orders(&order);
                     }
@@ -120,6 +134,39 @@ pub fn insert_disabled_volts(&self, volts: Vec<VoltID>) -> Result<()> {
         Ok(())
     }
 
+    /// The permissions the user agreed to, for every plugin that asked for some.
+    pub fn get_granted_volt_permissions(
+        &self,
+    ) -> Result<Vec<(VoltID, VoltPermissions)>> {
+        let sled_db = self.get_db()?;
+        let granted = sled_db
+            .get("granted_volt_permissions")?
+            .ok_or_else(|| anyhow!("can't find granted volt permissions"))?;
+        let granted = std::str::from_utf8(&granted)?;
+        let granted = serde_json::from_str(granted)?;
+        Ok(granted)
+    }
+
+    pub fn save_granted_volt_permissions(
+        &self,
+        granted: Vec<(VoltID, VoltPermissions)>,
+    ) {
+        let _ = self
+            .save_tx
+            .send(SaveEvent::GrantedVoltPermissions(granted));
+    }
+
+    pub fn insert_granted_volt_permissions(
+        &self,
+        granted: Vec<(VoltID, VoltPermissions)>,
+    ) -> Result<()> {
+        let sled_db = self.get_db()?;
+        let granted = serde_json::to_string(&granted)?;
+        sled_db.insert(b"granted_volt_permissions", granted.as_str())?;
+        sled_db.flush()?;
+        Ok(())
+    }
+
     pub fn insert_workspace_disabled_volts(
         &self,
         workspace: Arc<LapceWorkspace>,
@@ -145,6 +192,82 @@ pub fn get_workspace_disabled_volts(
         Ok(volts)
     }
 
+    pub fn save_closed_files(
+        &self,
+        workspace: Arc<LapceWorkspace>,
+        paths: Vec<PathBuf>,
+    ) {
+        let _ = self.save_tx.send(SaveEvent::ClosedFiles(workspace, paths));
+    }
+
+    fn insert_closed_files(
+        &self,
+        workspace: &LapceWorkspace,
+        paths: &[PathBuf],
+    ) -> Result<()> {
+        let sled_db = self.get_db()?;
+        let paths = serde_json::to_string(paths)?;
+        sled_db.insert(format!("closed_files:{workspace}"), paths.as_str())?;
+        sled_db.flush()?;
+        Ok(())
+    }
+
+    /// The files whose editors were closed in the workspace, most recent first.
+    pub fn get_closed_files(
+        &self,
+        workspace: &LapceWorkspace,
+    ) -> Result<Vec<PathBuf>> {
+        let sled_db = self.get_db()?;
+        let paths = sled_db
+            .get(format!("closed_files:{workspace}"))?
+            .ok_or_else(|| anyhow!("can't find closed files"))?;
+        let paths = std::str::from_utf8(&paths)?;
+        let paths: Vec<PathBuf> = serde_json::from_str(paths)?;
+        Ok(paths)
+    }
+
+    pub fn save_input_history(
+        &self,
+        workspace: Arc<LapceWorkspace>,
+        kind: InputHistoryKind,
+        entries: Vec<String>,
+    ) {
+        let _ = self
+            .save_tx
+            .send(SaveEvent::InputHistory(workspace, kind, entries));
+    }
+
+    fn insert_input_history(
+        &self,
+        workspace: &LapceWorkspace,
+        kind: InputHistoryKind,
+        entries: &[String],
+    ) -> Result<()> {
+        let sled_db = self.get_db()?;
+        let entries = serde_json::to_string(entries)?;
+        sled_db.insert(
+            format!("input_history:{}:{workspace}", kind.as_str()),
+            entries.as_str(),
+        )?;
+        sled_db.flush()?;
+        Ok(())
+    }
+
+    /// The entries of the history of an input in the workspace, newest first.
+    pub fn get_input_history(
+        &self,
+        workspace: &LapceWorkspace,
+        kind: InputHistoryKind,
+    ) -> Result<Vec<String>> {
+        let sled_db = self.get_db()?;
+        let entries = sled_db
+            .get(format!("input_history:{}:{workspace}", kind.as_str()))?
+            .ok_or_else(|| anyhow!("can't find input history"))?;
+        let entries = std::str::from_utf8(&entries)?;
+        let entries: Vec<String> = serde_json::from_str(entries)?;
+        Ok(entries)
+    }
+
     pub fn recent_workspaces(&self) -> Result<Vec<LapceWorkspace>> {
         let sled_db = self.get_db()?;
         let workspaces = sled_db
@@ -155,6 +278,70 @@ pub fn recent_workspaces(&self) -> Result<Vec<LapceWorkspace>> {
         Ok(workspaces)
     }
 
+    /// The recent workspaces with the pinned ones first, in the order they were
+    /// pinned, each with whether it's pinned.
+    pub fn recent_workspaces_pinned_first(&self) -> Vec<(LapceWorkspace, bool)> {
+        let mut workspaces: Vec<(LapceWorkspace, bool)> = self
+            .recent_workspaces()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|w| (w, false))
+            .collect();
+        let pinned = self.pinned_workspaces().unwrap_or_default();
+        for pinned in pinned.iter().rev() {
+            let index = workspaces
+                .iter()
+                .position(|(w, _)| w.path == pinned.path && w.kind == pinned.kind);
+            if let Some(index) = index {
+                let (workspace, _) = workspaces.remove(index);
+                workspaces.insert(0, (workspace, true));
+            }
+        }
+        workspaces
+    }
+
+    /// The workspaces pinned to the top of the recent workspaces.
+    pub fn pinned_workspaces(&self) -> Result<Vec<LapceWorkspace>> {
+        let sled_db = self.get_db()?;
+        let workspaces = sled_db
+            .get("pinned_workspaces")?
+            .ok_or_else(|| anyhow!("can't find pinned workspaces"))?;
+        let workspaces = std::str::from_utf8(&workspaces)?;
+        let workspaces: Vec<LapceWorkspace> = serde_json::from_str(workspaces)?;
+        Ok(workspaces)
+    }
+
+    pub fn set_workspace_pinned(
+        &self,
+        workspace: &LapceWorkspace,
+        pinned: bool,
+    ) -> Result<()> {
+        let sled_db = self.get_db()?;
+        let mut workspaces = self.pinned_workspaces().unwrap_or_default();
+        workspaces
+            .retain(|w| !(w.path == workspace.path && w.kind == workspace.kind));
+        if pinned {
+            workspaces.push(workspace.clone());
+        }
+        let workspaces = serde_json::to_string(&workspaces)?;
+        sled_db.insert("pinned_workspaces", workspaces.as_str())?;
+        sled_db.flush()?;
+        Ok(())
+    }
+
+    /// Remove a workspace from the recent workspaces, and unpin it.
+    pub fn remove_recent_workspace(&self, workspace: &LapceWorkspace) -> Result<()> {
+        self.set_workspace_pinned(workspace, false)?;
+        let sled_db = self.get_db()?;
+        let mut workspaces = self.recent_workspaces().unwrap_or_default();
+        workspaces
+            .retain(|w| !(w.path == workspace.path && w.kind == workspace.kind));
+        let workspaces = serde_json::to_string(&workspaces)?;
+        sled_db.insert("recent_workspaces", workspaces.as_str())?;
+        sled_db.flush()?;
+        Ok(())
+    }
+
     pub fn update_recent_workspace(&self, workspace: &LapceWorkspace) -> Result<()> {
         if workspace.path.is_none() {
             return Ok(());
@@ -181,6 +368,7 @@ fn insert_recent_workspace(&self, workspace: LapceWorkspace) -> Result<()> {
         let mut exits = false;
         for w in workspaces.iter_mut() {
             if w.path == workspace.path && w.kind == workspace.kind {
+                w.folders = workspace.folders.clone();
                 w.last_open = std::time::SystemTime::now()
                     .duration_since(std::time::UNIX_EPOCH)
                     .unwrap()
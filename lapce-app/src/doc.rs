@@ -1,17 +1,23 @@
 use std::{
     cell::RefCell,
-    collections::HashMap,
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
     path::{Path, PathBuf},
     rc::Rc,
     sync::{atomic, Arc},
     time::Duration,
 };
 
+#[cfg(target_os = "linux")]
+use clipboard::x11_clipboard::{Primary, X11ClipboardContext};
 use clipboard::{ClipboardContext, ClipboardProvider};
 use floem::{
     action::exec_after,
-    cosmic_text::{Attrs, AttrsList, FamilyOwned, TextLayout},
+    cosmic_text::{
+        Attrs, AttrsList, FamilyOwned, Style as FontStyle, TextLayout, Weight,
+    },
     ext_event::create_ext_action,
+    peniko::kurbo::Vec2,
     reactive::{RwSignal, Scope},
 };
 use itertools::Itertools;
@@ -21,20 +27,23 @@
         rope_text::RopeText,
         Buffer, InvalLines,
     },
+    color,
     command::EditCommand,
     cursor::Cursor,
     editor::{EditType, Editor},
     language::LapceLanguage,
     register::{Clipboard, Register},
     selection::{InsertDrift, Selection},
+    spell,
     style::line_styles,
+    surround::Surround,
     syntax::{edit::SyntaxEdit, Syntax},
     word::WordCursor,
 };
 use lapce_rpc::{
-    buffer::BufferId,
+    buffer::{BufferId, LineEnding},
     plugin::PluginId,
-    proxy::ProxyResponse,
+    proxy::{ProxyResponse, Runnable},
     style::{LineStyle, LineStyles, Style},
 };
 use lapce_xi_rope::{
@@ -42,23 +51,36 @@
     Interval, Rope, RopeDelta, Transformer,
 };
 use lsp_types::{
-    CodeActionResponse, Diagnostic, DiagnosticSeverity, InlayHint, InlayHintLabel,
+    CodeActionResponse, Diagnostic, DiagnosticSeverity, DocumentSymbol,
+    DocumentSymbolResponse, InlayHint, InlayHintLabel, MessageType,
+    SymbolInformation, Url,
 };
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 
 use self::phantom_text::{PhantomText, PhantomTextKind, PhantomTextLine};
 use crate::{
+    alert::AlertButton,
+    color_picker::DocumentColor,
+    command::InternalCommand,
     config::{color::LapceColor, LapceConfig},
-    editor::view_data::{LineExtraStyle, TextLayoutCache, TextLayoutLine},
-    find::{Find, FindProgress, FindResult},
+    editor::view_data::{
+        LineExtraStyle, LongLineLayout, TextLayoutCache, TextLayoutLine,
+        LONG_LINE_LENGTH,
+    },
+    find::{occurrences_in_scope, Find, FindProgress, FindResult},
     history::DocumentHistory,
+    localization::{tr, tr_args},
+    perf::{self, Metric},
+    recovery,
     window_tab::CommonData,
     workspace::LapceWorkspace,
 };
 
 pub mod phantom_text;
 
+const SPELL_CHECK_DELAY: Duration = Duration::from_millis(500);
+
 pub struct SystemClipboard {
     ctx: ClipboardContext,
 }
@@ -87,12 +109,79 @@ fn put_string(&mut self, s: impl AsRef<str>) {
     }
 }
 
+/// The X11 primary selection, which is what was last selected rather than
+/// copied, and which the middle button pastes.
+#[cfg(target_os = "linux")]
+pub struct PrimarySelection {
+    ctx: Option<X11ClipboardContext<Primary>>,
+}
+
+#[cfg(target_os = "linux")]
+impl Default for PrimarySelection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl PrimarySelection {
+    /// There's no primary selection to use under Wayland without XWayland, so
+    /// this doesn't fail without one and does nothing instead.
+    pub fn new() -> Self {
+        PrimarySelection {
+            ctx: X11ClipboardContext::new().ok(),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Clipboard for PrimarySelection {
+    fn get_string(&mut self) -> Option<String> {
+        self.ctx.as_mut()?.get_contents().ok()
+    }
+
+    fn put_string(&mut self, s: impl AsRef<str>) {
+        if let Some(ctx) = self.ctx.as_mut() {
+            let _ = ctx.set_contents(s.as_ref().to_string());
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct DiagnosticData {
     pub expanded: RwSignal<bool>,
     pub diagnostics: RwSignal<im::Vector<EditorDiagnostic>>,
 }
 
+/// The indent levels of blank lines, which continue the indent guides of the lines
+/// around them. They are only valid for the buffer revision and the indent and tab
+/// widths they were found with.
+#[derive(Default)]
+pub struct BlankLineLevels {
+    rev: u64,
+    widths: (usize, usize),
+    levels: HashMap<usize, usize>,
+}
+
+impl BlankLineLevels {
+    /// The level of the blank `line`, found with `level` the first time it's asked
+    /// for at this revision and these widths.
+    pub fn get_or_insert_with(
+        &mut self,
+        rev: u64,
+        widths: (usize, usize),
+        line: usize,
+        level: impl FnOnce() -> usize,
+    ) -> usize {
+        if self.rev != rev || self.widths != widths {
+            self.rev = rev;
+            self.widths = widths;
+            self.levels.clear();
+        }
+        *self.levels.entry(line).or_insert_with(level)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct EditorDiagnostic {
     pub range: (usize, usize),
@@ -176,12 +265,20 @@ pub struct Document {
     pub cache_rev: RwSignal<u64>,
     /// Whether the buffer's content has been loaded/initialized into the buffer.
     pub loaded: RwSignal<bool>,
+    /// Whether the file is too big for syntax highlighting and language server support.
+    pub large_file: RwSignal<bool>,
+    /// Whether the rest of a large file is still being fetched from the proxy.
+    pub loading_chunks: RwSignal<bool>,
+    /// The line ending the file uses on disk.
+    pub line_ending: RwSignal<LineEnding>,
     pub buffer: RwSignal<Buffer>,
     pub syntax: RwSignal<Syntax>,
     /// Semantic highlighting information (which is provided by the LSP)
     semantic_styles: RwSignal<Option<Spans<Style>>>,
     /// Inlay hints for the document
     pub inlay_hints: RwSignal<Option<Spans<InlayHint>>>,
+    /// The document symbols from the LSP, nested by their ranges
+    pub document_symbols: RwSignal<Option<Rc<Vec<DocumentSymbol>>>>,
     /// Current completion lens text, if any.
     /// This will be displayed even on views that are not focused.
     pub completion_lens: RwSignal<Option<String>>,
@@ -200,9 +297,20 @@ pub struct Document {
     text_layouts: Rc<RefCell<TextLayoutCache>>,
     /// A cache for the sticky headers which maps a line to the lines it should show in the header.
     pub sticky_headers: Rc<RefCell<HashMap<usize, Option<Vec<usize>>>>>,
+    /// A cache for the indent levels the indent guides of blank lines are drawn at.
+    pub blank_line_levels: Rc<RefCell<BlankLineLevels>>,
     pub find_result: FindResult,
     /// The diagnostics for the document
     pub diagnostics: DiagnosticData,
+    /// The ranges of the misspelled words in comments and strings
+    pub misspellings: RwSignal<im::Vector<(usize, usize)>>,
+    /// The colors written in the document, which have a swatch shown before them
+    pub document_colors: RwSignal<im::Vector<DocumentColor>>,
+    /// The links the language server found in the document, with their targets
+    pub document_links: RwSignal<im::Vector<(usize, usize, Url)>>,
+    /// The tests and binaries that can be run from the document, by the offset
+    /// of the name of the function they are run from
+    pub runnables: RwSignal<im::Vector<(usize, Runnable)>>,
     common: Rc<CommonData>,
 }
 
@@ -223,6 +331,7 @@ pub fn new(
             line_styles: Rc::new(RefCell::new(HashMap::new())),
             semantic_styles: cx.create_rw_signal(None),
             inlay_hints: cx.create_rw_signal(None),
+            document_symbols: cx.create_rw_signal(None),
             diagnostics,
             completion_lens: cx.create_rw_signal(None),
             completion_pos: cx.create_rw_signal((0, 0)),
@@ -231,13 +340,21 @@ pub fn new(
                 read_only: false,
             }),
             loaded: cx.create_rw_signal(false),
+            large_file: cx.create_rw_signal(false),
+            loading_chunks: cx.create_rw_signal(false),
+            line_ending: cx.create_rw_signal(LineEnding::default()),
             histories: cx.create_rw_signal(im::HashMap::new()),
             head_changes: cx.create_rw_signal(im::Vector::new()),
             text_layouts: Rc::new(RefCell::new(TextLayoutCache::new())),
             sticky_headers: Rc::new(RefCell::new(HashMap::new())),
+            blank_line_levels: Rc::new(RefCell::new(BlankLineLevels::default())),
             code_actions: cx.create_rw_signal(im::HashMap::new()),
             find_result: FindResult::new(cx),
             preedit: cx.create_rw_signal(None),
+            misspellings: cx.create_rw_signal(im::Vector::new()),
+            document_colors: cx.create_rw_signal(im::Vector::new()),
+            document_links: cx.create_rw_signal(im::Vector::new()),
+            runnables: cx.create_rw_signal(im::Vector::new()),
             common,
         }
     }
@@ -261,8 +378,10 @@ pub fn new_content(
             syntax: cx.create_rw_signal(Syntax::plaintext()),
             line_styles: Rc::new(RefCell::new(HashMap::new())),
             sticky_headers: Rc::new(RefCell::new(HashMap::new())),
+            blank_line_levels: Rc::new(RefCell::new(BlankLineLevels::default())),
             semantic_styles: cx.create_rw_signal(None),
             inlay_hints: cx.create_rw_signal(None),
+            document_symbols: cx.create_rw_signal(None),
             diagnostics: DiagnosticData {
                 expanded: cx.create_rw_signal(true),
                 diagnostics: cx.create_rw_signal(im::Vector::new()),
@@ -270,12 +389,19 @@ pub fn new_content(
             completion_lens: cx.create_rw_signal(None),
             completion_pos: cx.create_rw_signal((0, 0)),
             loaded: cx.create_rw_signal(true),
+            large_file: cx.create_rw_signal(false),
+            loading_chunks: cx.create_rw_signal(false),
+            line_ending: cx.create_rw_signal(LineEnding::default()),
             histories: cx.create_rw_signal(im::HashMap::new()),
             head_changes: cx.create_rw_signal(im::Vector::new()),
             text_layouts: Rc::new(RefCell::new(TextLayoutCache::new())),
             code_actions: cx.create_rw_signal(im::HashMap::new()),
             find_result: FindResult::new(cx),
             preedit: cx.create_rw_signal(None),
+            misspellings: cx.create_rw_signal(im::Vector::new()),
+            document_colors: cx.create_rw_signal(im::Vector::new()),
+            document_links: cx.create_rw_signal(im::Vector::new()),
+            runnables: cx.create_rw_signal(im::Vector::new()),
             common,
         }
     }
@@ -300,8 +426,10 @@ pub fn new_hisotry(
             syntax: cx.create_rw_signal(syntax),
             line_styles: Rc::new(RefCell::new(HashMap::new())),
             sticky_headers: Rc::new(RefCell::new(HashMap::new())),
+            blank_line_levels: Rc::new(RefCell::new(BlankLineLevels::default())),
             semantic_styles: cx.create_rw_signal(None),
             inlay_hints: cx.create_rw_signal(None),
+            document_symbols: cx.create_rw_signal(None),
             diagnostics: DiagnosticData {
                 expanded: cx.create_rw_signal(true),
                 diagnostics: cx.create_rw_signal(im::Vector::new()),
@@ -309,12 +437,19 @@ pub fn new_hisotry(
             completion_lens: cx.create_rw_signal(None),
             completion_pos: cx.create_rw_signal((0, 0)),
             loaded: cx.create_rw_signal(true),
+            large_file: cx.create_rw_signal(false),
+            loading_chunks: cx.create_rw_signal(false),
+            line_ending: cx.create_rw_signal(LineEnding::default()),
             histories: cx.create_rw_signal(im::HashMap::new()),
             head_changes: cx.create_rw_signal(im::Vector::new()),
             text_layouts: Rc::new(RefCell::new(TextLayoutCache::new())),
             code_actions: cx.create_rw_signal(im::HashMap::new()),
             find_result: FindResult::new(cx),
             preedit: cx.create_rw_signal(None),
+            misspellings: cx.create_rw_signal(im::Vector::new()),
+            document_colors: cx.create_rw_signal(im::Vector::new()),
+            document_links: cx.create_rw_signal(im::Vector::new()),
+            runnables: cx.create_rw_signal(im::Vector::new()),
             common,
         }
     }
@@ -327,9 +462,15 @@ pub fn set_syntax(&self, syntax: Syntax) {
         self.clear_sticky_headers_cache();
     }
 
-    /// Set the syntax highlighting this document should use.
+    /// Set the language this document should use, which also changes the
+    /// language servers it is sent to.
     pub fn set_language(&self, language: LapceLanguage) {
-        self.syntax.set(Syntax::from_language(language));
+        self.set_syntax(Syntax::from_language(language));
+        if let DocContent::File { path, .. } = self.content.get_untracked() {
+            self.common
+                .proxy
+                .set_language(path, language.name().to_string());
+        }
     }
 
     pub fn find(&self) -> &Find {
@@ -341,8 +482,188 @@ pub fn loaded(&self) -> bool {
         self.loaded.get_untracked()
     }
 
+    /// Switch the document into large file mode, dropping the tree-sitter syntax
+    /// and skipping any requests to the language server.
+    pub fn set_large_file(&self) {
+        self.large_file.set(true);
+        self.set_syntax(Syntax::plaintext());
+    }
+
+    /// Whether the document can't be edited, because the file is read only or
+    /// it is still being loaded.
+    pub fn is_read_only(&self) -> bool {
+        self.loading_chunks.get_untracked()
+            || self.content.with_untracked(|c| c.read_only())
+    }
+
+    /// Fetch the rest of a large file from the proxy a chunk at a time, adding each
+    /// to the end as it arrives. Nothing is edited or saved until the whole file is
+    /// there, whether or not the file is made writable meanwhile.
+    pub fn load_remaining_chunks(&self) {
+        let path = match self.content.get_untracked() {
+            DocContent::File { path, .. } => path,
+            _ => return,
+        };
+        self.loading_chunks.set(true);
+        self.load_next_chunk(path);
+    }
+
+    fn load_next_chunk(&self, path: PathBuf) {
+        let offset = self.buffer.with_untracked(|b| b.len());
+        let doc = self.clone();
+        let file_path = path.clone();
+        let send =
+            create_ext_action(self.scope, move |chunk: Option<(Rope, bool)>| {
+                let Some((content, more)) = chunk else {
+                    return;
+                };
+                doc.append_content(content);
+                if more {
+                    doc.load_next_chunk(file_path.clone());
+                } else {
+                    doc.loading_chunks.set(false);
+                }
+            });
+        self.common.proxy.buffer_chunk(path, offset, move |result| {
+            let chunk = match result {
+                Ok(ProxyResponse::BufferChunkResponse { content, more }) => {
+                    Some((Rope::from(content), more))
+                }
+                Ok(_) => None,
+                Err(err) => {
+                    tracing::error!("failed to load the file: {}", err.message);
+                    None
+                }
+            };
+            send(chunk);
+        });
+    }
+
+    /// Add a chunk of a large file that is still being loaded to the end.
+    fn append_content(&self, content: Rope) {
+        let inval_lines = self
+            .buffer
+            .try_update(|buffer| buffer.append_content(content))
+            .unwrap();
+        self.text_layouts
+            .borrow_mut()
+            .apply_inval_lines(&inval_lines);
+        self.on_update(None);
+    }
+
+    /// Ask whether the unsaved changes backed up by a previous session should be restored.
+    pub fn offer_recovery(&self, recovered: String) {
+        let path = match self.content.get_untracked() {
+            DocContent::File { path, .. } => path,
+            _ => return,
+        };
+        let name = path
+            .file_name()
+            .and_then(|f| f.to_str())
+            .unwrap_or_default()
+            .to_string();
+
+        let internal_command = self.common.internal_command;
+        let doc = self.clone();
+        let restore = AlertButton {
+            text: "Restore".to_string(),
+            action: Rc::new(move || {
+                internal_command.send(InternalCommand::HideAlert);
+                doc.reload(Rope::from(recovered.as_str()), false);
+            }),
+        };
+        let doc = self.clone();
+        let discard = AlertButton {
+            text: "Discard".to_string(),
+            action: Rc::new(move || {
+                internal_command.send(InternalCommand::HideAlert);
+                doc.remove_recovery();
+            }),
+        };
+        internal_command.send(InternalCommand::ShowAlert {
+            title: format!("{name} has unsaved changes from a previous session"),
+            msg: "Do you want to restore them?".to_string(),
+            buttons: vec![restore, discard],
+        });
+    }
+
+    /// Mark a writable file as read only, or ask the proxy to make a read only file
+    /// writable. If that isn't allowed the user is offered to save a copy instead.
+    pub fn toggle_read_only(&self) {
+        let (path, read_only) = match self.content.get_untracked() {
+            DocContent::File { path, read_only } => (path, read_only),
+            _ => return,
+        };
+        if !read_only {
+            self.content.set(DocContent::File {
+                path,
+                read_only: true,
+            });
+            return;
+        }
+
+        let doc = self.clone();
+        let file_path = path.clone();
+        let send = create_ext_action(self.scope, move |result| {
+            let internal_command = doc.common.internal_command;
+            match result {
+                Ok(_) => {
+                    doc.content.set(DocContent::File {
+                        path: file_path.clone(),
+                        read_only: false,
+                    });
+                }
+                Err(err) => {
+                    let save_as_doc = doc.clone();
+                    let save_as = AlertButton {
+                        text: "Save As".to_string(),
+                        action: Rc::new(move || {
+                            internal_command.send(InternalCommand::HideAlert);
+                            internal_command.send(InternalCommand::SaveDocAs {
+                                doc: save_as_doc.clone(),
+                            });
+                        }),
+                    };
+                    internal_command.send(InternalCommand::ShowAlert {
+                        title: format!("Can't make {file_path:?} writable"),
+                        msg: err.to_string(),
+                        buttons: vec![save_as],
+                    });
+                }
+            }
+        });
+        self.common.proxy.make_writable(path, move |result| {
+            send(result.map_err(|e| e.message));
+        });
+    }
+
+    /// Change the line ending the file is written with. The buffer content itself
+    /// doesn't change, the document is marked as having unsaved changes so the
+    /// file gets the new line ending when it is saved.
+    pub fn set_line_ending(&self, line_ending: LineEnding) {
+        if self.line_ending.get_untracked() == line_ending {
+            return;
+        }
+        self.line_ending.set(line_ending);
+        if let DocContent::File { path, .. } = self.content.get_untracked() {
+            self.common.proxy.set_line_ending(path, line_ending);
+            self.buffer.update(|buffer| buffer.set_dirty());
+        }
+    }
+
     //// Initialize the content with some text, this marks the document as loaded.
     pub fn init_content(&self, content: Rope) {
+        // The content can tell the language when the path doesn't, or overrule it
+        // with a modeline. The proxy detects the same language for the language
+        // servers.
+        if let DocContent::File { path, .. } = self.content.get_untracked() {
+            let language = LapceLanguage::from_content(&path, &content);
+            if !self.large_file.get_untracked()
+                && language != self.syntax.with_untracked(|s| s.language)
+            {
+                self.syntax.set(Syntax::from_language(language));
+            }
+        }
         self.syntax.with_untracked(|syntax| {
             self.buffer.update(|buffer| {
                 buffer.init_content(content);
@@ -367,26 +688,26 @@ pub fn reload(&self, content: Rope, set_pristine: bool) {
         self.apply_deltas(&[delta]);
     }
 
-    pub fn handle_file_changed(&self, content: Rope) {
-        if self.is_pristine() {
-            self.reload(content, true);
-        }
-    }
-
+    /// `scroll` is where the editor is scrolled to, for undo to go back to.
     pub fn do_insert(
         &self,
         cursor: &mut Cursor,
         s: &str,
         config: &LapceConfig,
+        scroll: Vec2,
     ) -> Vec<(RopeDelta, InvalLines, SyntaxEdit)> {
-        if self.content.with_untracked(|c| c.read_only()) {
+        if self.is_read_only() {
             return Vec::new();
         }
 
+        let timer = perf::time(Metric::RopeEdit);
         let old_cursor = cursor.mode.clone();
         let deltas = self.syntax.with_untracked(|syntax| {
             self.buffer
                 .try_update(|buffer| {
+                    buffer
+                        .set_undo_group_timeout(config.editor.undo_group_timeout());
+                    buffer.reset_edit_type_if_moved(&old_cursor);
                     Editor::insert(
                         cursor,
                         buffer,
@@ -398,10 +719,12 @@ pub fn do_insert(
                 })
                 .unwrap()
         });
+        drop(timer);
         // Keep track of the change in the cursor mode for undo/redo
         self.buffer.update(|buffer| {
             buffer.set_cursor_before(old_cursor);
             buffer.set_cursor_after(cursor.mode.clone());
+            buffer.set_scroll((scroll.x, scroll.y));
         });
         self.apply_deltas(&deltas);
         deltas
@@ -412,13 +735,15 @@ pub fn do_raw_edit(
         edits: &[(impl AsRef<Selection>, &str)],
         edit_type: EditType,
     ) -> Option<(RopeDelta, InvalLines, SyntaxEdit)> {
-        if self.content.with_untracked(|c| c.read_only()) {
+        if self.is_read_only() {
             return None;
         }
+        let timer = perf::time(Metric::RopeEdit);
         let (delta, inval_lines, edits) = self
             .buffer
             .try_update(|buffer| buffer.edit(edits, edit_type))
             .unwrap();
+        drop(timer);
         self.apply_deltas(&[(delta.clone(), inval_lines.clone(), edits.clone())]);
         Some((delta, inval_lines, edits))
     }
@@ -430,18 +755,24 @@ pub fn do_edit(
         modal: bool,
         register: &mut Register,
         smart_tab: bool,
+        scroll: Vec2,
     ) -> Vec<(RopeDelta, InvalLines, SyntaxEdit)> {
-        if self.content.with_untracked(|c| c.read_only())
-            && !cmd.not_changing_buffer()
-        {
+        if self.is_read_only() && !cmd.not_changing_buffer() {
             return Vec::new();
         }
 
         let mut clipboard = SystemClipboard::new();
         let old_cursor = cursor.mode.clone();
+        let undo_group_timeout = self
+            .common
+            .config
+            .with_untracked(|config| config.editor.undo_group_timeout());
+        let timer = perf::time(Metric::RopeEdit);
         let deltas = self.syntax.with_untracked(|syntax| {
             self.buffer
                 .try_update(|buffer| {
+                    buffer.set_undo_group_timeout(undo_group_timeout);
+                    buffer.reset_edit_type_if_moved(&old_cursor);
                     Editor::do_edit(
                         cursor,
                         buffer,
@@ -455,11 +786,21 @@ pub fn do_edit(
                 })
                 .unwrap()
         });
+        drop(timer);
 
         if !deltas.is_empty() {
             self.buffer.update(|buffer| {
                 buffer.set_cursor_before(old_cursor);
                 buffer.set_cursor_after(cursor.mode.clone());
+                // Undo and redo already know where to scroll back to
+                if !matches!(
+                    cmd,
+                    EditCommand::Undo
+                        | EditCommand::Redo
+                        | EditCommand::UndoToLastSave
+                ) {
+                    buffer.set_scroll((scroll.x, scroll.y));
+                }
             });
         }
 
@@ -467,14 +808,67 @@ pub fn do_edit(
         deltas
     }
 
+    /// Go to the state of the document right after the edits of an undo group
+    /// were made.
+    pub fn do_undo_to_group(
+        &self,
+        cursor: &mut Cursor,
+        group: usize,
+        modal: bool,
+    ) -> Vec<(RopeDelta, InvalLines, SyntaxEdit)> {
+        if self.is_read_only() {
+            return Vec::new();
+        }
+
+        let deltas = self
+            .buffer
+            .try_update(|buffer| Editor::undo_to_group(cursor, buffer, group, modal))
+            .unwrap();
+        self.apply_deltas(&deltas);
+        deltas
+    }
+
+    pub fn do_surround(
+        &self,
+        cursor: &mut Cursor,
+        surround: Surround,
+    ) -> Vec<(RopeDelta, InvalLines, SyntaxEdit)> {
+        if self.is_read_only() {
+            return Vec::new();
+        }
+
+        let old_cursor = cursor.mode.clone();
+        let deltas = self.syntax.with_untracked(|syntax| {
+            self.buffer
+                .try_update(|buffer| {
+                    Editor::surround(cursor, buffer, syntax, surround)
+                })
+                .unwrap()
+        });
+        if !deltas.is_empty() {
+            self.buffer.update(|buffer| {
+                buffer.set_cursor_before(old_cursor);
+                buffer.set_cursor_after(cursor.mode.clone());
+            });
+        }
+        self.apply_deltas(&deltas);
+        deltas
+    }
+
     pub fn apply_deltas(&self, deltas: &[(RopeDelta, InvalLines, SyntaxEdit)]) {
         let rev = self.rev() - deltas.len() as u64;
         for (i, (delta, inval, _)) in deltas.iter().enumerate() {
             self.update_styles(delta);
             self.update_inlay_hints(delta);
             self.update_diagnostics(delta);
+            self.update_misspellings(delta);
+            self.update_document_colors(delta);
+            self.update_document_links(delta);
+            self.update_runnables(delta);
             self.update_completion_lens(delta);
             self.update_find_result(delta);
+            self.find_result.invalidate_lines(inval);
+            self.text_layouts.borrow_mut().apply_inval_lines(inval);
             if let DocContent::File { path, .. } = self.content.get_untracked() {
                 self.update_breakpoints(delta, &path, &inval.old_text);
                 self.common
@@ -500,15 +894,29 @@ pub fn rev(&self) -> u64 {
     }
 
     fn on_update(&self, edits: Option<SmallVec<[SyntaxEdit; 3]>>) {
+        let is_edit = edits.is_some();
         self.clear_code_actions();
         self.clear_style_cache();
         self.trigger_syntax_change(edits);
+        self.schedule_spell_check();
         self.clear_sticky_headers_cache();
         self.trigger_head_change();
         self.check_auto_save();
+        self.check_recovery();
         self.get_semantic_styles();
         self.get_inlay_hints();
-        self.find_result.reset();
+        self.get_document_colors();
+        self.get_document_links();
+        self.get_runnables();
+        self.get_document_symbols();
+        // The occurrences of a search that can't span lines only have to be found
+        // again on the lines the edit changed
+        if !is_edit
+            || self.find_result.progress.get_untracked() != FindProgress::Ready
+            || self.common.find.is_multiline()
+        {
+            self.find_result.reset();
+        }
     }
 
     fn check_auto_save(&self) {
@@ -534,12 +942,77 @@ fn check_auto_save(&self) {
                         return;
                     }
 
-                    doc.save(|| {});
+                    doc.save(|| {}, || {});
                 },
             );
         }
     }
 
+    /// Back up the unsaved content once it hasn't changed for the recovery interval.
+    fn check_recovery(&self) {
+        let config = self.common.config.get_untracked();
+        // Backing up a large file would write all of it every time
+        if config.editor.recovery_interval == 0
+            || self.is_pristine()
+            || self.large_file.get_untracked()
+        {
+            return;
+        }
+        if !self.content.with_untracked(|c| c.is_file()) {
+            return;
+        }
+        let rev = self.rev();
+        let doc = self.clone();
+        exec_after(
+            Duration::from_millis(config.editor.recovery_interval),
+            move |_| {
+                let current_rev = match doc
+                    .buffer
+                    .try_with_untracked(|b| b.as_ref().map(|b| b.rev()))
+                {
+                    Some(rev) => rev,
+                    None => return,
+                };
+
+                if current_rev != rev || doc.is_pristine() {
+                    return;
+                }
+
+                doc.write_recovery();
+            },
+        );
+    }
+
+    /// Back up the unsaved changes of the file right away.
+    pub fn write_recovery(&self) {
+        if self.is_pristine() || self.large_file.get_untracked() {
+            return;
+        }
+        if let DocContent::File { path, .. } = self.content.get_untracked() {
+            let content = self.buffer.with_untracked(|b| b.to_string());
+            recovery::write_recovery(
+                &self.common.workspace.kind,
+                path,
+                self.buffer_id,
+                self.rev(),
+                content,
+            );
+        }
+    }
+
+    /// Remove the backup of the unsaved changes, because they were saved or
+    /// discarded.
+    pub fn remove_recovery(&self) {
+        if let DocContent::File { path, .. } = self.content.get_untracked() {
+            recovery::remove_recovery(
+                &self.common.workspace.kind,
+                path,
+                self.buffer_id,
+                self.rev(),
+            );
+        }
+    }
+
     /// Update the styles after an edit, so the highlights are at the correct positions.
     /// This does not do a reparse of the document itself.
     fn update_styles(&self, delta: &RopeDelta) {
@@ -576,7 +1049,8 @@ fn update_inlay_hints(&self, delta: &RopeDelta) {
 
     fn clear_style_cache(&self) {
         self.line_styles.borrow_mut().clear();
-        self.clear_text_cache();
+        self.bump_cache_rev();
+        self.text_layouts.borrow_mut().invalidate_styles();
     }
 
     fn clear_code_actions(&self) {
@@ -606,14 +1080,14 @@ pub fn clear_preedit(&self) {
 
     /// Inform any dependents on this document that they should clear any cached text.
     pub fn clear_text_cache(&self) {
-        let cache_rev = self
-            .cache_rev
-            .try_update(|cache_rev| {
-                *cache_rev += 1;
-                *cache_rev
-            })
-            .unwrap();
-        self.text_layouts.borrow_mut().clear(cache_rev);
+        self.bump_cache_rev();
+        self.text_layouts.borrow_mut().clear();
+    }
+
+    fn bump_cache_rev(&self) {
+        self.cache_rev.update(|cache_rev| {
+            *cache_rev += 1;
+        });
     }
 
     fn clear_sticky_headers_cache(&self) {
@@ -652,7 +1126,7 @@ pub fn line_style(&self, line: usize) -> Arc<Vec<LineStyle>> {
 
     /// Request semantic styles for the buffer from the LSP through the proxy.
     fn get_semantic_styles(&self) {
-        if !self.loaded() {
+        if !self.loaded() || self.large_file.get_untracked() {
             return;
         }
 
@@ -670,12 +1144,14 @@ fn get_semantic_styles(&self) {
 
         let doc = self.clone();
         let send = create_ext_action(self.scope, move |styles| {
+            perf::highlight_done();
             if doc.buffer.with_untracked(|b| b.rev()) == rev {
                 doc.semantic_styles.set(Some(styles));
                 doc.clear_style_cache();
             }
         });
 
+        perf::highlight_queued();
         self.common.proxy.get_semantic_tokens(path, move |result| {
             if let Ok(ProxyResponse::GetSemanticTokens { styles }) = result {
                 rayon::spawn(move || {
@@ -702,13 +1178,15 @@ fn get_semantic_styles(&self) {
 
                     send(styles);
                 });
+            } else {
+                perf::highlight_done();
             }
         });
     }
 
     /// Request inlay hints for the buffer from the LSP through the proxy.
     fn get_inlay_hints(&self) {
-        if !self.loaded() {
+        if !self.loaded() || self.large_file.get_untracked() {
             return;
         }
 
@@ -752,6 +1230,217 @@ fn get_inlay_hints(&self) {
         });
     }
 
+    /// Request the colors in the document from the LSP through the proxy, or find
+    /// the hex codes and `rgb()` calls in the text when there's no server for it.
+    fn get_document_colors(&self) {
+        let config = self.common.config.get_untracked();
+        if !self.loaded()
+            || self.large_file.get_untracked()
+            || !config.editor.color_swatches
+        {
+            if !self.document_colors.with_untracked(|c| c.is_empty()) {
+                self.document_colors.set(im::Vector::new());
+                self.clear_text_cache();
+            }
+            return;
+        }
+
+        let path =
+            if let DocContent::File { path, .. } = self.content.get_untracked() {
+                path
+            } else {
+                return;
+            };
+
+        let (buffer, rev) = self.buffer.with_untracked(|b| (b.clone(), b.rev()));
+
+        let doc = self.clone();
+        let send = create_ext_action(
+            self.scope,
+            move |colors: im::Vector<DocumentColor>| {
+                if doc.buffer.with_untracked(|b| b.rev()) == rev
+                    && doc.document_colors.with_untracked(|c| *c != colors)
+                {
+                    doc.document_colors.set(colors);
+                    doc.clear_text_cache();
+                }
+            },
+        );
+
+        self.common.proxy.get_document_colors(path, move |result| {
+            let colors = match result {
+                Ok(ProxyResponse::GetDocumentColors { colors }) => colors
+                    .into_iter()
+                    .map(|info| {
+                        let start = buffer.offset_of_position(&info.range.start);
+                        let end = buffer.offset_of_position(&info.range.end);
+                        let notation =
+                            color::parse_color(&buffer.slice_to_cow(start..end))
+                                .map(|(_, notation)| notation);
+                        let lsp_types::Color {
+                            red,
+                            green,
+                            blue,
+                            alpha,
+                        } = info.color;
+                        let rgba = [red, green, blue, alpha]
+                            .map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+                        DocumentColor {
+                            start,
+                            end,
+                            rgba,
+                            notation,
+                        }
+                    })
+                    .collect(),
+                Err(_) => color::find_colors(&buffer.to_string())
+                    .into_iter()
+                    .map(|color| DocumentColor {
+                        start: color.start,
+                        end: color.end,
+                        rgba: color.rgba,
+                        notation: Some(color.notation),
+                    })
+                    .collect(),
+                Ok(_) => return,
+            };
+            send(colors);
+        });
+    }
+
+    /// Request the links in the document from the LSP through the proxy. The
+    /// links without a target would have to be resolved first, so they're left
+    /// out.
+    fn get_document_links(&self) {
+        if !self.loaded() || self.large_file.get_untracked() {
+            return;
+        }
+
+        let path =
+            if let DocContent::File { path, .. } = self.content.get_untracked() {
+                path
+            } else {
+                return;
+            };
+
+        let (buffer, rev) = self.buffer.with_untracked(|b| (b.clone(), b.rev()));
+
+        let doc = self.clone();
+        let send = create_ext_action(
+            self.scope,
+            move |links: im::Vector<(usize, usize, Url)>| {
+                if doc.rev() == rev {
+                    doc.document_links.set(links);
+                }
+            },
+        );
+
+        self.common.proxy.get_document_links(path, move |result| {
+            if let Ok(ProxyResponse::GetDocumentLinks { links }) = result {
+                let links = links
+                    .into_iter()
+                    .filter_map(|link| {
+                        let start = buffer.offset_of_position(&link.range.start);
+                        let end = buffer.offset_of_position(&link.range.end);
+                        Some((start, end, link.target?))
+                    })
+                    .collect();
+                send(links);
+            }
+        });
+    }
+
+    /// Request the tests and binaries that can be run from the document from the
+    /// LSP through the proxy, to show buttons to run or debug them.
+    fn get_runnables(&self) {
+        let config = self.common.config.get_untracked();
+        if !self.loaded()
+            || self.large_file.get_untracked()
+            || !config.editor.show_runnables
+        {
+            if !self.runnables.with_untracked(|r| r.is_empty()) {
+                self.runnables.set(im::Vector::new());
+                self.clear_text_cache();
+            }
+            return;
+        }
+
+        let path =
+            if let DocContent::File { path, .. } = self.content.get_untracked() {
+                path
+            } else {
+                return;
+            };
+
+        let (buffer, rev) = self.buffer.with_untracked(|b| (b.clone(), b.rev()));
+
+        let doc = self.clone();
+        let send = create_ext_action(
+            self.scope,
+            move |runnables: im::Vector<(usize, Runnable)>| {
+                if doc.rev() == rev
+                    && doc.runnables.with_untracked(|r| *r != runnables)
+                {
+                    doc.runnables.set(runnables);
+                    doc.clear_text_cache();
+                }
+            },
+        );
+
+        self.common.proxy.get_runnables(path, move |result| {
+            if let Ok(ProxyResponse::GetRunnables { runnables }) = result {
+                let runnables = runnables
+                    .into_iter()
+                    .filter(|runnable| runnable.kind == "cargo")
+                    .filter_map(|runnable| {
+                        let position =
+                            runnable.location.as_ref()?.target_selection_range.start;
+                        Some((buffer.offset_of_position(&position), runnable))
+                    })
+                    .collect();
+                send(runnables);
+            }
+        });
+    }
+
+    /// Request the document symbols for the breadcrumbs from the LSP through
+    /// the proxy.
+    fn get_document_symbols(&self) {
+        if !self.loaded() || self.large_file.get_untracked() {
+            return;
+        }
+        if !self.common.config.get_untracked().editor.show_bread_crumbs {
+            return;
+        }
+
+        let path =
+            if let DocContent::File { path, .. } = self.content.get_untracked() {
+                path
+            } else {
+                return;
+            };
+
+        let rev = self.rev();
+        let doc = self.clone();
+        let send = create_ext_action(self.scope, move |symbols| {
+            if doc.rev() == rev {
+                doc.document_symbols.set(Some(Rc::new(symbols)));
+            }
+        });
+
+        self.common.proxy.get_document_symbols(path, move |result| {
+            if let Ok(ProxyResponse::GetDocumentSymbols { resp }) = result {
+                let symbols = match resp {
+                    DocumentSymbolResponse::Nested(symbols) => symbols,
+                    DocumentSymbolResponse::Flat(symbols) => {
+                        nest_symbol_information(symbols)
+                    }
+                };
+                send(symbols);
+            }
+        });
+    }
+
     /// Get the phantom text for a given line
     pub fn line_phantom_text(&self, line: usize) -> PhantomTextLine {
         let config = self.common.config.get_untracked();
@@ -863,6 +1552,48 @@ pub fn line_phantom_text(&self, line: usize) -> PhantomTextLine {
             });
         let mut diag_text: SmallVec<[PhantomText; 6]> = diag_text.collect();
 
+        self.document_colors.with_untracked(|colors| {
+            for color in colors
+                .iter()
+                .filter(|c| c.start >= start_offset && c.start < end_offset)
+            {
+                text.push(PhantomText {
+                    kind: PhantomTextKind::ColorSwatch,
+                    col: color.start - start_offset,
+                    text: "\u{25a0} ".to_string(),
+                    font_size: None,
+                    fg: Some(color.swatch_color()),
+                    bg: None,
+                    under_line: None,
+                });
+            }
+        });
+
+        self.runnables.with_untracked(|runnables| {
+            if !runnables
+                .iter()
+                .any(|(offset, _)| *offset >= start_offset && *offset < end_offset)
+            {
+                return;
+            }
+            let col = end_offset - start_offset;
+            let fg = *config.get_color(LapceColor::EDITOR_LINK);
+            for (kind, label) in [
+                (PhantomTextKind::Run, "    \u{25b6} Run"),
+                (PhantomTextKind::Debug, "  Debug"),
+            ] {
+                text.push(PhantomText {
+                    kind,
+                    col,
+                    text: label.to_string(),
+                    font_size: Some(config.editor.inlay_hint_font_size()),
+                    fg: Some(fg),
+                    bg: None,
+                    under_line: None,
+                });
+            }
+        });
+
         text.append(&mut diag_text);
 
         let (completion_line, completion_col) = self.completion_pos.get_untracked();
@@ -955,6 +1686,113 @@ fn update_diagnostics(&self, delta: &RopeDelta) {
         });
     }
 
+    /// Move the misspellings along with the text an edit moves, until the
+    /// spelling is checked again.
+    fn update_misspellings(&self, delta: &RopeDelta) {
+        if self.misspellings.with_untracked(|m| m.is_empty()) {
+            return;
+        }
+        self.misspellings.update(|misspellings| {
+            for (start, end) in misspellings.iter_mut() {
+                let mut transformer = Transformer::new(delta);
+                *start = transformer.transform(*start, false);
+                *end = transformer.transform(*end, true);
+            }
+        });
+    }
+
+    /// Move the colors along with the text an edit moves, until they're requested
+    /// again.
+    fn update_document_colors(&self, delta: &RopeDelta) {
+        if self.document_colors.with_untracked(|c| c.is_empty()) {
+            return;
+        }
+        self.document_colors.update(|colors| {
+            for color in colors.iter_mut() {
+                let mut transformer = Transformer::new(delta);
+                color.start = transformer.transform(color.start, false);
+                color.end = transformer.transform(color.end, true);
+            }
+        });
+    }
+
+    fn update_document_links(&self, delta: &RopeDelta) {
+        if self.document_links.with_untracked(|l| l.is_empty()) {
+            return;
+        }
+        self.document_links.update(|links| {
+            for (start, end, _) in links.iter_mut() {
+                let mut transformer = Transformer::new(delta);
+                *start = transformer.transform(*start, false);
+                *end = transformer.transform(*end, true);
+            }
+        });
+    }
+
+    fn update_runnables(&self, delta: &RopeDelta) {
+        if self.runnables.with_untracked(|r| r.is_empty()) {
+            return;
+        }
+        self.runnables.update(|runnables| {
+            for (offset, _) in runnables.iter_mut() {
+                let mut transformer = Transformer::new(delta);
+                *offset = transformer.transform(*offset, false);
+            }
+        });
+    }
+
+    /// Check the spelling once the document hasn't changed for a moment, so that
+    /// it isn't done for every key typed.
+    fn schedule_spell_check(&self) {
+        if self
+            .common
+            .spell_dictionary
+            .with_untracked(|d| d.is_empty())
+        {
+            return;
+        }
+        let rev = self.rev();
+        let doc = self.clone();
+        exec_after(SPELL_CHECK_DELAY, move |_| {
+            let current_rev = doc
+                .buffer
+                .try_with_untracked(|b| b.as_ref().map(|b| b.rev()));
+            if current_rev == Some(rev) {
+                doc.check_spelling();
+            }
+        });
+    }
+
+    /// Find the misspelled words in the comments and strings of the document.
+    pub fn check_spelling(&self) {
+        let dictionary = self.common.spell_dictionary.get_untracked();
+        let misspellings = if dictionary.is_empty()
+            || self.large_file.get_untracked()
+            || self.content.with_untracked(|c| c.is_local())
+        {
+            im::Vector::new()
+        } else {
+            let ranges = self
+                .syntax
+                .with_untracked(|s| s.comment_and_string_ranges());
+            self.buffer.with_untracked(|buffer| {
+                ranges
+                    .into_iter()
+                    .flat_map(|(start, end)| {
+                        let text = buffer.slice_to_cow(start..end);
+                        spell::misspellings(&dictionary, &text)
+                            .into_iter()
+                            .map(move |(s, e)| (start + s, start + e))
+                    })
+                    .collect()
+            })
+        };
+        if self.misspellings.with_untracked(|m| *m != misspellings) {
+            self.misspellings.set(misspellings);
+            self.clear_text_cache();
+        }
+    }
+
     /// init diagnostics offset ranges from lsp positions
     pub fn init_diagnostics(&self) {
         self.clear_text_cache();
@@ -999,7 +1837,19 @@ pub fn clear_completion_lens(&self) {
     fn update_find_result(&self, delta: &RopeDelta) {
         self.find_result.occurrences.update(|s| {
             *s = s.apply_delta(delta, true, InsertDrift::Default);
-        })
+        });
+        if let Some(scope) = self.find_result.scope.get_untracked() {
+            let scope = scope.apply_delta(delta, true, InsertDrift::Inside);
+            self.find_result.scope.set(Some(scope));
+        }
+    }
+
+    /// Limit the search to the selection, or search the whole document again
+    /// with `None`.
+    pub fn set_find_scope(&self, scope: Option<Selection>) {
+        self.find_result.scope.set(scope);
+        self.find_result.reset();
+        self.find_result.occurrences.set(Selection::new());
     }
 
     fn update_breakpoints(&self, delta: &RopeDelta, path: &Path, old_text: &Rope) {
@@ -1093,6 +1943,10 @@ pub fn update_find(&self) {
             self.find_result.find_rev.set(find_rev);
         }
 
+        if self.find_result.progress.get_untracked() == FindProgress::Ready {
+            self.update_find_lines();
+            return;
+        }
         if self.find_result.progress.get_untracked() != FindProgress::Started {
             return;
         }
@@ -1119,6 +1973,7 @@ pub fn update_find(&self) {
         let text = self.buffer.with_untracked(|b| b.text().clone());
         let case_matching = self.common.find.case_matching.get_untracked();
         let whole_words = self.common.find.whole_words.get_untracked();
+        let scope = self.find_result.scope.get_untracked();
         rayon::spawn(move || {
             let mut occurrences = Selection::new();
             Find::find(
@@ -1131,10 +1986,48 @@ pub fn update_find(&self) {
                 true,
                 &mut occurrences,
             );
+            if let Some(scope) = scope {
+                occurrences = occurrences_in_scope(&occurrences, &scope);
+            }
             send(occurrences);
         });
     }
 
+    /// Search the lines edits changed again, replacing the occurrences that were
+    /// on them.
+    fn update_find_lines(&self) {
+        let dirty_lines = self
+            .find_result
+            .dirty_lines
+            .try_update(|lines| lines.take());
+        let Some((start_line, end_line)) = dirty_lines.flatten() else {
+            return;
+        };
+        let Some(search) = self.common.find.search_string.get_untracked() else {
+            return;
+        };
+
+        let text = self.buffer.with_untracked(|b| b.text().clone());
+        let start = text.offset_of_line(start_line);
+        let end = text.offset_of_line(end_line);
+        let mut occurrences = self.find_result.occurrences.get_untracked();
+        occurrences.delete_range(start, end);
+        Find::find(
+            &text,
+            &search,
+            start,
+            end,
+            self.common.find.case_matching.get_untracked(),
+            self.common.find.whole_words.get_untracked(),
+            false,
+            &mut occurrences,
+        );
+        if let Some(scope) = self.find_result.scope.get_untracked() {
+            occurrences = occurrences_in_scope(&occurrences, &scope);
+        }
+        self.find_result.occurrences.set(occurrences);
+    }
+
     /// Get the sticky headers for a particular line, creating them if necessary.
     pub fn sticky_headers(&self, line: usize) -> Option<Vec<usize>> {
         if let Some(lines) = self.sticky_headers.borrow().get(&line) {
@@ -1257,9 +2150,9 @@ fn new_whitespace_layout(
         let mut render_boundary = false;
         let mut render_between = false;
 
-        // TODO: render whitespaces only on highlighted text
+        // Whitespace outside of the selections is filtered out when painting
         match config.editor.render_whitespace.as_str() {
-            "all" => {
+            "all" | "selection" => {
                 render_leading = true;
                 render_boundary = true;
                 render_between = true;
@@ -1340,13 +2233,15 @@ fn new_text_layout(&self, line: usize, _font_size: usize) -> TextLayoutLine {
                     &line_content_original[..],
                 )
             };
+        if line_content_original.len() > LONG_LINE_LENGTH {
+            return Self::new_long_line_layout(line_content_original, &config);
+        }
         // Combine the phantom text with the line content
         let phantom_text = self.line_phantom_text(line);
         let line_content = phantom_text.combine_with_text(line_content);
 
         let color = config.get_color(LapceColor::EDITOR_FOREGROUND);
-        let family: Vec<FamilyOwned> =
-            FamilyOwned::parse_list(&config.editor.font_family).collect();
+        let family: Vec<FamilyOwned> = config.editor.font_family();
         let attrs = Attrs::new()
             .color(*color)
             .family(&family)
@@ -1355,13 +2250,34 @@ fn new_text_layout(&self, line: usize, _font_size: usize) -> TextLayoutLine {
 
         // Apply various styles to the line's text based on our semantic/syntax highlighting
         let styles = self.line_style(line);
+        let mut underlines = Vec::new();
         for line_style in styles.iter() {
-            if let Some(fg_color) = line_style.style.fg_color.as_ref() {
-                if let Some(fg_color) = config.get_style_color(fg_color) {
-                    let start = phantom_text.col_at(line_style.start);
-                    let end = phantom_text.col_at(line_style.end);
-                    attrs_list.add_span(start..end, attrs.color(*fg_color));
+            if let Some(scope) = line_style.style.fg_color.as_ref() {
+                let fg_color = config.get_style_color(scope);
+                let font_style = config.get_style_font(scope);
+                if fg_color.is_none() && font_style.is_none() {
+                    continue;
+                }
+
+                let start = phantom_text.col_at(line_style.start);
+                let end = phantom_text.col_at(line_style.end);
+                let mut attrs = attrs;
+                if let Some(fg_color) = fg_color {
+                    attrs = attrs.color(*fg_color);
                 }
+                if let Some(font_style) = font_style {
+                    if font_style.italic {
+                        attrs = attrs.style(FontStyle::Italic);
+                    }
+                    if font_style.bold {
+                        attrs = attrs.weight(Weight::BOLD);
+                    }
+                    if font_style.underline {
+                        let color = fg_color.unwrap_or(color);
+                        underlines.push((start, end, *color));
+                    }
+                }
+                attrs_list.add_span(start..end, attrs);
             }
         }
 
@@ -1411,6 +2327,19 @@ fn new_text_layout(&self, line: usize, _font_size: usize) -> TextLayoutLine {
             }
         }
 
+        // Underlines from the font style of syntax scopes are drawn the same way
+        for (start, end, color) in underlines {
+            let x0 = text_layout.hit_position(start).point.x;
+            let x1 = text_layout.hit_position(end).point.x;
+            extra_style.push(LineExtraStyle {
+                x: x0,
+                width: Some(x1 - x0),
+                bg_color: None,
+                under_line: Some(color),
+                wave_line: None,
+            });
+        }
+
         // Add the styling for the diagnostic severity, if applicable
         if let Some(max_severity) = phantom_text.max_severity {
             let theme_prop = if max_severity == DiagnosticSeverity::ERROR {
@@ -1482,6 +2411,32 @@ fn new_text_layout(&self, line: usize, _font_size: usize) -> TextLayoutLine {
             })
         });
 
+        self.misspellings.with_untracked(|misspellings| {
+            if misspellings.is_empty() {
+                return;
+            }
+            let (line_start, line_end) = self.buffer.with_untracked(|buffer| {
+                (buffer.offset_of_line(line), buffer.offset_of_line(line + 1))
+            });
+            let color = *config.get_color(LapceColor::EDITOR_SPELLING_ERROR);
+            for (start, end) in misspellings.iter() {
+                if *start < line_start || *end > line_end {
+                    continue;
+                }
+                let start = phantom_text.col_after(start - line_start, true);
+                let end = phantom_text.col_after(end - line_start, false);
+                let x0 = text_layout.hit_position(start).point.x;
+                let x1 = text_layout.hit_position(end).point.x;
+                extra_style.push(LineExtraStyle {
+                    x: x0,
+                    width: Some(x1 - x0),
+                    bg_color: None,
+                    under_line: None,
+                    wave_line: Some(color),
+                });
+            }
+        });
+
         let whitespaces = Self::new_whitespace_layout(
             line_content_original,
             &text_layout,
@@ -1517,6 +2472,35 @@ fn new_text_layout(&self, line: usize, _font_size: usize) -> TextLayoutLine {
             extra_style,
             whitespaces,
             indent,
+            long_line: None,
+        }
+    }
+
+    /// Create the text layout of a line that is too long to be shaped at once.
+    /// The line is shown without syntax highlighting, phantom text or
+    /// whitespace rendering.
+    fn new_long_line_layout(
+        line_content: &str,
+        config: &LapceConfig,
+    ) -> TextLayoutLine {
+        let family: Vec<FamilyOwned> = config.editor.font_family();
+        let attrs = Attrs::new()
+            .color(*config.get_color(LapceColor::EDITOR_FOREGROUND))
+            .family(&family)
+            .font_size(config.editor.font_size() as f32);
+        let long_line = LongLineLayout::new(
+            line_content,
+            AttrsList::new(attrs),
+            config.editor.tab_width,
+        );
+
+        let indent_col = line_content.len() - line_content.trim_start().len();
+        TextLayoutLine {
+            extra_style: Vec::new(),
+            text: long_line.layout(0).clone(),
+            whitespaces: None,
+            indent: long_line.hit_position(indent_col).x,
+            long_line: Some(long_line),
         }
     }
 
@@ -1530,68 +2514,112 @@ pub fn get_text_layout(
         let config = self.common.config.get_untracked();
         // Check if the text layout needs to update due to the config being changed
         self.text_layouts.borrow_mut().check_attributes(config.id);
-        // If we don't have a second layer of the hashmap initialized for this specific font size,
-        // do it now
-        if self.text_layouts.borrow().layouts.get(&font_size).is_none() {
-            let mut cache = self.text_layouts.borrow_mut();
-            cache.layouts.insert(font_size, HashMap::new());
-        }
-
-        // Get whether there's an entry for this specific font size and line
-        let cache_exists = self
-            .text_layouts
-            .borrow()
-            .layouts
-            .get(&font_size)
-            .unwrap()
-            .get(&line)
-            .is_some();
-        // If there isn't an entry then we actually have to create it
-        if !cache_exists {
-            let text_layout = Arc::new(self.new_text_layout(line, font_size));
-            let mut cache = self.text_layouts.borrow_mut();
-            let width = text_layout.text.size().width;
-            if width > cache.max_width {
-                cache.max_width = width;
-            }
-            cache
-                .layouts
-                .get_mut(&font_size)
-                .unwrap()
-                .insert(line, text_layout);
+
+        let content = self.buffer.with_untracked(|buffer| {
+            let mut hasher = DefaultHasher::new();
+            buffer.line_content(line).hash(&mut hasher);
+            hasher.finish()
+        });
+        let cached =
+            self.text_layouts
+                .borrow_mut()
+                .get(font_size, line, content, || self.line_style(line));
+        if let Some(text_layout) = cached {
+            return text_layout;
         }
 
-        // Just get the entry, assuming it has been created because we initialize it above.
-        self.text_layouts
-            .borrow()
-            .layouts
-            .get(&font_size)
-            .unwrap()
-            .get(&line)
-            .cloned()
-            .unwrap()
+        let styles = self.line_style(line);
+        let text_layout = Arc::new(self.new_text_layout(line, font_size));
+        self.text_layouts.borrow_mut().insert(
+            font_size,
+            line,
+            content,
+            styles,
+            text_layout.clone(),
+        );
+        text_layout
+    }
+
+    /// The width of the widest line that has been laid out.
+    pub fn max_text_layout_width(&self) -> f64 {
+        self.text_layouts.borrow().max_width
     }
 
-    pub fn save(&self, after_action: impl Fn() + 'static) {
+    /// Save the file, and then call `after_action`. If it can't be saved the user
+    /// is told, and `failed` is called instead, as it is when the document
+    /// changes while it is saved.
+    pub fn save(
+        &self,
+        after_action: impl Fn() + 'static,
+        failed: impl Fn() + 'static,
+    ) {
+        if self.loading_chunks.get_untracked() {
+            failed();
+            return;
+        }
         let content = self.content.get_untracked();
         if let DocContent::File { path, .. } = content {
             let rev = self.rev();
             let buffer = self.buffer;
+            let buffer_id = self.buffer_id;
+            let saved_path = path.clone();
+            let workspace = self.common.workspace.clone();
+            let internal_command = self.common.internal_command;
             let send = create_ext_action(self.scope, move |result| {
-                if let Ok(ProxyResponse::SaveResponse {}) = result {
-                    let current_rev = buffer.with_untracked(|buffer| buffer.rev());
-                    if current_rev == rev {
-                        buffer.update(|buffer| {
-                            buffer.set_pristine();
+                match result {
+                    Ok(ProxyResponse::SaveResponse {}) => {}
+                    Ok(_) => {
+                        failed();
+                        return;
+                    }
+                    Err(err) => {
+                        internal_command.send(InternalCommand::ShowNotification {
+                            title: tr("notification-save-failed"),
+                            level: MessageType::ERROR,
+                            message: tr_args(
+                                "notification-save-failed-message",
+                                &[
+                                    ("path", &saved_path.display().to_string()),
+                                    ("error", &err.message),
+                                ],
+                            ),
                         });
-                        after_action();
+                        failed();
+                        return;
                     }
                 }
+                let current_rev = buffer.with_untracked(|buffer| buffer.rev());
+                if current_rev != rev {
+                    failed();
+                    return;
+                }
+                buffer.update(|buffer| {
+                    buffer.set_pristine();
+                });
+                recovery::remove_recovery(
+                    &workspace.kind,
+                    saved_path.clone(),
+                    buffer_id,
+                    rev,
+                );
+                after_action();
             });
 
-            self.common.proxy.save(rev, path, true, move |result| {
-                send(result);
-            })
+            let write_through_symlink = self
+                .common
+                .config
+                .with_untracked(|config| config.editor.write_through_symlink);
+            self.common.proxy.save(
+                rev,
+                path,
+                true,
+                write_through_symlink,
+                move |result| {
+                    send(result);
+                },
+            )
+        } else {
+            failed();
         }
     }
 
@@ -1613,3 +2641,48 @@ pub fn find_enclosing_brackets(&self, offset: usize) -> Option<(usize, usize)> {
             })
     }
 }
+
+/// Servers that reply with flat symbol information don't say how the symbols
+/// are nested, so nest them by which symbol's range contains which.
+#[allow(deprecated)]
+fn nest_symbol_information(
+    mut symbols: Vec<SymbolInformation>,
+) -> Vec<DocumentSymbol> {
+    fn insert(symbols: &mut Vec<DocumentSymbol>, symbol: DocumentSymbol) {
+        if let Some(last) = symbols.last_mut() {
+            if last.range.start <= symbol.range.start
+                && symbol.range.end <= last.range.end
+            {
+                insert(last.children.get_or_insert_with(Vec::new), symbol);
+                return;
+            }
+        }
+        symbols.push(symbol);
+    }
+
+    // Outer symbols sort before the symbols they contain
+    symbols.sort_by(|a, b| {
+        a.location
+            .range
+            .start
+            .cmp(&b.location.range.start)
+            .then(b.location.range.end.cmp(&a.location.range.end))
+    });
+    let mut nested = Vec::new();
+    for symbol in symbols {
+        insert(
+            &mut nested,
+            DocumentSymbol {
+                name: symbol.name,
+                detail: None,
+                kind: symbol.kind,
+                tags: symbol.tags,
+                deprecated: symbol.deprecated,
+                range: symbol.location.range,
+                selection_range: symbol.location.range,
+                children: None,
+            },
+        );
+    }
+    nested
+}
@@ -17,7 +17,7 @@ pub struct PhantomText {
     pub under_line: Option<Color>,
 }
 
-#[derive(Ord, Eq, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Ord, Eq, PartialEq, PartialOrd)]
 pub enum PhantomTextKind {
     /// Input methods
     Ime,
@@ -25,6 +25,12 @@ pub enum PhantomTextKind {
     Completion,
     /// Inlay hints supplied by an LSP/PSP (like type annotations)
     InlayHint,
+    /// The swatch before a color written in the text
+    ColorSwatch,
+    /// The button to run a test or binary, after the function it is run from
+    Run,
+    /// The button to debug a test or binary
+    Debug,
     /// Error lens
     Diagnostic,
 }
@@ -1,36 +1,52 @@
 use std::{
-    cmp::Ordering, collections::HashMap, rc::Rc, str::FromStr, sync::Arc,
-    time::Duration,
+    cmp::Ordering, collections::HashMap, path::PathBuf, rc::Rc, str::FromStr,
+    sync::Arc, time::Duration,
 };
 
 use anyhow::Result;
 use floem::{
-    action::{exec_after, show_context_menu, TimerToken},
+    action::{exec_after, TimerToken},
     ext_event::create_ext_action,
     keyboard::ModifiersState,
-    menu::{Menu, MenuItem},
     peniko::kurbo::{Point, Rect, Vec2},
     pointer::{PointerButton, PointerInputEvent, PointerMoveEvent},
     reactive::{use_context, RwSignal, Scope},
 };
 use lapce_core::{
-    buffer::{diff::DiffLines, rope_text::RopeText, InvalLines},
+    buffer::{
+        diff::{next_diff_hunk_line, DiffLines},
+        rope_text::RopeText,
+        InvalLines,
+    },
     command::{EditCommand, FocusCommand, MotionModeCommand, MultiSelectionCommand},
     cursor::{Cursor, CursorMode},
     editor::EditType,
-    mode::{Mode, MotionMode},
+    link::{self, LinkTarget, TextLink},
+    mode::{Mode, MotionMode, VisualMode},
     movement::Movement,
-    selection::{InsertDrift, Selection},
+    register::{Clipboard, Register, RegisterName},
+    selection::{InsertDrift, SelRegion, Selection},
+    surround::Surround,
     syntax::edit::SyntaxEdit,
+    word::word_occurrences,
+};
+use lapce_rpc::{
+    buffer::BufferId,
+    plugin::PluginId,
+    proxy::{ProxyResponse, Runnable},
 };
-use lapce_rpc::{buffer::BufferId, plugin::PluginId, proxy::ProxyResponse};
-use lapce_xi_rope::{Rope, RopeDelta, Transformer};
+use lapce_xi_rope::{find::CaseMatching, Rope, RopeDelta, Transformer};
 use lsp_types::{
-    CompletionItem, CompletionTextEdit, GotoDefinitionResponse, HoverContents,
-    Location, MarkedString, MarkupKind, TextEdit,
+    CodeAction, CodeActionKind, CodeActionOrCommand, CompletionItem,
+    CompletionTextEdit, DocumentHighlight, DocumentHighlightKind,
+    GotoDefinitionResponse, HoverContents, Location, MarkedString, MarkupKind,
+    Position, TextEdit, Url, WorkspaceEdit,
 };
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 
+#[cfg(target_os = "linux")]
+use crate::doc::PrimarySelection;
 use crate::{
     command::{
         CommandExecuted, CommandKind, InternalCommand, LapceCommand,
@@ -38,28 +54,39 @@
     },
     completion::{clear_completion_lens, CompletionStatus},
     config::LapceConfig,
+    context_menu::ContextMenu,
     db::LapceDb,
-    doc::{DocContent, Document},
+    debug::RunDebugMode,
+    doc::{phantom_text::PhantomTextKind, DocContent, Document, SystemClipboard},
     editor::location::{EditorLocation, EditorPosition},
     editor_tab::EditorTabChild,
+    find::{Find, FindSearchString},
     id::{DiffEditorId, EditorId, EditorTabId},
     keypress::{condition::Condition, KeyPressFocus},
-    main_split::{MainSplitData, SplitDirection, SplitMoveDirection},
+    localization::tr,
+    main_split::{
+        workspace_edits, MainSplitData, SplitDirection, SplitMoveDirection,
+    },
     markdown::{
         from_marked_string, from_plaintext, parse_markdown, MarkdownContent,
     },
     proxy::path_from_url,
     snippet::Snippet,
+    spell,
     window_tab::{CommonData, Focus, WindowTabData},
 };
 
 use self::{
-    view::{DiffSection, DiffSectionKind, LineInfo, ScreenLines},
+    view::{
+        scroll_bar_content_height, DiffSection, DiffSectionKind, LineInfo,
+        ScreenLines, SCROLL_BAR_WIDTH,
+    },
     view_data::{EditorViewData, EditorViewKind},
 };
 
 pub mod diff;
 pub mod gutter;
+pub mod image_preview;
 pub mod location;
 pub mod movement;
 pub mod view;
@@ -67,6 +94,30 @@
 
 const CHAR_WIDTH: f64 = 7.5;
 const FONT_SIZE: usize = 12;
+/// How often the editor scrolls while a selection is dragged past its edge.
+const DRAG_SCROLL_INTERVAL: Duration = Duration::from_millis(16);
+/// How far the editor scrolls each time at most, when the pointer is far past
+/// its edge.
+const MAX_DRAG_SCROLL: f64 = 60.0;
+
+/// How long the cursor rests before the occurrences of the symbol under it are
+/// highlighted.
+const SYMBOL_HIGHLIGHT_DELAY: Duration = Duration::from_millis(250);
+/// How long the cursor rests before the code actions at it are requested.
+const CODE_ACTIONS_DELAY: Duration = Duration::from_millis(250);
+/// Documents longer than this are searched for the occurrences of the selected
+/// text, or of the symbol under the cursor, on another thread, once the
+/// selection stops changing for a moment.
+const OCCURRENCES_THREAD_LEN: usize = 256 * 1024;
+const SELECTION_OCCURRENCES_DELAY: Duration = Duration::from_millis(100);
+
+/// What a drag with the primary button selects by, after a double or triple
+/// click started it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DragUnit {
+    Word,
+    Line,
+}
 
 #[derive(Clone, Debug)]
 pub enum InlineFindDirection {
@@ -74,6 +125,43 @@ pub enum InlineFindDirection {
     Right,
 }
 
+/// A surround command waiting for the characters of its pairs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SurroundInput {
+    Add,
+    Delete,
+    /// Waiting for the pair to change, and then for the one to change it to.
+    Change(Option<char>),
+}
+
+/// An occurrence of the symbol under the cursor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SymbolHighlight {
+    pub start: usize,
+    pub end: usize,
+    /// Whether the symbol is written to there, rather than read.
+    pub write: bool,
+}
+
+/// The occurrences of the symbol under the cursor, for a revision of the
+/// document.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SymbolHighlights {
+    pub rev: u64,
+    /// Ordered by where they start.
+    pub highlights: Vec<SymbolHighlight>,
+}
+
+/// The occurrences of the selected text, for a revision of the document.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SelectionOccurrences {
+    pub rev: u64,
+    pub text: String,
+    /// The range of the selection, which isn't highlighted as one of them.
+    pub selection: (usize, usize),
+    pub occurrences: Selection,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct EditorInfo {
     pub content: DocContent,
@@ -172,6 +260,10 @@ pub struct EditorData {
     pub diff_editor_id: RwSignal<Option<(EditorTabId, DiffEditorId)>>,
     pub view: EditorViewData,
     pub confirmed: RwSignal<bool>,
+    /// Set for an editor that only shows its document, like the one of the peek
+    /// window. Nothing done in it edits the document, and clicking it doesn't
+    /// move the focus.
+    pub read_only: RwSignal<bool>,
     pub cursor: RwSignal<Cursor>,
     pub window_origin: RwSignal<Point>,
     pub viewport: RwSignal<Rect>,
@@ -181,9 +273,39 @@ pub struct EditorData {
     pub last_movement: RwSignal<Movement>,
     pub inline_find: RwSignal<Option<InlineFindDirection>>,
     pub last_inline_find: RwSignal<Option<(InlineFindDirection, String)>>,
+    /// Set after `"`, for the next character to be the register the next yank,
+    /// delete or paste uses.
+    pub select_register: RwSignal<bool>,
+    pub surround_input: RwSignal<Option<SurroundInput>>,
     pub find_focus: RwSignal<bool>,
+    /// Whether the Emacs style mark is set, in which case movements extend the
+    /// selection from it.
+    pub mark_active: RwSignal<bool>,
     pub active: RwSignal<bool>,
+    /// Set while a drag selects whole words or lines, with the word or line the
+    /// click that started it selected.
+    pub drag_unit: RwSignal<Option<(DragUnit, usize, usize)>>,
+    /// The timer that scrolls while a selection is dragged past the edge.
+    pub drag_scroll_timer: RwSignal<TimerToken>,
+    /// The timer that shows completions once typing pauses.
+    pub completion_timer: RwSignal<TimerToken>,
     pub sticky_header_height: RwSignal<f64>,
+    /// The lines shown in the sticky header, from the top
+    pub sticky_header_lines: RwSignal<Vec<usize>>,
+    /// The link under the pointer while the modifier that follows links is
+    /// held, with its range in the document
+    pub hovered_link: RwSignal<Option<TextLink>>,
+    /// The files the paths written in links are of, or `None` for a path that
+    /// isn't of a file or that the proxy hasn't answered for yet.
+    pub link_paths: RwSignal<HashMap<String, Option<PathBuf>>>,
+    pub symbol_highlights: RwSignal<SymbolHighlights>,
+    /// The timer that highlights the symbol under the cursor once it rests.
+    pub symbol_highlight_timer: RwSignal<TimerToken>,
+    /// The timer that requests the code actions at the cursor once it rests.
+    pub code_actions_timer: RwSignal<TimerToken>,
+    pub selection_occurrences: RwSignal<SelectionOccurrences>,
+    /// The timer that searches a long document for the selected text.
+    pub selection_occurrences_timer: RwSignal<TimerToken>,
     pub common: Rc<CommonData>,
 }
 
@@ -232,6 +354,7 @@ pub fn new(
             view,
             cursor,
             confirmed: cx.create_rw_signal(false),
+            read_only: cx.create_rw_signal(false),
             snippet: cx.create_rw_signal(None),
             window_origin: cx.create_rw_signal(Point::ZERO),
             viewport: cx.create_rw_signal(Rect::ZERO),
@@ -240,9 +363,24 @@ pub fn new(
             last_movement: cx.create_rw_signal(Movement::Left),
             inline_find: cx.create_rw_signal(None),
             last_inline_find: cx.create_rw_signal(None),
+            select_register: cx.create_rw_signal(false),
+            surround_input: cx.create_rw_signal(None),
             find_focus: cx.create_rw_signal(false),
+            mark_active: cx.create_rw_signal(false),
             active: cx.create_rw_signal(false),
+            drag_unit: cx.create_rw_signal(None),
+            drag_scroll_timer: cx.create_rw_signal(TimerToken::INVALID),
+            completion_timer: cx.create_rw_signal(TimerToken::INVALID),
             sticky_header_height: cx.create_rw_signal(0.0),
+            sticky_header_lines: cx.create_rw_signal(Vec::new()),
+            hovered_link: cx.create_rw_signal(None),
+            link_paths: cx.create_rw_signal(HashMap::new()),
+            symbol_highlights: cx.create_rw_signal(SymbolHighlights::default()),
+            symbol_highlight_timer: cx.create_rw_signal(TimerToken::INVALID),
+            code_actions_timer: cx.create_rw_signal(TimerToken::INVALID),
+            selection_occurrences: cx
+                .create_rw_signal(SelectionOccurrences::default()),
+            selection_occurrences_timer: cx.create_rw_signal(TimerToken::INVALID),
             common,
         }
     }
@@ -270,6 +408,8 @@ pub fn editor_info(&self, _data: &WindowTabData) -> EditorInfo {
     /// Swap out the document this editor is for.
     pub fn update_doc(&self, doc: Rc<Document>) {
         self.view.update_doc(doc);
+        // Relative paths are of a different directory now
+        self.link_paths.set(HashMap::new());
     }
 
     pub fn copy(
@@ -302,18 +442,37 @@ pub fn copy(
             )),
             window_origin: cx.create_rw_signal(Point::ZERO),
             confirmed: cx.create_rw_signal(true),
+            read_only: cx.create_rw_signal(self.read_only.get_untracked()),
             snippet: cx.create_rw_signal(None),
             last_movement: cx.create_rw_signal(self.last_movement.get_untracked()),
             inline_find: cx.create_rw_signal(None),
             last_inline_find: cx.create_rw_signal(None),
+            select_register: cx.create_rw_signal(false),
+            surround_input: cx.create_rw_signal(None),
             find_focus: cx.create_rw_signal(false),
+            mark_active: cx.create_rw_signal(false),
             active: cx.create_rw_signal(false),
+            drag_unit: cx.create_rw_signal(None),
+            drag_scroll_timer: cx.create_rw_signal(TimerToken::INVALID),
+            completion_timer: cx.create_rw_signal(TimerToken::INVALID),
             sticky_header_height: cx.create_rw_signal(0.0),
+            sticky_header_lines: cx.create_rw_signal(Vec::new()),
+            hovered_link: cx.create_rw_signal(None),
+            link_paths: cx.create_rw_signal(self.link_paths.get_untracked()),
+            symbol_highlights: cx.create_rw_signal(SymbolHighlights::default()),
+            symbol_highlight_timer: cx.create_rw_signal(TimerToken::INVALID),
+            code_actions_timer: cx.create_rw_signal(TimerToken::INVALID),
+            selection_occurrences: cx
+                .create_rw_signal(SelectionOccurrences::default()),
+            selection_occurrences_timer: cx.create_rw_signal(TimerToken::INVALID),
             common: self.common.clone(),
         }
     }
 
     fn run_edit_command(&self, cmd: &EditCommand) -> CommandExecuted {
+        if self.read_only.get_untracked() && !cmd.not_changing_buffer() {
+            return CommandExecuted::No;
+        }
         let doc = self.view.doc.get_untracked();
         let modal = self
             .common
@@ -327,7 +486,7 @@ fn run_edit_command(&self, cmd: &EditCommand) -> CommandExecuted {
         let doc_before_edit =
             doc.buffer.with_untracked(|buffer| buffer.text().clone());
         let mut cursor = self.cursor.get_untracked();
-        let mut register = self.common.register.get_untracked();
+        let mut register = self.command_register();
 
         let yank_data =
             if let lapce_core::cursor::CursorMode::Visual { .. } = &cursor.mode {
@@ -342,16 +501,31 @@ fn run_edit_command(&self, cmd: &EditCommand) -> CommandExecuted {
                 None
             };
 
-        let deltas = doc.do_edit(&mut cursor, cmd, modal, &mut register, smart_tab);
+        let scroll = self.viewport.get_untracked().origin().to_vec2();
+        let deltas =
+            doc.do_edit(&mut cursor, cmd, modal, &mut register, smart_tab, scroll);
 
         if !deltas.is_empty() {
             if let Some(data) = yank_data {
                 register.add_delete(data);
             }
+            if let EditCommand::Undo
+            | EditCommand::Redo
+            | EditCommand::UndoToLastSave = cmd
+            {
+                self.scroll_to_undo();
+            }
         }
 
+        // A register picked for a command that doesn't use one isn't kept for
+        // the next
+        register.clear_selected();
         self.cursor.set(cursor);
-        self.common.register.set(register);
+        self.set_command_register(register);
+        self.mark_active.set(matches!(
+            cmd,
+            EditCommand::SetMark | EditCommand::ExchangePointAndMark
+        ));
 
         if show_completion(cmd, &doc_before_edit, &deltas) {
             self.update_completion(false);
@@ -366,6 +540,64 @@ fn run_edit_command(&self, cmd: &EditCommand) -> CommandExecuted {
         CommandExecuted::Yes
     }
 
+    /// The register for a command, with what's on the system clipboard in the
+    /// `+` register if `"+` picked it for the command.
+    fn command_register(&self) -> Register {
+        let mut register = self.common.register.get_untracked();
+        if register.selects_clipboard() {
+            register.load_clipboard(SystemClipboard::new().get_string());
+        }
+        register
+    }
+
+    /// Keep the register after a command, and put on the system clipboard what
+    /// the command put in the `+` register.
+    fn set_command_register(&self, mut register: Register) {
+        if let Some(content) = register.take_clipboard_write() {
+            SystemClipboard::new().put_string(content);
+        }
+        self.common.register.set(register);
+    }
+
+    /// Paste an entry of the clipboard history, the newest first.
+    pub fn paste_from_history(&self, index: usize) {
+        self.common
+            .register
+            .update(|register| register.select(RegisterName::History(index)));
+        self.run_edit_command(&EditCommand::Paste);
+    }
+
+    /// Scroll back to where the editor was when the edits that were just undone
+    /// or redone were made.
+    fn scroll_to_undo(&self) {
+        let doc = self.view.doc.get_untracked();
+        if let Some((x, y)) =
+            doc.buffer.with_untracked(|buffer| buffer.undo_scroll())
+        {
+            self.scroll_to.set(Some(Vec2::new(x, y)));
+        }
+    }
+
+    /// Go to a state in the undo history of the document, from the undo tree
+    /// panel.
+    pub fn undo_to_group(&self, group: usize) {
+        let doc = self.view.doc.get_untracked();
+        let modal = self
+            .common
+            .config
+            .with_untracked(|config| config.core.modal)
+            && !doc.content.with_untracked(|content| content.is_local());
+        let mut cursor = self.cursor.get_untracked();
+        let deltas = doc.do_undo_to_group(&mut cursor, group, modal);
+        if deltas.is_empty() {
+            return;
+        }
+        self.cursor.set(cursor);
+        self.cancel_completion();
+        self.scroll_to_undo();
+        self.apply_deltas(&deltas);
+    }
+
     fn run_motion_mode_command(
         &self,
         cmd: &MotionModeCommand,
@@ -379,7 +611,7 @@ fn run_motion_mode_command(
             MotionModeCommand::MotionModeYank => MotionMode::Yank { count },
         };
         let mut cursor = self.cursor.get_untracked();
-        let mut register = self.common.register.get_untracked();
+        let mut register = self.command_register();
 
         movement::do_motion_mode(
             &self.view.doc.get_untracked(),
@@ -389,7 +621,7 @@ fn run_motion_mode_command(
         );
 
         self.cursor.set(cursor);
-        self.common.register.set(register);
+        self.set_command_register(register);
 
         CommandExecuted::Yes
     }
@@ -399,13 +631,28 @@ fn run_multi_selection_command(
         cmd: &MultiSelectionCommand,
     ) -> CommandExecuted {
         let mut cursor = self.cursor.get_untracked();
-        movement::do_multi_selection(&self.view, &mut cursor, cmd);
+        let mut register = self.common.register.get_untracked();
+        movement::do_multi_selection(&self.view, &mut cursor, cmd, &mut register);
         self.cursor.set(cursor);
+        self.common.register.set(register);
         // self.cancel_signature();
         self.cancel_completion();
         CommandExecuted::Yes
     }
 
+    /// Keep the selections that the regex matches, or remove them if `keep` is
+    /// false, from the palette.
+    pub fn filter_selections(&self, regex: &Regex, keep: bool) {
+        let rope_text = self.view.rope_text();
+        let mut cursor = self.cursor.get_untracked();
+        if let CursorMode::Insert(selection) = &cursor.mode {
+            let selection =
+                movement::filter_selection(&rope_text, selection, regex, keep);
+            cursor.set_insert(selection);
+            self.cursor.set(cursor);
+        }
+    }
+
     fn run_move_command(
         &self,
         movement: &lapce_core::movement::Movement,
@@ -434,16 +681,18 @@ fn run_move_command(
         self.last_movement.set(movement.clone());
 
         let mut cursor = self.cursor.get_untracked();
-        self.common.register.update(|register| {
-            movement::move_cursor(
-                &self.view,
-                &mut cursor,
-                movement,
-                count.unwrap_or(1),
-                mods.shift_key(),
-                register,
-            )
-        });
+        // The movement can finish a delete or yank, like the `w` of `dw`
+        let mut register = self.command_register();
+        movement::move_cursor(
+            &self.view,
+            &mut cursor,
+            movement,
+            count.unwrap_or(1),
+            mods.shift_key() || self.mark_active.get_untracked(),
+            &mut register,
+        );
+        register.clear_selected();
+        self.set_command_register(register);
 
         self.cursor.set(cursor);
 
@@ -741,9 +990,42 @@ pub fn run_focus_command(
             FocusCommand::GotoDefinition => {
                 self.go_to_definition();
             }
+            FocusCommand::PeekDefinition => {
+                self.peek_definition();
+            }
+            FocusCommand::FindReferences => {
+                self.find_references();
+            }
             FocusCommand::ShowCodeActions => {
                 self.show_code_actions(false);
             }
+            FocusCommand::OrganizeImports => {
+                self.organize_imports();
+            }
+            FocusCommand::ExtractFunction => {
+                self.refactor_selection(
+                    CodeActionKind::REFACTOR_EXTRACT,
+                    &["function", "method"],
+                );
+            }
+            FocusCommand::ExtractVariable => {
+                self.refactor_selection(
+                    CodeActionKind::REFACTOR_EXTRACT,
+                    &["variable", "constant"],
+                );
+            }
+            FocusCommand::InlineSymbol => {
+                self.refactor_selection(CodeActionKind::REFACTOR_INLINE, &[]);
+            }
+            FocusCommand::PickColor => {
+                let offset = self.cursor.with_untracked(|c| c.offset());
+                self.common
+                    .internal_command
+                    .send(InternalCommand::StartColorPicker { offset });
+            }
+            FocusCommand::GetCompletion => {
+                self.update_completion(true);
+            }
             FocusCommand::SearchWholeWordForward => {
                 self.search_whole_word_forward(mods);
             }
@@ -753,8 +1035,20 @@ pub fn run_focus_command(
             FocusCommand::SearchBackward => {
                 self.search_backward(mods);
             }
+            FocusCommand::NextDiff => {
+                self.go_to_diff_hunk(true, mods);
+            }
+            FocusCommand::PreviousDiff => {
+                self.go_to_diff_hunk(false, mods);
+            }
             FocusCommand::Save => {
-                self.save(true, || {});
+                self.save(true, || {}, || {});
+            }
+            FocusCommand::SaveAs => {
+                let doc = self.view.doc.get_untracked();
+                self.common
+                    .internal_command
+                    .send(InternalCommand::SaveDocAs { doc });
             }
             FocusCommand::InlineFindLeft => {
                 self.inline_find.set(Some(InlineFindDirection::Left));
@@ -762,6 +1056,31 @@ pub fn run_focus_command(
             FocusCommand::InlineFindRight => {
                 self.inline_find.set(Some(InlineFindDirection::Right));
             }
+            FocusCommand::SelectRegister => {
+                self.select_register.set(true);
+            }
+            FocusCommand::SurroundAdd => {
+                self.surround_input.set(Some(SurroundInput::Add));
+            }
+            FocusCommand::SurroundChange => {
+                self.surround_input.set(Some(SurroundInput::Change(None)));
+            }
+            FocusCommand::SurroundDelete => {
+                self.surround_input.set(Some(SurroundInput::Delete));
+            }
+            FocusCommand::MotionModeSurround => {
+                let motion_mode =
+                    self.cursor.try_update(|cursor| cursor.motion_mode.take());
+                match motion_mode.flatten() {
+                    Some(MotionMode::Delete { .. }) => {
+                        self.surround_input.set(Some(SurroundInput::Delete));
+                    }
+                    Some(MotionMode::Yank { .. }) => {
+                        self.surround_input.set(Some(SurroundInput::Add));
+                    }
+                    _ => {}
+                }
+            }
             FocusCommand::RepeatLastInlineFind => {
                 if let Some((direction, c)) = self.last_inline_find.get_untracked() {
                     self.inline_find(direction, &c);
@@ -773,6 +1092,9 @@ pub fn run_focus_command(
             FocusCommand::ClearSearch => {
                 self.clear_search();
             }
+            FocusCommand::ToggleFindInSelection => {
+                self.toggle_find_in_selection();
+            }
             FocusCommand::Search => {
                 self.search();
             }
@@ -802,6 +1124,33 @@ pub fn run_focus_command(
         CommandExecuted::Yes
     }
 
+    fn receive_surround_char(&self, input: SurroundInput, c: &str) {
+        self.surround_input.set(None);
+        let mut chars = c.chars();
+        let (Some(c), None) = (chars.next(), chars.next()) else {
+            return;
+        };
+        let surround = match input {
+            SurroundInput::Add => Surround::Add(c),
+            SurroundInput::Delete => Surround::Delete(c),
+            SurroundInput::Change(None) => {
+                self.surround_input
+                    .set(Some(SurroundInput::Change(Some(c))));
+                return;
+            }
+            SurroundInput::Change(Some(from)) => Surround::Change(from, c),
+        };
+
+        let doc = self.view.doc.get_untracked();
+        let mut cursor = self.cursor.get_untracked();
+        let deltas = doc.do_surround(&mut cursor, surround);
+        if deltas.is_empty() {
+            return;
+        }
+        self.cursor.set(cursor);
+        self.apply_deltas(&deltas);
+    }
+
     /// Jump to the next/previous column on the line which matches the given text
     fn inline_find(&self, direction: InlineFindDirection, c: &str) {
         let offset = self.cursor.with_untracked(|c| c.offset());
@@ -843,7 +1192,55 @@ fn inline_find(&self, direction: InlineFindDirection, c: &str) {
         }
     }
 
-    fn go_to_definition(&self) {
+    /// List the references to the symbol at the cursor in the palette.
+    fn find_references(&self) {
+        let doc = self.view.doc.get_untracked();
+        let path = match if doc.loaded() {
+            doc.content.with_untracked(|c| c.path().cloned())
+        } else {
+            None
+        } {
+            Some(path) => path,
+            None => return,
+        };
+
+        let offset = self.cursor.with_untracked(|c| c.offset());
+        let position = doc
+            .buffer
+            .with_untracked(|buffer| buffer.offset_to_position(offset));
+
+        let internal_command = self.common.internal_command;
+        let send =
+            create_ext_action(self.scope, move |references: Vec<Location>| {
+                internal_command.send(InternalCommand::PaletteReferences {
+                    references: references
+                        .into_iter()
+                        .map(|l| EditorLocation {
+                            path: path_from_url(&l.uri),
+                            position: Some(EditorPosition::Position(l.range.start)),
+                            scroll_offset: None,
+                            ignore_unconfirmed: false,
+                            same_editor_tab: false,
+                        })
+                        .collect(),
+                });
+            });
+        self.common
+            .proxy
+            .get_references(path, position, move |result| {
+                if let Ok(ProxyResponse::GetReferencesResponse { references }) =
+                    result
+                {
+                    if !references.is_empty() {
+                        send(references);
+                    }
+                }
+            });
+    }
+
+    /// Show the definitions of the symbol at the cursor in the peek window, or
+    /// its references when the cursor is on the definition already.
+    fn peek_definition(&self) {
         let doc = self.view.doc.get_untracked();
         let path = match if doc.loaded() {
             doc.content.with_untracked(|c| c.path().cloned())
@@ -862,41 +1259,25 @@ fn go_to_definition(&self) {
             (start_position, position)
         });
 
-        enum DefinitionOrReferece {
-            Location(EditorLocation),
-            References(Vec<Location>),
-        }
-
         let internal_command = self.common.internal_command;
         let cursor = self.cursor.read_only();
-        let send = create_ext_action(self.scope, move |d| {
-            let current_offset = cursor.with_untracked(|c| c.offset());
-            if current_offset != offset {
+        let send = create_ext_action(self.scope, move |locations: Vec<Location>| {
+            if cursor.with_untracked(|c| c.offset()) != offset {
                 return;
             }
-
-            match d {
-                DefinitionOrReferece::Location(location) => {
-                    internal_command
-                        .send(InternalCommand::JumpToLocation { location });
-                }
-                DefinitionOrReferece::References(locations) => {
-                    internal_command.send(InternalCommand::PaletteReferences {
-                        references: locations
-                            .into_iter()
-                            .map(|l| EditorLocation {
-                                path: path_from_url(&l.uri),
-                                position: Some(EditorPosition::Position(
-                                    l.range.start,
-                                )),
-                                scroll_offset: None,
-                                ignore_unconfirmed: false,
-                                same_editor_tab: false,
-                            })
-                            .collect(),
-                    });
-                }
-            }
+            internal_command.send(InternalCommand::ShowPeek {
+                offset,
+                locations: locations
+                    .into_iter()
+                    .map(|l| EditorLocation {
+                        path: path_from_url(&l.uri),
+                        position: Some(EditorPosition::Position(l.range.start)),
+                        scroll_offset: None,
+                        ignore_unconfirmed: false,
+                        same_editor_tab: false,
+                    })
+                    .collect(),
+            });
         });
         let proxy = self.common.proxy.clone();
         self.common.proxy.get_definition(
@@ -904,100 +1285,457 @@ enum DefinitionOrReferece {
             path.clone(),
             position,
             move |result| {
-                if let Ok(ProxyResponse::GetDefinitionResponse {
-                    definition, ..
-                }) = result
-                {
-                    if let Some(location) = match definition {
-                        GotoDefinitionResponse::Scalar(location) => Some(location),
-                        GotoDefinitionResponse::Array(locations) => {
-                            if !locations.is_empty() {
-                                Some(locations[0].clone())
-                            } else {
-                                None
+                let Ok(ProxyResponse::GetDefinitionResponse { definition, .. }) =
+                    result
+                else {
+                    return;
+                };
+                let locations = match definition {
+                    GotoDefinitionResponse::Scalar(location) => vec![location],
+                    GotoDefinitionResponse::Array(locations) => locations,
+                    GotoDefinitionResponse::Link(location_links) => location_links
+                        .into_iter()
+                        .map(|link| Location {
+                            uri: link.target_uri,
+                            range: link.target_selection_range,
+                        })
+                        .collect(),
+                };
+                let on_definition = locations
+                    .first()
+                    .is_some_and(|location| location.range.start == start_position);
+                if on_definition {
+                    proxy.get_references(path, position, move |result| {
+                        if let Ok(ProxyResponse::GetReferencesResponse {
+                            references,
+                        }) = result
+                        {
+                            if !references.is_empty() {
+                                send(references);
                             }
                         }
-                        GotoDefinitionResponse::Link(location_links) => {
-                            let location_link = location_links[0].clone();
-                            Some(Location {
-                                uri: location_link.target_uri,
-                                range: location_link.target_selection_range,
-                            })
-                        }
-                    } {
-                        if location.range.start == start_position {
-                            proxy.get_references(
-                                path.clone(),
-                                position,
-                                move |result| {
-                                    if let Ok(
-                                        ProxyResponse::GetReferencesResponse {
-                                            references,
-                                        },
-                                    ) = result
-                                    {
-                                        if references.is_empty() {
-                                            return;
-                                        }
-                                        if references.len() == 1 {
-                                            let location = &references[0];
-                                            send(DefinitionOrReferece::Location(
-                                                EditorLocation {
-                                                    path: path_from_url(
-                                                        &location.uri,
-                                                    ),
-                                                    position: Some(
-                                                        EditorPosition::Position(
-                                                            location.range.start,
-                                                        ),
-                                                    ),
-                                                    scroll_offset: None,
-                                                    ignore_unconfirmed: false,
-                                                    same_editor_tab: false,
-                                                },
-                                            ));
-                                        } else {
-                                            send(DefinitionOrReferece::References(
-                                                references,
-                                            ));
-                                        }
-                                    }
-                                },
-                            );
-                        } else {
-                            let path = path_from_url(&location.uri);
-                            send(DefinitionOrReferece::Location(EditorLocation {
-                                path,
-                                position: Some(EditorPosition::Position(
-                                    location.range.start,
-                                )),
-                                scroll_offset: None,
-                                ignore_unconfirmed: false,
-                                same_editor_tab: false,
-                            }));
-                        }
-                    }
+                    });
+                } else if !locations.is_empty() {
+                    send(locations);
                 }
             },
         );
     }
 
-    fn page_move(&self, down: bool, mods: ModifiersState) {
-        let config = self.common.config.get_untracked();
-        let viewport = self.viewport.get_untracked();
-        let line_height = config.editor.line_height() as f64;
-        let lines = (viewport.height() / line_height / 2.0).round() as usize;
-        let distance = (lines as f64) * line_height;
-        self.scroll_delta
-            .set(Vec2::new(0.0, if down { distance } else { -distance }));
-        self.run_move_command(
-            if down {
-                &lapce_core::movement::Movement::Down
-            } else {
-                &lapce_core::movement::Movement::Up
-            },
-            Some(lines),
-            mods,
+    /// Highlight the occurrences of the symbol under the cursor once the cursor
+    /// rests. The highlights stay while the cursor moves within one of them.
+    pub fn schedule_symbol_highlights(&self) {
+        let doc = self.view.doc.get_untracked();
+        let enabled = self
+            .common
+            .config
+            .with_untracked(|config| config.editor.highlight_symbol);
+        if !enabled || doc.content.with_untracked(|c| c.is_local()) {
+            self.clear_symbol_highlights();
+            return;
+        }
+
+        let offset = self.cursor.with_untracked(|c| c.offset());
+        let rev = doc.rev();
+        let on_highlight = self.symbol_highlights.with_untracked(|highlights| {
+            highlights.rev == rev
+                && highlights
+                    .highlights
+                    .iter()
+                    .any(|h| h.start <= offset && offset <= h.end)
+        });
+        if on_highlight {
+            return;
+        }
+        self.clear_symbol_highlights();
+
+        let editor = self.clone();
+        let timer = self.symbol_highlight_timer;
+        let token = exec_after(SYMBOL_HIGHLIGHT_DELAY, move |token| {
+            if timer.try_get_untracked() == Some(token) {
+                editor.update_symbol_highlights();
+            }
+        });
+        timer.set(token);
+    }
+
+    fn clear_symbol_highlights(&self) {
+        self.symbol_highlight_timer.set(TimerToken::INVALID);
+        if self
+            .symbol_highlights
+            .with_untracked(|highlights| !highlights.highlights.is_empty())
+        {
+            self.symbol_highlights.set(SymbolHighlights::default());
+        }
+    }
+
+    /// Highlight the whole-word matches of the word under the cursor, and then
+    /// the occurrences the language server finds instead, which tell reads from
+    /// writes and leave out matches that are another symbol.
+    fn update_symbol_highlights(&self) {
+        let doc = self.view.doc.get_untracked();
+        let offset = self.cursor.with_untracked(|c| c.offset());
+        let rev = doc.rev();
+
+        let text = self.view.text();
+        let (start, end) = self.view.rope_text().select_word(offset);
+        let symbol = text.slice_to_cow(start..end).to_string();
+        let word_chars = self.view.word_chars();
+        let long = text.len() > OCCURRENCES_THREAD_LEN;
+        let find_highlights = move || -> Vec<SymbolHighlight> {
+            word_occurrences(&text, &symbol, &word_chars)
+                .into_iter()
+                .map(|(start, end)| SymbolHighlight {
+                    start,
+                    end,
+                    write: false,
+                })
+                .collect()
+        };
+        if !long {
+            let highlights = find_highlights();
+            self.symbol_highlights
+                .set(SymbolHighlights { rev, highlights });
+        } else {
+            let buffer = doc.buffer;
+            let cursor = self.cursor.read_only();
+            let symbol_highlights = self.symbol_highlights;
+            let send = create_ext_action(
+                self.scope,
+                move |highlights: Vec<SymbolHighlight>| {
+                    // The ones of the language server may be there already
+                    if buffer.with_untracked(|b| b.rev()) != rev
+                        || cursor.with_untracked(|c| c.offset()) != offset
+                        || symbol_highlights.with_untracked(|h| {
+                            h.rev == rev && !h.highlights.is_empty()
+                        })
+                    {
+                        return;
+                    }
+                    symbol_highlights.set(SymbolHighlights { rev, highlights });
+                },
+            );
+            std::thread::spawn(move || {
+                send(find_highlights());
+            });
+        }
+
+        let path = match if doc.loaded() {
+            doc.content.with_untracked(|c| c.path().cloned())
+        } else {
+            None
+        } {
+            Some(path) => path,
+            None => return,
+        };
+        let position = doc
+            .buffer
+            .with_untracked(|buffer| buffer.offset_to_position(offset));
+
+        let buffer = doc.buffer;
+        let cursor = self.cursor.read_only();
+        let symbol_highlights = self.symbol_highlights;
+        let send = create_ext_action(
+            self.scope,
+            move |highlights: Vec<DocumentHighlight>| {
+                if buffer.with_untracked(|b| b.rev()) != rev
+                    || cursor.with_untracked(|c| c.offset()) != offset
+                {
+                    return;
+                }
+                let mut highlights: Vec<SymbolHighlight> =
+                    buffer.with_untracked(|buffer| {
+                        highlights
+                            .iter()
+                            .map(|h| SymbolHighlight {
+                                start: buffer.offset_of_position(&h.range.start),
+                                end: buffer.offset_of_position(&h.range.end),
+                                write: h.kind == Some(DocumentHighlightKind::WRITE),
+                            })
+                            .collect()
+                    });
+                highlights.sort_by_key(|h| h.start);
+                symbol_highlights.set(SymbolHighlights { rev, highlights });
+            },
+        );
+        self.common
+            .proxy
+            .get_document_highlights(path, position, move |result| {
+                if let Ok(ProxyResponse::GetDocumentHighlightsResponse {
+                    highlights,
+                }) = result
+                {
+                    if !highlights.is_empty() {
+                        send(highlights);
+                    }
+                }
+            });
+    }
+
+    /// Find the other occurrences of the selected text, when it's on one line
+    /// and long enough. Long documents are searched on another thread.
+    pub fn update_selection_occurrences(&self) {
+        let doc = self.view.doc.get_untracked();
+        let (enabled, min_len) = self.common.config.with_untracked(|config| {
+            (
+                config.editor.highlight_selection_occurrences,
+                config.editor.highlight_selection_min_length,
+            )
+        });
+        let selected = if enabled && !doc.content.with_untracked(|c| c.is_local()) {
+            self.selected_text()
+        } else {
+            None
+        };
+        let Some((selection, search)) = selected.filter(|(_, text)| {
+            text.chars().count() >= min_len.max(1)
+                && !text.contains('\n')
+                && !text.trim().is_empty()
+        }) else {
+            self.selection_occurrences_timer.set(TimerToken::INVALID);
+            if self
+                .selection_occurrences
+                .with_untracked(|o| !o.occurrences.is_empty())
+            {
+                self.selection_occurrences
+                    .set(SelectionOccurrences::default());
+            }
+            return;
+        };
+
+        let rev = doc.rev();
+        if self
+            .selection_occurrences
+            .with_untracked(|o| o.rev == rev && o.text == search)
+        {
+            self.selection_occurrences
+                .update(|o| o.selection = selection);
+            return;
+        }
+
+        let text = self.view.text();
+        if text.len() <= OCCURRENCES_THREAD_LEN {
+            let occurrences = find_occurrences(&text, &search);
+            self.selection_occurrences.set(SelectionOccurrences {
+                rev,
+                text: search,
+                selection,
+                occurrences,
+            });
+            return;
+        }
+
+        self.selection_occurrences
+            .set(SelectionOccurrences::default());
+        let editor = self.clone();
+        let timer = self.selection_occurrences_timer;
+        let token = exec_after(SELECTION_OCCURRENCES_DELAY, move |token| {
+            if timer.try_get_untracked() != Some(token) {
+                return;
+            }
+            let selection_occurrences = editor.selection_occurrences;
+            let current = editor.clone();
+            let send = create_ext_action(editor.scope, {
+                let search = search.clone();
+                move |occurrences| {
+                    // The selection may have changed while it was searched for
+                    let Some((selection, _)) =
+                        current.selected_text().filter(|(_, text)| *text == search)
+                    else {
+                        return;
+                    };
+                    if current.view.doc.get_untracked().rev() == rev {
+                        selection_occurrences.set(SelectionOccurrences {
+                            rev,
+                            text: search,
+                            selection,
+                            occurrences,
+                        });
+                    }
+                }
+            });
+            let text = text.clone();
+            let search = search.clone();
+            std::thread::spawn(move || {
+                send(find_occurrences(&text, &search));
+            });
+        });
+        timer.set(token);
+    }
+
+    /// The range and text of the selection, when there is one region selected.
+    fn selected_text(&self) -> Option<((usize, usize), String)> {
+        let doc = self.view.doc.get_untracked();
+        doc.buffer.with_untracked(|buffer| {
+            let selection = self.cursor.with_untracked(|c| c.edit_selection(buffer));
+            match selection.regions() {
+                [region] if !region.is_caret() => {
+                    let (start, end) = (region.min(), region.max());
+                    Some((
+                        (start, end),
+                        buffer.text().slice_to_cow(start..end).to_string(),
+                    ))
+                }
+                _ => None,
+            }
+        })
+    }
+
+    fn go_to_definition(&self) {
+        let doc = self.view.doc.get_untracked();
+        let path = match if doc.loaded() {
+            doc.content.with_untracked(|c| c.path().cloned())
+        } else {
+            None
+        } {
+            Some(path) => path,
+            None => return,
+        };
+
+        let offset = self.cursor.with_untracked(|c| c.offset());
+        let (start_position, position) = doc.buffer.with_untracked(|buffer| {
+            let start_offset = buffer.prev_code_boundary(offset);
+            let start_position = buffer.offset_to_position(start_offset);
+            let position = buffer.offset_to_position(offset);
+            (start_position, position)
+        });
+
+        enum DefinitionOrReferece {
+            Location(EditorLocation),
+            References(Vec<Location>),
+        }
+
+        let internal_command = self.common.internal_command;
+        let cursor = self.cursor.read_only();
+        let send = create_ext_action(self.scope, move |d| {
+            let current_offset = cursor.with_untracked(|c| c.offset());
+            if current_offset != offset {
+                return;
+            }
+
+            match d {
+                DefinitionOrReferece::Location(location) => {
+                    internal_command
+                        .send(InternalCommand::JumpToLocation { location });
+                }
+                DefinitionOrReferece::References(locations) => {
+                    internal_command.send(InternalCommand::PaletteReferences {
+                        references: locations
+                            .into_iter()
+                            .map(|l| EditorLocation {
+                                path: path_from_url(&l.uri),
+                                position: Some(EditorPosition::Position(
+                                    l.range.start,
+                                )),
+                                scroll_offset: None,
+                                ignore_unconfirmed: false,
+                                same_editor_tab: false,
+                            })
+                            .collect(),
+                    });
+                }
+            }
+        });
+        let proxy = self.common.proxy.clone();
+        self.common.proxy.get_definition(
+            offset,
+            path.clone(),
+            position,
+            move |result| {
+                if let Ok(ProxyResponse::GetDefinitionResponse {
+                    definition, ..
+                }) = result
+                {
+                    if let Some(location) = match definition {
+                        GotoDefinitionResponse::Scalar(location) => Some(location),
+                        GotoDefinitionResponse::Array(locations) => {
+                            if !locations.is_empty() {
+                                Some(locations[0].clone())
+                            } else {
+                                None
+                            }
+                        }
+                        GotoDefinitionResponse::Link(location_links) => {
+                            let location_link = location_links[0].clone();
+                            Some(Location {
+                                uri: location_link.target_uri,
+                                range: location_link.target_selection_range,
+                            })
+                        }
+                    } {
+                        if location.range.start == start_position {
+                            proxy.get_references(
+                                path.clone(),
+                                position,
+                                move |result| {
+                                    if let Ok(
+                                        ProxyResponse::GetReferencesResponse {
+                                            references,
+                                        },
+                                    ) = result
+                                    {
+                                        if references.is_empty() {
+                                            return;
+                                        }
+                                        if references.len() == 1 {
+                                            let location = &references[0];
+                                            send(DefinitionOrReferece::Location(
+                                                EditorLocation {
+                                                    path: path_from_url(
+                                                        &location.uri,
+                                                    ),
+                                                    position: Some(
+                                                        EditorPosition::Position(
+                                                            location.range.start,
+                                                        ),
+                                                    ),
+                                                    scroll_offset: None,
+                                                    ignore_unconfirmed: false,
+                                                    same_editor_tab: false,
+                                                },
+                                            ));
+                                        } else {
+                                            send(DefinitionOrReferece::References(
+                                                references,
+                                            ));
+                                        }
+                                    }
+                                },
+                            );
+                        } else {
+                            let path = path_from_url(&location.uri);
+                            send(DefinitionOrReferece::Location(EditorLocation {
+                                path,
+                                position: Some(EditorPosition::Position(
+                                    location.range.start,
+                                )),
+                                scroll_offset: None,
+                                ignore_unconfirmed: false,
+                                same_editor_tab: false,
+                            }));
+                        }
+                    }
+                }
+            },
+        );
+    }
+
+    fn page_move(&self, down: bool, mods: ModifiersState) {
+        let config = self.common.config.get_untracked();
+        let viewport = self.viewport.get_untracked();
+        let line_height = config.editor.line_height() as f64;
+        let lines = (viewport.height() / line_height / 2.0).round() as usize;
+        let distance = (lines as f64) * line_height;
+        self.scroll_delta
+            .set(Vec2::new(0.0, if down { distance } else { -distance }));
+        self.run_move_command(
+            if down {
+                &lapce_core::movement::Movement::Down
+            } else {
+                &lapce_core::movement::Movement::Up
+            },
+            Some(lines),
+            mods,
         );
     }
 
@@ -1053,7 +1791,7 @@ fn scroll(&self, down: bool, count: usize, mods: ModifiersState) {
         };
     }
 
-    fn select_completion(&self) {
+    pub fn select_completion(&self) {
         let item = self
             .common
             .completion
@@ -1104,6 +1842,7 @@ fn select_completion(&self) {
     }
 
     pub fn cancel_completion(&self) {
+        self.completion_timer.set(TimerToken::INVALID);
         if self.common.completion.with_untracked(|c| c.status)
             == CompletionStatus::Inactive
         {
@@ -1119,6 +1858,12 @@ pub fn cancel_completion(&self) {
     /// Update the displayed autocompletion box
     /// Sends a request to the LSP for completion information
     fn update_completion(&self, display_if_empty_input: bool) {
+        self.request_completion(display_if_empty_input, !display_if_empty_input);
+    }
+
+    /// Show the completions at the cursor, waiting for typing to pause first
+    /// if `debounce` and a new completion would be started.
+    fn request_completion(&self, display_if_empty_input: bool, debounce: bool) {
         if self.get_mode() != Mode::Insert {
             self.cancel_completion();
             return;
@@ -1148,19 +1893,33 @@ fn update_completion(&self, display_if_empty_input: bool) {
             };
             (start_offset, input, char)
         });
-        if !display_if_empty_input && input.is_empty() && char != "." && char != ":"
-        {
-            self.cancel_completion();
-            return;
-        }
-
-        if self.common.completion.with_untracked(|completion| {
+        let config = self.common.config.get_untracked();
+        let active = self.common.completion.with_untracked(|completion| {
             completion.status != CompletionStatus::Inactive
                 && completion.offset == start_offset
                 && completion.path == path
-        }) {
+        });
+        if !display_if_empty_input {
+            let language = doc.syntax.with_untracked(|syntax| syntax.language);
+            let triggered = config
+                .editor
+                .completion_trigger_characters(language)
+                .iter()
+                .any(|trigger| trigger == &char);
+            let min_length = config.editor.completion_min_prefix_length.max(1);
+            if (!active && !config.editor.completion_on_typing)
+                || (!triggered && input.chars().count() < min_length)
+            {
+                self.cancel_completion();
+                return;
+            }
+        }
+
+        if active {
             self.common.completion.update(|completion| {
                 completion.update_input(input.clone());
+                completion.auto_insert = display_if_empty_input
+                    && config.editor.completion_auto_insert_single;
 
                 let cursor_offset = self.cursor.with_untracked(|c| c.offset());
                 completion.update_document_completion(&self.view, cursor_offset);
@@ -1194,11 +1953,27 @@ fn update_completion(&self, display_if_empty_input: bool) {
             return;
         }
 
-        let doc = self.view.doc.get_untracked();
+        if let Some(delay) = config.editor.completion_debounce().filter(|_| debounce)
+        {
+            let editor = self.clone();
+            let completion_timer = self.completion_timer;
+            let timer_token = exec_after(delay, move |token| {
+                if completion_timer.try_get_untracked() == Some(token)
+                    && editor.editor_tab_id.try_get_untracked().is_some()
+                {
+                    editor.request_completion(false, false);
+                }
+            });
+            completion_timer.set(timer_token);
+            return;
+        }
+
         self.common.completion.update(|completion| {
             completion.path = path.clone();
             completion.offset = start_offset;
             completion.input = input.clone();
+            completion.auto_insert = display_if_empty_input
+                && config.editor.completion_auto_insert_single;
             completion.status = CompletionStatus::Started;
             completion.input_items.clear();
             completion.request_id += 1;
@@ -1325,6 +2100,9 @@ fn completion_apply_snippet(
         additional_edit: Vec<(Selection, &str)>,
         start_offset: usize,
     ) -> Result<()> {
+        if self.read_only.get_untracked() {
+            return Err(anyhow::anyhow!("the editor is read only"));
+        }
         let snippet = Snippet::from_str(snippet)?;
         let text = snippet.text();
         let mut cursor = self.cursor.get_untracked();
@@ -1411,6 +2189,9 @@ fn do_edit(
         selection: &Selection,
         edits: &[(impl AsRef<Selection>, &str)],
     ) {
+        if self.read_only.get_untracked() {
+            return;
+        }
         let mut cursor = self.cursor.get_untracked();
         let doc = self.view.doc.get_untracked();
         let (delta, inval_lines, edits) =
@@ -1454,6 +2235,16 @@ pub fn do_text_edit(&self, edits: &[TextEdit]) {
         self.do_edit(&selection, &edits);
     }
 
+    /// Insert the text an input method composed in place of its preedit text, as
+    /// an undo group of its own rather than merged into the typing around it.
+    pub fn commit_ime(&self, text: &str) {
+        let doc = self.view.doc.get_untracked();
+        doc.clear_preedit();
+        doc.buffer.update(|buffer| buffer.reset_edit_type());
+        self.receive_char(text);
+        doc.buffer.update(|buffer| buffer.reset_edit_type());
+    }
+
     fn apply_deltas(&self, deltas: &[(RopeDelta, InvalLines, SyntaxEdit)]) {
         if !deltas.is_empty() && !self.confirmed.get_untracked() {
             self.confirmed.set(true);
@@ -1564,6 +2355,32 @@ pub fn go_to_position(
     }
 
     pub fn get_code_actions(&self) {
+        let offset = self.cursor.with_untracked(|c| c.offset());
+        self.code_actions_at(offset, |_| {});
+    }
+
+    /// Request the code actions at the cursor once it rests, so the lightbulb
+    /// shows wherever it was moved to, by a click as well.
+    pub fn schedule_code_actions(&self) {
+        let editor = self.clone();
+        let timer = self.code_actions_timer;
+        let token = exec_after(CODE_ACTIONS_DELAY, move |token| {
+            if timer.try_get_untracked() == Some(token) {
+                editor.get_code_actions();
+            }
+        });
+        timer.set(token);
+    }
+
+    /// Request the code actions at `offset`, unless they were already, and call
+    /// `f` with them once the language server answers. `f` gets no code actions
+    /// if there's no language server to ask, and the ones there are so far if
+    /// they were already requested.
+    fn code_actions_at(
+        &self,
+        offset: usize,
+        f: impl FnOnce(Option<Arc<(PluginId, Vec<CodeActionOrCommand>)>>) + 'static,
+    ) {
         let doc = self.view.doc.get_untracked();
         let path = match if doc.loaded() {
             doc.content.with_untracked(|c| c.path().cloned())
@@ -1571,13 +2388,15 @@ pub fn get_code_actions(&self) {
             None
         } {
             Some(path) => path,
-            None => return,
+            None => {
+                f(None);
+                return;
+            }
         };
 
-        let offset = self.cursor.with_untracked(|c| c.offset());
-        let exists = doc.code_actions.with_untracked(|c| c.contains_key(&offset));
-
-        if exists {
+        let existing = doc.code_actions.with_untracked(|c| c.get(&offset).cloned());
+        if let Some(existing) = existing {
+            f(Some(existing));
             return;
         }
 
@@ -1608,12 +2427,32 @@ pub fn get_code_actions(&self) {
             (position, rev, diagnostics)
         });
 
+        // Corrections of a misspelled word at the offset come first
+        let mut spelling = spell::code_actions(
+            &doc,
+            &self.common.spell_dictionary.get_untracked(),
+            &self.common.workspace,
+            offset,
+        );
         let send = create_ext_action(self.scope, move |resp| {
+            let resp = match resp {
+                Some((plugin_id, actions)) => {
+                    spelling.extend(actions);
+                    (plugin_id, spelling)
+                }
+                None if !spelling.is_empty() => (PluginId(0), spelling),
+                None => {
+                    f(None);
+                    return;
+                }
+            };
+            let resp = Arc::new(resp);
             if doc.rev() == rev {
                 doc.code_actions.update(|c| {
-                    c.insert(offset, Arc::new(resp));
+                    c.insert(offset, resp.clone());
                 });
             }
+            f(Some(resp));
         });
 
         self.common.proxy.get_code_actions(
@@ -1626,7 +2465,9 @@ pub fn get_code_actions(&self) {
                     resp,
                 }) = result
                 {
-                    send((plugin_id, resp))
+                    send(Some((plugin_id, resp)))
+                } else {
+                    send(None)
                 }
             },
         );
@@ -1650,62 +2491,393 @@ pub fn show_code_actions(&self, mouse_click: bool) {
         }
     }
 
-    fn do_save(&self, after_action: impl Fn() + 'static) {
-        self.view.doc.get_untracked().save(after_action);
+    fn organize_imports(&self) {
+        let doc = self.view.doc.get_untracked();
+        let len = doc.buffer.with_untracked(|buffer| buffer.len());
+        let internal_command = self.common.internal_command;
+        self.code_action_of_kind(
+            0..len,
+            CodeActionKind::SOURCE_ORGANIZE_IMPORTS,
+            |_| true,
+            move |plugin_id, action| {
+                internal_command.send(InternalCommand::RunCodeAction {
+                    plugin_id,
+                    action: CodeActionOrCommand::CodeAction(action),
+                });
+            },
+        );
+    }
+
+    /// Run the refactor of `kind` for the selection, or for the cursor when
+    /// nothing is selected. Servers mostly use the same kind for every
+    /// extraction, so the action is picked by one of `words` in its title when
+    /// there are any. The symbol an extraction creates is renamed afterwards.
+    fn refactor_selection(
+        &self,
+        kind: CodeActionKind,
+        words: &'static [&'static str],
+    ) {
+        let doc = self.view.doc.get_untracked();
+        let (start, end) = doc.buffer.with_untracked(|buffer| {
+            self.cursor.with_untracked(|c| match &c.mode {
+                CursorMode::Normal(offset) => (*offset, *offset),
+                _ => c
+                    .edit_selection(buffer)
+                    .regions()
+                    .first()
+                    .map_or((c.offset(), c.offset()), |r| (r.min(), r.max())),
+            })
+        });
+        let rename = kind == CodeActionKind::REFACTOR_EXTRACT;
+        let filter = move |action: &CodeAction| {
+            let title = action.title.to_lowercase();
+            words.is_empty() || words.iter().any(|word| title.contains(word))
+        };
+
+        let editor = self.clone();
+        let proxy = self.common.proxy.clone();
+        let scope = self.scope;
+        self.code_action_of_kind(
+            start..end,
+            kind,
+            filter,
+            move |plugin_id, action| {
+                if let Some(edit) = action.edit.as_ref() {
+                    editor.apply_refactor(edit, start, rename);
+                    return;
+                }
+                let send = create_ext_action(scope, move |edit| {
+                    editor.apply_refactor(&edit, start, rename);
+                });
+                proxy.code_action_resolve(action, plugin_id, move |result| {
+                    if let Ok(ProxyResponse::CodeActionResolveResponse { item }) =
+                        result
+                    {
+                        if let Some(edit) = item.edit {
+                            send(edit);
+                        }
+                    }
+                });
+            },
+        );
+    }
+
+    /// Apply the edit of a refactor, and when `rename` is set start renaming
+    /// what ends up where `offset` was, which is where an extraction puts the
+    /// name of the new function or variable.
+    fn apply_refactor(&self, edit: &WorkspaceEdit, offset: usize, rename: bool) {
+        let doc = self.view.doc.get_untracked();
+        let Some(path) = doc.content.with_untracked(|c| c.path().cloned()) else {
+            return;
+        };
+        let Some(mut edits) = workspace_edits(edit) else {
+            return;
+        };
+        let local = Url::from_file_path(&path)
+            .ok()
+            .and_then(|url| edits.remove(&url))
+            .unwrap_or_default();
+        if !edits.is_empty() {
+            self.common
+                .internal_command
+                .send(InternalCommand::ApplyWorkspaceEdit {
+                    edit: WorkspaceEdit {
+                        changes: Some(edits),
+                        ..Default::default()
+                    },
+                });
+        }
+        if local.is_empty() {
+            return;
+        }
+
+        let offset = doc.buffer.with_untracked(|buffer| {
+            let edits = local
+                .iter()
+                .map(|edit| {
+                    (
+                        buffer.offset_of_position(&edit.range.start),
+                        buffer.offset_of_position(&edit.range.end),
+                        edit.new_text.len(),
+                    )
+                })
+                .collect();
+            offset_after_edits(offset, edits)
+        });
+        self.do_text_edit(&local);
+        if rename {
+            self.cursor
+                .update(|cursor| cursor.set_offset(offset, false, false));
+            self.rename();
+        }
+    }
+
+    /// Ask for the code actions of `kind` for the range of the document, and call
+    /// `f` with the first one `filter` accepts.
+    fn code_action_of_kind(
+        &self,
+        range: std::ops::Range<usize>,
+        kind: CodeActionKind,
+        filter: impl Fn(&CodeAction) -> bool + Send + 'static,
+        f: impl FnOnce(PluginId, CodeAction) + 'static,
+    ) {
+        let doc = self.view.doc.get_untracked();
+        let Some(path) = doc.content.with_untracked(|c| c.path().cloned()) else {
+            return;
+        };
+        let (range, diagnostics) = doc.buffer.with_untracked(|buffer| {
+            let range = lsp_types::Range {
+                start: buffer.offset_to_position(range.start),
+                end: buffer.offset_to_position(range.end),
+            };
+            let diagnostics = doc.diagnostics.diagnostics.with_untracked(|d| {
+                d.iter()
+                    .map(|d| &d.diagnostic)
+                    .filter(|d| {
+                        d.range.start <= range.end && d.range.end >= range.start
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>()
+            });
+            (range, diagnostics)
+        });
+
+        let send = create_ext_action(self.scope, move |action| {
+            if let Some((plugin_id, action)) = action {
+                f(plugin_id, action);
+            }
+        });
+        self.common.proxy.get_range_code_actions(
+            path,
+            range,
+            diagnostics,
+            Some(vec![kind.clone()]),
+            move |result| {
+                let Ok(ProxyResponse::GetCodeActionsResponse { plugin_id, resp }) =
+                    result
+                else {
+                    send(None);
+                    return;
+                };
+                let action = resp
+                    .into_iter()
+                    .filter_map(|action| match action {
+                        CodeActionOrCommand::CodeAction(action) => Some(action),
+                        CodeActionOrCommand::Command(_) => None,
+                    })
+                    .find(|action| {
+                        is_code_action_kind(action, &kind) && filter(action)
+                    });
+                send(action.map(|action| (plugin_id, action)));
+            },
+        );
+    }
+
+    fn do_save(
+        &self,
+        after_action: impl Fn() + 'static,
+        failed: impl Fn() + 'static,
+    ) {
+        self.apply_whitespace_on_save();
+        self.view.doc.get_untracked().save(after_action, failed);
+    }
+
+    fn apply_whitespace_on_save(&self) {
+        let config = self.common.config.get_untracked();
+        let editor_config = &config.editor;
+        if !editor_config.trim_trailing_whitespace
+            && !editor_config.insert_final_newline
+            && !editor_config.trim_final_newlines
+        {
+            return;
+        }
+
+        let doc = self.view.doc.get_untracked();
+        let cursor = self.cursor.get_untracked();
+        let (selection, edits) = doc.buffer.with_untracked(|buffer| {
+            let keep_line = editor_config
+                .trim_trailing_whitespace_skip_cursor_line
+                .then(|| buffer.line_of_offset(cursor.offset()));
+            let edits = buffer.whitespace_edits_on_save(
+                editor_config.trim_trailing_whitespace,
+                keep_line,
+                editor_config.insert_final_newline,
+                editor_config.trim_final_newlines,
+            );
+            (cursor.edit_selection(buffer), edits)
+        });
+        if !edits.is_empty() {
+            self.do_edit(&selection, &edits);
+        }
+    }
+
+    /// Save the document, and then call `after_action`, or `failed` if it
+    /// wasn't saved.
+    pub fn save(
+        &self,
+        allow_formatting: bool,
+        after_action: impl Fn() + 'static + Copy,
+        failed: impl Fn() + 'static + Copy,
+    ) {
+        let doc = self.view.doc.get_untracked();
+        let is_pristine = doc.is_pristine();
+        let content = doc.content.get_untracked();
+
+        if let DocContent::Scratch { .. } = &content {
+            self.common
+                .internal_command
+                .send(InternalCommand::SaveDocAs { doc });
+            return;
+        }
+
+        if content.path().is_some() && is_pristine {
+            // Saved already
+            after_action();
+            return;
+        }
+
+        let config = self.common.config.get_untracked();
+        if let DocContent::File { path, .. } = content {
+            let kinds = if allow_formatting {
+                let language = doc.syntax.with_untracked(|syntax| syntax.language);
+                config.editor.code_actions_on_save(language).to_vec()
+            } else {
+                Vec::new()
+            };
+            let editor = self.clone();
+            self.run_code_actions_on_save(path.clone(), kinds, move || {
+                editor.format_and_save(path, allow_formatting, after_action, failed);
+            });
+        } else {
+            failed();
+        }
+    }
+
+    /// Run the code actions of each kind in turn, each on the text the one before
+    /// left, and apply their edits to this document. Then call `f`.
+    fn run_code_actions_on_save(
+        &self,
+        path: PathBuf,
+        mut kinds: Vec<String>,
+        f: impl FnOnce() + 'static,
+    ) {
+        if kinds.is_empty() {
+            f();
+            return;
+        }
+        let kind = CodeActionKind::from(kinds.remove(0));
+        let doc = self.view.doc.get_untracked();
+        let rev = doc.rev();
+        let range = doc.buffer.with_untracked(|buffer| lsp_types::Range {
+            start: buffer.offset_to_position(0),
+            end: buffer.offset_to_position(buffer.len()),
+        });
+        let diagnostics = doc.diagnostics.diagnostics.with_untracked(|d| {
+            d.iter().map(|d| d.diagnostic.clone()).collect::<Vec<_>>()
+        });
+
+        let editor = self.clone();
+        let next_path = path.clone();
+        let send =
+            create_ext_action(self.scope, move |edits: Option<Vec<TextEdit>>| {
+                if let Some(edits) = edits {
+                    if editor.view.doc.with_untracked(|doc| doc.rev()) == rev {
+                        editor.do_text_edit(&edits);
+                    }
+                }
+                editor.run_code_actions_on_save(next_path, kinds, f);
+            });
+
+        let proxy = self.common.proxy.clone();
+        std::thread::spawn(move || {
+            let timeout = Duration::from_secs(1);
+            let (tx, rx) = crossbeam_channel::bounded(1);
+            proxy.get_range_code_actions(
+                path.clone(),
+                range,
+                diagnostics,
+                Some(vec![kind.clone()]),
+                move |result| {
+                    let _ = tx.send(result);
+                },
+            );
+            let Ok(Ok(ProxyResponse::GetCodeActionsResponse { plugin_id, resp })) =
+                rx.recv_timeout(timeout)
+            else {
+                send(None);
+                return;
+            };
+            // Commands are left out, as what they do can't be waited for
+            let action = resp
+                .into_iter()
+                .filter_map(|action| match action {
+                    CodeActionOrCommand::CodeAction(action) => Some(action),
+                    CodeActionOrCommand::Command(_) => None,
+                })
+                .find(|action| is_code_action_kind(action, &kind));
+            let Some(mut action) = action else {
+                send(None);
+                return;
+            };
+            if action.edit.is_none() {
+                let (tx, rx) = crossbeam_channel::bounded(1);
+                proxy.code_action_resolve(
+                    action.clone(),
+                    plugin_id,
+                    move |result| {
+                        let _ = tx.send(result);
+                    },
+                );
+                if let Ok(Ok(ProxyResponse::CodeActionResolveResponse { item })) =
+                    rx.recv_timeout(timeout)
+                {
+                    action = *item;
+                }
+            }
+            let edits = action
+                .edit
+                .as_ref()
+                .and_then(workspace_edits)
+                .zip(Url::from_file_path(&path).ok())
+                .and_then(|(mut edits, url)| edits.remove(&url));
+            send(edits);
+        });
     }
 
-    pub fn save(
+    fn format_and_save(
         &self,
+        path: PathBuf,
         allow_formatting: bool,
         after_action: impl Fn() + 'static + Copy,
+        failed: impl Fn() + 'static + Copy,
     ) {
-        let doc = self.view.doc.get_untracked();
-        let rev = doc.rev();
-        let is_pristine = doc.is_pristine();
-        let content = doc.content.get_untracked();
-
-        if let DocContent::Scratch { .. } = &content {
-            self.common
-                .internal_command
-                .send(InternalCommand::SaveScratchDoc { doc });
-            return;
-        }
-
-        if content.path().is_some() && is_pristine {
+        let config = self.common.config.get_untracked();
+        if !allow_formatting || !config.editor.format_on_save {
+            self.do_save(after_action, failed);
             return;
         }
 
-        let config = self.common.config.get_untracked();
-        if let DocContent::File { path, .. } = content {
-            let format_on_save = allow_formatting && config.editor.format_on_save;
-            if format_on_save {
-                let editor = self.clone();
-                let send = create_ext_action(self.scope, move |result| {
-                    if let Ok(Ok(ProxyResponse::GetDocumentFormatting { edits })) =
-                        result
-                    {
-                        let current_rev =
-                            editor.view.doc.with_untracked(|doc| doc.rev());
-                        if current_rev == rev {
-                            editor.do_text_edit(&edits);
-                        }
-                    }
-                    editor.do_save(after_action);
-                });
-
-                let (tx, rx) = crossbeam_channel::bounded(1);
-                let proxy = self.common.proxy.clone();
-                std::thread::spawn(move || {
-                    proxy.get_document_formatting(path, move |result| {
-                        let _ = tx.send(result);
-                    });
-                    let result = rx.recv_timeout(std::time::Duration::from_secs(1));
-                    send(result);
-                });
-            } else {
-                self.do_save(after_action);
+        let rev = self.view.doc.with_untracked(|doc| doc.rev());
+        let editor = self.clone();
+        let send = create_ext_action(self.scope, move |result| {
+            if let Ok(Ok(ProxyResponse::GetDocumentFormatting { edits })) = result {
+                let current_rev = editor.view.doc.with_untracked(|doc| doc.rev());
+                if current_rev == rev {
+                    editor.do_text_edit(&edits);
+                }
             }
-        }
+            editor.do_save(after_action, failed);
+        });
+
+        let (tx, rx) = crossbeam_channel::bounded(1);
+        let proxy = self.common.proxy.clone();
+        std::thread::spawn(move || {
+            proxy.get_document_formatting(path, move |result| {
+                let _ = tx.send(result);
+            });
+            let result = rx.recv_timeout(std::time::Duration::from_secs(1));
+            send(result);
+        });
     }
 
     fn search_whole_word_forward(&self, mods: ModifiersState) {
@@ -1741,7 +2913,8 @@ fn search_forward(&self, mods: ModifiersState) {
             .get_untracked()
             .buffer
             .with_untracked(|buffer| buffer.text().clone());
-        let next = self.common.find.next(&text, offset, false, true);
+        let next = self.find_next(&text, offset, false);
+        self.add_search_history();
 
         if let Some((start, _end)) = next {
             self.run_move_command(
@@ -1752,6 +2925,26 @@ fn search_forward(&self, mods: ModifiersState) {
         }
     }
 
+    /// Move to the next or previous changed block, either of the diff editor this
+    /// is part of, or of the file compared to its git HEAD.
+    fn go_to_diff_hunk(&self, forward: bool, mods: ModifiersState) {
+        let doc = self.view.doc.get_untracked();
+        let (changes, is_right) = match self.view.kind.get_untracked() {
+            EditorViewKind::Diff(diff_info) => {
+                (diff_info.changes, diff_info.is_right)
+            }
+            EditorViewKind::Normal => {
+                (doc.head_changes.get_untracked().into_iter().collect(), true)
+            }
+        };
+        let offset = self.cursor.with_untracked(|c| c.offset());
+        let line = doc.buffer.with_untracked(|b| b.line_of_offset(offset));
+        if let Some(line) = next_diff_hunk_line(&changes, is_right, line, forward) {
+            let offset = doc.buffer.with_untracked(|b| b.offset_of_line(line));
+            self.run_move_command(&Movement::Offset(offset), None, mods);
+        }
+    }
+
     fn search_backward(&self, mods: ModifiersState) {
         let offset = self.cursor.with_untracked(|c| c.offset());
         let text = self
@@ -1760,7 +2953,8 @@ fn search_backward(&self, mods: ModifiersState) {
             .get_untracked()
             .buffer
             .with_untracked(|buffer| buffer.text().clone());
-        let next = self.common.find.next(&text, offset, true, true);
+        let next = self.find_next(&text, offset, true);
+        self.add_search_history();
 
         if let Some((start, _end)) = next {
             self.run_move_command(
@@ -1771,7 +2965,56 @@ fn search_backward(&self, mods: ModifiersState) {
         }
     }
 
+    /// The next or previous occurrence of what is searched for from the offset,
+    /// going around at the end. Only the selection the search is limited to is
+    /// searched, if it is.
+    fn find_next(
+        &self,
+        text: &Rope,
+        offset: usize,
+        reverse: bool,
+    ) -> Option<(usize, usize)> {
+        let scope = self.view.doc.get_untracked().find_result.scope;
+        scope.with_untracked(|scope| match scope {
+            Some(scope) => self
+                .common
+                .find
+                .next_in_scope(text, scope, offset, reverse, true),
+            None => self.common.find.next(text, offset, reverse, true),
+        })
+    }
+
+    /// Limit the search to the selection, or search the whole document again if
+    /// it was limited.
+    pub fn toggle_find_in_selection(&self) {
+        let doc = self.view.doc.get_untracked();
+        if doc.find_result.scope.with_untracked(Option::is_some) {
+            doc.set_find_scope(None);
+            return;
+        }
+        let selection = doc.buffer.with_untracked(|buffer| {
+            self.cursor.with_untracked(|c| c.edit_selection(buffer))
+        });
+        let mut scope = Selection::new();
+        for region in selection.regions() {
+            if !region.is_caret() {
+                scope.add_region(*region);
+            }
+        }
+        if !scope.is_empty() {
+            doc.set_find_scope(Some(scope));
+        }
+    }
+
+    /// Remember what is searched for, to step back to it from the find bar.
+    fn add_search_history(&self) {
+        if let Some(search_string) = self.common.find.search_string.get_untracked() {
+            self.common.search_history.add(&search_string.content);
+        }
+    }
+
     fn replace_next(&self, text: &str) {
+        self.common.replace_history.add(text);
         let offset = self.cursor.with_untracked(|c| c.offset());
         let buffer = self
             .view
@@ -1779,7 +3022,7 @@ fn replace_next(&self, text: &str) {
             .get_untracked()
             .buffer
             .with_untracked(|buffer| buffer.clone());
-        let next = self.common.find.next(buffer.text(), offset, false, true);
+        let next = self.find_next(buffer.text(), offset, false);
 
         if let Some((start, end)) = next {
             let selection = Selection::region(start, end);
@@ -1788,6 +3031,7 @@ fn replace_next(&self, text: &str) {
     }
 
     fn replace_all(&self, text: &str) {
+        self.common.replace_history.add(text);
         let offset = self.cursor.with_untracked(|c| c.offset());
 
         self.view.update_find();
@@ -1958,6 +3202,10 @@ pub fn word_at_cursor(&self) -> String {
     pub fn clear_search(&self) {
         self.common.find.visual.set(false);
         self.find_focus.set(false);
+        let doc = self.view.doc.get_untracked();
+        if doc.find_result.scope.with_untracked(Option::is_some) {
+            doc.set_find_scope(None);
+        }
     }
 
     fn search(&self) {
@@ -1978,6 +3226,16 @@ fn search(&self) {
     }
 
     pub fn pointer_down(&self, pointer_event: &PointerInputEvent) {
+        if self.read_only.get_untracked() {
+            // Only the cursor can be moved, without a menu or a paste
+            if let PointerButton::Primary = pointer_event.button {
+                if !self.click_scroll_bar(pointer_event) {
+                    self.active.set(true);
+                    self.left_click(pointer_event);
+                }
+            }
+            return;
+        }
         if let Some(editor_tab_id) = self.editor_tab_id.get_untracked() {
             self.common
                 .internal_command
@@ -1995,17 +3253,216 @@ pub fn pointer_down(&self, pointer_event: &PointerInputEvent) {
         }
         match pointer_event.button {
             PointerButton::Primary => {
+                if self.click_scroll_bar(pointer_event)
+                    || self.click_link(pointer_event)
+                {
+                    return;
+                }
                 self.active.set(true);
+                self.mark_active.set(false);
                 self.left_click(pointer_event);
             }
             PointerButton::Secondary => {
                 self.right_click(pointer_event);
             }
+            #[cfg(target_os = "linux")]
+            PointerButton::Auxiliary => {
+                self.middle_click(pointer_event);
+            }
             _ => {}
         }
     }
 
+    /// Follow the link that was clicked while holding the modifier for it. Returns
+    /// `false` if no link was clicked.
+    fn click_link(&self, pointer_event: &PointerInputEvent) -> bool {
+        if !follows_links(pointer_event.modifiers) {
+            return false;
+        }
+        let mode = self.cursor.with_untracked(|c| c.get_mode());
+        let (offset, is_inside) = self.view.offset_of_point(mode, pointer_event.pos);
+        let Some(link) = is_inside.then(|| self.link_at(offset)).flatten() else {
+            return false;
+        };
+        self.hovered_link.set(None);
+        self.open_link(&link.target);
+        true
+    }
+
+    /// The test or binary the buttons on the line of the offset run.
+    fn runnable_on_line_of(&self, offset: usize) -> Option<Runnable> {
+        let doc = self.view.doc.get_untracked();
+        let line = doc.buffer.with_untracked(|b| b.line_of_offset(offset));
+        doc.runnables.with_untracked(|runnables| {
+            runnables
+                .iter()
+                .find(|(offset, _)| {
+                    doc.buffer.with_untracked(|b| b.line_of_offset(*offset)) == line
+                })
+                .map(|(_, runnable)| runnable.clone())
+        })
+    }
+
+    /// The link at the offset, with its range in the document. The links the
+    /// language server found come first, and otherwise a path has to be of a
+    /// file that exists to be a link.
+    fn link_at(&self, offset: usize) -> Option<TextLink> {
+        let doc = self.view.doc.get_untracked();
+        let lsp_link = doc.document_links.with_untracked(|links| {
+            links
+                .iter()
+                .find(|(start, end, _)| *start <= offset && offset < *end)
+                .map(|(start, end, target)| TextLink {
+                    start: *start,
+                    end: *end,
+                    target: LinkTarget::Url(target.to_string()),
+                })
+        });
+        if lsp_link.is_some() {
+            return lsp_link;
+        }
+
+        let (line_start, text) = doc.buffer.with_untracked(|buffer| {
+            let line = buffer.line_of_offset(offset);
+            (
+                buffer.offset_of_line(line),
+                buffer.line_content(line).to_string(),
+            )
+        });
+        let link = link::link_at(&text, offset - line_start)?;
+        if let LinkTarget::Path { path, .. } = &link.target {
+            self.resolve_link_path(path)?;
+        }
+        Some(TextLink {
+            start: line_start + link.start,
+            end: line_start + link.end,
+            ..link
+        })
+    }
+
+    /// The file a path in the text is of: an absolute path, or one relative to
+    /// the workspace or to the directory of the document. The proxy is asked
+    /// which of them is a file the first time the path is seen, and until it
+    /// answers the path isn't a link.
+    fn resolve_link_path(&self, path: &str) -> Option<PathBuf> {
+        if let Some(file) = self
+            .link_paths
+            .with_untracked(|paths| paths.get(path).cloned())
+        {
+            return file;
+        }
+        self.link_paths.update(|paths| {
+            paths.insert(path.to_string(), None);
+        });
+
+        let written = PathBuf::from(path);
+        let candidates = if written.is_absolute() {
+            vec![written]
+        } else {
+            let doc_dir =
+                self.view.doc.get_untracked().content.with_untracked(|c| {
+                    c.path().and_then(|p| p.parent()).map(PathBuf::from)
+                });
+            self.common
+                .workspace
+                .path
+                .iter()
+                .chain(doc_dir.iter())
+                .map(|dir| dir.join(&written))
+                .collect()
+        };
+        let link_paths = self.link_paths;
+        let path = path.to_string();
+        let send = create_ext_action(self.scope, move |file: Option<PathBuf>| {
+            if file.is_some() {
+                link_paths.update(|paths| {
+                    paths.insert(path.clone(), file);
+                });
+            }
+        });
+        self.common.proxy.find_file(candidates, move |result| {
+            if let Ok(ProxyResponse::FindFileResponse { path }) = result {
+                send(path);
+            }
+        });
+        None
+    }
+
+    /// Open a URL in the browser, or a file in the editor at the line and column
+    /// the link has for it. A `file://` URI is opened in the editor too.
+    fn open_link(&self, target: &LinkTarget) {
+        let (path, line, col) = match target {
+            LinkTarget::Url(url) if url.starts_with("file://") => {
+                let (url, line, col) = link::split_line_col(url);
+                let Ok(url) = Url::parse(url) else {
+                    return;
+                };
+                // Like the `#L10` of a link to a line on GitHub
+                let line =
+                    line.or_else(|| url.fragment()?.strip_prefix('L')?.parse().ok());
+                (path_from_url(&url), line, col)
+            }
+            LinkTarget::Url(url) => {
+                self.common
+                    .internal_command
+                    .send(InternalCommand::OpenWebUri { uri: url.clone() });
+                return;
+            }
+            LinkTarget::Path { path, line, col } => {
+                let Some(path) = self.resolve_link_path(path) else {
+                    return;
+                };
+                (path, *line, *col)
+            }
+        };
+        let position = line.map(|line| {
+            EditorPosition::Position(Position {
+                line: line.saturating_sub(1) as u32,
+                character: col.unwrap_or(1).saturating_sub(1) as u32,
+            })
+        });
+        self.common
+            .internal_command
+            .send(InternalCommand::JumpToLocation {
+                location: EditorLocation {
+                    path,
+                    position,
+                    scroll_offset: None,
+                    ignore_unconfirmed: false,
+                    same_editor_tab: false,
+                },
+            });
+    }
+
+    /// Scroll to the part of the document that was clicked in the overview in the
+    /// scroll bar, centered.
+    fn click_scroll_bar(&self, pointer_event: &PointerInputEvent) -> bool {
+        let viewport = self.viewport.get_untracked();
+        let doc = self.view.doc.get_untracked();
+        if pointer_event.pos.x < viewport.x1 - SCROLL_BAR_WIDTH
+            || doc.content.with_untracked(|content| content.is_local())
+            || !self.view.kind.get_untracked().is_normal()
+        {
+            return false;
+        }
+
+        let config = self.common.config.get_untracked();
+        let last_line = doc.buffer.with_untracked(|buffer| buffer.last_line());
+        let content_height =
+            scroll_bar_content_height(last_line, viewport.height(), &config);
+        let y =
+            (pointer_event.pos.y - viewport.y0) / viewport.height() * content_height;
+        self.scroll_to.set(Some(Vec2::new(
+            viewport.x0,
+            (y - viewport.height() / 2.0).max(0.0),
+        )));
+        true
+    }
+
     fn left_click(&self, pointer_event: &PointerInputEvent) {
+        if self.click_sticky_header(pointer_event) {
+            return;
+        }
         match pointer_event.count {
             1 => {
                 self.single_click(pointer_event);
@@ -2020,7 +3477,60 @@ fn left_click(&self, pointer_event: &PointerInputEvent) {
         }
     }
 
+    /// Jump to the header of the enclosing scope that was clicked in the sticky
+    /// header. Returns `false` if the click wasn't in the sticky header.
+    fn click_sticky_header(&self, pointer_event: &PointerInputEvent) -> bool {
+        let config = self.common.config.get_untracked();
+        if !config.editor.sticky_header {
+            return false;
+        }
+        let y = pointer_event.pos.y - self.viewport.get_untracked().y0;
+        if y < 0.0 || y >= self.sticky_header_height.get_untracked() {
+            return false;
+        }
+        let index = (y / config.editor.line_height() as f64).floor() as usize;
+        let Some(line) = self
+            .sticky_header_lines
+            .with_untracked(|lines| lines.get(index).copied())
+        else {
+            return false;
+        };
+        let offset = self.view.first_non_blank_character_on_line(line);
+        self.run_move_command(
+            &Movement::Offset(offset),
+            None,
+            pointer_event.modifiers,
+        );
+        true
+    }
+
     fn single_click(&self, pointer_event: &PointerInputEvent) {
+        self.drag_unit.set(None);
+        match self.view.phantom_text_of_point(pointer_event.pos) {
+            Some((PhantomTextKind::ColorSwatch, offset)) => {
+                self.common
+                    .internal_command
+                    .send(InternalCommand::StartColorPicker { offset });
+                return;
+            }
+            Some((
+                kind @ (PhantomTextKind::Run | PhantomTextKind::Debug),
+                offset,
+            )) => {
+                let mode = if kind == PhantomTextKind::Run {
+                    RunDebugMode::Run
+                } else {
+                    RunDebugMode::Debug
+                };
+                if let Some(runnable) = self.runnable_on_line_of(offset) {
+                    self.common
+                        .internal_command
+                        .send(InternalCommand::RunRunnable { mode, runnable });
+                }
+                return;
+            }
+            _ => {}
+        }
         let mode = self.cursor.with_untracked(|c| c.get_mode());
         let (new_offset, _) = self.view.offset_of_point(mode, pointer_event.pos);
         self.cursor.update(|cursor| {
@@ -2036,6 +3546,7 @@ fn double_click(&self, pointer_event: &PointerInputEvent) {
         let mode = self.cursor.with_untracked(|c| c.get_mode());
         let (mouse_offset, _) = self.view.offset_of_point(mode, pointer_event.pos);
         let (start, end) = self.view.select_word(mouse_offset);
+        self.drag_unit.set(Some((DragUnit::Word, start, end)));
 
         self.cursor.update(|cursor| {
             cursor.add_region(
@@ -2053,6 +3564,7 @@ fn triple_click(&self, pointer_event: &PointerInputEvent) {
         let line = self.view.line_of_offset(mouse_offset);
         let start = self.view.offset_of_line(line);
         let end = self.view.offset_of_line(line + 1);
+        self.drag_unit.set(Some((DragUnit::Line, start, end)));
 
         self.cursor.update(|cursor| {
             cursor.add_region(
@@ -2067,12 +3579,17 @@ fn triple_click(&self, pointer_event: &PointerInputEvent) {
     pub fn pointer_move(&self, pointer_event: &PointerMoveEvent) {
         let mode = self.cursor.with_untracked(|c| c.get_mode());
         let (offset, is_inside) = self.view.offset_of_point(mode, pointer_event.pos);
-        if self.active.get_untracked()
-            && self.cursor.with_untracked(|c| c.offset()) != offset
-        {
-            self.cursor.update(|cursor| {
-                cursor.set_offset(offset, true, pointer_event.modifiers.alt_key())
-            });
+        let link = (is_inside && follows_links(pointer_event.modifiers))
+            .then(|| self.link_at(offset))
+            .flatten();
+        if self.hovered_link.with_untracked(|l| *l != link) {
+            self.hovered_link.set(link);
+        }
+        if self.active.get_untracked() {
+            let new_cursor = pointer_event.modifiers.alt_key();
+            self.drag_select(offset, new_cursor);
+            let pointer = pointer_event.pos - self.viewport.get_untracked().origin();
+            self.drag_scroll(pointer, new_cursor);
         }
         if self.common.hover.active.get_untracked() {
             let hover_editor_id = self.common.hover.editor_id.get_untracked();
@@ -2119,7 +3636,147 @@ pub fn pointer_move(&self, pointer_event: &PointerMoveEvent) {
     }
 
     pub fn pointer_up(&self, _pointer_event: &PointerInputEvent) {
+        #[cfg(target_os = "linux")]
+        if self.active.get_untracked() {
+            self.set_primary_selection();
+        }
         self.active.set(false);
+        self.drag_unit.set(None);
+        self.drag_scroll_timer.set(TimerToken::INVALID);
+    }
+
+    /// Extend the selection to the offset the primary button was dragged to, by
+    /// whole words or lines if a double or triple click started the drag.
+    fn drag_select(&self, offset: usize, new_cursor: bool) {
+        let Some((unit, anchor_start, anchor_end)) = self.drag_unit.get_untracked()
+        else {
+            if self.cursor.with_untracked(|c| c.offset()) != offset {
+                self.cursor
+                    .update(|cursor| cursor.set_offset(offset, true, new_cursor));
+            }
+            return;
+        };
+
+        let (start, end) = match unit {
+            DragUnit::Word => self.view.select_word(offset),
+            DragUnit::Line => {
+                let line = self.view.line_of_offset(offset);
+                (
+                    self.view.offset_of_line(line),
+                    self.view.offset_of_line(line + 1),
+                )
+            }
+        };
+        // The word or line the drag started on stays selected, and the cursor
+        // goes to the far end of the one under the pointer
+        let (from, to) = if start < anchor_start {
+            (anchor_end, start)
+        } else {
+            (anchor_start, end.max(anchor_end))
+        };
+        self.cursor.update(|cursor| match &cursor.mode {
+            CursorMode::Insert(selection) => {
+                let mut selection = selection.clone();
+                let region = SelRegion::new(from, to, None);
+                match selection.last_inserted_mut() {
+                    Some(last) => *last = region,
+                    None => selection.add_region(region),
+                }
+                cursor.set_insert(selection);
+            }
+            CursorMode::Normal(_) | CursorMode::Visual { .. } => {
+                // The end of a visual selection is on its last character
+                let (start, end) = if to >= from {
+                    (from, to.saturating_sub(1).max(from))
+                } else {
+                    (from.saturating_sub(1).max(to), to)
+                };
+                cursor.mode = CursorMode::Visual {
+                    start,
+                    end,
+                    mode: VisualMode::Normal,
+                };
+            }
+        });
+    }
+
+    /// Keep scrolling while a selection is dragged past the top or bottom of the
+    /// editor, faster the further past it the pointer is, and extend the
+    /// selection along. `pointer` is relative to the viewport, so it stays the
+    /// same while the text scrolls under a pointer that doesn't move.
+    fn drag_scroll(&self, pointer: Vec2, new_cursor: bool) {
+        let height = self.viewport.get_untracked().height();
+        let past = if pointer.y < 0.0 {
+            pointer.y
+        } else if pointer.y > height {
+            pointer.y - height
+        } else {
+            0.0
+        };
+        if past == 0.0 || !self.active.get_untracked() {
+            self.drag_scroll_timer.set(TimerToken::INVALID);
+            return;
+        }
+
+        let editor = self.clone();
+        let timer = self.drag_scroll_timer;
+        let token = exec_after(DRAG_SCROLL_INTERVAL, move |token| {
+            if timer.try_get_untracked() != Some(token)
+                || !editor.active.get_untracked()
+            {
+                return;
+            }
+            let delta = past.clamp(-MAX_DRAG_SCROLL, MAX_DRAG_SCROLL);
+            editor.scroll_delta.set(Vec2::new(0.0, delta));
+            let point = editor.viewport.get_untracked().origin()
+                + pointer
+                + Vec2::new(0.0, delta);
+            let mode = editor.cursor.with_untracked(|c| c.get_mode());
+            let (offset, _) = editor.view.offset_of_point(mode, point);
+            editor.drag_select(offset, new_cursor);
+            editor.drag_scroll(pointer, new_cursor);
+        });
+        timer.set(token);
+    }
+
+    /// Paste the primary selection where the middle button was clicked, like
+    /// other apps on Linux do.
+    #[cfg(target_os = "linux")]
+    fn middle_click(&self, pointer_event: &PointerInputEvent) {
+        let doc = self.view.doc.get_untracked();
+        if doc.is_read_only() {
+            return;
+        }
+        let Some(text) = PrimarySelection::new().get_string() else {
+            return;
+        };
+        let mode = self.cursor.with_untracked(|c| c.get_mode());
+        let (offset, _) = self.view.offset_of_point(mode, pointer_event.pos);
+        let selection = Selection::caret(offset);
+        self.do_edit(&selection, &[(&selection, text.as_str())]);
+    }
+
+    /// Make what the mouse selected the primary selection, which the middle
+    /// button pastes.
+    #[cfg(target_os = "linux")]
+    fn set_primary_selection(&self) {
+        let doc = self.view.doc.get_untracked();
+        let cursor = self.cursor.get_untracked();
+        let text = doc.buffer.with_untracked(|buffer| {
+            cursor
+                .edit_selection(buffer)
+                .regions()
+                .iter()
+                .filter(|region| !region.is_caret())
+                .map(|region| {
+                    buffer.slice_to_cow(region.min()..region.max()).to_string()
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        });
+        if !text.is_empty() {
+            PrimarySelection::new().put_string(text);
+        }
     }
 
     pub fn pointer_leave(&self) {
@@ -2140,51 +3797,69 @@ fn right_click(&self, pointer_event: &PointerInputEvent) {
         }
 
         let is_file = doc.content.with_untracked(|content| content.is_file());
-        let mut menu = Menu::new("");
-        let cmds = if is_file {
-            vec![
-                Some(CommandKind::Focus(FocusCommand::GotoDefinition)),
-                Some(CommandKind::Focus(FocusCommand::GotoTypeDefinition)),
-                None,
-                Some(CommandKind::Focus(FocusCommand::Rename)),
-                None,
-                Some(CommandKind::Edit(EditCommand::ClipboardCut)),
-                Some(CommandKind::Edit(EditCommand::ClipboardCopy)),
-                Some(CommandKind::Edit(EditCommand::ClipboardPaste)),
-                None,
-                Some(CommandKind::Workbench(
-                    LapceWorkbenchCommand::PaletteCommand,
-                )),
-            ]
-        } else {
-            vec![
-                Some(CommandKind::Edit(EditCommand::ClipboardCut)),
-                Some(CommandKind::Edit(EditCommand::ClipboardCopy)),
-                Some(CommandKind::Edit(EditCommand::ClipboardPaste)),
-                None,
-                Some(CommandKind::Workbench(
-                    LapceWorkbenchCommand::PaletteCommand,
-                )),
-            ]
-        };
         let lapce_command = self.common.lapce_command;
-        for cmd in cmds {
-            if let Some(cmd) = cmd {
-                menu = menu.entry(
-                    MenuItem::new(cmd.desc().unwrap_or_else(|| cmd.str())).action(
-                        move || {
-                            lapce_command.send(LapceCommand {
-                                kind: cmd.clone(),
-                                data: None,
-                            })
-                        },
-                    ),
-                );
-            } else {
-                menu = menu.separator();
+        let internal_command = self.common.internal_command;
+        let offset = self.cursor.with_untracked(|c| c.offset());
+        // The menu waits for the code actions at the click, which the language
+        // server is asked for now if it wasn't when the cursor got there
+        self.code_actions_at(offset, move |code_actions| {
+            let mut menu = ContextMenu::new(lapce_command);
+            if is_file {
+                menu = menu
+                    .command(CommandKind::Focus(FocusCommand::GotoDefinition))
+                    .command(CommandKind::Focus(FocusCommand::GotoTypeDefinition))
+                    .command(CommandKind::Focus(FocusCommand::FindReferences))
+                    .separator()
+                    .command(CommandKind::Focus(FocusCommand::Rename))
+                    .submenu(&tr("menu-refactor"), |submenu| {
+                        submenu
+                            .command(CommandKind::Focus(
+                                FocusCommand::ExtractFunction,
+                            ))
+                            .command(CommandKind::Focus(
+                                FocusCommand::ExtractVariable,
+                            ))
+                            .command(CommandKind::Focus(FocusCommand::InlineSymbol))
+                    })
+                    .submenu(&tr("menu-code-actions"), |mut submenu| {
+                        let Some(code_actions) = code_actions else {
+                            return submenu;
+                        };
+                        let plugin_id = code_actions.0;
+                        for action in code_actions.1.iter() {
+                            let title = match action {
+                                CodeActionOrCommand::Command(c) => &c.title,
+                                CodeActionOrCommand::CodeAction(a) => &a.title,
+                            };
+                            let action = action.clone();
+                            submenu = submenu.action(title.clone(), move || {
+                                internal_command.send(
+                                    InternalCommand::RunCodeAction {
+                                        plugin_id,
+                                        action: action.clone(),
+                                    },
+                                );
+                            });
+                        }
+                        submenu
+                    })
+                    .separator();
             }
-        }
-        show_context_menu(menu, None);
+            menu = menu
+                .command(CommandKind::Edit(EditCommand::ClipboardCut))
+                .command(CommandKind::Edit(EditCommand::ClipboardCopy))
+                .command(CommandKind::Edit(EditCommand::ClipboardPaste))
+                .separator();
+            if is_file {
+                menu = menu
+                    .command(CommandKind::Focus(FocusCommand::FormatDocument))
+                    .separator();
+            }
+            menu.command(CommandKind::Workbench(
+                LapceWorkbenchCommand::PaletteCommand,
+            ))
+            .show();
+        });
     }
 
     fn update_hover(&self, offset: usize) {
@@ -2431,6 +4106,11 @@ fn get_mode(&self) -> Mode {
         }
     }
 
+    fn language(&self) -> Option<&'static str> {
+        let doc = self.view.doc.get_untracked();
+        Some(doc.syntax.with_untracked(|syntax| syntax.language.name()))
+    }
+
     fn check_condition(&self, condition: Condition) -> bool {
         match condition {
             Condition::InputFocus => {
@@ -2439,7 +4119,14 @@ fn check_condition(&self, condition: Condition) -> bool {
             }
             Condition::ListFocus => self.has_completions(),
             Condition::CompletionFocus => self.has_completions(),
+            Condition::CompletionTabOnly => self
+                .common
+                .config
+                .with_untracked(|config| !config.editor.completion_accept_on_enter),
             Condition::InSnippet => self.snippet.with_untracked(|s| s.is_some()),
+            Condition::MotionMode => {
+                self.cursor.with_untracked(|c| c.motion_mode.is_some())
+            }
             Condition::EditorFocus => self
                 .view
                 .doc
@@ -2480,7 +4167,10 @@ fn run_command(
             match &command.kind {
                 CommandKind::Edit(_)
                 | CommandKind::Move(_)
-                | CommandKind::MultiSelection(_) => {
+                | CommandKind::MultiSelection(_)
+                | CommandKind::Focus(
+                    FocusCommand::HistoryPrevious | FocusCommand::HistoryNext,
+                ) => {
                     if self.common.find.replace_focus.get_untracked() {
                         self.common.internal_command.send(
                             InternalCommand::ReplaceEditorCommand {
@@ -2538,6 +4228,8 @@ fn expect_char(&self) -> bool {
             false
         } else {
             self.inline_find.with_untracked(|f| f.is_some())
+                || self.select_register.get_untracked()
+                || self.surround_input.with_untracked(|s| s.is_some())
         }
     }
 
@@ -2554,16 +4246,20 @@ fn receive_char(&self, c: &str) {
                     InternalCommand::FindEditorReceiveChar { s: c.to_string() },
                 );
             }
-        } else {
+        } else if !self.read_only.get_untracked() {
             // normal editor receive char
-            if self.get_mode() == Mode::Insert {
+            if let Some(input) = self.surround_input.get_untracked() {
+                self.receive_surround_char(input, c);
+            } else if self.get_mode() == Mode::Insert {
                 let mut cursor = self.cursor.get_untracked();
                 let config = self.common.config.get_untracked();
-                let deltas =
-                    self.view
-                        .doc
-                        .get_untracked()
-                        .do_insert(&mut cursor, c, &config);
+                let scroll = self.viewport.get_untracked().origin().to_vec2();
+                let deltas = self.view.doc.get_untracked().do_insert(
+                    &mut cursor,
+                    c,
+                    &config,
+                    scroll,
+                );
                 self.cursor.set(cursor);
 
                 if !c
@@ -2579,11 +4275,80 @@ fn receive_char(&self, c: &str) {
                 self.inline_find(direction.clone(), c);
                 self.last_inline_find.set(Some((direction, c.to_string())));
                 self.inline_find.set(None);
+            } else if self.select_register.get_untracked() {
+                self.select_register.set(false);
+                let mut chars = c.chars();
+                if let (Some(c), None) = (chars.next(), chars.next()) {
+                    if let Some(name) = RegisterName::from_char(c) {
+                        self.common
+                            .register
+                            .update(|register| register.select(name));
+                    }
+                }
             }
         }
     }
 }
 
+/// The occurrences of the text in the document, with the case matching.
+fn find_occurrences(text: &Rope, search: &str) -> Selection {
+    let mut occurrences = Selection::new();
+    let search = FindSearchString {
+        content: search.to_string(),
+        regex: None,
+    };
+    Find::find(
+        text,
+        &search,
+        0,
+        text.len(),
+        CaseMatching::Exact,
+        false,
+        false,
+        &mut occurrences,
+    );
+    occurrences
+}
+
+/// Whether the action is of the kind or a more specific kind of it, like
+/// `source.organizeImports.biome` is of `source.organizeImports`. Actions without
+/// a kind are taken to be of the kind that was asked for.
+fn is_code_action_kind(action: &CodeAction, kind: &CodeActionKind) -> bool {
+    action.kind.as_ref().map_or(true, |k| {
+        k.as_str() == kind.as_str()
+            || k.as_str().starts_with(&format!("{}.", kind.as_str()))
+    })
+}
+
+/// Whether the modifier that makes a click follow a link is held, which is Cmd
+/// on macOS and Ctrl elsewhere.
+fn follows_links(mods: ModifiersState) -> bool {
+    if cfg!(target_os = "macos") {
+        mods.super_key()
+    } else {
+        mods.control_key()
+    }
+}
+
+/// Where `offset` ends up after the edits, given as the start and end of the
+/// text each replaces and the length of its new text. An offset inside replaced
+/// text moves to the start of what replaces it.
+fn offset_after_edits(
+    offset: usize,
+    mut edits: Vec<(usize, usize, usize)>,
+) -> usize {
+    edits.sort();
+    let mut new_offset = offset;
+    for (start, end, len) in edits {
+        if end <= offset {
+            new_offset = new_offset - (end - start) + len;
+        } else if start < offset {
+            new_offset -= offset - start;
+        }
+    }
+    new_offset
+}
+
 /// Checks if completion should be triggered if the received command
 /// is one that inserts whitespace or deletes whitespace
 fn show_completion(
@@ -1,4 +1,4 @@
-use std::{rc::Rc, sync::atomic};
+use std::{collections::HashMap, ops::Range, rc::Rc, sync::atomic};
 
 use floem::{
     event::EventListener,
@@ -9,10 +9,15 @@
     views::{clip, empty, label, list, stack, svg, Decorators},
 };
 use lapce_core::buffer::{
-    diff::{expand_diff_lines, rope_diff, DiffExpand, DiffLines},
+    diff::{
+        expand_diff_lines, intra_line_changes, rope_diff, DiffExpand, DiffLines,
+    },
     rope_text::RopeText,
 };
-use lapce_rpc::{buffer::BufferId, proxy::ProxyResponse};
+use lapce_rpc::{
+    buffer::{BufferId, LineEnding},
+    proxy::ProxyResponse,
+};
 use lapce_xi_rope::Rope;
 use serde::{Deserialize, Serialize};
 
@@ -31,8 +36,17 @@
 pub struct DiffInfo {
     pub is_right: bool,
     pub changes: Vec<DiffLines>,
+    /// The changed columns of modified lines, keyed by line.
+    pub intra_line: HashMap<usize, Range<usize>>,
 }
 
+/// The diff lines, and the intra line changes of the left and right side.
+type DiffChanges = (
+    Vec<DiffLines>,
+    HashMap<usize, Range<usize>>,
+    HashMap<usize, Range<usize>>,
+);
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct DiffEditorInfo {
     pub left_content: DocContent,
@@ -67,21 +81,55 @@ pub fn to_data(
 
                     {
                         let doc = doc.clone();
-                        let send = create_ext_action(cx, move |result| {
-                            if let Ok(ProxyResponse::BufferHeadResponse {
-                                content,
-                                ..
-                            }) = result
-                            {
-                                doc.init_content(Rope::from(content));
-                            }
+                        let send = create_ext_action(cx, move |content: String| {
+                            doc.init_content(Rope::from(content));
                         });
-                        common.proxy.get_buffer_head(
-                            history.path.clone(),
-                            move |result| {
-                                send(result);
-                            },
-                        );
+                        let path = history.path.clone();
+                        match history.version.as_str() {
+                            "head" => {
+                                common.proxy.get_buffer_head(path, move |result| {
+                                    if let Ok(ProxyResponse::BufferHeadResponse {
+                                        content,
+                                        ..
+                                    }) = result
+                                    {
+                                        send(content);
+                                    }
+                                });
+                            }
+                            // The saved content, compared with the unsaved changes
+                            "disk" => {
+                                common.proxy.read_file(path, move |result| {
+                                    if let Ok(ProxyResponse::ReadFileResponse {
+                                        content,
+                                    }) = result
+                                    {
+                                        let content =
+                                            String::from_utf8_lossy(&content)
+                                                .to_string();
+                                        send(LineEnding::normalize(content));
+                                    }
+                                });
+                            }
+                            // Anything else is a commit from the file's history
+                            revision => {
+                                common.proxy.git_file_revision(
+                                    path,
+                                    revision.to_string(),
+                                    move |result| {
+                                        if let Ok(
+                                            ProxyResponse::BufferHeadResponse {
+                                                content,
+                                                ..
+                                            },
+                                        ) = result
+                                        {
+                                            send(content);
+                                        }
+                                    },
+                                );
+                            }
+                        }
                     }
 
                     doc
@@ -256,12 +304,13 @@ fn listen_diff_changes(&self) {
 
             let send = {
                 let right_atomic_rev = right_atomic_rev.clone();
-                create_ext_action(cx, move |changes: Option<Vec<DiffLines>>| {
-                    let changes = if let Some(changes) = changes {
-                        changes
-                    } else {
-                        return;
-                    };
+                create_ext_action(cx, move |changes: Option<DiffChanges>| {
+                    let (changes, left_intra_line, right_intra_line) =
+                        if let Some(changes) = changes {
+                            changes
+                        } else {
+                            return;
+                        };
 
                     if left_atomic_rev.load(atomic::Ordering::Acquire) != left_rev {
                         return;
@@ -275,22 +324,29 @@ fn listen_diff_changes(&self) {
                     left_editor_view.set(EditorViewKind::Diff(DiffInfo {
                         is_right: false,
                         changes: changes.clone(),
+                        intra_line: left_intra_line,
                     }));
                     right_editor_view.set(EditorViewKind::Diff(DiffInfo {
                         is_right: true,
                         changes,
+                        intra_line: right_intra_line,
                     }));
                 })
             };
 
             rayon::spawn(move || {
                 let changes = rope_diff(
-                    left_rope,
-                    right_rope,
+                    left_rope.clone(),
+                    right_rope.clone(),
                     right_rev,
                     right_atomic_rev.clone(),
                     Some(3),
-                );
+                )
+                .map(|changes| {
+                    let (left_intra_line, right_intra_line) =
+                        intra_line_changes(&left_rope, &right_rope, &changes);
+                    (changes, left_intra_line, right_intra_line)
+                });
                 send(changes);
             });
         });
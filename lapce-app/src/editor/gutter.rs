@@ -158,8 +158,7 @@ fn paint(&mut self, cx: &mut floem::context::PaintCx) {
             .buffer
             .with_untracked(|buffer| buffer.line_of_offset(offset));
 
-        let family: Vec<FamilyOwned> =
-            FamilyOwned::parse_list(&config.editor.font_family).collect();
+        let family: Vec<FamilyOwned> = config.editor.font_family();
         let attrs = Attrs::new()
             .family(&family)
             .color(*config.get_color(LapceColor::EDITOR_DIM))
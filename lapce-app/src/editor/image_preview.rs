@@ -0,0 +1,52 @@
+use std::{
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use floem::{
+    ext_event::create_ext_action,
+    reactive::create_rw_signal,
+    view::View,
+    views::{container, img, Decorators},
+};
+use lapce_rpc::proxy::ProxyResponse;
+
+use crate::{config::color::LapceColor, window_tab::CommonData};
+
+/// Whether the file is an image format that is shown as a picture rather than opened as text.
+pub fn is_image_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| {
+            matches!(
+                ext.to_lowercase().as_str(),
+                "png" | "jpg" | "jpeg" | "gif" | "bmp" | "ico" | "webp" | "tiff"
+            )
+        })
+        .unwrap_or(false)
+}
+
+/// Shows the image at `path`, with its bytes read through the proxy so that this also works
+/// for remote workspaces.
+pub fn image_preview_view(path: PathBuf, common: Rc<CommonData>) -> impl View {
+    let config = common.config;
+    let content = create_rw_signal(Vec::new());
+
+    let send = create_ext_action(common.scope, move |result| {
+        if let Ok(ProxyResponse::ReadFileResponse { content: bytes }) = result {
+            content.set(bytes);
+        }
+    });
+    common.proxy.read_file(path, move |result| {
+        send(result);
+    });
+
+    container(img(move || content.get()).style(|s| s.max_width_pct(100.0))).style(
+        move |s| {
+            s.size_full()
+                .items_center()
+                .justify_center()
+                .background(*config.get().get_color(LapceColor::EDITOR_BACKGROUND))
+        },
+    )
+}
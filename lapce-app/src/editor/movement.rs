@@ -12,7 +12,9 @@
     register::Register,
     selection::{SelRegion, Selection},
     soft_tab::{snap_to_soft_tab, SnapDirection},
+    syntax::TextObject,
 };
+use regex::Regex;
 
 use crate::doc::Document;
 
@@ -96,24 +98,38 @@ pub fn move_offset(
 
     match movement {
         Movement::Left => {
-            let new_offset = move_left(
-                view.rope_text(),
-                offset,
-                mode,
-                count,
-                config.editor.atomic_soft_tab_width(),
-            );
+            let new_offset = config
+                .editor
+                .visual_cursor_movement
+                .then(|| view.move_visually(offset, mode, count, true))
+                .flatten()
+                .unwrap_or_else(|| {
+                    move_left(
+                        view.rope_text(),
+                        offset,
+                        mode,
+                        count,
+                        config.editor.atomic_soft_tab_width(),
+                    )
+                });
 
             (new_offset, None)
         }
         Movement::Right => {
-            let new_offset = move_right(
-                view.rope_text(),
-                offset,
-                mode,
-                count,
-                config.editor.atomic_soft_tab_width(),
-            );
+            let new_offset = config
+                .editor
+                .visual_cursor_movement
+                .then(|| view.move_visually(offset, mode, count, false))
+                .flatten()
+                .unwrap_or_else(|| {
+                    move_right(
+                        view.rope_text(),
+                        offset,
+                        mode,
+                        count,
+                        config.editor.atomic_soft_tab_width(),
+                    )
+                });
 
             (new_offset, None)
         }
@@ -180,6 +196,15 @@ pub fn move_offset(
                 view.rope_text().move_n_words_backward(offset, count, mode);
             (new_offset, None)
         }
+        Movement::SubwordForward => {
+            let new_offset = view.rope_text().move_n_subwords_forward(offset, count);
+            (new_offset, None)
+        }
+        Movement::SubwordBackward => {
+            let new_offset =
+                view.rope_text().move_n_subwords_backward(offset, count);
+            (new_offset, None)
+        }
         Movement::NextUnmatched(char) => {
             let new_offset = view.find_unmatched(offset, false, *char);
 
@@ -205,6 +230,16 @@ pub fn move_offset(
             let new_offset =
                 view.rope_text().move_n_paragraphs_backward(offset, count);
 
+            (new_offset, None)
+        }
+        Movement::NextItem(item) => {
+            let new_offset = view.find_item(offset, *item, false, count);
+
+            (new_offset, None)
+        }
+        Movement::PreviousItem(item) => {
+            let new_offset = view.find_item(offset, *item, true, count);
+
             (new_offset, None)
         }
     }
@@ -502,6 +537,7 @@ pub fn do_multi_selection(
     view: &EditorViewData,
     cursor: &mut Cursor,
     cmd: &MultiSelectionCommand,
+    register: &mut Register,
 ) {
     use MultiSelectionCommand::*;
     let rope_text = view.rope_text();
@@ -584,6 +620,12 @@ pub fn do_multi_selection(
                 cursor.set_insert(new_selection);
             }
         }
+        SplitSelectionIntoLines => {
+            if let CursorMode::Insert(selection) = cursor.mode.clone() {
+                cursor
+                    .set_insert(split_selection_into_lines(&rope_text, &selection));
+            }
+        }
         SelectAllCurrent => {
             if let CursorMode::Insert(mut selection) = cursor.mode.clone() {
                 if !selection.is_empty() {
@@ -711,6 +753,195 @@ pub fn do_multi_selection(
             let new_selection = Selection::region(0, rope_text.len());
             cursor.set_insert(new_selection);
         }
+        ShrinkSelection => shrink_selection(cursor, rope_text.len()),
+        ExpandSelection | SelectAroundFunction | SelectInsideFunction
+        | SelectAroundClass | SelectInsideClass | SelectAroundArgument
+        | SelectInsideArgument | SelectAroundString | SelectInsideString => {
+            select_syntax(view, cursor, cmd, rope_text.len(), register)
+        }
+    }
+}
+
+/// Split each region of the selection that spans several lines into one region
+/// on each of them, a caret where a line has none of it.
+fn split_selection_into_lines(
+    rope_text: &impl RopeText,
+    selection: &Selection,
+) -> Selection {
+    let mut new_selection = Selection::new();
+    for region in selection.regions() {
+        let (start, end) = (region.min(), region.max());
+        let start_line = rope_text.line_of_offset(start);
+        let end_line = rope_text.line_of_offset(end);
+        if start_line == end_line {
+            new_selection.add_region(*region);
+            continue;
+        }
+        for line in start_line..=end_line {
+            let line_start = rope_text.offset_of_line(line).max(start);
+            let line_end = rope_text.line_end_offset(line, true).min(end);
+            new_selection.add_region(SelRegion::new(line_start, line_end, None));
+        }
+    }
+    new_selection
+}
+
+/// Keep the regions of the selection whose text the regex matches, or the ones
+/// it doesn't match if `keep` is false. A caret is matched by its line. The
+/// selection is left as it is if no region would be left.
+pub fn filter_selection(
+    rope_text: &impl RopeText,
+    selection: &Selection,
+    regex: &Regex,
+    keep: bool,
+) -> Selection {
+    let mut new_selection = Selection::new();
+    for region in selection.regions() {
+        let text = if region.is_caret() {
+            let line = rope_text.line_of_offset(region.start);
+            rope_text.slice_to_cow(
+                rope_text.offset_of_line(line)
+                    ..rope_text.line_end_offset(line, true),
+            )
+        } else {
+            rope_text.slice_to_cow(region.min()..region.max())
+        };
+        if regex.is_match(&text) == keep {
+            new_selection.add_region(*region);
+        }
+    }
+    if new_selection.is_empty() {
+        selection.clone()
+    } else {
+        new_selection
+    }
+}
+
+/// The range that a structural selection command selects from `start..end`,
+/// found in the syntax tree of the document.
+fn syntax_range(
+    doc: &Document,
+    cmd: &MultiSelectionCommand,
+    start: usize,
+    end: usize,
+) -> Option<(usize, usize)> {
+    use MultiSelectionCommand::*;
+    let (object, inside) = match cmd {
+        ExpandSelection => {
+            return doc
+                .syntax
+                .with_untracked(|syntax| syntax.expand_selection(start, end));
+        }
+        SelectAroundFunction => (TextObject::Function, false),
+        SelectInsideFunction => (TextObject::Function, true),
+        SelectAroundClass => (TextObject::Class, false),
+        SelectInsideClass => (TextObject::Class, true),
+        SelectAroundArgument => (TextObject::Argument, false),
+        SelectInsideArgument => (TextObject::Argument, true),
+        SelectAroundString => (TextObject::String, false),
+        SelectInsideString => (TextObject::String, true),
+        _ => return None,
+    };
+    doc.syntax
+        .with_untracked(|syntax| syntax.text_object(start, object, inside))
+}
+
+/// Select the syntax range of a structural selection command. In normal mode
+/// with a pending operator, like the `d` of `daf`, the operator is applied to the
+/// range instead.
+fn select_syntax(
+    view: &EditorViewData,
+    cursor: &mut Cursor,
+    cmd: &MultiSelectionCommand,
+    len: usize,
+    register: &mut Register,
+) {
+    let doc = view.doc.get_untracked();
+    match cursor.mode.clone() {
+        CursorMode::Insert(selection) => {
+            let mut new_selection = Selection::new();
+            for region in selection.regions() {
+                let (start, end) =
+                    syntax_range(&doc, cmd, region.min(), region.max())
+                        .unwrap_or((region.min(), region.max()));
+                new_selection.add_region(SelRegion::new(start, end, None));
+            }
+            if new_selection != selection {
+                cursor.set_insert(new_selection);
+            }
+        }
+        CursorMode::Visual { start, end, .. } => {
+            // The character under the cursor is part of a visual selection
+            let (min, max) = (start.min(end), (start.max(end) + 1).min(len));
+            if let Some((start, end)) = syntax_range(&doc, cmd, min, max) {
+                cursor.history_selections.push(Selection::region(min, max));
+                cursor.mode = visual_range(start, end);
+            }
+        }
+        CursorMode::Normal(offset) => {
+            let range = syntax_range(&doc, cmd, offset, offset);
+            let motion_mode = cursor.motion_mode.take();
+            let Some((start, end)) = range else {
+                return;
+            };
+            if let Some(motion_mode) = motion_mode {
+                let deltas = doc
+                    .buffer
+                    .try_update(|buffer| {
+                        Editor::execute_motion_mode(
+                            cursor,
+                            buffer,
+                            motion_mode,
+                            start,
+                            end,
+                            false,
+                            register,
+                        )
+                    })
+                    .unwrap();
+                doc.apply_deltas(&deltas);
+            } else {
+                cursor.history_selections.push(Selection::caret(offset));
+                cursor.mode = visual_range(start, end);
+            }
+        }
+    }
+}
+
+/// Go back to the selection that the last expansion started from, as long as it
+/// is inside the current one.
+fn shrink_selection(cursor: &mut Cursor, len: usize) {
+    let (min, max) = match &cursor.mode {
+        CursorMode::Insert(selection) => {
+            (selection.min_offset(), selection.max_offset())
+        }
+        CursorMode::Visual { start, end, .. } => {
+            (*start.min(end), (*start.max(end) + 1).min(len))
+        }
+        CursorMode::Normal(_) => return,
+    };
+    let Some(previous) = cursor
+        .history_selections
+        .last()
+        .filter(|s| s.min_offset() >= min && s.max_offset() <= max)
+        .cloned()
+    else {
+        return;
+    };
+    cursor.history_selections.pop();
+    cursor.mode = match cursor.mode {
+        CursorMode::Insert(_) => CursorMode::Insert(previous),
+        _ if previous.is_caret() => CursorMode::Normal(previous.min_offset()),
+        _ => visual_range(previous.min_offset(), previous.max_offset()),
+    };
+}
+
+/// A visual selection of `start..end`, which ends on the last selected character.
+fn visual_range(start: usize, end: usize) -> CursorMode {
+    CursorMode::Visual {
+        start,
+        end: end.saturating_sub(1).max(start),
+        mode: VisualMode::Normal,
     }
 }
 
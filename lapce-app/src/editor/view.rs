@@ -1,12 +1,14 @@
 use std::{cmp, collections::HashMap, path::PathBuf, rc::Rc, sync::Arc};
 
 use floem::{
-    action::{set_ime_allowed, set_ime_cursor_area},
+    action::{set_ime_allowed, set_ime_cursor_area, show_context_menu},
     context::PaintCx,
     cosmic_text::{Attrs, AttrsList, FamilyOwned, TextLayout},
     event::{Event, EventListener},
+    ext_event::create_ext_action,
     id::Id,
     keyboard::ModifiersState,
+    menu::{Menu, MenuItem},
     peniko::{
         kurbo::{BezPath, Line, Point, Rect, Size},
         Color,
@@ -21,16 +23,27 @@
     EventPropagation, Renderer,
 };
 use lapce_core::{
-    buffer::{diff::DiffLines, rope_text::RopeText},
+    buffer::{diff::DiffLines, rope_text::RopeText, Buffer},
+    chars::char_is_rtl,
     cursor::{ColPosition, CursorMode},
     mode::{Mode, VisualMode},
+    selection::Selection,
+};
+use lapce_rpc::{
+    dap_types::{DapId, SourceBreakpoint},
+    file::FileNodeItem,
+    proxy::ProxyResponse,
 };
-use lapce_rpc::dap_types::{DapId, SourceBreakpoint};
 use lapce_xi_rope::find::CaseMatching;
+use lsp_types::{
+    CodeActionKind, CodeActionOrCommand, DiagnosticSeverity, DocumentSymbol,
+    Position,
+};
 
 use super::{
     gutter::editor_gutter_view,
-    view_data::{EditorViewData, LineExtraStyle},
+    location::{EditorLocation, EditorPosition},
+    view_data::{EditorViewData, EditorViewKind, LineExtraStyle},
     EditorData, CHAR_WIDTH, FONT_SIZE,
 };
 use crate::{
@@ -39,12 +52,20 @@
     config::{color::LapceColor, icon::LapceIcons, LapceConfig},
     debug::LapceBreakpoint,
     doc::{DocContent, Document},
+    find::occurrence_position,
     keypress::KeyPressFocus,
+    localization::{tr, tr_args},
+    perf::{self, Metric},
     text_input::text_input,
-    window_tab::{Focus, WindowTabData},
+    window_tab::{CommonData, Focus, WindowTabData},
     workspace::LapceWorkspace,
 };
 
+/// The width of the scroll bar, which has an overview of the document.
+pub const SCROLL_BAR_WIDTH: f64 = 10.0;
+/// The smallest height of a mark in the scroll bar.
+const MARK_HEIGHT: f64 = 3.0;
+
 pub enum DiffSectionKind {
     NoCode,
     Added,
@@ -82,9 +103,13 @@ pub struct EditorView {
     editor: Rc<EditorData>,
     is_active: Memo<bool>,
     inner_node: Option<Node>,
+    /// The width of the widest line when the editor was last laid out.
+    max_width: f64,
     viewport: RwSignal<Rect>,
     debug_breakline: Memo<Option<(usize, PathBuf)>>,
     sticky_header_info: StickyHeaderInfo,
+    /// The width of a character in the editor font, which the rulers are placed by.
+    char_width: Memo<f64>,
 }
 
 pub fn editor_view(
@@ -106,25 +131,63 @@ pub fn editor_view(
     });
 
     let hide_cursor = editor.common.window_common.hide_cursor;
+    let hovered_link = editor.hovered_link;
+    let symbol_highlights = editor.symbol_highlights;
+    let selection_occurrences = editor.selection_occurrences;
     create_effect(move |_| {
         hide_cursor.track();
         let occurrences = doc.with(|doc| doc.find_result.occurrences);
         occurrences.track();
+        hovered_link.track();
+        symbol_highlights.track();
+        selection_occurrences.track();
         id.request_paint();
     });
 
-    create_effect(move |last_rev| {
+    {
+        let editor = editor.clone();
+        create_effect(move |_| {
+            editor.cursor.track();
+            let buffer = doc.with(|doc| doc.buffer);
+            buffer.track();
+            editor.schedule_symbol_highlights();
+            editor.schedule_code_actions();
+            editor.update_selection_occurrences();
+        });
+    }
+
+    create_effect(move |last| {
         let buffer = doc.with(|doc| doc.buffer);
-        let rev = buffer.with(|buffer| buffer.rev());
-        if last_rev == Some(rev) {
-            return rev;
+        let (rev, last_line) =
+            buffer.with(|buffer| (buffer.rev(), buffer.last_line()));
+        match last {
+            Some((last_rev, _)) if last_rev == rev => {}
+            // An edit that keeps the number of lines doesn't change the height of
+            // the editor, and a line that got wider is noticed when it's painted
+            Some((_, last_last_line)) if last_last_line == last_line => {
+                id.request_paint();
+            }
+            _ => id.request_layout(),
         }
-        id.request_layout();
-        rev
+        (rev, last_line)
     });
 
     let config = editor.common.config;
+    // Measured when the font changes, rather than on every paint
+    let char_width = create_memo(move |_| {
+        config.with(|config| {
+            let family: Vec<FamilyOwned> = config.editor.font_family();
+            let attrs = Attrs::new()
+                .family(&family)
+                .font_size(config.editor.font_size() as f32);
+            let mut text = TextLayout::new();
+            text.set_text("W", AttrsList::new(attrs));
+            text.hit_position(1).point.x
+        })
+    });
+
     let sticky_header_height_signal = editor.sticky_header_height;
+    let sticky_header_lines_signal = editor.sticky_header_lines;
     create_effect(move |last_rev| {
         let config = config.get();
         if !config.editor.sticky_header {
@@ -147,6 +210,7 @@ pub fn editor_view(
             doc,
             viewport,
             sticky_header_height_signal,
+            sticky_header_lines_signal,
             &config,
         );
 
@@ -178,12 +242,20 @@ pub fn editor_view(
                     set_ime_allowed(true);
                 }
                 let offset = cursor.with(|c| c.offset());
-                let (_, point_below) = editor_view.points_of_offset(offset);
+                // The caret moves through the text being composed
+                doc.with(|doc| doc.preedit.track());
+                let LineRegion { x, width, .. } =
+                    cursor_caret(&editor_view, offset, false);
+                let (point_above, point_below) =
+                    editor_view.points_of_offset(offset);
                 let window_origin = editor_window_origin.get();
                 let viewport = editor_viewport.get();
-                let pos = window_origin
-                    + (point_below.x - viewport.x0, point_below.y - viewport.y0);
-                set_ime_cursor_area(pos, Size::new(800.0, 600.0));
+                let pos =
+                    window_origin + (x - viewport.x0, point_above.y - viewport.y0);
+                set_ime_cursor_area(
+                    pos,
+                    Size::new(width, point_below.y - point_above.y),
+                );
             }
         }
     });
@@ -194,8 +266,10 @@ pub fn editor_view(
         editor,
         is_active,
         inner_node: None,
+        max_width: 0.0,
         viewport,
         debug_breakline,
+        char_width,
         sticky_header_info: StickyHeaderInfo {
             sticky_lines: Vec::new(),
             last_sticky_should_scroll: false,
@@ -224,9 +298,7 @@ pub fn editor_view(
         }
 
         if let Event::ImeCommit(text) = event {
-            let doc = doc.get_untracked();
-            doc.clear_preedit();
-            local_editor.receive_char(text);
+            local_editor.commit_ime(text);
         }
         EventPropagation::Stop
     })
@@ -289,6 +361,37 @@ fn paint_diff_sections(
                 }
             }
         }
+
+        self.paint_diff_intra_line(cx, screen_lines, config);
+    }
+
+    /// Highlight the part of a modified line that changed, on top of the line's
+    /// background.
+    fn paint_diff_intra_line(
+        &self,
+        cx: &mut PaintCx,
+        screen_lines: &ScreenLines,
+        config: &LapceConfig,
+    ) {
+        let view = &self.editor.view;
+        let EditorViewKind::Diff(diff_info) = view.kind.get_untracked() else {
+            return;
+        };
+        let color = if diff_info.is_right {
+            config.get_color(LapceColor::SOURCE_CONTROL_ADDED)
+        } else {
+            config.get_color(LapceColor::SOURCE_CONTROL_REMOVED)
+        }
+        .with_alpha_factor(0.4);
+        let line_height = config.editor.line_height() as f64;
+        for (line, range) in diff_info.intra_line.iter() {
+            if let Some(line_info) = screen_lines.info.get(line) {
+                let x0 = Self::calculate_col_x(view, *line, range.start, true);
+                let x1 = Self::calculate_col_x(view, *line, range.end, false);
+                let y0 = line_info.y as f64;
+                cx.fill(&Rect::new(x0, y0, x1, y0 + line_height), color, 0.0);
+            }
+        }
     }
 
     fn paint_diff_no_code(
@@ -366,10 +469,32 @@ fn paint_normal_selection(
                     view.line_end_col(line, true)
                 };
                 let right_col = phantom_text.col_after(right_col, false);
+                let left_col = if line == start_line {
+                    phantom_text.col_after(start_col, is_block_cursor)
+                } else {
+                    0
+                };
+
+                if view.rope_text().line_content(line).chars().any(char_is_rtl) {
+                    // The selected part of text in both directions can be in more
+                    // than one place on the line
+                    let text_layout = view.get_text_layout(line, FONT_SIZE);
+                    let mut ranges = text_layout.col_ranges(left_col, right_col);
+                    if line != end_line {
+                        let x = text_layout.width();
+                        ranges.push((x, x + CHAR_WIDTH));
+                    }
+                    for (x0, x1) in ranges {
+                        let rect = Rect::from_origin_size(
+                            (x0, info.y as f64),
+                            (x1 - x0, line_height),
+                        );
+                        cx.fill(&rect, color, 0.0);
+                    }
+                    continue;
+                }
 
                 let x0 = if line == start_line {
-                    let left_col =
-                        phantom_text.col_after(start_col, is_block_cursor);
                     view.line_point_of_line_col(line, left_col, FONT_SIZE).x
                 } else {
                     viewport.x0
@@ -734,8 +859,7 @@ fn paint_text(
         let font_size = config.editor.font_size();
 
         let indent_unit = view.indent_unit();
-        let family: Vec<FamilyOwned> =
-            FamilyOwned::parse_list(&config.editor.font_family).collect();
+        let family: Vec<FamilyOwned> = config.editor.font_family();
         let attrs = Attrs::new()
             .family(&family)
             .font_size(config.editor.font_size() as f32);
@@ -747,6 +871,19 @@ fn paint_text(
 
         let last_line = view.last_line();
 
+        let show_indent_guide = config.editor.show_indent_guide;
+        let (blank_line_levels, active_block) = if show_indent_guide {
+            self.indent_guide_info(
+                screen_lines,
+                indent_unit,
+                config.editor.tab_width,
+            )
+        } else {
+            (HashMap::new(), None)
+        };
+        let only_selected_whitespace =
+            config.editor.render_whitespace == "selection";
+
         for line in &screen_lines.lines {
             let line = *line;
             if line > last_line {
@@ -755,7 +892,7 @@ fn paint_text(
 
             let info = screen_lines.info.get(&line).unwrap();
             let text_layout = view.get_text_layout(line, font_size);
-            let height = text_layout.text.size().height;
+            let height = text_layout.height();
             let y = info.y;
 
             self.paint_extra_style(
@@ -768,8 +905,7 @@ fn paint_text(
             );
 
             if let Some(whitespaces) = &text_layout.whitespaces {
-                let family: Vec<FamilyOwned> =
-                    FamilyOwned::parse_list(&config.editor.font_family).collect();
+                let family: Vec<FamilyOwned> = config.editor.font_family();
                 let attrs = Attrs::new()
                     .color(*config.get_color(LapceColor::EDITOR_VISIBLE_WHITESPACE))
                     .family(&family)
@@ -780,7 +916,17 @@ fn paint_text(
                 let mut tab_text = TextLayout::new();
                 tab_text.set_text("→", attrs_list);
 
-                for (c, (x0, _x1)) in whitespaces.iter() {
+                let selected =
+                    only_selected_whitespace.then(|| self.selected_x_ranges(line));
+                for (c, (x0, x1)) in whitespaces.iter() {
+                    if let Some(selected) = selected.as_ref() {
+                        if !selected
+                            .iter()
+                            .any(|(s0, s1)| *s0 <= *x0 + 0.5 && *x1 <= *s1 + 0.5)
+                        {
+                            continue;
+                        }
+                    }
                     match *c {
                         '\t' => {
                             cx.draw_text(
@@ -805,28 +951,193 @@ fn paint_text(
                 }
             }
 
-            if config.editor.show_indent_guide {
+            if show_indent_guide {
+                // Blank lines continue the guides of the lines around them
+                let indent = match blank_line_levels.get(&line) {
+                    Some(level) => *level as f64 * indent_text_width,
+                    None => text_layout.indent,
+                };
                 let mut x = 0.0;
-                while x + 1.0 < text_layout.indent {
+                let mut level = 1;
+                while x + 1.0 < indent {
+                    let is_active =
+                        active_block.is_some_and(|(start, end, active)| {
+                            level == active && start <= line && line <= end
+                        });
+                    let color = if is_active {
+                        LapceColor::EDITOR_INDENT_GUIDE_ACTIVE
+                    } else {
+                        LapceColor::EDITOR_INDENT_GUIDE
+                    };
                     cx.stroke(
                         &Line::new(
                             Point::new(x, y as f64),
                             Point::new(x, y as f64 + line_height),
                         ),
-                        config.get_color(LapceColor::EDITOR_INDENT_GUIDE),
+                        config.get_color(color),
                         1.0,
                     );
                     x += indent_text_width;
+                    level += 1;
                 }
             }
 
-            cx.draw_text(
-                &text_layout.text,
-                Point::new(0.0, y as f64 + (line_height - height) / 2.0),
+            let text_y = y as f64 + (line_height - height) / 2.0;
+            text_layout.draw(cx, viewport.x0, viewport.x1, Point::new(0.0, text_y));
+        }
+    }
+
+    fn paint_rulers(&self, cx: &mut PaintCx, viewport: Rect, config: &LapceConfig) {
+        let doc = self.editor.view.doc.get_untracked();
+        let language = doc.syntax.with_untracked(|s| s.language);
+        let rulers = config.editor.rulers(language);
+        if rulers.is_empty() {
+            return;
+        }
+
+        let char_width = self.char_width.get_untracked();
+        for col in rulers {
+            let x = (*col as f64 * char_width).round() + 0.5;
+            if x < viewport.x0 || x > viewport.x1 {
+                continue;
+            }
+            cx.stroke(
+                &Line::new(Point::new(x, viewport.y0), Point::new(x, viewport.y1)),
+                config.get_color(LapceColor::EDITOR_RULER),
+                1.0,
             );
         }
     }
 
+    /// The indent levels of the blank lines on screen, and the first line, last
+    /// line and indent level of the block the cursor is in, whose guide is
+    /// highlighted.
+    fn indent_guide_info(
+        &self,
+        screen_lines: &ScreenLines,
+        indent_unit: &str,
+        tab_width: usize,
+    ) -> (HashMap<usize, usize>, Option<(usize, usize, usize)>) {
+        let (Some(min_line), Some(max_line)) = (
+            screen_lines.lines.first().copied(),
+            screen_lines.lines.last().copied(),
+        ) else {
+            return (HashMap::new(), None);
+        };
+        let tab_width = tab_width.max(1);
+        let indent_width = if indent_unit == "\t" {
+            tab_width
+        } else {
+            indent_unit.len().max(1)
+        };
+
+        let view = &self.editor.view;
+        let offset = self.editor.cursor.with_untracked(|c| c.offset());
+        let cursor_line = view.line_of_offset(offset);
+        let doc = view.doc.get_untracked();
+        doc.buffer.with_untracked(|buffer| {
+            let level =
+                |line: usize| indent_level(buffer, line, indent_width, tab_width);
+            // Finding the lines around a blank line can take a while in a long run
+            // of blank lines, so the levels are kept until the text changes
+            let blank_level = |line: usize| {
+                doc.blank_line_levels.borrow_mut().get_or_insert_with(
+                    buffer.rev(),
+                    (indent_width, tab_width),
+                    line,
+                    || {
+                        let prev = (0..line).rev().find_map(level).unwrap_or(0);
+                        let next = (line + 1..buffer.num_lines()).find_map(level);
+                        prev.min(next.unwrap_or(0))
+                    },
+                )
+            };
+            let blank_line_levels = screen_lines
+                .lines
+                .iter()
+                .filter(|line| level(**line).is_none())
+                .map(|line| (*line, blank_level(*line)))
+                .collect();
+
+            if !(min_line..=max_line).contains(&cursor_line) {
+                return (blank_line_levels, None);
+            }
+            let active =
+                level(cursor_line).unwrap_or_else(|| blank_level(cursor_line));
+            let in_block =
+                |line: usize| !matches!(level(line), Some(l) if l < active);
+            let active_block = (active > 0).then(|| {
+                let mut start = cursor_line;
+                while start > min_line && in_block(start - 1) {
+                    start -= 1;
+                }
+                let mut end = cursor_line;
+                while end < max_line && in_block(end + 1) {
+                    end += 1;
+                }
+                (start, end, active)
+            });
+            (blank_line_levels, active_block)
+        })
+    }
+
+    /// The horizontal ranges of a line that are covered by the selections.
+    fn selected_x_ranges(&self, line: usize) -> Vec<(f64, f64)> {
+        let view = &self.editor.view;
+        let regions: Vec<(usize, usize)> =
+            self.editor
+                .cursor
+                .with_untracked(|cursor| match cursor.mode {
+                    CursorMode::Normal(_) => Vec::new(),
+                    CursorMode::Visual {
+                        start,
+                        end,
+                        mode: VisualMode::Linewise,
+                    } => {
+                        let start_line = view.line_of_offset(start.min(end));
+                        let end_line = view.line_of_offset(start.max(end));
+                        vec![(
+                            view.offset_of_line(start_line),
+                            view.offset_of_line(end_line + 1),
+                        )]
+                    }
+                    CursorMode::Visual { start, end, .. } => vec![(
+                        start.min(end),
+                        view.move_right(start.max(end), Mode::Insert, 1),
+                    )],
+                    CursorMode::Insert(_) => cursor
+                        .regions_iter()
+                        .filter(|(start, end)| start != end)
+                        .map(|(start, end)| (start.min(end), start.max(end)))
+                        .collect(),
+                });
+
+        let phantom_text = view.line_phantom_text(line);
+        regions
+            .into_iter()
+            .filter_map(|(start, end)| {
+                let (start_line, start_col) = view.offset_to_line_col(start);
+                let (end_line, end_col) = view.offset_to_line_col(end);
+                if line < start_line || line > end_line {
+                    return None;
+                }
+                let x0 = if line == start_line {
+                    let col = phantom_text.col_after(start_col, false);
+                    view.line_point_of_line_col(line, col, FONT_SIZE).x
+                } else {
+                    f64::MIN
+                };
+                let x1 = if line == end_line {
+                    let col = phantom_text.col_after(end_col, false);
+                    view.line_point_of_line_col(line, col, FONT_SIZE).x
+                } else {
+                    f64::MAX
+                };
+                Some((x0, x1))
+            })
+            .collect()
+    }
+
     fn paint_find(&self, cx: &mut PaintCx, screen_lines: &ScreenLines) {
         let visual = self.editor.common.find.visual;
         if !visual.get_untracked() {
@@ -906,6 +1217,122 @@ fn paint_find(&self, cx: &mut PaintCx, screen_lines: &ScreenLines) {
         }
     }
 
+    /// Fill the background of the occurrences of the symbol under the cursor on
+    /// the screen, with a color of its own where it's written to.
+    fn paint_symbol_highlights(&self, cx: &mut PaintCx, screen_lines: &ScreenLines) {
+        let (Some(min_line), Some(max_line)) =
+            (screen_lines.lines.first(), screen_lines.lines.last())
+        else {
+            return;
+        };
+        let view = &self.editor.view;
+        let config = self.editor.common.config.get_untracked();
+        let line_height = config.editor.line_height() as f64;
+        // The occurrences of the selected text are shown instead
+        if self
+            .editor
+            .selection_occurrences
+            .with_untracked(|o| !o.occurrences.is_empty())
+        {
+            return;
+        }
+        let start = view.offset_of_line(*min_line);
+        let end = view.offset_of_line(*max_line + 1);
+        self.editor.symbol_highlights.with_untracked(|highlights| {
+            let highlights = &highlights.highlights;
+            let first = highlights.partition_point(|h| h.end < start);
+            let on_screen =
+                highlights[first..].iter().take_while(|h| h.start <= end);
+            for highlight in on_screen {
+                let (line, start_col) = view.offset_to_line_col(highlight.start);
+                let (end_line, end_col) = view.offset_to_line_col(highlight.end);
+                if line != end_line {
+                    continue;
+                }
+                let Some(info) = screen_lines.info.get(&line) else {
+                    continue;
+                };
+                let x0 = Self::calculate_col_x(view, line, start_col, true);
+                let x1 = Self::calculate_col_x(view, line, end_col, false);
+                let rect =
+                    Rect::new(x0, info.y as f64, x1, info.y as f64 + line_height);
+                let color = if highlight.write {
+                    LapceColor::EDITOR_SYMBOL_HIGHLIGHT_WRITE
+                } else {
+                    LapceColor::EDITOR_SYMBOL_HIGHLIGHT
+                };
+                cx.fill(&rect, config.get_color(color), 0.0);
+            }
+        });
+    }
+
+    /// Fill the background of the other occurrences of the selected text on
+    /// the screen.
+    fn paint_selection_occurrences(
+        &self,
+        cx: &mut PaintCx,
+        screen_lines: &ScreenLines,
+    ) {
+        let (Some(min_line), Some(max_line)) =
+            (screen_lines.lines.first(), screen_lines.lines.last())
+        else {
+            return;
+        };
+        let view = &self.editor.view;
+        let config = self.editor.common.config.get_untracked();
+        let line_height = config.editor.line_height() as f64;
+        let color = config.get_color(LapceColor::EDITOR_SELECTION_OCCURRENCE);
+        let start = view.offset_of_line(*min_line);
+        let end = view.offset_of_line(*max_line + 1);
+        self.editor.selection_occurrences.with_untracked(|o| {
+            for region in o.occurrences.regions_in_range(start, end) {
+                let (start, end) = (region.min(), region.max());
+                if (start, end) == o.selection {
+                    continue;
+                }
+                let (line, start_col) = view.offset_to_line_col(start);
+                let Some(info) = screen_lines.info.get(&line) else {
+                    continue;
+                };
+                let end_col = start_col + (end - start);
+                let x0 = Self::calculate_col_x(view, line, start_col, true);
+                let x1 = Self::calculate_col_x(view, line, end_col, false);
+                let rect =
+                    Rect::new(x0, info.y as f64, x1, info.y as f64 + line_height);
+                cx.fill(&rect, color, 0.0);
+            }
+        });
+    }
+
+    /// Underline the link under the pointer, which a click follows.
+    fn paint_hovered_link(&self, cx: &mut PaintCx, screen_lines: &ScreenLines) {
+        let Some(link) = self.editor.hovered_link.get_untracked() else {
+            return;
+        };
+        let view = &self.editor.view;
+        let (line, start_col) = view.offset_to_line_col(link.start);
+        let Some(info) = screen_lines.info.get(&line) else {
+            return;
+        };
+        let end_col = start_col + (link.end - link.start);
+
+        let config = self.editor.common.config.get_untracked();
+        let line_height = config.editor.line_height() as f64;
+        let height = view
+            .get_text_layout(line, config.editor.font_size())
+            .text
+            .size()
+            .height;
+        let x0 = Self::calculate_col_x(view, line, start_col, true);
+        let x1 = Self::calculate_col_x(view, line, end_col, false);
+        let y = info.y as f64 + height + (line_height - height) / 2.0;
+        cx.stroke(
+            &Line::new(Point::new(x0, y), Point::new(x1, y)),
+            config.get_color(LapceColor::EDITOR_LINK),
+            1.0,
+        );
+    }
+
     fn paint_sticky_headers(&self, cx: &mut PaintCx, viewport: Rect) {
         let config = self.editor.common.config.get_untracked();
         if !config.editor.sticky_header {
@@ -991,14 +1418,17 @@ fn paint_sticky_headers(&self, cx: &mut PaintCx, viewport: Rect) {
                 .get_text_layout(line, config.editor.font_size());
             let y = viewport.y0
                 + line_height * i as f64
-                + (line_height - text_layout.text.size().height) / 2.0
+                + (line_height - text_layout.height()) / 2.0
                 - y_diff;
-            cx.draw_text(&text_layout.text, Point::new(viewport.x0, y));
+            // Sticky headers aren't scrolled horizontally
+            text_layout.draw(cx, 0.0, viewport.width(), Point::new(viewport.x0, y));
 
             cx.restore();
         }
     }
 
+    /// The overview of the document in the scroll bar, with marks for the changes,
+    /// the search matches, the diagnostics and the cursor.
     fn paint_scroll_bar(
         &self,
         cx: &mut PaintCx,
@@ -1009,14 +1439,11 @@ fn paint_scroll_bar(
         if is_local {
             return;
         }
-        const BAR_WIDTH: f64 = 10.0;
+        let bar_x = viewport.x0 + viewport.width() - SCROLL_BAR_WIDTH;
         cx.fill(
             &Rect::ZERO
                 .with_size(Size::new(1.0, viewport.height()))
-                .with_origin(Point::new(
-                    viewport.x0 + viewport.width() - BAR_WIDTH,
-                    viewport.y0,
-                ))
+                .with_origin(Point::new(bar_x, viewport.y0))
                 .inflate(0.0, 10.0),
             config.get_color(LapceColor::LAPCE_SCROLL_BAR),
             0.0,
@@ -1030,28 +1457,117 @@ fn paint_scroll_bar(
         let total_len = doc.buffer.with_untracked(|buffer| buffer.last_line());
         let changes = doc.head_changes.get_untracked();
         let total_height = viewport.height();
-        let total_width = viewport.width();
         let line_height = config.editor.line_height();
-        let content_height = if config.editor.scroll_beyond_last_line {
-            (total_len * line_height) as f64 + total_height - line_height as f64
-        } else {
-            (total_len * line_height) as f64
+        let content_height =
+            scroll_bar_content_height(total_len, total_height, &config);
+        // The mark of `lines` lines from `line`, `x` into the bar
+        let mark = |line: usize, lines: usize, x: f64, width: f64| {
+            let y = (line * line_height) as f64 / content_height * total_height;
+            let height = ((lines * line_height) as f64 / content_height
+                * total_height)
+                .max(MARK_HEIGHT);
+            Rect::ZERO
+                .with_size(Size::new(width, height))
+                .with_origin(Point::new(bar_x + x, y + viewport.y0))
         };
 
         let colors = changes_colors(changes, 0, total_len, &config);
-        for (y, height, _, color) in colors {
-            let y = (y * line_height) as f64 / content_height * total_height;
-            let height = ((height * line_height) as f64 / content_height
-                * total_height)
-                .max(3.0);
-            let rect = Rect::ZERO.with_size(Size::new(3.0, height)).with_origin(
-                Point::new(
-                    viewport.x0 + total_width - BAR_WIDTH + 1.0,
-                    y + viewport.y0,
-                ),
-            );
-            cx.fill(&rect, color, 0.0);
+        for (line, lines, _, color) in colors {
+            cx.fill(&mark(line, lines, 1.0, 3.0), color, 0.0);
+        }
+
+        // Looked up for each mark that fits in the bar rather than for each
+        // match, since there can be a lot more of those
+        let paint_occurrences =
+            |cx: &mut PaintCx, occurrences: &Selection, color: Color| {
+                let line_of_y = |y: f64| {
+                    (y / total_height * content_height / line_height as f64) as usize
+                };
+                let mut y = 0.0;
+                while y < total_height {
+                    let start_line = line_of_y(y);
+                    let end_line = line_of_y(y + MARK_HEIGHT).max(start_line + 1);
+                    if start_line > total_len {
+                        break;
+                    }
+                    let start = self.editor.view.offset_of_line(start_line);
+                    let end = self.editor.view.offset_of_line(end_line);
+                    if !occurrences.regions_in_range(start, end).is_empty() {
+                        let rect = Rect::ZERO
+                            .with_size(Size::new(3.0, MARK_HEIGHT))
+                            .with_origin(Point::new(bar_x + 4.0, y + viewport.y0));
+                        cx.fill(&rect, color, 0.0);
+                    }
+                    y += MARK_HEIGHT;
+                }
+            };
+
+        if self.editor.common.find.visual.get_untracked() {
+            self.editor.view.update_find();
+            let occurrences = self.editor.view.find_result().occurrences;
+            let color = config
+                .get_color(LapceColor::EDITOR_FOREGROUND)
+                .with_alpha_factor(0.6);
+            occurrences.with_untracked(|occurrences| {
+                paint_occurrences(cx, occurrences, color)
+            });
+        } else {
+            let color = config
+                .get_color(LapceColor::EDITOR_SELECTION)
+                .with_alpha_factor(0.8);
+            self.editor.selection_occurrences.with_untracked(|o| {
+                if o.occurrences.len() > 1 {
+                    paint_occurrences(cx, &o.occurrences, color)
+                }
+            });
         }
+
+        // Errors are painted over the warnings on the same line
+        for severity in [DiagnosticSeverity::WARNING, DiagnosticSeverity::ERROR] {
+            let color = if severity == DiagnosticSeverity::ERROR {
+                config.get_color(LapceColor::LAPCE_ERROR)
+            } else {
+                config.get_color(LapceColor::LAPCE_WARN)
+            };
+            doc.diagnostics.diagnostics.with_untracked(|diagnostics| {
+                for diagnostic in diagnostics {
+                    if diagnostic.diagnostic.severity != Some(severity) {
+                        continue;
+                    }
+                    let line = doc.buffer.with_untracked(|buffer| {
+                        buffer.line_of_offset(diagnostic.range.0)
+                    });
+                    cx.fill(&mark(line, 1, 7.0, 3.0), color, 0.0);
+                }
+            });
+        }
+
+        let cursor = self.editor.cursor.get_untracked();
+        let regions = match &cursor.mode {
+            CursorMode::Normal(offset) => vec![(*offset, *offset)],
+            CursorMode::Visual { start, end, .. } => {
+                vec![(*start.min(end), *start.max(end))]
+            }
+            CursorMode::Insert(selection) => selection
+                .regions()
+                .iter()
+                .map(|region| (region.min(), region.max()))
+                .collect(),
+        };
+        let color = config.get_color(LapceColor::EDITOR_CARET);
+        doc.buffer.with_untracked(|buffer| {
+            for (start, end) in regions {
+                let start_line = buffer.line_of_offset(start);
+                let end_line = buffer.line_of_offset(end);
+                let rect = mark(
+                    start_line,
+                    end_line - start_line + 1,
+                    1.0,
+                    SCROLL_BAR_WIDTH - 1.0,
+                );
+                cx.fill(&rect, color.with_alpha_factor(0.6), 0.0);
+            }
+        });
     }
 
     /// Calculate the `x` coordinate of the left edge of the given column on the given line.
@@ -1291,6 +1807,7 @@ fn layout(
         &mut self,
         cx: &mut floem::context::LayoutCx,
     ) -> floem::taffy::prelude::Node {
+        let _timer = perf::time(Metric::MainSplitLayout);
         cx.layout_node(self.id, true, |cx| {
             if self.inner_node.is_none() {
                 self.inner_node = Some(cx.new_node());
@@ -1307,7 +1824,8 @@ fn layout(
             }
 
             let doc = self.editor.view.doc.get_untracked();
-            let width = self.editor.view.text_layouts.borrow().max_width + 20.0;
+            self.max_width = self.editor.view.max_width();
+            let width = self.max_width + 20.0;
             let height = line_height
                 * (self.editor.view.visual_line(
                     doc.buffer.with_untracked(|buffer| buffer.last_line()),
@@ -1332,6 +1850,7 @@ fn compute_layout(
     }
 
     fn paint(&mut self, cx: &mut PaintCx) {
+        let _timer = perf::time(Metric::Paint);
         let viewport = self.viewport.get_untracked();
         let config = self.editor.common.config.get_untracked();
         let screen_lines = self.editor.screen_lines();
@@ -1340,12 +1859,23 @@ fn paint(&mut self, cx: &mut PaintCx) {
         let is_local = doc.content.with_untracked(|content| content.is_local());
 
         self.paint_cursor(cx, is_local, &screen_lines);
+        if !is_local {
+            self.paint_rulers(cx, viewport, &config);
+        }
         self.paint_diff_sections(cx, viewport, &screen_lines, &config);
+        self.paint_symbol_highlights(cx, &screen_lines);
+        self.paint_selection_occurrences(cx, &screen_lines);
         self.paint_find(cx, &screen_lines);
         self.paint_bracket_highlights_scope_lines(cx, viewport, &screen_lines);
         self.paint_text(cx, viewport, &screen_lines);
+        self.paint_hovered_link(cx, &screen_lines);
         self.paint_sticky_headers(cx, viewport);
         self.paint_scroll_bar(cx, viewport, is_local, config);
+
+        // Painting laid out a line that is wider than the editor
+        if self.editor.view.max_width() != self.max_width {
+            self.id.request_layout();
+        }
     }
 }
 
@@ -1353,6 +1883,7 @@ fn get_sticky_header_info(
     doc: Rc<Document>,
     viewport: RwSignal<Rect>,
     sticky_header_height_signal: RwSignal<f64>,
+    sticky_header_lines_signal: RwSignal<Vec<usize>>,
     config: &LapceConfig,
 ) -> StickyHeaderInfo {
     let viewport = viewport.get();
@@ -1410,6 +1941,7 @@ fn get_sticky_header_info(
 
     if total_sticky_lines == 0 {
         sticky_header_height_signal.set(0.0);
+        sticky_header_lines_signal.set(Vec::new());
         return StickyHeaderInfo {
             sticky_lines: Vec::new(),
             last_sticky_should_scroll: false,
@@ -1435,6 +1967,7 @@ fn get_sticky_header_info(
     }
 
     sticky_header_height_signal.set(sticky_header_height);
+    sticky_header_lines_signal.set(sticky_lines[..total_sticky_lines].to_vec());
     StickyHeaderInfo {
         sticky_lines,
         last_sticky_should_scroll,
@@ -1449,6 +1982,26 @@ pub struct LineRegion {
     pub line: usize,
 }
 
+/// The indent level of a line, with tabs going up to the next tab stop, or
+/// `None` if the line is blank.
+fn indent_level(
+    buffer: &Buffer,
+    line: usize,
+    indent_width: usize,
+    tab_width: usize,
+) -> Option<usize> {
+    let mut col = 0;
+    for c in buffer.line_content(line).chars() {
+        match c {
+            ' ' => col += 1,
+            '\t' => col += tab_width - col % tab_width,
+            '\r' | '\n' => return None,
+            _ => return Some(col / indent_width),
+        }
+    }
+    None
+}
+
 pub fn cursor_caret(
     view: &EditorViewData,
     offset: usize,
@@ -1639,6 +2192,27 @@ fn editor_gutter(
         }
     });
 
+    // The lightbulb is yellow when one of the code actions fixes something,
+    // rather than only refactoring
+    let has_quick_fix = create_memo(move |_| {
+        let doc = doc.get();
+        let offset = cursor.with(|cursor| cursor.offset());
+        doc.code_actions.with(|c| {
+            c.get(&offset).is_some_and(|c| {
+                c.1.iter().any(|action| match action {
+                    CodeActionOrCommand::CodeAction(action) => {
+                        action.is_preferred == Some(true)
+                            || action.kind.as_ref().is_some_and(|kind| {
+                                kind.as_str()
+                                    .starts_with(CodeActionKind::QUICKFIX.as_str())
+                            })
+                    }
+                    CodeActionOrCommand::Command(_) => false,
+                })
+            })
+        })
+    });
+
     let gutter_rect = create_rw_signal(Rect::ZERO);
     let gutter_width = create_memo(move |_| gutter_rect.get().width());
 
@@ -1837,8 +2411,12 @@ fn editor_gutter(
                         move |s| {
                             let config = config.get();
                             let size = config.ui.icon_size() as f32;
-                            s.size(size, size)
-                                .color(*config.get_color(LapceColor::LAPCE_WARN))
+                            let color = if has_quick_fix.get() {
+                                LapceColor::LAPCE_WARN
+                            } else {
+                                LapceColor::LAPCE_ICON_ACTIVE
+                            };
+                            s.size(size, size).color(*config.get_color(color))
                         },
                     ),
                 )
@@ -1898,6 +2476,9 @@ fn editor_breadcrumbs(
     config: ReadSignal<Arc<LapceConfig>>,
 ) -> impl View {
     let doc = editor.view.doc;
+    let cursor = editor.cursor;
+    let common = editor.common.clone();
+    let internal_command = editor.common.internal_command;
     let doc_path = create_memo(move |_| {
         let doc = doc.get();
         let content = doc.content.get();
@@ -1907,6 +2488,26 @@ fn editor_breadcrumbs(
             content.path().cloned()
         }
     });
+    let symbol_path = create_memo(move |_| {
+        let doc = doc.get();
+        let Some(symbols) = doc.document_symbols.get() else {
+            return Vec::new();
+        };
+        let offset = cursor.with(|c| c.offset());
+        let position = doc.buffer.with_untracked(|b| b.offset_to_position(offset));
+        symbol_path(&symbols, position)
+    });
+    let separator = move |hide: bool| {
+        svg(move || config.get().ui_svg(LapceIcons::BREADCRUMB_SEPARATOR)).style(
+            move |s| {
+                let config = config.get();
+                let size = config.ui.icon_size() as f32;
+                s.apply_if(hide, |s| s.hide())
+                    .size(size, size)
+                    .color(*config.get_color(LapceColor::LAPCE_ICON_ACTIVE))
+            },
+        )
+    };
     container(
         scroll(
             stack((
@@ -1915,52 +2516,126 @@ fn editor_breadcrumbs(
                     list(
                         move || {
                             let full_path = doc_path.get().unwrap_or_default();
-                            let mut path = full_path;
-                            if let Some(workspace_path) =
-                                workspace.clone().path.as_ref()
-                            {
-                                path = path
-                                    .strip_prefix(workspace_path)
-                                    .unwrap_or(&path)
-                                    .to_path_buf();
-                            }
-                            path.ancestors()
+                            let root = workspace
+                                .path
+                                .clone()
+                                .filter(|root| full_path.starts_with(root));
+                            full_path
+                                .ancestors()
+                                .take_while(|path| Some(*path) != root.as_deref())
                                 .filter_map(|path| {
-                                    Some(
+                                    Some((
                                         path.file_name()?
                                             .to_string_lossy()
                                             .into_owned(),
-                                    )
+                                        path.to_path_buf(),
+                                    ))
                                 })
                                 .collect::<Vec<_>>()
                                 .into_iter()
                                 .rev()
                                 .enumerate()
                         },
-                        |(i, section)| (*i, section.to_string()),
-                        move |(i, section)| {
+                        |(i, (section, _))| (*i, section.to_string()),
+                        move |(i, (section, path))| {
+                            let common = common.clone();
                             stack((
-                                svg(move || {
-                                    config
-                                        .get()
-                                        .ui_svg(LapceIcons::BREADCRUMB_SEPARATOR)
-                                })
-                                .style(move |s| {
-                                    let config = config.get();
-                                    let size = config.ui.icon_size() as f32;
-                                    s.apply_if(i == 0, |s| s.hide())
-                                        .size(size, size)
-                                        .color(*config.get_color(
-                                            LapceColor::LAPCE_ICON_ACTIVE,
-                                        ))
-                                }),
+                                separator(i == 0),
                                 label(move || section.clone()),
                             ))
+                            .on_click_stop(move |_| {
+                                if let Some(dir) = path.parent() {
+                                    breadcrumb_dir_menu(
+                                        common.clone(),
+                                        dir.to_path_buf(),
+                                    );
+                                }
+                            })
                             .style(|s| s.items_center())
                         },
                     )
-                    .style(|s| s.padding_horiz(10.0))
+                    .style(|s| s.padding_left(10.0))
                 },
+                list(
+                    move || {
+                        let path = symbol_path.get();
+                        let symbols = doc.get().document_symbols.get_untracked();
+                        let mut level = symbols.as_deref().map_or(&[][..], |s| s);
+                        let mut items = Vec::new();
+                        for (depth, i) in path.iter().enumerate() {
+                            let Some(symbol) = level.get(*i) else {
+                                break;
+                            };
+                            items.push((depth, symbol.name.clone(), symbol.kind));
+                            level = symbol.children.as_deref().unwrap_or(&[]);
+                        }
+                        items
+                    },
+                    |(depth, name, _)| (*depth, name.clone()),
+                    move |(depth, name, kind)| {
+                        stack((
+                            separator(false),
+                            svg(move || {
+                                let config = config.get();
+                                config.symbol_svg(&kind).unwrap_or_else(|| {
+                                    config.ui_svg(LapceIcons::FILE)
+                                })
+                            })
+                            .style(move |s| {
+                                let config = config.get();
+                                let size = config.ui.icon_size() as f32;
+                                s.min_width(size)
+                                    .size(size, size)
+                                    .margin_right(4.0)
+                                    .color(
+                                        *config.get_color(
+                                            LapceColor::LAPCE_ICON_ACTIVE,
+                                        ),
+                                    )
+                            }),
+                            label(move || name.clone()),
+                        ))
+                        .on_click_stop(move |_| {
+                            let Some(path) = doc_path.get_untracked() else {
+                                return;
+                            };
+                            let doc = doc.get_untracked();
+                            let Some(symbols) = doc.document_symbols.get_untracked()
+                            else {
+                                return;
+                            };
+                            let symbol_path = symbol_path.get_untracked();
+                            let symbol_path =
+                                &symbol_path[..(depth + 1).min(symbol_path.len())];
+                            let mut menu = Menu::new("");
+                            for symbol in sibling_symbols(&symbols, symbol_path) {
+                                let location = EditorLocation {
+                                    path: path.clone(),
+                                    position: Some(EditorPosition::Position(
+                                        symbol.selection_range.start,
+                                    )),
+                                    scroll_offset: None,
+                                    ignore_unconfirmed: false,
+                                    same_editor_tab: false,
+                                };
+                                menu = menu.entry(
+                                    MenuItem::new(symbol.name.clone()).action(
+                                        move || {
+                                            internal_command.send(
+                                                InternalCommand::JumpToLocation {
+                                                    location: location.clone(),
+                                                },
+                                            );
+                                        },
+                                    ),
+                                );
+                            }
+                            show_context_menu(menu, None);
+                        })
+                        .style(|s| s.items_center())
+                    },
+                )
+                .style(|s| s.padding_right(10.0)),
                 label(move || {
                     let doc = doc.get();
                     if let DocContent::History(history) = doc.content.get() {
@@ -2003,6 +2678,69 @@ fn editor_breadcrumbs(
     })
 }
 
+/// The indices of the nested symbols that contain `position`, from the
+/// outermost symbol in.
+fn symbol_path(symbols: &[DocumentSymbol], position: Position) -> Vec<usize> {
+    let mut path = Vec::new();
+    let mut level = symbols;
+    while let Some(i) = level.iter().position(|symbol| {
+        symbol.range.start <= position && position <= symbol.range.end
+    }) {
+        path.push(i);
+        level = level[i].children.as_deref().unwrap_or(&[]);
+    }
+    path
+}
+
+/// The symbols on the same level as the last symbol of `path`.
+fn sibling_symbols<'a>(
+    symbols: &'a [DocumentSymbol],
+    path: &[usize],
+) -> &'a [DocumentSymbol] {
+    let mut level = symbols;
+    for i in &path[..path.len().saturating_sub(1)] {
+        level = level
+            .get(*i)
+            .and_then(|symbol| symbol.children.as_deref())
+            .unwrap_or(&[]);
+    }
+    level
+}
+
+/// Show a dropdown of the entries in `dir`. Picking a file opens it and
+/// picking a directory shows the entries in that directory.
+fn breadcrumb_dir_menu(common: Rc<CommonData>, dir: PathBuf) {
+    let internal_command = common.internal_command;
+    let scope = common.scope;
+    let send = create_ext_action(scope, move |mut items: Vec<FileNodeItem>| {
+        items.sort();
+        let mut menu = Menu::new("");
+        for item in items {
+            let Some(name) = item.path.file_name() else {
+                continue;
+            };
+            let name = name.to_string_lossy().into_owned();
+            let common = common.clone();
+            let FileNodeItem { path, is_dir, .. } = item;
+            let text = if is_dir { format!("{name}/") } else { name };
+            menu = menu.entry(MenuItem::new(text).action(move || {
+                if is_dir {
+                    breadcrumb_dir_menu(common.clone(), path.clone());
+                } else {
+                    internal_command
+                        .send(InternalCommand::OpenFile { path: path.clone() });
+                }
+            }));
+        }
+        show_context_menu(menu, None);
+    });
+    common.proxy.read_dir(dir, move |result| {
+        if let Ok(ProxyResponse::ReadDirResponse { items }) = result {
+            send(items);
+        }
+    });
+}
+
 fn editor_content(
     editor: RwSignal<Rc<EditorData>>,
     debug_breakline: Memo<Option<(usize, PathBuf)>>,
@@ -2015,6 +2753,7 @@ fn editor_content(
         window_origin,
         viewport,
         sticky_header_height,
+        hovered_link,
         config,
     ) = editor.with_untracked(|editor| {
         (
@@ -2024,6 +2763,7 @@ fn editor_content(
             editor.window_origin,
             editor.viewport,
             editor.sticky_header_height,
+            editor.hovered_link,
             editor.common.config,
         )
     });
@@ -2039,8 +2779,13 @@ fn editor_content(
                     } else {
                         0.0
                     };
+                    let cursor = if hovered_link.with(Option::is_some) {
+                        CursorStyle::Pointer
+                    } else {
+                        CursorStyle::Text
+                    };
                     s.padding_bottom(padding_bottom)
-                        .cursor(CursorStyle::Text)
+                        .cursor(cursor)
                         .min_size_pct(100.0, 100.0)
                 },
             );
@@ -2117,6 +2862,7 @@ fn editor_content(
 }
 
 fn search_editor_view(
+    editor: RwSignal<Rc<EditorData>>,
     find_editor: EditorData,
     find_focus: RwSignal<bool>,
     is_active: impl Fn(bool) -> bool + 'static + Copy,
@@ -2178,6 +2924,19 @@ fn search_editor_view(
             || false,
             config,
         )
+        .style(|s| s.padding_left(6.0)),
+        clickable_icon(
+            || LapceIcons::SEARCH_IN_SELECTION,
+            move || {
+                editor.get_untracked().toggle_find_in_selection();
+            },
+            move || {
+                let scope = editor.get().view.doc.get().find_result.scope;
+                scope.with(|scope| scope.is_some())
+            },
+            || false,
+            config,
+        )
         .style(|s| s.padding_horiz(6.0)),
     ))
     .style(move |s| {
@@ -2254,14 +3013,7 @@ fn find_view(
         let cursor = editor.cursor;
         let offset = cursor.with(|cursor| cursor.offset());
         let occurrences = editor.view.doc.get().find_result.occurrences;
-        occurrences.with(|occurrences| {
-            for (i, region) in occurrences.regions().iter().enumerate() {
-                if offset <= region.max() {
-                    return (i + 1, occurrences.regions().len());
-                }
-            }
-            (occurrences.regions().len(), occurrences.regions().len())
-        })
+        occurrences.with(|occurrences| occurrence_position(occurrences, offset))
     });
 
     container(
@@ -2284,6 +3036,7 @@ fn find_view(
                 )
                 .style(|s| s.padding_horiz(6.0)),
                 search_editor_view(
+                    editor,
                     find_editor,
                     find_focus,
                     is_active,
@@ -2292,12 +3045,18 @@ fn find_view(
                 label(move || {
                     let (current, all) = find_pos.get();
                     if all == 0 {
-                        "No Results".to_string()
+                        tr("find-no-results")
                     } else {
-                        format!("{current} of {all}")
+                        tr_args(
+                            "find-match-count",
+                            &[
+                                ("current", &current.to_string()),
+                                ("all", &all.to_string()),
+                            ],
+                        )
                     }
                 })
-                .style(|s| s.margin_left(6.0).min_width(70.0)),
+                .style(|s| s.margin_left(6.0).min_width(100.0)),
                 clickable_icon(
                     || LapceIcons::SEARCH_BACKWARD,
                     move || {
@@ -2413,6 +3172,21 @@ fn find_view(
     })
 }
 
+/// How much of the document the height of the scroll bar stands for.
+pub fn scroll_bar_content_height(
+    last_line: usize,
+    viewport_height: f64,
+    config: &LapceConfig,
+) -> f64 {
+    let line_height = config.editor.line_height() as f64;
+    let height = if config.editor.scroll_beyond_last_line {
+        last_line as f64 * line_height + viewport_height - line_height
+    } else {
+        last_line as f64 * line_height
+    };
+    height.max(line_height)
+}
+
 pub fn changes_colors(
     changes: im::Vector<DiffLines>,
     min_line: usize,
@@ -1,8 +1,12 @@
 use std::{cell::RefCell, collections::HashMap, rc::Rc, sync::Arc};
 
 use floem::{
-    cosmic_text::TextLayout,
-    peniko::{kurbo::Point, Color},
+    context::PaintCx,
+    cosmic_text::{AttrsList, TextLayout},
+    peniko::{
+        kurbo::{Point, Rect},
+        Color,
+    },
     reactive::{ReadSignal, RwSignal, Scope},
     views::VirtualListVector,
 };
@@ -10,18 +14,26 @@
     buffer::{
         diff::DiffLines,
         rope_text::{RopeText, RopeTextVal},
+        InvalLines,
     },
     char_buffer::CharBuffer,
+    chars::char_is_rtl,
     cursor::ColPosition,
     mode::Mode,
     soft_tab::{snap_to_soft_tab_line_col, SnapDirection},
+    syntax::SyntaxItem,
     word::WordCursor,
 };
+use lapce_rpc::style::LineStyle;
 use lapce_xi_rope::Rope;
+use once_cell::unsync::OnceCell;
 
 use crate::{
     config::LapceConfig,
-    doc::{phantom_text::PhantomTextLine, Document},
+    doc::{
+        phantom_text::{PhantomTextKind, PhantomTextLine},
+        Document,
+    },
     find::{Find, FindResult},
 };
 
@@ -44,44 +56,384 @@ pub struct TextLayoutLine {
     pub text: TextLayout,
     pub whitespaces: Option<Vec<(char, (f64, f64))>>,
     pub indent: f64,
+    /// Set instead of laying out all of `text` when the line is very long
+    pub long_line: Option<LongLineLayout>,
+}
+
+impl TextLayoutLine {
+    /// The point of the given column, `x` being the leading edge of the
+    /// character and `y` being the baseline.
+    pub fn hit_position(&self, col: usize) -> Point {
+        match &self.long_line {
+            Some(long_line) => long_line.hit_position(col),
+            None => self.text.hit_position(col).point,
+        }
+    }
+
+    /// The column at `x`, and whether `x` is inside of the text.
+    pub fn hit_point(&self, x: f64) -> (usize, bool) {
+        match &self.long_line {
+            Some(long_line) => long_line.hit_point(x),
+            None => {
+                let hit_point = self.text.hit_point(Point::new(x, 0.0));
+                (hit_point.index, hit_point.is_inside)
+            }
+        }
+    }
+
+    /// The horizontal ranges the columns from `start` to `end` are shown in. Text
+    /// in both directions can show a range of columns in more than one place,
+    /// so there is a range for each run of glyphs that are next to each other.
+    pub fn col_ranges(&self, start: usize, end: usize) -> Vec<(f64, f64)> {
+        if self.long_line.is_some() {
+            return vec![(self.hit_position(start).x, self.hit_position(end).x)];
+        }
+
+        let mut ranges = self
+            .text
+            .layout_runs()
+            .flat_map(|run| run.glyphs.iter())
+            .filter(|glyph| glyph.start < end && start < glyph.end)
+            .map(|glyph| (glyph.x as f64, (glyph.x + glyph.w) as f64))
+            .collect::<Vec<_>>();
+        ranges.sort_by(|a, b| a.0.total_cmp(&b.0));
+        ranges.dedup_by(|next, range| {
+            if next.0 - range.1 < 0.5 {
+                range.1 = range.1.max(next.1);
+                true
+            } else {
+                false
+            }
+        });
+        ranges
+    }
+
+    pub fn width(&self) -> f64 {
+        match &self.long_line {
+            Some(long_line) => long_line.width(),
+            None => self.text.size().width,
+        }
+    }
+
+    pub fn height(&self) -> f64 {
+        match &self.long_line {
+            Some(long_line) => long_line.height(),
+            None => self.text.size().height,
+        }
+    }
+
+    /// Draw the part of the line in the horizontal range `x0..x1` of it, with
+    /// the start of the line at `origin`. Only a long line is cut to the range.
+    pub fn draw(&self, cx: &mut PaintCx, x0: f64, x1: f64, origin: Point) {
+        match &self.long_line {
+            Some(long_line) => long_line.paint(cx, x0, x1, origin),
+            None => cx.draw_text(&self.text, origin),
+        }
+    }
+}
+
+/// Lines longer than this, in bytes, are laid out with a [`LongLineLayout`].
+pub const LONG_LINE_LENGTH: usize = 10_000;
+const LONG_LINE_CHUNK_LENGTH: usize = 1_000;
+
+/// The layout of a line that is too long to be shaped at once, like the lines
+/// of minified files and logs. The line is split into chunks which are only
+/// shaped once they are painted or hit tested. A chunk is placed after the
+/// shaped width of the chunks before it, so chunks are only shaped as far into
+/// the line as it has been scrolled.
+#[derive(Clone)]
+pub struct LongLineLayout {
+    content: String,
+    attrs_list: AttrsList,
+    tab_width: usize,
+    /// The start column of each chunk, the number of characters in it, and its
+    /// layout once it's shaped
+    chunks: Vec<(usize, usize, OnceCell<TextLayout>)>,
+    /// The `x` of the chunks that have been placed so far, which are the ones
+    /// up to the first chunk that wasn't shaped yet.
+    chunk_xs: RefCell<Vec<f64>>,
+    /// The width of a character, to estimate the width of the chunks that
+    /// aren't shaped yet.
+    char_width: f64,
+}
+
+impl LongLineLayout {
+    pub fn new(content: &str, attrs_list: AttrsList, tab_width: usize) -> Self {
+        let mut measure = TextLayout::new();
+        measure.set_text("W", attrs_list.clone());
+        let char_width = measure.hit_position(1).point.x;
+
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        let mut chars = 0;
+        for (i, c) in content.char_indices() {
+            if i - start >= LONG_LINE_CHUNK_LENGTH {
+                chunks.push((start, chars, OnceCell::new()));
+                start = i;
+                chars = 0;
+            }
+            chars += if c == '\t' { tab_width } else { 1 };
+        }
+        chunks.push((start, chars, OnceCell::new()));
+
+        Self {
+            content: content.to_string(),
+            attrs_list,
+            tab_width,
+            chunks,
+            chunk_xs: RefCell::new(vec![0.0]),
+            char_width,
+        }
+    }
+
+    pub fn layout(&self, chunk: usize) -> &TextLayout {
+        let (start, _, layout) = &self.chunks[chunk];
+        layout.get_or_init(|| {
+            let end = self
+                .chunks
+                .get(chunk + 1)
+                .map(|(start, _, _)| *start)
+                .unwrap_or(self.content.len());
+            let mut text_layout = TextLayout::new();
+            text_layout.set_tab_width(self.tab_width);
+            text_layout
+                .set_text(&self.content[*start..end], self.attrs_list.clone());
+            text_layout
+        })
+    }
+
+    /// The `x` of the chunk, shaping the chunks before it to place it.
+    fn chunk_x(&self, chunk: usize) -> f64 {
+        let mut xs = self.chunk_xs.borrow_mut();
+        while xs.len() <= chunk {
+            let prev = xs.len() - 1;
+            let x = xs[prev] + self.layout(prev).size().width;
+            xs.push(x);
+        }
+        xs[chunk]
+    }
+
+    fn chunk_of_col(&self, col: usize) -> usize {
+        self.chunks
+            .partition_point(|(start, _, _)| *start <= col)
+            .saturating_sub(1)
+    }
+
+    fn chunk_of_x(&self, x: f64) -> usize {
+        // Place chunks until one starts after `x`, or all of them are placed
+        let mut chunk = self.chunk_xs.borrow().len() - 1;
+        while chunk + 1 < self.chunks.len() && self.chunk_x(chunk) <= x {
+            chunk += 1;
+            self.chunk_x(chunk);
+        }
+        self.chunk_xs
+            .borrow()
+            .partition_point(|chunk_x| *chunk_x <= x)
+            .saturating_sub(1)
+    }
+
+    pub fn hit_position(&self, col: usize) -> Point {
+        let chunk = self.chunk_of_col(col);
+        let (start, _, _) = &self.chunks[chunk];
+        let x = self.chunk_x(chunk);
+        let point = self.layout(chunk).hit_position(col - start).point;
+        Point::new(point.x + x, point.y)
+    }
+
+    fn hit_point(&self, x: f64) -> (usize, bool) {
+        let chunk = self.chunk_of_x(x);
+        let (start, _, _) = &self.chunks[chunk];
+        let chunk_x = self.chunk_x(chunk);
+        let hit_point = self.layout(chunk).hit_point(Point::new(x - chunk_x, 0.0));
+        (start + hit_point.index, hit_point.is_inside)
+    }
+
+    /// The width of the line. The chunks that aren't placed yet are estimated,
+    /// so this grows more exact as more of the line is shown.
+    pub fn width(&self) -> f64 {
+        let placed = self.chunk_xs.borrow().len() - 1;
+        let x = self.chunk_x(placed);
+        let placed_width = x + self.layout(placed).size().width;
+        let estimated: usize = self.chunks[placed + 1..]
+            .iter()
+            .map(|(_, chars, _)| *chars)
+            .sum();
+        placed_width + estimated as f64 * self.char_width
+    }
+
+    /// The height of the tallest chunk that was shaped.
+    pub fn height(&self) -> f64 {
+        self.chunks
+            .iter()
+            .filter_map(|(_, _, layout)| layout.get())
+            .map(|layout| layout.size().height)
+            .fold(self.layout(0).size().height, f64::max)
+    }
+
+    /// Paint the chunks that are in the horizontal range `x0..x1` of the line,
+    /// with the start of the line at `origin`.
+    pub fn paint(&self, cx: &mut PaintCx, x0: f64, x1: f64, origin: Point) {
+        let first = self.chunk_of_x(x0);
+        let last = self.chunk_of_x(x1);
+        for chunk in first..=last {
+            let x = self.chunk_x(chunk);
+            cx.draw_text(self.layout(chunk), Point::new(origin.x + x, origin.y));
+        }
+    }
+}
+
+/// How many text layouts are kept, over all font sizes, before the ones that
+/// were used the longest ago are dropped. Only matters for very large files.
+const MAX_CACHED_LAYOUTS: usize = 4096;
+
+/// A text layout, with what it was laid out from.
+#[derive(Clone)]
+struct CachedLayout {
+    /// A hash of the content of the line.
+    content: u64,
+    /// The style generation at which `styles` were last the styles of the line.
+    style_rev: u64,
+    styles: Arc<Vec<LineStyle>>,
+    layout: Arc<TextLayoutLine>,
+    last_used: u64,
 }
 
-/// Keeps track of the text layouts so that we can efficiently reuse them.
+/// Keeps track of the text layouts of a document so that we can efficiently reuse
+/// them, for every view of the document.
+/// An edit moves the layouts of the lines after it rather than dropping them, and
+/// when the styles change only the lines whose styles are different are laid out
+/// again.
 #[derive(Clone, Default)]
 pub struct TextLayoutCache {
     /// The id of the last config, which lets us know when the config changes so we can update
     /// the cache.
     config_id: u64,
-    cache_rev: u64,
+    style_rev: u64,
+    /// Counts the lookups, to know which layouts were used the longest ago.
+    tick: u64,
     /// (Font Size -> (Line Number -> Text Layout))
     /// Different font-sizes are cached separately, which is useful for features like code lens
     /// where the text becomes small but you may wish to revert quickly.
-    pub layouts: HashMap<usize, HashMap<usize, Arc<TextLayoutLine>>>,
+    layouts: HashMap<usize, HashMap<usize, CachedLayout>>,
+    len: usize,
     pub max_width: f64,
 }
 
 impl TextLayoutCache {
     pub fn new() -> Self {
-        Self {
-            config_id: 0,
-            cache_rev: 0,
-            layouts: HashMap::new(),
-            max_width: 0.0,
-        }
+        Self::default()
     }
 
-    pub fn clear(&mut self, cache_rev: u64) {
+    pub fn clear(&mut self) {
         self.layouts.clear();
-        self.cache_rev = cache_rev;
+        self.len = 0;
         self.max_width = 0.0;
     }
 
     pub fn check_attributes(&mut self, config_id: u64) {
         if self.config_id != config_id {
-            self.clear(self.cache_rev + 1);
+            self.clear();
             self.config_id = config_id;
         }
     }
+
+    /// Start a new style generation. A layout from an older one is only used
+    /// again if its line still has the same styles.
+    pub fn invalidate_styles(&mut self) {
+        self.style_rev += 1;
+    }
+
+    /// Drop the layouts of the lines an edit changed, and move the ones of the
+    /// lines after it to their new line numbers.
+    pub fn apply_inval_lines(&mut self, inval_lines: &InvalLines) {
+        let start = inval_lines.start_line;
+        let end = start + inval_lines.inval_count;
+        for layouts in self.layouts.values_mut() {
+            *layouts = std::mem::take(layouts)
+                .into_iter()
+                .filter_map(|(line, cached)| {
+                    if line < start {
+                        Some((line, cached))
+                    } else if line >= end {
+                        let line =
+                            line - inval_lines.inval_count + inval_lines.new_count;
+                        Some((line, cached))
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+        }
+        self.len = self.layouts.values().map(HashMap::len).sum();
+    }
+
+    /// The layout of the line, if it was laid out from the same content. When the
+    /// style generation changed since, the current styles of the line are got from
+    /// `styles` and have to be the same too.
+    pub fn get(
+        &mut self,
+        font_size: usize,
+        line: usize,
+        content: u64,
+        styles: impl FnOnce() -> Arc<Vec<LineStyle>>,
+    ) -> Option<Arc<TextLayoutLine>> {
+        self.tick += 1;
+        let cached = self.layouts.get_mut(&font_size)?.get_mut(&line)?;
+        if cached.content != content {
+            return None;
+        }
+        if cached.style_rev != self.style_rev {
+            let styles = styles();
+            if styles != cached.styles {
+                return None;
+            }
+            cached.styles = styles;
+            cached.style_rev = self.style_rev;
+        }
+        cached.last_used = self.tick;
+        Some(cached.layout.clone())
+    }
+
+    pub fn insert(
+        &mut self,
+        font_size: usize,
+        line: usize,
+        content: u64,
+        styles: Arc<Vec<LineStyle>>,
+        layout: Arc<TextLayoutLine>,
+    ) {
+        self.tick += 1;
+        self.max_width = self.max_width.max(layout.width());
+        let cached = CachedLayout {
+            content,
+            style_rev: self.style_rev,
+            styles,
+            layout,
+            last_used: self.tick,
+        };
+        let layouts = self.layouts.entry(font_size).or_default();
+        if layouts.insert(line, cached).is_none() {
+            self.len += 1;
+        }
+        if self.len > MAX_CACHED_LAYOUTS {
+            self.evict();
+        }
+    }
+
+    /// Drop the quarter of the layouts that were used the longest ago.
+    fn evict(&mut self) {
+        let mut last_used: Vec<u64> = self
+            .layouts
+            .values()
+            .flat_map(|layouts| layouts.values().map(|cached| cached.last_used))
+            .collect();
+        let (_, threshold, _) = last_used.select_nth_unstable(self.len / 4);
+        let threshold = *threshold;
+        for layouts in self.layouts.values_mut() {
+            layouts.retain(|_, cached| cached.last_used > threshold);
+        }
+        self.len = self.layouts.values().map(HashMap::len).sum();
+    }
 }
 
 pub struct DocLine {
@@ -114,8 +466,6 @@ pub struct EditorViewData {
     /// Equivalent to the `EditorData::doc` that contains this view.
     pub doc: RwSignal<Rc<Document>>,
     pub kind: RwSignal<EditorViewKind>,
-    /// The text layouts for the document. This may be shared with other views.
-    pub text_layouts: Rc<RefCell<TextLayoutCache>>,
 
     pub config: ReadSignal<Arc<LapceConfig>>,
 }
@@ -151,7 +501,6 @@ pub fn new(
         EditorViewData {
             doc: cx.create_rw_signal(doc),
             kind: cx.create_rw_signal(kind),
-            text_layouts: Rc::new(RefCell::new(TextLayoutCache::new())),
             config,
         }
     }
@@ -174,7 +523,18 @@ pub fn text(&self) -> Rope {
     /// Unfortunately, we can't implement [`RopeText`] directly on [`EditorViewData`] due to
     /// it not having a reference to the rope.
     pub fn rope_text(&self) -> RopeTextVal {
-        RopeTextVal::new(self.text())
+        RopeTextVal::new(self.text()).with_word_chars(self.word_chars())
+    }
+
+    /// The characters that are part of words in the language of the document,
+    /// besides letters, digits and `_`.
+    pub fn word_chars(&self) -> String {
+        let language = self
+            .doc
+            .with_untracked(|doc| doc.syntax.with_untracked(|s| s.language));
+        self.config.with_untracked(|config| {
+            config.editor.word_characters(language).to_string()
+        })
     }
 
     /// Return the [`Document`]'s [`Find`] instance. Find uses signals, and so can be updated.
@@ -211,18 +571,15 @@ pub fn cache_rev(&self) -> u64 {
     /// The document for the given view was swapped out.
     pub fn update_doc(&self, doc: Rc<Document>) {
         self.doc.set(doc);
-        self.text_layouts.borrow_mut().clear(0);
     }
 
     /// Duplicate as a new view which refers to the same document.
     pub fn duplicate(&self, cx: Scope) -> Self {
-        // TODO: This is correct right now, as it has the views share the same text layout cache.
-        // However, once we have line wrapping or other view-specific rendering changes, this should check for whether they're different.
-        // This will likely require more information to be passed into duplicate,
-        // like whether the wrap width will be the editor's width, and so it is unlikely to be exactly the same as the current view.
+        // The views share the text layouts of the document. Once we have line
+        // wrapping or other view-specific rendering changes, this should check
+        // whether they're different.
         EditorViewData {
             doc: cx.create_rw_signal(self.doc.get_untracked()),
-            text_layouts: Rc::new(RefCell::new(TextLayoutCache::new())),
             kind: cx.create_rw_signal(self.kind.get_untracked()),
             config: self.config,
         }
@@ -232,67 +589,19 @@ pub fn line_phantom_text(&self, line: usize) -> PhantomTextLine {
         self.doc.with_untracked(|doc| doc.line_phantom_text(line))
     }
 
-    /// Get the text layout for the given line.
-    /// If the text layout is not cached, it will be created and cached.
+    /// Get the text layout for the given line, from the cache of the document.
     pub fn get_text_layout(
         &self,
         line: usize,
         font_size: usize,
     ) -> Arc<TextLayoutLine> {
-        {
-            let mut text_layouts = self.text_layouts.borrow_mut();
-            let cache_rev = self.doc.get_untracked().cache_rev.get_untracked();
-            if cache_rev != text_layouts.cache_rev {
-                text_layouts.clear(cache_rev);
-            }
-        }
-
-        // TODO: Should we just move the config cache check into `check_cache`?
-        let config = self.config.get_untracked();
-        // Check if the text layout needs to update due to the config being changed
-        self.text_layouts.borrow_mut().check_attributes(config.id);
-        // If we don't have a second layer of the hashmap initialized for this specific font size,
-        // do it now
-        if self.text_layouts.borrow().layouts.get(&font_size).is_none() {
-            let mut cache = self.text_layouts.borrow_mut();
-            cache.layouts.insert(font_size, HashMap::new());
-        }
-
-        // Get whether there's an entry for this specific font size and line
-        let cache_exists = self
-            .text_layouts
-            .borrow()
-            .layouts
-            .get(&font_size)
-            .unwrap()
-            .get(&line)
-            .is_some();
-        // If there isn't an entry then we actually have to create it
-        if !cache_exists {
-            let text_layout = self
-                .doc
-                .with_untracked(|doc| doc.get_text_layout(line, font_size));
-            let mut cache = self.text_layouts.borrow_mut();
-            let width = text_layout.text.size().width;
-            if width > cache.max_width {
-                cache.max_width = width;
-            }
-            cache
-                .layouts
-                .get_mut(&font_size)
-                .unwrap()
-                .insert(line, text_layout);
-        }
+        self.doc
+            .with_untracked(|doc| doc.get_text_layout(line, font_size))
+    }
 
-        // Just get the entry, assuming it has been created because we initialize it above.
-        self.text_layouts
-            .borrow()
-            .layouts
-            .get(&font_size)
-            .unwrap()
-            .get(&line)
-            .cloned()
-            .unwrap()
+    /// The width of the widest line that has been laid out.
+    pub fn max_width(&self) -> f64 {
+        self.doc.with_untracked(|doc| doc.max_text_layout_width())
     }
 
     pub fn indent_unit(&self) -> &'static str {
@@ -357,9 +666,7 @@ pub fn line_end_col(&self, line: usize, caret: bool) -> usize {
     }
 
     pub fn select_word(&self, offset: usize) -> (usize, usize) {
-        self.doc.with_untracked(|doc| {
-            doc.buffer.with_untracked(|b| b.select_word(offset))
-        })
+        self.rope_text().select_word(offset)
     }
 
     // ==== Points of locations ====
@@ -380,7 +687,7 @@ pub fn line_point_of_line_col(
         font_size: usize,
     ) -> Point {
         let text_layout = self.get_text_layout(line, font_size);
-        text_layout.text.hit_position(col).point
+        text_layout.hit_position(col)
     }
 
     /// Get the (point above, point below) of a particular offset within the editor.
@@ -420,8 +727,8 @@ pub fn points_of_line_col(&self, line: usize, col: usize) -> (Point, Point) {
                 let normal_text_layout =
                     self.get_text_layout(line, config.editor.font_size());
                 let small_text_layout = self.get_text_layout(line, font_size);
-                x_shift = normal_text_layout.text.hit_position(col).point.x
-                    - small_text_layout.text.hit_position(col).point.x;
+                x_shift = normal_text_layout.hit_position(col).x
+                    - small_text_layout.hit_position(col).x;
             }
         }
 
@@ -653,11 +960,11 @@ pub fn line_col_of_point(
         let line = line.min(self.last_line());
         let font_size = config.editor.font_size();
         let text_layout = self.get_text_layout(line, font_size);
-        let hit_point = text_layout.text.hit_point(Point::new(point.x, 0.0));
+        let (index, is_inside) = text_layout.hit_point(point.x);
         // We have to unapply the phantom text shifting in order to get back to the column in
         // the actual buffer
         let phantom_text = self.line_phantom_text(line);
-        let col = phantom_text.before_col(hit_point.index);
+        let col = phantom_text.before_col(index);
         // Ensure that the column doesn't end up out of bounds, so things like clicking on the far
         // right end will just go to the end of the line.
         let max_col = self.line_end_col(line, mode != Mode::Normal);
@@ -673,7 +980,37 @@ pub fn line_col_of_point(
             );
         }
 
-        ((line, col), hit_point.is_inside)
+        ((line, col), is_inside)
+    }
+
+    /// The kind of the phantom text at the point and the offset it is shown
+    /// before, if there is phantom text there.
+    pub fn phantom_text_of_point(
+        &self,
+        point: Point,
+    ) -> Option<(PhantomTextKind, usize)> {
+        let config = self.config.get_untracked();
+
+        let visual_line =
+            (point.y / config.editor.line_height() as f64).floor() as usize;
+        let line = self.actual_line(visual_line, true);
+        if line > self.last_line() {
+            return None;
+        }
+        let text_layout = self.get_text_layout(line, config.editor.font_size());
+        let (index, is_inside) = text_layout.hit_point(point.x);
+        if !is_inside {
+            return None;
+        }
+        let phantom_text = self.line_phantom_text(line);
+        let (_, _, col, phantom) =
+            phantom_text
+                .offset_size_iter()
+                .find(|(col_shift, size, col, _)| {
+                    let start = col + col_shift;
+                    (start..start + size).contains(&index)
+                })?;
+        Some((phantom.kind, self.offset_of_line_col(line, col)))
     }
 
     pub fn line_horiz_col(
@@ -686,8 +1023,7 @@ pub fn line_horiz_col(
         match *horiz {
             ColPosition::Col(x) => {
                 let text_layout = self.get_text_layout(line, font_size);
-                let hit_point = text_layout.text.hit_point(Point::new(x, 0.0));
-                let n = hit_point.index;
+                let (n, _) = text_layout.hit_point(x);
 
                 n.min(self.line_end_col(line, caret))
             }
@@ -717,6 +1053,62 @@ pub fn move_left(&self, offset: usize, mode: Mode, count: usize) -> usize {
         })
     }
 
+    /// Move `count` characters left or right on screen, rather than through the
+    /// text, on a line with right-to-left text in it. Lines that are all left
+    /// to right give `None`, as the two are the same for them.
+    pub fn move_visually(
+        &self,
+        offset: usize,
+        mode: Mode,
+        count: usize,
+        left: bool,
+    ) -> Option<usize> {
+        let line = self.line_of_offset(offset);
+        if !self.rope_text().line_content(line).chars().any(char_is_rtl) {
+            return None;
+        }
+
+        let phantom_text = self.line_phantom_text(line);
+        let text_layout = self.get_text_layout(line, FONT_SIZE);
+        let line_start = self.offset_of_line(line);
+        let x_of = |offset: usize| {
+            let col = phantom_text.col_after(offset - line_start, false);
+            text_layout.hit_position(col).x
+        };
+
+        // Every offset the caret can be at on the line, with where it is shown
+        let mut offsets = vec![(x_of(line_start), line_start)];
+        loop {
+            let (_, last) = offsets[offsets.len() - 1];
+            let next = self.move_right(last, mode, 1);
+            if next == last {
+                break;
+            }
+            offsets.push((x_of(next), next));
+        }
+
+        let mut new_offset = offset;
+        for _ in 0..count {
+            let x = x_of(new_offset);
+            let next = if left {
+                offsets
+                    .iter()
+                    .filter(|(next_x, _)| *next_x < x)
+                    .max_by(|a, b| a.0.total_cmp(&b.0))
+            } else {
+                offsets
+                    .iter()
+                    .filter(|(next_x, _)| *next_x > x)
+                    .min_by(|a, b| a.0.total_cmp(&b.0))
+            };
+            match next {
+                Some((_, next)) => new_offset = *next,
+                None => break,
+            }
+        }
+        Some(new_offset)
+    }
+
     /// Find the next/previous offset of the match of the given character.
     /// This is intended for use by the [`Movement::NextUnmatched`] and
     /// [`Movement::PreviousUnmatched`] commands.
@@ -744,6 +1136,31 @@ pub fn find_unmatched(&self, offset: usize, previous: bool, ch: char) -> usize {
         })
     }
 
+    /// Find the start of the `count`th next/previous function, class or top-level
+    /// item in the syntax tree.
+    /// This is intended for use by the [`Movement::NextItem`] and
+    /// [`Movement::PreviousItem`] commands.
+    pub fn find_item(
+        &self,
+        offset: usize,
+        item: SyntaxItem,
+        previous: bool,
+        count: usize,
+    ) -> usize {
+        self.doc.with_untracked(|doc| {
+            doc.syntax.with_untracked(|syntax| {
+                let mut offset = offset;
+                for _ in 0..count.max(1) {
+                    match syntax.find_item(offset, item, previous) {
+                        Some(new_offset) => offset = new_offset,
+                        None => break,
+                    }
+                }
+                offset
+            })
+        })
+    }
+
     /// Find the offset of the matching pair character.
     /// This is intended for use by the [`Movement::MatchPairs`] command.
     pub fn find_matching_pair(&self, offset: usize) -> usize {
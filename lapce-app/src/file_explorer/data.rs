@@ -6,45 +6,233 @@
 
 use floem::{
     ext_event::create_ext_action,
-    reactive::{RwSignal, Scope},
+    keyboard::ModifiersState,
+    reactive::{Memo, RwSignal, Scope},
 };
-use lapce_rpc::{file::FileNodeItem, proxy::ProxyResponse};
+use lapce_core::{
+    command::EditCommand, mode::Mode, register::Clipboard, selection::Selection,
+};
+use lapce_rpc::{
+    file::FileNodeItem,
+    proxy::{ProxyResponse, PATH_EXISTS_ERROR_CODE},
+    RpcError,
+};
+use lapce_xi_rope::Rope;
+use nucleo::Utf32Str;
 
-use crate::{command::InternalCommand, window_tab::CommonData};
+use crate::{
+    alert::AlertButton,
+    command::{CommandExecuted, CommandKind, InternalCommand},
+    doc::SystemClipboard,
+    editor::EditorData,
+    id::EditorId,
+    keypress::{condition::Condition, KeyPressFocus},
+    palette::char_indices_to_byte_indices,
+    window_tab::CommonData,
+};
+
+/// The most files the filter shows, the best matches are kept.
+const MAX_FILTER_MATCHES: usize = 1000;
+
+/// The tree of the files matching the filter, and the matched byte offsets in
+/// their names.
+#[derive(Clone, PartialEq)]
+pub struct FilteredFiles {
+    pub root: FileNodeItem,
+    pub indices: HashMap<PathBuf, Vec<usize>>,
+}
+
+/// A file or folder that was cut or copied in the explorer, to be pasted into a
+/// folder.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileClipboard {
+    pub path: PathBuf,
+    pub cut: bool,
+}
+
+/// What a copy or move does when something is already at its destination.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PathConflict {
+    /// Ask whether to replace it or to keep both.
+    Ask,
+    Replace,
+    /// Add " copy" and the number to the name, counting up until it is free.
+    KeepBoth(usize),
+}
 
 #[derive(Clone)]
 pub struct FileExplorerData {
     pub id: RwSignal<usize>,
     pub root: RwSignal<FileNodeItem>,
+    /// The file being dragged from the explorer, to be dropped on an editor tab.
+    pub dragging: RwSignal<Option<PathBuf>>,
+    pub clipboard: RwSignal<Option<FileClipboard>>,
+    pub filter_editor: EditorData,
+    pub filter: RwSignal<String>,
+    /// All the files in the workspace, loaded the first time the filter is used.
+    files: RwSignal<Option<Rc<Vec<PathBuf>>>>,
+    /// What the tree shows instead of `root` while there is a filter.
+    pub filtered: Memo<Option<FilteredFiles>>,
     pub common: Rc<CommonData>,
 }
 
+impl KeyPressFocus for FileExplorerData {
+    fn get_mode(&self) -> Mode {
+        Mode::Insert
+    }
+
+    fn check_condition(&self, condition: Condition) -> bool {
+        matches!(condition, Condition::PanelFocus)
+    }
+
+    fn run_command(
+        &self,
+        command: &crate::command::LapceCommand,
+        count: Option<usize>,
+        mods: ModifiersState,
+    ) -> CommandExecuted {
+        match &command.kind {
+            CommandKind::Edit(EditCommand::NormalMode) => {
+                self.clear_filter();
+                return CommandExecuted::Yes;
+            }
+            CommandKind::Workbench(_) => {}
+            CommandKind::Focus(_) => {}
+            CommandKind::Edit(_)
+            | CommandKind::Move(_)
+            | CommandKind::MultiSelection(_) => {
+                return self.filter_editor.run_command(command, count, mods);
+            }
+            CommandKind::MotionMode(_) => {}
+        }
+        CommandExecuted::No
+    }
+
+    fn receive_char(&self, c: &str) {
+        self.filter_editor.receive_char(c);
+    }
+}
+
 impl FileExplorerData {
     pub fn new(cx: Scope, common: Rc<CommonData>) -> Self {
-        let path = common.workspace.path.clone().unwrap_or_default();
-        let root = cx.create_rw_signal(FileNodeItem {
-            path: path.clone(),
-            is_dir: true,
-            read: false,
-            open: false,
-            children: HashMap::new(),
-            children_open_count: 0,
-        });
+        let roots = common.workspace.roots();
+        let root = cx.create_rw_signal(root_node(&roots));
+        let filter_editor =
+            EditorData::new_local(cx, EditorId::next(), common.clone());
+        let filter = cx.create_rw_signal(String::new());
+        {
+            let buffer = filter_editor.view.doc.get_untracked().buffer;
+            cx.create_effect(move |_| {
+                let content = buffer.with(|buffer| buffer.to_string());
+                filter.set(content);
+            });
+        }
+        let files: RwSignal<Option<Rc<Vec<PathBuf>>>> = cx.create_rw_signal(None);
+        let filtered = {
+            let roots = roots.clone();
+            cx.create_memo(move |_| {
+                let filter = filter.get();
+                if filter.trim().is_empty() {
+                    return None;
+                }
+                let files = files.get()?;
+                Some(filter_files(&roots, &files, &filter))
+            })
+        };
         let data = Self {
             id: cx.create_rw_signal(0),
             root,
+            dragging: cx.create_rw_signal(None),
+            clipboard: cx.create_rw_signal(None),
+            filter_editor,
+            filter,
+            files,
+            filtered,
             common,
         };
-        if data.common.workspace.path.is_some() {
-            // only fill in the child files if there is open folder
-            data.toggle_expand(&path);
+        {
+            let data = data.clone();
+            cx.create_effect(move |_| {
+                let active = !data.filter.with(|f| f.trim().is_empty());
+                if active && data.files.with_untracked(|f| f.is_none()) {
+                    data.load_files();
+                }
+            });
+        }
+        // only fill in the child files if there is open folder
+        for path in &roots {
+            data.toggle_expand(path);
         }
         data
     }
 
     pub fn reload(&self) {
-        let path = self.root.with_untracked(|root| root.path.clone());
-        self.read_dir(&path);
+        for path in self.common.workspace.roots() {
+            self.read_dir(&path);
+        }
+        self.files.set(None);
+        if !self.filter.with_untracked(|f| f.trim().is_empty()) {
+            self.load_files();
+        }
+    }
+
+    /// Get the files the filter searches from the proxy.
+    fn load_files(&self) {
+        let files = self.files;
+        let send = create_ext_action(self.common.scope, move |items| {
+            files.set(Some(Rc::new(items)));
+        });
+        self.common.proxy.get_files(move |result| {
+            if let Ok(ProxyResponse::GetFilesResponse { items }) = result {
+                send(items);
+            }
+        });
+    }
+
+    pub fn clear_filter(&self) {
+        let doc = self.filter_editor.view.doc.get_untracked();
+        doc.reload(Rope::from(""), true);
+        self.filter_editor
+            .cursor
+            .update(|cursor| cursor.set_insert(Selection::caret(0)));
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        if let Some(filtered) = self.filtered.get_untracked() {
+            return filtered
+                .root
+                .get_file_node(path)
+                .map(|n| n.is_dir)
+                .unwrap_or(false);
+        }
+        self.root
+            .with_untracked(|root| root.get_file_node(path).map(|n| n.is_dir))
+            .unwrap_or(false)
+    }
+
+    /// Read the folders that hold paths which were created, removed or renamed
+    /// again, as long as they have been expanded before. The rest of the tree is
+    /// left alone.
+    pub fn reload_paths(&self, paths: &[PathBuf]) {
+        let mut dirs: Vec<&Path> = paths.iter().filter_map(|p| p.parent()).collect();
+        dirs.sort();
+        dirs.dedup();
+        let dirs: Vec<&Path> = self.root.with_untracked(|root| {
+            dirs.into_iter()
+                .filter(|dir| {
+                    root.get_file_node(dir).map(|n| n.read).unwrap_or(false)
+                })
+                .collect()
+        });
+        for dir in dirs {
+            self.read_dir_with(dir, false);
+        }
+        if self.files.with_untracked(|f| f.is_some()) {
+            self.files.set(None);
+            if !self.filter.with_untracked(|f| f.trim().is_empty()) {
+                self.load_files();
+            }
+        }
     }
 
     pub fn toggle_expand(&self, path: &Path) {
@@ -77,6 +265,12 @@ pub fn toggle_expand(&self, path: &Path) {
     }
 
     pub fn read_dir(&self, path: &Path) {
+        self.read_dir_with(path, true);
+    }
+
+    /// Read the children of the folder. With `recursive`, the folders below it
+    /// that were read before are read again as well.
+    fn read_dir_with(&self, path: &Path, recursive: bool) {
         let root = self.root;
         let id = self.id;
         let data = self.clone();
@@ -103,7 +297,7 @@ pub fn read_dir(&self, path: &Path) {
                             for item in items {
                                 if let Some(existing) = node.children.get(&item.path)
                                 {
-                                    if existing.read {
+                                    if recursive && existing.read {
                                         data.read_dir(&existing.path);
                                     }
                                 } else {
@@ -124,11 +318,11 @@ pub fn read_dir(&self, path: &Path) {
     }
 
     pub fn click(&self, path: &Path) {
-        let is_dir = self
-            .root
-            .with_untracked(|root| root.get_file_node(path).map(|n| n.is_dir))
-            .unwrap_or(false);
+        let is_dir = self.is_dir(path);
         if is_dir {
+            if self.filtered.with_untracked(|f| f.is_some()) {
+                return;
+            }
             self.toggle_expand(path);
         } else {
             self.common
@@ -140,10 +334,7 @@ pub fn click(&self, path: &Path) {
     }
 
     pub fn double_click(&self, path: &Path) -> bool {
-        let is_dir = self
-            .root
-            .with_untracked(|root| root.get_file_node(path).map(|n| n.is_dir))
-            .unwrap_or(false);
+        let is_dir = self.is_dir(path);
         if is_dir {
             false
         } else {
@@ -154,11 +345,192 @@ pub fn double_click(&self, path: &Path) -> bool {
         }
     }
 
+    /// Put the path on the system clipboard. With `relative`, it is the path
+    /// inside its root folder.
+    pub fn copy_path(&self, path: &Path, relative: bool) {
+        let roots = self.common.workspace.roots();
+        let path = if relative {
+            roots
+                .iter()
+                .filter(|root| path.starts_with(root))
+                .max_by_key(|root| root.components().count())
+                .and_then(|root| path.strip_prefix(root).ok())
+                .unwrap_or(path)
+        } else {
+            path
+        };
+        let mut clipboard = SystemClipboard::new();
+        clipboard.put_string(path.to_string_lossy());
+    }
+
+    pub fn cut(&self, path: &Path) {
+        self.clipboard.set(Some(FileClipboard {
+            path: path.to_path_buf(),
+            cut: true,
+        }));
+    }
+
+    pub fn copy(&self, path: &Path) {
+        self.clipboard.set(Some(FileClipboard {
+            path: path.to_path_buf(),
+            cut: false,
+        }));
+    }
+
+    /// The folder that a paste or drop on the path goes into, which is the path
+    /// itself for a folder and the folder it is in for a file.
+    fn target_folder(&self, path: &Path) -> Option<PathBuf> {
+        if self.is_dir(path) {
+            Some(path.to_path_buf())
+        } else {
+            path.parent().map(Path::to_path_buf)
+        }
+    }
+
+    /// Paste the file or folder that was cut or copied into the folder of `path`.
+    pub fn paste(&self, path: &Path) {
+        let Some(clipboard) = self.clipboard.get_untracked() else {
+            return;
+        };
+        let Some(folder) = self.target_folder(path) else {
+            return;
+        };
+        let Some(name) = clipboard.path.file_name() else {
+            return;
+        };
+        let to = folder.join(name);
+        if to == clipboard.path {
+            if clipboard.cut {
+                self.clipboard.set(None);
+            } else {
+                // A copy pasted where it came from is a duplicate
+                self.duplicate(&to);
+            }
+            return;
+        }
+        self.transfer(clipboard.path, to, clipboard.cut, PathConflict::Ask);
+    }
+
+    pub fn duplicate(&self, path: &Path) {
+        self.transfer(
+            path.to_path_buf(),
+            path.to_path_buf(),
+            false,
+            PathConflict::KeepBoth(1),
+        );
+    }
+
+    /// Move the file that is being dragged into the folder of `path`.
+    pub fn drop_on(&self, path: &Path) {
+        let Some(from) = self.dragging.get_untracked() else {
+            return;
+        };
+        let Some(folder) = self.target_folder(path) else {
+            return;
+        };
+        let Some(name) = from.file_name() else {
+            return;
+        };
+        let to = folder.join(name);
+        if to == from || folder.starts_with(&from) {
+            return;
+        }
+        self.transfer(from, to, true, PathConflict::Ask);
+    }
+
+    /// Copy, or with `cut` move, `from` to `to` in the proxy.
+    fn transfer(
+        &self,
+        from: PathBuf,
+        to: PathBuf,
+        cut: bool,
+        conflict: PathConflict,
+    ) {
+        let destination = match conflict {
+            PathConflict::KeepBoth(n) => numbered_path(&to, n),
+            PathConflict::Ask | PathConflict::Replace => to.clone(),
+        };
+        let overwrite = conflict == PathConflict::Replace;
+        let source = from.clone();
+        let data = self.clone();
+        let send = create_ext_action(
+            self.common.scope,
+            move |result: Result<ProxyResponse, RpcError>| match result {
+                Ok(_) => {
+                    if cut {
+                        data.clipboard.update(|clipboard| {
+                            if clipboard.as_ref().map(|c| &c.path) == Some(&from) {
+                                *clipboard = None;
+                            }
+                        });
+                    }
+                }
+                Err(e) if e.code == PATH_EXISTS_ERROR_CODE => match conflict {
+                    PathConflict::Ask => data.ask_replace(from, to, cut),
+                    PathConflict::KeepBoth(n) => {
+                        data.transfer(from, to, cut, PathConflict::KeepBoth(n + 1))
+                    }
+                    PathConflict::Replace => {}
+                },
+                Err(e) => {
+                    let name = from
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    let action = if cut { "move" } else { "copy" };
+                    data.common
+                        .internal_command
+                        .send(InternalCommand::ShowAlert {
+                            title: format!("Couldn't {action} \"{name}\""),
+                            msg: e.message,
+                            buttons: Vec::new(),
+                        });
+                }
+            },
+        );
+        let f = move |result| send(result);
+        if cut {
+            self.common
+                .proxy
+                .move_path(source, destination, overwrite, f);
+        } else {
+            self.common
+                .proxy
+                .copy_path(source, destination, overwrite, f);
+        }
+    }
+
+    /// Ask what to do with a copy or move to where something already is.
+    fn ask_replace(&self, from: PathBuf, to: PathBuf, cut: bool) {
+        let internal_command = self.common.internal_command;
+        let button = |text: &str, conflict: PathConflict| {
+            let data = self.clone();
+            let from = from.clone();
+            let to = to.clone();
+            AlertButton {
+                text: text.to_string(),
+                action: Rc::new(move || {
+                    internal_command.send(InternalCommand::HideAlert);
+                    data.transfer(from.clone(), to.clone(), cut, conflict);
+                }),
+            }
+        };
+        let name = to
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        internal_command.send(InternalCommand::ShowAlert {
+            title: format!("\"{name}\" already exists in this folder"),
+            msg: "Do you want to replace it?".to_string(),
+            buttons: vec![
+                button("Replace", PathConflict::Replace),
+                button("Keep Both", PathConflict::KeepBoth(1)),
+            ],
+        });
+    }
+
     pub fn middle_click(&self, path: &Path) -> bool {
-        let is_dir = self
-            .root
-            .with_untracked(|root| root.get_file_node(path).map(|n| n.is_dir))
-            .unwrap_or(false);
+        let is_dir = self.is_dir(path);
         if is_dir {
             false
         } else {
@@ -171,3 +543,138 @@ pub fn middle_click(&self, path: &Path) -> bool {
         }
     }
 }
+
+/// `path` with " copy" added to the name, and the number as well from the second
+/// copy on.
+fn numbered_path(path: &Path, n: usize) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut name = if n > 1 {
+        format!("{stem} copy {n}")
+    } else {
+        format!("{stem} copy")
+    };
+    if let Some(extension) = path.extension() {
+        name.push('.');
+        name.push_str(&extension.to_string_lossy());
+    }
+    path.with_file_name(name)
+}
+
+/// The root of the tree. With more than one root folder, it is a node with an
+/// empty path that has the root folders as its children.
+fn root_node(roots: &[PathBuf]) -> FileNodeItem {
+    let folder = |path: PathBuf| FileNodeItem {
+        path,
+        is_dir: true,
+        read: false,
+        open: false,
+        ignored: false,
+        children: HashMap::new(),
+        children_open_count: 0,
+    };
+    if roots.len() <= 1 {
+        return folder(roots.first().cloned().unwrap_or_default());
+    }
+    let mut root = folder(PathBuf::new());
+    root.read = true;
+    root.open = true;
+    root.children = roots
+        .iter()
+        .map(|path| (path.clone(), folder(path.clone())))
+        .collect();
+    root
+}
+
+/// Match the filter against the file names and build the tree of the matches,
+/// with every folder on the way to them open.
+fn filter_files(
+    roots: &[PathBuf],
+    files: &[PathBuf],
+    filter: &str,
+) -> FilteredFiles {
+    let pattern = nucleo::pattern::Pattern::parse(
+        filter,
+        nucleo::pattern::CaseMatching::Ignore,
+    );
+    let mut matcher = nucleo::Matcher::new(nucleo::Config::DEFAULT);
+    let mut matches = Vec::new();
+    for path in files {
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let mut buf = Vec::new();
+        let mut indices = Vec::new();
+        let name_str = Utf32Str::new(name, &mut buf);
+        if let Some(score) = pattern.indices(name_str, &mut matcher, &mut indices) {
+            matches.push((score, path, char_indices_to_byte_indices(name, indices)));
+        }
+    }
+    matches.sort_by(|a, b| b.0.cmp(&a.0));
+    matches.truncate(MAX_FILTER_MATCHES);
+
+    let mut root = open_node(root_node(roots));
+    let mut indices = HashMap::new();
+    for (_, path, name_indices) in matches {
+        let Some(root_path) = roots
+            .iter()
+            .filter(|root| path.starts_with(root))
+            .max_by_key(|root| root.components().count())
+        else {
+            continue;
+        };
+        let Ok(relative) = path.strip_prefix(root_path) else {
+            continue;
+        };
+        let Some(mut node) = root.get_file_node_mut(root_path) else {
+            continue;
+        };
+        let components = relative.components().count();
+        let mut current = root_path.to_path_buf();
+        for (i, component) in relative.components().enumerate() {
+            current.push(component);
+            node = node.children.entry(current.clone()).or_insert_with(|| {
+                FileNodeItem {
+                    path: current.clone(),
+                    is_dir: i + 1 < components,
+                    read: true,
+                    open: true,
+                    ignored: false,
+                    children: HashMap::new(),
+                    children_open_count: 0,
+                }
+            });
+        }
+        indices.insert(path.clone(), name_indices);
+    }
+    if root.is_multi_root() {
+        root.children
+            .retain(|_, folder| !folder.children.is_empty());
+    }
+    update_open_count(&mut root);
+
+    FilteredFiles { root, indices }
+}
+
+/// Mark the node and the nodes below it as read and open.
+fn open_node(mut node: FileNodeItem) -> FileNodeItem {
+    node.read = true;
+    node.open = true;
+    node.children = node
+        .children
+        .into_iter()
+        .map(|(path, child)| (path, open_node(child)))
+        .collect();
+    node
+}
+
+fn update_open_count(node: &mut FileNodeItem) -> usize {
+    node.children_open_count = node
+        .children
+        .values_mut()
+        .map(|child| update_open_count(child) + 1)
+        .sum();
+    node.children_open_count
+}
@@ -1,28 +1,38 @@
-use std::rc::Rc;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
 use floem::{
     cosmic_text::Style as FontStyle,
     event::{Event, EventListener},
     peniko::Color,
-    reactive::{create_rw_signal, RwSignal},
+    reactive::{create_memo, create_rw_signal, Memo, RwSignal},
     style::{CursorStyle, Style},
     view::View,
     views::{
-        container, label, list, scroll, stack, svg, virtual_list, Decorators,
+        container, empty, label, list, scroll, stack, svg, virtual_list, Decorators,
         VirtualListDirection, VirtualListItemSize,
     },
     EventPropagation,
 };
+use lapce_rpc::source_control::FileDiff;
+use lsp_types::DiagnosticSeverity;
 
 use super::{data::FileExplorerData, node::FileNodeVirtualList};
 use crate::{
     app::clickable_icon,
     command::InternalCommand,
     config::{color::LapceColor, icon::LapceIcons},
+    context_menu::ContextMenu,
     editor_tab::{EditorTabChild, EditorTabData},
-    panel::{position::PanelPosition, view::panel_header},
+    focus_text::focus_text,
+    localization::tr,
+    panel::{kind::PanelKind, position::PanelPosition, view::panel_header},
     plugin::PluginData,
-    window_tab::WindowTabData,
+    text_input::text_input,
+    window_tab::{Focus, WindowTabData},
 };
 
 pub fn file_explorer_panel(
@@ -31,17 +41,19 @@ pub fn file_explorer_panel(
 ) -> impl View {
     let config = window_tab_data.common.config;
     let data = window_tab_data.file_explorer.clone();
+    let badges = file_badges(&window_tab_data);
     stack((
         stack((
-            panel_header("Open Editors".to_string(), config),
+            panel_header("panel-open-editors", config),
             container(open_editors_view(window_tab_data.clone()))
                 .style(|s| s.size_pct(100.0, 100.0)),
         ))
         .style(|s| s.width_pct(100.0).flex_col().height(150.0)),
         stack((
-            panel_header("File Explorer".to_string(), config),
+            panel_header("panel-file-explorer", config),
+            file_filter_view(window_tab_data.clone()),
             container(
-                scroll(new_file_node_view(data))
+                scroll(new_file_node_view(data, badges))
                     .style(|s| s.absolute().size_pct(100.0, 100.0)),
             )
             .style(|s| s.size_pct(100.0, 100.0).line_height(1.6)),
@@ -54,14 +66,132 @@ pub fn file_explorer_panel(
     })
 }
 
-fn new_file_node_view(data: FileExplorerData) -> impl View {
+fn file_filter_view(window_tab_data: Rc<WindowTabData>) -> impl View {
+    let config = window_tab_data.common.config;
+    let focus = window_tab_data.common.focus;
+    let editor = window_tab_data.file_explorer.filter_editor.clone();
+    let is_focused = move || focus.get() == Focus::Panel(PanelKind::FileExplorer);
+    container(
+        text_input(editor, is_focused)
+            .placeholder(|| "Filter Files".to_string())
+            .on_event_cont(EventListener::PointerDown, move |_| {
+                focus.set(Focus::Panel(PanelKind::FileExplorer));
+            })
+            .style(move |s| {
+                let config = config.get();
+                s.width_pct(100.0)
+                    .padding_vert(4.0)
+                    .padding_horiz(10.0)
+                    .cursor(CursorStyle::Text)
+                    .background(*config.get_color(LapceColor::EDITOR_BACKGROUND))
+                    .border(1.0)
+                    .border_radius(6.0)
+                    .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+            }),
+    )
+    .style(|s| s.padding_horiz(10.0).padding_vert(6.0).width_pct(100.0))
+}
+
+/// How a file or folder is decorated in the explorer. A folder gets the badges of
+/// everything inside it.
+#[derive(Clone, Copy, Default, PartialEq)]
+struct FileBadge {
+    git_color: Option<&'static str>,
+    errors: usize,
+    warnings: usize,
+}
+
+/// The paths the badge of `path` shows on, which is the path itself followed by
+/// the folders above it inside its root folder.
+fn badge_paths<'a>(
+    roots: &'a [PathBuf],
+    path: &'a Path,
+) -> impl Iterator<Item = &'a Path> {
+    path.ancestors().take_while(move |p| {
+        roots.is_empty()
+            || roots.iter().any(|root| p.starts_with(root) && *p != root)
+    })
+}
+
+fn file_badges(
+    window_tab_data: &WindowTabData,
+) -> Memo<HashMap<PathBuf, FileBadge>> {
+    let file_diffs = window_tab_data.source_control.file_diffs;
+    let diagnostics = window_tab_data.main_split.diagnostics;
+    let roots = window_tab_data.workspace.roots();
+    create_memo(move |_| {
+        let mut badges: HashMap<PathBuf, FileBadge> = HashMap::new();
+
+        file_diffs.with(|file_diffs| {
+            for (path, (diff, _)) in file_diffs {
+                let color = match diff {
+                    FileDiff::Added(_) => LapceColor::SOURCE_CONTROL_ADDED,
+                    FileDiff::Modified(_)
+                    | FileDiff::Deleted(_)
+                    | FileDiff::Renamed(_, _) => LapceColor::SOURCE_CONTROL_MODIFIED,
+                };
+                for p in badge_paths(&roots, path) {
+                    let badge = badges.entry(p.to_path_buf()).or_default();
+                    // A folder with both new and changed files shows as changed
+                    if badge.git_color.is_none()
+                        || color == LapceColor::SOURCE_CONTROL_MODIFIED
+                    {
+                        badge.git_color = Some(color);
+                    }
+                }
+            }
+        });
+
+        diagnostics.with(|diagnostics| {
+            for (path, data) in diagnostics.iter() {
+                let (errors, warnings) =
+                    data.diagnostics.with(|diagnostics| {
+                        diagnostics.iter().fold((0, 0), |(errors, warnings), d| {
+                            match d.diagnostic.severity {
+                                Some(DiagnosticSeverity::ERROR) => {
+                                    (errors + 1, warnings)
+                                }
+                                Some(DiagnosticSeverity::WARNING) => {
+                                    (errors, warnings + 1)
+                                }
+                                _ => (errors, warnings),
+                            }
+                        })
+                    });
+                if errors == 0 && warnings == 0 {
+                    continue;
+                }
+                for p in badge_paths(&roots, path) {
+                    let badge = badges.entry(p.to_path_buf()).or_default();
+                    badge.errors += errors;
+                    badge.warnings += warnings;
+                }
+            }
+        });
+
+        badges
+    })
+}
+
+fn new_file_node_view(
+    data: FileExplorerData,
+    badges: Memo<HashMap<PathBuf, FileBadge>>,
+) -> impl View {
     let root = data.root;
+    let filtered = data.filtered;
     let ui_line_height = data.common.ui_line_height;
     let config = data.common.config;
     virtual_list(
         VirtualListDirection::Vertical,
         VirtualListItemSize::Fixed(Box::new(move || ui_line_height.get())),
-        move || FileNodeVirtualList(root.get()),
+        move || {
+            FileNodeVirtualList(
+                filtered
+                    .get()
+                    .map(|filtered| filtered.root)
+                    .unwrap_or_else(|| root.get()),
+            )
+        },
         move |node| (node.path.clone(), node.is_dir, node.open, node.level),
         move |node| {
             let level = node.level;
@@ -72,8 +202,18 @@ fn new_file_node_view(data: FileExplorerData) -> impl View {
             let click_path = node.path.clone();
             let double_click_path = node.path.clone();
             let aux_click_path = path.clone();
+            let drag_path = path.clone();
+            let drop_data = data.clone();
+            let drop_path = path.clone();
+            let dragging = data.dragging;
+            let drag_over = create_rw_signal(false);
             let open = node.open;
             let is_dir = node.is_dir;
+            let ignored = node.ignored;
+            let badge = {
+                let path = path.clone();
+                move || badges.with(|badges| badges.get(&path).copied())
+            };
             stack((
                 svg(move || {
                     let config = config.get();
@@ -128,23 +268,87 @@ fn new_file_node_view(data: FileExplorerData) -> impl View {
                             })
                     })
                 },
-                label(move || {
-                    node.path
-                        .file_name()
-                        .map(|f| f.to_string_lossy().to_string())
-                        .unwrap_or_default()
+                {
+                    let path = path.clone();
+                    focus_text(
+                        move || {
+                            node.path
+                                .file_name()
+                                .map(|f| f.to_string_lossy().to_string())
+                                .unwrap_or_default()
+                        },
+                        move || {
+                            filtered.with(|filtered| {
+                                filtered
+                                    .as_ref()
+                                    .and_then(|f| f.indices.get(&path).cloned())
+                                    .unwrap_or_default()
+                            })
+                        },
+                        move || *config.get().get_color(LapceColor::EDITOR_FOCUS),
+                    )
+                }
+                .style({
+                    let badge = badge.clone();
+                    move |s| {
+                        let config = config.get();
+                        let badge = badge().unwrap_or_default();
+                        let color = if badge.errors > 0 {
+                            Some(LapceColor::LAPCE_ERROR)
+                        } else if badge.warnings > 0 {
+                            Some(LapceColor::LAPCE_WARN)
+                        } else if ignored {
+                            Some(LapceColor::EDITOR_DIM)
+                        } else {
+                            badge.git_color
+                        };
+                        s.apply_opt(color, |s, color| {
+                            s.color(*config.get_color(color))
+                        })
+                    }
                 }),
+                empty().style(|s| s.flex_grow(1.0)),
+                {
+                    let badge = badge.clone();
+                    label(move || {
+                        let badge = badge().unwrap_or_default();
+                        match (badge.errors, badge.warnings) {
+                            (0, 0) => String::new(),
+                            (0, warnings) => warnings.to_string(),
+                            (errors, _) => errors.to_string(),
+                        }
+                    })
+                    .style(move |s| {
+                        let config = config.get();
+                        let badge = badge().unwrap_or_default();
+                        let color = if badge.errors > 0 {
+                            LapceColor::LAPCE_ERROR
+                        } else {
+                            LapceColor::LAPCE_WARN
+                        };
+                        s.padding_left(10.0)
+                            .color(*config.get_color(color))
+                            .apply_if(
+                                badge.errors == 0 && badge.warnings == 0,
+                                |s| s.hide(),
+                            )
+                    })
+                },
             ))
             .style(move |s| {
+                let config = config.get();
                 s.items_center()
                     .padding_right(10.0)
                     .padding_left((level * 10) as f32)
                     .min_width_pct(100.0)
+                    .apply_if(drag_over.get(), |s| {
+                        s.background(
+                            *config.get_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                        )
+                    })
                     .hover(|s| {
                         s.background(
-                            *config
-                                .get()
-                                .get_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                            *config.get_color(LapceColor::PANEL_HOVERED_BACKGROUND),
                         )
                         .cursor(CursorStyle::Pointer)
                     })
@@ -163,14 +367,70 @@ fn new_file_node_view(data: FileExplorerData) -> impl View {
                 if let Event::PointerDown(pointer_event) = event {
                     if pointer_event.button.is_auxiliary() {
                         aux_click_data.middle_click(&aux_click_path);
+                    } else if pointer_event.button.is_secondary() {
+                        file_node_menu(&aux_click_data, &aux_click_path);
                     }
                 }
             })
+            .on_event_stop(EventListener::DragOver, move |_| {
+                if dragging.with_untracked(|dragging| dragging.is_some())
+                    && !drag_over.get_untracked()
+                {
+                    drag_over.set(true);
+                }
+            })
+            .on_event_stop(EventListener::DragLeave, move |_| {
+                drag_over.set(false);
+            })
+            .on_event_stop(EventListener::Drop, move |_| {
+                drag_over.set(false);
+                drop_data.drop_on(&drop_path);
+            })
+            .draggable()
+            .on_event_stop(EventListener::DragStart, move |_| {
+                if !is_dir {
+                    dragging.set(Some(drag_path.clone()));
+                }
+            })
+            .on_event_stop(EventListener::DragEnd, move |_| {
+                dragging.set(None);
+            })
         },
     )
     .style(|s| s.flex_col().min_width_pct(100.0))
 }
 
+fn file_node_menu(data: &FileExplorerData, path: &Path) {
+    let action = |f: fn(&FileExplorerData, &Path)| {
+        let data = data.clone();
+        let path = path.to_path_buf();
+        move || f(&data, &path)
+    };
+    let can_paste = data.clipboard.with_untracked(|c| c.is_some());
+    ContextMenu::new(data.common.lapce_command)
+        .action(tr("menu-cut"), action(|data, path| data.cut(path)))
+        .action(tr("menu-copy"), action(|data, path| data.copy(path)))
+        .action_enabled(
+            tr("menu-paste"),
+            can_paste,
+            action(|data, path| data.paste(path)),
+        )
+        .action(
+            tr("menu-duplicate"),
+            action(|data, path| data.duplicate(path)),
+        )
+        .separator()
+        .action(
+            tr("menu-copy-path"),
+            action(|data, path| data.copy_path(path, false)),
+        )
+        .action(
+            tr("menu-copy-relative-path"),
+            action(|data, path| data.copy_path(path, true)),
+        )
+        .show();
+}
+
 fn open_editors_view(window_tab_data: Rc<WindowTabData>) -> impl View {
     let diff_editors = window_tab_data.main_split.diff_editors;
     let editors = window_tab_data.main_split.editors;
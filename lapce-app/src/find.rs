@@ -2,18 +2,17 @@
 
 use floem::reactive::{RwSignal, Scope};
 use lapce_core::{
+    buffer::InvalLines,
+    search::RopeRegex,
     selection::{SelRegion, Selection},
     word::WordCursor,
 };
 use lapce_xi_rope::{
-    find::{find, is_multiline_regex, CaseMatching},
+    find::{find, CaseMatching},
     Cursor, Interval, Rope,
 };
-use regex::{Regex, RegexBuilder};
 use serde::{Deserialize, Serialize};
 
-const REGEX_SIZE_LIMIT: usize = 1000000;
-
 /// Indicates what changed in the find state.
 #[derive(PartialEq, Debug, Clone)]
 pub enum FindProgress {
@@ -54,7 +53,7 @@ pub struct FindStatus {
 #[derive(Clone)]
 pub struct FindSearchString {
     pub content: String,
-    pub regex: Option<Regex>,
+    pub regex: Option<RopeRegex>,
 }
 
 #[derive(Clone)]
@@ -174,11 +173,7 @@ pub fn set_find(&self, search_string: &str) {
         // create regex from untrusted input
         let regex = match is_regex {
             false => None,
-            true => RegexBuilder::new(search_string)
-                .size_limit(REGEX_SIZE_LIMIT)
-                .case_insensitive(!self.case_sensitive(false))
-                .build()
-                .ok(),
+            true => RopeRegex::new(search_string, !self.case_sensitive(false)).ok(),
         };
 
         self.search_string.set(Some(FindSearchString {
@@ -202,115 +197,145 @@ pub fn next(
         self.search_string.with_untracked(
             |search_string| -> Option<(usize, usize)> {
                 let search_string = search_string.as_ref()?;
+                let for_each_match =
+                    |start, end, f: &mut dyn FnMut(usize, usize) -> bool| {
+                        Self::for_each_match(
+                            text,
+                            search_string,
+                            start,
+                            end,
+                            case_matching,
+                            whole_words,
+                            f,
+                        )
+                    };
+                let mut found = None;
                 if !reverse {
-                    let mut raw_lines = text.lines_raw(offset..text.len());
-                    let mut find_cursor = Cursor::new(text, offset);
-                    while let Some(start) = find(
-                        &mut find_cursor,
-                        &mut raw_lines,
-                        case_matching,
-                        &search_string.content,
-                        search_string.regex.as_ref(),
-                    ) {
-                        let end = find_cursor.pos();
-
-                        if whole_words
-                            && !Self::is_matching_whole_words(text, start, end)
-                        {
-                            raw_lines =
-                                text.lines_raw(find_cursor.pos()..text.len());
-                            continue;
-                        }
-                        raw_lines = text.lines_raw(find_cursor.pos()..text.len());
-
+                    for_each_match(offset, text.len(), &mut |start, end| {
                         if start > offset {
-                            return Some((start, end));
-                        }
-                    }
-                    if wrap {
-                        let mut raw_lines = text.lines_raw(0..offset);
-                        let mut find_cursor = Cursor::new(text, 0);
-                        while let Some(start) = find(
-                            &mut find_cursor,
-                            &mut raw_lines,
-                            case_matching,
-                            &search_string.content,
-                            search_string.regex.as_ref(),
-                        ) {
-                            let end = find_cursor.pos();
-
-                            if whole_words
-                                && !Self::is_matching_whole_words(text, start, end)
-                            {
-                                raw_lines =
-                                    text.lines_raw(find_cursor.pos()..offset);
-                                continue;
-                            }
-                            return Some((start, end));
+                            found = Some((start, end));
+                            return false;
                         }
+                        true
+                    });
+                    if found.is_none() && wrap {
+                        for_each_match(0, offset, &mut |start, end| {
+                            found = Some((start, end));
+                            false
+                        });
                     }
                 } else {
-                    let mut raw_lines = text.lines_raw(0..offset);
-                    let mut find_cursor = Cursor::new(text, 0);
-                    let mut regions = Vec::new();
-                    while let Some(start) = find(
-                        &mut find_cursor,
-                        &mut raw_lines,
-                        case_matching,
-                        &search_string.content,
-                        search_string.regex.as_ref(),
-                    ) {
-                        let end = find_cursor.pos();
-                        raw_lines = text.lines_raw(find_cursor.pos()..offset);
-                        if whole_words
-                            && !Self::is_matching_whole_words(text, start, end)
-                        {
-                            continue;
-                        }
+                    for_each_match(0, offset, &mut |start, end| {
                         if start < offset {
-                            regions.push((start, end));
+                            found = Some((start, end));
                         }
-                    }
-                    if !regions.is_empty() {
-                        return Some(regions[regions.len() - 1]);
-                    }
-                    if wrap {
-                        let mut raw_lines = text.lines_raw(offset..text.len());
-                        let mut find_cursor = Cursor::new(text, offset);
-                        let mut regions = Vec::new();
-                        while let Some(start) = find(
-                            &mut find_cursor,
-                            &mut raw_lines,
-                            case_matching,
-                            &search_string.content,
-                            search_string.regex.as_ref(),
-                        ) {
-                            let end = find_cursor.pos();
-
-                            if whole_words
-                                && !Self::is_matching_whole_words(text, start, end)
-                            {
-                                raw_lines =
-                                    text.lines_raw(find_cursor.pos()..text.len());
-                                continue;
-                            }
-                            raw_lines =
-                                text.lines_raw(find_cursor.pos()..text.len());
-
+                        true
+                    });
+                    if found.is_none() && wrap {
+                        for_each_match(offset, text.len(), &mut |start, end| {
                             if start > offset {
-                                regions.push((start, end));
+                                found = Some((start, end));
                             }
-                        }
-                        if !regions.is_empty() {
-                            return Some(regions[regions.len() - 1]);
-                        }
+                            true
+                        });
                     }
                 }
-                None
+                found
             },
         )
     }
 
+    /// Like [`Find::next`], but only for the occurrences within the regions of
+    /// the scope.
+    pub fn next_in_scope(
+        &self,
+        text: &Rope,
+        scope: &Selection,
+        offset: usize,
+        reverse: bool,
+        wrap: bool,
+    ) -> Option<(usize, usize)> {
+        if !self.visual.get_untracked() {
+            self.visual.set(true);
+        }
+        let mut occurrences = Selection::new();
+        for region in scope.regions() {
+            self.update_find(
+                text,
+                region.min(),
+                region.max(),
+                false,
+                &mut occurrences,
+            );
+        }
+        let occurrences = occurrences_in_scope(&occurrences, scope);
+        let regions = occurrences.regions();
+        let found = if !reverse {
+            regions
+                .iter()
+                .find(|region| region.min() > offset)
+                .or_else(|| regions.first().filter(|_| wrap))
+        } else {
+            regions
+                .iter()
+                .rev()
+                .find(|region| region.min() < offset)
+                .or_else(|| regions.last().filter(|_| wrap))
+        };
+        found.map(|region| (region.min(), region.max()))
+    }
+
+    /// Call `f` with every occurrence in the range, in order, for as long as it
+    /// returns `true`.
+    fn for_each_match(
+        text: &Rope,
+        search: &FindSearchString,
+        start: usize,
+        end: usize,
+        case_matching: CaseMatching,
+        whole_words: bool,
+        f: &mut dyn FnMut(usize, usize) -> bool,
+    ) {
+        let is_word = |start: usize, end: usize| {
+            start < end && Self::is_matching_whole_words(text, start, end)
+        };
+
+        if let Some(regex) = search.regex.as_ref() {
+            for m in regex.find_iter(text, start..end) {
+                if whole_words && !is_word(m.start, m.end) {
+                    continue;
+                }
+                if !f(m.start, m.end) {
+                    return;
+                }
+            }
+            return;
+        }
+
+        let mut to_cursor = Cursor::new(text, end);
+        let _ = to_cursor.next_leaf();
+        let sub_text = text.subseq(Interval::new(0, to_cursor.pos()));
+        let mut find_cursor = Cursor::new(&sub_text, start);
+        let mut raw_lines = text.lines_raw(start..end);
+        while let Some(match_start) = find(
+            &mut find_cursor,
+            &mut raw_lines,
+            case_matching,
+            &search.content,
+            None,
+        ) {
+            let match_end = find_cursor.pos();
+            // update line iterator so that line starts at current cursor position
+            raw_lines = text.lines_raw(find_cursor.pos()..end);
+            if whole_words && !is_word(match_start, match_end) {
+                continue;
+            }
+            if !f(match_start, match_end) {
+                return;
+            }
+        }
+    }
+
     /// Checks if the start and end of a match is matching whole words.
     fn is_matching_whole_words(text: &Rope, start: usize, end: usize) -> bool {
         let mut word_end_cursor = WordCursor::new(text, end - 1);
@@ -327,11 +352,13 @@ fn is_matching_whole_words(text: &Rope, start: usize, end: usize) -> bool {
         true
     }
 
-    /// Returns `true` if the search query is a multi-line regex.
-    pub fn is_multiline_regex(&self) -> bool {
+    /// Returns `true` if the search query can match across lines, which is when
+    /// it contains a newline or is a regex. Regexes are searched over the whole
+    /// rope, so classes like `\s` or `[^;]` can match a line break too.
+    pub fn is_multiline(&self) -> bool {
         self.search_string.with_untracked(|search| {
             if let Some(search) = search.as_ref() {
-                search.regex.is_some() && is_multiline_regex(&search.content)
+                search.regex.is_some() || search.content.contains('\n')
             } else {
                 false
             }
@@ -349,8 +376,8 @@ pub fn find(
         include_slop: bool,
         occurrences: &mut Selection,
     ) {
-        let search_string = &search.content;
-
+        // extend the search by twice the string length (twice, because case matching may increase
+        // the length of an occurrence)
         let slop = if include_slop {
             search.content.len() * 2
         } else {
@@ -366,58 +393,21 @@ pub fn find(
         let to = text
             .at_or_next_codepoint_boundary(expanded_end)
             .unwrap_or_else(|| text.len());
-        let mut to_cursor = Cursor::new(text, to);
-        let _ = to_cursor.next_leaf();
-
-        let sub_text = text.subseq(Interval::new(0, to_cursor.pos()));
-        let mut find_cursor = Cursor::new(&sub_text, from);
-
-        let mut raw_lines = text.lines_raw(from..to);
 
-        while let Some(start) = find(
-            &mut find_cursor,
-            &mut raw_lines,
+        Self::for_each_match(
+            text,
+            search,
+            from,
+            to,
             case_matching,
-            search_string,
-            search.regex.as_ref(),
-        ) {
-            let end = find_cursor.pos();
-
-            if whole_words && !Self::is_matching_whole_words(text, start, end) {
-                raw_lines = text.lines_raw(find_cursor.pos()..to);
-                continue;
-            }
-
-            let region = SelRegion::new(start, end, None);
-            let (_, e) = occurrences.add_range_distinct(region);
-            // in case of ambiguous search results (e.g. search "aba" in "ababa"),
-            // the search result closer to the beginning of the file wins
-            if e != end {
-                // Skip the search result and keep the occurrence that is closer to
-                // the beginning of the file. Re-align the cursor to the kept
-                // occurrence
-                find_cursor.set(e);
-                raw_lines = text.lines_raw(find_cursor.pos()..to);
-                continue;
-            }
-
-            // in case current cursor matches search result (for example query a* matches)
-            // all cursor positions, then cursor needs to be increased so that search
-            // continues at next position. Otherwise, search will result in overflow since
-            // search will always repeat at current cursor position.
-            if start == end {
-                // determine whether end of text is reached and stop search or increase
-                // cursor manually
-                if end + 1 >= text.len() {
-                    break;
-                } else {
-                    find_cursor.set(end + 1);
-                }
-            }
-
-            // update line iterator so that line starts at current cursor position
-            raw_lines = text.lines_raw(find_cursor.pos()..to);
-        }
+            whole_words,
+            &mut |start, end| {
+                // in case of ambiguous search results (e.g. search "aba" in "ababa"),
+                // the search result closer to the beginning of the file wins
+                occurrences.add_range_distinct(SelRegion::new(start, end, None));
+                true
+            },
+        );
     }
 
     /// Execute the search on the provided text in the range provided by `start` and `end`.
@@ -429,83 +419,19 @@ pub fn update_find(
         include_slop: bool,
         occurrences: &mut Selection,
     ) {
-        if self.search_string.with_untracked(|search| search.is_none()) {
+        let Some(search) = self.search_string.get_untracked() else {
             return;
-        }
-
-        let search = self.search_string.get_untracked().unwrap();
-        let search_string = &search.content;
-        // extend the search by twice the string length (twice, because case matching may increase
-        // the length of an occurrence)
-        let slop = if include_slop {
-            search.content.len() * 2
-        } else {
-            0
         };
-
-        // expand region to be able to find occurrences around the region's edges
-        let expanded_start = max(start, slop) - slop;
-        let expanded_end = min(end + slop, text.len());
-        let from = text
-            .at_or_prev_codepoint_boundary(expanded_start)
-            .unwrap_or(0);
-        let to = text
-            .at_or_next_codepoint_boundary(expanded_end)
-            .unwrap_or_else(|| text.len());
-        let mut to_cursor = Cursor::new(text, to);
-        let _ = to_cursor.next_leaf();
-
-        let sub_text = text.subseq(Interval::new(0, to_cursor.pos()));
-        let mut find_cursor = Cursor::new(&sub_text, from);
-
-        let mut raw_lines = text.lines_raw(from..to);
-
-        let case_matching = self.case_matching.get_untracked();
-        let whole_words = self.whole_words.get_untracked();
-        while let Some(start) = find(
-            &mut find_cursor,
-            &mut raw_lines,
-            case_matching,
-            search_string,
-            search.regex.as_ref(),
-        ) {
-            let end = find_cursor.pos();
-
-            if whole_words && !Self::is_matching_whole_words(text, start, end) {
-                raw_lines = text.lines_raw(find_cursor.pos()..to);
-                continue;
-            }
-
-            let region = SelRegion::new(start, end, None);
-            let (_, e) = occurrences.add_range_distinct(region);
-            // in case of ambiguous search results (e.g. search "aba" in "ababa"),
-            // the search result closer to the beginning of the file wins
-            if e != end {
-                // Skip the search result and keep the occurrence that is closer to
-                // the beginning of the file. Re-align the cursor to the kept
-                // occurrence
-                find_cursor.set(e);
-                raw_lines = text.lines_raw(find_cursor.pos()..to);
-                continue;
-            }
-
-            // in case current cursor matches search result (for example query a* matches)
-            // all cursor positions, then cursor needs to be increased so that search
-            // continues at next position. Otherwise, search will result in overflow since
-            // search will always repeat at current cursor position.
-            if start == end {
-                // determine whether end of text is reached and stop search or increase
-                // cursor manually
-                if end + 1 >= text.len() {
-                    break;
-                } else {
-                    find_cursor.set(end + 1);
-                }
-            }
-
-            // update line iterator so that line starts at current cursor position
-            raw_lines = text.lines_raw(find_cursor.pos()..to);
-        }
+        Self::find(
+            text,
+            &search,
+            start,
+            end,
+            self.case_matching.get_untracked(),
+            self.whole_words.get_untracked(),
+            include_slop,
+            occurrences,
+        );
     }
 }
 
@@ -518,6 +444,12 @@ pub struct FindResult {
     pub case_matching: RwSignal<CaseMatching>,
     pub whole_words: RwSignal<bool>,
     pub is_regex: RwSignal<bool>,
+    /// The lines edits changed since the occurrences were found, which are
+    /// searched again rather than the whole document. `(start, end)`, with `end`
+    /// exclusive.
+    pub dirty_lines: RwSignal<Option<(usize, usize)>>,
+    /// The selection the search is limited to, if it is.
+    pub scope: RwSignal<Option<Selection>>,
 }
 
 impl FindResult {
@@ -530,10 +462,99 @@ pub fn new(cx: Scope) -> Self {
             case_matching: cx.create_rw_signal(CaseMatching::Exact),
             whole_words: cx.create_rw_signal(false),
             is_regex: cx.create_rw_signal(false),
+            dirty_lines: cx.create_rw_signal(None),
+            scope: cx.create_rw_signal(None),
         }
     }
 
     pub fn reset(&self) {
         self.progress.set(FindProgress::Started);
+        self.dirty_lines.set(None);
+    }
+
+    /// Mark the lines an edit changed as needing to be searched again, and move
+    /// the ones marked before to where they are after it.
+    pub fn invalidate_lines(&self, inval_lines: &InvalLines) {
+        let start = inval_lines.start_line;
+        let old_end = start + inval_lines.inval_count;
+        let new_end = start + inval_lines.new_count;
+        let shift = |line: usize| {
+            if line >= old_end {
+                line - inval_lines.inval_count + inval_lines.new_count
+            } else {
+                line.min(new_end)
+            }
+        };
+        self.dirty_lines.update(|dirty| {
+            *dirty = Some(match *dirty {
+                Some((dirty_start, dirty_end)) => {
+                    (shift(dirty_start).min(start), shift(dirty_end).max(new_end))
+                }
+                None => (start, new_end),
+            });
+        });
+    }
+}
+
+/// The occurrences that are within the regions of the scope.
+pub fn occurrences_in_scope(
+    occurrences: &Selection,
+    scope: &Selection,
+) -> Selection {
+    let mut in_scope = Selection::new();
+    for region in occurrences.regions() {
+        let (start, end) = (region.min(), region.max());
+        if scope
+            .regions_in_range(start, end)
+            .iter()
+            .any(|scope| scope.min() <= start && end <= scope.max())
+        {
+            in_scope.add_range_distinct(*region);
+        }
+    }
+    in_scope
+}
+
+/// Which of the occurrences the offset is on, or the next one after it,
+/// counting from one, and how many occurrences there are.
+pub fn occurrence_position(
+    occurrences: &Selection,
+    offset: usize,
+) -> (usize, usize) {
+    let regions = occurrences.regions();
+    let before = regions.partition_point(|region| region.max() < offset);
+    ((before + 1).min(regions.len()), regions.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selection(regions: &[(usize, usize)]) -> Selection {
+        let mut selection = Selection::new();
+        for (start, end) in regions {
+            selection.add_range_distinct(SelRegion::new(*start, *end, None));
+        }
+        selection
+    }
+
+    #[test]
+    fn test_occurrences_in_scope() {
+        let occurrences = selection(&[(0, 3), (5, 8), (10, 13), (20, 23)]);
+        let scope = selection(&[(4, 12), (18, 30)]);
+        assert_eq!(
+            occurrences_in_scope(&occurrences, &scope),
+            selection(&[(5, 8), (20, 23)])
+        );
+    }
+
+    #[test]
+    fn test_occurrence_position() {
+        let occurrences = selection(&[(0, 3), (5, 8), (10, 13)]);
+        assert_eq!(occurrence_position(&occurrences, 0), (1, 3));
+        assert_eq!(occurrence_position(&occurrences, 6), (2, 3));
+        assert_eq!(occurrence_position(&occurrences, 9), (3, 3));
+        assert_eq!(occurrence_position(&occurrences, 20), (3, 3));
+        assert_eq!(occurrence_position(&Selection::new(), 0), (0, 0));
     }
 }
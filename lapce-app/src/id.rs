@@ -25,3 +25,4 @@ pub fn to_raw(self) -> u64 {
 pub type EditorId = Id;
 pub type DiffEditorId = Id;
 pub type TerminalTabId = Id;
+pub type NotificationId = Id;
@@ -0,0 +1,268 @@
+//! What was searched for, replaced with and typed in the palette, kept for each
+//! workspace across sessions, and stepped back through with Up and Down.
+
+use std::sync::Arc;
+
+use floem::reactive::{use_context, ReadSignal, RwSignal, Scope};
+use lapce_core::{command::FocusCommand, selection::Selection};
+use lapce_xi_rope::Rope;
+
+use crate::{
+    command::{CommandKind, LapceCommand},
+    config::LapceConfig,
+    db::LapceDb,
+    editor::EditorData,
+    workspace::LapceWorkspace,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputHistoryKind {
+    Palette,
+    Search,
+    Replace,
+}
+
+impl InputHistoryKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InputHistoryKind::Palette => "palette",
+            InputHistoryKind::Search => "search",
+            InputHistoryKind::Replace => "replace",
+        }
+    }
+}
+
+/// The entries of a history, newest first, and how far back in them the input
+/// was stepped.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InputHistory {
+    entries: Vec<String>,
+    /// The entry the input shows, if it was stepped back into the history.
+    index: Option<usize>,
+    /// What was typed before stepping back, shown again after the newest entry.
+    draft: String,
+}
+
+impl InputHistory {
+    pub fn new(entries: Vec<String>) -> Self {
+        Self {
+            entries,
+            index: None,
+            draft: String::new(),
+        }
+    }
+
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    /// Add the text as the newest entry, keeping at most `max` of them.
+    pub fn add(&mut self, text: &str, max: usize) {
+        self.index = None;
+        if text.is_empty() {
+            return;
+        }
+        self.entries.retain(|entry| entry != text);
+        self.entries.insert(0, text.to_string());
+        self.entries.truncate(max);
+    }
+
+    /// Whether the input shows the entry it was stepped to, without edits.
+    pub fn is_browsing(&self, current: &str) -> bool {
+        self.index
+            .and_then(|index| self.entries.get(index))
+            .is_some_and(|entry| entry == current)
+    }
+
+    /// The entry older than the one the input shows, or the newest one if the
+    /// input wasn't stepped into the history or was edited since.
+    pub fn previous(&mut self, current: &str) -> Option<String> {
+        let index = match self.index {
+            Some(index) if self.is_browsing(current) => index + 1,
+            _ => 0,
+        };
+        let entry = self.entries.get(index)?.clone();
+        if index == 0 {
+            self.draft = current.to_string();
+        }
+        self.index = Some(index);
+        Some(entry)
+    }
+
+    /// The entry newer than the one the input shows, or what was typed before
+    /// stepping back after the newest one.
+    pub fn next(&mut self, current: &str) -> Option<String> {
+        if !self.is_browsing(current) {
+            return None;
+        }
+        match self.index? {
+            0 => {
+                self.index = None;
+                Some(std::mem::take(&mut self.draft))
+            }
+            index => {
+                self.index = Some(index - 1);
+                self.entries.get(index - 1).cloned()
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// A history of an input of the workspace, saved to the database as it changes.
+#[derive(Clone)]
+pub struct InputHistoryData {
+    kind: InputHistoryKind,
+    history: RwSignal<InputHistory>,
+    workspace: Arc<LapceWorkspace>,
+    config: ReadSignal<Arc<LapceConfig>>,
+}
+
+impl InputHistoryData {
+    pub fn new(
+        cx: Scope,
+        kind: InputHistoryKind,
+        workspace: Arc<LapceWorkspace>,
+        config: ReadSignal<Arc<LapceConfig>>,
+    ) -> Self {
+        let entries = use_context::<Arc<LapceDb>>()
+            .and_then(|db| db.get_input_history(&workspace, kind).ok())
+            .unwrap_or_default();
+        Self {
+            kind,
+            history: cx.create_rw_signal(InputHistory::new(entries)),
+            workspace,
+            config,
+        }
+    }
+
+    pub fn add(&self, text: &str) {
+        let max = self
+            .config
+            .with_untracked(|config| config.core.input_history_size);
+        self.history.update(|history| history.add(text, max));
+        self.save();
+    }
+
+    pub fn clear(&self) {
+        self.history.update(|history| history.clear());
+        self.save();
+    }
+
+    /// Whether the input of the editor shows the entry it was stepped to.
+    pub fn is_browsing(&self, editor: &EditorData) -> bool {
+        let text = editor_text(editor);
+        self.history
+            .with_untracked(|history| history.is_browsing(&text))
+    }
+
+    /// Show the older entry in the input of the editor, returning false if there
+    /// is none.
+    pub fn previous(&self, editor: &EditorData) -> bool {
+        let text = editor_text(editor);
+        let entry = self
+            .history
+            .try_update(|history| history.previous(&text))
+            .flatten();
+        self.show(editor, entry)
+    }
+
+    /// Show the newer entry in the input of the editor, returning false if it
+    /// wasn't stepped back into the history.
+    pub fn next(&self, editor: &EditorData) -> bool {
+        let text = editor_text(editor);
+        let entry = self
+            .history
+            .try_update(|history| history.next(&text))
+            .flatten();
+        self.show(editor, entry)
+    }
+
+    /// Run the command if it steps through the history, returning whether it
+    /// did.
+    pub fn run_command(&self, editor: &EditorData, command: &LapceCommand) -> bool {
+        match &command.kind {
+            CommandKind::Focus(FocusCommand::HistoryPrevious) => {
+                self.previous(editor);
+                true
+            }
+            CommandKind::Focus(FocusCommand::HistoryNext) => {
+                self.next(editor);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn show(&self, editor: &EditorData, entry: Option<String>) -> bool {
+        let Some(entry) = entry else {
+            return false;
+        };
+        let len = entry.len();
+        editor
+            .view
+            .doc
+            .get_untracked()
+            .reload(Rope::from(entry), true);
+        editor
+            .cursor
+            .update(|cursor| cursor.set_insert(Selection::caret(len)));
+        true
+    }
+
+    fn save(&self) {
+        if let Some(db) = use_context::<Arc<LapceDb>>() {
+            let entries = self
+                .history
+                .with_untracked(|history| history.entries().to_vec());
+            db.save_input_history(self.workspace.clone(), self.kind, entries);
+        }
+    }
+}
+
+fn editor_text(editor: &EditorData) -> String {
+    editor
+        .view
+        .doc
+        .get_untracked()
+        .buffer
+        .with_untracked(|buffer| buffer.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_entries() {
+        let mut history = InputHistory::default();
+        history.add("foo", 2);
+        history.add("bar", 2);
+        history.add("", 2);
+        history.add("foo", 2);
+        history.add("baz", 2);
+        assert_eq!(history.entries(), &["baz", "foo"]);
+    }
+
+    #[test]
+    fn test_step_through_entries() {
+        let mut history =
+            InputHistory::new(vec!["new".to_string(), "old".to_string()]);
+        assert_eq!(history.next("typed"), None);
+        assert_eq!(history.previous("typed").as_deref(), Some("new"));
+        assert_eq!(history.previous("new").as_deref(), Some("old"));
+        assert_eq!(history.previous("old"), None);
+        assert_eq!(history.next("old").as_deref(), Some("new"));
+        assert_eq!(history.next("new").as_deref(), Some("typed"));
+        assert_eq!(history.next("typed"), None);
+
+        // An edited entry starts over from the newest one
+        assert_eq!(history.previous("typed").as_deref(), Some("new"));
+        assert!(history.is_browsing("new"));
+        assert!(!history.is_browsing("newer"));
+        assert_eq!(history.previous("newer").as_deref(), Some("new"));
+    }
+}
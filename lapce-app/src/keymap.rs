@@ -13,6 +13,7 @@
         VirtualListDirection, VirtualListItemSize,
     },
 };
+use itertools::Itertools;
 use lapce_core::mode::Modes;
 
 use crate::{
@@ -59,11 +60,8 @@ pub fn keymap_view(common: Rc<CommonData>) -> impl View {
                 let cmd = keypress.commands.get(&keymap.command).cloned()?;
                 let match_pattern =
                     cmd.kind.str().replace('_', " ").contains(&pattern)
-                        || cmd
-                            .kind
-                            .desc()
-                            .map(|desc| desc.to_lowercase().contains(&pattern))
-                            .unwrap_or(false);
+                        || cmd.kind.name().to_lowercase().contains(&pattern)
+                        || keymap_label(keymap).contains(&pattern);
                 if !match_pattern {
                     return None;
                 }
@@ -72,11 +70,7 @@ pub fn keymap_view(common: Rc<CommonData>) -> impl View {
             .collect::<im::Vector<(LapceCommand, Option<KeyMap>)>>();
         items.extend(keypress.commands_without_keymap.iter().filter_map(|cmd| {
             let match_pattern = cmd.kind.str().replace('_', " ").contains(&pattern)
-                || cmd
-                    .kind
-                    .desc()
-                    .map(|desc| desc.to_lowercase().contains(&pattern))
-                    .unwrap_or(false);
+                || cmd.kind.name().to_lowercase().contains(&pattern);
             if !match_pattern {
                 return None;
             }
@@ -92,23 +86,25 @@ pub fn keymap_view(common: Rc<CommonData>) -> impl View {
         move |(i, (cmd, keymap)): (usize, (LapceCommand, Option<KeyMap>))| {
             let local_keymap = keymap.clone();
             let local_cmd = cmd.clone();
+            // Keys that conflict with another binding are shown in the error color
+            let conflict = keymap
+                .as_ref()
+                .map(|keymap| {
+                    !keypress
+                        .get_untracked()
+                        .conflicting_commands(keymap)
+                        .is_empty()
+                })
+                .unwrap_or(false);
             stack((
-                container(
-                    text(
-                        cmd.kind
-                            .desc()
-                            .map(|desc| desc.to_string())
-                            .unwrap_or_else(|| cmd.kind.str().replace('_', " ")),
-                    )
-                    .style(|s| {
-                        s.text_ellipsis()
-                            .absolute()
-                            .items_center()
-                            .min_width(0.0)
-                            .padding_horiz(10.0)
-                            .size_pct(100.0, 100.0)
-                    }),
-                )
+                container(text(cmd.kind.name()).style(|s| {
+                    s.text_ellipsis()
+                        .absolute()
+                        .items_center()
+                        .min_width(0.0)
+                        .padding_horiz(10.0)
+                        .size_pct(100.0, 100.0)
+                }))
                 .style(move |s| {
                     s.height_pct(100.0)
                         .min_width(0.0)
@@ -138,16 +134,20 @@ pub fn keymap_view(common: Rc<CommonData>) -> impl View {
                         |k| k.clone(),
                         move |key| {
                             text(key.clone()).style(move |s| {
+                                let config = config.get();
                                 s.padding_horiz(5.0)
                                     .padding_vert(1.0)
                                     .margin_right(5.0)
                                     .border(1.0)
                                     .border_radius(3.0)
                                     .border_color(
-                                        *config
-                                            .get()
-                                            .get_color(LapceColor::LAPCE_BORDER),
+                                        *config.get_color(LapceColor::LAPCE_BORDER),
                                     )
+                                    .apply_if(conflict, |s| {
+                                        let color = config
+                                            .get_color(LapceColor::LAPCE_ERROR);
+                                        s.border_color(*color).color(*color)
+                                    })
                             })
                         },
                     )
@@ -352,7 +352,7 @@ pub fn keymap_view(common: Rc<CommonData>) -> impl View {
             .style(|s| s.absolute().size_pct(100.0, 100.0)),
         )
         .style(|s| s.width_pct(100.0).flex_basis(0.0).flex_grow(1.0)),
-        keyboard_picker_view(picker, common.ui_line_height, config),
+        keyboard_picker_view(picker, keypress, common.ui_line_height, config),
     ))
     .style(|s| {
         s.absolute()
@@ -364,8 +364,18 @@ pub fn keymap_view(common: Rc<CommonData>) -> impl View {
     })
 }
 
+/// The keys of the keymap as they are typed in the search box, like `ctrl+k ctrl+s`.
+fn keymap_label(keymap: &KeyMap) -> String {
+    keymap
+        .key
+        .iter()
+        .map(|key| key.to_string().to_lowercase())
+        .join(" ")
+}
+
 fn keyboard_picker_view(
     picker: KeymapPicker,
+    keypress: RwSignal<KeyPressData>,
     ui_line_height: Memo<f64>,
     config: ReadSignal<Arc<LapceConfig>>,
 ) -> impl View {
@@ -374,14 +384,7 @@ fn keyboard_picker_view(
         stack((
             label(move || {
                 picker_cmd.with(|cmd| {
-                    cmd.as_ref()
-                        .map(|cmd| {
-                            cmd.kind
-                                .desc()
-                                .map(|desc| desc.to_string())
-                                .unwrap_or_else(|| cmd.kind.str().replace('_', " "))
-                        })
-                        .unwrap_or_default()
+                    cmd.as_ref().map(|cmd| cmd.kind.name()).unwrap_or_default()
                 })
             }),
             list(
@@ -488,6 +491,50 @@ fn keyboard_picker_view(
                     .on_click_stop(move |_| {
                         picker.keymap.set(None);
                     }),
+                text("Reset")
+                    .style(move |s| {
+                        let config = config.get();
+                        let customized = picker.keymap.with(|keymap| {
+                            keymap
+                                .as_ref()
+                                .map(|keymap| {
+                                    keypress.with(|keypress| {
+                                        keypress.customized.contains(&keymap.command)
+                                    })
+                                })
+                                .unwrap_or(false)
+                        });
+                        s.margin_left(20.0)
+                            .width(100.0)
+                            .justify_center()
+                            .padding_vert(8.0)
+                            .border(1.0)
+                            .border_radius(6.0)
+                            .border_color(
+                                *config.get_color(LapceColor::LAPCE_BORDER),
+                            )
+                            .hover(|s| {
+                                s.cursor(CursorStyle::Pointer).background(
+                                    *config.get_color(
+                                        LapceColor::PANEL_HOVERED_BACKGROUND,
+                                    ),
+                                )
+                            })
+                            .active(|s| {
+                                s.background(*config.get_color(
+                                    LapceColor::PANEL_HOVERED_ACTIVE_BACKGROUND,
+                                ))
+                            })
+                            .apply_if(!customized, |s| s.hide())
+                    })
+                    .on_click_stop(move |_| {
+                        // Drop what the keymaps file changes about the command so
+                        // the default keymaps are used again
+                        if let Some(keymap) = picker.keymap.get_untracked() {
+                            picker.keymap.set(None);
+                            KeyPressData::reset_file(&keymap.command);
+                        }
+                    }),
             ))
             .style(move |s| {
                 let config = config.get();
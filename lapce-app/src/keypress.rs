@@ -4,7 +4,7 @@
 mod loader;
 mod press;
 
-use std::{path::PathBuf, rc::Rc, str::FromStr};
+use std::{collections::HashSet, path::PathBuf, rc::Rc, str::FromStr};
 
 use anyhow::Result;
 use floem::{
@@ -17,12 +17,17 @@
 use lapce_core::mode::{Mode, Modes};
 use tracing::{debug, error};
 
-use self::{key::KeyInput, keymap::KeyMap, loader::KeyMapLoader};
+use self::{
+    key::KeyInput,
+    keymap::{find_conflicts, KeyMap},
+    loader::KeyMapLoader,
+};
 use crate::{
     command::{lapce_internal_commands, CommandExecuted, CommandKind, LapceCommand},
-    config::LapceConfig,
+    config::{core::KeymapPreset, LapceConfig},
+    crash,
     keypress::{
-        condition::{CheckCondition, Condition},
+        condition::{parse_language_condition, CheckCondition, Condition},
         keymap::KeymapMatch,
     },
 };
@@ -35,6 +40,8 @@
     include_str!("../../defaults/keymaps-macos.toml");
 const DEFAULT_KEYMAPS_NONMACOS: &str =
     include_str!("../../defaults/keymaps-nonmacos.toml");
+const DEFAULT_KEYMAPS_EMACS: &str =
+    include_str!("../../defaults/keymaps-emacs.toml");
 
 pub trait KeyPressFocus {
     fn get_mode(&self) -> Mode;
@@ -56,6 +63,12 @@ fn focus_only(&self) -> bool {
         false
     }
 
+    /// The language of the focused document, for `language == <name>` in the
+    /// `when` of a keymap.
+    fn language(&self) -> Option<&'static str> {
+        None
+    }
+
     fn receive_char(&self, c: &str);
 }
 
@@ -86,6 +99,10 @@ pub struct KeyPressData {
     pub command_keymaps: Rc<IndexMap<String, Vec<KeyMap>>>,
     pub commands_with_keymap: Rc<Vec<KeyMap>>,
     pub commands_without_keymap: Rc<Vec<LapceCommand>>,
+    /// Keymaps for different commands that share keys in the same context.
+    pub conflicts: Rc<Vec<(KeyMap, KeyMap)>>,
+    /// Commands that are bound or unbound in the user's keymaps file.
+    pub customized: Rc<HashSet<String>>,
 }
 
 impl KeyPressData {
@@ -100,6 +117,8 @@ pub fn new(cx: Scope, config: &LapceConfig) -> Self {
             commands: Rc::new(lapce_internal_commands()),
             commands_with_keymap: Rc::new(Vec::new()),
             commands_without_keymap: Rc::new(Vec::new()),
+            conflicts: Rc::new(Vec::new()),
+            customized: Rc::new(HashSet::new()),
         };
         keypress.load_commands();
         keypress
@@ -132,6 +151,36 @@ fn load_commands(&mut self) {
 
         self.commands_with_keymap = Rc::new(commands_with_keymap);
         self.commands_without_keymap = Rc::new(commands_without_keymap);
+        self.conflicts = Rc::new(find_conflicts(&self.command_keymaps));
+        self.customized = Rc::new(
+            Self::get_file_array()
+                .map(|array| {
+                    array
+                        .iter()
+                        .filter_map(|value| value.get("command")?.as_str())
+                        .map(|command| {
+                            command.trim().trim_start_matches('-').to_string()
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        );
+    }
+
+    /// The commands whose keymaps conflict with the keymap.
+    pub fn conflicting_commands(&self, keymap: &KeyMap) -> Vec<String> {
+        self.conflicts
+            .iter()
+            .filter_map(|(a, b)| {
+                if a == keymap {
+                    Some(b.command.clone())
+                } else if b == keymap {
+                    Some(a.command.clone())
+                } else {
+                    None
+                }
+            })
+            .collect()
     }
 
     fn handle_count<T: KeyPressFocus>(
@@ -172,7 +221,13 @@ fn run_command<T: KeyPressFocus>(
         focus: &T,
     ) -> CommandExecuted {
         if let Some(cmd) = self.commands.get(command) {
-            focus.run_command(cmd, count, mods)
+            let executed = focus.run_command(cmd, count, mods);
+            // A key goes to every focus in turn until one runs its command, so
+            // only the one that ran it records it
+            if executed == CommandExecuted::Yes {
+                crash::record_command(command);
+            }
+            executed
         } else {
             CommandExecuted::No
         }
@@ -371,7 +426,13 @@ fn check_one_condition<T: KeyPressFocus>(
             check: &T,
         ) -> bool {
             let trimmed = condition.trim();
-            if let Some(stripped) = trimmed.strip_prefix('!') {
+            if let Some((language, equal)) = parse_language_condition(trimmed) {
+                let matches = check
+                    .language()
+                    .map(|l| l.eq_ignore_ascii_case(language))
+                    .unwrap_or(false);
+                matches == equal
+            } else if let Some(stripped) = trimmed.strip_prefix('!') {
                 if let Ok(condition) = Condition::from_str(stripped) {
                     !check.check_condition(condition)
                 } else {
@@ -428,6 +489,12 @@ fn get_keymaps(
             error!("Failed to load OS defaults: {err}");
         }
 
+        if config.core.keymap_preset == KeymapPreset::Emacs {
+            if let Err(err) = loader.load_from_str(DEFAULT_KEYMAPS_EMACS, is_modal) {
+                error!("Failed to load Emacs keymaps: {err}");
+            }
+        }
+
         if let Some(path) = Self::file() {
             if let Ok(content) = std::fs::read_to_string(&path) {
                 if let Err(err) = loader.load_from_str(&content, is_modal) {
@@ -529,6 +596,27 @@ pub fn update_file(keymap: &KeyMap, keys: &[KeyPress]) -> Option<()> {
             }
         }
 
+        Self::write_file_array(array)
+    }
+
+    /// Remove everything the keymaps file changes about the command, so it gets
+    /// its default keymaps back.
+    pub fn reset_file(command: &str) -> Option<()> {
+        let mut array = toml_edit::ArrayOfTables::new();
+        for value in Self::get_file_array()?.iter() {
+            let is_command = value
+                .get("command")
+                .and_then(|c| c.as_str())
+                .map(|c| c.trim().trim_start_matches('-') == command)
+                .unwrap_or(false);
+            if !is_command {
+                array.push(value.clone());
+            }
+        }
+        Self::write_file_array(array)
+    }
+
+    fn write_file_array(array: toml_edit::ArrayOfTables) -> Option<()> {
         let mut table = toml_edit::Document::new();
         table.insert("keymaps", toml_edit::Item::ArrayOfTables(array));
         let path = Self::file()?;
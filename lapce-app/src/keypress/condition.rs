@@ -37,6 +37,22 @@ pub(super) fn parse_first(condition: &'a str) -> Self {
     }
 }
 
+/// Parse a `language == rust` or `language != rust` condition into the language
+/// and whether it has to be the language of the focused document.
+pub(super) fn parse_language_condition(condition: &str) -> Option<(&str, bool)> {
+    let rest = condition.strip_prefix("language")?.trim_start();
+    let (rest, equal) = if let Some(rest) = rest.strip_prefix("==") {
+        (rest, true)
+    } else {
+        (rest.strip_prefix("!=")?, false)
+    };
+    let language = rest.trim();
+    if language.is_empty() {
+        return None;
+    }
+    Some((language, equal))
+}
+
 #[derive(EnumString, PartialEq, Eq)]
 pub enum Condition {
     #[strum(serialize = "editor_focus")]
@@ -49,10 +65,16 @@ pub enum Condition {
     PaletteFocus,
     #[strum(serialize = "completion_focus")]
     CompletionFocus,
+    /// Only Tab accepts the completion item, as set by the
+    /// `completion-accept-on-enter` setting.
+    #[strum(serialize = "completion_tab_only")]
+    CompletionTabOnly,
     #[strum(serialize = "modal_focus")]
     ModalFocus,
     #[strum(serialize = "in_snippet")]
     InSnippet,
+    #[strum(serialize = "motion_mode")]
+    MotionMode,
     #[strum(serialize = "terminal_focus")]
     TerminalFocus,
     #[strum(serialize = "source_control_focus")]
@@ -61,6 +83,8 @@ pub enum Condition {
     PanelFocus,
     #[strum(serialize = "rename_focus")]
     RenameFocus,
+    #[strum(serialize = "peek_focus")]
+    PeekFocus,
     #[strum(serialize = "search_active")]
     SearchActive,
     #[strum(serialize = "search_focus")]
@@ -79,6 +103,7 @@ mod test {
 
     struct MockFocus {
         accepted_conditions: &'static [Condition],
+        language: Option<&'static str>,
     }
 
     impl KeyPressFocus for MockFocus {
@@ -86,6 +111,10 @@ fn check_condition(&self, condition: Condition) -> bool {
             self.accepted_conditions.contains(&condition)
         }
 
+        fn language(&self) -> Option<&'static str> {
+            self.language
+        }
+
         fn get_mode(&self) -> Mode {
             unimplemented!()
         }
@@ -128,6 +157,7 @@ fn test_parse() {
     fn test_check_condition() {
         let focus = MockFocus {
             accepted_conditions: &[Condition::EditorFocus, Condition::ListFocus],
+            language: None,
         };
 
         let test_cases = [
@@ -154,4 +184,38 @@ fn test_check_condition() {
             );
         }
     }
+
+    #[test]
+    fn test_check_language_condition() {
+        let focus = MockFocus {
+            accepted_conditions: &[Condition::EditorFocus],
+            language: Some("Rust"),
+        };
+
+        let test_cases = [
+            ("language == rust", true),
+            ("language==Rust", true),
+            ("language != rust", false),
+            ("language == go", false),
+            ("language != go", true),
+            ("editor_focus && language == rust", true),
+            ("editor_focus && language == go", false),
+            ("language == go || editor_focus", true),
+        ];
+
+        for (condition, should_accept) in test_cases.into_iter() {
+            assert_eq!(
+                should_accept,
+                KeyPressData::check_condition(condition, &focus),
+                "Condition check failed. Condition: {condition}. Expected result: {should_accept}",
+            );
+        }
+
+        let focus = MockFocus {
+            accepted_conditions: &[],
+            language: None,
+        };
+        assert!(!KeyPressData::check_condition("language == rust", &focus));
+        assert!(KeyPressData::check_condition("language != rust", &focus));
+    }
 }
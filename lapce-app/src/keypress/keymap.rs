@@ -1,3 +1,4 @@
+use indexmap::IndexMap;
 use lapce_core::mode::Modes;
 
 use super::KeyPress;
@@ -17,3 +18,40 @@ pub struct KeyMap {
     pub when: Option<String>,
     pub command: String,
 }
+
+impl KeyMap {
+    /// Whether the keymaps can be pressed at the same time, which is when they
+    /// share a mode and have the same `when` condition.
+    fn same_context(&self, other: &KeyMap) -> bool {
+        let modes = self.modes.is_empty()
+            || other.modes.is_empty()
+            || self.modes.intersects(other.modes);
+        let when = self.when.as_deref().map(str::trim);
+        modes && when == other.when.as_deref().map(str::trim)
+    }
+}
+
+/// Pairs of keymaps for different commands that get in each other's way. They
+/// either have the same keys, or the keys of one start with all the keys of the
+/// other, which then never runs.
+pub fn find_conflicts(
+    command_keymaps: &IndexMap<String, Vec<KeyMap>>,
+) -> Vec<(KeyMap, KeyMap)> {
+    let keymaps: Vec<&KeyMap> = command_keymaps
+        .values()
+        .flatten()
+        .filter(|keymap| !keymap.key.is_empty())
+        .collect();
+    let mut conflicts = Vec::new();
+    for (i, a) in keymaps.iter().enumerate() {
+        for b in &keymaps[i + 1..] {
+            if a.command == b.command || !a.same_context(b) {
+                continue;
+            }
+            if a.key.starts_with(&b.key) || b.key.starts_with(&a.key) {
+                conflicts.push(((*a).clone(), (*b).clone()));
+            }
+        }
+    }
+    conflicts
+}
@@ -130,6 +130,7 @@ fn get_keymap(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::keypress::keymap::find_conflicts;
 
     #[test]
     fn test_keymap() {
@@ -227,6 +228,59 @@ fn test_keymap() {
         let keypress = KeyPress::parse("Ctrl+MouseMiddle");
         assert_eq!(keymaps.get(&keypress).unwrap().len(), 1);
     }
+
+    #[test]
+    fn test_keymap_conflicts() {
+        let keymaps = r#"
+[[keymaps]]
+key = "ctrl+k"
+command = "first"
+
+[[keymaps]]
+key = "ctrl+k ctrl+s"
+command = "second"
+
+[[keymaps]]
+key = "ctrl+j"
+command = "editor_down"
+when = "editor_focus"
+
+[[keymaps]]
+key = "ctrl+j"
+command = "list_down"
+when = "list_focus"
+
+[[keymaps]]
+key = "ctrl+l"
+command = "insert_mode"
+mode = "i"
+
+[[keymaps]]
+key = "ctrl+l"
+command = "normal_mode"
+mode = "n"
+
+[[keymaps]]
+key = "ctrl+m"
+command = "third"
+mode = "nv"
+
+[[keymaps]]
+key = "ctrl+m"
+command = "fourth"
+mode = "v"
+        "#;
+        let mut loader = KeyMapLoader::new();
+        loader.load_from_str(keymaps, true).unwrap();
+
+        let (_, command_keymaps) = loader.finalize();
+        let conflicts = find_conflicts(&command_keymaps);
+        let conflicts: Vec<(&str, &str)> = conflicts
+            .iter()
+            .map(|(a, b)| (a.command.as_str(), b.command.as_str()))
+            .collect();
+        assert_eq!(conflicts, vec![("first", "second"), ("third", "fourth")]);
+    }
 }
 
 fn get_modes(toml_keymap: &toml_edit::Table) -> Modes {
@@ -2,9 +2,12 @@
 pub mod alert;
 pub mod app;
 pub mod code_action;
+pub mod color_picker;
 pub mod command;
 pub mod completion;
 pub mod config;
+pub mod context_menu;
+pub mod crash;
 pub mod db;
 pub mod debug;
 pub mod doc;
@@ -17,20 +20,29 @@
 pub mod history;
 pub mod hover;
 pub mod id;
+pub mod input_history;
 pub mod keymap;
 pub mod keypress;
 pub mod listener;
+pub mod localization;
 pub mod main_split;
 pub mod markdown;
+pub mod notification;
 pub mod palette;
 pub mod panel;
+pub mod peek;
+pub mod perf;
 pub mod plugin;
 pub mod proxy;
+pub mod recovery;
 pub mod rename;
+pub mod runnable;
 pub mod settings;
 pub mod snippet;
 pub mod source_control;
+pub mod spell;
 pub mod status;
+pub mod task;
 pub mod terminal;
 pub mod text_area;
 pub mod text_input;
@@ -38,6 +50,7 @@
 pub mod update;
 pub mod wave;
 pub mod web_link;
+pub mod welcome;
 pub mod window;
 pub mod window_tab;
 pub mod workspace;
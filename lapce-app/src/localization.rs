@@ -0,0 +1,162 @@
+//! The text of the UI in the language picked in the settings.
+//!
+//! Catalogs are files of `key = text` lines, like a small part of Fluent: `#`
+//! starts a comment, an indented line continues the text of the line above,
+//! and `{ $name }` is replaced with an argument. Text that a catalog leaves out
+//! is taken from the English one.
+
+use std::{collections::HashMap, path::Path};
+
+use lapce_core::directory::Directory;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+
+const DEFAULT_CATALOG: &str = include_str!("../../defaults/locales/en-US.ftl");
+
+/// The catalog of the language in use, with the English text under it.
+static CATALOG: Lazy<RwLock<Catalog>> =
+    Lazy::new(|| RwLock::new(Catalog::parse(DEFAULT_CATALOG)));
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Catalog {
+    messages: HashMap<String, String>,
+}
+
+impl Catalog {
+    pub fn parse(text: &str) -> Self {
+        let mut messages: HashMap<String, String> = HashMap::new();
+        let mut last_key: Option<String> = None;
+        for line in text.lines() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with(char::is_whitespace) {
+                if let Some(message) =
+                    last_key.as_ref().and_then(|key| messages.get_mut(key))
+                {
+                    if !message.is_empty() {
+                        message.push('\n');
+                    }
+                    message.push_str(trimmed);
+                }
+                continue;
+            }
+
+            last_key = line.split_once('=').map(|(key, message)| {
+                let key = key.trim().to_string();
+                messages.insert(key.clone(), message.trim().to_string());
+                key
+            });
+        }
+        Self { messages }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.messages.get(key).map(String::as_str)
+    }
+
+    /// Add the messages of the other catalog, over the ones with the same key.
+    pub fn extend(&mut self, other: Catalog) {
+        self.messages.extend(other.messages);
+    }
+}
+
+/// Load the catalogs of the locale, like `de-DE`, from the locales folder, or
+/// the ones of the locale of the operating system if it's empty. The catalog
+/// of the language, `de.ftl`, is used under the one of the region, so that one
+/// only has to have the text that differs between regions.
+pub fn load(locale: &str) {
+    let mut catalog = Catalog::parse(DEFAULT_CATALOG);
+    let locale = if locale.is_empty() {
+        system_locale()
+    } else {
+        Some(locale.replace('_', "-"))
+    };
+    if let (Some(locale), Some(dir)) = (locale, Directory::locales_directory()) {
+        let language = locale.split('-').next().unwrap_or(&locale);
+        catalog.extend(read_catalog(&dir, language));
+        if language != locale {
+            catalog.extend(read_catalog(&dir, &locale));
+        }
+    }
+    *CATALOG.write() = catalog;
+}
+
+fn read_catalog(dir: &Path, name: &str) -> Catalog {
+    std::fs::read_to_string(dir.join(format!("{name}.ftl")))
+        .map(|text| Catalog::parse(&text))
+        .unwrap_or_default()
+}
+
+/// The locale the operating system is set to, like `de-DE`.
+fn system_locale() -> Option<String> {
+    locale_config::Locale::user_default()
+        .to_string()
+        .split(',')
+        .find(|tag| !tag.is_empty() && !tag.contains('='))
+        .map(|tag| tag.to_string())
+}
+
+/// The text of the key in the language in use, if any catalog has it.
+pub fn get(key: &str) -> Option<String> {
+    CATALOG.read().get(key).map(|message| message.to_string())
+}
+
+/// The text of the key in the language in use. The key itself is shown if no
+/// catalog has it, so that it can be spotted and added.
+pub fn tr(key: &str) -> String {
+    get(key).unwrap_or_else(|| key.to_string())
+}
+
+/// The text of the key in the language in use, with the arguments put in.
+pub fn tr_args(key: &str, args: &[(&str, &str)]) -> String {
+    format_message(&tr(key), args)
+}
+
+fn format_message(message: &str, args: &[(&str, &str)]) -> String {
+    args.iter()
+        .fold(message.to_string(), |message, (name, value)| {
+            message
+                .replace(&format!("{{ ${name} }}"), value)
+                .replace(&format!("{{${name}}}"), value)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_catalog() {
+        let mut catalog = Catalog::parse(
+            "# comment\npanel-errors = Errors\nlong =\n    first\n    second\n",
+        );
+        assert_eq!(catalog.get("panel-errors"), Some("Errors"));
+        assert_eq!(catalog.get("long"), Some("first\nsecond"));
+        assert_eq!(catalog.get("missing"), None);
+
+        catalog.extend(Catalog::parse("panel-errors = Fehler"));
+        assert_eq!(catalog.get("panel-errors"), Some("Fehler"));
+        assert_eq!(catalog.get("long"), Some("first\nsecond"));
+    }
+
+    #[test]
+    fn test_format_message() {
+        assert_eq!(
+            format_message(
+                "Open { $name } in {$dir}",
+                &[("name", "a.rs"), ("dir", "src")]
+            ),
+            "Open a.rs in src"
+        );
+    }
+
+    #[test]
+    fn test_default_catalog() {
+        let catalog = Catalog::parse(DEFAULT_CATALOG);
+        assert_eq!(catalog.get("mode-visual-line"), Some("Visual Line"));
+        assert_eq!(catalog.get("menu-open-folder"), Some("Open Folder"));
+    }
+}
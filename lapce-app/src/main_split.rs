@@ -1,7 +1,8 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     path::{Path, PathBuf},
     rc::Rc,
+    sync::Arc,
 };
 
 use floem::{
@@ -10,17 +11,17 @@
     file::{FileDialogOptions, FileInfo},
     keyboard::ModifiersState,
     peniko::kurbo::{Point, Rect, Vec2},
-    reactive::{Memo, RwSignal, Scope},
+    reactive::{use_context, Memo, RwSignal, Scope},
 };
 use itertools::Itertools;
 use lapce_core::{
     buffer::rope_text::RopeText, command::FocusCommand, cursor::Cursor,
-    selection::Selection, syntax::Syntax,
+    directory::Directory, selection::Selection, syntax::Syntax,
 };
 use lapce_rpc::{
-    buffer::BufferId,
+    buffer::{BufferId, LineEnding},
     plugin::{PluginId, VoltID},
-    proxy::ProxyResponse,
+    proxy::{ProxyResponse, RestoredBuffer},
 };
 use lapce_xi_rope::Rope;
 use lsp_types::{
@@ -33,6 +34,7 @@
 use crate::{
     alert::AlertButton,
     command::InternalCommand,
+    db::LapceDb,
     doc::{DiagnosticData, DocContent, DocHistory, Document, EditorDiagnostic},
     editor::{
         diff::DiffEditorData,
@@ -47,9 +49,13 @@
         ThemeColorSettingsId, VoltViewId,
     },
     keypress::{EventRef, KeyPressData},
+    recovery, spell,
     window_tab::{CommonData, Focus, WindowTabData},
 };
 
+const MAX_RECENT_FILES: usize = 50;
+const MAX_CLOSED_FILES: usize = 30;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SplitDirection {
     Vertical,
@@ -134,6 +140,9 @@ pub struct SplitData {
 pub struct SplitInfo {
     pub children: Vec<SplitContentInfo>,
     pub direction: SplitDirection,
+    /// The relative sizes of the children
+    #[serde(default)]
+    pub sizes: Vec<f64>,
 }
 
 impl SplitInfo {
@@ -153,9 +162,18 @@ pub fn to_data(
                 children: self
                     .children
                     .iter()
-                    .map(|child| {
+                    .enumerate()
+                    .map(|(i, child)| {
+                        // Children collapsed by a maximized split get their
+                        // size back
+                        let size = self
+                            .sizes
+                            .get(i)
+                            .copied()
+                            .filter(|size| *size > 0.0)
+                            .unwrap_or(1.0);
                         (
-                            cx.create_rw_signal(1.0),
+                            cx.create_rw_signal(size),
                             child.to_data(data.clone(), split_id),
                         )
                     })
@@ -209,6 +227,11 @@ pub fn split_info(&self, data: &WindowTabData) -> SplitInfo {
                 .iter()
                 .map(|(_, child)| child.content_info(data))
                 .collect(),
+            sizes: self
+                .children
+                .iter()
+                .map(|(size, _)| size.get_untracked())
+                .collect(),
         };
         info
     }
@@ -224,6 +247,18 @@ pub fn content_index(&self, content: &SplitContent) -> Option<usize> {
     }
 }
 
+/// The content of a file buffer as loaded by the proxy.
+struct LoadedBuffer {
+    content: Rope,
+    read_only: bool,
+    large_file: bool,
+    line_ending: LineEnding,
+    /// The content is only the first chunk of a large file.
+    more: bool,
+    /// Unsaved changes backed up before Lapce last exited.
+    recovered: Option<String>,
+}
+
 #[derive(Clone)]
 pub struct MainSplitData {
     pub scope: Scope,
@@ -242,6 +277,12 @@ pub struct MainSplitData {
     pub locations: RwSignal<im::Vector<EditorLocation>>,
     pub current_location: RwSignal<usize>,
     pub width: RwSignal<f64>,
+    /// The sizes the splits had before one of them was maximized.
+    pub maximized_split_sizes: RwSignal<Option<Vec<(RwSignal<f64>, f64)>>>,
+    /// The files in the order their editors were last active, most recent first.
+    pub recent_files: RwSignal<im::Vector<PathBuf>>,
+    /// The files whose editors were closed, most recent first.
+    pub closed_files: RwSignal<im::Vector<PathBuf>>,
     pub common: Rc<CommonData>,
 }
 
@@ -294,6 +335,32 @@ pub fn new(cx: Scope, common: Rc<CommonData>) -> Self {
             Some(editor)
         });
 
+        let recent_files = cx.create_rw_signal(im::Vector::new());
+        cx.create_effect(move |_| {
+            let path = active_editor.get().and_then(|editor| {
+                editor
+                    .view
+                    .doc
+                    .get()
+                    .content
+                    .with(|content| content.path().cloned())
+            });
+            if let Some(path) = path {
+                recent_files.update(|recent_files: &mut im::Vector<PathBuf>| {
+                    recent_files.retain(|p| p != &path);
+                    recent_files.push_front(path);
+                    recent_files.truncate(MAX_RECENT_FILES);
+                });
+            }
+        });
+
+        let closed_files = cx.create_rw_signal(
+            use_context::<Arc<LapceDb>>()
+                .and_then(|db| db.get_closed_files(&common.workspace).ok())
+                .map(im::Vector::from)
+                .unwrap_or_default(),
+        );
+
         {
             let buffer = find_editor.view.doc.get_untracked().buffer;
             let find = common.find.clone();
@@ -320,6 +387,9 @@ pub fn new(cx: Scope, common: Rc<CommonData>) -> Self {
             locations,
             current_location,
             width: cx.create_rw_signal(0.0),
+            maximized_split_sizes: cx.create_rw_signal(None),
+            recent_files,
+            closed_files,
             common,
         }
     }
@@ -461,34 +531,83 @@ pub fn get_doc(&self, path: PathBuf) -> (Rc<Document>, bool) {
             {
                 let doc = doc.clone();
                 let local_doc = doc.clone();
-                let send = create_ext_action(cx, move |result| {
-                    if let Ok(ProxyResponse::NewBufferResponse {
-                        content,
-                        read_only,
-                    }) = result
-                    {
-                        local_doc.init_content(Rope::from(content));
-                        if read_only {
-                            local_doc.content.update(|content| {
-                                if let DocContent::File { read_only, .. } = content {
-                                    *read_only = true;
-                                }
-                            });
-                        }
+                let send = create_ext_action(cx, move |loaded: LoadedBuffer| {
+                    if loaded.large_file {
+                        local_doc.set_large_file();
+                    }
+                    local_doc.line_ending.set(loaded.line_ending);
+                    local_doc.init_content(loaded.content);
+                    if loaded.read_only {
+                        local_doc.content.update(|content| {
+                            if let DocContent::File { read_only, .. } = content {
+                                *read_only = true;
+                            }
+                        });
+                    }
+                    if let Some(recovered) = loaded.recovered {
+                        local_doc.offer_recovery(recovered);
+                    }
+                    if loaded.more {
+                        local_doc.load_remaining_chunks();
                     }
                 });
 
-                self.common
-                    .proxy
-                    .new_buffer(doc.buffer_id, path, move |result| {
-                        send(result);
-                    });
+                let kind = self.common.workspace.kind.clone();
+                self.common.proxy.new_buffer(
+                    doc.buffer_id,
+                    path.clone(),
+                    move |result| {
+                        if let Ok(ProxyResponse::NewBufferResponse {
+                            content,
+                            read_only,
+                            large_file,
+                            line_ending,
+                            more,
+                        }) = result
+                        {
+                            // Only offer to restore a backup that differs from what is on
+                            // disk. Large files aren't backed up.
+                            let recovered = recovery::read_recovery(&kind, &path)
+                                .map(|r| r.content)
+                                .filter(|recovered| {
+                                    !large_file && recovered != &content
+                                });
+                            // Build the rope here rather than on the UI thread, since
+                            // that can take a while for big files
+                            send(LoadedBuffer {
+                                content: Rope::from(content),
+                                read_only,
+                                large_file,
+                                line_ending,
+                                more,
+                                recovered,
+                            });
+                        }
+                    },
+                );
             }
 
             (doc, true)
         }
     }
 
+    /// Send the open documents to a remote proxy that was started again after the
+    /// connection to it dropped, so it has the changes that weren't saved.
+    pub fn restore_buffers(&self) {
+        let buffers = self.docs.with_untracked(|docs| {
+            docs.iter()
+                .filter(|(_, doc)| doc.loaded())
+                .map(|(path, doc)| RestoredBuffer {
+                    buffer_id: doc.buffer_id,
+                    path: path.clone(),
+                    rev: doc.rev(),
+                    content: doc.buffer.with_untracked(|b| b.to_string()),
+                })
+                .collect()
+        });
+        self.common.proxy.restore_buffers(buffers);
+    }
+
     pub fn go_to_location(
         &self,
         location: EditorLocation,
@@ -497,6 +616,27 @@ pub fn go_to_location(
         if self.common.focus.get_untracked() != Focus::Workbench {
             self.common.focus.set(Focus::Workbench);
         }
+        self.show_location(location, edits);
+    }
+
+    /// Show the location in the unconfirmed editor of the active editor tab
+    /// without taking the focus, so that the palette can preview the file of the
+    /// item it has selected. Returns the editor tab and child it is shown in, and
+    /// whether the document was loaded for it.
+    pub fn preview_location(
+        &self,
+        location: EditorLocation,
+    ) -> Option<(EditorTabId, EditorTabChild, bool)> {
+        let (child, new_doc) = self.show_location(location, None);
+        let editor_tab_id = self.active_editor_tab.get_untracked()?;
+        Some((editor_tab_id, child, new_doc))
+    }
+
+    fn show_location(
+        &self,
+        location: EditorLocation,
+        edits: Option<Vec<TextEdit>>,
+    ) -> (EditorTabChild, bool) {
         let path = location.path.clone();
         let (doc, new_doc) = self.get_doc(path.clone());
 
@@ -505,14 +645,81 @@ pub fn go_to_location(
             location.ignore_unconfirmed,
             location.same_editor_tab,
         );
-        if let EditorTabChild::Editor(editor_id) = child {
+        if let EditorTabChild::Editor(editor_id) = &child {
             if let Some(editor) = self
                 .editors
-                .with_untracked(|editors| editors.get(&editor_id).cloned())
+                .with_untracked(|editors| editors.get(editor_id).cloned())
             {
                 editor.go_to_location(location, new_doc, edits);
             }
         }
+        (child, new_doc)
+    }
+
+    /// Drop the document of the file if no editor shows it and it has no unsaved
+    /// changes, like one that was only loaded to be previewed.
+    pub fn discard_doc(&self, path: &Path) {
+        let shows_path = |editor: &Rc<EditorData>| {
+            editor.view.doc.with_untracked(|doc| {
+                doc.content
+                    .with_untracked(|content| content.path() == Some(path))
+            })
+        };
+        let shown = self
+            .editors
+            .with_untracked(|editors| editors.values().any(shows_path))
+            || self.diff_editors.with_untracked(|diff_editors| {
+                diff_editors.values().any(|diff_editor| {
+                    shows_path(&diff_editor.left) || shows_path(&diff_editor.right)
+                })
+            });
+        if shown {
+            return;
+        }
+        self.docs.update(|docs| {
+            if docs.get(path).is_some_and(|doc| doc.is_pristine()) {
+                docs.remove(path);
+            }
+        });
+    }
+
+    /// Open a file that was dropped on an editor tab, either in the editor tab
+    /// or in a new split on the side of it it was dropped on.
+    pub fn open_file_in_editor_tab(
+        &self,
+        path: PathBuf,
+        editor_tab_id: EditorTabId,
+        split: Option<SplitMoveDirection>,
+    ) -> Option<()> {
+        let editor_tab = self.editor_tabs.with_untracked(|editor_tabs| {
+            editor_tabs.get(&editor_tab_id).copied()
+        })?;
+        self.active_editor_tab.set(Some(editor_tab_id));
+        self.go_to_location(
+            EditorLocation {
+                path,
+                position: None,
+                scroll_offset: None,
+                ignore_unconfirmed: true,
+                same_editor_tab: true,
+            },
+            None,
+        );
+        if let Some(split) = split {
+            let (active, len) = editor_tab.with_untracked(|editor_tab| {
+                (editor_tab.active, editor_tab.children.len())
+            });
+            // The file is already the only thing in the editor tab
+            if len > 1 {
+                self.move_editor_tab_child_to_new_split(
+                    editor_tab_id,
+                    active,
+                    editor_tab_id,
+                    split,
+                );
+            }
+        }
+        Some(())
     }
 
     pub fn open_file_changes(&self, path: PathBuf) {
@@ -547,6 +754,41 @@ pub fn open_file_changes(&self, path: PathBuf) {
         );
     }
 
+    /// Open a diff of the file at a git revision against the working copy.
+    pub fn open_file_revision_changes(&self, path: PathBuf, revision: String) {
+        let (right, _) = self.get_doc(path.clone());
+        let left = Document::new_hisotry(
+            self.scope,
+            DocContent::History(DocHistory {
+                path: path.clone(),
+                version: revision.chars().take(7).collect(),
+            }),
+            self.common.clone(),
+        );
+        let left = Rc::new(left);
+
+        let send = {
+            let left = left.clone();
+            create_ext_action(self.scope, move |result| {
+                if let Ok(ProxyResponse::BufferHeadResponse { content, .. }) = result
+                {
+                    left.init_content(Rope::from(content));
+                }
+            })
+        };
+        self.common
+            .proxy
+            .git_file_revision(path, revision, move |result| {
+                send(result);
+            });
+
+        self.get_editor_tab_child(
+            EditorTabChildSource::DiffEditor { left, right },
+            false,
+            false,
+        );
+    }
+
     fn new_editor_tab(
         &self,
         editor_tab_id: EditorTabId,
@@ -1297,13 +1539,15 @@ pub fn split(
             let new_editor_tab_id =
                 new_editor_tab.with_untracked(|editor_tab| editor_tab.editor_tab_id);
             split.update(|split| {
-                for (size, _) in &split.children {
-                    size.set(1.0);
-                }
+                // The new editor tab takes half of the space of the one it's
+                // split from, so the rest of the layout keeps its proportions
+                let size = split.children[index].0;
+                let half = size.get_untracked() / 2.0;
+                size.set(half);
                 split.children.insert(
                     index + 1,
                     (
-                        split.scope.create_rw_signal(1.0),
+                        split.scope.create_rw_signal(half),
                         SplitContent::EditorTab(new_editor_tab_id),
                     ),
                 );
@@ -1554,6 +1798,135 @@ pub fn split_exchange(&self, editor_tab_id: EditorTabId) -> Option<()> {
         Some(())
     }
 
+    /// Move the last child of the split the editor tab is in to the front.
+    pub fn split_rotate(&self, editor_tab_id: EditorTabId) -> Option<()> {
+        let editor_tab = self.editor_tabs.with_untracked(|editor_tabs| {
+            editor_tabs.get(&editor_tab_id).copied()
+        })?;
+        let split_id = editor_tab.with_untracked(|editor_tab| editor_tab.split);
+        let split = self
+            .splits
+            .with_untracked(|splits| splits.get(&split_id).copied())?;
+        split.update(|split| {
+            split.children.rotate_right(1);
+        });
+        Some(())
+    }
+
+    /// Give every child of every split the same size.
+    pub fn split_equalize(&self) {
+        self.maximized_split_sizes.set(None);
+        self.splits.with_untracked(|splits| {
+            for split in splits.values() {
+                split.with_untracked(|split| {
+                    for (size, _) in &split.children {
+                        size.set(1.0);
+                    }
+                });
+            }
+        });
+    }
+
+    /// Collapse everything but the editor tab, or restore the sizes from
+    /// before if a split is already maximized.
+    pub fn split_toggle_maximize(&self, editor_tab_id: EditorTabId) -> Option<()> {
+        if let Some(sizes) = self.maximized_split_sizes.get_untracked() {
+            for (size, value) in sizes {
+                size.set(value);
+            }
+            self.maximized_split_sizes.set(None);
+            return Some(());
+        }
+
+        let mut sizes = Vec::new();
+        for (split, index) in self.editor_tab_split_path(editor_tab_id) {
+            split.with_untracked(|split| {
+                for (i, (size, _)) in split.children.iter().enumerate() {
+                    if i != index {
+                        sizes.push((*size, size.get_untracked()));
+                        size.set(0.0);
+                    }
+                }
+            });
+        }
+        self.maximized_split_sizes.set(Some(sizes));
+        Some(())
+    }
+
+    /// Grow or shrink the editor tab in the closest split with the given
+    /// direction, taking the space from or giving it to its neighbour.
+    pub fn split_resize(
+        &self,
+        editor_tab_id: EditorTabId,
+        direction: SplitDirection,
+        grow: bool,
+    ) -> Option<()> {
+        let (split, index) = self
+            .editor_tab_split_path(editor_tab_id)
+            .into_iter()
+            .rev()
+            .find(|(split, _)| {
+                split.with_untracked(|split| {
+                    split.direction == direction && split.children.len() > 1
+                })
+            })?;
+        split.with_untracked(|split| {
+            let total: f64 = split
+                .children
+                .iter()
+                .map(|(size, _)| size.get_untracked())
+                .sum();
+            let neighbour = if index + 1 < split.children.len() {
+                index + 1
+            } else {
+                index - 1
+            };
+            let size = split.children[index].0;
+            let neighbour = split.children[neighbour].0;
+            let pair = size.get_untracked() + neighbour.get_untracked();
+            let min = total * 0.05;
+            if pair < min * 2.0 {
+                return;
+            }
+            let step = if grow { total * 0.05 } else { -total * 0.05 };
+            let new_size = (size.get_untracked() + step).clamp(min, pair - min);
+            size.set(new_size);
+            neighbour.set(pair - new_size);
+        });
+        Some(())
+    }
+
+    /// The splits from the root down to the editor tab, each with the index of
+    /// the child that holds the editor tab.
+    fn editor_tab_split_path(
+        &self,
+        editor_tab_id: EditorTabId,
+    ) -> Vec<(RwSignal<SplitData>, usize)> {
+        let mut path = Vec::new();
+        let Some(editor_tab) = self
+            .editor_tabs
+            .with_untracked(|editor_tabs| editor_tabs.get(&editor_tab_id).copied())
+        else {
+            return path;
+        };
+        let splits = self.splits.get_untracked();
+        let mut content = SplitContent::EditorTab(editor_tab_id);
+        let mut split_id =
+            Some(editor_tab.with_untracked(|editor_tab| editor_tab.split));
+        while let Some(split) = split_id.and_then(|id| splits.get(&id).copied()) {
+            let Some(index) =
+                split.with_untracked(|split| split.content_index(&content))
+            else {
+                break;
+            };
+            path.push((split, index));
+            content = SplitContent::Split(split.with_untracked(|s| s.split_id));
+            split_id = split.with_untracked(|split| split.parent_split);
+        }
+        path.reverse();
+        path
+    }
+
     fn split_content_focus(&self, content: &SplitContent) {
         match content {
             SplitContent::EditorTab(editor_tab_id) => {
@@ -1709,17 +2082,173 @@ fn editor_tab_remove(&self, editor_tab_id: EditorTabId) -> Option<()> {
         Some(())
     }
 
+    /// Close every child of the editor tab, asking once about all the changes
+    /// that would be lost.
     pub fn editor_tab_close(&self, editor_tab_id: EditorTabId) -> Option<()> {
         let editor_tabs = self.editor_tabs.get_untracked();
         let editor_tab = editor_tabs.get(&editor_tab_id).copied()?;
-        let editor_tab = editor_tab.get_untracked();
-        for (_, _, child) in editor_tab.children {
-            self.editor_tab_child_close(editor_tab_id, child, false);
-        }
+        let children = editor_tab.with_untracked(|editor_tab| {
+            editor_tab
+                .children
+                .iter()
+                .map(|(_, _, child)| child.clone())
+                .collect::<Vec<_>>()
+        });
+        let closing = children
+            .iter()
+            .filter_map(|child| match child {
+                EditorTabChild::Editor(editor_id) => Some(*editor_id),
+                _ => None,
+            })
+            .collect();
+
+        let main_split = self.clone();
+        self.confirm_close(
+            self.unsaved_documents(&closing),
+            Rc::new(move || {
+                for child in children.iter() {
+                    main_split.editor_tab_child_close(
+                        editor_tab_id,
+                        child.clone(),
+                        true,
+                    );
+                }
+            }),
+        );
 
         Some(())
     }
 
+    /// The documents with unsaved changes that closing the editors would lose,
+    /// with their names. A document that an editor which stays open also shows
+    /// isn't lost.
+    pub fn unsaved_documents(
+        &self,
+        closing: &HashSet<EditorId>,
+    ) -> Vec<(String, Rc<Document>)> {
+        let mut docs: Vec<(String, Rc<Document>)> = Vec::new();
+        self.editors.with_untracked(|editors| {
+            for (editor_id, editor) in editors.iter() {
+                if !closing.contains(editor_id) {
+                    continue;
+                }
+                let doc = editor.view.doc.get_untracked();
+                if doc.is_pristine()
+                    || docs.iter().any(|(_, d)| d.buffer_id == doc.buffer_id)
+                {
+                    continue;
+                }
+                let name = match doc.content.get_untracked() {
+                    DocContent::File { path, .. } => path
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .map(|name| name.to_string()),
+                    DocContent::Scratch { name, .. } => Some(name),
+                    DocContent::Local | DocContent::History(_) => None,
+                };
+                let Some(name) = name else {
+                    continue;
+                };
+                let stays_open = editors.iter().any(|(id, editor)| {
+                    !closing.contains(id)
+                        && editor
+                            .view
+                            .doc
+                            .with_untracked(|d| d.buffer_id == doc.buffer_id)
+                });
+                if !stays_open {
+                    docs.push((name, doc));
+                }
+            }
+        });
+        docs.sort_by(|(a, _), (b, _)| a.cmp(b));
+        docs
+    }
+
+    /// Run `close` once the user decided what to do with the unsaved changes of
+    /// the documents, saving them all first or dropping them. It runs right
+    /// away when there are none, and not at all when the user cancels.
+    pub fn confirm_close(
+        &self,
+        docs: Vec<(String, Rc<Document>)>,
+        close: Rc<dyn Fn()>,
+    ) {
+        if docs.is_empty() {
+            close();
+            return;
+        }
+
+        let internal_command = self.common.internal_command;
+        let (title, msg) = match docs.as_slice() {
+            [(name, _)] => (
+                format!("Do you want to save the changes you made to {name}?"),
+                "Your changes will be lost if you don't save them.".to_string(),
+            ),
+            _ => (
+                format!(
+                    "Do you want to save the changes you made to {} files?",
+                    docs.len()
+                ),
+                format!(
+                    "{}\n\nYour changes will be lost if you don't save them.",
+                    docs.iter().map(|(name, _)| name).join(", ")
+                ),
+            ),
+        };
+        let docs: Vec<Rc<Document>> = docs.into_iter().map(|(_, doc)| doc).collect();
+
+        let save = {
+            let main_split = self.clone();
+            let docs = docs.clone();
+            let close = close.clone();
+            AlertButton {
+                text: if docs.len() == 1 { "Save" } else { "Save All" }.to_string(),
+                action: Rc::new(move || {
+                    internal_command.send(InternalCommand::HideAlert);
+                    main_split.save_documents(docs.clone(), close.clone());
+                }),
+            }
+        };
+        let discard = AlertButton {
+            text: "Don't Save".to_string(),
+            action: Rc::new(move || {
+                internal_command.send(InternalCommand::HideAlert);
+                for doc in docs.iter() {
+                    doc.remove_recovery();
+                }
+                close();
+            }),
+        };
+        internal_command.send(InternalCommand::ShowAlert {
+            title,
+            msg,
+            buttons: vec![save, discard],
+        });
+    }
+
+    /// Save the documents one after the other, asking where to for the scratch
+    /// ones, then run `after`. It stops at one that isn't saved.
+    fn save_documents(&self, mut docs: Vec<Rc<Document>>, after: Rc<dyn Fn()>) {
+        let Some(doc) = docs.pop() else {
+            after();
+            return;
+        };
+        let main_split = self.clone();
+        let next =
+            Rc::new(move || main_split.save_documents(docs.clone(), after.clone()));
+        if let DocContent::Scratch { .. } = doc.content.get_untracked() {
+            let main_split = self.clone();
+            save_as(FileDialogOptions::new(), move |file: Option<FileInfo>| {
+                if let Some(file) = file {
+                    let next = next.clone();
+                    main_split.save_as(doc.clone(), file.path, move || next());
+                }
+            });
+        } else {
+            doc.save(move || next(), || {});
+        }
+    }
+
     fn editor_tab_child_close_warning(
         &self,
         child: &EditorTabChild,
@@ -1774,6 +2303,25 @@ pub fn split_exchange_active(&self) -> Option<()> {
         Some(())
     }
 
+    pub fn split_rotate_active(&self) -> Option<()> {
+        let active_editor_tab = self.active_editor_tab.get_untracked()?;
+        self.split_rotate(active_editor_tab)
+    }
+
+    pub fn split_toggle_maximize_active(&self) -> Option<()> {
+        let active_editor_tab = self.active_editor_tab.get_untracked()?;
+        self.split_toggle_maximize(active_editor_tab)
+    }
+
+    pub fn split_resize_active(
+        &self,
+        direction: SplitDirection,
+        grow: bool,
+    ) -> Option<()> {
+        let active_editor_tab = self.active_editor_tab.get_untracked()?;
+        self.split_resize(active_editor_tab, direction, grow)
+    }
+
     pub fn split_move_active(&self, direction: SplitMoveDirection) -> Option<()> {
         let active_editor_tab = self.active_editor_tab.get_untracked()?;
         self.split_move(direction, active_editor_tab)?;
@@ -1856,19 +2404,23 @@ pub fn editor_tab_child_close(
                         let editor_id = editor.editor_id;
                         let save_action = Rc::new(move || {
                             internal_command.send(InternalCommand::HideAlert);
-                            editor.save(false, move || {
-                                if let Some(editor) =
-                                    editors.with_untracked(|editors| {
-                                        editors.get(&editor_id).cloned()
-                                    })
-                                {
-                                    editor.clone().run_focus_command(
-                                        &FocusCommand::SplitClose,
-                                        None,
-                                        ModifiersState::empty(),
-                                    );
-                                }
-                            });
+                            editor.save(
+                                false,
+                                move || {
+                                    if let Some(editor) =
+                                        editors.with_untracked(|editors| {
+                                            editors.get(&editor_id).cloned()
+                                        })
+                                    {
+                                        editor.clone().run_focus_command(
+                                            &FocusCommand::SplitClose,
+                                            None,
+                                            ModifiersState::empty(),
+                                        );
+                                    }
+                                },
+                                || {},
+                            );
                         });
                         Some(AlertButton {
                             text: "Save".to_string(),
@@ -1881,6 +2433,7 @@ pub fn editor_tab_child_close(
                 if let Some(save_button) = save_button {
                     let main_split = self.clone();
                     let child = child.clone();
+                    let doc = doc.clone();
                     self.common
                         .internal_command
                         .send(InternalCommand::ShowAlert {
@@ -1896,6 +2449,7 @@ pub fn editor_tab_child_close(
                                     action: Rc::new(move || {
                                         internal_command
                                             .send(InternalCommand::HideAlert);
+                                        doc.remove_recovery();
                                         main_split.editor_tab_child_close(
                                             editor_tab_id,
                                             child.clone(),
@@ -1929,6 +2483,16 @@ pub fn editor_tab_child_close(
 
         match child {
             EditorTabChild::Editor(editor_id) => {
+                let path = self.editors.with_untracked(|editors| {
+                    editors.get(&editor_id).and_then(|editor| {
+                        editor.view.doc.with_untracked(|doc| {
+                            doc.content.with_untracked(|c| c.path().cloned())
+                        })
+                    })
+                });
+                if let Some(path) = path {
+                    self.add_closed_file(path);
+                }
                 self.remove_editor(&editor_id);
             }
             EditorTabChild::DiffEditor(diff_editor_id) => {
@@ -1953,6 +2517,58 @@ pub fn editor_tab_child_close(
         Some(())
     }
 
+    fn add_closed_file(&self, path: PathBuf) {
+        let closed_files = self
+            .closed_files
+            .try_update(|closed_files| {
+                closed_files.retain(|p| p != &path);
+                closed_files.push_front(path);
+                closed_files.truncate(MAX_CLOSED_FILES);
+                closed_files.clone()
+            })
+            .unwrap();
+        if let Some(db) = use_context::<Arc<LapceDb>>() {
+            db.save_closed_files(
+                self.common.workspace.clone(),
+                closed_files.into_iter().collect(),
+            );
+        }
+    }
+
+    /// Open the file whose editor was closed most recently, skipping the ones
+    /// that are open again already.
+    pub fn reopen_closed_file(&self) {
+        let open_paths: Vec<PathBuf> = self.editors.with_untracked(|editors| {
+            editors
+                .values()
+                .filter_map(|editor| {
+                    editor.view.doc.with_untracked(|doc| {
+                        doc.content.with_untracked(|c| c.path().cloned())
+                    })
+                })
+                .collect()
+        });
+        let path = self
+            .closed_files
+            .try_update(|closed_files| {
+                closed_files.retain(|p| !open_paths.contains(p));
+                closed_files.pop_front()
+            })
+            .flatten();
+        let Some(path) = path else {
+            return;
+        };
+        if let Some(db) = use_context::<Arc<LapceDb>>() {
+            db.save_closed_files(
+                self.common.workspace.clone(),
+                self.closed_files.get_untracked().into_iter().collect(),
+            );
+        }
+        self.common
+            .internal_command
+            .send(InternalCommand::OpenFile { path });
+    }
+
     pub fn editor_tab_update_layout(
         &self,
         editor_tab_id: &EditorTabId,
@@ -1974,7 +2590,18 @@ pub fn editor_tab_update_layout(
 
     pub fn run_code_action(&self, plugin_id: PluginId, action: CodeActionOrCommand) {
         match action {
-            CodeActionOrCommand::Command(_) => {}
+            CodeActionOrCommand::Command(command) => {
+                if command.command == spell::ADD_TO_DICTIONARY {
+                    let word = command
+                        .arguments
+                        .as_ref()
+                        .and_then(|args| args.first())
+                        .and_then(|word| word.as_str());
+                    if let Some(word) = word {
+                        spell::add_to_dictionary(&self.common, word);
+                    }
+                }
+            }
             CodeActionOrCommand::CodeAction(action) => {
                 if let Some(edit) = action.edit.as_ref() {
                     self.apply_workspace_edit(edit);
@@ -2125,7 +2752,167 @@ pub fn open_file_changed(&self, path: &Path, content: &str) {
             None => return,
         };
 
-        doc.handle_file_changed(Rope::from(content));
+        if doc.is_pristine() {
+            self.reload_changed_file(&doc, Rope::from(content));
+            return;
+        }
+
+        // The buffer has unsaved changes, so let the user decide which version to keep
+        let name = path
+            .file_name()
+            .and_then(|f| f.to_str())
+            .unwrap_or_default()
+            .to_string();
+        let internal_command = self.common.internal_command;
+        let reload = {
+            let main_split = self.clone();
+            let doc = doc.clone();
+            let content = content.to_string();
+            AlertButton {
+                text: "Reload".to_string(),
+                action: Rc::new(move || {
+                    internal_command.send(InternalCommand::HideAlert);
+                    main_split
+                        .reload_changed_file(&doc, Rope::from(content.as_str()));
+                }),
+            }
+        };
+        let compare = {
+            let main_split = self.clone();
+            let path = path.to_path_buf();
+            let content = content.to_string();
+            AlertButton {
+                text: "Compare".to_string(),
+                action: Rc::new(move || {
+                    internal_command.send(InternalCommand::HideAlert);
+                    main_split.open_disk_changes(path.clone(), &content);
+                }),
+            }
+        };
+        let keep = AlertButton {
+            text: "Keep Mine".to_string(),
+            action: Rc::new(move || {
+                internal_command.send(InternalCommand::HideAlert);
+            }),
+        };
+        internal_command.send(InternalCommand::ShowAlert {
+            title: format!("{name} has been changed on disk"),
+            msg: "The file also has unsaved changes in the editor.".to_string(),
+            buttons: vec![reload, compare, keep],
+        });
+    }
+
+    /// Replace the document's content with what is on disk, keeping the cursors of the
+    /// editors showing it on the same line and column.
+    fn reload_changed_file(&self, doc: &Rc<Document>, content: Rope) {
+        let editors = self.editors.with_untracked(|editors| {
+            editors
+                .values()
+                .filter(|editor| {
+                    editor
+                        .view
+                        .doc
+                        .with_untracked(|d| d.buffer_id == doc.buffer_id)
+                })
+                .cloned()
+                .collect::<Vec<_>>()
+        });
+        let positions = doc.buffer.with_untracked(|buffer| {
+            editors
+                .iter()
+                .map(|editor| {
+                    let offset = editor.cursor.with_untracked(|c| c.offset());
+                    buffer.offset_to_line_col(offset)
+                })
+                .collect::<Vec<_>>()
+        });
+
+        doc.reload(content, true);
+
+        let tail = is_log_file(doc);
+        doc.buffer.with_untracked(|buffer| {
+            for (editor, (line, col)) in editors.iter().zip(positions) {
+                let offset = if tail {
+                    buffer.len()
+                } else {
+                    let line = line.min(buffer.last_line());
+                    buffer.offset_of_line_col(line, col)
+                };
+                editor
+                    .cursor
+                    .update(|cursor| cursor.set_offset(offset, false, false));
+            }
+        });
+    }
+
+    /// Open a log file read-only, at its end, which then follows what is written
+    /// to it.
+    pub fn open_log_file(&self, path: PathBuf) {
+        let (doc, _) = self.get_doc(path.clone());
+        doc.content.update(|content| {
+            if let DocContent::File { read_only, .. } = content {
+                *read_only = true;
+            }
+        });
+        self.go_to_location(
+            EditorLocation {
+                path,
+                position: Some(EditorPosition::Line(usize::MAX)),
+                scroll_offset: None,
+                ignore_unconfirmed: false,
+                same_editor_tab: false,
+            },
+            None,
+        );
+    }
+
+    /// Open a diff editor comparing two files, with `left_path` on the left.
+    pub fn open_diff_files(&self, left_path: PathBuf, right_path: PathBuf) {
+        let (left, _) = self.get_doc(left_path);
+        let (right, _) = self.get_doc(right_path);
+        self.get_editor_tab_child(
+            EditorTabChildSource::DiffEditor { left, right },
+            false,
+            false,
+        );
+    }
+
+    /// Compare the unsaved content of a file with what is on disk.
+    pub fn open_saved_changes(&self, path: PathBuf) {
+        let main_split = self.clone();
+        let send = {
+            let path = path.clone();
+            create_ext_action(self.scope, move |content: String| {
+                main_split.open_disk_changes(path.clone(), &content);
+            })
+        };
+        self.common.proxy.read_file(path, move |result| {
+            if let Ok(ProxyResponse::ReadFileResponse { content }) = result {
+                let content = String::from_utf8_lossy(&content).to_string();
+                send(LineEnding::normalize(content));
+            }
+        });
+    }
+
+    /// Open a diff between the file's content on disk and the unsaved document.
+    pub fn open_disk_changes(&self, path: PathBuf, content: &str) {
+        let (right, _) = self.get_doc(path.clone());
+        let left = Document::new_hisotry(
+            self.scope,
+            DocContent::History(DocHistory {
+                path,
+                version: "disk".to_string(),
+            }),
+            self.common.clone(),
+        );
+        left.init_content(Rope::from(content));
+        let left = Rc::new(left);
+
+        self.get_editor_tab_child(
+            EditorTabChildSource::DiffEditor { left, right },
+            false,
+            false,
+        );
     }
 
     pub fn set_find_pattern(&self, pattern: Option<String>) {
@@ -2172,6 +2959,84 @@ pub fn new_file(&self) -> EditorTabChild {
         self.get_editor_tab_child(EditorTabChildSource::NewFileEditor, false, false)
     }
 
+    /// Open a new untitled file that already has the text in it, which isn't
+    /// saved anywhere yet.
+    pub fn new_file_with_content(&self, content: String) {
+        let EditorTabChild::Editor(editor_id) = self.new_file() else {
+            return;
+        };
+        let editor = self
+            .editors
+            .with_untracked(|editors| editors.get(&editor_id).cloned());
+        if let Some(editor) = editor {
+            editor
+                .view
+                .doc
+                .get_untracked()
+                .reload(Rope::from(content), false);
+        }
+    }
+
+    /// An editor for each file with unsaved changes.
+    fn unsaved_file_editors(&self) -> Vec<Rc<EditorData>> {
+        let mut paths = HashSet::new();
+        self.editors.with_untracked(|editors| {
+            editors
+                .values()
+                .filter(|editor| {
+                    editor.view.doc.with_untracked(|doc| {
+                        let DocContent::File { path, .. } =
+                            doc.content.get_untracked()
+                        else {
+                            return false;
+                        };
+                        !doc.is_pristine() && paths.insert(path)
+                    })
+                })
+                .cloned()
+                .collect()
+        })
+    }
+
+    pub fn has_unsaved_files(&self) -> bool {
+        !self.unsaved_file_editors().is_empty()
+    }
+
+    /// Save every file with unsaved changes, formatting them first if
+    /// `allow_formatting` and format on save are on, and run `after` once they
+    /// are all saved.
+    pub fn save_all(&self, allow_formatting: bool, after: impl Fn() + 'static) {
+        let editors = self.unsaved_file_editors();
+        if editors.is_empty() {
+            after();
+            return;
+        }
+
+        // Saving wants callbacks that are `Copy`, so the state lives in signals.
+        // `after` only runs if every file was saved, but the scope goes away
+        // either way once they are all done.
+        let cx = self.scope.create_child();
+        let pending = cx.create_rw_signal(editors.len());
+        let all_saved = cx.create_rw_signal(true);
+        let after: Rc<dyn Fn()> = Rc::new(after);
+        let after = cx.create_rw_signal(after);
+        let done = move |saved: bool| {
+            if !saved {
+                all_saved.set(false);
+            }
+            pending.update(|pending| *pending -= 1);
+            if pending.get_untracked() == 0 {
+                if all_saved.get_untracked() {
+                    (after.get_untracked())();
+                }
+                cx.dispose();
+            }
+        };
+        for editor in editors {
+            editor.save(allow_formatting, move || done(true), move || done(false));
+        }
+    }
+
     pub fn save_as(
         &self,
         doc: Rc<Document>,
@@ -2217,8 +3082,35 @@ pub fn save_as(
                     }),
                 );
             }
+            DocContent::File { .. } => {
+                // Write the contents to the new path and switch over to it, the
+                // original file is left as it was on disk.
+                let internal_command = self.common.internal_command;
+                let send = {
+                    let path = path.clone();
+                    create_ext_action(self.scope, move |result| {
+                        if let Err(err) = result {
+                            warn!("Failed to save as a file: {:?}", err);
+                        } else {
+                            internal_command.send(InternalCommand::OpenFile {
+                                path: path.clone(),
+                            });
+                            action();
+                        }
+                    })
+                };
+                self.common.proxy.save_buffer_as(
+                    BufferId::next(),
+                    path,
+                    rev,
+                    content,
+                    true,
+                    Box::new(move |result| {
+                        send(result);
+                    }),
+                );
+            }
             DocContent::Local => {}
-            DocContent::File { .. } => {}
             DocContent::History(_) => {}
         }
     }
@@ -2272,7 +3164,7 @@ pub fn can_jump_location_forward(&self, tracked: bool) -> bool {
         }
     }
 
-    pub fn save_scratch_doc(&self, doc: Rc<Document>) {
+    pub fn save_doc_as(&self, doc: Rc<Document>) {
         let main_split = self.clone();
         save_as(FileDialogOptions::new(), move |file: Option<FileInfo>| {
             if let Some(file) = file {
@@ -2621,7 +3513,7 @@ pub fn export_theme(&self) {
     }
 }
 
-fn workspace_edits(edit: &WorkspaceEdit) -> Option<HashMap<Url, Vec<TextEdit>>> {
+pub fn workspace_edits(edit: &WorkspaceEdit) -> Option<HashMap<Url, Vec<TextEdit>>> {
     if let Some(changes) = edit.changes.as_ref() {
         return Some(changes.clone());
     }
@@ -2697,3 +3589,15 @@ fn next_in_file_errors_offset(
         file_diagnostics[0].2[0].diagnostic.range.start,
     )
 }
+
+/// Whether the document is a log opened with [`MainSplitData::open_log_file`].
+fn is_log_file(doc: &Document) -> bool {
+    doc.content.with_untracked(|content| match content {
+        DocContent::File { path, read_only } => {
+            *read_only
+                && Directory::logs_directory()
+                    .is_some_and(|dir| path.starts_with(dir))
+        }
+        _ => false,
+    })
+}
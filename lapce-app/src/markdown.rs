@@ -24,8 +24,7 @@ pub fn parse_markdown(
     let mut res = Vec::new();
 
     let mut current_text = String::new();
-    let code_font_family: Vec<FamilyOwned> =
-        FamilyOwned::parse_list(&config.editor.font_family).collect();
+    let code_font_family: Vec<FamilyOwned> = config.editor.font_family();
 
     let default_attrs = Attrs::new()
         .color(*config.get_color(LapceColor::EDITOR_FOREGROUND))
@@ -0,0 +1,517 @@
+use std::{
+    fmt,
+    rc::Rc,
+    sync::{atomic::AtomicU64, Arc},
+    time::Duration,
+};
+
+use chrono::{DateTime, Local};
+use floem::{
+    action::exec_after,
+    cosmic_text::Weight,
+    event::EventListener,
+    reactive::{ReadSignal, RwSignal, Scope},
+    style::{CursorStyle, Style},
+    view::View,
+    views::{container, empty, label, list, scroll, stack, svg, text, Decorators},
+};
+use indexmap::IndexMap;
+use lapce_rpc::proxy::ProxyRpcHandler;
+use lsp_types::{MessageType, ProgressToken};
+
+use crate::{
+    app::clickable_icon,
+    config::{color::LapceColor, icon::LapceIcons, LapceConfig},
+    id::NotificationId,
+    localization::tr,
+    window_tab::WorkProgress,
+};
+
+/// How long a toast is shown for, unless it's an error or has buttons, which stay
+/// until they are closed.
+const TOAST_TIMEOUT: Duration = Duration::from_secs(8);
+/// The notification center forgets the oldest ones past this.
+const MAX_NOTIFICATIONS: usize = 100;
+const WIDTH: f64 = 360.0;
+
+/// A button of a notification. Clicking one closes the notification.
+#[derive(Clone)]
+pub struct NotificationAction {
+    pub text: String,
+    pub action: Rc<dyn Fn()>,
+}
+
+impl fmt::Debug for NotificationAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut s = f.debug_struct("NotificationAction");
+        s.field("text", &self.text);
+        s.finish()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Notification {
+    pub id: NotificationId,
+    pub title: String,
+    pub message: String,
+    pub level: MessageType,
+    pub actions: Vec<NotificationAction>,
+    /// The id of the `ShowMessageRequest` of a plugin, which is answered when
+    /// the notification is closed.
+    pub request: Option<u64>,
+    pub time: DateTime<Local>,
+}
+
+#[derive(Clone)]
+pub struct NotificationData {
+    /// All of them, the oldest first, for the notification center.
+    pub notifications: RwSignal<im::Vector<Notification>>,
+    /// The ones that are still shown as toasts.
+    pub toasts: RwSignal<im::Vector<NotificationId>>,
+    pub center_visible: RwSignal<bool>,
+    /// How many came in since the notification center was last opened.
+    pub unread: RwSignal<usize>,
+    proxy: ProxyRpcHandler,
+}
+
+impl NotificationData {
+    pub fn new(cx: Scope, proxy: ProxyRpcHandler) -> Self {
+        Self {
+            notifications: cx.create_rw_signal(im::Vector::new()),
+            toasts: cx.create_rw_signal(im::Vector::new()),
+            center_visible: cx.create_rw_signal(false),
+            unread: cx.create_rw_signal(0),
+            proxy,
+        }
+    }
+
+    pub fn notify(
+        &self,
+        title: impl Into<String>,
+        level: MessageType,
+        message: impl Into<String>,
+        actions: Vec<NotificationAction>,
+    ) -> NotificationId {
+        self.add(title.into(), level, message.into(), actions, None)
+    }
+
+    /// Show the `ShowMessageRequest` of a plugin, whose buttons answer it with
+    /// their index.
+    pub fn notify_request(
+        &self,
+        id: u64,
+        title: String,
+        level: MessageType,
+        message: String,
+        actions: Vec<String>,
+    ) {
+        let actions = actions
+            .into_iter()
+            .enumerate()
+            .map(|(index, text)| {
+                let proxy = self.proxy.clone();
+                NotificationAction {
+                    text,
+                    action: Rc::new(move || {
+                        proxy.plugin_message_action_result(id, Some(index));
+                    }),
+                }
+            })
+            .collect();
+        self.add(title, level, message, actions, Some(id));
+    }
+
+    fn add(
+        &self,
+        title: String,
+        level: MessageType,
+        message: String,
+        actions: Vec<NotificationAction>,
+        request: Option<u64>,
+    ) -> NotificationId {
+        let id = NotificationId::next();
+        let stays = level == MessageType::ERROR || !actions.is_empty();
+        let notification = Notification {
+            id,
+            title,
+            message,
+            level,
+            actions,
+            request,
+            time: Local::now(),
+        };
+
+        let mut dropped = None;
+        self.notifications.update(|notifications| {
+            notifications.push_back(notification);
+            if notifications.len() > MAX_NOTIFICATIONS {
+                dropped = notifications.pop_front();
+            }
+        });
+        if let Some(dropped) = dropped {
+            self.dismiss_toast(dropped.id);
+            self.cancel(&dropped);
+        }
+        self.toasts.update(|toasts| toasts.push_back(id));
+        if !self.center_visible.get_untracked() {
+            self.unread.update(|unread| *unread += 1);
+        }
+
+        if !stays {
+            let toasts = self.toasts;
+            exec_after(TOAST_TIMEOUT, move |_| {
+                toasts.try_update(|toasts| toasts.retain(|t| *t != id));
+            });
+        }
+        id
+    }
+
+    /// Hide the toast of the notification, which stays in the notification
+    /// center.
+    pub fn dismiss_toast(&self, id: NotificationId) {
+        self.toasts.update(|toasts| toasts.retain(|t| *t != id));
+    }
+
+    /// Remove the notification. A plugin that waits for the user to click one
+    /// of its buttons gets `None`.
+    pub fn close(&self, id: NotificationId) {
+        if let Some(notification) = self.take(id) {
+            self.cancel(&notification);
+        }
+    }
+
+    pub fn run_action(&self, id: NotificationId, index: usize) {
+        if let Some(notification) = self.take(id) {
+            if let Some(action) = notification.actions.get(index) {
+                (action.action)();
+            }
+        }
+    }
+
+    pub fn clear(&self) {
+        let notifications = self.notifications.get_untracked();
+        self.notifications.set(im::Vector::new());
+        self.toasts.set(im::Vector::new());
+        for notification in notifications.iter() {
+            self.cancel(notification);
+        }
+    }
+
+    pub fn toggle_center(&self) {
+        let visible = !self.center_visible.get_untracked();
+        self.center_visible.set(visible);
+        if visible {
+            self.unread.set(0);
+        }
+    }
+
+    fn take(&self, id: NotificationId) -> Option<Notification> {
+        self.dismiss_toast(id);
+        let mut taken = None;
+        self.notifications.update(|notifications| {
+            if let Some(i) = notifications.iter().position(|n| n.id == id) {
+                taken = Some(notifications.remove(i));
+            }
+        });
+        taken
+    }
+
+    /// Ask the proxy to cancel the work, whose progress ends once it stopped.
+    pub fn cancel_progress(&self, token: ProgressToken) {
+        self.proxy.cancel_work_done_progress(token);
+    }
+
+    /// Tell the plugin that asked, if one did, that no button was clicked.
+    fn cancel(&self, notification: &Notification) {
+        if let Some(request) = notification.request {
+            self.proxy.plugin_message_action_result(request, None);
+        }
+    }
+}
+
+/// The toasts and the progress of long running work in the bottom right corner,
+/// or the notification center in their place when it's open.
+pub fn notification_view(
+    notification: NotificationData,
+    progresses: RwSignal<IndexMap<ProgressToken, WorkProgress>>,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let center_visible = notification.center_visible;
+    let notifications = notification.notifications;
+    let toasts = notification.toasts;
+
+    let toast_list = {
+        let notification = notification.clone();
+        list(
+            move || {
+                let toasts = toasts.get();
+                notifications.with(|notifications| {
+                    notifications
+                        .iter()
+                        .filter(|n| toasts.contains(&n.id))
+                        .cloned()
+                        .collect::<im::Vector<_>>()
+                })
+            },
+            |n| n.id,
+            move |n| notification_item(n, notification.clone(), true, config),
+        )
+        .style(|s| s.flex_col().width_full())
+    };
+
+    let center = {
+        let notification = notification.clone();
+        let clear = notification.clone();
+        let toggle = notification.clone();
+        stack((
+            stack((
+                label(|| tr("notification-center")).style(|s| {
+                    s.flex_grow(1.0).flex_basis(0.0).font_weight(Weight::BOLD)
+                }),
+                label(|| tr("notification-clear-all"))
+                    .on_click_stop(move |_| clear.clear())
+                    .style(move |s| {
+                        s.padding_horiz(6.0).border_radius(6.0).hover(|s| {
+                            s.cursor(CursorStyle::Pointer).background(
+                                *config
+                                    .get()
+                                    .get_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                            )
+                        })
+                    }),
+                clickable_icon(
+                    || LapceIcons::CLOSE,
+                    move || toggle.toggle_center(),
+                    || false,
+                    || false,
+                    config,
+                )
+                .style(|s| s.margin_left(6.0)),
+            ))
+            .style(|s| s.width_full().items_center().padding(10.0)),
+            container(progress_list(progresses, notification.clone(), config))
+                .style(|s| s.width_full().padding_horiz(10.0)),
+            scroll(
+                list(
+                    move || notifications.get().into_iter().rev(),
+                    |n| n.id,
+                    move |n| {
+                        notification_item(n, notification.clone(), false, config)
+                    },
+                )
+                .style(|s| s.flex_col().width_full().padding_horiz(10.0)),
+            )
+            .style(|s| s.width_full().min_height(0.0).flex_grow(1.0)),
+            label(|| tr("notification-none")).style(move |s| {
+                s.apply_if(!notifications.with(|n| n.is_empty()), |s| s.hide())
+                    .padding(10.0)
+                    .color(*config.get().get_color(LapceColor::EDITOR_DIM))
+            }),
+        ))
+        .on_event_stop(EventListener::PointerDown, |_| {})
+        .style(move |s| {
+            let config = config.get();
+            s.apply_if(!center_visible.get(), |s| s.hide())
+                .flex_col()
+                .width_full()
+                .max_height_pct(80.0)
+                .border(1.0)
+                .border_radius(6.0)
+                .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+                .background(*config.get_color(LapceColor::PANEL_BACKGROUND))
+        })
+    };
+
+    container(
+        stack((
+            stack((
+                toast_list,
+                progress_list(progresses, notification.clone(), config),
+            ))
+            .style(move |s| {
+                s.apply_if(center_visible.get(), |s| s.hide())
+                    .flex_col()
+                    .width_full()
+            }),
+            center,
+        ))
+        .style(move |s| {
+            s.flex_col()
+                .justify_end()
+                .width(WIDTH as f32)
+                .max_width_pct(80.0)
+                .height_full()
+                .padding(10.0)
+        }),
+    )
+    .style(|s| s.absolute().size_full().justify_end())
+}
+
+fn progress_list(
+    progresses: RwSignal<IndexMap<ProgressToken, WorkProgress>>,
+    notification: NotificationData,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let id = AtomicU64::new(0);
+    list(
+        move || progresses.get(),
+        move |_| id.fetch_add(1, std::sync::atomic::Ordering::Relaxed),
+        move |(_, progress)| {
+            let WorkProgress {
+                token,
+                title,
+                message,
+                percentage,
+                cancellable,
+            } = progress;
+            let notification = notification.clone();
+            let message = match (message, percentage) {
+                (Some(message), Some(percentage)) => {
+                    format!("{message} ({percentage}%)")
+                }
+                (Some(message), None) => message,
+                (None, Some(percentage)) => format!("{percentage}%"),
+                (None, None) => String::new(),
+            };
+            let has_message = !message.is_empty();
+            stack((
+                stack((
+                    text(title).style(|s| {
+                        s.min_width(0.0)
+                            .flex_basis(0.0)
+                            .flex_grow(1.0)
+                            .font_weight(Weight::BOLD)
+                    }),
+                    container(clickable_icon(
+                        || LapceIcons::CLOSE,
+                        move || notification.cancel_progress(token.clone()),
+                        || false,
+                        || false,
+                        config,
+                    ))
+                    .style(move |s| {
+                        s.apply_if(!cancellable, |s| s.hide()).margin_left(6.0)
+                    }),
+                ))
+                .style(|s| s.width_full().items_start()),
+                text(message).style(move |s| {
+                    s.min_width(0.0)
+                        .margin_top(5.0)
+                        .apply_if(!has_message, |s| s.hide())
+                }),
+                empty().style(move |s| {
+                    let config = config.get();
+                    s.apply_if(percentage.is_none(), |s| s.hide())
+                        .margin_top(5.0)
+                        .height(3.0)
+                        .width_pct(percentage.unwrap_or(0).min(100) as f64)
+                        .background(*config.get_color(LapceColor::EDITOR_CARET))
+                }),
+            ))
+            .style(move |s| item_style(s, &config.get()).flex_col())
+        },
+    )
+    .style(|s| s.flex_col().width_full())
+}
+
+fn notification_item(
+    n: Notification,
+    notification: NotificationData,
+    toast: bool,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let id = n.id;
+    let level = n.level;
+    let icon = move || match level {
+        MessageType::ERROR => LapceIcons::ERROR,
+        MessageType::WARNING => LapceIcons::WARNING,
+        _ => LapceIcons::INFO,
+    };
+    let time = n.time.format("%H:%M").to_string();
+    let actions = n.actions.into_iter().enumerate().collect::<Vec<_>>();
+    let has_actions = !actions.is_empty();
+    let close = notification.clone();
+
+    stack((
+        svg(move || config.get().ui_svg(icon())).style(move |s| {
+            let config = config.get();
+            let size = config.ui.icon_size() as f32;
+            let color = match level {
+                MessageType::ERROR => config.get_color(LapceColor::LAPCE_ERROR),
+                MessageType::WARNING => config.get_color(LapceColor::LAPCE_WARN),
+                _ => config.get_color(LapceColor::LAPCE_ICON_ACTIVE),
+            };
+            s.min_width(size)
+                .size(size, size)
+                .margin_right(10.0)
+                .margin_top(4.0)
+                .color(*color)
+        }),
+        stack((
+            text(n.title).style(|s| {
+                s.min_width(0.0).line_height(1.6).font_weight(Weight::BOLD)
+            }),
+            text(n.message)
+                .style(|s| s.min_width(0.0).line_height(1.6).margin_top(5.0)),
+            list(
+                move || actions.clone(),
+                |(i, _)| *i,
+                move |(i, action)| {
+                    let notification = notification.clone();
+                    label(move || action.text.clone())
+                        .on_click_stop(move |_| notification.run_action(id, i))
+                        .style(move |s| {
+                            let config = config.get();
+                            s.margin_right(6.0)
+                                .padding_horiz(10.0)
+                                .line_height(1.6)
+                                .border(1.0)
+                                .border_radius(6.0)
+                                .border_color(
+                                    *config.get_color(LapceColor::LAPCE_BORDER),
+                                )
+                                .hover(|s| {
+                                    s.cursor(CursorStyle::Pointer).background(
+                                        *config.get_color(
+                                            LapceColor::PANEL_HOVERED_BACKGROUND,
+                                        ),
+                                    )
+                                })
+                        })
+                },
+            )
+            .style(move |s| s.apply_if(!has_actions, |s| s.hide()).margin_top(10.0)),
+            label(move || time.clone()).style(move |s| {
+                s.apply_if(toast, |s| s.hide())
+                    .margin_top(5.0)
+                    .color(*config.get().get_color(LapceColor::EDITOR_DIM))
+            }),
+        ))
+        .style(|s| s.flex_col().min_width(0.0).flex_basis(0.0).flex_grow(1.0)),
+        clickable_icon(
+            || LapceIcons::CLOSE,
+            move || {
+                if toast {
+                    close.dismiss_toast(id);
+                } else {
+                    close.close(id);
+                }
+            },
+            || false,
+            || false,
+            config,
+        )
+        .style(|s| s.margin_left(6.0)),
+    ))
+    .style(move |s| item_style(s, &config.get()).items_start())
+}
+
+fn item_style(s: Style, config: &LapceConfig) -> Style {
+    s.width_full()
+        .margin_bottom(10.0)
+        .padding(10.0)
+        .border(1.0)
+        .border_radius(6.0)
+        .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+        .background(*config.get_color(LapceColor::PANEL_BACKGROUND))
+}
@@ -15,17 +15,23 @@
 use floem::{
     ext_event::{create_ext_action, create_signal_from_channel},
     keyboard::ModifiersState,
+    peniko::kurbo::Vec2,
     reactive::{use_context, ReadSignal, RwSignal, Scope},
 };
 use itertools::Itertools;
 use lapce_core::{
-    buffer::rope_text::RopeText, command::FocusCommand, language::LapceLanguage,
-    mode::Mode, movement::Movement, selection::Selection, syntax::Syntax,
+    buffer::rope_text::RopeText, command::FocusCommand, cursor::Cursor,
+    indent::IndentStyle, language::LapceLanguage, mode::Mode, movement::Movement,
+    selection::Selection,
+};
+use lapce_rpc::{
+    buffer::LineEnding, plugin::PluginQuickPickItem, proxy::ProxyResponse,
+    source_control::FileCommit,
 };
-use lapce_rpc::proxy::ProxyResponse;
 use lapce_xi_rope::Rope;
 use lsp_types::DocumentSymbolResponse;
 use nucleo::Utf32Str;
+use regex::Regex;
 use strum::{EnumMessage, IntoEnumIterator};
 use tracing::error;
 
@@ -39,23 +45,32 @@
     },
     db::LapceDb,
     debug::{RunDebugConfigs, RunDebugMode},
+    doc::Document,
     editor::{
         location::{EditorLocation, EditorPosition},
         EditorData,
     },
-    id::EditorId,
+    editor_tab::EditorTabChild,
+    id::{EditorId, EditorTabId},
     keypress::{condition::Condition, KeyPressData, KeyPressFocus},
     main_split::MainSplitData,
-    proxy::path_from_url,
+    perf::{self, Metric},
+    proxy::{container::ContainerRemote, path_from_url},
     source_control::SourceControlData,
+    task::TaskConfigs,
     window_tab::{CommonData, Focus},
-    workspace::{LapceWorkspace, LapceWorkspaceType, SshHost},
+    workspace::{ContainerHost, LapceWorkspace, LapceWorkspaceType, SshHost},
 };
 
 pub mod item;
 pub mod kind;
 
+/// Added to the score of a file item when the input matches its file name, on top
+/// of the usual bonuses for consecutive characters and word boundaries.
+const FILE_NAME_MATCH_BONUS: u32 = 64;
+
 const DEFAULT_RUN_TOML: &str = include_str!("../../defaults/run.toml");
+const DEFAULT_TASKS_TOML: &str = include_str!("../../defaults/tasks.toml");
 
 #[derive(Clone, PartialEq, Eq)]
 pub enum PaletteStatus {
@@ -78,6 +93,39 @@ pub fn update_input(&mut self, input: String, kind: PaletteKind) {
     }
 }
 
+/// A file shown in the editor of the main split while its item is selected, which
+/// is closed again if the palette is cancelled.
+#[derive(Clone)]
+struct FilePreview {
+    path: PathBuf,
+    editor_tab_id: EditorTabId,
+    child: EditorTabChild,
+    /// Whether the document was only loaded to be previewed.
+    new_doc: bool,
+    /// What the active editor tab showed before the first preview.
+    origin: Option<(EditorTabId, EditorTabChild)>,
+    /// What the editor of `origin` showed, as a preview can be shown in it.
+    origin_editor: Option<OriginEditor>,
+}
+
+/// The document an editor showed and where in it, to show it again after a
+/// preview.
+#[derive(Clone)]
+struct OriginEditor {
+    doc: Rc<Document>,
+    cursor: Cursor,
+    scroll: Vec2,
+}
+
+/// The items a plugin asked the user to pick from, which is answered to the
+/// plugin with `id` once the palette closes.
+#[derive(Clone, Debug)]
+pub struct PluginQuickPick {
+    pub id: u64,
+    pub placeholder: Option<String>,
+    pub items: Vec<PluginQuickPickItem>,
+}
+
 #[derive(Clone)]
 pub struct PaletteData {
     run_id_counter: Arc<AtomicU64>,
@@ -87,12 +135,15 @@ pub struct PaletteData {
     pub index: RwSignal<usize>,
     pub preselect_index: RwSignal<Option<usize>>,
     pub items: RwSignal<im::Vector<PaletteItem>>,
+    /// The run that is waiting for the proxy to finish building the file list.
+    indexing_files: RwSignal<Option<u64>>,
     pub filtered_items: ReadSignal<im::Vector<PaletteItem>>,
     pub input: RwSignal<PaletteInput>,
     kind: RwSignal<PaletteKind>,
     pub input_editor: EditorData,
     pub preview_editor: Rc<EditorData>,
     pub has_preview: RwSignal<bool>,
+    file_preview: RwSignal<Option<FilePreview>>,
     pub keypress: ReadSignal<KeyPressData>,
     /// Listened on for which entry in the palette has been clicked
     pub clicked_index: RwSignal<Option<usize>>,
@@ -100,6 +151,7 @@ pub struct PaletteData {
     pub executed_run_configs: Rc<RefCell<HashMap<(RunDebugMode, String), Instant>>>,
     pub main_split: MainSplitData,
     pub references: RwSignal<Vec<EditorLocation>>,
+    pub quick_pick: RwSignal<Option<PluginQuickPick>>,
     pub source_control: SourceControlData,
     pub common: Rc<CommonData>,
 }
@@ -115,9 +167,11 @@ pub fn new(
     ) -> Self {
         let status = cx.create_rw_signal(PaletteStatus::Inactive);
         let items = cx.create_rw_signal(im::Vector::new());
+        let indexing_files = cx.create_rw_signal(None);
         let preselect_index = cx.create_rw_signal(None);
         let index = cx.create_rw_signal(0);
         let references = cx.create_rw_signal(Vec::new());
+        let quick_pick = cx.create_rw_signal(None);
         let input = cx.create_rw_signal(PaletteInput {
             input: "".to_string(),
             kind: PaletteKind::File,
@@ -206,10 +260,12 @@ pub fn new(
             index,
             preselect_index,
             items,
+            indexing_files,
             filtered_items,
             input_editor,
             preview_editor,
             has_preview,
+            file_preview: cx.create_rw_signal(None),
             input,
             kind,
             keypress,
@@ -217,6 +273,7 @@ pub fn new(
             executed_commands: Rc::new(RefCell::new(HashMap::new())),
             executed_run_configs: Rc::new(RefCell::new(HashMap::new())),
             references,
+            quick_pick,
             source_control,
             common,
         };
@@ -276,10 +333,16 @@ pub fn new(
                         .unwrap();
                     if let Some(new_kind) = new_kind {
                         palette.run_inner(new_kind);
-                    } else if input
-                        .with_untracked(|i| i.kind == PaletteKind::WorkspaceSymbol)
-                    {
-                        palette.run_inner(PaletteKind::WorkspaceSymbol);
+                    } else {
+                        let kind = input.with_untracked(|i| i.kind);
+                        if let PaletteKind::WorkspaceSymbol
+                        | PaletteKind::GoToLine
+                        | PaletteKind::KeepSelections
+                        | PaletteKind::RemoveSelections
+                        | PaletteKind::SCMReferences = kind
+                        {
+                            palette.run_inner(kind);
+                        }
                     }
                 }
                 Some(new_input)
@@ -329,6 +392,10 @@ pub fn run(&self, kind: PaletteKind) {
     /// Execute the internal behavior of the palette for the given kind. This ignores updating and
     /// focusing the palette input.
     fn run_inner(&self, kind: PaletteKind) {
+        // A plugin waiting for a pick gets nothing if the palette switches away
+        if kind != PaletteKind::PluginQuickPick {
+            self.finish_plugin_quick_pick(None);
+        }
         self.has_preview.set(false);
 
         let run_id = self.run_id_counter.fetch_add(1, Ordering::Relaxed) + 1;
@@ -339,13 +406,21 @@ fn run_inner(&self, kind: PaletteKind) {
             PaletteKind::File => {
                 self.get_files();
             }
+            PaletteKind::RecentFiles => {
+                self.get_recent_files();
+            }
             PaletteKind::Line => {
                 self.get_lines();
             }
+            PaletteKind::GoToLine => {
+                self.get_go_to_line();
+            }
             PaletteKind::Command => {
                 self.get_commands();
             }
-            PaletteKind::Workspace => {
+            PaletteKind::Workspace
+            | PaletteKind::RemoveRecentWorkspace
+            | PaletteKind::TogglePinRecentWorkspace => {
                 self.get_workspaces();
             }
             PaletteKind::Reference => {
@@ -360,9 +435,18 @@ fn run_inner(&self, kind: PaletteKind) {
             PaletteKind::SshHost => {
                 self.get_ssh_hosts();
             }
+            PaletteKind::WslDistro => {
+                self.get_wsl_distros();
+            }
+            PaletteKind::Container => {
+                self.get_containers();
+            }
             PaletteKind::RunAndDebug => {
                 self.get_run_configs();
             }
+            PaletteKind::Task => {
+                self.get_tasks();
+            }
             PaletteKind::ColorTheme => {
                 self.get_color_themes();
             }
@@ -372,10 +456,29 @@ fn run_inner(&self, kind: PaletteKind) {
             PaletteKind::Language => {
                 self.get_languages();
             }
+            PaletteKind::LineEnding => {
+                self.get_line_endings();
+            }
+            PaletteKind::Indentation => {
+                self.get_indentations();
+            }
+            PaletteKind::ClipboardHistory => {
+                self.get_clipboard_history();
+            }
+            PaletteKind::KeepSelections => {
+                self.get_filter_selections(true);
+            }
+            PaletteKind::RemoveSelections => {
+                self.get_filter_selections(false);
+            }
             PaletteKind::SCMReferences => {
                 self.get_scm_references();
             }
+            PaletteKind::FileHistory => {
+                self.get_file_history();
+            }
             PaletteKind::TerminalProfile => self.get_terminal_profiles(),
+            PaletteKind::PluginQuickPick => self.get_plugin_quick_pick(),
         }
     }
 
@@ -417,39 +520,61 @@ fn get_palette_help(&self) {
     fn get_files(&self) {
         let workspace = self.workspace.clone();
         let set_items = self.items.write_only();
-        let send =
-            create_ext_action(self.common.scope, move |items: Vec<PathBuf>| {
-                let items = items
-                    .into_iter()
-                    .map(|path| {
-                        let full_path = path.clone();
-                        // Strip the workspace prefix off the path, to avoid clutter
-                        let path =
-                            if let Some(workspace_path) = workspace.path.as_ref() {
-                                path.strip_prefix(workspace_path)
-                                    .unwrap_or(&full_path)
-                                    .to_path_buf()
-                            } else {
-                                path
-                            };
-                        let filter_text = path.to_str().unwrap_or("").to_string();
-                        PaletteItem {
-                            content: PaletteItemContent::File { path, full_path },
-                            filter_text,
-                            score: 0,
-                            indices: Vec::new(),
-                        }
-                    })
-                    .collect::<im::Vector<_>>();
+        let indexing_files = self.indexing_files;
+        let run_id = self.run_id.get_untracked();
+        indexing_files.set(Some(run_id));
+        let send = create_ext_action(
+            self.common.scope,
+            move |items: im::Vector<PaletteItem>| {
+                if indexing_files.get_untracked() == Some(run_id) {
+                    indexing_files.set(None);
+                }
                 set_items.set(items);
-            });
+            },
+        );
         self.common.proxy.get_files(move |result| {
             if let Ok(ProxyResponse::GetFilesResponse { items }) = result {
+                // Building the items of a huge workspace takes a while, so it's done
+                // here rather than on the UI thread
+                let items = items
+                    .into_iter()
+                    .map(|path| file_item(&workspace, path))
+                    .collect::<im::Vector<_>>();
                 send(items);
             }
         });
     }
 
+    /// Initialize the palette with the open files, the most recently used first.
+    /// The previous file is selected so that switching back is a single key.
+    fn get_recent_files(&self) {
+        let docs = self.main_split.docs.get_untracked();
+        let items: im::Vector<PaletteItem> = self
+            .main_split
+            .recent_files
+            .get_untracked()
+            .into_iter()
+            .filter(|path| docs.contains_key(path))
+            .map(|path| file_item(&self.workspace, path))
+            .collect();
+        if items.len() > 1 {
+            self.preselect_index.set(Some(1));
+        }
+        self.items.set(items);
+    }
+
+    /// Show the files the proxy has found so far while it builds the file list
+    /// for the first time, instead of waiting for the whole workspace.
+    pub fn add_indexed_files(&self, paths: Vec<PathBuf>) {
+        if self.indexing_files.get_untracked() != Some(self.run_id.get_untracked()) {
+            return;
+        }
+        let workspace = self.workspace.clone();
+        self.items.update(|items| {
+            items.extend(paths.into_iter().map(|path| file_item(&workspace, path)))
+        });
+    }
+
     /// Initialize the palette with the lines in the current document.
     fn get_lines(&self) {
         let editor = self.main_split.active_editor.get_untracked();
@@ -490,12 +615,85 @@ fn get_lines(&self) {
         self.items.set(items);
     }
 
+    /// Initialize the palette with the line and column typed after `:`, which
+    /// can be absolute or an offset from the cursor like `+10` or `-5`.
+    fn get_go_to_line(&self) {
+        let editor = self.main_split.active_editor.get_untracked();
+        let (doc, cursor) = match editor {
+            Some(editor) => (editor.view.doc.get_untracked(), editor.cursor),
+            None => {
+                self.items.set(im::Vector::new());
+                return;
+            }
+        };
+
+        let input = self
+            .input_editor
+            .view
+            .doc
+            .get_untracked()
+            .buffer
+            .with_untracked(|buffer| buffer.to_string());
+        let input = input.strip_prefix(':').unwrap_or(&input);
+
+        let buffer = doc.buffer.get_untracked();
+        let last_line = buffer.last_line();
+        let current_line =
+            buffer.line_of_offset(cursor.with_untracked(|c| c.offset()));
+        let items = match parse_go_to_line(input, current_line, last_line) {
+            Some((line, column)) => {
+                let filter_text = match column {
+                    Some(column) => {
+                        format!("Go to line {}, column {}", line + 1, column + 1)
+                    }
+                    None => format!("Go to line {}", line + 1),
+                };
+                im::vector![PaletteItem {
+                    content: PaletteItemContent::GoToLine { line, column },
+                    filter_text,
+                    score: 0,
+                    indices: vec![],
+                }]
+            }
+            None => im::Vector::new(),
+        };
+        self.items.set(items);
+    }
+
+    /// Initialize the palette with the regex typed in, which the selections of
+    /// the active editor are kept or removed by.
+    fn get_filter_selections(&self, keep: bool) {
+        let pattern = self
+            .input_editor
+            .view
+            .doc
+            .get_untracked()
+            .buffer
+            .with_untracked(|buffer| buffer.to_string());
+        let items = if pattern.is_empty() || Regex::new(&pattern).is_err() {
+            im::Vector::new()
+        } else {
+            let filter_text = if keep {
+                format!("Keep selections matching {pattern}")
+            } else {
+                format!("Remove selections matching {pattern}")
+            };
+            im::vector![PaletteItem {
+                content: PaletteItemContent::FilterSelections { pattern, keep },
+                filter_text,
+                score: 0,
+                indices: vec![],
+            }]
+        };
+        self.items.set(items);
+    }
+
     fn get_commands(&self) {
         const EXCLUDED_ITEMS: &[&str] = &["palette.command"];
 
-        let items = self.keypress.with_untracked(|keypress| {
+        let mut items = self.keypress.with_untracked(|keypress| {
             // Get all the commands we've executed, and sort them by how recently they were
-            // executed. Ignore commands without descriptions.
+            // executed.
             let mut items: im::Vector<PaletteItem> = self
                 .executed_commands
                 .borrow()
@@ -503,18 +701,17 @@ fn get_commands(&self) {
                 .sorted_by_key(|(_, i)| *i)
                 .rev()
                 .filter_map(|(key, _)| {
-                    keypress.commands.get(key).and_then(|c| {
-                        c.kind.desc().as_ref().map(|m| PaletteItem {
-                            content: PaletteItemContent::Command { cmd: c.clone() },
-                            filter_text: m.to_string(),
-                            score: 0,
-                            indices: vec![],
-                        })
+                    keypress.commands.get(key).map(|c| PaletteItem {
+                        content: PaletteItemContent::Command { cmd: c.clone() },
+                        filter_text: c.kind.name(),
+                        score: 0,
+                        indices: vec![],
                     })
                 })
                 .collect();
             // Add all the rest of the commands, ignoring palette commands (because we're in it)
-            // and commands that are sorted earlier due to being executed.
+            // and commands that are sorted earlier due to being executed. Commands without a
+            // description are listed by a name made from the command.
             items.extend(keypress.commands.iter().filter_map(|(_, c)| {
                 if EXCLUDED_ITEMS.contains(&c.kind.str()) {
                     return None;
@@ -524,9 +721,9 @@ fn get_commands(&self) {
                     return None;
                 }
 
-                c.kind.desc().as_ref().map(|m| PaletteItem {
+                Some(PaletteItem {
                     content: PaletteItemContent::Command { cmd: c.clone() },
-                    filter_text: m.to_string(),
+                    filter_text: c.kind.name(),
                     score: 0,
                     indices: vec![],
                 })
@@ -534,6 +731,15 @@ fn get_commands(&self) {
 
             items
         });
+        // Plugin commands aren't bound to keys, so they come after the others
+        items.extend(self.common.plugin_commands.get_untracked().into_iter().map(
+            |command| PaletteItem {
+                filter_text: command.title.clone(),
+                content: PaletteItemContent::PluginCommand { command },
+                score: 0,
+                indices: vec![],
+            },
+        ));
 
         self.items.set(items);
     }
@@ -541,11 +747,11 @@ fn get_commands(&self) {
     /// Initialize the palette with all the available workspaces, local and remote.
     fn get_workspaces(&self) {
         let db: Arc<LapceDb> = use_context().unwrap();
-        let workspaces = db.recent_workspaces().unwrap_or_default();
+        let workspaces = db.recent_workspaces_pinned_first();
 
         let items = workspaces
             .into_iter()
-            .filter_map(|w| {
+            .filter_map(|(w, pinned)| {
                 let text = w.path.as_ref()?.to_str()?.to_string();
                 let filter_text = match &w.kind {
                     LapceWorkspaceType::Local => text,
@@ -553,12 +759,18 @@ fn get_workspaces(&self) {
                         format!("[{ssh}] {text}")
                     }
                     #[cfg(windows)]
-                    LapceWorkspaceType::RemoteWSL => {
-                        format!("[wsl] {text}")
+                    LapceWorkspaceType::RemoteWSL(wsl) => {
+                        format!("[wsl: {}] {text}", wsl.distro)
+                    }
+                    LapceWorkspaceType::RemoteContainer(host) => {
+                        format!("[container: {}] {text}", host.container)
                     }
                 };
                 Some(PaletteItem {
-                    content: PaletteItemContent::Workspace { workspace: w },
+                    content: PaletteItemContent::Workspace {
+                        workspace: w,
+                        pinned,
+                    },
                     filter_text,
                     score: 0,
                     indices: vec![],
@@ -716,28 +928,102 @@ fn get_workspace_symbols(&self) {
             });
     }
 
+    /// The hosts saved in the settings, followed by the ones of recent workspaces.
     fn get_ssh_hosts(&self) {
+        let config = self.common.config.get_untracked();
+        let mut saved: Vec<(&String, &SshHost)> =
+            config.core.ssh_hosts.iter().collect();
+        saved.sort_by(|a, b| a.0.cmp(b.0));
+
         let db: Arc<LapceDb> = use_context().unwrap();
         let workspaces = db.recent_workspaces().unwrap_or_default();
         let mut hosts = HashSet::new();
         for workspace in workspaces.iter() {
             if let LapceWorkspaceType::RemoteSSH(ssh) = &workspace.kind {
-                hosts.insert(ssh.clone());
+                if !saved.iter().any(|(_, host)| *host == ssh) {
+                    hosts.insert(ssh.clone());
+                }
             }
         }
 
-        let items = hosts
+        let items = saved
             .iter()
-            .map(|ssh| PaletteItem {
+            .map(|(name, ssh)| PaletteItem {
+                content: PaletteItemContent::SshHost {
+                    host: (*ssh).clone(),
+                },
+                filter_text: format!("{name} ({ssh})"),
+                score: 0,
+                indices: vec![],
+            })
+            .chain(hosts.iter().map(|ssh| PaletteItem {
                 content: PaletteItemContent::SshHost { host: ssh.clone() },
                 filter_text: ssh.to_string(),
                 score: 0,
                 indices: vec![],
-            })
+            }))
             .collect();
         self.items.set(items);
     }
 
+    /// The WSL distros installed on this machine, the default one first.
+    fn get_wsl_distros(&self) {
+        #[cfg(windows)]
+        {
+            let set_items = self.items.write_only();
+            let send = create_ext_action(
+                self.common.scope,
+                move |distros: Vec<crate::proxy::wsl::WslDistro>| {
+                    let items = distros
+                        .into_iter()
+                        .map(|distro| PaletteItem {
+                            filter_text: distro.name.clone(),
+                            content: PaletteItemContent::WslDistro {
+                                name: distro.name,
+                            },
+                            score: 0,
+                            indices: vec![],
+                        })
+                        .collect();
+                    set_items.set(items);
+                },
+            );
+            std::thread::spawn(move || {
+                let mut distros =
+                    crate::proxy::wsl::WslDistro::all().unwrap_or_default();
+                distros.sort_by_key(|distro| !distro.default);
+                send(distros);
+            });
+        }
+        #[cfg(not(windows))]
+        self.items.update(|items| items.clear());
+    }
+
+    /// The Docker containers that are running.
+    fn get_containers(&self) {
+        let set_items = self.items.write_only();
+        let send =
+            create_ext_action(self.common.scope, move |containers: Vec<String>| {
+                let items = containers
+                    .into_iter()
+                    .map(|name| PaletteItem {
+                        filter_text: name.clone(),
+                        content: PaletteItemContent::Container { name },
+                        score: 0,
+                        indices: vec![],
+                    })
+                    .collect();
+                set_items.set(items);
+            });
+        std::thread::spawn(move || {
+            let containers = ContainerRemote::running().unwrap_or_else(|e| {
+                error!("Failed to list the running containers: {e}");
+                Vec::new()
+            });
+            send(containers);
+        });
+    }
+
     fn set_run_configs(&self, content: String) {
         let configs: Option<RunDebugConfigs> = toml::from_str(&content).ok();
         if configs.is_none() {
@@ -828,6 +1114,68 @@ fn get_run_configs(&self) {
         }
     }
 
+    fn set_tasks(&self, content: String) {
+        let tasks: Option<TaskConfigs> = toml::from_str(&content).ok();
+        let Some(tasks) = tasks else {
+            if let Some(path) = self.workspace.path.as_ref() {
+                let path = path.join(".lapce").join("tasks.toml");
+                self.common
+                    .internal_command
+                    .send(InternalCommand::OpenFile { path });
+            }
+            self.items.set(im::Vector::new());
+            return;
+        };
+
+        let items = tasks
+            .tasks
+            .into_iter()
+            .map(|task| PaletteItem {
+                filter_text: format!(
+                    "{} {} {}",
+                    task.name,
+                    task.command,
+                    task.args.clone().unwrap_or_default().join(" ")
+                ),
+                content: PaletteItemContent::Task { task },
+                score: 0,
+                indices: vec![],
+            })
+            .collect();
+        self.items.set(items);
+    }
+
+    fn get_tasks(&self) {
+        if let Some(workspace) = self.common.workspace.path.as_deref() {
+            let tasks_toml = workspace.join(".lapce").join("tasks.toml");
+            let (doc, new_doc) = self.main_split.get_doc(tasks_toml);
+            if !new_doc {
+                let content = doc.buffer.with_untracked(|b| b.to_string());
+                self.set_tasks(content);
+            } else {
+                let loaded = doc.loaded;
+                let palette = self.clone();
+                self.common.scope.create_effect(move |prev_loaded| {
+                    if prev_loaded == Some(true) {
+                        return true;
+                    }
+
+                    let loaded = loaded.get();
+                    if loaded {
+                        let mut content =
+                            doc.buffer.with_untracked(|b| b.to_string());
+                        if content.is_empty() {
+                            doc.reload(Rope::from(DEFAULT_TASKS_TOML), false);
+                            content = DEFAULT_TASKS_TOML.to_string();
+                        }
+                        palette.set_tasks(content);
+                    }
+                    loaded
+                });
+            }
+        }
+    }
+
     fn get_color_themes(&self) {
         let config = self.common.config.get_untracked();
         let items = config
@@ -888,6 +1236,70 @@ fn get_languages(&self) {
         self.items.set(items);
     }
 
+    fn get_line_endings(&self) {
+        let items = [LineEnding::Lf, LineEnding::CrLf]
+            .iter()
+            .map(|kind| PaletteItem {
+                content: PaletteItemContent::LineEnding { kind: *kind },
+                filter_text: kind.name().to_string(),
+                score: 0,
+                indices: Vec::new(),
+            })
+            .collect();
+        if let Some(editor) = self.main_split.active_editor.get_untracked() {
+            let doc = editor.view.doc.get_untracked();
+            let line_ending = doc.line_ending.get_untracked();
+            self.preselect_matching(&items, line_ending.name());
+        }
+        self.items.set(items);
+    }
+
+    fn get_indentations(&self) {
+        let items = [
+            IndentStyle::Spaces(2),
+            IndentStyle::Spaces(4),
+            IndentStyle::Spaces(8),
+            IndentStyle::Tabs,
+        ]
+        .iter()
+        .map(|style| PaletteItem {
+            content: PaletteItemContent::Indentation { style: *style },
+            filter_text: style.name(),
+            score: 0,
+            indices: Vec::new(),
+        })
+        .collect();
+        if let Some(editor) = self.main_split.active_editor.get_untracked() {
+            let doc = editor.view.doc.get_untracked();
+            let style = doc.buffer.with_untracked(|b| b.indent_style());
+            self.preselect_matching(&items, &style.name());
+        }
+        self.items.set(items);
+    }
+
+    fn get_clipboard_history(&self) {
+        let items = self.common.register.with_untracked(|register| {
+            register
+                .history()
+                .enumerate()
+                .map(|(index, data)| PaletteItem {
+                    content: PaletteItemContent::ClipboardHistory { index },
+                    // Shown on one line, with where the line breaks were marked
+                    filter_text: data
+                        .content
+                        .trim()
+                        .lines()
+                        .map(str::trim_end)
+                        .collect::<Vec<_>>()
+                        .join(" ↵ "),
+                    score: 0,
+                    indices: Vec::new(),
+                })
+                .collect()
+        });
+        self.items.set(items);
+    }
+
     fn get_scm_references(&self) {
         let branches = self.source_control.branches.get_untracked();
         let tags = self.source_control.tags.get_untracked();
@@ -912,9 +1324,74 @@ fn get_scm_references(&self) {
                 indices: Vec::new(),
             });
         }
+        let input = self.input.with_untracked(|input| input.input.clone());
+        let name = input.trim();
+        if !name.is_empty() && !items.iter().any(|item| item.filter_text == name) {
+            items.push_back(PaletteItem {
+                content: PaletteItemContent::SCMCreateBranch {
+                    name: name.to_string(),
+                },
+                filter_text: format!("Create branch {name}"),
+                score: 0,
+                indices: Vec::new(),
+            });
+        }
         self.items.set(items);
     }
 
+    /// Initialize the palette with the commits that changed the active file.
+    fn get_file_history(&self) {
+        let Some(editor) = self.main_split.active_editor.get_untracked() else {
+            return;
+        };
+        let Some(path) = editor
+            .view
+            .doc
+            .with_untracked(|doc| doc.content.with_untracked(|c| c.path().cloned()))
+        else {
+            return;
+        };
+
+        let set_items = self.items.write_only();
+        let send = {
+            let path = path.clone();
+            create_ext_action(self.common.scope, move |commits: Vec<FileCommit>| {
+                let items = commits
+                    .into_iter()
+                    .map(|commit| {
+                        let date = chrono::NaiveDateTime::from_timestamp_opt(
+                            commit.time,
+                            0,
+                        )
+                        .map(|time| time.format("%Y-%m-%d").to_string())
+                        .unwrap_or_default();
+                        let filter_text = format!(
+                            "{} {} ({}, {date})",
+                            &commit.id[..7.min(commit.id.len())],
+                            commit.summary,
+                            commit.author,
+                        );
+                        PaletteItem {
+                            content: PaletteItemContent::FileHistory {
+                                path: path.clone(),
+                                commit,
+                            },
+                            filter_text,
+                            score: 0,
+                            indices: Vec::new(),
+                        }
+                    })
+                    .collect::<im::Vector<_>>();
+                set_items.set(items);
+            })
+        };
+        self.common.proxy.git_file_history(path, move |result| {
+            if let Ok(ProxyResponse::GitFileHistoryResponse { commits }) = result {
+                send(commits);
+            }
+        });
+    }
+
     fn get_terminal_profiles(&self) {
         let profiles = self.common.config.get().terminal.profiles.clone();
         let mut items: im::Vector<PaletteItem> = im::Vector::new();
@@ -951,6 +1428,64 @@ fn get_terminal_profiles(&self) {
         self.items.set(items);
     }
 
+    fn get_plugin_quick_pick(&self) {
+        let items = self
+            .quick_pick
+            .with_untracked(|quick_pick| {
+                quick_pick.as_ref().map(|quick_pick| {
+                    quick_pick
+                        .items
+                        .iter()
+                        .enumerate()
+                        .map(|(index, item)| PaletteItem {
+                            content: PaletteItemContent::PluginQuickPickItem {
+                                index,
+                            },
+                            filter_text: match &item.description {
+                                Some(description) => {
+                                    format!("{} {description}", item.label)
+                                }
+                                None => item.label.clone(),
+                            },
+                            score: 0,
+                            indices: Vec::new(),
+                        })
+                        .collect()
+                })
+            })
+            .unwrap_or_default();
+
+        self.items.set(items);
+    }
+
+    /// Let the user pick one of the items of a plugin, answering the plugin's
+    /// earlier quick pick if it is still open.
+    pub fn run_plugin_quick_pick(&self, quick_pick: PluginQuickPick) {
+        self.finish_plugin_quick_pick(None);
+        self.quick_pick.set(Some(quick_pick));
+        self.run(PaletteKind::PluginQuickPick);
+    }
+
+    /// Send the picked item to the plugin that is waiting for it, if any.
+    fn finish_plugin_quick_pick(&self, index: Option<usize>) {
+        if let Some(quick_pick) = self.quick_pick.try_update(|q| q.take()).flatten()
+        {
+            self.common
+                .proxy
+                .plugin_quick_pick_result(quick_pick.id, index);
+        }
+    }
+
+    /// The text shown in the empty input.
+    pub fn placeholder(&self) -> String {
+        if self.kind.get() != PaletteKind::PluginQuickPick {
+            return String::new();
+        }
+        self.quick_pick
+            .with(|q| q.as_ref().and_then(|q| q.placeholder.clone()))
+            .unwrap_or_default()
+    }
+
     fn preselect_matching(&self, items: &im::Vector<PaletteItem>, matching: &str) {
         let Some((idx, _)) = items
             .iter()
@@ -965,6 +1500,16 @@ fn preselect_matching(&self, items: &im::Vector<PaletteItem>, matching: &str) {
     fn select(&self) {
         let index = self.index.get_untracked();
         let items = self.filtered_items.get_untracked();
+        if let Some(PaletteItemContent::PluginQuickPickItem { index }) =
+            items.get(index).map(|item| &item.content)
+        {
+            self.finish_plugin_quick_pick(Some(*index));
+        }
+        if self.input.with_untracked(|input| !input.input.is_empty()) {
+            let doc = self.input_editor.view.doc.get_untracked();
+            let text = doc.buffer.with_untracked(|buffer| buffer.to_string());
+            self.common.palette_history.add(&text);
+        }
         self.close();
         if let Some(item) = items.get(index) {
             match &item.content {
@@ -1011,15 +1556,60 @@ fn select(&self) {
                         },
                     );
                 }
+                PaletteItemContent::GoToLine { line, column } => {
+                    let editor = self.main_split.active_editor.get_untracked();
+                    let doc = match editor {
+                        Some(editor) => editor.view.doc.get_untracked(),
+                        None => {
+                            return;
+                        }
+                    };
+                    let path = doc
+                        .content
+                        .with_untracked(|content| content.path().cloned());
+                    let path = match path {
+                        Some(path) => path,
+                        None => return,
+                    };
+                    let position = go_to_line_position(&doc, *line, *column);
+                    self.common.internal_command.send(
+                        InternalCommand::JumpToLocation {
+                            location: EditorLocation {
+                                path,
+                                position: Some(position),
+                                scroll_offset: None,
+                                ignore_unconfirmed: false,
+                                same_editor_tab: false,
+                            },
+                        },
+                    );
+                }
                 PaletteItemContent::Command { cmd } => {
                     self.common.lapce_command.send(cmd.clone());
                 }
-                PaletteItemContent::Workspace { workspace } => {
-                    self.common.window_common.window_command.send(
-                        WindowCommand::SetWorkspace {
-                            workspace: workspace.clone(),
-                        },
-                    );
+                PaletteItemContent::Workspace { workspace, pinned } => {
+                    let db: Arc<LapceDb> = use_context().unwrap();
+                    match self.kind.get_untracked() {
+                        PaletteKind::RemoveRecentWorkspace => {
+                            if let Err(err) = db.remove_recent_workspace(workspace) {
+                                error!("{:?}", err);
+                            }
+                        }
+                        PaletteKind::TogglePinRecentWorkspace => {
+                            if let Err(err) =
+                                db.set_workspace_pinned(workspace, !pinned)
+                            {
+                                error!("{:?}", err);
+                            }
+                        }
+                        _ => {
+                            self.common.window_common.window_command.send(
+                                WindowCommand::SetWorkspace {
+                                    workspace: workspace.clone(),
+                                },
+                            );
+                        }
+                    }
                 }
                 PaletteItemContent::Reference { location, .. } => {
                     self.common.internal_command.send(
@@ -1034,11 +1624,35 @@ fn select(&self) {
                             workspace: LapceWorkspace {
                                 kind: LapceWorkspaceType::RemoteSSH(host.clone()),
                                 path: None,
+                                folders: Vec::new(),
+                                last_open: 0,
+                            },
+                        },
+                    );
+                }
+                #[cfg(windows)]
+                PaletteItemContent::WslDistro { name } => {
+                    self.common.window_common.window_command.send(
+                        WindowCommand::SetWorkspace {
+                            workspace: LapceWorkspace {
+                                kind: LapceWorkspaceType::RemoteWSL(
+                                    crate::workspace::WslHost {
+                                        distro: name.clone(),
+                                    },
+                                ),
+                                path: None,
+                                folders: Vec::new(),
                                 last_open: 0,
                             },
                         },
                     );
                 }
+                // Only listed on Windows
+                #[cfg(not(windows))]
+                PaletteItemContent::WslDistro { .. } => {}
+                PaletteItemContent::Container { name } => {
+                    self.attach_container(name.clone());
+                }
                 PaletteItemContent::DocumentSymbol { range, .. } => {
                     let editor = self.main_split.active_editor.get_untracked();
                     let doc = match editor {
@@ -1084,6 +1698,11 @@ fn select(&self) {
                         },
                     );
                 }
+                PaletteItemContent::Task { task } => {
+                    self.common
+                        .internal_command
+                        .send(InternalCommand::RunTask { task: task.clone() });
+                }
                 PaletteItemContent::ColorTheme { name } => self
                     .common
                     .internal_command
@@ -1107,7 +1726,7 @@ fn select(&self) {
                         }
                     };
                     if name.is_empty() || name.to_lowercase().eq("plain text") {
-                        doc.set_syntax(Syntax::plaintext())
+                        doc.set_language(LapceLanguage::Plaintext)
                     } else {
                         let lang = match LapceLanguage::from_name(name) {
                             Some(v) => v,
@@ -1117,6 +1736,36 @@ fn select(&self) {
                     }
                     doc.trigger_syntax_change(None);
                 }
+                PaletteItemContent::LineEnding { kind } => {
+                    let editor = self.main_split.active_editor.get_untracked();
+                    if let Some(editor) = editor {
+                        editor.view.doc.get_untracked().set_line_ending(*kind);
+                    }
+                }
+                PaletteItemContent::ClipboardHistory { index } => {
+                    let editor = self.main_split.active_editor.get_untracked();
+                    if let Some(editor) = editor {
+                        editor.paste_from_history(*index);
+                    }
+                }
+                PaletteItemContent::FilterSelections { pattern, keep } => {
+                    let editor = self.main_split.active_editor.get_untracked();
+                    if let (Some(editor), Ok(regex)) = (editor, Regex::new(pattern))
+                    {
+                        editor.filter_selections(&regex, *keep);
+                    }
+                }
+                PaletteItemContent::Indentation { style } => {
+                    let editor = self.main_split.active_editor.get_untracked();
+                    if let Some(editor) = editor {
+                        editor
+                            .view
+                            .doc
+                            .get_untracked()
+                            .buffer
+                            .update(|buffer| buffer.set_indent_style(*style));
+                    }
+                }
                 PaletteItemContent::SCMReference { name } => {
                     self.common
                         .lapce_command
@@ -1127,12 +1776,27 @@ fn select(&self) {
                         data: Some(serde_json::json!(name.to_owned())),
                     });
                 }
+                PaletteItemContent::SCMCreateBranch { name } => {
+                    self.common.proxy.git_create_branch(name.to_owned());
+                }
+                PaletteItemContent::FileHistory { path, commit } => {
+                    self.main_split
+                        .open_file_revision_changes(path.clone(), commit.id.clone());
+                }
                 PaletteItemContent::TerminalProfile { name: _, profile } => self
                     .common
                     .internal_command
                     .send(InternalCommand::NewTerminal {
                         profile: Some(profile.to_owned()),
                     }),
+                PaletteItemContent::PluginCommand { command } => {
+                    self.common.proxy.execute_plugin_command(
+                        command.volt.clone(),
+                        command.id.clone(),
+                    );
+                }
+                // Answered before the palette was closed
+                PaletteItemContent::PluginQuickPickItem { .. } => {}
             }
         } else if self.kind.get_untracked() == PaletteKind::SshHost {
             let input = self.input.with_untracked(|input| input.input.clone());
@@ -1142,13 +1806,37 @@ fn select(&self) {
                     workspace: LapceWorkspace {
                         kind: LapceWorkspaceType::RemoteSSH(ssh),
                         path: None,
+                        folders: Vec::new(),
                         last_open: 0,
                     },
                 },
             );
+        } else if self.kind.get_untracked() == PaletteKind::Container {
+            // The container may not be listed yet while `docker ps` runs
+            let input = self.input.with_untracked(|input| input.input.clone());
+            let name = input.trim();
+            if !name.is_empty() {
+                self.attach_container(name.to_string());
+            }
         }
     }
 
+    fn attach_container(&self, container: String) {
+        self.common
+            .window_common
+            .window_command
+            .send(WindowCommand::SetWorkspace {
+                workspace: LapceWorkspace {
+                    kind: LapceWorkspaceType::RemoteContainer(ContainerHost {
+                        container,
+                    }),
+                    path: None,
+                    folders: Vec::new(),
+                    last_open: 0,
+                },
+            });
+    }
+
     /// Update the preview for the currently active palette item, if it has one.
     fn preview(&self) {
         if self.status.get_untracked() == PaletteStatus::Inactive {
@@ -1160,7 +1848,15 @@ fn preview(&self) {
         if let Some(item) = items.get(index) {
             match &item.content {
                 PaletteItemContent::PaletteHelp { .. } => {}
-                PaletteItemContent::File { .. } => {}
+                PaletteItemContent::File { full_path, .. } => {
+                    self.preview_file(EditorLocation {
+                        path: full_path.clone(),
+                        position: None,
+                        scroll_offset: None,
+                        ignore_unconfirmed: false,
+                        same_editor_tab: false,
+                    });
+                }
                 PaletteItemContent::Line { line, .. } => {
                     self.has_preview.set(true);
                     let editor = self.main_split.active_editor.get_untracked();
@@ -1190,21 +1886,50 @@ fn preview(&self) {
                         None,
                     );
                 }
+                PaletteItemContent::GoToLine { line, column } => {
+                    self.has_preview.set(true);
+                    let editor = self.main_split.active_editor.get_untracked();
+                    let doc = match editor {
+                        Some(editor) => editor.view.doc.get_untracked(),
+                        None => {
+                            return;
+                        }
+                    };
+                    let path = doc
+                        .content
+                        .with_untracked(|content| content.path().cloned());
+                    let path = match path {
+                        Some(path) => path,
+                        None => return,
+                    };
+                    let position = go_to_line_position(&doc, *line, *column);
+                    self.preview_editor.update_doc(doc);
+                    self.preview_editor.go_to_location(
+                        EditorLocation {
+                            path,
+                            position: Some(position),
+                            scroll_offset: None,
+                            ignore_unconfirmed: false,
+                            same_editor_tab: false,
+                        },
+                        false,
+                        None,
+                    );
+                }
                 PaletteItemContent::Command { .. } => {}
                 PaletteItemContent::Workspace { .. } => {}
                 PaletteItemContent::RunAndDebug { .. } => {}
+                PaletteItemContent::Task { .. } => {}
                 PaletteItemContent::SshHost { .. } => {}
+                PaletteItemContent::WslDistro { .. } => {}
+                PaletteItemContent::Container { .. } => {}
                 PaletteItemContent::Language { .. } => {}
+                PaletteItemContent::LineEnding { .. } => {}
+                PaletteItemContent::Indentation { .. } => {}
+                PaletteItemContent::ClipboardHistory { .. } => {}
+                PaletteItemContent::FilterSelections { .. } => {}
                 PaletteItemContent::Reference { location, .. } => {
-                    self.has_preview.set(true);
-                    let (doc, new_doc) =
-                        self.main_split.get_doc(location.path.clone());
-                    self.preview_editor.update_doc(doc);
-                    self.preview_editor.go_to_location(
-                        location.clone(),
-                        new_doc,
-                        None,
-                    );
+                    self.preview_file(location.clone());
                 }
                 PaletteItemContent::DocumentSymbol { range, .. } => {
                     self.has_preview.set(true);
@@ -1261,7 +1986,151 @@ fn preview(&self) {
                         save: false,
                     }),
                 PaletteItemContent::SCMReference { .. } => {}
+                PaletteItemContent::SCMCreateBranch { .. } => {}
+                PaletteItemContent::FileHistory { .. } => {}
                 PaletteItemContent::TerminalProfile { .. } => {}
+                PaletteItemContent::PluginCommand { .. } => {}
+                PaletteItemContent::PluginQuickPickItem { .. } => {}
+            }
+        }
+    }
+
+    /// Show the location in the editor of the main split that previews files, so
+    /// that looking through the items doesn't leave each of them open.
+    fn preview_file(&self, location: EditorLocation) {
+        let previous = self.file_preview.get_untracked();
+        let (origin, origin_editor) = match &previous {
+            Some(preview) => (preview.origin.clone(), preview.origin_editor.clone()),
+            None => {
+                let origin = self.active_editor_tab_child();
+                let origin_editor = self.origin_editor(origin.as_ref());
+                (origin, origin_editor)
+            }
+        };
+        let path = location.path.clone();
+        let Some((editor_tab_id, child, new_doc)) =
+            self.main_split.preview_location(location)
+        else {
+            return;
+        };
+        let new_doc = match previous {
+            Some(previous) if previous.path == path => new_doc || previous.new_doc,
+            Some(previous) => {
+                if previous.new_doc {
+                    self.main_split.discard_doc(&previous.path);
+                }
+                new_doc
+            }
+            None => new_doc,
+        };
+        self.file_preview.set(Some(FilePreview {
+            path,
+            editor_tab_id,
+            child,
+            new_doc,
+            origin,
+            origin_editor,
+        }));
+    }
+
+    fn origin_editor(
+        &self,
+        origin: Option<&(EditorTabId, EditorTabChild)>,
+    ) -> Option<OriginEditor> {
+        let Some((_, EditorTabChild::Editor(editor_id))) = origin else {
+            return None;
+        };
+        let editor = self
+            .main_split
+            .editors
+            .with_untracked(|editors| editors.get(editor_id).cloned())?;
+        Some(OriginEditor {
+            doc: editor.view.doc.get_untracked(),
+            cursor: editor.cursor.get_untracked(),
+            scroll: editor.viewport.with_untracked(|v| v.origin().to_vec2()),
+        })
+    }
+
+    fn active_editor_tab_child(&self) -> Option<(EditorTabId, EditorTabChild)> {
+        let editor_tab_id = self.main_split.active_editor_tab.get_untracked()?;
+        let editor_tab =
+            self.main_split.editor_tabs.with_untracked(|editor_tabs| {
+                editor_tabs.get(&editor_tab_id).copied()
+            })?;
+        let child = editor_tab.with_untracked(|editor_tab| {
+            editor_tab
+                .children
+                .get(editor_tab.active)
+                .map(|(_, _, child)| child.clone())
+        })?;
+        Some((editor_tab_id, child))
+    }
+
+    /// Close the file previewed while the palette was open, unless it was edited
+    /// since, and show what was shown before again. A preview that was shown in
+    /// the editor that was active is replaced with what that editor showed.
+    fn discard_file_preview(&self) {
+        let Some(preview) = self.file_preview.try_update(|p| p.take()).flatten()
+        else {
+            return;
+        };
+        let confirmed = match &preview.child {
+            EditorTabChild::Editor(editor_id) => self
+                .main_split
+                .editors
+                .with_untracked(|editors| {
+                    editors.get(editor_id).map(|e| e.confirmed.get_untracked())
+                })
+                .unwrap_or(true),
+            _ => true,
+        };
+        let is_origin =
+            preview.origin.as_ref().map(|(_, child)| child) == Some(&preview.child);
+        if is_origin {
+            if let (EditorTabChild::Editor(editor_id), Some(origin_editor)) =
+                (&preview.child, preview.origin_editor)
+            {
+                let editor = self
+                    .main_split
+                    .editors
+                    .with_untracked(|editors| editors.get(editor_id).cloned());
+                if let Some(editor) = editor {
+                    let buffer_id = editor.view.doc.get_untracked().buffer_id;
+                    if buffer_id != origin_editor.doc.buffer_id {
+                        editor.update_doc(origin_editor.doc);
+                    }
+                    editor.cursor.set(origin_editor.cursor);
+                    editor.scroll_to.set(Some(origin_editor.scroll));
+                }
+            }
+            if preview.new_doc {
+                self.main_split.discard_doc(&preview.path);
+            }
+        } else if !confirmed {
+            self.main_split.editor_tab_child_close(
+                preview.editor_tab_id,
+                preview.child,
+                true,
+            );
+            if preview.new_doc {
+                self.main_split.discard_doc(&preview.path);
+            }
+        }
+
+        if let Some((editor_tab_id, child)) = preview.origin {
+            let editor_tab =
+                self.main_split.editor_tabs.with_untracked(|editor_tabs| {
+                    editor_tabs.get(&editor_tab_id).copied()
+                });
+            if let Some(editor_tab) = editor_tab {
+                editor_tab.update(|editor_tab| {
+                    if let Some(index) =
+                        editor_tab.children.iter().position(|(_, _, c)| c == &child)
+                    {
+                        editor_tab.active = index;
+                    }
+                });
+                self.main_split.active_editor_tab.set(Some(editor_tab_id));
             }
         }
     }
@@ -1282,7 +2151,10 @@ fn cancel(&self) {
 
     /// Close the palette, reverting focus back to the workbench.
     fn close(&self) {
+        // A plugin waiting for a pick gets nothing if none was picked
+        self.finish_plugin_quick_pick(None);
         self.status.set(PaletteStatus::Inactive);
+        self.file_preview.set(None);
         if self.common.focus.get_untracked() == Focus::Palette {
             self.common.focus.set(Focus::Workbench);
         }
@@ -1299,7 +2171,7 @@ fn close(&self) {
     }
 
     /// Move to the next entry in the palette list, wrapping around if needed.
-    fn next(&self) {
+    pub fn next(&self) {
         let index = self.index.get_untracked();
         let len = self.filtered_items.with_untracked(|i| i.len());
         let new_index = Movement::Down.update_index(index, len, 1, true);
@@ -1325,6 +2197,7 @@ fn previous_page(&self) {
     fn run_focus_command(&self, cmd: &FocusCommand) -> CommandExecuted {
         match cmd {
             FocusCommand::ModalClose => {
+                self.discard_file_preview();
                 self.cancel();
             }
             FocusCommand::ListNext => {
@@ -1347,6 +2220,32 @@ fn run_focus_command(&self, cmd: &FocusCommand) -> CommandExecuted {
         CommandExecuted::Yes
     }
 
+    /// Step back to an earlier query, but only from the first item of the list
+    /// when nothing was typed, so that Up still moves through the items.
+    fn history_previous(&self) -> CommandExecuted {
+        let history = &self.common.palette_history;
+        if self.index.get_untracked() == 0
+            && (self.input.with_untracked(|input| input.input.is_empty())
+                || history.is_browsing(&self.input_editor))
+            && history.previous(&self.input_editor)
+        {
+            CommandExecuted::Yes
+        } else {
+            CommandExecuted::No
+        }
+    }
+
+    fn history_next(&self) -> CommandExecuted {
+        let history = &self.common.palette_history;
+        if history.is_browsing(&self.input_editor)
+            && history.next(&self.input_editor)
+        {
+            CommandExecuted::Yes
+        } else {
+            CommandExecuted::No
+        }
+    }
+
     fn filter_items(
         run_id: Arc<AtomicU64>,
         current_run_id: u64,
@@ -1354,6 +2253,7 @@ fn filter_items(
         items: im::Vector<PaletteItem>,
         matcher: &mut nucleo::Matcher,
     ) -> Option<im::Vector<PaletteItem>> {
+        let _timer = perf::time(Metric::Palette);
         if input.is_empty() {
             return Some(items);
         }
@@ -1376,13 +2276,45 @@ fn filter_items(
             let mut indices = Vec::new();
             let mut filter_text_buf = Vec::new();
             let filter_text = Utf32Str::new(&i.filter_text, &mut filter_text_buf);
-            if let Some(score) = pattern.indices(filter_text, matcher, &mut indices)
-            {
-                let mut item = i.clone();
-                item.score = score;
-                item.indices = indices.into_iter().map(|i| i as usize).collect();
-                filtered_items.push(item);
+            let Some(mut score) =
+                pattern.indices(filter_text, matcher, &mut indices)
+            else {
+                continue;
+            };
+
+            // A file whose name matches on its own is almost always the one that
+            // is wanted, so it beats files that only match across the path.
+            if let PaletteItemContent::File { .. } = &i.content {
+                let name_start = i
+                    .filter_text
+                    .rfind(std::path::is_separator)
+                    .map(|i| i + 1)
+                    .unwrap_or(0);
+                if name_start > 0 {
+                    let mut name_indices = Vec::new();
+                    let mut name_buf = Vec::new();
+                    let name =
+                        Utf32Str::new(&i.filter_text[name_start..], &mut name_buf);
+                    if let Some(name_score) =
+                        pattern.indices(name, matcher, &mut name_indices)
+                    {
+                        let name_start =
+                            i.filter_text[..name_start].chars().count() as u32;
+                        score = score.max(name_score) + FILE_NAME_MATCH_BONUS;
+                        indices = name_indices
+                            .into_iter()
+                            .map(|i| i + name_start)
+                            .collect();
+                    }
+                } else {
+                    score += FILE_NAME_MATCH_BONUS;
+                }
             }
+
+            let mut item = i.clone();
+            item.score = score;
+            item.indices = char_indices_to_byte_indices(&i.filter_text, indices);
+            filtered_items.push(item);
         }
 
         filtered_items.sort_by(|a, b| {
@@ -1479,6 +2411,12 @@ fn run_command(
     ) -> CommandExecuted {
         match &command.kind {
             CommandKind::Workbench(_) => {}
+            CommandKind::Focus(FocusCommand::HistoryPrevious) => {
+                return self.history_previous();
+            }
+            CommandKind::Focus(FocusCommand::HistoryNext) => {
+                return self.history_next();
+            }
             CommandKind::Focus(cmd) => {
                 self.run_focus_command(cmd);
             }
@@ -1496,3 +2434,142 @@ fn receive_char(&self, c: &str) {
         self.input_editor.receive_char(c);
     }
 }
+
+fn file_item(workspace: &LapceWorkspace, path: PathBuf) -> PaletteItem {
+    let full_path = path.clone();
+    // Strip the workspace prefix off the path, to avoid clutter. The root folders
+    // of a multi-root workspace keep their name to tell them apart.
+    let root = workspace
+        .path
+        .iter()
+        .chain(workspace.folders.iter())
+        .filter(|root| full_path.starts_with(root))
+        .max_by_key(|root| root.components().count());
+    let path = match root {
+        Some(root) => {
+            let prefix = if workspace.folders.is_empty() {
+                root.as_path()
+            } else {
+                root.parent().unwrap_or(root)
+            };
+            path.strip_prefix(prefix)
+                .unwrap_or(&full_path)
+                .to_path_buf()
+        }
+        None => path,
+    };
+    let filter_text = path.to_str().unwrap_or("").to_string();
+    PaletteItem {
+        content: PaletteItemContent::File { path, full_path },
+        filter_text,
+        score: 0,
+        indices: Vec::new(),
+    }
+}
+
+/// The matcher reports the positions of the matched characters, while the
+/// palette items highlight by byte offset into the filter text. The indices of a
+/// pattern with several words can also come out of order or repeated.
+pub fn char_indices_to_byte_indices(
+    text: &str,
+    mut indices: Vec<u32>,
+) -> Vec<usize> {
+    indices.sort_unstable();
+    indices.dedup();
+    let mut indices = indices.into_iter().peekable();
+    let mut byte_indices = Vec::new();
+    for (char_index, (byte_index, _)) in text.char_indices().enumerate() {
+        let Some(&next) = indices.peek() else {
+            break;
+        };
+        if next as usize == char_index {
+            byte_indices.push(byte_index);
+            indices.next();
+        }
+    }
+    byte_indices
+}
+
+/// Parse the input of the go to line palette, `line` or `line:column`, into zero
+/// based positions. The line can also be relative to `current_line`, like `+10`.
+fn parse_go_to_line(
+    input: &str,
+    current_line: usize,
+    last_line: usize,
+) -> Option<(usize, Option<usize>)> {
+    let input = input.trim();
+    let (line, column) = match input.split_once([':', ',']) {
+        Some((line, column)) => {
+            let column = column.trim();
+            (line.trim(), (!column.is_empty()).then_some(column))
+        }
+        None => (input, None),
+    };
+
+    let line = if let Some(n) = line.strip_prefix('+') {
+        current_line.saturating_add(n.parse::<usize>().ok()?)
+    } else if let Some(n) = line.strip_prefix('-') {
+        current_line.saturating_sub(n.parse::<usize>().ok()?)
+    } else {
+        line.parse::<usize>().ok()?.saturating_sub(1)
+    };
+    let column = match column {
+        Some(column) => Some(column.parse::<usize>().ok()?.saturating_sub(1)),
+        None => None,
+    };
+    Some((line.min(last_line), column))
+}
+
+/// The column counts characters and stops at the end of the line.
+fn go_to_line_position(
+    doc: &Document,
+    line: usize,
+    column: Option<usize>,
+) -> EditorPosition {
+    let Some(column) = column else {
+        return EditorPosition::Line(line);
+    };
+    let buffer = doc.buffer.get_untracked();
+    let start = buffer.offset_of_line(line);
+    let end = buffer.line_end_offset(line, true);
+    let offset = buffer
+        .slice_to_cow(start..end)
+        .char_indices()
+        .nth(column)
+        .map(|(i, _)| start + i)
+        .unwrap_or(end);
+    EditorPosition::Offset(offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{char_indices_to_byte_indices, parse_go_to_line};
+
+    #[test]
+    fn test_char_indices_to_byte_indices() {
+        assert_eq!(
+            char_indices_to_byte_indices("abc", vec![2, 0, 2]),
+            vec![0, 2]
+        );
+        assert_eq!(
+            char_indices_to_byte_indices("héllo/wörld", vec![1, 2, 8]),
+            vec![1, 3, 10]
+        );
+    }
+
+    #[test]
+    fn test_parse_go_to_line() {
+        assert_eq!(parse_go_to_line("12", 0, 100), Some((11, None)));
+        assert_eq!(parse_go_to_line("12:5", 0, 100), Some((11, Some(4))));
+        assert_eq!(parse_go_to_line(" 12, 5 ", 0, 100), Some((11, Some(4))));
+        assert_eq!(parse_go_to_line("12:", 0, 100), Some((11, None)));
+        assert_eq!(parse_go_to_line("+10", 20, 100), Some((30, None)));
+        assert_eq!(parse_go_to_line("-5:2", 20, 100), Some((15, Some(1))));
+        assert_eq!(parse_go_to_line("-50", 20, 100), Some((0, None)));
+        assert_eq!(parse_go_to_line("500", 0, 100), Some((100, None)));
+        assert_eq!(parse_go_to_line("0", 0, 100), Some((0, None)));
+        assert_eq!(parse_go_to_line("", 0, 100), None);
+        assert_eq!(parse_go_to_line("abc", 0, 100), None);
+        assert_eq!(parse_go_to_line("12:x", 0, 100), None);
+    }
+}
@@ -1,12 +1,17 @@
 use std::path::PathBuf;
 
-use lapce_rpc::dap_types::RunDebugConfig;
+use lapce_core::indent::IndentStyle;
+use lapce_rpc::{
+    buffer::LineEnding, dap_types::RunDebugConfig, plugin::PluginCommand,
+    source_control::FileCommit,
+};
 use lsp_types::{Range, SymbolKind};
 
 use crate::{
     command::{LapceCommand, LapceWorkbenchCommand},
     debug::RunDebugMode,
     editor::location::EditorLocation,
+    task::TaskConfig,
     workspace::{LapceWorkspace, SshHost},
 };
 
@@ -31,11 +36,17 @@ pub enum PaletteItemContent {
         line: usize,
         content: String,
     },
+    /// The target of the `:` palette, both zero based.
+    GoToLine {
+        line: usize,
+        column: Option<usize>,
+    },
     Command {
         cmd: LapceCommand,
     },
     Workspace {
         workspace: LapceWorkspace,
+        pinned: bool,
     },
     Reference {
         path: PathBuf,
@@ -56,10 +67,19 @@ pub enum PaletteItemContent {
     SshHost {
         host: SshHost,
     },
+    WslDistro {
+        name: String,
+    },
+    Container {
+        name: String,
+    },
     RunAndDebug {
         mode: RunDebugMode,
         config: RunDebugConfig,
     },
+    Task {
+        task: TaskConfig,
+    },
     ColorTheme {
         name: String,
     },
@@ -69,11 +89,41 @@ pub enum PaletteItemContent {
     Language {
         name: String,
     },
+    LineEnding {
+        kind: LineEnding,
+    },
+    Indentation {
+        style: IndentStyle,
+    },
+    /// An entry of the clipboard history, the newest first.
+    ClipboardHistory {
+        index: usize,
+    },
+    /// Keep the selections the pattern matches, or remove them.
+    FilterSelections {
+        pattern: String,
+        keep: bool,
+    },
     SCMReference {
         name: String,
     },
+    /// Create a branch with the name typed in.
+    SCMCreateBranch {
+        name: String,
+    },
+    FileHistory {
+        path: PathBuf,
+        commit: FileCommit,
+    },
     TerminalProfile {
         name: String,
         profile: lapce_rpc::terminal::TerminalProfile,
     },
+    PluginCommand {
+        command: PluginCommand,
+    },
+    /// One of the items a plugin asked the user to pick from.
+    PluginQuickPickItem {
+        index: usize,
+    },
 }
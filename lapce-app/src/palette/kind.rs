@@ -6,19 +6,33 @@
 pub enum PaletteKind {
     PaletteHelp,
     File,
+    RecentFiles,
     Line,
+    GoToLine,
     Command,
     Workspace,
+    RemoveRecentWorkspace,
+    TogglePinRecentWorkspace,
     Reference,
     DocumentSymbol,
     WorkspaceSymbol,
     SshHost,
+    WslDistro,
+    Container,
     RunAndDebug,
+    Task,
     ColorTheme,
     IconTheme,
     Language,
+    LineEnding,
+    Indentation,
+    ClipboardHistory,
+    KeepSelections,
+    RemoveSelections,
     SCMReferences,
+    FileHistory,
     TerminalProfile,
+    PluginQuickPick,
 }
 
 impl PaletteKind {
@@ -27,20 +41,34 @@ pub fn symbol(&self) -> &'static str {
         match &self {
             PaletteKind::PaletteHelp => "?",
             PaletteKind::Line => "/",
+            PaletteKind::GoToLine => ":",
             PaletteKind::DocumentSymbol => "@",
             PaletteKind::WorkspaceSymbol => "#",
             // PaletteKind::GlobalSearch => "?",
-            PaletteKind::Workspace => ">",
-            PaletteKind::Command => ":",
+            PaletteKind::Workspace => "~",
+            PaletteKind::Command => ">",
             PaletteKind::TerminalProfile => "<",
             PaletteKind::File
+            | PaletteKind::RecentFiles
+            | PaletteKind::RemoveRecentWorkspace
+            | PaletteKind::TogglePinRecentWorkspace
             | PaletteKind::Reference
             | PaletteKind::SshHost
+            | PaletteKind::WslDistro
+            | PaletteKind::Container
             | PaletteKind::RunAndDebug
+            | PaletteKind::Task
             | PaletteKind::ColorTheme
             | PaletteKind::IconTheme
             | PaletteKind::Language
-            | PaletteKind::SCMReferences => "",
+            | PaletteKind::LineEnding
+            | PaletteKind::Indentation
+            | PaletteKind::ClipboardHistory
+            | PaletteKind::KeepSelections
+            | PaletteKind::RemoveSelections
+            | PaletteKind::SCMReferences
+            | PaletteKind::FileHistory
+            | PaletteKind::PluginQuickPick => "",
         }
     }
 
@@ -51,8 +79,9 @@ pub fn from_input(input: &str) -> PaletteKind {
             _ if input.starts_with('/') => PaletteKind::Line,
             _ if input.starts_with('@') => PaletteKind::DocumentSymbol,
             _ if input.starts_with('#') => PaletteKind::WorkspaceSymbol,
-            _ if input.starts_with('>') => PaletteKind::Workspace,
-            _ if input.starts_with(':') => PaletteKind::Command,
+            _ if input.starts_with('~') => PaletteKind::Workspace,
+            _ if input.starts_with(':') => PaletteKind::GoToLine,
+            _ if input.starts_with('>') => PaletteKind::Command,
             _ if input.starts_with('<') => PaletteKind::TerminalProfile,
             _ => PaletteKind::File,
         }
@@ -63,6 +92,7 @@ pub fn command(self) -> Option<LapceWorkbenchCommand> {
         match self {
             PaletteKind::PaletteHelp => Some(LapceWorkbenchCommand::PaletteHelp),
             PaletteKind::Line => Some(LapceWorkbenchCommand::PaletteLine),
+            PaletteKind::GoToLine => Some(LapceWorkbenchCommand::PaletteGoToLine),
             PaletteKind::DocumentSymbol => {
                 Some(LapceWorkbenchCommand::PaletteSymbol)
             }
@@ -70,20 +100,51 @@ pub fn command(self) -> Option<LapceWorkbenchCommand> {
                 Some(LapceWorkbenchCommand::PaletteWorkspaceSymbol)
             }
             PaletteKind::Workspace => Some(LapceWorkbenchCommand::PaletteWorkspace),
+            PaletteKind::RemoveRecentWorkspace => {
+                Some(LapceWorkbenchCommand::PaletteRemoveRecentWorkspace)
+            }
+            PaletteKind::TogglePinRecentWorkspace => {
+                Some(LapceWorkbenchCommand::PaletteTogglePinRecentWorkspace)
+            }
             PaletteKind::Command => Some(LapceWorkbenchCommand::PaletteCommand),
             PaletteKind::File => Some(LapceWorkbenchCommand::Palette),
+            PaletteKind::RecentFiles => {
+                Some(LapceWorkbenchCommand::PaletteRecentFiles)
+            }
             PaletteKind::Reference => None, // InternalCommand::PaletteReferences
             PaletteKind::SshHost => Some(LapceWorkbenchCommand::ConnectSshHost),
+            PaletteKind::WslDistro => Some(LapceWorkbenchCommand::ConnectWsl),
+            PaletteKind::Container => Some(LapceWorkbenchCommand::ConnectContainer),
             PaletteKind::RunAndDebug => {
                 Some(LapceWorkbenchCommand::PaletteRunAndDebug)
             }
+            PaletteKind::Task => Some(LapceWorkbenchCommand::PaletteRunTask),
             PaletteKind::ColorTheme => Some(LapceWorkbenchCommand::ChangeColorTheme),
             PaletteKind::IconTheme => Some(LapceWorkbenchCommand::ChangeIconTheme),
             PaletteKind::Language => Some(LapceWorkbenchCommand::ChangeFileLanguage),
+            PaletteKind::LineEnding => {
+                Some(LapceWorkbenchCommand::ChangeFileLineEnding)
+            }
+            PaletteKind::Indentation => {
+                Some(LapceWorkbenchCommand::ChangeFileIndentation)
+            }
+            PaletteKind::ClipboardHistory => {
+                Some(LapceWorkbenchCommand::PaletteClipboardHistory)
+            }
+            PaletteKind::KeepSelections => {
+                Some(LapceWorkbenchCommand::KeepSelections)
+            }
+            PaletteKind::RemoveSelections => {
+                Some(LapceWorkbenchCommand::RemoveSelections)
+            }
             PaletteKind::SCMReferences => {
                 Some(LapceWorkbenchCommand::PaletteSCMReferences)
             }
+            PaletteKind::FileHistory => {
+                Some(LapceWorkbenchCommand::SourceControlFileHistory)
+            }
             PaletteKind::TerminalProfile => None, // InternalCommand::NewTerminal
+            PaletteKind::PluginQuickPick => None, // CoreNotification::ShowQuickPick
         }
     }
 
@@ -101,13 +162,24 @@ pub fn command(self) -> Option<LapceWorkbenchCommand> {
     pub fn get_input<'a>(&self, input: &'a str) -> &'a str {
         match self {
             PaletteKind::File
+            | PaletteKind::RecentFiles
+            | PaletteKind::RemoveRecentWorkspace
+            | PaletteKind::TogglePinRecentWorkspace
             | PaletteKind::Reference
             | PaletteKind::SshHost
+            | PaletteKind::WslDistro
+            | PaletteKind::Container
             | PaletteKind::RunAndDebug
+            | PaletteKind::Task
             | PaletteKind::ColorTheme
             | PaletteKind::IconTheme
             | PaletteKind::Language
-            | PaletteKind::SCMReferences => input,
+            | PaletteKind::LineEnding
+            | PaletteKind::Indentation
+            | PaletteKind::ClipboardHistory
+            | PaletteKind::SCMReferences
+            | PaletteKind::FileHistory
+            | PaletteKind::PluginQuickPick => input,
             PaletteKind::PaletteHelp
             | PaletteKind::Command
             | PaletteKind::Workspace
@@ -117,6 +189,11 @@ pub fn get_input<'a>(&self, input: &'a str) -> &'a str {
             | PaletteKind::TerminalProfile
             // | PaletteType::GlobalSearch
              => input.get(1..).unwrap_or(""),
+            // The target is parsed from the raw input, so the single item that
+            // describes it is never filtered out
+            PaletteKind::GoToLine
+            | PaletteKind::KeepSelections
+            | PaletteKind::RemoveSelections => "",
         }
     }
 
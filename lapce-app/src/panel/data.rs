@@ -27,6 +27,7 @@ pub fn default_panel_order() -> PanelOrder {
             PanelKind::Plugin,
             PanelKind::SourceControl,
             PanelKind::Debug,
+            PanelKind::UndoTree,
         ],
     );
     order.insert(
@@ -42,23 +42,23 @@ pub fn debug_panel(
         {
             let terminal = terminal.clone();
             stack((
-                panel_header("Processes".to_string(), config),
+                panel_header("panel-processes", config),
                 debug_processes(terminal, config),
             ))
             .style(|s| s.width_pct(100.0).flex_col().height(150.0))
         },
         stack((
-            panel_header("Variables".to_string(), config),
+            panel_header("panel-variables", config),
             variables_view(window_tab_data.clone()),
         ))
         .style(|s| s.width_pct(100.0).flex_grow(1.0).flex_basis(0.0).flex_col()),
         stack((
-            panel_header("Stack Frames".to_string(), config),
+            panel_header("panel-stack-frames", config),
             debug_stack_traces(terminal, internal_command, config),
         ))
         .style(|s| s.width_pct(100.0).flex_grow(1.0).flex_basis(0.0).flex_col()),
         stack((
-            panel_header("Breakpoints".to_string(), config),
+            panel_header("panel-breakpoints", config),
             breakpoints_view(window_tab_data.clone()),
         ))
         .style(|s| s.width_pct(100.0).flex_col().height(150.0)),
@@ -15,6 +15,7 @@ pub enum PanelKind {
     Search,
     Problem,
     Debug,
+    UndoTree,
 }
 
 impl PanelKind {
@@ -27,6 +28,7 @@ pub fn svg_name(&self) -> &'static str {
             PanelKind::Search => LapceIcons::SEARCH,
             PanelKind::Problem => LapceIcons::PROBLEM,
             PanelKind::Debug => LapceIcons::DEBUG,
+            PanelKind::UndoTree => LapceIcons::UNDO_TREE,
         }
     }
 
@@ -8,4 +8,5 @@
 pub mod source_control_view;
 pub mod style;
 pub mod terminal_view;
+pub mod undo_tree_view;
 pub mod view;
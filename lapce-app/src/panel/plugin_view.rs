@@ -69,12 +69,12 @@ pub fn plugin_panel(
 
     stack((
         stack((
-            panel_header("Installed".to_string(), config),
+            panel_header("panel-installed", config),
             installed_view(plugin.clone()),
         ))
         .style(|s| s.flex_col().width_pct(100.0).flex_grow(1.0).flex_basis(0.0)),
         stack((
-            panel_header("Available".to_string(), config),
+            panel_header("panel-available", config),
             available_view(plugin.clone()),
         ))
         .style(|s| s.flex_col().width_pct(100.0).flex_grow(1.0).flex_basis(0.0)),
@@ -29,7 +29,7 @@ pub fn problem_panel(
     let is_bottom = position.is_bottom();
     stack((
         stack((
-            panel_header("Errors".to_string(), config),
+            panel_header("panel-errors", config),
             problem_section(window_tab_data.clone(), DiagnosticSeverity::ERROR),
         ))
         .style(move |s| {
@@ -42,7 +42,7 @@ pub fn problem_panel(
                 .apply_if(!is_bottom, |s| s.border_bottom(1.0))
         }),
         stack((
-            panel_header("Warnings".to_string(), config),
+            panel_header("panel-warnings", config),
             problem_section(window_tab_data, DiagnosticSeverity::WARNING),
         ))
         .style(|s| s.flex_col().flex_basis(0.0).flex_grow(1.0)),
@@ -18,6 +18,7 @@
     command::{CommandKind, InternalCommand, LapceCommand, LapceWorkbenchCommand},
     config::{color::LapceColor, icon::LapceIcons},
     editor::view::{cursor_caret, editor_view, LineRegion},
+    localization::tr,
     settings::checkbox,
     source_control::SourceControlData,
     window_tab::{Focus, WindowTabData},
@@ -170,7 +171,7 @@ pub fn source_control_panel(
         ))
         .style(|s| s.flex_col().width_pct(100.0).padding(10.0)),
         stack((
-            panel_header("Changes".to_string(), config),
+            panel_header("panel-changes", config),
             file_diffs_view(source_control),
         ))
         .style(|s| s.flex_col().size_pct(100.0, 100.0)),
@@ -308,8 +309,9 @@ fn file_diffs_view(source_control: SourceControlData) -> impl View {
 
             if let Event::PointerDown(pointer_event) = event {
                 if pointer_event.button.is_secondary() {
-                    let menu = Menu::new("")
-                        .entry(MenuItem::new("Discard Changes").action(discard));
+                    let menu = Menu::new("").entry(
+                        MenuItem::new(tr("menu-discard-changes")).action(discard),
+                    );
                     show_context_menu(menu, None);
                 }
             }
@@ -0,0 +1,152 @@
+use std::{rc::Rc, sync::Arc, time::Instant};
+
+use floem::{
+    reactive::{create_memo, Memo, ReadSignal},
+    style::CursorStyle,
+    view::View,
+    views::{container, label, list, scroll, stack, Decorators},
+};
+use lapce_core::buffer::{Buffer, UndoGroup};
+
+use super::{position::PanelPosition, view::panel_header};
+use crate::{
+    config::{color::LapceColor, LapceConfig},
+    editor::EditorData,
+    window_tab::WindowTabData,
+};
+
+/// How far a branch is indented from the one it branched off.
+const BRANCH_INDENT: f32 = 12.0;
+
+/// An undo group in the order the tree is drawn in.
+#[derive(Clone, PartialEq)]
+struct UndoTreeRow {
+    group: usize,
+    /// How many branches deep the group is.
+    depth: usize,
+    time: Instant,
+    current: bool,
+    saved: bool,
+}
+
+/// The groups from the oldest down, with the branches that were left by undoing
+/// and typing something else indented under the group they branched off.
+fn undo_tree_rows(buffer: &Buffer) -> Vec<UndoTreeRow> {
+    let groups = buffer.undo_groups();
+    let current = buffer.current_undo_group();
+    let saved = buffer.pristine_undo_group();
+
+    let mut children = vec![Vec::new(); groups.len()];
+    for (group, UndoGroup { parent, .. }) in groups.iter().enumerate() {
+        if let Some(parent) = parent {
+            children[*parent].push(group);
+        }
+    }
+
+    let mut rows = Vec::with_capacity(groups.len());
+    // Not recursive, since the history of a long session is a very deep tree
+    let mut stack = vec![(0, 0)];
+    while let Some((group, depth)) = stack.pop() {
+        rows.push(UndoTreeRow {
+            group,
+            depth,
+            time: groups[group].time,
+            current: group == current,
+            saved: group == saved,
+        });
+        // The newest edit continues the line, after the older branches
+        if let Some((newest, older)) = children[group].split_last() {
+            stack.push((*newest, depth));
+            stack.extend(older.iter().rev().map(|child| (*child, depth + 1)));
+        }
+    }
+    rows
+}
+
+fn time_ago(time: Instant) -> String {
+    let secs = time.elapsed().as_secs();
+    if secs < 5 {
+        "just now".to_string()
+    } else if secs < 60 {
+        format!("{secs}s ago")
+    } else if secs < 60 * 60 {
+        format!("{}m ago", secs / 60)
+    } else {
+        format!("{}h ago", secs / (60 * 60))
+    }
+}
+
+pub fn undo_tree_panel(
+    window_tab_data: Rc<WindowTabData>,
+    _position: PanelPosition,
+) -> impl View {
+    let config = window_tab_data.common.config;
+    let active_editor = window_tab_data.main_split.active_editor;
+    let rows = create_memo(move |_| {
+        let Some(editor) = active_editor.get() else {
+            return Vec::new();
+        };
+        let doc = editor.view.doc.get();
+        doc.buffer.with(undo_tree_rows)
+    });
+
+    stack((
+        panel_header("panel-undo-tree", config),
+        container(
+            scroll(
+                list(
+                    move || rows.get(),
+                    |row| (row.group, row.depth, row.current, row.saved),
+                    move |row| row_view(row, active_editor, config),
+                )
+                .style(|s| s.flex_col().width_pct(100.0).line_height(1.6)),
+            )
+            .style(|s| s.absolute().size_pct(100.0, 100.0)),
+        )
+        .style(|s| s.size_pct(100.0, 100.0)),
+    ))
+    .style(|s| s.flex_col().size_pct(100.0, 100.0))
+}
+
+fn row_view(
+    row: UndoTreeRow,
+    active_editor: Memo<Option<Rc<EditorData>>>,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let text = if row.group == 0 {
+        "Original".to_string()
+    } else {
+        format!("Edit {}", row.group)
+    };
+    let marker = if row.current { "●" } else { "○" };
+    let detail = if row.saved {
+        format!("{}, saved", time_ago(row.time))
+    } else {
+        time_ago(row.time)
+    };
+    stack((
+        label(move || format!("{marker} {text}")).style(|s| s.margin_right(6.0)),
+        label(move || detail.clone()).style(move |s| {
+            s.color(*config.get().get_color(LapceColor::EDITOR_DIM))
+        }),
+    ))
+    .on_click_stop(move |_| {
+        if let Some(editor) = active_editor.get_untracked() {
+            editor.undo_to_group(row.group);
+        }
+    })
+    .style(move |s| {
+        let config = config.get();
+        s.width_pct(100.0)
+            .padding_left(10.0 + row.depth as f32 * BRANCH_INDENT)
+            .padding_right(10.0)
+            .apply_if(row.current, |s| {
+                s.background(*config.get_color(LapceColor::PANEL_CURRENT_BACKGROUND))
+            })
+            .hover(|s| {
+                s.cursor(CursorStyle::Pointer).background(
+                    *config.get_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                )
+            })
+    })
+}
@@ -19,11 +19,13 @@
     problem_view::problem_panel,
     source_control_view::source_control_panel,
     terminal_view::terminal_panel,
+    undo_tree_view::undo_tree_panel,
 };
 use crate::{
     app::clickable_icon,
     config::{color::LapceColor, icon::LapceIcons, LapceConfig},
     file_explorer::view::file_explorer_panel,
+    localization::tr,
     window_tab::{DragContent, WindowTabData},
 };
 
@@ -312,6 +314,9 @@ fn panel_view(
                 PanelKind::Debug => {
                     container_box(debug_panel(window_tab_data.clone(), position))
                 }
+                PanelKind::UndoTree => {
+                    container_box(undo_tree_panel(window_tab_data.clone(), position))
+                }
             };
             view.style(|s| s.size_pct(100.0, 100.0))
         },
@@ -325,11 +330,13 @@ fn panel_view(
     })
 }
 
+/// The header of a section of a panel, with the text of `key` in the catalog.
 pub fn panel_header(
-    header: String,
+    key: &'static str,
     config: ReadSignal<Arc<LapceConfig>>,
 ) -> impl View {
-    container(label(move || header.clone())).style(move |s| {
+    // The catalogs are reloaded along with the config
+    container(label(move || config.with(|_| tr(key)))).style(move |s| {
         s.padding_horiz(10.0)
             .padding_vert(6.0)
             .width_pct(100.0)
@@ -364,6 +371,7 @@ fn panel_picker(
                 PanelKind::Search => LapceIcons::SEARCH,
                 PanelKind::Problem => LapceIcons::PROBLEM,
                 PanelKind::Debug => LapceIcons::DEBUG_ALT,
+                PanelKind::UndoTree => LapceIcons::UNDO_TREE,
             };
             let is_active = {
                 let window_tab_data = window_tab_data.clone();
@@ -0,0 +1,245 @@
+//! The peek window, which shows where a symbol is defined or referenced in a
+//! small read-only editor below the cursor line, instead of jumping there.
+
+use std::{path::PathBuf, rc::Rc};
+
+use floem::{
+    keyboard::ModifiersState,
+    peniko::kurbo::Rect,
+    reactive::{RwSignal, Scope},
+};
+use lapce_core::{command::FocusCommand, mode::Mode};
+
+use crate::{
+    command::{CommandExecuted, CommandKind, InternalCommand, LapceCommand},
+    editor::{
+        location::{EditorLocation, EditorPosition},
+        EditorData,
+    },
+    id::EditorId,
+    keypress::{condition::Condition, KeyPressFocus},
+    main_split::MainSplitData,
+    window_tab::{CommonData, Focus},
+};
+
+#[derive(Clone)]
+pub struct PeekData {
+    pub active: RwSignal<bool>,
+    /// The editor the peek window was opened from.
+    pub source: RwSignal<Option<Rc<EditorData>>>,
+    /// The offset in the source editor whose line the window is shown below.
+    pub offset: RwSignal<usize>,
+    pub locations: RwSignal<Vec<EditorLocation>>,
+    /// Which of the locations is shown.
+    pub index: RwSignal<usize>,
+    pub editor: RwSignal<Rc<EditorData>>,
+    pub layout_rect: RwSignal<Rect>,
+    /// The files that were only loaded to be shown in the window, which are
+    /// dropped again when it closes.
+    new_docs: RwSignal<Vec<PathBuf>>,
+    main_split: MainSplitData,
+    pub common: Rc<CommonData>,
+}
+
+impl KeyPressFocus for PeekData {
+    fn get_mode(&self) -> Mode {
+        Mode::Insert
+    }
+
+    fn check_condition(&self, condition: Condition) -> bool {
+        matches!(condition, Condition::PeekFocus | Condition::ModalFocus)
+    }
+
+    fn run_command(
+        &self,
+        command: &LapceCommand,
+        count: Option<usize>,
+        mods: ModifiersState,
+    ) -> CommandExecuted {
+        match &command.kind {
+            CommandKind::Focus(cmd) => self.run_focus_command(cmd),
+            // The shown file can be moved around in, but not edited
+            CommandKind::Move(_) => {
+                self.editor
+                    .get_untracked()
+                    .run_command(command, count, mods);
+                CommandExecuted::Yes
+            }
+            CommandKind::Workbench(_)
+            | CommandKind::Edit(_)
+            | CommandKind::MultiSelection(_)
+            | CommandKind::MotionMode(_) => CommandExecuted::No,
+        }
+    }
+
+    fn receive_char(&self, _c: &str) {}
+}
+
+impl PeekData {
+    pub fn new(
+        cx: Scope,
+        main_split: MainSplitData,
+        common: Rc<CommonData>,
+    ) -> Self {
+        let editor = EditorData::new_local(cx, EditorId::next(), common.clone());
+        editor.read_only.set(true);
+        Self {
+            active: cx.create_rw_signal(false),
+            source: cx.create_rw_signal(None),
+            offset: cx.create_rw_signal(0),
+            locations: cx.create_rw_signal(Vec::new()),
+            index: cx.create_rw_signal(0),
+            editor: cx.create_rw_signal(Rc::new(editor)),
+            layout_rect: cx.create_rw_signal(Rect::ZERO),
+            new_docs: cx.create_rw_signal(Vec::new()),
+            main_split,
+            common,
+        }
+    }
+
+    /// Open the window below the line of the offset in the editor, showing the
+    /// first of the locations.
+    pub fn show(
+        &self,
+        source: Rc<EditorData>,
+        offset: usize,
+        locations: Vec<EditorLocation>,
+    ) {
+        if locations.is_empty() {
+            return;
+        }
+        self.source.set(Some(source));
+        self.offset.set(offset);
+        self.locations.set(locations);
+        self.index.set(0);
+        self.show_location();
+        self.active.set(true);
+        self.common.focus.set(Focus::Peek);
+    }
+
+    /// The file and line of the shown location, and which of the locations it is.
+    pub fn title(&self) -> String {
+        let index = self.index.get();
+        self.locations.with(|locations| {
+            let Some(location) = locations.get(index) else {
+                return String::new();
+            };
+            let name = location
+                .path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let editor = self.editor.get();
+            let line = editor.cursor.with(|cursor| {
+                editor
+                    .view
+                    .doc
+                    .get()
+                    .buffer
+                    .with(|buffer| buffer.line_of_offset(cursor.offset()))
+            });
+            if locations.len() > 1 {
+                format!("{name}:{}  ({}/{})", line + 1, index + 1, locations.len())
+            } else {
+                format!("{name}:{}", line + 1)
+            }
+        })
+    }
+
+    fn show_location(&self) {
+        let index = self.index.get_untracked();
+        let Some(location) = self
+            .locations
+            .with_untracked(|locations| locations.get(index).cloned())
+        else {
+            return;
+        };
+        let (doc, new_doc) = self.main_split.get_doc(location.path.clone());
+        if new_doc {
+            self.new_docs
+                .update(|new_docs| new_docs.push(location.path.clone()));
+        }
+        let editor = self.editor.get_untracked();
+        editor.update_doc(doc);
+        editor.go_to_location(location, new_doc, None);
+    }
+
+    /// Show the next of the locations, going around to the first after the last.
+    fn next(&self) {
+        let len = self.locations.with_untracked(|locations| locations.len());
+        if len > 1 {
+            self.index.update(|index| *index = (*index + 1) % len);
+            self.show_location();
+        }
+    }
+
+    fn previous(&self) {
+        let len = self.locations.with_untracked(|locations| locations.len());
+        if len > 1 {
+            self.index.update(|index| *index = (*index + len - 1) % len);
+            self.show_location();
+        }
+    }
+
+    /// Jump to where the cursor is in the shown file, closing the window.
+    fn open(&self) {
+        let index = self.index.get_untracked();
+        let Some(mut location) = self
+            .locations
+            .with_untracked(|locations| locations.get(index).cloned())
+        else {
+            return;
+        };
+        let offset = self
+            .editor
+            .get_untracked()
+            .cursor
+            .with_untracked(|c| c.offset());
+        location.position = Some(EditorPosition::Offset(offset));
+        self.hide();
+        self.common
+            .internal_command
+            .send(InternalCommand::JumpToLocation { location });
+        // The file that was jumped to is shown by an editor now, so it's kept
+        self.discard_docs();
+    }
+
+    fn run_focus_command(&self, cmd: &FocusCommand) -> CommandExecuted {
+        match cmd {
+            FocusCommand::ModalClose => {
+                self.close();
+            }
+            FocusCommand::PeekNext => {
+                self.next();
+            }
+            FocusCommand::PeekPrevious => {
+                self.previous();
+            }
+            FocusCommand::PeekOpen => {
+                self.open();
+            }
+            _ => return CommandExecuted::No,
+        }
+        CommandExecuted::Yes
+    }
+
+    pub fn close(&self) {
+        self.hide();
+        self.discard_docs();
+    }
+
+    fn hide(&self) {
+        self.active.set(false);
+        if let Focus::Peek = self.common.focus.get_untracked() {
+            self.common.focus.set(Focus::Workbench);
+        }
+    }
+
+    /// Drop the documents that were only loaded to be shown in the window,
+    /// unless an editor shows them by now.
+    fn discard_docs(&self) {
+        for path in self.new_docs.try_update(std::mem::take).unwrap_or_default() {
+            self.main_split.discard_doc(&path);
+        }
+    }
+}
@@ -0,0 +1,290 @@
+use std::{
+    collections::VecDeque,
+    path::Path,
+    rc::Rc,
+    sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use floem::{
+    action::{exec_after, TimerToken},
+    reactive::{create_effect, RwSignal},
+    view::View,
+    views::{label, list, Decorators},
+};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use crate::{config::color::LapceColor, window_tab::WindowTabData};
+
+/// How many spans are kept for the overlay and the trace, the oldest are dropped.
+const MAX_SPANS: usize = 50_000;
+/// How many of the last spans of a metric its average and maximum are taken over.
+const SAMPLES: usize = 100;
+const REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+const OVERLAY_WIDTH: f64 = 320.0;
+
+/// Whether timings are recorded, which is only while the overlay is shown.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static START: Lazy<Instant> = Lazy::new(Instant::now);
+static SPANS: Lazy<Mutex<VecDeque<Span>>> = Lazy::new(Default::default);
+/// Semantic highlighting requests that haven't been applied yet.
+static HIGHLIGHT_QUEUE: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Metric {
+    /// Painting an editor.
+    Paint,
+    /// Laying out the text of the editors in the main split.
+    MainSplitLayout,
+    /// Filtering and sorting the completion items.
+    Completion,
+    /// Filtering and sorting the palette items.
+    Palette,
+    /// Applying an edit to the rope of a document.
+    RopeEdit,
+}
+
+impl Metric {
+    const ALL: [Metric; 5] = [
+        Metric::Paint,
+        Metric::MainSplitLayout,
+        Metric::Completion,
+        Metric::Palette,
+        Metric::RopeEdit,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Metric::Paint => "Editor paint",
+            Metric::MainSplitLayout => "Main split layout",
+            Metric::Completion => "Completion",
+            Metric::Palette => "Palette",
+            Metric::RopeEdit => "Rope edit",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Span {
+    metric: Metric,
+    /// Since the first span.
+    start: Duration,
+    duration: Duration,
+    thread: u64,
+}
+
+/// Records how long it lives for the metric, when dropped.
+pub struct Timer {
+    metric: Metric,
+    start: Instant,
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        let span = Span {
+            metric: self.metric,
+            start: self.start.saturating_duration_since(*START),
+            duration: self.start.elapsed(),
+            thread: thread_id(),
+        };
+        let mut spans = SPANS.lock();
+        if spans.len() == MAX_SPANS {
+            spans.pop_front();
+        }
+        spans.push_back(span);
+    }
+}
+
+/// Start timing the metric, if the overlay is shown.
+pub fn time(metric: Metric) -> Option<Timer> {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return None;
+    }
+    Lazy::force(&START);
+    Some(Timer {
+        metric,
+        start: Instant::now(),
+    })
+}
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn highlight_queued() {
+    HIGHLIGHT_QUEUE.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn highlight_done() {
+    let _ =
+        HIGHLIGHT_QUEUE.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+            n.checked_sub(1)
+        });
+}
+
+/// A small number for each thread, since the ids of std can't be read as numbers.
+fn thread_id() -> u64 {
+    static NEXT: AtomicU64 = AtomicU64::new(1);
+    thread_local! {
+        static ID: u64 = NEXT.fetch_add(1, Ordering::Relaxed);
+    }
+    ID.with(|id| *id)
+}
+
+/// One line for every metric, with the average and the maximum of its last
+/// spans.
+fn summary() -> Vec<String> {
+    let spans = SPANS.lock();
+    let mut lines: Vec<String> = Metric::ALL
+        .iter()
+        .map(|metric| {
+            let durations: Vec<Duration> = spans
+                .iter()
+                .rev()
+                .filter(|span| span.metric == *metric)
+                .take(SAMPLES)
+                .map(|span| span.duration)
+                .collect();
+            let max = durations.iter().max().copied().unwrap_or_default();
+            let average = if durations.is_empty() {
+                Duration::ZERO
+            } else {
+                durations.iter().sum::<Duration>() / durations.len() as u32
+            };
+            format!(
+                "{:<18} avg {:>6.2}ms  max {:>6.2}ms",
+                metric.name(),
+                average.as_secs_f64() * 1000.0,
+                max.as_secs_f64() * 1000.0,
+            )
+        })
+        .collect();
+    lines.push(format!(
+        "{:<18} {}",
+        "Highlight queue",
+        HIGHLIGHT_QUEUE.load(Ordering::Relaxed)
+    ));
+    lines
+}
+
+/// An event of the Chrome tracing format, which `chrome://tracing` and Perfetto
+/// open.
+#[derive(Serialize)]
+struct TraceEvent {
+    name: &'static str,
+    cat: &'static str,
+    ph: &'static str,
+    /// In microseconds, like `dur`.
+    ts: u64,
+    dur: u64,
+    pid: u32,
+    tid: u64,
+}
+
+fn trace_events(spans: &VecDeque<Span>) -> Vec<TraceEvent> {
+    spans
+        .iter()
+        .map(|span| TraceEvent {
+            name: span.metric.name(),
+            cat: "lapce",
+            ph: "X",
+            ts: span.start.as_micros() as u64,
+            dur: span.duration.as_micros() as u64,
+            pid: std::process::id(),
+            tid: span.thread,
+        })
+        .collect()
+}
+
+/// Write the recorded spans to the file, in the Chrome tracing format. Returns
+/// how many there were.
+pub fn write_trace(path: &Path) -> Result<usize> {
+    let events = trace_events(&SPANS.lock());
+    std::fs::write(path, serde_json::to_string(&events)?)?;
+    Ok(events.len())
+}
+
+fn refresh(
+    visible: RwSignal<bool>,
+    lines: RwSignal<Vec<String>>,
+    timer: RwSignal<TimerToken>,
+) {
+    lines.set(summary());
+    let token = exec_after(REFRESH_INTERVAL, move |token| {
+        if visible.get_untracked() && timer.get_untracked() == token {
+            refresh(visible, lines, timer);
+        }
+    });
+    timer.set(token);
+}
+
+/// The timings, in the top right corner of the window tab.
+pub fn perf_overlay(window_tab_data: Rc<WindowTabData>) -> impl View {
+    let visible = window_tab_data.perf_overlay;
+    let config = window_tab_data.common.config;
+    let layout_rect = window_tab_data.layout_rect;
+    let title_height = window_tab_data.title_height;
+    let lines = window_tab_data.scope.create_rw_signal(Vec::new());
+    let timer = window_tab_data.scope.create_rw_signal(TimerToken::INVALID);
+
+    create_effect(move |_| {
+        let visible_now = visible.get();
+        set_enabled(visible_now);
+        if visible_now {
+            refresh(visible, lines, timer);
+        }
+    });
+
+    list(
+        move || lines.get().into_iter().enumerate(),
+        |(i, line)| (*i, line.clone()),
+        |(_, line)| label(move || line.clone()),
+    )
+    .style(move |s| {
+        if !visible.get() {
+            return s.hide();
+        }
+        let config = config.get();
+        let left = layout_rect.get().width() - OVERLAY_WIDTH - 10.0;
+        s.absolute()
+            .flex_col()
+            .width(OVERLAY_WIDTH as f32)
+            .margin_left(left.max(0.0) as f32)
+            .margin_top((title_height.get() + 10.0) as f32)
+            .padding(8.0)
+            .border(1.0)
+            .border_radius(6.0)
+            .font_family(config.editor.font_family_list())
+            .font_size((config.ui.font_size() as f32 - 1.0).max(8.0))
+            .color(*config.get_color(LapceColor::EDITOR_FOREGROUND))
+            .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+            .background(*config.get_color(LapceColor::PANEL_BACKGROUND))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::VecDeque, time::Duration};
+
+    use super::{trace_events, Metric, Span};
+
+    #[test]
+    fn test_trace_events() {
+        let spans = VecDeque::from([Span {
+            metric: Metric::RopeEdit,
+            start: Duration::from_millis(12),
+            duration: Duration::from_micros(250),
+            thread: 1,
+        }]);
+        let events = serde_json::to_value(trace_events(&spans)).unwrap();
+        let event = &events[0];
+        assert_eq!(event["name"], "Rope edit");
+        assert_eq!(event["ph"], "X");
+        assert_eq!(event["ts"], 12_000);
+        assert_eq!(event["dur"], 250);
+        assert_eq!(event["tid"], 1);
+    }
+}
@@ -1,5 +1,5 @@
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     rc::Rc,
     sync::{atomic::AtomicU64, Arc},
 };
@@ -24,17 +24,19 @@
 use indexmap::IndexMap;
 use lapce_core::{directory::Directory, mode::Mode};
 use lapce_proxy::plugin::{download_volt, volt_icon, wasi::find_all_volts};
-use lapce_rpc::plugin::{VoltID, VoltInfo, VoltMetadata};
+use lapce_rpc::plugin::{VoltID, VoltInfo, VoltMetadata, VoltPermissions};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
 use crate::{
-    command::{CommandExecuted, CommandKind},
+    alert::AlertButton,
+    command::{CommandExecuted, CommandKind, InternalCommand},
     config::{color::LapceColor, LapceConfig},
     db::LapceDb,
     editor::EditorData,
     id::EditorId,
     keypress::{condition::Condition, KeyPressFocus},
+    localization::tr,
     markdown::{parse_markdown, MarkdownContent},
     panel::plugin_view::VOLT_DEFAULT_PNG,
     web_link::web_link,
@@ -101,6 +103,8 @@ pub struct PluginData {
     pub all: RwSignal<im::HashMap<VoltID, AvailableVoltData>>,
     pub disabled: RwSignal<HashSet<VoltID>>,
     pub workspace_disabled: RwSignal<HashSet<VoltID>>,
+    /// The permissions the user agreed to give each plugin.
+    pub granted_permissions: RwSignal<HashMap<VoltID, VoltPermissions>>,
     pub common: Rc<CommonData>,
 }
 
@@ -145,6 +149,7 @@ pub fn new(
         cx: Scope,
         disabled: HashSet<VoltID>,
         workspace_disabled: HashSet<VoltID>,
+        granted_permissions: HashMap<VoltID, VoltPermissions>,
         common: Rc<CommonData>,
     ) -> Self {
         let installed = cx.create_rw_signal(IndexMap::new());
@@ -161,6 +166,7 @@ pub fn new(
         };
         let disabled = cx.create_rw_signal(disabled);
         let workspace_disabled = cx.create_rw_signal(workspace_disabled);
+        let granted_permissions = cx.create_rw_signal(granted_permissions);
 
         let plugin = Self {
             installed,
@@ -168,6 +174,7 @@ pub fn new(
             all: cx.create_rw_signal(im::HashMap::new()),
             disabled,
             workspace_disabled,
+            granted_permissions,
             common,
         };
 
@@ -473,6 +480,10 @@ pub fn install_volt(&self, info: VoltInfo) {
             let send = create_ext_action(self.common.scope, move |result| {
                 if let Ok((meta, icon)) = result {
                     plugin.volt_installed(&meta, &icon);
+                    // The language servers of the plugin run in the proxy
+                    if meta.language_servers.is_some() {
+                        plugin.common.proxy.reload_volt(meta);
+                    }
                 }
             });
             std::thread::spawn(move || {
@@ -487,6 +498,27 @@ pub fn install_volt(&self, info: VoltInfo) {
         }
     }
 
+    /// Install the latest version of every installed plugin that has a newer one
+    /// in the registry.
+    pub fn update_all_volts(&self) {
+        let outdated: Vec<VoltInfo> = self.installed.with_untracked(|installed| {
+            installed
+                .values()
+                .filter_map(|volt| {
+                    let latest = volt.latest.get_untracked();
+                    volt.meta
+                        .with_untracked(|meta| {
+                            is_newer(&latest.version, &meta.version)
+                        })
+                        .then_some(latest)
+                })
+                .collect()
+        });
+        for info in outdated {
+            self.install_volt(info);
+        }
+    }
+
     pub fn plugin_disabled(&self, id: &VoltID) -> bool {
         self.disabled.with_untracked(|d| d.contains(id))
             || self.workspace_disabled.with_untracked(|d| d.contains(id))
@@ -525,7 +557,10 @@ pub fn enable_volt_for_ws(&self, volt: VoltInfo) {
         let db: Arc<LapceDb> = use_context().unwrap();
         db.save_workspace_disabled_volts(
             self.common.workspace.clone(),
-            self.disabled.get_untracked().into_iter().collect(),
+            self.workspace_disabled
+                .get_untracked()
+                .into_iter()
+                .collect(),
         );
     }
 
@@ -538,12 +573,15 @@ pub fn disable_volt_for_ws(&self, volt: VoltInfo) {
         let db: Arc<LapceDb> = use_context().unwrap();
         db.save_workspace_disabled_volts(
             self.common.workspace.clone(),
-            self.disabled.get_untracked().into_iter().collect(),
+            self.workspace_disabled
+                .get_untracked()
+                .into_iter()
+                .collect(),
         );
     }
 
     pub fn uninstall_volt(&self, volt: VoltMetadata) {
-        if volt.wasm.is_some() {
+        if volt.wasm.is_some() || volt.language_servers.is_some() {
             self.common.proxy.remove_volt(volt);
         } else {
             let plugin = self.clone();
@@ -572,12 +610,67 @@ pub fn reload_volt(&self, volt: VoltMetadata) {
         self.common.proxy.reload_volt(volt);
     }
 
+    /// Ask the user whether the plugin can have the permissions it declares. It
+    /// only starts once they are granted.
+    pub fn ask_for_permissions(&self, volt: VoltMetadata) {
+        let internal_command = self.common.internal_command;
+        let permissions = volt.permissions();
+        let title = format!("{} asks for permissions", volt.display_name);
+        let mut msg = permissions.describe();
+        if let Some(memory) = permissions.memory {
+            msg.push(format!("Use up to {memory} MB of memory"));
+        }
+        let allow = AlertButton {
+            text: "Allow".to_string(),
+            action: Rc::new({
+                let plugin = self.clone();
+                move || {
+                    internal_command.send(InternalCommand::HideAlert);
+                    plugin.grant_permissions(volt.clone());
+                }
+            }),
+        };
+        internal_command.send(InternalCommand::ShowAlert {
+            title,
+            msg: msg.join("\n"),
+            buttons: vec![allow],
+        });
+    }
+
+    pub fn grant_permissions(&self, volt: VoltMetadata) {
+        self.granted_permissions.update(|granted| {
+            granted.insert(volt.id(), volt.permissions());
+        });
+        self.save_granted_permissions();
+        self.common.proxy.grant_volt_permissions(volt);
+    }
+
+    /// Take back the permissions the plugin was given, which stops it until the
+    /// user agrees to them again.
+    pub fn revoke_permissions(&self, volt: VoltInfo) {
+        self.granted_permissions.update(|granted| {
+            granted.remove(&volt.id());
+        });
+        self.save_granted_permissions();
+        self.common.proxy.revoke_volt_permissions(volt);
+    }
+
+    fn save_granted_permissions(&self) {
+        let db: Arc<LapceDb> = use_context().unwrap();
+        db.save_granted_volt_permissions(
+            self.granted_permissions
+                .get_untracked()
+                .into_iter()
+                .collect(),
+        );
+    }
+
     pub fn plugin_controls(&self, meta: VoltMetadata, latest: VoltInfo) -> Menu {
         let volt_id = meta.id();
         let mut menu = Menu::new("");
-        if meta.version != latest.version {
+        if is_newer(&latest.version, &meta.version) {
             menu = menu
-                .entry(MenuItem::new("Upgrade Plugin").action({
+                .entry(MenuItem::new(tr("menu-upgrade-plugin")).action({
                     let plugin = self.clone();
                     let info = latest.clone();
                     move || {
@@ -587,7 +680,7 @@ pub fn plugin_controls(&self, meta: VoltMetadata, latest: VoltInfo) -> Menu {
                 .separator();
         }
         menu = menu
-            .entry(MenuItem::new("Reload Plugin").action({
+            .entry(MenuItem::new(tr("menu-reload-plugin")).action({
                 let plugin = self.clone();
                 let meta = meta.clone();
                 move || {
@@ -596,7 +689,7 @@ pub fn plugin_controls(&self, meta: VoltMetadata, latest: VoltInfo) -> Menu {
             }))
             .separator()
             .entry(
-                MenuItem::new("Enable")
+                MenuItem::new(tr("menu-enable"))
                     .enabled(
                         self.disabled
                             .with_untracked(|disabled| disabled.contains(&volt_id)),
@@ -610,7 +703,7 @@ pub fn plugin_controls(&self, meta: VoltMetadata, latest: VoltInfo) -> Menu {
                     }),
             )
             .entry(
-                MenuItem::new("Disable")
+                MenuItem::new(tr("menu-disable"))
                     .enabled(
                         self.disabled
                             .with_untracked(|disabled| !disabled.contains(&volt_id)),
@@ -625,7 +718,7 @@ pub fn plugin_controls(&self, meta: VoltMetadata, latest: VoltInfo) -> Menu {
             )
             .separator()
             .entry(
-                MenuItem::new("Enable For Workspace")
+                MenuItem::new(tr("menu-enable-for-workspace"))
                     .enabled(
                         self.workspace_disabled
                             .with_untracked(|disabled| disabled.contains(&volt_id)),
@@ -639,7 +732,7 @@ pub fn plugin_controls(&self, meta: VoltMetadata, latest: VoltInfo) -> Menu {
                     }),
             )
             .entry(
-                MenuItem::new("Disable For Workspace")
+                MenuItem::new(tr("menu-disable-for-workspace"))
                     .enabled(
                         self.workspace_disabled
                             .with_untracked(|disabled| !disabled.contains(&volt_id)),
@@ -653,7 +746,23 @@ pub fn plugin_controls(&self, meta: VoltMetadata, latest: VoltInfo) -> Menu {
                     }),
             )
             .separator()
-            .entry(MenuItem::new("Uninstall").action({
+            .entry(
+                MenuItem::new(tr("menu-revoke-permissions"))
+                    .enabled(
+                        self.granted_permissions.with_untracked(|granted| {
+                            granted.contains_key(&volt_id)
+                        }),
+                    )
+                    .action({
+                        let plugin = self.clone();
+                        let volt = meta.info();
+                        move || {
+                            plugin.revoke_permissions(volt.clone());
+                        }
+                    }),
+            )
+            .separator()
+            .entry(MenuItem::new(tr("menu-uninstall")).action({
                 let plugin = self.clone();
                 move || {
                     plugin.uninstall_volt(meta.clone());
@@ -663,6 +772,19 @@ pub fn plugin_controls(&self, meta: VoltMetadata, latest: VoltInfo) -> Menu {
     }
 }
 
+/// Whether the `latest` version of a plugin is newer than the `installed` one.
+/// Versions that aren't semver are never taken to be newer, so that updating
+/// can't install an older one.
+fn is_newer(latest: &str, installed: &str) -> bool {
+    match (
+        semver::Version::parse(latest),
+        semver::Version::parse(installed),
+    ) {
+        (Ok(latest), Ok(installed)) => latest > installed,
+        _ => false,
+    }
+}
+
 pub fn plugin_info_view(plugin: PluginData, volt: VoltID) -> impl View {
     let config = plugin.common.config;
     let header_rect = create_rw_signal(Rect::ZERO);
@@ -708,7 +830,7 @@ pub fn plugin_info_view(plugin: PluginData, volt: VoltID) -> impl View {
             let control = {
                 move |version_info: Option<(String, Option<String>)>| {
                     match version_info.as_ref().map(|(v, l)| match l {
-                        Some(l) => (true, l == v),
+                        Some(l) => (true, !is_newer(l, v)),
                         None => (false, false),
                     }) {
                         Some((true, true)) => "Installed ▼",
@@ -1003,3 +1125,17 @@ pub fn plugin_info_view(plugin: PluginData, volt: VoltID) -> impl View {
     })
     .style(|s| s.absolute().size_full())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::is_newer;
+
+    #[test]
+    fn test_is_newer() {
+        assert!(is_newer("0.2.0", "0.1.9"));
+        assert!(is_newer("1.0.0", "1.0.0-beta.1"));
+        assert!(!is_newer("0.1.0", "0.1.0"));
+        assert!(!is_newer("0.1.0", "0.2.0"));
+        assert!(!is_newer("latest", "0.1.0"));
+    }
+}
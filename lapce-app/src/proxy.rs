@@ -5,23 +5,24 @@
 use lapce_proxy::dispatch::Dispatcher;
 use lapce_rpc::{
     core::{CoreHandler, CoreNotification, CoreRpcHandler},
-    plugin::VoltID,
+    plugin::{VoltID, VoltPermissions},
     proxy::{ProxyRpcHandler, ProxyStatus},
     terminal::TermId,
 };
 use lsp_types::Url;
 use tracing::error;
 
-use self::{remote::start_remote, ssh::SshRemote};
+use self::{container::ContainerRemote, remote::start_remote, ssh::SshRemote};
 use crate::{
     terminal::event::TermEvent,
     workspace::{LapceWorkspace, LapceWorkspaceType},
 };
 
+pub mod container;
 mod remote;
 mod ssh;
 #[cfg(windows)]
-mod wsl;
+pub mod wsl;
 
 pub struct Proxy {
     pub tx: Sender<CoreNotification>,
@@ -45,6 +46,7 @@ pub fn shutdown(&self) {
 pub fn new_proxy(
     workspace: Arc<LapceWorkspace>,
     disabled_volts: Vec<VoltID>,
+    granted_permissions: Vec<(VoltID, VoltPermissions)>,
     plugin_configurations: HashMap<String, HashMap<String, serde_json::Value>>,
     term_tx: Sender<(TermId, TermEvent)>,
 ) -> ProxyData {
@@ -60,10 +62,13 @@ pub fn new_proxy(
             });
             proxy_rpc.initialize(
                 workspace.path.clone(),
+                workspace.folders.clone(),
                 disabled_volts,
+                granted_permissions,
                 plugin_configurations,
                 1,
                 1,
+                workspace.kind.is_remote(),
             );
 
             match &workspace.kind {
@@ -83,23 +88,38 @@ pub fn new_proxy(
                         proxy_rpc.clone(),
                     ) {
                         error!("Failed to start SSH remote: {e}");
+                        core_rpc.notification(CoreNotification::ProxyStatus {
+                            status: ProxyStatus::Disconnected,
+                        });
                     }
                 }
                 #[cfg(windows)]
-                LapceWorkspaceType::RemoteWSL => {
-                    use wsl::{WslDistro, WslRemote};
-                    let distro = WslDistro::all()
-                        .ok()
-                        .and_then(|d| d.into_iter().find(|distro| distro.default))
-                        .map(|d| d.name);
-                    if let Some(distro) = distro {
-                        if let Err(e) = start_remote(
-                            WslRemote { distro },
-                            core_rpc.clone(),
-                            proxy_rpc.clone(),
-                        ) {
-                            error!("Failed to start SSH remote: {e}");
-                        }
+                LapceWorkspaceType::RemoteWSL(wsl) => {
+                    if let Err(e) = start_remote(
+                        wsl::WslRemote {
+                            distro: wsl.distro.clone(),
+                        },
+                        core_rpc.clone(),
+                        proxy_rpc.clone(),
+                    ) {
+                        error!("Failed to start WSL remote: {e}");
+                        core_rpc.notification(CoreNotification::ProxyStatus {
+                            status: ProxyStatus::Disconnected,
+                        });
+                    }
+                }
+                LapceWorkspaceType::RemoteContainer(host) => {
+                    if let Err(e) = start_remote(
+                        ContainerRemote {
+                            container: host.container.clone(),
+                        },
+                        core_rpc.clone(),
+                        proxy_rpc.clone(),
+                    ) {
+                        error!("Failed to start container remote: {e}");
+                        core_rpc.notification(CoreNotification::ProxyStatus {
+                            status: ProxyStatus::Disconnected,
+                        });
                     }
                 }
             }
@@ -0,0 +1,68 @@
+use std::{
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use anyhow::{anyhow, Result};
+
+use super::{new_command, remote::Remote};
+
+pub struct ContainerRemote {
+    pub container: String,
+}
+
+impl ContainerRemote {
+    /// The names of the Docker containers that are running.
+    pub fn running() -> Result<Vec<String>> {
+        let cmd = new_command("docker")
+            .args(["ps", "--format", "{{.Names}}"])
+            .stdout(Stdio::piped())
+            .output()?;
+
+        if !cmd.status.success() {
+            return Err(anyhow!("failed to execute `docker ps`"));
+        }
+
+        let containers = String::from_utf8_lossy(&cmd.stdout)
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .map(|line| line.to_string())
+            .collect();
+
+        Ok(containers)
+    }
+}
+
+impl Remote for ContainerRemote {
+    fn upload_file(&self, local: impl AsRef<Path>, remote: &str) -> Result<()> {
+        // `docker cp` doesn't go through a shell in the container
+        let remote = if remote.starts_with('~') {
+            remote.replacen('~', &self.home_dir()?, 1)
+        } else {
+            remote.to_string()
+        };
+        let output = new_command("docker")
+            .arg("cp")
+            .arg(local.as_ref())
+            .arg(format!("{}:{remote}", self.container))
+            .output()?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "failed to copy to the container: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+        Ok(())
+    }
+
+    fn command_builder(&self) -> Command {
+        // Unlike SSH, `docker exec` runs the program directly, so the arguments
+        // are joined and evaluated by a shell to get the same pipes and
+        // variables
+        let mut cmd = new_command("docker");
+        cmd.args(["exec", "-i", &self.container]);
+        cmd.args(["sh", "-c", "eval \"$*\"", "sh"]);
+        cmd
+    }
+}
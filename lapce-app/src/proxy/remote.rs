@@ -1,23 +1,40 @@
 use std::{
+    collections::BTreeMap,
     io::{BufReader, Write},
     path::Path,
-    process::{Command, Stdio},
+    process::{Child, Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
 };
 
 use anyhow::{anyhow, Result};
+use crossbeam_channel::{Receiver, Sender};
 use flate2::read::GzDecoder;
 use lapce_core::{directory::Directory, meta};
 use lapce_rpc::{
-    core::CoreRpcHandler,
-    proxy::{ProxyRpc, ProxyRpcHandler},
-    stdio_transport, RpcMessage,
+    core::{CoreNotification, CoreRequest, CoreResponse, CoreRpcHandler},
+    proxy::{
+        ProxyNotification, ProxyRequest, ProxyResponse, ProxyRpc, ProxyRpcHandler,
+        ProxyStatus, PROXY_PROTOCOL_VERSION,
+    },
+    stdio::compressed_stdio_transport,
+    RequestId, RpcError, RpcMessage,
 };
+use parking_lot::Mutex;
 use thiserror::Error;
 use tracing::{debug, error};
 
 const UNIX_PROXY_SCRIPT: &[u8] = include_bytes!("../../../extra/proxy.sh");
 const WINDOWS_PROXY_SCRIPT: &[u8] = include_bytes!("../../../extra/proxy.ps1");
 
+/// How long to wait before starting the proxy again after the connection drops.
+/// It doubles after every attempt that fails, up to `MAX_RECONNECT_DELAY`.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
 #[derive(Clone, Copy, Error, Debug, PartialEq, Eq, strum_macros::Display)]
 #[strum(ascii_case_insensitive)]
 enum HostPlatform {
@@ -68,11 +85,133 @@ fn home_dir(&self) -> Result<String> {
     fn command_builder(&self) -> Command;
 }
 
+/// Start the proxy on the remote host and connect to it. If the connection drops
+/// later on, the proxy is started again, and what the editor sends in the meantime
+/// is kept until then.
 pub fn start_remote(
-    remote: impl Remote,
+    remote: impl Remote + Send + 'static,
     core_rpc: CoreRpcHandler,
     proxy_rpc: ProxyRpcHandler,
 ) -> Result<()> {
+    let child = spawn_proxy(&remote)?;
+    let connection = Arc::new(Mutex::new(Connection::default()));
+    let reader_rx = connection.lock().connect(child)?;
+
+    let local_proxy_rpc = proxy_rpc.clone();
+    let local_connection = connection.clone();
+    std::thread::spawn(move || {
+        for msg in local_proxy_rpc.rx() {
+            match msg {
+                ProxyRpc::Request(id, rpc) => {
+                    local_connection.lock().send(RpcMessage::Request(id, rpc));
+                }
+                ProxyRpc::Notification(rpc) => {
+                    local_connection.lock().send(RpcMessage::Notification(rpc));
+                }
+                ProxyRpc::Shutdown => {
+                    local_connection.lock().shutdown();
+                    return;
+                }
+            }
+        }
+    });
+
+    std::thread::spawn(move || {
+        let mut reader_rx = reader_rx;
+        loop {
+            for msg in reader_rx {
+                match msg {
+                    RpcMessage::Request(id, req) => {
+                        let connection = connection.clone();
+                        let core_rpc = core_rpc.clone();
+                        std::thread::spawn(move || {
+                            let resp = match core_rpc.request(req) {
+                                Ok(resp) => RpcMessage::Response(id, resp),
+                                Err(e) => RpcMessage::Error(id, e),
+                            };
+                            connection.lock().respond(resp);
+                        });
+                    }
+                    RpcMessage::Notification(n) => {
+                        if let CoreNotification::ProxyHandshake {
+                            protocol_version,
+                        } = &n
+                        {
+                            connection.lock().handshake(*protocol_version);
+                        }
+                        core_rpc.notification(n);
+                    }
+                    RpcMessage::Response(id, resp) => {
+                        connection.lock().answered(id);
+                        proxy_rpc.handle_response(id, Ok(resp));
+                    }
+                    RpcMessage::Error(id, err) => {
+                        connection.lock().answered(id);
+                        proxy_rpc.handle_response(id, Err(err));
+                    }
+                }
+            }
+
+            let lost = connection.lock().disconnect();
+            for id in lost {
+                proxy_rpc.handle_response(
+                    id,
+                    Err(RpcError {
+                        code: 0,
+                        message: "the connection to the remote proxy was lost"
+                            .to_string(),
+                    }),
+                );
+            }
+            if connection.lock().shut_down {
+                return;
+            }
+            match reconnect(&remote, &connection, &core_rpc) {
+                Some(rx) => reader_rx = rx,
+                None => return,
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Keep starting the proxy again, waiting longer after every attempt that
+/// fails. Returns `None` when the editor shut the proxy down in the meantime.
+fn reconnect(
+    remote: &impl Remote,
+    connection: &Mutex<Connection>,
+    core_rpc: &CoreRpcHandler,
+) -> Option<Receiver<CoreMessage>> {
+    core_rpc.notification(CoreNotification::ProxyStatus {
+        status: ProxyStatus::Disconnected,
+    });
+    let mut delay = RECONNECT_DELAY;
+    loop {
+        std::thread::sleep(delay);
+        if connection.lock().shut_down {
+            return None;
+        }
+        core_rpc.notification(CoreNotification::ProxyStatus {
+            status: ProxyStatus::Reconnecting,
+        });
+        let reader_rx =
+            spawn_proxy(remote).and_then(|child| connection.lock().connect(child));
+        match reader_rx {
+            Ok(reader_rx) => return Some(reader_rx),
+            Err(e) => {
+                error!("Failed to reconnect to the remote proxy: {e}");
+                core_rpc.notification(CoreNotification::ProxyStatus {
+                    status: ProxyStatus::Disconnected,
+                });
+            }
+        }
+        delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+    }
+}
+
+/// Install the proxy on the remote host if needed, and start it.
+fn spawn_proxy(remote: &impl Remote) -> Result<Child> {
     // Note about platforms:
     // Windows can use either cmd.exe, powershell.exe or pwsh.exe as
     // SSH shell, syntax logic varies significantly that's why we bet on
@@ -84,7 +223,7 @@ pub fn start_remote(
     // shells retain similar syntax, although shells like Nushell might not
     // work (hopefully no one uses it as login shell)
     use HostPlatform::*;
-    let (platform, architecture) = host_specification(&remote).unwrap();
+    let (platform, architecture) = host_specification(remote)?;
 
     if platform == UnknownOS || architecture == HostArchitecture::UnknownArch {
         error!("detected remote host: {platform}/{architecture}");
@@ -127,7 +266,7 @@ pub fn start_remote(
         .unwrap_or(false)
     {
         download_remote(
-            &remote,
+            remote,
             &platform,
             &architecture,
             &remote_proxy_path,
@@ -137,7 +276,7 @@ pub fn start_remote(
 
     debug!("remote proxy path: {remote_proxy_path}");
 
-    let mut child = match platform {
+    let child = match platform {
         // Force cmd.exe usage to resolve %envvar% variables
         Windows => remote
             .command_builder()
@@ -155,71 +294,227 @@ pub fn start_remote(
             .stdout(Stdio::piped())
             .spawn()?,
     };
-    let stdin = child
-        .stdin
-        .take()
-        .ok_or_else(|| anyhow!("can't find stdin"))?;
-    let stdout = BufReader::new(
-        child
-            .stdout
-            .take()
-            .ok_or_else(|| anyhow!("can't find stdout"))?,
-    );
+
     debug!("process id: {}", child.id());
+    Ok(child)
+}
 
-    let (writer_tx, writer_rx) = crossbeam_channel::unbounded();
-    let (reader_tx, reader_rx) = crossbeam_channel::unbounded();
-    stdio_transport(stdin, writer_rx, stdout, reader_tx);
+/// What goes to the proxy, and what comes back from it.
+type ProxyMessage = RpcMessage<ProxyRequest, ProxyNotification, CoreResponse>;
+type CoreMessage = RpcMessage<CoreRequest, CoreNotification, ProxyResponse>;
+
+/// The connection to the remote proxy, which keeps the messages for it while it's
+/// down.
+#[derive(Default)]
+struct Connection {
+    /// `None` while the connection is down.
+    writer: Option<Sender<ProxyMessage>>,
+    child: Option<Child>,
+    /// Whether the proxy can read compressed messages.
+    compress: Arc<AtomicBool>,
+    /// The notification that started the proxy, which starts it again after a
+    /// reconnect.
+    initialize: Option<ProxyNotification>,
+    /// Set after a reconnect until the editor has restored its buffers.
+    restoring: bool,
+    /// The messages that couldn't be sent yet, such as saves made while offline.
+    queue: Vec<ProxyMessage>,
+    /// The requests that haven't been answered. If the connection drops before
+    /// they are, the reads are sent again and the rest fail, as the proxy may
+    /// have carried them out already.
+    pending: BTreeMap<RequestId, ProxyRequest>,
+    shut_down: bool,
+}
 
-    let local_proxy_rpc = proxy_rpc.clone();
-    let local_writer_tx = writer_tx.clone();
-    std::thread::spawn(move || {
-        for msg in local_proxy_rpc.rx() {
-            match msg {
-                ProxyRpc::Request(id, rpc) => {
-                    let _ = local_writer_tx.send(RpcMessage::Request(id, rpc));
-                }
-                ProxyRpc::Notification(rpc) => {
-                    let _ = local_writer_tx.send(RpcMessage::Notification(rpc));
-                }
-                ProxyRpc::Shutdown => {
-                    let _ = child.kill();
-                    let _ = child.wait();
-                    return;
-                }
+impl Connection {
+    fn connect(&mut self, mut child: Child) -> Result<Receiver<CoreMessage>> {
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow!("can't find stdin"))?;
+        let stdout = BufReader::new(
+            child
+                .stdout
+                .take()
+                .ok_or_else(|| anyhow!("can't find stdout"))?,
+        );
+
+        let (writer_tx, writer_rx) = crossbeam_channel::unbounded();
+        let (reader_tx, reader_rx) = crossbeam_channel::unbounded();
+        // Nothing is compressed until the new proxy says it speaks the protocol
+        self.compress = Arc::new(AtomicBool::new(false));
+        compressed_stdio_transport(
+            stdin,
+            writer_rx,
+            stdout,
+            reader_tx,
+            self.compress.clone(),
+        );
+
+        self.writer = Some(writer_tx);
+        self.child = Some(child);
+        if let Some(initialize) = self.initialize.clone() {
+            // The new proxy doesn't have the open documents yet, so the rest waits
+            // until the editor has sent them
+            self.restoring = true;
+            self.write(RpcMessage::Notification(initialize));
+        }
+        Ok(reader_rx)
+    }
+
+    fn send(&mut self, msg: ProxyMessage) {
+        if let RpcMessage::Notification(
+            initialize @ ProxyNotification::Initialize { .. },
+        ) = &msg
+        {
+            self.initialize = Some(initialize.clone());
+        }
+        if !self.restoring {
+            self.write(msg);
+            return;
+        }
+        if let RpcMessage::Notification(ProxyNotification::RestoreBuffers {
+            ..
+        }) = &msg
+        {
+            // The restored buffers already have the edits that were waiting
+            self.queue.retain(|msg| {
+                !matches!(
+                    msg,
+                    RpcMessage::Notification(ProxyNotification::Update { .. })
+                )
+            });
+            self.restoring = false;
+            self.write(msg);
+            for msg in std::mem::take(&mut self.queue) {
+                self.write(msg);
             }
+        } else {
+            self.queue.push(msg);
         }
-    });
+    }
 
-    std::thread::spawn(move || {
-        for msg in reader_rx {
-            match msg {
-                RpcMessage::Request(id, req) => {
-                    let writer_tx = writer_tx.clone();
-                    let core_rpc = core_rpc.clone();
-                    std::thread::spawn(move || match core_rpc.request(req) {
-                        Ok(resp) => {
-                            let _ = writer_tx.send(RpcMessage::Response(id, resp));
-                        }
-                        Err(e) => {
-                            let _ = writer_tx.send(RpcMessage::Error(id, e));
-                        }
-                    });
-                }
-                RpcMessage::Notification(n) => {
-                    core_rpc.notification(n);
-                }
-                RpcMessage::Response(id, resp) => {
-                    proxy_rpc.handle_response(id, Ok(resp));
-                }
-                RpcMessage::Error(id, err) => {
-                    proxy_rpc.handle_response(id, Err(err));
+    fn write(&mut self, msg: ProxyMessage) {
+        let Some(writer) = self.writer.as_ref() else {
+            self.queue.push(msg);
+            return;
+        };
+        let request = match &msg {
+            RpcMessage::Request(id, req) => Some((*id, req.clone())),
+            _ => None,
+        };
+        match writer.send(msg) {
+            Ok(()) => {
+                if let Some((id, req)) = request {
+                    self.pending.insert(id, req);
                 }
             }
+            Err(err) => self.queue.push(err.into_inner()),
         }
-    });
+    }
 
-    Ok(())
+    fn handshake(&mut self, protocol_version: u32) {
+        self.compress.store(
+            protocol_version == PROXY_PROTOCOL_VERSION,
+            Ordering::Relaxed,
+        );
+    }
+
+    /// Answer a request of the proxy, unless it's gone in the meantime.
+    fn respond(&mut self, msg: ProxyMessage) {
+        if let Some(writer) = self.writer.as_ref() {
+            let _ = writer.send(msg);
+        }
+    }
+
+    fn answered(&mut self, id: RequestId) {
+        self.pending.remove(&id);
+    }
+
+    /// Mark the connection as down, and queue the reads that weren't answered to
+    /// be sent again. Returns the other requests that weren't answered, which
+    /// can't be sent again as they may have been carried out already.
+    fn disconnect(&mut self) -> Vec<RequestId> {
+        self.writer = None;
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        let mut lost = Vec::new();
+        let mut queue = Vec::new();
+        for (id, req) in std::mem::take(&mut self.pending) {
+            if is_read(&req) {
+                queue.push(RpcMessage::Request(id, req));
+            } else {
+                lost.push(id);
+            }
+        }
+        queue.append(&mut self.queue);
+        self.queue = queue;
+        lost
+    }
+
+    fn shutdown(&mut self) {
+        self.shut_down = true;
+        self.writer = None;
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// Whether the request only reads, so sending it again doesn't change anything.
+fn is_read(req: &ProxyRequest) -> bool {
+    match req {
+        ProxyRequest::Save { .. }
+        | ProxyRequest::SaveBufferAs { .. }
+        | ProxyRequest::CreateFile { .. }
+        | ProxyRequest::CreateDirectory { .. }
+        | ProxyRequest::TrashPath { .. }
+        | ProxyRequest::MakeWritable { .. }
+        | ProxyRequest::DuplicatePath { .. }
+        | ProxyRequest::RenamePath { .. }
+        | ProxyRequest::CopyPath { .. }
+        | ProxyRequest::MovePath { .. }
+        | ProxyRequest::BuildRunnable { .. } => false,
+        ProxyRequest::NewBuffer { .. }
+        | ProxyRequest::BufferChunk { .. }
+        | ProxyRequest::BufferHead { .. }
+        | ProxyRequest::GitFileHistory { .. }
+        | ProxyRequest::GitFileRevision { .. }
+        | ProxyRequest::ReadFile { .. }
+        | ProxyRequest::GlobalSearch { .. }
+        | ProxyRequest::CompletionResolve { .. }
+        | ProxyRequest::CodeActionResolve { .. }
+        | ProxyRequest::GetHover { .. }
+        | ProxyRequest::GetSignature { .. }
+        | ProxyRequest::GetSelectionRange { .. }
+        | ProxyRequest::GitGetRemoteFileUrl { .. }
+        | ProxyRequest::GetReferences { .. }
+        | ProxyRequest::GetDocumentHighlights { .. }
+        | ProxyRequest::GetDefinition { .. }
+        | ProxyRequest::GetTypeDefinition { .. }
+        | ProxyRequest::GetInlayHints { .. }
+        | ProxyRequest::GetDocumentColors { .. }
+        | ProxyRequest::GetColorPresentations { .. }
+        | ProxyRequest::GetDocumentLinks { .. }
+        | ProxyRequest::GetRunnables { .. }
+        | ProxyRequest::GetSemanticTokens { .. }
+        | ProxyRequest::PrepareRename { .. }
+        | ProxyRequest::Rename { .. }
+        | ProxyRequest::GetCodeActions { .. }
+        | ProxyRequest::GetRangeCodeActions { .. }
+        | ProxyRequest::GetDocumentSymbols { .. }
+        | ProxyRequest::GetWorkspaceSymbols { .. }
+        | ProxyRequest::GetDocumentFormatting { .. }
+        | ProxyRequest::GetOpenFilesContent { .. }
+        | ProxyRequest::GetFiles { .. }
+        | ProxyRequest::ReadDir { .. }
+        | ProxyRequest::FindFile { .. }
+        | ProxyRequest::DapVariable { .. }
+        | ProxyRequest::DapGetScopes { .. } => true,
+    }
 }
 
 fn download_remote(
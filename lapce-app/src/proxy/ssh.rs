@@ -4,7 +4,10 @@
 use tracing::debug;
 
 use super::remote::Remote;
-use crate::{proxy::new_command, workspace::SshHost};
+use crate::{
+    proxy::new_command,
+    workspace::{SshAuth, SshHost},
+};
 
 pub struct SshRemote {
     pub ssh: SshHost,
@@ -25,6 +28,24 @@ impl SshRemote {
         "-o",
         "ConnectTimeout=15",
     ];
+
+    fn auth_args(&self, cmd: &mut Command) {
+        match &self.ssh.auth {
+            SshAuth::Agent => {}
+            SshAuth::KeyFile(path) => {
+                cmd.arg("-i").arg(path).args(["-o", "IdentitiesOnly=yes"]);
+            }
+            SshAuth::Password => {
+                // There's no terminal to type it in, so it has to be asked for
+                // with a window
+                cmd.args([
+                    "-o",
+                    "PreferredAuthentications=keyboard-interactive,password",
+                ])
+                .env("SSH_ASKPASS_REQUIRE", "force");
+            }
+        }
+    }
 }
 
 impl Remote for SshRemote {
@@ -32,6 +53,7 @@ fn upload_file(&self, local: impl AsRef<Path>, remote: &str) -> Result<()> {
         let mut cmd = new_command("scp");
 
         cmd.args(Self::SSH_ARGS);
+        self.auth_args(&mut cmd);
 
         if let Some(port) = self.ssh.port {
             cmd.arg("-P").arg(port.to_string());
@@ -51,6 +73,7 @@ fn upload_file(&self, local: impl AsRef<Path>, remote: &str) -> Result<()> {
     fn command_builder(&self) -> Command {
         let mut cmd = new_command("ssh");
         cmd.args(Self::SSH_ARGS);
+        self.auth_args(&mut cmd);
 
         if let Some(port) = self.ssh.port {
             cmd.arg("-p").arg(port.to_string());
@@ -49,6 +49,15 @@ pub fn all() -> Result<Vec<WslDistro>> {
 
         Ok(distros)
     }
+
+    /// The name of the distro `wsl` runs when none is given.
+    pub fn default_name() -> Option<String> {
+        WslDistro::all()
+            .ok()?
+            .into_iter()
+            .find(|distro| distro.default)
+            .map(|distro| distro.name)
+    }
 }
 
 impl Remote for WslRemote {
@@ -0,0 +1,169 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crossbeam_channel::{Receiver, Sender};
+use lapce_core::directory::Directory;
+use lapce_rpc::buffer::BufferId;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::workspace::LapceWorkspaceType;
+
+/// A backup of the unsaved content of a file, written periodically so that the changes
+/// can be restored if Lapce exits without saving them.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecoveryFile {
+    /// Where the file is, like `Local` or `ssh://user@host`, since the same path
+    /// can be of different files on different hosts.
+    #[serde(default)]
+    pub workspace: String,
+    pub path: PathBuf,
+    pub rev: u64,
+    pub content: String,
+}
+
+enum RecoveryOp {
+    Write {
+        buffer_id: BufferId,
+        recovery: RecoveryFile,
+    },
+    Remove {
+        workspace: String,
+        path: PathBuf,
+        buffer_id: BufferId,
+        rev: u64,
+    },
+    /// Answered once everything sent before it is done.
+    Flush(Sender<()>),
+}
+
+/// Backups are written and removed one after the other on a single thread, so a
+/// backup can't be written after the removal that was asked for after it.
+static RECOVERY_WORKER: Lazy<Sender<RecoveryOp>> = Lazy::new(|| {
+    let (sender, receiver) = crossbeam_channel::unbounded();
+    std::thread::spawn(move || recovery_worker(receiver));
+    sender
+});
+
+fn recovery_worker(receiver: Receiver<RecoveryOp>) {
+    // The document and revision each backup was last removed at, because it was
+    // saved or its changes were discarded. Backups of that document up to that
+    // revision are stale.
+    let mut removed: HashMap<(String, PathBuf), (BufferId, u64)> = HashMap::new();
+    for op in receiver {
+        match op {
+            RecoveryOp::Write {
+                buffer_id,
+                recovery,
+            } => {
+                let key = (recovery.workspace.clone(), recovery.path.clone());
+                let stale = removed.get(&key).is_some_and(|(id, rev)| {
+                    *id == buffer_id && recovery.rev <= *rev
+                });
+                if stale {
+                    continue;
+                }
+                let Some(recovery_path) =
+                    recovery_file_path(&recovery.workspace, &recovery.path)
+                else {
+                    continue;
+                };
+                if let Ok(s) = serde_json::to_string(&recovery) {
+                    if let Err(err) = std::fs::write(&recovery_path, s) {
+                        tracing::error!("failed to write recovery file: {err}");
+                    }
+                }
+            }
+            RecoveryOp::Remove {
+                workspace,
+                path,
+                buffer_id,
+                rev,
+            } => {
+                if let Some(recovery_path) = recovery_file_path(&workspace, &path) {
+                    if recovery_path.exists() {
+                        let _ = std::fs::remove_file(recovery_path);
+                    }
+                }
+                removed.insert((workspace, path), (buffer_id, rev));
+            }
+            RecoveryOp::Flush(done) => {
+                let _ = done.send(());
+            }
+        }
+    }
+}
+
+fn recovery_file_path(workspace: &str, path: &Path) -> Option<PathBuf> {
+    Directory::recovery_directory().map(|dir| {
+        let mut hasher = Sha256::new();
+        hasher.update(workspace.as_bytes());
+        hasher.update([0]);
+        hasher.update(path.to_string_lossy().as_bytes());
+        dir.join(format!("{:x}.json", hasher.finalize()))
+    })
+}
+
+/// Back up the content of the file at `path` in a workspace of the kind at
+/// revision `rev` of the document, replacing any older backup of it.
+pub fn write_recovery(
+    kind: &LapceWorkspaceType,
+    path: PathBuf,
+    buffer_id: BufferId,
+    rev: u64,
+    content: String,
+) {
+    let _ = RECOVERY_WORKER.send(RecoveryOp::Write {
+        buffer_id,
+        recovery: RecoveryFile {
+            workspace: kind.to_string(),
+            path,
+            rev,
+            content,
+        },
+    });
+}
+
+/// Read the backup of the file at `path` in a workspace of the kind, if there is
+/// one.
+pub fn read_recovery(
+    kind: &LapceWorkspaceType,
+    path: &Path,
+) -> Option<RecoveryFile> {
+    let workspace = kind.to_string();
+    let recovery_path = recovery_file_path(&workspace, path)?;
+    let s = std::fs::read_to_string(recovery_path).ok()?;
+    let recovery: RecoveryFile = serde_json::from_str(&s).ok()?;
+    (recovery.workspace == workspace && recovery.path == path).then_some(recovery)
+}
+
+/// Remove the backup of the file at `path` in a workspace of the kind, because
+/// the document was saved or its changes discarded at revision `rev`. Backups
+/// of earlier revisions of the document that are still pending aren't written
+/// anymore.
+pub fn remove_recovery(
+    kind: &LapceWorkspaceType,
+    path: PathBuf,
+    buffer_id: BufferId,
+    rev: u64,
+) {
+    let _ = RECOVERY_WORKER.send(RecoveryOp::Remove {
+        workspace: kind.to_string(),
+        path,
+        buffer_id,
+        rev,
+    });
+}
+
+/// Wait until the backups asked for so far are written, for when Lapce may exit
+/// right after.
+pub fn flush_recovery() {
+    let (done, wait) = crossbeam_channel::bounded(1);
+    if RECOVERY_WORKER.send(RecoveryOp::Flush(done)).is_ok() {
+        let _ = wait.recv_timeout(Duration::from_secs(5));
+    }
+}
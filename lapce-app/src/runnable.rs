@@ -0,0 +1,142 @@
+//! Running and debugging the tests and binaries a language server finds in a
+//! document, like the `#[test]` functions and `main` rust-analyzer lists as
+//! runnables.
+
+use floem::{ext_event::create_ext_action, reactive::Scope};
+use lapce_rpc::{
+    dap_types::RunDebugConfig,
+    proxy::{ProxyResponse, ProxyRpcHandler, Runnable, RunnableArgs},
+};
+
+use crate::{
+    command::InternalCommand,
+    debug::RunDebugMode,
+    listener::Listener,
+    task::{ProblemMatcher, TaskConfig},
+};
+
+/// The debugger the executables Cargo builds are debugged with.
+const DEBUGGER: &str = "lldb";
+
+/// The task that runs the runnable with Cargo, so that its errors end up in the
+/// problems like those of any other task.
+pub fn runnable_task(runnable: &Runnable) -> TaskConfig {
+    let RunnableArgs {
+        workspace_root,
+        cargo_args,
+        cargo_extra_args,
+        executable_args,
+    } = &runnable.args;
+    let mut args = cargo_args
+        .iter()
+        .chain(cargo_extra_args)
+        .cloned()
+        .collect::<Vec<_>>();
+    if !executable_args.is_empty() {
+        args.push("--".to_string());
+        args.extend(executable_args.iter().cloned());
+    }
+    TaskConfig {
+        name: runnable.label.clone(),
+        command: "cargo".to_string(),
+        args: Some(args),
+        cwd: workspace_root
+            .as_ref()
+            .map(|root| root.to_string_lossy().to_string()),
+        env: None,
+        problem_matcher: Some(ProblemMatcher::Builtin("rustc".to_string())),
+    }
+}
+
+/// Have the proxy build the executable of the runnable, where the workspace is,
+/// and start debugging it once it is built. If it can't be built, the error
+/// Cargo gave is shown.
+pub fn debug_runnable(
+    cx: Scope,
+    internal_command: Listener<InternalCommand>,
+    proxy: &ProxyRpcHandler,
+    runnable: Runnable,
+) {
+    let label = runnable.label.clone();
+    let args = runnable.args.clone();
+    let send =
+        create_ext_action(cx, move |result: Result<String, String>| match result {
+            Ok(program) => internal_command.send(InternalCommand::RunAndDebug {
+                mode: RunDebugMode::Debug,
+                config: RunDebugConfig {
+                    ty: Some(DEBUGGER.to_string()),
+                    name: label,
+                    program,
+                    args: Some(args.executable_args),
+                    cwd: args
+                        .workspace_root
+                        .map(|root| root.to_string_lossy().to_string()),
+                    env: None,
+                    prelaunch: None,
+                    debug_command: None,
+                    dap_id: Default::default(),
+                    collect_output: false,
+                },
+            }),
+            Err(msg) => internal_command.send(InternalCommand::ShowAlert {
+                title: format!("Couldn't build \"{label}\""),
+                msg,
+                buttons: Vec::new(),
+            }),
+        });
+
+    proxy.build_runnable(runnable.args, move |result| {
+        send(match result {
+            Ok(ProxyResponse::BuildRunnableResponse { executable }) => {
+                Ok(executable)
+            }
+            Ok(_) => Err("unexpected response".to_string()),
+            Err(err) => Err(err.message),
+        });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_runnable() -> Runnable {
+        Runnable {
+            label: "test tests::it_works".to_string(),
+            location: None,
+            kind: "cargo".to_string(),
+            args: RunnableArgs {
+                workspace_root: Some("/project".into()),
+                cargo_args: vec![
+                    "test".to_string(),
+                    "--package".to_string(),
+                    "app".to_string(),
+                ],
+                cargo_extra_args: vec!["--release".to_string()],
+                executable_args: vec![
+                    "tests::it_works".to_string(),
+                    "--exact".to_string(),
+                ],
+            },
+        }
+    }
+
+    #[test]
+    fn test_runnable_task() {
+        let task = runnable_task(&test_runnable());
+        assert_eq!(task.command, "cargo");
+        assert_eq!(
+            task.args.unwrap(),
+            vec![
+                "test",
+                "--package",
+                "app",
+                "--release",
+                "--",
+                "tests::it_works",
+                "--exact"
+            ]
+        );
+        assert_eq!(task.cwd.as_deref(), Some("/project"));
+    }
+}
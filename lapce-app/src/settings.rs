@@ -69,6 +69,54 @@ fn from(v: serde_json::Value) -> Self {
     }
 }
 
+impl SettingsValue {
+    /// The value as it is written to the settings file.
+    fn json(&self) -> Option<serde_json::Value> {
+        Some(match self {
+            SettingsValue::Float(n) => serde_json::Value::from(*n),
+            SettingsValue::Integer(n) => serde_json::Value::from(*n),
+            SettingsValue::String(s) => serde_json::Value::String(s.clone()),
+            SettingsValue::Bool(b) => serde_json::Value::Bool(*b),
+            SettingsValue::Dropdown(dropdown) => serde_json::Value::String(
+                dropdown.items.get(dropdown.active_index)?.clone(),
+            ),
+            SettingsValue::Empty => return None,
+        })
+    }
+}
+
+/// Only plain values are shown as defaults, tables such as the terminal
+/// profiles aren't.
+fn default_value(value: Option<serde_json::Value>) -> Option<serde_json::Value> {
+    value
+        .filter(|value| value.is_boolean() || value.is_number() || value.is_string())
+}
+
+/// The text of a value in an input box, where strings aren't quoted.
+fn value_text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Write the setting to the settings file, or remove it from there when it is
+/// set back to its default.
+fn save_setting(
+    kind: &str,
+    field: &str,
+    value: &serde_json::Value,
+    default: Option<&serde_json::Value>,
+) {
+    if default == Some(value) {
+        LapceConfig::reset_setting(kind, field);
+    } else if let Ok(value) =
+        serde::Serialize::serialize(value, toml_edit::ser::ValueSerializer::new())
+    {
+        LapceConfig::update_file(kind, field, value);
+    }
+}
+
 #[derive(Clone)]
 struct SettingsItem {
     kind: String,
@@ -77,6 +125,8 @@ struct SettingsItem {
     description: String,
     filter_text: String,
     value: SettingsValue,
+    /// The value the setting has when it isn't in the settings file.
+    default: Option<serde_json::Value>,
     pos: RwSignal<Point>,
     size: RwSignal<Size>,
     // this is only the header that give an visual sepeartion between different type of settings
@@ -145,34 +195,39 @@ fn into_settings_map(
         }
 
         let config = common.config.get_untracked();
+        let default_config = LapceConfig::default_settings();
         let mut items = im::Vector::new();
         let mut kinds = im::Vector::new();
         let mut item_height_accum = 0.0;
 
-        for (kind, fields, descs, mut settings_map) in [
+        for (kind, fields, descs, mut settings_map, mut default_map) in [
             (
                 "Core",
                 &CoreConfig::FIELDS[..],
                 &CoreConfig::DESCS[..],
                 into_settings_map(&config.core),
+                into_settings_map(&default_config.core),
             ),
             (
                 "Editor",
                 &EditorConfig::FIELDS[..],
                 &EditorConfig::DESCS[..],
                 into_settings_map(&config.editor),
+                into_settings_map(&default_config.editor),
             ),
             (
                 "UI",
                 &UIConfig::FIELDS[..],
                 &UIConfig::DESCS[..],
                 into_settings_map(&config.ui),
+                into_settings_map(&default_config.ui),
             ),
             (
                 "Terminal",
                 &TerminalConfig::FIELDS[..],
                 &TerminalConfig::DESCS[..],
                 into_settings_map(&config.terminal),
+                into_settings_map(&default_config.terminal),
             ),
         ] {
             let pos = cx.create_rw_signal(Point::new(0.0, item_height_accum));
@@ -183,6 +238,7 @@ fn into_settings_map(
                 filter_text: "".to_string(),
                 description: "".to_string(),
                 value: SettingsValue::Empty,
+                default: None,
                 pos,
                 size: cx.create_rw_signal(Size::ZERO),
                 header: true,
@@ -199,6 +255,7 @@ fn into_settings_map(
                     let value = settings_map.remove(&field).unwrap();
                     SettingsValue::from(value)
                 };
+                let default = default_value(default_map.remove(&field));
 
                 let name =
                     format!("{kind}: {}", name.replace('_', " ").to_title_case());
@@ -213,6 +270,7 @@ fn into_settings_map(
                     filter_text,
                     description: desc.to_string(),
                     value,
+                    default,
                     pos: cx.create_rw_signal(Point::ZERO),
                     size: cx.create_rw_signal(Size::ZERO),
                     header: false,
@@ -243,6 +301,7 @@ fn into_settings_map(
                         filter_text: "".to_string(),
                         description: "".to_string(),
                         value: SettingsValue::Empty,
+                        default: None,
                         pos,
                         size: cx.create_rw_signal(Size::ZERO),
                         header: true,
@@ -269,8 +328,9 @@ fn into_settings_map(
 
                             let value = plugin_config
                                 .and_then(|config| config.get(&field).cloned())
-                                .unwrap_or(config.default);
+                                .unwrap_or_else(|| config.default.clone());
                             let value = SettingsValue::from(value);
+                            let default = default_value(Some(config.default));
 
                             let item = SettingsItem {
                                 kind: kind.clone(),
@@ -279,6 +339,7 @@ fn into_settings_map(
                                 filter_text,
                                 description: desc.to_string(),
                                 value,
+                                default,
                                 pos: cx.create_rw_signal(Point::ZERO),
                                 size: cx.create_rw_signal(Size::ZERO),
                                 header: false,
@@ -533,6 +594,10 @@ fn settings_item_view(settings_data: SettingsData, item: SettingsItem) -> impl V
 
     let timer = create_rw_signal(TimerToken::INVALID);
 
+    let current = create_rw_signal(item.value.json());
+    // Bumped when the setting is reset, so its input shows the default again
+    let reset = create_rw_signal(0usize);
+
     let editor_value = match &item.value {
         SettingsValue::Float(n) => Some(n.to_string()),
         SettingsValue::Integer(n) => Some(n.to_string()),
@@ -555,9 +620,23 @@ fn settings_item_view(settings_data: SettingsData, item: SettingsItem) -> impl V
                 let doc = editor.view.doc.get_untracked();
                 doc.reload(Rope::from(editor_value), true);
 
+                {
+                    let doc = doc.clone();
+                    let default = item.default.clone();
+                    create_effect(move |_| {
+                        if reset.get() == 0 {
+                            return;
+                        }
+                        if let Some(default) = default.as_ref() {
+                            doc.reload(Rope::from(value_text(default)), true);
+                        }
+                    });
+                }
+
                 let kind = item.kind.clone();
                 let field = item.field.clone();
                 let item_value = item.value.clone();
+                let default = item.default.clone();
                 create_effect(move |last| {
                     let rev = doc.buffer.with(|b| b.rev());
                     if last.is_none() {
@@ -570,6 +649,7 @@ fn settings_item_view(settings_data: SettingsData, item: SettingsItem) -> impl V
                     let field = field.clone();
                     let buffer = doc.buffer;
                     let item_value = item_value.clone();
+                    let default = default.clone();
                     let token =
                         exec_after(Duration::from_millis(500), move |token| {
                             if let Some(timer) = timer.try_get_untracked() {
@@ -577,33 +657,25 @@ fn settings_item_view(settings_data: SettingsData, item: SettingsItem) -> impl V
                                     let value =
                                         buffer.with_untracked(|b| b.to_string());
                                     let value = match &item_value {
-                                        SettingsValue::Float(_) => {
-                                            value.parse::<f64>().ok().and_then(|v| {
-                                                serde::Serialize::serialize(
-                                        &v,
-                                        toml_edit::ser::ValueSerializer::new(),
-                                    ).ok()
-                                            })
-                                        }
-                                        SettingsValue::Integer(_) => {
-                                            value.parse::<i64>().ok().and_then(|v| {
-                                                serde::Serialize::serialize(
-                                        &v,
-                                        toml_edit::ser::ValueSerializer::new(),
-                                    ).ok()
-                                            })
-                                        }
-                                        _ => serde::Serialize::serialize(
-                                            &value,
-                                            toml_edit::ser::ValueSerializer::new(),
-                                        )
-                                        .ok(),
+                                        SettingsValue::Float(_) => value
+                                            .parse::<f64>()
+                                            .ok()
+                                            .map(serde_json::Value::from),
+                                        SettingsValue::Integer(_) => value
+                                            .parse::<i64>()
+                                            .ok()
+                                            .map(serde_json::Value::from),
+                                        _ => Some(serde_json::Value::String(value)),
                                     };
 
                                     if let Some(value) = value {
-                                        LapceConfig::update_file(
-                                            &kind, &field, value,
+                                        save_setting(
+                                            &kind,
+                                            &field,
+                                            &value,
+                                            default.as_ref(),
                                         );
+                                        current.set(Some(value));
                                     }
                                 }
                             }
@@ -637,21 +709,33 @@ fn settings_item_view(settings_data: SettingsData, item: SettingsItem) -> impl V
                     .unwrap_or_default();
                 let current_value = create_rw_signal(current_value);
 
+                {
+                    let default = item.default.clone();
+                    create_effect(move |_| {
+                        if reset.get() == 0 {
+                            return;
+                        }
+                        if let Some(default) = default.as_ref() {
+                            current_value.set(value_text(default));
+                        }
+                    });
+                }
+
                 let kind = item.kind.clone();
                 let field = item.field.clone();
+                let default = item.default.clone();
                 let view_fn = move |item_string: String| {
                     let kind = kind.clone();
                     let field = field.clone();
+                    let default = default.clone();
                     let local_item_string = item_string.clone();
                     label(move || local_item_string.clone())
                         .on_click_stop(move |_| {
                             current_value.set(item_string.clone());
-                            if let Ok(value) = serde::Serialize::serialize(
-                                &item_string,
-                                toml_edit::ser::ValueSerializer::new(),
-                            ) {
-                                LapceConfig::update_file(&kind, &field, value);
-                            }
+                            let value =
+                                serde_json::Value::String(item_string.clone());
+                            save_setting(&kind, &field, &value, default.as_ref());
+                            current.set(Some(value));
                             expanded.set(false);
                         })
                         .style(move |s| {
@@ -802,16 +886,25 @@ fn settings_item_view(settings_data: SettingsData, item: SettingsItem) -> impl V
 
                 let kind = item.kind.clone();
                 let field = item.field.clone();
+                let default = item.default.clone();
                 create_effect(move |last| {
                     let checked = checked.get();
                     if last.is_none() {
                         return;
                     }
-                    if let Ok(value) = serde::Serialize::serialize(
-                        &checked,
-                        toml_edit::ser::ValueSerializer::new(),
-                    ) {
-                        LapceConfig::update_file(&kind, &field, value);
+                    let value = serde_json::Value::Bool(checked);
+                    save_setting(&kind, &field, &value, default.as_ref());
+                    current.set(Some(value));
+                });
+
+                let default = item.default.clone();
+                create_effect(move |_| {
+                    if reset.get() == 0 {
+                        return;
+                    }
+                    if let Some(default) = default.as_ref().and_then(|d| d.as_bool())
+                    {
+                        checked.set(default);
                     }
                 });
 
@@ -837,6 +930,52 @@ fn settings_item_view(settings_data: SettingsData, item: SettingsItem) -> impl V
                 container_box(empty()).style(|s| s.hide())
             },
         )),
+        {
+            let kind = item.kind.clone();
+            let field = item.field.clone();
+            let default = item.default.clone();
+            let is_default = {
+                let default = default.clone();
+                move || default.is_none() || current.get() == default
+            };
+            let default_text = default
+                .as_ref()
+                .map(|default| format!("Default: {default}"))
+                .unwrap_or_default();
+            stack((
+                label(move || default_text.clone()).style(move |s| {
+                    s.color(*config.get().get_color(LapceColor::EDITOR_DIM))
+                }),
+                text("Reset")
+                    .on_click_stop(move |_| {
+                        LapceConfig::reset_setting(&kind, &field);
+                        current.set(default.clone());
+                        reset.update(|reset| *reset += 1);
+                    })
+                    .style(move |s| {
+                        let config = config.get();
+                        s.margin_left(10)
+                            .padding_horiz(6)
+                            .cursor(CursorStyle::Pointer)
+                            .border(1)
+                            .border_radius(6)
+                            .border_color(
+                                *config.get_color(LapceColor::LAPCE_BORDER),
+                            )
+                            .active(|s| {
+                                s.background(
+                                    *config.get_color(LapceColor::PANEL_BACKGROUND),
+                                )
+                            })
+                    }),
+            ))
+            .style(move |s| {
+                s.items_center()
+                    .margin_top(6.0)
+                    .line_height(1.6)
+                    .apply_if(is_default(), |s| s.hide())
+            })
+        },
         view().style(move |s| s.apply_if(!item.header, |s| s.margin_top(6.0))),
     ))
     .on_resize(move |rect| {
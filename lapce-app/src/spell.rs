@@ -0,0 +1,152 @@
+//! The dictionary the spelling in comments and strings is checked against, and
+//! the code actions that correct a misspelled word.
+
+use std::{collections::HashMap, io::Write, path::PathBuf, sync::Arc};
+
+use floem::ext_event::create_ext_action;
+use lapce_core::{buffer::rope_text::RopeText, spell::Dictionary};
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, Command, TextEdit, Url,
+    WorkspaceEdit,
+};
+
+use crate::{doc::Document, window_tab::CommonData, workspace::LapceWorkspace};
+
+/// The command of the code action that adds a misspelled word to the workspace
+/// dictionary, which is run by the editor rather than by a language server.
+pub const ADD_TO_DICTIONARY: &str = "lapce.addToDictionary";
+
+const SYSTEM_WORD_LIST: &str = "/usr/share/dict/words";
+
+/// How many corrections are offered for a misspelled word.
+const MAX_SUGGESTIONS: usize = 5;
+
+/// The words of the workspace that aren't in the word list, like the names of
+/// its own things. Only a local workspace has one, as the path of a remote
+/// workspace isn't on this machine.
+fn workspace_dictionary_path(workspace: &LapceWorkspace) -> Option<PathBuf> {
+    if !workspace.kind.is_local() {
+        return None;
+    }
+    workspace
+        .path
+        .as_ref()
+        .map(|path| path.join(".lapce").join("dictionary.txt"))
+}
+
+/// Read the word list and the workspace dictionary in the background, and set
+/// the dictionary once they're read. With spell checking off the dictionary is
+/// emptied instead, so that nothing is underlined.
+pub fn load_dictionary(common: &CommonData) {
+    let dictionary = common.spell_dictionary;
+    let config = common.config.get_untracked();
+    if !config.editor.spell_check {
+        if !dictionary.with_untracked(|d| d.is_empty()) {
+            dictionary.set(Arc::new(Dictionary::new()));
+        }
+        return;
+    }
+
+    let word_list = if config.editor.spell_check_dictionary.is_empty() {
+        PathBuf::from(SYSTEM_WORD_LIST)
+    } else {
+        PathBuf::from(&config.editor.spell_check_dictionary)
+    };
+    let workspace_dictionary = workspace_dictionary_path(&common.workspace);
+    let send = create_ext_action(common.scope, move |loaded| {
+        dictionary.set(Arc::new(loaded));
+    });
+    std::thread::spawn(move || {
+        let mut loaded = Dictionary::new();
+        for path in std::iter::once(word_list).chain(workspace_dictionary) {
+            if let Ok(list) = std::fs::read_to_string(&path) {
+                loaded.add_words(&list);
+            }
+        }
+        send(loaded);
+    });
+}
+
+/// Add the word to the workspace dictionary, creating it if it doesn't exist,
+/// and load the dictionary again.
+pub fn add_to_dictionary(common: &CommonData, word: &str) {
+    let Some(path) = workspace_dictionary_path(&common.workspace) else {
+        return;
+    };
+    let result = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| {
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+        })
+        .and_then(|mut file| writeln!(file, "{word}"));
+    if let Err(err) = result {
+        tracing::error!("failed to add {word} to the dictionary: {err}");
+        return;
+    }
+    load_dictionary(common);
+}
+
+/// The corrections of the misspelled word at `offset`, and adding it to the
+/// workspace dictionary, as code actions.
+pub fn code_actions(
+    doc: &Document,
+    dictionary: &Dictionary,
+    workspace: &LapceWorkspace,
+    offset: usize,
+) -> Vec<CodeActionOrCommand> {
+    let misspelling = doc.misspellings.with_untracked(|misspellings| {
+        misspellings
+            .iter()
+            .find(|(start, end)| *start <= offset && offset <= *end)
+            .copied()
+    });
+    let Some((start, end)) = misspelling else {
+        return Vec::new();
+    };
+    let Some(url) = doc
+        .content
+        .with_untracked(|c| c.path().cloned())
+        .and_then(|path| Url::from_file_path(path).ok())
+    else {
+        return Vec::new();
+    };
+    let (word, range) = doc.buffer.with_untracked(|buffer| {
+        let range = lsp_types::Range {
+            start: buffer.offset_to_position(start),
+            end: buffer.offset_to_position(end),
+        };
+        (buffer.slice_to_cow(start..end).to_string(), range)
+    });
+
+    let mut actions = dictionary
+        .suggestions(&word, MAX_SUGGESTIONS)
+        .into_iter()
+        .map(|suggestion| {
+            let edit = TextEdit {
+                range,
+                new_text: suggestion.clone(),
+            };
+            CodeActionOrCommand::CodeAction(CodeAction {
+                title: format!("Change to \"{suggestion}\""),
+                kind: Some(CodeActionKind::QUICKFIX),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from([(url.clone(), vec![edit])])),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+        })
+        .collect::<Vec<_>>();
+    if workspace_dictionary_path(workspace).is_some() {
+        actions.push(CodeActionOrCommand::Command(Command {
+            title: format!("Add \"{word}\" to Workspace Dictionary"),
+            command: ADD_TO_DICTIONARY.to_string(),
+            arguments: Some(vec![word.into()]),
+        }));
+    }
+    actions
+}
@@ -1,27 +1,39 @@
 use std::{
+    fmt,
     rc::Rc,
     sync::{atomic::AtomicU64, Arc},
 };
 
 use floem::{
-    reactive::{create_memo, ReadSignal, RwSignal},
+    reactive::{create_memo, Memo, ReadSignal, RwSignal},
     style::{AlignItems, CursorStyle, Display},
     view::View,
     views::{label, list, stack, svg, Decorators},
 };
 use indexmap::IndexMap;
+use itertools::Itertools;
 use lapce_core::mode::{Mode, VisualMode};
+use lapce_rpc::{
+    plugin::{
+        LanguageServerStatus, PluginId, PluginStatusItem, StatusItemAlignment,
+    },
+    proxy::{ProxyRpcHandler, ProxyStatus},
+};
 use lsp_types::{DiagnosticSeverity, ProgressToken};
 
 use crate::{
     app::clickable_icon,
     command::LapceWorkbenchCommand,
     config::{color::LapceColor, icon::LapceIcons, LapceConfig},
+    editor::EditorData,
     listener::Listener,
+    localization::tr,
+    notification::NotificationData,
     palette::kind::PaletteKind,
     panel::{kind::PanelKind, position::PanelContainerPosition},
     source_control::SourceControlData,
     window_tab::{WindowTabData, WorkProgress},
+    workspace::LapceWorkspace,
 };
 
 pub fn status(
@@ -67,19 +79,28 @@ pub fn status(
     };
 
     let progresses = window_tab_data.progresses;
+    let language_servers = window_tab_data.language_servers;
+    let notification = window_tab_data.notification.clone();
+    let plugin_status_items = window_tab_data.common.plugin_status_items;
+    let proxy = window_tab_data.common.proxy.clone();
+    let workspace = window_tab_data.common.workspace.clone();
+    let proxy_status = window_tab_data.common.proxy_status;
     let mode = create_memo(move |_| window_tab_data.mode());
 
     stack((
         stack((
-            label(move || match mode.get() {
-                Mode::Normal => "Normal".to_string(),
-                Mode::Insert => "Insert".to_string(),
-                Mode::Visual(mode) => match mode {
-                    VisualMode::Normal => "Visual".to_string(),
-                    VisualMode::Linewise => "Visual Line".to_string(),
-                    VisualMode::Blockwise => "Visual Block".to_string(),
-                },
-                Mode::Terminal => "Terminal".to_string(),
+            label(move || {
+                let key = match mode.get() {
+                    Mode::Normal => "mode-normal",
+                    Mode::Insert => "mode-insert",
+                    Mode::Visual(mode) => match mode {
+                        VisualMode::Normal => "mode-visual",
+                        VisualMode::Linewise => "mode-visual-line",
+                        VisualMode::Blockwise => "mode-visual-block",
+                    },
+                    Mode::Terminal => "mode-terminal",
+                };
+                config.with(|_| tr(key))
             })
             .style(move |s| {
                 let config = config.get();
@@ -118,6 +139,7 @@ pub fn status(
                     .height_pct(100.0)
                     .align_items(Some(AlignItems::Center))
             }),
+            remote_status_view(workspace, proxy_status, config),
             stack((
                 svg(move || config.get().ui_svg(LapceIcons::SCM)).style(move |s| {
                     let config = config.get();
@@ -207,7 +229,13 @@ pub fn status(
                         })
                 })
             },
-            progress_view(config, progresses),
+            progress_view(config, progresses, notification.clone()),
+            plugin_status_items_view(
+                plugin_status_items,
+                StatusItemAlignment::Left,
+                proxy.clone(),
+                config,
+            ),
         ))
         .style(|s| {
             s.height_pct(100.0)
@@ -298,104 +326,158 @@ pub fn status(
         }),
         stack({
             let palette_clone = palette.clone();
-            let cursor_info = label(move || {
-                if let Some(editor) = editor.get() {
-                    let mut status = String::new();
+            let cursor_info = file_segment(
+                editor,
+                move || {
+                    let Some(editor) = editor.get() else {
+                        return String::new();
+                    };
                     let cursor = editor.cursor.get();
-                    if let Some((line, column, character)) = editor
+                    let mut status = editor
                         .view
                         .doc
                         .get()
                         .buffer
                         .with(|buffer| cursor.get_line_col_char(buffer))
-                    {
-                        status = format!(
-                            "Ln {}, Col {}, Char {}",
-                            line, column, character,
-                        );
-                    }
-                    if let Some(selection) = cursor.get_selection() {
-                        let selection_range = selection.0.abs_diff(selection.1);
-
-                        if selection.0 != selection.1 {
-                            status =
-                                format!("{status} ({selection_range} selected)");
+                        .map(|(line, column, character)| {
+                            format!("Ln {line}, Col {column}, Char {character}")
+                        })
+                        .unwrap_or_default();
+                    if let Some((start, end)) = cursor.get_selection() {
+                        if start != end {
+                            let selected = start.abs_diff(end);
+                            status = format!("{status} ({selected} selected)");
                         }
                     }
                     let selection_count = cursor.get_selection_count();
                     if selection_count > 1 {
                         status = format!("{status} {selection_count} selections");
                     }
-                    return status;
-                }
-                String::new()
-            })
-            .on_click_stop(move |_| {
-                palette_clone.run(PaletteKind::Line);
-            })
-            .style(move |s| {
-                let config = config.get();
-                s.display(
-                    if editor
+                    status
+                },
+                Some(Box::new(move || palette_clone.run(PaletteKind::Line))),
+                config,
+            );
+            let indentation_info = file_segment(
+                editor,
+                move || {
+                    editor
                         .get()
                         .map(|editor| {
-                            editor.view.doc.get().content.with(|c| c.is_file())
+                            let doc = editor.view.doc.get();
+                            doc.buffer.with(|b| b.indent_style()).name()
                         })
-                        .unwrap_or(false)
-                    {
-                        Display::Flex
-                    } else {
-                        Display::None
-                    },
-                )
-                .height_pct(100.0)
-                .padding_horiz(10.0)
-                .items_center()
-                .color(*config.get_color(LapceColor::STATUS_FOREGROUND))
-                .hover(|s| {
-                    s.cursor(CursorStyle::Pointer).background(
-                        *config.get_color(LapceColor::PANEL_HOVERED_BACKGROUND),
-                    )
-                })
-            });
+                        .unwrap_or_default()
+                },
+                Some(Box::new(move || {
+                    workbench_command
+                        .send(LapceWorkbenchCommand::ChangeFileIndentation);
+                })),
+                config,
+            );
+            // The proxy only opens files that are UTF-8
+            let encoding_info = file_segment(editor, || "UTF-8", None, config);
+            let line_ending_info = file_segment(
+                editor,
+                move || {
+                    editor
+                        .get()
+                        .map(|editor| editor.view.doc.get().line_ending.get().name())
+                        .unwrap_or_default()
+                },
+                Some(Box::new(move || {
+                    workbench_command
+                        .send(LapceWorkbenchCommand::ChangeFileLineEnding);
+                })),
+                config,
+            );
             let palette_clone = palette.clone();
-            let language_info = label(move || {
-                if let Some(editor) = editor.get() {
-                    let doc = editor.view.doc.get();
-                    doc.syntax.with(|s| s.language.name())
-                } else {
-                    "unknown"
-                }
-            })
-            .on_click_stop(move |_| {
-                palette_clone.run(PaletteKind::Language);
-            })
-            .style(move |s| {
-                let config = config.get();
-                s.display(
-                    if editor
+            let language_info = file_segment(
+                editor,
+                move || {
+                    editor
                         .get()
                         .map(|editor| {
-                            editor.view.doc.get().content.with(|c| c.is_file())
+                            let doc = editor.view.doc.get();
+                            doc.syntax.with(|s| s.language.name())
                         })
-                        .unwrap_or(false)
-                    {
-                        Display::Flex
-                    } else {
-                        Display::None
-                    },
-                )
-                .height_pct(100.0)
-                .padding_horiz(10.0)
-                .items_center()
-                .color(*config.get_color(LapceColor::STATUS_FOREGROUND))
-                .hover(|s| {
-                    s.cursor(CursorStyle::Pointer).background(
-                        *config.get_color(LapceColor::PANEL_HOVERED_BACKGROUND),
-                    )
+                        .unwrap_or("unknown")
+                },
+                Some(Box::new(move || palette_clone.run(PaletteKind::Language))),
+                config,
+            );
+            let read_only_info = label(|| "Read Only".to_string())
+                .on_click_stop(move |_| {
+                    workbench_command.send(LapceWorkbenchCommand::ToggleReadOnly);
                 })
-            });
-            (cursor_info, language_info)
+                .style(move |s| {
+                    let config = config.get();
+                    s.display(
+                        if editor
+                            .get()
+                            .map(|editor| {
+                                editor
+                                    .view
+                                    .doc
+                                    .get()
+                                    .content
+                                    .with(|c| c.is_file() && c.read_only())
+                            })
+                            .unwrap_or(false)
+                        {
+                            Display::Flex
+                        } else {
+                            Display::None
+                        },
+                    )
+                    .height_pct(100.0)
+                    .padding_horiz(10.0)
+                    .items_center()
+                    .color(*config.get_color(LapceColor::STATUS_FOREGROUND))
+                    .hover(|s| {
+                        s.cursor(CursorStyle::Pointer).background(
+                            *config.get_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                        )
+                    })
+                });
+            let color_theme_info =
+                label(move || config.get().color_theme.name.clone())
+                    .on_click_stop(move |_| {
+                        workbench_command
+                            .send(LapceWorkbenchCommand::ToggleDarkLightTheme);
+                    })
+                    .style(move |s| {
+                        let config = config.get();
+                        s.apply_if(!config.core.follow_os_theme, |s| s.hide())
+                            .height_pct(100.0)
+                            .padding_horiz(10.0)
+                            .items_center()
+                            .color(*config.get_color(LapceColor::STATUS_FOREGROUND))
+                            .hover(|s| {
+                                s.cursor(CursorStyle::Pointer).background(
+                                    *config.get_color(
+                                        LapceColor::PANEL_HOVERED_BACKGROUND,
+                                    ),
+                                )
+                            })
+                    });
+            (
+                plugin_status_items_view(
+                    plugin_status_items,
+                    StatusItemAlignment::Right,
+                    proxy,
+                    config,
+                ),
+                read_only_info,
+                cursor_info,
+                indentation_info,
+                encoding_info,
+                line_ending_info,
+                language_info,
+                language_servers_view(language_servers, config),
+                color_theme_info,
+                notifications_view(notification, config),
+            )
         })
         .style(|s| {
             s.height_pct(100.0)
@@ -420,9 +502,12 @@ pub fn status(
     })
 }
 
+/// The work in progress, which opens the notification center to see all of it
+/// or cancel it.
 fn progress_view(
     config: ReadSignal<Arc<LapceConfig>>,
     progresses: RwSignal<IndexMap<ProgressToken, WorkProgress>>,
+    notification: NotificationData,
 ) -> impl View {
     let id = AtomicU64::new(0);
     list(
@@ -444,4 +529,195 @@ fn progress_view(
             })
         },
     )
+    .on_click_stop(move |_| notification.toggle_center())
+    .style(|s| {
+        s.height_pct(100.0)
+            .items_center()
+            .cursor(CursorStyle::Pointer)
+    })
+}
+
+/// The state of the connection to a remote workspace. Nothing is shown for a
+/// local one.
+fn remote_status_view(
+    workspace: Arc<LapceWorkspace>,
+    proxy_status: RwSignal<Option<ProxyStatus>>,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let is_remote = workspace.kind.is_remote();
+    let kind = workspace.kind.to_string();
+    label(move || match proxy_status.get() {
+        Some(ProxyStatus::Connecting) => format!("Connecting to {kind}"),
+        Some(ProxyStatus::Reconnecting) => format!("Reconnecting to {kind}"),
+        Some(ProxyStatus::Disconnected) => {
+            format!("{kind} is offline, saves wait until it's back")
+        }
+        Some(ProxyStatus::Connected) | None => kind.clone(),
+    })
+    .style(move |s| {
+        s.display(if is_remote {
+            Display::Flex
+        } else {
+            Display::None
+        })
+        .height_pct(100.0)
+        .padding_horiz(10.0)
+        .items_center()
+        .color(*config.get().get_color(LapceColor::STATUS_FOREGROUND))
+    })
+}
+
+/// A segment about the file in the active editor, which is hidden when the
+/// editor isn't showing a file.
+fn file_segment<S: fmt::Display + 'static>(
+    editor: Memo<Option<Rc<EditorData>>>,
+    text: impl Fn() -> S + 'static,
+    on_click: Option<Box<dyn Fn()>>,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let clickable = on_click.is_some();
+    label(text)
+        .on_click_stop(move |_| {
+            if let Some(on_click) = on_click.as_ref() {
+                on_click();
+            }
+        })
+        .style(move |s| {
+            let config = config.get();
+            let is_file = editor
+                .get()
+                .map(|editor| editor.view.doc.get().content.with(|c| c.is_file()))
+                .unwrap_or(false);
+            s.apply_if(!is_file, |s| s.hide())
+                .height_pct(100.0)
+                .padding_horiz(10.0)
+                .items_center()
+                .color(*config.get_color(LapceColor::STATUS_FOREGROUND))
+                .apply_if(clickable, |s| {
+                    s.hover(|s| {
+                        s.cursor(CursorStyle::Pointer).background(
+                            *config.get_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                        )
+                    })
+                })
+        })
+}
+
+/// The language servers plugins started, with how far along they are.
+fn language_servers_view(
+    language_servers: RwSignal<IndexMap<PluginId, (String, LanguageServerStatus)>>,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let text = move || {
+        language_servers.with(|servers| {
+            servers
+                .values()
+                .map(|(name, status)| match status {
+                    LanguageServerStatus::Starting => format!("{name} (starting)"),
+                    LanguageServerStatus::Running => name.clone(),
+                    LanguageServerStatus::Stopped => format!("{name} (stopped)"),
+                })
+                .join(", ")
+        })
+    };
+    label(text).style(move |s| {
+        let config = config.get();
+        s.apply_if(language_servers.with(|servers| servers.is_empty()), |s| {
+            s.hide()
+        })
+        .height_pct(100.0)
+        .padding_horiz(10.0)
+        .items_center()
+        .color(*config.get_color(LapceColor::STATUS_FOREGROUND))
+    })
+}
+
+/// Opens the notification center, with how many notifications came in since it
+/// was last opened.
+fn notifications_view(
+    notification: NotificationData,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let unread = notification.unread;
+    stack((
+        svg(move || config.get().ui_svg(LapceIcons::NOTIFICATIONS)).style(
+            move |s| {
+                let config = config.get();
+                let size = config.ui.icon_size() as f32;
+                s.size(size, size)
+                    .color(*config.get_color(LapceColor::LAPCE_ICON_ACTIVE))
+            },
+        ),
+        label(move || unread.get().to_string()).style(move |s| {
+            s.apply_if(unread.get() == 0, |s| s.hide())
+                .margin_left(5.0)
+                .color(*config.get().get_color(LapceColor::STATUS_FOREGROUND))
+        }),
+    ))
+    .on_click_stop(move |_| notification.toggle_center())
+    .style(move |s| {
+        s.height_pct(100.0)
+            .padding_horiz(10.0)
+            .items_center()
+            .hover(|s| {
+                s.cursor(CursorStyle::Pointer).background(
+                    *config.get().get_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                )
+            })
+    })
+}
+
+/// The items plugins show at one end of the status bar. Clicking one runs its
+/// command.
+fn plugin_status_items_view(
+    items: RwSignal<im::Vector<PluginStatusItem>>,
+    alignment: StatusItemAlignment,
+    proxy: ProxyRpcHandler,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    list(
+        move || {
+            items.with(|items| {
+                items
+                    .iter()
+                    .filter(|item| item.alignment == alignment)
+                    .cloned()
+                    .collect::<im::Vector<_>>()
+            })
+        },
+        |item| (item.volt.clone(), item.id.clone(), item.text.clone()),
+        move |item| {
+            let proxy = proxy.clone();
+            let PluginStatusItem {
+                volt,
+                text,
+                command,
+                ..
+            } = item;
+            let has_command = command.is_some();
+            label(move || text.clone())
+                .on_click_stop(move |_| {
+                    if let Some(command) = command.clone() {
+                        proxy.execute_plugin_command(volt.clone(), command);
+                    }
+                })
+                .style(move |s| {
+                    let config = config.get();
+                    s.height_pct(100.0)
+                        .padding_horiz(10.0)
+                        .items_center()
+                        .color(*config.get_color(LapceColor::STATUS_FOREGROUND))
+                        .apply_if(has_command, |s| {
+                            s.hover(|s| {
+                                s.cursor(CursorStyle::Pointer).background(
+                                    *config.get_color(
+                                        LapceColor::PANEL_HOVERED_BACKGROUND,
+                                    ),
+                                )
+                            })
+                        })
+                })
+        },
+    )
+    .style(|s| s.height_pct(100.0).items_center())
 }
@@ -0,0 +1,378 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use floem::reactive::{RwSignal, Scope};
+use lapce_rpc::{dap_types::RunDebugConfig, terminal::TermId};
+use lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{doc::EditorDiagnostic, main_split::MainSplitData};
+
+#[derive(Deserialize, Serialize)]
+pub struct TaskConfigs {
+    pub tasks: Vec<TaskConfig>,
+}
+
+/// A build or run task defined in `.lapce/tasks.toml`.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct TaskConfig {
+    pub name: String,
+    pub command: String,
+    pub args: Option<Vec<String>>,
+    pub cwd: Option<String>,
+    pub env: Option<HashMap<String, String>>,
+    pub problem_matcher: Option<ProblemMatcher>,
+}
+
+impl TaskConfig {
+    /// The task is run in a terminal like a run config without a debugger.
+    pub fn run_config(&self) -> RunDebugConfig {
+        RunDebugConfig {
+            ty: None,
+            name: self.name.clone(),
+            program: self.command.clone(),
+            args: self.args.clone(),
+            cwd: self.cwd.clone(),
+            env: self.env.clone(),
+            prelaunch: None,
+            debug_command: None,
+            dap_id: Default::default(),
+            collect_output: self.problem_matcher.is_some(),
+        }
+    }
+
+    /// The directory relative paths in the task output are resolved against.
+    fn working_directory(&self, workspace: Option<&Path>) -> PathBuf {
+        let workspace = workspace.map(Path::to_path_buf).unwrap_or_default();
+        match self.cwd.as_ref() {
+            Some(cwd) => PathBuf::from(
+                cwd.replace("${workspace}", &workspace.to_string_lossy()),
+            ),
+            None => workspace,
+        }
+    }
+}
+
+/// How errors are found in the output of a task.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(untagged)]
+pub enum ProblemMatcher {
+    /// One of the built in matchers, `rustc` or `gcc`.
+    Builtin(String),
+    /// A regex that is matched against every line of output, and the capture
+    /// groups the fields of the problem are in.
+    Pattern {
+        regexp: String,
+        file: usize,
+        line: usize,
+        column: Option<usize>,
+        severity: Option<usize>,
+        message: usize,
+    },
+}
+
+/// The key in the `data` of the diagnostics found in task output, with the name
+/// of the task as its value. It tells them apart from the diagnostics of language
+/// servers, which replace only their own when they publish new ones.
+const TASK_PROBLEM_KEY: &str = "lapceTask";
+
+/// The output of a task that is searched for problems is capped at this many
+/// bytes, so a task that prints without end doesn't use up the memory.
+pub const MAX_TASK_OUTPUT: usize = 8 * 1024 * 1024;
+
+/// Whether the diagnostic is a problem found in the output of a task.
+pub fn is_task_problem(diagnostic: &Diagnostic) -> bool {
+    diagnostic
+        .data
+        .as_ref()
+        .is_some_and(|data| data.get(TASK_PROBLEM_KEY).is_some())
+}
+
+static ANSI_ESCAPE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\x1b\[[0-9;?]*[A-Za-z]").unwrap());
+static RUSTC_MESSAGE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(error|warning)(?:\[\w+\])?: (.+)$").unwrap());
+static RUSTC_LOCATION: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s*--> (.+):(\d+):(\d+)$").unwrap());
+static GCC_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(.+?):(\d+):(\d+): (?:fatal )?(error|warning|note): (.+)$")
+        .unwrap()
+});
+
+fn severity_from_str(s: &str) -> DiagnosticSeverity {
+    match s.to_lowercase().as_str() {
+        "warning" | "warn" => DiagnosticSeverity::WARNING,
+        "note" | "info" => DiagnosticSeverity::INFORMATION,
+        "hint" => DiagnosticSeverity::HINT,
+        _ => DiagnosticSeverity::ERROR,
+    }
+}
+
+fn problem(
+    cwd: &Path,
+    file: &str,
+    line: &str,
+    column: Option<&str>,
+    severity: DiagnosticSeverity,
+    message: &str,
+    source: &str,
+) -> Option<(PathBuf, Diagnostic)> {
+    let line = line.parse::<u32>().ok()?.saturating_sub(1);
+    let column = column
+        .and_then(|c| c.parse::<u32>().ok())
+        .unwrap_or(1)
+        .saturating_sub(1);
+    let position = Position::new(line, column);
+    let diagnostic = Diagnostic {
+        range: Range::new(position, position),
+        severity: Some(severity),
+        source: Some(source.to_string()),
+        message: message.to_string(),
+        data: Some(serde_json::json!({ TASK_PROBLEM_KEY: source })),
+        ..Default::default()
+    };
+    Some((cwd.join(file), diagnostic))
+}
+
+/// Find the problems in the output of a task. Relative paths are resolved
+/// against `cwd`, and the diagnostics get `source` as their source.
+pub fn parse_problems(
+    output: &str,
+    matcher: &ProblemMatcher,
+    cwd: &Path,
+    source: &str,
+) -> Vec<(PathBuf, Diagnostic)> {
+    let output = ANSI_ESCAPE.replace_all(output, "");
+    let lines = output.lines().map(|l| l.trim_end_matches('\r'));
+    let mut problems = Vec::new();
+    match matcher {
+        ProblemMatcher::Builtin(name) if name == "rustc" => {
+            // The message comes first and the location on one of the next lines
+            let mut pending: Option<(DiagnosticSeverity, String)> = None;
+            for line in lines {
+                if let Some(caps) = RUSTC_MESSAGE.captures(line) {
+                    pending =
+                        Some((severity_from_str(&caps[1]), caps[2].to_string()));
+                } else if let Some(caps) = RUSTC_LOCATION.captures(line) {
+                    if let Some((severity, message)) = pending.take() {
+                        problems.extend(problem(
+                            cwd,
+                            &caps[1],
+                            &caps[2],
+                            Some(&caps[3]),
+                            severity,
+                            &message,
+                            source,
+                        ));
+                    }
+                }
+            }
+        }
+        ProblemMatcher::Builtin(name) if name == "gcc" => {
+            for line in lines {
+                if let Some(caps) = GCC_PATTERN.captures(line) {
+                    problems.extend(problem(
+                        cwd,
+                        &caps[1],
+                        &caps[2],
+                        Some(&caps[3]),
+                        severity_from_str(&caps[4]),
+                        &caps[5],
+                        source,
+                    ));
+                }
+            }
+        }
+        ProblemMatcher::Builtin(_) => {}
+        ProblemMatcher::Pattern {
+            regexp,
+            file,
+            line,
+            column,
+            severity,
+            message,
+        } => {
+            let Ok(regex) = Regex::new(regexp) else {
+                return problems;
+            };
+            for l in lines {
+                if let Some(caps) = regex.captures(l) {
+                    let get = |i: usize| caps.get(i).map(|m| m.as_str());
+                    let (Some(f), Some(ln), Some(msg)) =
+                        (get(*file), get(*line), get(*message))
+                    else {
+                        continue;
+                    };
+                    problems.extend(problem(
+                        cwd,
+                        f,
+                        ln,
+                        column.and_then(get),
+                        severity
+                            .and_then(get)
+                            .map(severity_from_str)
+                            .unwrap_or(DiagnosticSeverity::ERROR),
+                        msg,
+                        source,
+                    ));
+                }
+            }
+        }
+    }
+    problems
+}
+
+#[derive(Clone)]
+pub struct TaskData {
+    /// The tasks with a problem matcher that are running, by their terminal.
+    pub running: RwSignal<im::HashMap<TermId, TaskConfig>>,
+    pub main_split: MainSplitData,
+}
+
+impl TaskData {
+    pub fn new(cx: Scope, main_split: MainSplitData) -> Self {
+        Self {
+            running: cx.create_rw_signal(im::HashMap::new()),
+            main_split,
+        }
+    }
+
+    pub fn started(&self, term_id: TermId, task: TaskConfig) {
+        self.clear_problems(&task.name);
+        self.running.update(|running| {
+            running.insert(term_id, task);
+        });
+    }
+
+    /// Parse the output of a finished task and put its problems in the
+    /// diagnostics, next to the ones from language servers.
+    pub fn finished(&self, term_id: TermId, output: &str) {
+        let Some(task) = self
+            .running
+            .try_update(|running| running.remove(&term_id))
+            .flatten()
+        else {
+            return;
+        };
+        let Some(matcher) = task.problem_matcher.as_ref() else {
+            return;
+        };
+
+        let cwd =
+            task.working_directory(self.main_split.common.workspace.path.as_deref());
+        let mut problems: HashMap<PathBuf, Vec<Diagnostic>> = HashMap::new();
+        for (path, diagnostic) in parse_problems(output, matcher, &cwd, &task.name) {
+            problems.entry(path).or_default().push(diagnostic);
+        }
+        for (path, diagnostics) in problems {
+            let data = self.main_split.get_diagnostic_data(&path);
+            data.diagnostics.update(|existing| {
+                existing.extend(diagnostics.into_iter().map(|diagnostic| {
+                    EditorDiagnostic {
+                        range: (0, 0),
+                        diagnostic,
+                    }
+                }));
+                existing.sort_by(|a, b| {
+                    a.diagnostic.range.start.cmp(&b.diagnostic.range.start)
+                });
+            });
+            self.init_doc_diagnostics(&path);
+        }
+    }
+
+    /// Remove the problems found by the previous run of a task.
+    fn clear_problems(&self, source: &str) {
+        let diagnostics = self.main_split.diagnostics.get_untracked();
+        for (path, data) in diagnostics.iter() {
+            let has_problems = data.diagnostics.with_untracked(|d| {
+                d.iter()
+                    .any(|d| d.diagnostic.source.as_deref() == Some(source))
+            });
+            if has_problems {
+                data.diagnostics.update(|d| {
+                    d.retain(|d| d.diagnostic.source.as_deref() != Some(source))
+                });
+                self.init_doc_diagnostics(path);
+            }
+        }
+    }
+
+    fn init_doc_diagnostics(&self, path: &Path) {
+        if let Some(doc) = self
+            .main_split
+            .docs
+            .with_untracked(|docs| docs.get(path).cloned())
+        {
+            doc.init_diagnostics();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rustc_problems() {
+        let output = "\x1b[1m\x1b[31merror[E0308]\x1b[0m: mismatched types\r\n  --> src/main.rs:2:18\n   |\nwarning: unused variable: `x`\n --> src/lib.rs:10:9\n";
+        let problems = parse_problems(
+            output,
+            &ProblemMatcher::Builtin("rustc".to_string()),
+            Path::new("/project"),
+            "build",
+        );
+        assert_eq!(problems.len(), 2);
+        assert_eq!(problems[0].0, PathBuf::from("/project/src/main.rs"));
+        assert_eq!(problems[0].1.range.start, Position::new(1, 17));
+        assert_eq!(problems[0].1.message, "mismatched types");
+        assert_eq!(problems[0].1.severity, Some(DiagnosticSeverity::ERROR));
+        assert_eq!(problems[0].1.source.as_deref(), Some("build"));
+        assert!(is_task_problem(&problems[0].1));
+        assert!(!is_task_problem(&Diagnostic::default()));
+        assert_eq!(problems[1].0, PathBuf::from("/project/src/lib.rs"));
+        assert_eq!(problems[1].1.severity, Some(DiagnosticSeverity::WARNING));
+    }
+
+    #[test]
+    fn test_parse_gcc_problems() {
+        let output = "main.c:3:5: error: expected ';' before 'return'\nmake: *** [all] Error 1\n";
+        let problems = parse_problems(
+            output,
+            &ProblemMatcher::Builtin("gcc".to_string()),
+            Path::new("/project"),
+            "make",
+        );
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].0, PathBuf::from("/project/main.c"));
+        assert_eq!(problems[0].1.range.start, Position::new(2, 4));
+        assert_eq!(problems[0].1.message, "expected ';' before 'return'");
+    }
+
+    #[test]
+    fn test_parse_pattern_problems() {
+        let matcher = ProblemMatcher::Pattern {
+            regexp: r"^(\S+)\((\d+)\): (\w+) (.*)$".to_string(),
+            file: 1,
+            line: 2,
+            column: None,
+            severity: Some(3),
+            message: 4,
+        };
+        let problems = parse_problems(
+            "lib/a.ts(12): warning unused import\n",
+            &matcher,
+            Path::new("/project"),
+            "tsc",
+        );
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].0, PathBuf::from("/project/lib/a.ts"));
+        assert_eq!(problems[0].1.range.start, Position::new(11, 0));
+        assert_eq!(problems[0].1.severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(problems[0].1.message, "unused import");
+    }
+}
@@ -355,6 +355,10 @@ pub fn new_raw_terminal(
             common.proxy.clone(),
             common.term_notification_tx.clone(),
         )));
+        // Set up before the process starts, so none of its output is missed
+        if run_debug.is_some_and(|(run_debug, _)| run_debug.collect_output) {
+            raw.write().output = Some(Vec::new());
+        }
 
         let mut profile = match profile {
             Some(profile) => profile,
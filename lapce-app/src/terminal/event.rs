@@ -10,6 +10,7 @@
 pub enum TermNotification {
     SetTitle { term_id: TermId, title: String },
     RequestPaint,
+    TaskOutput { term_id: TermId, output: String },
 }
 
 pub enum TermEvent {
@@ -36,7 +37,17 @@ pub fn terminal_update_process(
         };
         match event {
             TermEvent::CloseTerminal => {
-                terminals.remove(&term_id);
+                if let Some(raw) = terminals.remove(&term_id) {
+                    let output = raw.write().output.take();
+                    if let Some(output) = output {
+                        let _ = term_notification_tx.send(
+                            TermNotification::TaskOutput {
+                                term_id,
+                                output: String::from_utf8_lossy(&output).to_string(),
+                            },
+                        );
+                    }
+                }
             }
             TermEvent::NewTerminal(raw) => {
                 terminals.insert(term_id, raw);
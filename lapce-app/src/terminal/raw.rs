@@ -4,6 +4,7 @@
 use lapce_rpc::{proxy::ProxyRpcHandler, terminal::TermId};
 
 use super::event::TermNotification;
+use crate::task::MAX_TASK_OUTPUT;
 
 pub struct EventProxy {
     term_id: TermId,
@@ -37,6 +38,10 @@ pub struct RawTerminal {
     pub parser: ansi::Processor,
     pub term: Term<EventProxy>,
     pub scroll_delta: f64,
+    /// The output of the process is kept here when it's set, so that it can
+    /// be searched for problems once the process finishes. Only the first
+    /// [`MAX_TASK_OUTPUT`] bytes are kept.
+    pub output: Option<Vec<u8>>,
 }
 
 impl RawTerminal {
@@ -60,10 +65,15 @@ pub fn new(
             parser,
             term,
             scroll_delta: 0.0,
+            output: None,
         }
     }
 
     pub fn update_content(&mut self, content: Vec<u8>) {
+        if let Some(output) = self.output.as_mut() {
+            let len = content.len().min(MAX_TASK_OUTPUT - output.len());
+            output.extend_from_slice(&content[..len]);
+        }
         for byte in content {
             self.parser.advance(&mut self.term, byte);
         }
@@ -113,7 +113,7 @@ fn char_size(&self) -> Size {
         let font_family = config.terminal_font_family();
         let font_size = config.terminal_font_size();
         let family: Vec<FamilyOwned> =
-            FamilyOwned::parse_list(font_family).collect();
+            FamilyOwned::parse_list(&font_family).collect();
         let attrs = Attrs::new().family(&family).font_size(font_size as f32);
         let attrs_list = AttrsList::new(attrs);
         let mut text_layout = TextLayout::new();
@@ -207,7 +207,7 @@ fn paint(&mut self, cx: &mut floem::context::PaintCx) {
         let char_width = char_size.width;
 
         let family: Vec<FamilyOwned> =
-            FamilyOwned::parse_list(font_family).collect();
+            FamilyOwned::parse_list(&font_family).collect();
         let attrs = Attrs::new().family(&family).font_size(font_size as f32);
 
         if let Some(error) = self.launch_error.get() {
@@ -116,10 +116,13 @@ pub fn text_input(
                 let viewport = editor.viewport.get();
                 let origin = window_origin
                     + Vec2::new(
-                        cursor_line.p1.x - viewport.x0,
-                        cursor_line.p1.y - viewport.y0,
+                        cursor_line.p0.x - viewport.x0,
+                        cursor_line.p0.y - viewport.y0,
                     );
-                set_ime_cursor_area(origin, Size::new(800.0, 600.0));
+                set_ime_cursor_area(
+                    origin,
+                    Size::new(1.0, cursor_line.p1.y - cursor_line.p0.y),
+                );
             }
         });
     }
@@ -205,9 +208,7 @@ pub fn text_input(
         }
 
         if let Event::ImeCommit(text) = event {
-            let doc = local_editor.view.doc.get_untracked();
-            doc.clear_preedit();
-            local_editor.receive_char(text);
+            local_editor.commit_ime(text);
         }
         EventPropagation::Stop
     })
@@ -531,9 +532,11 @@ fn compute_layout(
         self.layout_rect = size
             .to_rect()
             .with_origin(Point::new(location.x as f64, location.y as f64));
-        let offset = self.cursor.with_untracked(|c| c.offset());
         let cursor_line = self.text_layout.with_untracked(|text_layout| {
-            let hit_position = text_layout.as_ref().unwrap().hit_position(offset);
+            // The offset in the text with the preedit text, which is where the
+            // caret is drawn
+            let hit_position =
+                text_layout.as_ref().unwrap().hit_position(self.offset);
             let point = Point::new(location.x as f64, location.y as f64)
                 - self.text_viewport.origin().to_vec2();
             let cursor_point = hit_position.point + point.to_vec2();
@@ -17,6 +17,7 @@
     command::{LapceCommand, LapceWorkbenchCommand, WindowCommand},
     config::{color::LapceColor, icon::LapceIcons, LapceConfig},
     listener::Listener,
+    localization::{tr, tr_args},
     main_split::MainSplitData,
     update::ReleaseInfo,
     window_tab::WindowTabData,
@@ -73,17 +74,28 @@ fn left(
         ))
         .popout_menu(move || {
             #[allow(unused_mut)]
-            let mut menu = Menu::new("").entry(
-                MenuItem::new("Connect to SSH Host").action(move || {
-                    workbench_command.send(LapceWorkbenchCommand::ConnectSshHost);
-                }),
-            );
+            let mut menu = Menu::new("")
+                .entry(MenuItem::new(tr("menu-connect-to-ssh-host")).action(
+                    move || {
+                        workbench_command
+                            .send(LapceWorkbenchCommand::ConnectSshHost);
+                    },
+                ))
+                .entry(
+                    MenuItem::new(tr("menu-attach-to-running-container")).action(
+                        move || {
+                            workbench_command
+                                .send(LapceWorkbenchCommand::ConnectContainer);
+                        },
+                    ),
+                );
             #[cfg(windows)]
             {
-                menu =
-                    menu.entry(MenuItem::new("Connect to WSL").action(move || {
+                menu = menu.entry(MenuItem::new(tr("menu-connect-to-wsl")).action(
+                    move || {
                         workbench_command.send(LapceWorkbenchCommand::ConnectWsl);
-                    }));
+                    },
+                ));
             }
             menu
         })
@@ -96,7 +108,7 @@ fn left(
                     Some(ProxyStatus::Connected) => {
                         *config.get_color(LapceColor::LAPCE_REMOTE_CONNECTED)
                     }
-                    Some(ProxyStatus::Connecting) => {
+                    Some(ProxyStatus::Connecting | ProxyStatus::Reconnecting) => {
                         *config.get_color(LapceColor::LAPCE_REMOTE_CONNECTING)
                     }
                     Some(ProxyStatus::Disconnected) => {
@@ -181,12 +193,15 @@ fn middle(
         )
         .popout_menu(move || {
             Menu::new("")
-                .entry(MenuItem::new("Open Folder").action(move || {
+                .entry(MenuItem::new(tr("menu-open-folder")).action(move || {
                     workbench_command.send(LapceWorkbenchCommand::OpenFolder);
                 }))
-                .entry(MenuItem::new("Open Recent Workspace").action(move || {
-                    workbench_command.send(LapceWorkbenchCommand::PaletteWorkspace);
-                }))
+                .entry(MenuItem::new(tr("menu-open-recent-workspace")).action(
+                    move || {
+                        workbench_command
+                            .send(LapceWorkbenchCommand::PaletteWorkspace);
+                    },
+                ))
         })
     };
 
@@ -311,43 +326,57 @@ fn right(
             )
             .popout_menu(move || {
                 Menu::new("")
-                    .entry(MenuItem::new("Command Palette").action(move || {
-                        workbench_command.send(LapceWorkbenchCommand::PaletteCommand)
-                    }))
+                    .entry(MenuItem::new(tr("menu-command-palette")).action(
+                        move || {
+                            workbench_command
+                                .send(LapceWorkbenchCommand::PaletteCommand)
+                        },
+                    ))
                     .separator()
-                    .entry(MenuItem::new("Open Settings").action(move || {
-                        workbench_command.send(LapceWorkbenchCommand::OpenSettings)
-                    }))
-                    .entry(MenuItem::new("Open Keyboard Shortcuts").action(
+                    .entry(MenuItem::new(tr("menu-open-settings")).action(
                         move || {
                             workbench_command
-                                .send(LapceWorkbenchCommand::OpenKeyboardShortcuts)
+                                .send(LapceWorkbenchCommand::OpenSettings)
                         },
                     ))
-                    .entry(MenuItem::new("Open Theme Color Settings").action(
+                    .entry(MenuItem::new(tr("menu-open-keyboard-shortcuts")).action(
                         move || {
                             workbench_command
-                                .send(LapceWorkbenchCommand::OpenThemeColorSettings)
+                                .send(LapceWorkbenchCommand::OpenKeyboardShortcuts)
                         },
                     ))
+                    .entry(
+                        MenuItem::new(tr("menu-open-theme-color-settings")).action(
+                            move || {
+                                workbench_command.send(
+                                    LapceWorkbenchCommand::OpenThemeColorSettings,
+                                )
+                            },
+                        ),
+                    )
                     .separator()
                     .entry(if let Some(v) = latest_version.get_untracked() {
                         if update_in_progress.get_untracked() {
-                            MenuItem::new(format!("Update in progress ({v})"))
-                                .enabled(false)
+                            MenuItem::new(tr_args(
+                                "menu-update-in-progress",
+                                &[("version", v.as_str())],
+                            ))
+                            .enabled(false)
                         } else {
-                            MenuItem::new(format!("Restart to update ({v})")).action(
-                                move || {
-                                    workbench_command
-                                        .send(LapceWorkbenchCommand::RestartToUpdate)
-                                },
-                            )
+                            MenuItem::new(tr_args(
+                                "menu-restart-to-update",
+                                &[("version", v.as_str())],
+                            ))
+                            .action(move || {
+                                workbench_command
+                                    .send(LapceWorkbenchCommand::RestartToUpdate)
+                            })
                         }
                     } else {
-                        MenuItem::new("No update available").enabled(false)
+                        MenuItem::new(tr("menu-no-update-available")).enabled(false)
                     })
                     .separator()
-                    .entry(MenuItem::new("About Lapce").action(move || {
+                    .entry(MenuItem::new(tr("menu-about-lapce")).action(move || {
                         workbench_command.send(LapceWorkbenchCommand::ShowAbout)
                     }))
             }),
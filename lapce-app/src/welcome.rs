@@ -0,0 +1,166 @@
+use std::{rc::Rc, sync::Arc};
+
+use floem::{
+    reactive::{use_context, ReadSignal},
+    style::CursorStyle,
+    view::View,
+    views::{container, label, list, stack, svg, Decorators},
+};
+
+use crate::{
+    command::{LapceWorkbenchCommand, WindowCommand},
+    config::{color::LapceColor, LapceConfig},
+    db::LapceDb,
+    listener::Listener,
+    window_tab::WindowTabData,
+    workspace::{LapceWorkspace, LapceWorkspaceType},
+};
+
+/// How many recent workspaces the start view lists.
+const RECENT_WORKSPACES: usize = 10;
+
+fn welcome_action(
+    text: &'static str,
+    cmd: LapceWorkbenchCommand,
+    workbench_command: Listener<LapceWorkbenchCommand>,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    label(move || text.to_string())
+        .on_click_stop(move |_| {
+            workbench_command.send(cmd.clone());
+        })
+        .style(move |s| {
+            s.margin_top(6.0)
+                .color(*config.get().get_color(LapceColor::EDITOR_LINK))
+                .hover(|s| s.cursor(CursorStyle::Pointer))
+        })
+}
+
+fn section_title(
+    text: &'static str,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    label(move || text.to_string()).style(move |s| {
+        s.font_bold()
+            .margin_top(30.0)
+            .margin_bottom(4.0)
+            .color(*config.get().get_color(LapceColor::EDITOR_DIM))
+    })
+}
+
+/// The start view in the main split when no folder is open.
+pub fn welcome_view(window_tab_data: Rc<WindowTabData>) -> impl View {
+    let config = window_tab_data.common.config;
+    let workbench_command = window_tab_data.common.workbench_command;
+    let window_command = window_tab_data.common.window_common.window_command;
+    let editor_tabs = window_tab_data.main_split.editor_tabs;
+    let has_workspace = window_tab_data.workspace.path.is_some();
+
+    let recent_workspaces = move || {
+        let db: Arc<LapceDb> = use_context().unwrap();
+        db.recent_workspaces_pinned_first()
+            .into_iter()
+            .filter(|(w, _)| w.path.is_some())
+            .take(RECENT_WORKSPACES)
+            .collect::<Vec<_>>()
+    };
+
+    container(
+        stack((
+            svg(move || config.get().logo_svg()).style(move |s| {
+                s.size(80.0, 80.0)
+                    .color(*config.get().get_color(LapceColor::EDITOR_DIM))
+            }),
+            section_title("Start", config),
+            welcome_action(
+                "New File",
+                LapceWorkbenchCommand::NewFile,
+                workbench_command,
+                config,
+            ),
+            welcome_action(
+                "Open File",
+                LapceWorkbenchCommand::OpenFile,
+                workbench_command,
+                config,
+            ),
+            welcome_action(
+                "Open Folder",
+                LapceWorkbenchCommand::OpenFolder,
+                workbench_command,
+                config,
+            ),
+            welcome_action(
+                "Connect to SSH Host",
+                LapceWorkbenchCommand::ConnectSshHost,
+                workbench_command,
+                config,
+            ),
+            section_title("Recent", config),
+            list(
+                recent_workspaces,
+                |(w, pinned): &(LapceWorkspace, bool)| {
+                    (format!("{:?}", w.kind), w.path.clone(), *pinned)
+                },
+                move |(workspace, pinned)| {
+                    let path = workspace
+                        .path
+                        .as_ref()
+                        .map(|p| p.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    let text = match &workspace.kind {
+                        LapceWorkspaceType::Local => path,
+                        LapceWorkspaceType::RemoteSSH(ssh) => {
+                            format!("[{ssh}] {path}")
+                        }
+                        #[cfg(windows)]
+                        LapceWorkspaceType::RemoteWSL(wsl) => {
+                            format!("[wsl: {}] {path}", wsl.distro)
+                        }
+                        LapceWorkspaceType::RemoteContainer(host) => {
+                            format!("[container: {}] {path}", host.container)
+                        }
+                    };
+                    stack((
+                        label(move || text.clone())
+                            .on_click_stop(move |_| {
+                                window_command.send(WindowCommand::SetWorkspace {
+                                    workspace: workspace.clone(),
+                                });
+                            })
+                            .style(move |s| {
+                                s.color(
+                                    *config.get().get_color(LapceColor::EDITOR_LINK),
+                                )
+                                .hover(|s| s.cursor(CursorStyle::Pointer))
+                            }),
+                        label(|| "pinned".to_string()).style(move |s| {
+                            s.margin_left(8.0)
+                                .color(
+                                    *config.get().get_color(LapceColor::EDITOR_DIM),
+                                )
+                                .apply_if(!pinned, |s| s.hide())
+                        }),
+                    ))
+                    .style(|s| s.margin_top(6.0).items_center())
+                },
+            )
+            .style(|s| s.flex_col()),
+            welcome_action(
+                "More...",
+                LapceWorkbenchCommand::PaletteWorkspace,
+                workbench_command,
+                config,
+            ),
+        ))
+        .style(|s| s.flex_col()),
+    )
+    .style(move |s| {
+        let is_empty = editor_tabs.with(|tabs| tabs.is_empty());
+        s.absolute()
+            .size_full()
+            .items_center()
+            .justify_center()
+            .apply_if(has_workspace || !is_empty, |s| s.hide())
+    })
+}
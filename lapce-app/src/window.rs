@@ -15,6 +15,7 @@
     db::LapceDb,
     keypress::EventRef,
     listener::Listener,
+    recovery,
     update::ReleaseInfo,
     window_tab::WindowTabData,
     workspace::LapceWorkspace,
@@ -74,6 +75,9 @@ pub struct WindowData {
     pub window_scale: RwSignal<f64>,
     pub config: RwSignal<Arc<LapceConfig>>,
     pub ime_enabled: RwSignal<bool>,
+    /// Set once the user decided what to do with the unsaved changes before the
+    /// window is closed.
+    pub closing: RwSignal<bool>,
     pub common: Rc<WindowCommonData>,
 }
 
@@ -149,6 +153,7 @@ pub fn new(
             app_command,
             config,
             ime_enabled: cx.create_rw_signal(false),
+            closing: cx.create_rw_signal(false),
             common,
         };
 
@@ -249,27 +254,23 @@ pub fn run_window_command(&self, cmd: WindowCommand) {
                 self.active.set(active);
             }
             WindowCommand::CloseWorkspaceTab { index } => {
-                let active = self.active.get_untracked();
-                let index = index.unwrap_or(active);
-                self.window_tabs.update(|window_tabs| {
+                let index = index.unwrap_or_else(|| self.active.get_untracked());
+                let window_tab = self.window_tabs.with_untracked(|window_tabs| {
+                    // The last window tab is the window itself
                     if window_tabs.len() < 2 {
-                        return;
-                    }
-
-                    if index < window_tabs.len() {
-                        let (_, old_window_tab) = window_tabs.remove(index);
-                        old_window_tab.proxy.shutdown();
-                        let db: Arc<LapceDb> = use_context().unwrap();
-                        let _ = db.save_window_tab(old_window_tab);
+                        return None;
                     }
+                    window_tabs
+                        .get(index)
+                        .map(|(_, window_tab)| window_tab.clone())
                 });
-
-                let tabs_len = self.window_tabs.with_untracked(|tabs| tabs.len());
-
-                if active > index && active > 0 {
-                    self.active.set(active - 1);
-                } else if active >= tabs_len.saturating_sub(1) {
-                    self.active.set(tabs_len.saturating_sub(1));
+                if let Some(window_tab) = window_tab {
+                    let window = self.clone();
+                    let closing = window_tab.clone();
+                    self.confirm_close_window_tabs(
+                        vec![window_tab],
+                        Rc::new(move || window.close_window_tab(&closing)),
+                    );
                 }
             }
             WindowCommand::NextWorkspaceTab => {
@@ -296,17 +297,139 @@ pub fn run_window_command(&self, cmd: WindowCommand) {
                     self.active.set(active);
                 }
             }
+            WindowCommand::MoveWorkspaceTabToNewWindow { index } => {
+                let index = index.unwrap_or_else(|| self.active.get_untracked());
+                let window_tab = self.window_tabs.with_untracked(|window_tabs| {
+                    // The last window tab is the window itself
+                    if window_tabs.len() < 2 {
+                        return None;
+                    }
+                    window_tabs
+                        .get(index)
+                        .map(|(_, window_tab)| window_tab.clone())
+                });
+                if let Some(window_tab) = window_tab {
+                    // Save the editors right away so the new window opens them
+                    let db: Arc<LapceDb> = use_context().unwrap();
+                    let _ = db.insert_window_tab(window_tab.clone());
+                    let workspace = (*window_tab.workspace).clone();
+                    // The unsaved changes are recovered in the new window
+                    self.close_window_tab(&window_tab);
+                    self.app_command
+                        .send(AppCommand::NewWindowWithWorkspace(workspace));
+                }
+            }
             WindowCommand::NewWindow => {
                 self.app_command.send(AppCommand::NewWindow);
             }
             WindowCommand::CloseWindow => {
-                self.app_command
-                    .send(AppCommand::CloseWindow(self.window_id));
+                let app_command = self.app_command;
+                let window_id = self.window_id;
+                let closing = self.closing;
+                self.confirm_close(Rc::new(move || {
+                    closing.set(true);
+                    app_command.send(AppCommand::CloseWindow(window_id));
+                }));
+            }
+            WindowCommand::Quit => {
+                self.app_command.send(AppCommand::Quit);
             }
         }
         self.app_command.send(AppCommand::SaveApp);
     }
 
+    fn close_window_tab(&self, window_tab: &Rc<WindowTabData>) {
+        let Some(index) = self.window_tabs.with_untracked(|window_tabs| {
+            window_tabs
+                .iter()
+                .position(|(_, w)| w.window_tab_id == window_tab.window_tab_id)
+        }) else {
+            return;
+        };
+        let active = self.active.get_untracked();
+        self.window_tabs.update(|window_tabs| {
+            let (_, old_window_tab) = window_tabs.remove(index);
+            old_window_tab.proxy.shutdown();
+            let db: Arc<LapceDb> = use_context().unwrap();
+            let _ = db.save_window_tab(old_window_tab);
+        });
+
+        let tabs_len = self.window_tabs.with_untracked(|tabs| tabs.len());
+
+        if active > index && active > 0 {
+            self.active.set(active - 1);
+        } else if active >= tabs_len.saturating_sub(1) {
+            self.active.set(tabs_len.saturating_sub(1));
+        }
+        self.app_command.send(AppCommand::SaveApp);
+    }
+
+    /// Run `close` once the user decided what to do with the unsaved changes of
+    /// every window tab.
+    pub fn confirm_close(&self, close: Rc<dyn Fn()>) {
+        let window_tabs = self.window_tabs.with_untracked(|window_tabs| {
+            window_tabs
+                .iter()
+                .map(|(_, window_tab)| window_tab.clone())
+                .collect()
+        });
+        self.confirm_close_window_tabs(window_tabs, close);
+    }
+
+    /// Back up the unsaved changes right away, unless the user already decided
+    /// what to do with them. A window closed by the system, such as with the
+    /// close button of its title bar, is gone before it could ask about them, so
+    /// they're restored the next time the files are opened instead.
+    pub fn back_up_unsaved(&self) {
+        if self.closing.get_untracked()
+            || self.config.get_untracked().editor.recovery_interval == 0
+        {
+            return;
+        }
+        self.window_tabs.with_untracked(|window_tabs| {
+            for (_, window_tab) in window_tabs.iter() {
+                for (_, doc) in window_tab.unsaved_documents() {
+                    doc.write_recovery();
+                }
+            }
+        });
+        recovery::flush_recovery();
+    }
+
+    /// Ask about the unsaved changes of the window tabs one after the other,
+    /// showing each one that has some while asking, then run `close`.
+    fn confirm_close_window_tabs(
+        &self,
+        mut window_tabs: Vec<Rc<WindowTabData>>,
+        close: Rc<dyn Fn()>,
+    ) {
+        let (window_tab, docs) = loop {
+            if window_tabs.is_empty() {
+                close();
+                return;
+            }
+            let window_tab = window_tabs.remove(0);
+            let docs = window_tab.unsaved_documents();
+            if !docs.is_empty() {
+                break (window_tab, docs);
+            }
+        };
+
+        if let Some(index) = self.window_tabs.with_untracked(|tabs| {
+            tabs.iter()
+                .position(|(_, w)| w.window_tab_id == window_tab.window_tab_id)
+        }) {
+            self.active.set(index);
+        }
+        let window = self.clone();
+        window_tab.main_split.confirm_close(
+            docs,
+            Rc::new(move || {
+                window.confirm_close_window_tabs(window_tabs.clone(), close.clone())
+            }),
+        );
+    }
+
     pub fn key_down<'a>(&self, event: impl Into<EventRef<'a>> + Copy) -> bool {
         let active = self.active.get_untracked();
         let window_tab = self.window_tabs.with_untracked(|window_tabs| {
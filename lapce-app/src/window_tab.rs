@@ -21,18 +21,19 @@
 use indexmap::IndexMap;
 use itertools::Itertools;
 use lapce_core::{
-    command::FocusCommand, directory::Directory, meta, mode::Mode,
-    register::Register,
+    command::FocusCommand, directory::Directory, language::LapceLanguage, meta,
+    mode::Mode, register::Register, spell::Dictionary,
 };
 use lapce_rpc::{
     core::CoreNotification,
     dap_types::RunDebugConfig,
     file::PathObject,
-    proxy::{ProxyRpcHandler, ProxyStatus},
+    plugin::{LanguageServerStatus, PluginCommand, PluginId, PluginStatusItem},
+    proxy::{ProxyRpcHandler, ProxyStatus, PROXY_PROTOCOL_VERSION},
     source_control::FileDiff,
     terminal::TermId,
 };
-use lsp_types::{ProgressParams, ProgressToken, ShowMessageParams};
+use lsp_types::{MessageType, ProgressParams, ProgressToken, ShowMessageParams};
 use serde_json::Value;
 use tracing::{debug, error};
 
@@ -40,15 +41,20 @@
     about::AboutData,
     alert::{AlertBoxData, AlertButton},
     code_action::{CodeActionData, CodeActionStatus},
+    color_picker::ColorPickerData,
     command::{
         CommandExecuted, CommandKind, InternalCommand, LapceCommand,
         LapceWorkbenchCommand, WindowCommand,
     },
     completion::{CompletionData, CompletionStatus},
-    config::LapceConfig,
+    config::{
+        os_theme::{self, OsTheme},
+        LapceConfig,
+    },
+    crash,
     db::LapceDb,
     debug::{DapData, LapceBreakpoint, RunDebugMode, RunDebugProcess},
-    doc::{DocContent, EditorDiagnostic},
+    doc::{DocContent, Document, EditorDiagnostic},
     editor::location::{EditorLocation, EditorPosition},
     editor_tab::EditorTabChild,
     file_explorer::data::FileExplorerData,
@@ -56,19 +62,27 @@
     global_search::GlobalSearchData,
     hover::HoverData,
     id::WindowTabId,
+    input_history::{InputHistoryData, InputHistoryKind},
     keypress::{condition::Condition, EventRef, KeyPressData, KeyPressFocus},
     listener::Listener,
+    localization::{tr, tr_args},
     main_split::{MainSplitData, SplitData, SplitDirection, SplitMoveDirection},
-    palette::{kind::PaletteKind, PaletteData, PaletteStatus},
+    notification::{NotificationAction, NotificationData},
+    palette::{kind::PaletteKind, PaletteData, PaletteStatus, PluginQuickPick},
     panel::{
         data::{default_panel_order, PanelData},
         kind::PanelKind,
         position::PanelContainerPosition,
     },
+    peek::PeekData,
+    perf,
     plugin::PluginData,
     proxy::{new_proxy, path_from_url, ProxyData},
     rename::RenameData,
+    runnable,
     source_control::SourceControlData,
+    spell,
+    task::{is_task_problem, TaskConfig, TaskData},
     terminal::{
         event::{terminal_update_process, TermEvent, TermNotification},
         panel::TerminalPanelData,
@@ -77,12 +91,18 @@
     workspace::{LapceWorkspace, LapceWorkspaceType, WorkspaceInfo},
 };
 
+/// How long after the proxy is connected it has to say which version of the
+/// protocol it speaks. A proxy that doesn't is from before there were versions.
+const PROXY_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Focus {
     Workbench,
     Palette,
     CodeAction,
     Rename,
+    Peek,
+    ColorPicker,
     AboutPopup,
     Panel(PanelKind),
 }
@@ -105,6 +125,8 @@ pub struct WorkProgress {
     pub title: String,
     pub message: Option<String>,
     pub percentage: Option<u32>,
+    /// Whether the user can cancel the work, which is asked of the proxy.
+    pub cancellable: bool,
 }
 
 #[derive(Clone)]
@@ -117,6 +139,9 @@ pub struct CommonData {
     pub hover: HoverData,
     pub register: RwSignal<Register>,
     pub find: Find,
+    pub search_history: InputHistoryData,
+    pub replace_history: InputHistoryData,
+    pub palette_history: InputHistoryData,
     pub workbench_size: RwSignal<Size>,
     pub window_origin: RwSignal<Point>,
     pub internal_command: Listener<InternalCommand>,
@@ -132,6 +157,12 @@ pub struct CommonData {
     pub proxy_status: RwSignal<Option<ProxyStatus>>,
     pub mouse_hover_timer: RwSignal<TimerToken>,
     pub breakpoints: RwSignal<BTreeMap<PathBuf, BTreeMap<usize, LapceBreakpoint>>>,
+    /// The commands that plugins added to the palette.
+    pub plugin_commands: RwSignal<im::Vector<PluginCommand>>,
+    /// The items that plugins show in the status bar.
+    pub plugin_status_items: RwSignal<im::Vector<PluginStatusItem>>,
+    /// The words spell checking accepts, empty when it is off.
+    pub spell_dictionary: RwSignal<Arc<Dictionary>>,
     // the current focused view which will receive keyboard events
     pub keyboard_focus: RwSignal<Option<floem::id::Id>>,
     pub window_common: Rc<WindowCommonData>,
@@ -151,7 +182,10 @@ pub struct WindowTabData {
     pub code_action: RwSignal<CodeActionData>,
     pub source_control: SourceControlData,
     pub rename: RenameData,
+    pub peek: PeekData,
+    pub color_picker: ColorPickerData,
     pub global_search: GlobalSearchData,
+    pub tasks: TaskData,
     pub about_data: AboutData,
     pub alert_data: AlertBoxData,
     pub layout_rect: RwSignal<Rect>,
@@ -161,7 +195,14 @@ pub struct WindowTabData {
     pub set_config: WriteSignal<Arc<LapceConfig>>,
     pub update_in_progress: RwSignal<bool>,
     pub progresses: RwSignal<IndexMap<ProgressToken, WorkProgress>>,
-    pub messages: RwSignal<Vec<(String, ShowMessageParams)>>,
+    /// The language servers plugins started, with the names of the plugins.
+    pub language_servers:
+        RwSignal<IndexMap<PluginId, (String, LanguageServerStatus)>>,
+    pub notification: NotificationData,
+    /// Whether the timings of the performance overlay are shown.
+    pub perf_overlay: RwSignal<bool>,
+    /// The version of the protocol the proxy said it speaks.
+    pub proxy_protocol_version: RwSignal<Option<u32>>,
     pub common: Rc<CommonData>,
 }
 
@@ -257,6 +298,8 @@ pub fn new(
             .unwrap_or_default();
         let mut all_disabled_volts = disabled_volts.clone();
         all_disabled_volts.extend(workspace_disabled_volts.clone());
+        let granted_permissions =
+            db.get_granted_volt_permissions().unwrap_or_default();
 
         let workspace_info = if workspace.path.is_some() {
             db.get_workspace_info(&workspace).ok()
@@ -288,6 +331,7 @@ pub fn new(
         let proxy = new_proxy(
             workspace.clone(),
             all_disabled_volts,
+            granted_permissions.clone(),
             config.plugins.clone(),
             term_tx.clone(),
         );
@@ -325,6 +369,24 @@ pub fn new(
             hover,
             register,
             find,
+            search_history: InputHistoryData::new(
+                cx,
+                InputHistoryKind::Search,
+                workspace.clone(),
+                config,
+            ),
+            replace_history: InputHistoryData::new(
+                cx,
+                InputHistoryKind::Replace,
+                workspace.clone(),
+                config,
+            ),
+            palette_history: InputHistoryData::new(
+                cx,
+                InputHistoryKind::Palette,
+                workspace.clone(),
+                config,
+            ),
             internal_command,
             lapce_command,
             workbench_command,
@@ -340,6 +402,9 @@ pub fn new(
             mouse_hover_timer: cx.create_rw_signal(TimerToken::INVALID),
             window_origin: cx.create_rw_signal(Point::ZERO),
             breakpoints: cx.create_rw_signal(BTreeMap::new()),
+            plugin_commands: cx.create_rw_signal(im::Vector::new()),
+            plugin_status_items: cx.create_rw_signal(im::Vector::new()),
+            spell_dictionary: cx.create_rw_signal(Arc::new(Dictionary::new())),
             keyboard_focus: cx.create_rw_signal(None),
             window_common: window_common.clone(),
         });
@@ -444,18 +509,23 @@ pub fn new(
         }
 
         let rename = RenameData::new(cx, common.clone());
+        let peek = PeekData::new(cx, main_split.clone(), common.clone());
+        let color_picker = ColorPickerData::new(cx, common.clone());
         let global_search = GlobalSearchData::new(cx, main_split.clone());
+        let tasks = TaskData::new(cx, main_split.clone());
 
         let plugin = PluginData::new(
             cx,
             HashSet::from_iter(disabled_volts),
             HashSet::from_iter(workspace_disabled_volts),
+            granted_permissions.into_iter().collect(),
             common.clone(),
         );
 
         {
             let notification = create_signal_from_channel(term_notification_rx);
             let terminal = terminal.clone();
+            let tasks = tasks.clone();
             cx.create_effect(move |_| {
                 notification.with(|notification| {
                     if let Some(notification) = notification.as_ref() {
@@ -466,6 +536,9 @@ pub fn new(
                             TermNotification::RequestPaint => {
                                 view_id.get_untracked().request_paint();
                             }
+                            TermNotification::TaskOutput { term_id, output } => {
+                                tasks.finished(*term_id, output);
+                            }
                         }
                     }
                 });
@@ -488,7 +561,10 @@ pub fn new(
             source_control,
             plugin,
             rename,
+            peek,
+            color_picker,
             global_search,
+            tasks,
             about_data,
             alert_data,
             layout_rect: cx.create_rw_signal(Rect::ZERO),
@@ -498,7 +574,10 @@ pub fn new(
             set_config,
             update_in_progress: cx.create_rw_signal(false),
             progresses: cx.create_rw_signal(IndexMap::new()),
-            messages: cx.create_rw_signal(Vec::new()),
+            language_servers: cx.create_rw_signal(IndexMap::new()),
+            notification: NotificationData::new(cx, common.proxy.clone()),
+            perf_overlay: cx.create_rw_signal(false),
+            proxy_protocol_version: cx.create_rw_signal(None),
             common,
         };
 
@@ -506,6 +585,8 @@ pub fn new(
             let focus = window_tab_data.common.focus;
             let active_editor = window_tab_data.main_split.active_editor;
             let rename_active = window_tab_data.rename.active;
+            let peek = window_tab_data.peek.clone();
+            let color_picker = window_tab_data.color_picker.clone();
             let internal_command = window_tab_data.common.internal_command;
             cx.create_effect(move |_| {
                 let focus = focus.get();
@@ -515,6 +596,52 @@ pub fn new(
                 if focus != Focus::Rename && rename_active.get_untracked() {
                     rename_active.set(false);
                 }
+                if focus != Focus::Peek && peek.active.get_untracked() {
+                    peek.close();
+                }
+                if focus != Focus::ColorPicker && color_picker.active.get_untracked()
+                {
+                    color_picker.close();
+                }
+            });
+        }
+
+        {
+            let window_tab_id = window_tab_data.window_tab_id;
+            let docs = window_tab_data.main_split.docs;
+            cx.create_effect(move |_| {
+                let files = docs.with(|docs| docs.keys().cloned().collect());
+                crash::set_open_files(window_tab_id, files);
+            });
+        }
+
+        {
+            let common = window_tab_data.common.clone();
+            cx.create_effect(move |prev: Option<(bool, String)>| {
+                let settings = common.config.with(|config| {
+                    (
+                        config.editor.spell_check,
+                        config.editor.spell_check_dictionary.clone(),
+                    )
+                });
+                if prev.as_ref() != Some(&settings) {
+                    spell::load_dictionary(&common);
+                }
+                settings
+            });
+        }
+
+        {
+            let spell_dictionary = window_tab_data.common.spell_dictionary;
+            let docs = window_tab_data.main_split.docs;
+            let scratch_docs = window_tab_data.main_split.scratch_docs;
+            cx.create_effect(move |_| {
+                spell_dictionary.track();
+                let docs = docs.get_untracked();
+                let scratch_docs = scratch_docs.get_untracked();
+                for doc in docs.values().chain(scratch_docs.values()) {
+                    doc.check_spelling();
+                }
             });
         }
 
@@ -528,6 +655,7 @@ pub fn new(
         {
             let window_tab_data = window_tab_data.clone();
             window_tab_data.common.workbench_command.listen(move |cmd| {
+                crash::record_command(cmd.into());
                 window_tab_data.run_workbench_command(cmd, None);
             });
         }
@@ -573,6 +701,7 @@ pub fn reload_config(&self) {
     }
 
     pub fn run_lapce_command(&self, cmd: LapceCommand) {
+        crash::record_command(cmd.kind.str());
         match cmd.kind {
             CommandKind::Workbench(command) => {
                 self.run_workbench_command(command, cmd.data);
@@ -621,6 +750,7 @@ pub fn run_workbench_command(
                             let workspace = LapceWorkspace {
                                 kind: LapceWorkspaceType::Local,
                                 path: Some(file.path),
+                                folders: Vec::new(),
                                 last_open: std::time::SystemTime::now()
                                     .duration_since(std::time::UNIX_EPOCH)
                                     .unwrap()
@@ -632,12 +762,36 @@ pub fn run_workbench_command(
                     });
                 }
             }
+            AddFolderToWorkspace => {
+                if !self.workspace.kind.is_remote() {
+                    let window_command = self.common.window_common.window_command;
+                    let workspace = self.workspace.clone();
+                    let options = FileDialogOptions::new().select_directories();
+                    open_file(options, move |file| {
+                        let Some(file) = file else {
+                            return;
+                        };
+                        if workspace.roots().contains(&file.path) {
+                            return;
+                        }
+                        let mut workspace = (*workspace).clone();
+                        if workspace.path.is_none() {
+                            workspace.path = Some(file.path);
+                        } else {
+                            workspace.folders.push(file.path);
+                        }
+                        window_command
+                            .send(WindowCommand::SetWorkspace { workspace });
+                    });
+                }
+            }
             CloseFolder => {
                 if !self.workspace.kind.is_remote() {
                     let window_command = self.common.window_common.window_command;
                     let workspace = LapceWorkspace {
                         kind: LapceWorkspaceType::Local,
                         path: None,
+                        folders: Vec::new(),
                         last_open: 0,
                     };
                     window_command.send(WindowCommand::SetWorkspace { workspace });
@@ -675,34 +829,46 @@ pub fn run_workbench_command(
                     open_uri(path);
                 }
             }
-
-            SaveAll => {
-                self.main_split.editors.with_untracked(|editors| {
-                    let mut paths = HashSet::new();
-                    for (_, editor_data) in editors.iter() {
-                        let should_save =
-                            editor_data.view.doc.with_untracked(|doc| {
-                                let DocContent::File { path, .. } =
-                                    doc.content.get_untracked()
-                                else {
-                                    return false;
-                                };
-
-                                if paths.contains(&path) {
-                                    return false;
-                                }
-
-                                paths.insert(path.clone());
-
-                                true
-                            });
-
-                        if should_save {
-                            editor_data.save(true, || {});
-                        }
+            CompareWithFile => {
+                let Some(right_path) = self.active_file_path() else {
+                    return;
+                };
+                if self.workspace.kind.is_remote() {
+                    // The file dialog only picks local files, which the proxy
+                    // can't read
+                    self.notification.notify(
+                        tr("notification-compare-with-file"),
+                        MessageType::WARNING,
+                        tr("notification-compare-with-file-remote"),
+                        Vec::new(),
+                    );
+                    return;
+                }
+                let internal_command = self.common.internal_command;
+                let options = FileDialogOptions::new();
+                open_file(options, move |file| {
+                    if let Some(file) = file {
+                        internal_command.send(InternalCommand::OpenDiffFiles {
+                            left_path: file.path,
+                            right_path: right_path.clone(),
+                        })
                     }
                 });
             }
+            CompareWithSaved => {
+                if let Some(path) = self.active_file_path() {
+                    self.main_split.open_saved_changes(path);
+                }
+            }
+            CompareWithHead => {
+                if let Some(path) = self.active_file_path() {
+                    self.main_split.open_file_changes(path);
+                }
+            }
+
+            SaveAll => {
+                self.main_split.save_all(true, || {});
+            }
 
             // ==== Configuration / Info Files and Folders ====
             OpenSettings => {
@@ -749,13 +915,10 @@ pub fn run_workbench_command(
             }
             OpenLogFile => {
                 if let Some(dir) = Directory::logs_directory() {
-                    self.open_paths(&[PathObject::from_path(
-                        dir.join(format!(
-                            "lapce.{}.log",
-                            chrono::prelude::Local::now().format("%Y-%m-%d")
-                        )),
-                        false,
-                    )])
+                    self.main_split.open_log_file(dir.join(format!(
+                        "lapce.{}.log",
+                        chrono::prelude::Local::now().format("%Y-%m-%d")
+                    )));
                 }
             }
             OpenLogsDirectory => {
@@ -763,6 +926,18 @@ pub fn run_workbench_command(
                     open_uri(&dir);
                 }
             }
+            TogglePerformanceOverlay => {
+                self.perf_overlay.update(|shown| *shown = !*shown);
+            }
+            DumpPerformanceTrace => {
+                self.dump_performance_trace();
+            }
+            ToggleNotifications => {
+                self.notification.toggle_center();
+            }
+            ClearNotifications => {
+                self.notification.clear();
+            }
             OpenProxyDirectory => {
                 if let Some(dir) = Directory::proxy_directory() {
                     open_uri(&dir);
@@ -778,6 +953,9 @@ pub fn run_workbench_command(
                     open_uri(&dir);
                 }
             }
+            UpdatePlugins => {
+                self.plugin.update_all_volts();
+            }
 
             InstallTheme => {}
             ExportCurrentThemeSettings => {
@@ -820,6 +998,11 @@ pub fn run_workbench_command(
                     .window_command
                     .send(WindowCommand::CloseWorkspaceTab { index: None });
             }
+            MoveWindowTabToNewWindow => {
+                self.common.window_common.window_command.send(
+                    WindowCommand::MoveWorkspaceTabToNewWindow { index: None },
+                );
+            }
             NextWindowTab => {
                 self.common
                     .window_common
@@ -939,7 +1122,10 @@ pub fn run_workbench_command(
                 self.palette.run(PaletteKind::SshHost);
             }
             ConnectWsl => {
-                // TODO:
+                self.palette.run(PaletteKind::WslDistro);
+            }
+            ConnectContainer => {
+                self.palette.run(PaletteKind::Container);
             }
             DisconnectRemote => {
                 self.common.window_common.window_command.send(
@@ -947,6 +1133,7 @@ pub fn run_workbench_command(
                         workspace: LapceWorkspace {
                             kind: LapceWorkspaceType::Local,
                             path: None,
+                            folders: Vec::new(),
                             last_open: 0,
                         },
                     },
@@ -958,9 +1145,31 @@ pub fn run_workbench_command(
             PaletteLine => {
                 self.palette.run(PaletteKind::Line);
             }
+            PaletteGoToLine => {
+                self.palette.run(PaletteKind::GoToLine);
+            }
             Palette => {
                 self.palette.run(PaletteKind::File);
             }
+            PaletteRecentFiles => {
+                // Pressed again while open, it moves on to the next file
+                if self.palette.status.get_untracked() != PaletteStatus::Inactive
+                    && self.palette.input.with_untracked(|i| i.kind)
+                        == PaletteKind::RecentFiles
+                {
+                    self.palette.next();
+                } else {
+                    self.palette.run(PaletteKind::RecentFiles);
+                }
+            }
+            ReopenClosedEditor => {
+                self.main_split.reopen_closed_file();
+            }
+            ClearInputHistory => {
+                self.common.search_history.clear();
+                self.common.replace_history.clear();
+                self.common.palette_history.clear();
+            }
             PaletteSymbol => {
                 self.palette.run(PaletteKind::DocumentSymbol);
             }
@@ -971,21 +1180,64 @@ pub fn run_workbench_command(
             PaletteWorkspace => {
                 self.palette.run(PaletteKind::Workspace);
             }
+            PaletteRemoveRecentWorkspace => {
+                self.palette.run(PaletteKind::RemoveRecentWorkspace);
+            }
+            PaletteTogglePinRecentWorkspace => {
+                self.palette.run(PaletteKind::TogglePinRecentWorkspace);
+            }
+            PinWorkspace | UnpinWorkspace => {
+                let db: Arc<LapceDb> = use_context().unwrap();
+                if let Err(err) =
+                    db.set_workspace_pinned(&self.workspace, cmd == PinWorkspace)
+                {
+                    error!("{:?}", err);
+                }
+            }
             PaletteRunAndDebug => {
                 self.palette.run(PaletteKind::RunAndDebug);
             }
+            PaletteRunTask => {
+                self.palette.run(PaletteKind::Task);
+            }
+            RunFile => {
+                self.run_file();
+            }
             PaletteSCMReferences => {
                 self.palette.run(PaletteKind::SCMReferences);
             }
             ChangeColorTheme => {
                 self.palette.run(PaletteKind::ColorTheme);
             }
+            ToggleDarkLightTheme => {
+                os_theme::toggle_override();
+            }
             ChangeIconTheme => {
                 self.palette.run(PaletteKind::IconTheme);
             }
             ChangeFileLanguage => {
                 self.palette.run(PaletteKind::Language);
             }
+            ChangeFileLineEnding => {
+                self.palette.run(PaletteKind::LineEnding);
+            }
+            ChangeFileIndentation => {
+                self.palette.run(PaletteKind::Indentation);
+            }
+            PaletteClipboardHistory => {
+                self.palette.run(PaletteKind::ClipboardHistory);
+            }
+            KeepSelections => {
+                self.palette.run(PaletteKind::KeepSelections);
+            }
+            RemoveSelections => {
+                self.palette.run(PaletteKind::RemoveSelections);
+            }
+            ToggleReadOnly => {
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    editor.view.doc.get_untracked().toggle_read_only();
+                }
+            }
 
             // ==== Running / Debugging ====
             RunAndDebugRestart => {
@@ -1026,6 +1278,54 @@ pub fn run_workbench_command(
             ZoomReset => {
                 self.common.window_common.window_scale.set(1.0);
             }
+            EditorZoomIn => {
+                let config = self.common.config.get_untracked();
+                if config.editor.font_size() < 32 {
+                    self.set_editor_zoom_level(config.editor.zoom_level + 1);
+                }
+            }
+            EditorZoomOut => {
+                let config = self.common.config.get_untracked();
+                if config.editor.font_size() > 6 {
+                    self.set_editor_zoom_level(config.editor.zoom_level - 1);
+                }
+            }
+            EditorZoomReset => {
+                self.set_editor_zoom_level(0);
+            }
+
+            CloseSplit => {
+                if let Some(editor_tab_id) =
+                    self.main_split.active_editor_tab.get_untracked()
+                {
+                    self.main_split.editor_tab_close(editor_tab_id);
+                }
+            }
+            SplitRotate => {
+                self.main_split.split_rotate_active();
+            }
+            SplitEqualize => {
+                self.main_split.split_equalize();
+            }
+            SplitToggleMaximize => {
+                self.main_split.split_toggle_maximize_active();
+            }
+            SplitIncreaseWidth => {
+                self.main_split
+                    .split_resize_active(SplitDirection::Vertical, true);
+            }
+            SplitDecreaseWidth => {
+                self.main_split
+                    .split_resize_active(SplitDirection::Vertical, false);
+            }
+            SplitIncreaseHeight => {
+                self.main_split
+                    .split_resize_active(SplitDirection::Horizontal, true);
+            }
+            SplitDecreaseHeight => {
+                self.main_split
+                    .split_resize_active(SplitDirection::Horizontal, false);
+            }
 
             ToggleMaximizedPanel => {
                 if let Some(data) = data {
@@ -1109,6 +1409,9 @@ pub fn run_workbench_command(
             ToggleDebugVisual => {
                 self.toggle_panel_visual(PanelKind::Debug);
             }
+            ToggleUndoTreeVisual => {
+                self.toggle_panel_visual(PanelKind::UndoTree);
+            }
             ToggleSearchVisual => {
                 self.toggle_panel_visual(PanelKind::Search);
             }
@@ -1137,6 +1440,15 @@ pub fn run_workbench_command(
             SourceControlCommit => {
                 self.source_control.commit();
             }
+            SourceControlFileHistory => {
+                self.palette.run(PaletteKind::FileHistory);
+            }
+            SourceControlPull => {
+                self.proxy.proxy_rpc.git_pull();
+            }
+            SourceControlPush => {
+                self.proxy.proxy_rpc.git_push();
+            }
             SourceControlCopyActiveFileRemoteUrl => {
                 // TODO:
             }
@@ -1238,7 +1550,10 @@ pub fn run_workbench_command(
             }
             PreviousError => {}
             Quit => {
-                floem::quit_app();
+                self.common
+                    .window_common
+                    .window_command
+                    .send(WindowCommand::Quit);
             }
         }
     }
@@ -1285,6 +1600,12 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
             InternalCommand::OpenFileChanges { path } => {
                 self.main_split.open_file_changes(path);
             }
+            InternalCommand::OpenDiffFiles {
+                left_path,
+                right_path,
+            } => {
+                self.main_split.open_diff_files(left_path, right_path);
+            }
             InternalCommand::GoToLocation { location } => {
                 self.main_split.go_to_location(location, None);
             }
@@ -1325,8 +1646,13 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
                 mouse_click,
                 code_actions,
             } => {
+                let doc = self
+                    .main_split
+                    .active_editor
+                    .get_untracked()
+                    .map(|editor| editor.view.doc.get_untracked());
                 let mut code_action = self.code_action.get_untracked();
-                code_action.show(code_actions, offset, mouse_click);
+                code_action.show(code_actions, offset, mouse_click, doc);
                 self.code_action.set(code_action);
             }
             InternalCommand::RunCodeAction { plugin_id, action } => {
@@ -1359,8 +1685,54 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
                 self.terminal.split_exchange(term_id);
             }
             InternalCommand::RunAndDebug { mode, config } => {
+                if self.should_save_before_run() {
+                    let internal_command = self.common.internal_command;
+                    self.main_split.save_all(true, move || {
+                        internal_command.send(InternalCommand::RunAndDebug {
+                            mode,
+                            config: config.clone(),
+                        });
+                    });
+                    return;
+                }
                 self.run_and_debug(cx, &mode, &config);
             }
+            InternalCommand::RunTask { task } => {
+                if self.should_save_before_run() {
+                    let internal_command = self.common.internal_command;
+                    self.main_split.save_all(true, move || {
+                        internal_command
+                            .send(InternalCommand::RunTask { task: task.clone() });
+                    });
+                    return;
+                }
+                self.run_task(cx, task);
+            }
+            InternalCommand::RunRunnable { mode, runnable } => match mode {
+                RunDebugMode::Run => {
+                    self.common.internal_command.send(InternalCommand::RunTask {
+                        task: runnable::runnable_task(&runnable),
+                    });
+                }
+                RunDebugMode::Debug => {
+                    if self.should_save_before_run() {
+                        let internal_command = self.common.internal_command;
+                        self.main_split.save_all(true, move || {
+                            internal_command.send(InternalCommand::RunRunnable {
+                                mode,
+                                runnable: runnable.clone(),
+                            });
+                        });
+                        return;
+                    }
+                    runnable::debug_runnable(
+                        cx,
+                        self.common.internal_command,
+                        &self.common.proxy,
+                        runnable,
+                    );
+                }
+            },
             InternalCommand::StartRename {
                 path,
                 placeholder,
@@ -1369,6 +1741,31 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
             } => {
                 self.rename.start(path, placeholder, start, position);
             }
+            InternalCommand::ShowPeek { offset, locations } => {
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    self.peek.show(editor, offset, locations);
+                }
+            }
+            InternalCommand::StartColorPicker { offset } => {
+                let Some(editor) = self.main_split.active_editor.get_untracked()
+                else {
+                    return;
+                };
+                let color = editor
+                    .view
+                    .doc
+                    .get_untracked()
+                    .document_colors
+                    .with_untracked(|colors| {
+                        colors
+                            .iter()
+                            .find(|c| c.start <= offset && offset <= c.end)
+                            .copied()
+                    });
+                if let Some(color) = color {
+                    self.color_picker.start(editor, color);
+                }
+            }
             InternalCommand::Search { pattern } => {
                 self.main_split.set_find_pattern(pattern);
             }
@@ -1383,28 +1780,42 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
                 count,
                 mods,
             } => {
-                self.main_split
-                    .find_editor
-                    .run_command(&command, count, mods);
+                let editor = &self.main_split.find_editor;
+                if !self.common.search_history.run_command(editor, &command) {
+                    editor.run_command(&command, count, mods);
+                }
             }
             InternalCommand::ReplaceEditorCommand {
                 command,
                 count,
                 mods,
             } => {
-                self.main_split
-                    .replace_editor
-                    .run_command(&command, count, mods);
+                let editor = &self.main_split.replace_editor;
+                if !self.common.replace_history.run_command(editor, &command) {
+                    editor.run_command(&command, count, mods);
+                }
             }
             InternalCommand::FocusEditorTab { editor_tab_id } => {
                 self.main_split.active_editor_tab.set(Some(editor_tab_id));
             }
             InternalCommand::SetColorTheme { name, save } => {
                 if save {
+                    // While following the OS the theme for its current mode is set
+                    let key = match self
+                        .common
+                        .config
+                        .with_untracked(|config| config.core.follow_os_theme)
+                        .then(os_theme::current)
+                        .flatten()
+                    {
+                        Some(OsTheme::Dark) => "dark-color-theme",
+                        Some(OsTheme::Light) => "light-color-theme",
+                        None => "color-theme",
+                    };
                     // The config file is watched
                     LapceConfig::update_file(
                         "core",
-                        "color-theme",
+                        key,
                         toml_edit::Value::from(name),
                     );
                 } else {
@@ -1458,8 +1869,15 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
             InternalCommand::HideAlert => {
                 self.alert_data.active.set(false);
             }
-            InternalCommand::SaveScratchDoc { doc } => {
-                self.main_split.save_scratch_doc(doc);
+            InternalCommand::ShowNotification {
+                title,
+                level,
+                message,
+            } => {
+                self.notification.notify(title, level, message, Vec::new());
+            }
+            InternalCommand::SaveDocAs { doc } => {
+                self.main_split.save_doc_as(doc);
             }
             InternalCommand::UpdateProxyStatus { status } => {
                 self.common.proxy_status.set(Some(status));
@@ -1489,6 +1907,7 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
                             Focus::Workbench
                                 | Focus::Palette
                                 | Focus::Panel(PanelKind::Plugin)
+                                | Focus::Panel(PanelKind::FileExplorer)
                                 | Focus::Panel(PanelKind::Search)
                                 | Focus::Panel(PanelKind::SourceControl)
                         ) {
@@ -1515,7 +1934,24 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
         let cx = self.scope;
         match rpc {
             CoreNotification::ProxyStatus { status } => {
+                let reconnected = matches!(status, ProxyStatus::Connected)
+                    && matches!(
+                        self.common.proxy_status.get_untracked(),
+                        Some(ProxyStatus::Reconnecting | ProxyStatus::Disconnected)
+                    );
                 self.common.proxy_status.set(Some(status.to_owned()));
+                if reconnected {
+                    self.main_split.restore_buffers();
+                }
+                if matches!(status, ProxyStatus::Connected) {
+                    self.expect_proxy_handshake();
+                }
+            }
+            CoreNotification::ProxyHandshake { protocol_version } => {
+                self.proxy_protocol_version.set(Some(*protocol_version));
+                if *protocol_version != PROXY_PROTOCOL_VERSION {
+                    self.proxy_version_mismatch(Some(*protocol_version));
+                }
             }
             CoreNotification::DiffInfo { diff } => {
                 self.source_control.branch.set(diff.head.clone());
@@ -1550,6 +1986,7 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
                 resp,
                 plugin_id,
             } => {
+                let mut insert_single = None;
                 self.common.completion.update(|completion| {
                     completion.receive(*request_id, input, resp, *plugin_id);
 
@@ -1566,11 +2003,29 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
                             &editor_data.view,
                             cursor_offset,
                         );
+                        if completion.auto_insert
+                            && completion.filtered_items.len() == 1
+                        {
+                            insert_single = Some(editor_data);
+                        }
                     }
                 });
+                if let Some(editor_data) = insert_single {
+                    editor_data.select_completion();
+                }
             }
             CoreNotification::PublishDiagnostics { diagnostics } => {
                 let path = path_from_url(&diagnostics.uri);
+                let data = self.main_split.get_diagnostic_data(&path);
+                // The problems found by tasks stay until the task runs again
+                let task_problems: Vec<EditorDiagnostic> =
+                    data.diagnostics.with_untracked(|existing| {
+                        existing
+                            .iter()
+                            .filter(|d| is_task_problem(&d.diagnostic))
+                            .cloned()
+                            .collect()
+                    });
                 let diagnostics: im::Vector<EditorDiagnostic> = diagnostics
                     .diagnostics
                     .iter()
@@ -1578,13 +2033,11 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
                         range: (0, 0),
                         diagnostic: d.clone(),
                     })
+                    .chain(task_problems)
                     .sorted_by_key(|d| d.diagnostic.range.start)
                     .collect();
 
-                self.main_split
-                    .get_diagnostic_data(&path)
-                    .diagnostics
-                    .set(diagnostics);
+                data.diagnostics.set(diagnostics);
 
                 // inform the document about the diagnostics
                 if let Some(doc) = self
@@ -1636,6 +2089,9 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
             CoreNotification::OpenPaths { paths } => {
                 self.open_paths(paths);
             }
+            CoreNotification::OpenUntitled { content } => {
+                self.main_split.new_file_with_content(content.clone());
+            }
             CoreNotification::DapContinued { dap_id } => {
                 self.terminal.dap_continued(dap_id);
             }
@@ -1685,12 +2141,29 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
             CoreNotification::VoltRemoved { volt, .. } => {
                 self.plugin.volt_removed(volt);
             }
+            CoreNotification::VoltPermissionsRequested { volt } => {
+                self.plugin.ask_for_permissions(volt.clone());
+            }
             CoreNotification::WorkDoneProgress { progress } => {
                 self.update_progress(progress);
             }
             CoreNotification::ShowMessage { title, message } => {
                 self.show_message(title, message);
             }
+            CoreNotification::ShowMessageRequest {
+                id,
+                title,
+                message,
+                actions,
+            } => {
+                self.notification.notify_request(
+                    *id,
+                    title.clone(),
+                    message.typ,
+                    message.message.clone(),
+                    actions.clone(),
+                );
+            }
             CoreNotification::Log { level, message } => {
                 match level.as_str() {
                     "TRACE" => {
@@ -1713,8 +2186,46 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
                     }
                 };
             }
-            CoreNotification::WorkspaceFileChange => {
-                self.file_explorer.reload();
+            CoreNotification::WorkspaceFileChange { paths } => {
+                self.file_explorer.reload_paths(paths);
+            }
+            CoreNotification::IndexedFiles { items } => {
+                self.palette.add_indexed_files(items.clone());
+            }
+            CoreNotification::PluginCommands { volt, commands } => {
+                self.common.plugin_commands.update(|all| {
+                    all.retain(|c| &c.volt != volt);
+                    all.extend(commands.iter().cloned());
+                });
+            }
+            CoreNotification::PluginStatusItems { volt, items } => {
+                self.common.plugin_status_items.update(|all| {
+                    all.retain(|i| &i.volt != volt);
+                    all.extend(items.iter().cloned());
+                });
+            }
+            CoreNotification::LanguageServerStatus {
+                plugin_id,
+                name,
+                status,
+            } => {
+                self.language_servers.update(|servers| {
+                    servers.insert(*plugin_id, (name.clone(), *status));
+                });
+            }
+            CoreNotification::ShowQuickPick {
+                id,
+                placeholder,
+                items,
+            } => {
+                self.palette.run_plugin_quick_pick(PluginQuickPick {
+                    id: *id,
+                    placeholder: placeholder.clone(),
+                    items: items.clone(),
+                });
+            }
+            CoreNotification::ApplyWorkspaceEdit { edit } => {
+                self.main_split.apply_workspace_edit(edit);
             }
             _ => {}
         }
@@ -1736,6 +2247,8 @@ pub fn key_down<'a>(&self, event: impl Into<EventRef<'a>> + Copy) -> bool {
                 keypress.key_down(event, &code_action)
             }
             Focus::Rename => keypress.key_down(event, &self.rename),
+            Focus::Peek => keypress.key_down(event, &self.peek),
+            Focus::ColorPicker => keypress.key_down(event, &self.color_picker),
             Focus::AboutPopup => keypress.key_down(event, &self.about_data),
             Focus::Panel(PanelKind::Terminal) => {
                 self.terminal.key_down(event, &keypress)
@@ -1746,6 +2259,9 @@ pub fn key_down<'a>(&self, event: impl Into<EventRef<'a>> + Copy) -> bool {
             Focus::Panel(PanelKind::Plugin) => {
                 keypress.key_down(event, &self.plugin)
             }
+            Focus::Panel(PanelKind::FileExplorer) => {
+                keypress.key_down(event, &self.file_explorer)
+            }
             Focus::Panel(PanelKind::SourceControl) => {
                 keypress.key_down(event, &self.source_control)
             }
@@ -1964,6 +2480,84 @@ pub fn rename_origin(&self) -> Point {
         origin
     }
 
+    /// Below the line the peek window was opened on, or above it if there is no
+    /// room below.
+    pub fn peek_origin(&self) -> Point {
+        let config = self.common.config.get();
+        if !self.peek.active.get() {
+            return Point::ZERO;
+        }
+
+        let tab_size = self.layout_rect.get().size();
+        let peek_size = self.peek.layout_rect.get().size();
+
+        let Some(editor) = self.peek.source.get_untracked() else {
+            return Point::ZERO;
+        };
+
+        let (window_origin, viewport, view) =
+            (editor.window_origin, editor.viewport, editor.view.clone());
+
+        let (_point_above, point_below) =
+            view.points_of_offset(self.peek.offset.get_untracked());
+
+        let window_origin =
+            window_origin.get() - self.common.window_origin.get().to_vec2();
+        let viewport = viewport.get();
+
+        let mut origin = window_origin + Vec2::new(0.0, point_below.y - viewport.y0);
+
+        if origin.y + peek_size.height > tab_size.height {
+            origin.y =
+                origin.y - config.editor.line_height() as f64 - peek_size.height;
+        }
+        if origin.y <= 0.0 {
+            origin.y = 0.0;
+        }
+
+        origin
+    }
+
+    pub fn color_picker_origin(&self) -> Point {
+        let config = self.common.config.get();
+        if !self.color_picker.active.get() {
+            return Point::ZERO;
+        }
+
+        let tab_size = self.layout_rect.get().size();
+        let picker_size = self.color_picker.layout_rect.get().size();
+
+        let Some(editor) = self.color_picker.editor.get_untracked() else {
+            return Point::ZERO;
+        };
+
+        let (window_origin, viewport, view) =
+            (editor.window_origin, editor.viewport, editor.view.clone());
+
+        let (_point_above, point_below) =
+            view.points_of_offset(self.color_picker.color.get_untracked().start);
+
+        let window_origin =
+            window_origin.get() - self.common.window_origin.get().to_vec2();
+        let viewport = viewport.get();
+
+        let mut origin = window_origin
+            + Vec2::new(point_below.x - viewport.x0, point_below.y - viewport.y0);
+
+        if origin.y + picker_size.height > tab_size.height {
+            origin.y =
+                origin.y - config.editor.line_height() as f64 - picker_size.height;
+        }
+        if origin.x + picker_size.width + 1.0 > tab_size.width {
+            origin.x = tab_size.width - picker_size.width - 1.0;
+        }
+        if origin.x <= 0.0 {
+            origin.x = 0.0;
+        }
+
+        origin
+    }
+
     /// Get the mode for the current editor or terminal
     pub fn mode(&self) -> Mode {
         if self.common.config.get().core.modal {
@@ -1996,7 +2590,8 @@ fn toggle_panel_focus(&self, kind: PanelKind) {
             PanelKind::FileExplorer
             | PanelKind::Plugin
             | PanelKind::Problem
-            | PanelKind::Debug => {
+            | PanelKind::Debug
+            | PanelKind::UndoTree => {
                 // Some panels don't accept focus (yet). Fall back to visibility check
                 // in those cases.
                 self.panel.is_panel_visible(&kind)
@@ -2114,7 +2709,7 @@ fn run_in_terminal(
         mode: &RunDebugMode,
         config: &RunDebugConfig,
         from_dap: bool,
-    ) {
+    ) -> TermId {
         // if not from dap, then run prelaunch first
         let is_prelaunch = !from_dap;
         let term_id = if let Some(terminal) =
@@ -2156,6 +2751,117 @@ fn run_in_terminal(
         if !self.panel.is_panel_visible(&PanelKind::Terminal) {
             self.panel.show_panel(&PanelKind::Terminal);
         }
+
+        term_id
+    }
+
+    /// Zoom the editor font in or out right away, and save the zoom level so
+    /// that it's kept the next time.
+    fn set_editor_zoom_level(&self, zoom_level: isize) {
+        let mut config = self.common.config.get_untracked();
+        Arc::make_mut(&mut config).set_editor_zoom_level(zoom_level);
+        self.set_config.set(config);
+        // The config file is watched
+        if zoom_level == 0 {
+            LapceConfig::reset_setting("editor", "zoom-level");
+        } else {
+            LapceConfig::update_file(
+                "editor",
+                "zoom-level",
+                toml_edit::Value::from(zoom_level as i64),
+            );
+        }
+    }
+
+    /// Whether the files with unsaved changes are saved before running a task or
+    /// a debug session, which then runs once they are.
+    fn should_save_before_run(&self) -> bool {
+        self.common.config.get_untracked().editor.save_before_run
+            && self.main_split.has_unsaved_files()
+    }
+
+    /// Run a task in a terminal. If the task has a problem matcher, its output
+    /// is collected and the problems are added to the diagnostics when it
+    /// finishes.
+    fn run_task(&self, cx: Scope, task: TaskConfig) {
+        let term_id =
+            self.run_in_terminal(cx, &RunDebugMode::Run, &task.run_config(), false);
+        if task.problem_matcher.is_some() {
+            self.tasks.started(term_id, task);
+        }
+    }
+
+    /// Run the file of the active editor with the interpreter configured for
+    /// its language. The file is saved first if it has changes, and running
+    /// it again reuses the terminal of the previous run.
+    fn run_file(&self) {
+        let Some(editor) = self.main_split.active_editor.get_untracked() else {
+            return;
+        };
+        let doc = editor.view.doc.get_untracked();
+        let Some(path) = doc.content.with_untracked(|c| c.path().cloned()) else {
+            return;
+        };
+        let language = doc.syntax.with_untracked(|s| s.language);
+
+        let config = self.common.config.get_untracked();
+        let interpreter = config
+            .terminal
+            .run_file
+            .iter()
+            .find(|(name, _)| LapceLanguage::from_name(name) == Some(language))
+            .map(|(_, interpreter)| interpreter.clone());
+        let Some(interpreter) = interpreter else {
+            self.show_alert(
+                "Run File".to_string(),
+                format!(
+                    "No interpreter is set for {} in terminal.run-file",
+                    language.name()
+                ),
+                Vec::new(),
+            );
+            return;
+        };
+        let mut interpreter = interpreter.split_whitespace().map(String::from);
+        let Some(program) = interpreter.next() else {
+            return;
+        };
+        let mut args: Vec<String> = interpreter.collect();
+        args.push(path.to_string_lossy().to_string());
+
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let run_config = RunDebugConfig {
+            ty: None,
+            name: format!("Run File {file_name}"),
+            program,
+            args: Some(args),
+            cwd: self
+                .workspace
+                .path
+                .as_ref()
+                .map(|p| p.to_string_lossy().to_string()),
+            env: None,
+            prelaunch: None,
+            debug_command: None,
+            dap_id: Default::default(),
+            collect_output: false,
+        };
+
+        let internal_command = self.common.internal_command;
+        let run = move || {
+            internal_command.send(InternalCommand::RunAndDebug {
+                mode: RunDebugMode::Run,
+                config: run_config.clone(),
+            });
+        };
+        if doc.is_pristine() {
+            run();
+        } else {
+            doc.save(run, || {});
+        }
     }
 
     pub fn open_paths(&self, paths: &[PathObject]) {
@@ -2168,6 +2874,7 @@ pub fn open_paths(&self, paths: &[PathObject]) {
                     workspace: LapceWorkspace {
                         kind: self.workspace.kind.clone(),
                         path: Some(folder.path.clone()),
+                        folders: Vec::new(),
                         last_open: 0,
                     },
                     end: false,
@@ -2199,6 +2906,72 @@ pub fn open_paths(&self, paths: &[PathObject]) {
         }
     }
 
+    /// The path of the file in the active editor, if it is a file.
+    fn active_file_path(&self) -> Option<PathBuf> {
+        let editor = self.main_split.active_editor.get_untracked()?;
+        let doc = editor.view.doc.get_untracked();
+        let content = doc.content.get_untracked();
+        content.path().cloned()
+    }
+
+    /// Tell the user that the proxy speaks another version of the protocol, and
+    /// which side needs updating.
+    /// Treat the proxy as one that can't be talked to if it doesn't say which
+    /// version of the protocol it speaks soon after it is connected.
+    fn expect_proxy_handshake(&self) {
+        if self.proxy_protocol_version.get_untracked().is_some() {
+            return;
+        }
+        let window_tab_data = self.clone();
+        exec_after(PROXY_HANDSHAKE_TIMEOUT, move |_| {
+            if window_tab_data
+                .proxy_protocol_version
+                .try_get_untracked()
+                .is_some_and(|version| version.is_none())
+            {
+                window_tab_data.proxy_version_mismatch(None);
+            }
+        });
+    }
+
+    /// Tell the user the proxy speaks another version of the protocol, or none
+    /// at all when it's from before there were versions.
+    fn proxy_version_mismatch(&self, protocol_version: Option<u32>) {
+        self.common
+            .proxy_status
+            .set(Some(ProxyStatus::Disconnected));
+        let update = if protocol_version.map_or(true, |v| v < PROXY_PROTOCOL_VERSION)
+        {
+            "The proxy is installed again the next time you connect, if it's \
+             from a release of Lapce. Otherwise update it to this version."
+        } else {
+            "Update Lapce to the version of the proxy."
+        };
+        let speaks = match protocol_version {
+            Some(version) => format!("speaks version {version}"),
+            None => "doesn't say which version it speaks".to_string(),
+        };
+        self.show_alert(
+            "Lapce can't talk to its proxy".to_string(),
+            format!(
+                "Lapce speaks version {PROXY_PROTOCOL_VERSION} of the proxy \
+                 protocol, but the proxy on {} {speaks}. {update}",
+                self.workspace.kind
+            ),
+            Vec::new(),
+        );
+    }
+
+    /// The documents with unsaved changes that closing the window tab would
+    /// lose.
+    pub fn unsaved_documents(&self) -> Vec<(String, Rc<Document>)> {
+        let closing = self
+            .main_split
+            .editors
+            .with_untracked(|editors| editors.keys().copied().collect());
+        self.main_split.unsaved_documents(&closing)
+    }
+
     pub fn show_alert(&self, title: String, msg: String, buttons: Vec<AlertButton>) {
         self.alert_data.title.set(title);
         self.alert_data.msg.set(msg);
@@ -2216,6 +2989,7 @@ fn update_progress(&self, progress: &ProgressParams) {
                         title: progress.title.clone(),
                         message: progress.message.clone(),
                         percentage: progress.percentage,
+                        cancellable: progress.cancellable.unwrap_or(false),
                     };
                     self.progresses.update(|p| {
                         p.insert(token, progress);
@@ -2226,6 +3000,9 @@ fn update_progress(&self, progress: &ProgressParams) {
                         if let Some(progress) = p.get_mut(&token) {
                             progress.message = report.message.clone();
                             progress.percentage = report.percentage;
+                            if let Some(cancellable) = report.cancellable {
+                                progress.cancellable = cancellable;
+                            }
                         }
                     })
                 }
@@ -2238,10 +3015,65 @@ fn update_progress(&self, progress: &ProgressParams) {
         }
     }
 
+    /// Write the timings recorded while the performance overlay was shown to the
+    /// logs directory, in the Chrome tracing format.
+    fn dump_performance_trace(&self) {
+        let Some(dir) = Directory::logs_directory() else {
+            return;
+        };
+        let path = dir.join(format!(
+            "trace.{}.json",
+            chrono::prelude::Local::now().format("%Y-%m-%d-%H%M%S")
+        ));
+        let title = tr("notification-performance-trace");
+        match perf::write_trace(&path) {
+            Ok(0) => {
+                self.notification.notify(
+                    title,
+                    MessageType::WARNING,
+                    tr("notification-performance-trace-empty"),
+                    Vec::new(),
+                );
+            }
+            Ok(n) => {
+                let open = NotificationAction {
+                    text: tr("notification-open-logs-directory"),
+                    action: Rc::new(move || open_uri(&dir)),
+                };
+                self.notification.notify(
+                    title,
+                    MessageType::INFO,
+                    tr_args(
+                        "notification-performance-trace-written",
+                        &[
+                            ("count", &n.to_string()),
+                            ("path", &path.display().to_string()),
+                        ],
+                    ),
+                    vec![open],
+                );
+            }
+            Err(err) => {
+                self.notification.notify(
+                    title,
+                    MessageType::ERROR,
+                    tr_args(
+                        "notification-performance-trace-failed",
+                        &[("error", &err.to_string())],
+                    ),
+                    Vec::new(),
+                );
+            }
+        }
+    }
+
     fn show_message(&self, title: &str, message: &ShowMessageParams) {
-        self.messages.update(|messages| {
-            messages.push((title.to_string(), message.clone()));
-        });
+        self.notification.notify(
+            title,
+            message.typ,
+            message.message.clone(),
+            Vec::new(),
+        );
     }
 }
 
@@ -2252,7 +3084,7 @@ fn blink_cursor(
     config: ReadSignal<Arc<LapceConfig>>,
 ) {
     let blink_interval =
-        config.with_untracked(|config| config.editor.blink_interval());
+        config.with_untracked(|config| config.cursor_blink_interval());
     if blink_interval > 0 && should_blink() {
         let timer_token =
             exec_after(Duration::from_millis(blink_interval), move |timer_token| {
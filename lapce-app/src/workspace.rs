@@ -9,6 +9,21 @@ pub struct SshHost {
     pub user: Option<String>,
     pub host: String,
     pub port: Option<usize>,
+    #[serde(default)]
+    pub auth: SshAuth,
+}
+
+/// How to log in to an SSH host.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum SshAuth {
+    /// Whatever the SSH agent and the SSH config offer.
+    #[default]
+    Agent,
+    /// Only the given private key.
+    KeyFile(PathBuf),
+    /// A password, asked for by the `SSH_ASKPASS` program.
+    Password,
 }
 
 impl SshHost {
@@ -23,7 +38,12 @@ pub fn from_string(s: &str) -> Self {
         let host = splits.next().unwrap().to_string();
         let user = splits.next().map(|s| s.to_string());
         let port = whole_splits.next().and_then(|s| s.parse::<usize>().ok());
-        Self { user, host, port }
+        Self {
+            user,
+            host,
+            port,
+            auth: SshAuth::default(),
+        }
     }
 
     pub fn user_host(&self) -> String {
@@ -48,12 +68,70 @@ fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     }
 }
 
+/// A WSL distro the proxy runs in.
+#[cfg(windows)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub struct WslHost {
+    pub distro: String,
+}
+
+/// A running Docker container the proxy runs in, so that terminals, language
+/// servers and files are the ones of the container.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub struct ContainerHost {
+    pub container: String,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub enum LapceWorkspaceType {
     Local,
     RemoteSSH(SshHost),
     #[cfg(windows)]
-    RemoteWSL,
+    RemoteWSL(WslHost),
+    RemoteContainer(ContainerHost),
+}
+
+impl Serialize for LapceWorkspaceType {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        LapceWorkspaceType::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for LapceWorkspaceType {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        /// WSL workspaces were saved without a distro before one could be
+        /// picked, and are of the default one.
+        #[cfg(windows)]
+        #[derive(Deserialize)]
+        enum OldWorkspaceType {
+            RemoteWSL,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum StoredWorkspaceType {
+            Current(#[serde(with = "LapceWorkspaceType")] LapceWorkspaceType),
+            #[cfg(windows)]
+            Old(OldWorkspaceType),
+        }
+
+        Ok(match StoredWorkspaceType::deserialize(deserializer)? {
+            StoredWorkspaceType::Current(kind) => kind,
+            #[cfg(windows)]
+            StoredWorkspaceType::Old(OldWorkspaceType::RemoteWSL) => {
+                LapceWorkspaceType::RemoteWSL(WslHost {
+                    distro: crate::proxy::wsl::WslDistro::default_name()
+                        .unwrap_or_default(),
+                })
+            }
+        })
+    }
 }
 
 impl LapceWorkspaceType {
@@ -61,17 +139,8 @@ pub fn is_local(&self) -> bool {
         matches!(self, LapceWorkspaceType::Local)
     }
 
-    #[cfg(windows)]
     pub fn is_remote(&self) -> bool {
-        matches!(
-            self,
-            LapceWorkspaceType::RemoteSSH(_) | LapceWorkspaceType::RemoteWSL
-        )
-    }
-
-    #[cfg(not(windows))]
-    pub fn is_remote(&self) -> bool {
-        matches!(self, LapceWorkspaceType::RemoteSSH(_))
+        !self.is_local()
     }
 }
 
@@ -83,7 +152,12 @@ fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
                 write!(f, "ssh://{ssh}")
             }
             #[cfg(windows)]
-            LapceWorkspaceType::RemoteWSL => f.write_str("WSL"),
+            LapceWorkspaceType::RemoteWSL(wsl) => {
+                write!(f, "wsl://{}", wsl.distro)
+            }
+            LapceWorkspaceType::RemoteContainer(host) => {
+                write!(f, "docker://{}", host.container)
+            }
         }
     }
 }
@@ -92,10 +166,22 @@ fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 pub struct LapceWorkspace {
     pub kind: LapceWorkspaceType,
     pub path: Option<PathBuf>,
+    /// The root folders added to the workspace besides `path`.
+    #[serde(default)]
+    pub folders: Vec<PathBuf>,
     pub last_open: u64,
 }
 
 impl LapceWorkspace {
+    /// The workspace folder followed by the other root folders.
+    pub fn roots(&self) -> Vec<PathBuf> {
+        self.path
+            .iter()
+            .chain(self.folders.iter())
+            .cloned()
+            .collect()
+    }
+
     pub fn display(&self) -> Option<String> {
         let path = self.path.as_ref()?;
         let path = path
@@ -109,7 +195,12 @@ pub fn display(&self) -> Option<String> {
                 format!(" [SSH: {}]", ssh.host)
             }
             #[cfg(windows)]
-            LapceWorkspaceType::RemoteWSL => " [WSL]".to_string(),
+            LapceWorkspaceType::RemoteWSL(wsl) => {
+                format!(" [WSL: {}]", wsl.distro)
+            }
+            LapceWorkspaceType::RemoteContainer(host) => {
+                format!(" [Container: {}]", host.container)
+            }
         };
         Some(format!("{path}{remote}"))
     }
@@ -120,6 +211,7 @@ fn default() -> Self {
         Self {
             kind: LapceWorkspaceType::Local,
             path: None,
+            folders: Vec::new(),
             last_open: 0,
         }
     }
@@ -1,5 +1,6 @@
 use std::{
     borrow::Cow,
+    collections::HashMap,
     ops::Range,
     sync::{
         atomic::{self, AtomicU64},
@@ -9,6 +10,8 @@
 
 use lapce_xi_rope::Rope;
 
+use super::rope_text::{RopeText, RopeTextRef};
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum DiffResult<T> {
     Left(T),
@@ -244,3 +247,192 @@ pub fn rope_diff(
 
     Some(changes)
 }
+
+/// Returns the first line of every changed block on one side of the diff. A
+/// removal directly followed by an addition is a single block.
+pub fn diff_hunk_lines(changes: &[DiffLines], is_right: bool) -> Vec<usize> {
+    let mut lines = Vec::new();
+    let mut left_line = 0;
+    let mut right_line = 0;
+    let mut in_hunk = false;
+    for change in changes {
+        match change {
+            DiffLines::Both(info) => {
+                left_line = info.left.end;
+                right_line = info.right.end;
+                in_hunk = false;
+            }
+            DiffLines::Left(range) => {
+                if !in_hunk {
+                    lines.push(if is_right { right_line } else { range.start });
+                }
+                left_line = range.end;
+                in_hunk = true;
+            }
+            DiffLines::Right(range) => {
+                if !in_hunk {
+                    lines.push(if is_right { range.start } else { left_line });
+                }
+                right_line = range.end;
+                in_hunk = true;
+            }
+        }
+    }
+    lines
+}
+
+/// Find the changed block after (or before) `line`, wrapping around at the end
+/// of the document.
+pub fn next_diff_hunk_line(
+    changes: &[DiffLines],
+    is_right: bool,
+    line: usize,
+    forward: bool,
+) -> Option<usize> {
+    let lines = diff_hunk_lines(changes, is_right);
+    if forward {
+        lines
+            .iter()
+            .find(|l| **l > line)
+            .or_else(|| lines.first())
+            .copied()
+    } else {
+        lines
+            .iter()
+            .rev()
+            .find(|l| **l < line)
+            .or_else(|| lines.last())
+            .copied()
+    }
+}
+
+/// The byte ranges that differ between two versions of a line, found by trimming
+/// the common prefix and suffix.
+pub fn line_change_range(left: &str, right: &str) -> (Range<usize>, Range<usize>) {
+    let prefix = left
+        .char_indices()
+        .zip(right.chars())
+        .find(|((_, l), r)| l != r)
+        .map(|((i, _), _)| i)
+        .unwrap_or_else(|| left.len().min(right.len()));
+    let suffix: usize = left[prefix..]
+        .chars()
+        .rev()
+        .zip(right[prefix..].chars().rev())
+        .take_while(|(l, r)| l == r)
+        .map(|(c, _)| c.len_utf8())
+        .sum();
+    (prefix..left.len() - suffix, prefix..right.len() - suffix)
+}
+
+/// For lines that were modified rather than added or removed, the column range
+/// of the part that actually changed, keyed by line, for the left and right side.
+#[allow(clippy::type_complexity)]
+pub fn intra_line_changes(
+    left_rope: &Rope,
+    right_rope: &Rope,
+    changes: &[DiffLines],
+) -> (HashMap<usize, Range<usize>>, HashMap<usize, Range<usize>>) {
+    let left_text = RopeTextRef::new(left_rope);
+    let right_text = RopeTextRef::new(right_rope);
+    let mut left_changes = HashMap::new();
+    let mut right_changes = HashMap::new();
+    for pair in changes.windows(2) {
+        if let [DiffLines::Left(left), DiffLines::Right(right)] = pair {
+            for (left_line, right_line) in left.clone().zip(right.clone()) {
+                let left_content = left_text.line_content(left_line);
+                let right_content = right_text.line_content(right_line);
+                let left_content = left_content.trim_end_matches(['\r', '\n']);
+                let right_content = right_content.trim_end_matches(['\r', '\n']);
+                let (left_range, right_range) =
+                    line_change_range(left_content, right_content);
+                // Nothing in common, the whole line is highlighted already
+                if left_range.len() == left_content.len()
+                    && right_range.len() == right_content.len()
+                {
+                    continue;
+                }
+                if !left_range.is_empty() {
+                    left_changes.insert(left_line, left_range);
+                }
+                if !right_range.is_empty() {
+                    right_changes.insert(right_line, right_range);
+                }
+            }
+        }
+    }
+    (left_changes, right_changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_hunk_lines() {
+        let changes = vec![
+            DiffLines::Both(DiffBothInfo {
+                left: 0..3,
+                right: 0..3,
+                skip: None,
+            }),
+            DiffLines::Left(3..4),
+            DiffLines::Right(3..5),
+            DiffLines::Both(DiffBothInfo {
+                left: 4..10,
+                right: 5..11,
+                skip: None,
+            }),
+            DiffLines::Left(10..12),
+            DiffLines::Both(DiffBothInfo {
+                left: 12..13,
+                right: 11..12,
+                skip: None,
+            }),
+            DiffLines::Right(12..13),
+        ];
+        assert_eq!(diff_hunk_lines(&changes, true), vec![3, 11, 12]);
+        assert_eq!(diff_hunk_lines(&changes, false), vec![3, 10, 13]);
+
+        assert_eq!(next_diff_hunk_line(&changes, true, 0, true), Some(3));
+        assert_eq!(next_diff_hunk_line(&changes, true, 3, true), Some(11));
+        assert_eq!(next_diff_hunk_line(&changes, true, 12, true), Some(3));
+        assert_eq!(next_diff_hunk_line(&changes, true, 11, false), Some(3));
+        assert_eq!(next_diff_hunk_line(&changes, true, 3, false), Some(12));
+        assert_eq!(next_diff_hunk_line(&[], true, 3, false), None);
+    }
+
+    #[test]
+    fn test_line_change_range() {
+        assert_eq!(line_change_range("let a = 1;", "let b = 1;"), (4..5, 4..5));
+        assert_eq!(line_change_range("foo(a)", "foo(a, b)"), (5..5, 5..8));
+        assert_eq!(line_change_range("aaa", "aa"), (2..3, 2..2));
+        assert_eq!(line_change_range("héllo", "hallo"), (1..3, 1..2));
+        assert_eq!(line_change_range("same", "same"), (4..4, 4..4));
+    }
+
+    #[test]
+    fn test_intra_line_changes() {
+        let left = Rope::from("a\nlet x = 1;\nb\n");
+        let right = Rope::from("a\nlet y = 1;\nb\n");
+        let changes = vec![
+            DiffLines::Both(DiffBothInfo {
+                left: 0..1,
+                right: 0..1,
+                skip: None,
+            }),
+            DiffLines::Left(1..2),
+            DiffLines::Right(1..2),
+            DiffLines::Both(DiffBothInfo {
+                left: 2..4,
+                right: 2..4,
+                skip: None,
+            }),
+        ];
+        let (left_changes, right_changes) =
+            intra_line_changes(&left, &right, &changes);
+        assert_eq!(left_changes.get(&1), Some(&(4..5)));
+        assert_eq!(right_changes.get(&1), Some(&(4..5)));
+        assert_eq!(left_changes.len(), 1);
+    }
+}
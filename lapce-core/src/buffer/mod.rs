@@ -6,6 +6,7 @@
         atomic::{self, AtomicU64},
         Arc,
     },
+    time::{Duration, Instant},
 };
 
 use lapce_xi_rope::{
@@ -62,6 +63,18 @@ struct Revision {
     edit: Contents,
     cursor_before: Option<CursorMode>,
     cursor_after: Option<CursorMode>,
+    /// Where the editor the edit was made in was scrolled to.
+    scroll: Option<(f64, f64)>,
+}
+
+/// An undo group in the history of a buffer. Making an edit after undoing some
+/// starts a new branch, so the groups make up a tree.
+#[derive(Clone, Debug)]
+pub struct UndoGroup {
+    /// The group the edit was made on top of, `None` for the content the buffer
+    /// was opened with.
+    pub parent: Option<usize>,
+    pub time: Instant,
 }
 
 #[derive(Debug, Clone)]
@@ -83,12 +96,21 @@ pub struct Buffer {
     cur_undo: usize,
     undos: BTreeSet<usize>,
     undo_group_id: usize,
+    /// Indexed by the id of the group.
+    undo_groups: Vec<UndoGroup>,
+    /// The groups that are done, with the current one at `cur_undo - 1` and the
+    /// ones that can be redone after it.
     live_undos: Vec<usize>,
+    /// The group that was current when the buffer was last saved.
+    pristine_undo_group: usize,
     deletes_from_union: Subset,
     undone_groups: BTreeSet<usize>,
     tombstones: Rope,
     this_edit_type: EditType,
     last_edit_type: EditType,
+    last_edit_time: Option<Instant>,
+    /// Edits further apart than this aren't undone together.
+    undo_group_timeout: Option<Duration>,
 
     indent_style: IndentStyle,
 
@@ -120,17 +142,25 @@ pub fn new(text: &str) -> Self {
                 },
                 cursor_before: None,
                 cursor_after: None,
+                scroll: None,
             }],
             cur_undo: 1,
             undos: BTreeSet::new(),
             undo_group_id: 1,
+            undo_groups: vec![UndoGroup {
+                parent: None,
+                time: Instant::now(),
+            }],
             live_undos: vec![0],
+            pristine_undo_group: 0,
             deletes_from_union: Subset::new(text.len()),
             undone_groups: BTreeSet::new(),
             tombstones: Rope::default(),
 
             this_edit_type: EditType::Other,
             last_edit_type: EditType::Other,
+            last_edit_time: None,
+            undo_group_timeout: None,
             indent_style: IndentStyle::DEFAULT_INDENT,
 
             max_len: 0,
@@ -146,6 +176,16 @@ pub fn rev(&self) -> u64 {
     /// Mark the buffer as pristine (aka 'saved')
     pub fn set_pristine(&mut self) {
         self.pristine_rev_id = self.rev();
+        self.pristine_undo_group = self.current_undo_group();
+        // So that what is typed after saving can be undone back to the save
+        self.reset_edit_type();
+    }
+
+    /// Mark the buffer as having unsaved changes, for a change that is saved with
+    /// it but isn't in its text, like the line ending.
+    pub fn set_dirty(&mut self) {
+        // No revision has this id, so no revision is equivalent to it
+        self.pristine_rev_id = u64::MAX;
     }
 
     pub fn is_pristine(&self) -> bool {
@@ -164,6 +204,21 @@ pub fn set_cursor_after(&mut self, cursor: CursorMode) {
         }
     }
 
+    pub fn set_scroll(&mut self, scroll: (f64, f64)) {
+        if let Some(rev) = self.revs.last_mut() {
+            rev.scroll = Some(scroll);
+        }
+    }
+
+    /// Where the editor was scrolled to when the edits that were last undone or
+    /// redone were made.
+    pub fn undo_scroll(&self) -> Option<(f64, f64)> {
+        self.revs
+            .last()
+            .filter(|rev| matches!(rev.edit, Contents::Undo { .. }))
+            .and_then(|rev| rev.scroll)
+    }
+
     fn is_equivalent_revision(&self, base_rev: u64, other_rev: u64) -> bool {
         let base_subset = self
             .find_rev(base_rev)
@@ -253,6 +308,24 @@ pub fn init_content(&mut self, content: Rope) {
         self.set_pristine();
     }
 
+    /// Add more of the content that is being loaded to the end. Like
+    /// [`Buffer::init_content`], this isn't an edit that can be undone.
+    pub fn append_content(&mut self, content: Rope) -> InvalLines {
+        let len = self.text.len();
+        let delta = Delta::simple_edit(Interval::new(len, len), content, len);
+        let (new_rev, new_text, new_tombstones, new_deletes_from_union, _) =
+            self.mk_new_rev(0, delta.clone());
+        let inval_lines = self.apply_edit(
+            &delta,
+            new_rev,
+            new_text,
+            new_tombstones,
+            new_deletes_from_union,
+        );
+        self.set_pristine();
+        inval_lines
+    }
+
     pub fn reload(
         &mut self,
         content: Rope,
@@ -277,10 +350,37 @@ pub fn indent_unit(&self) -> &'static str {
         self.indent_style.as_str()
     }
 
+    pub fn indent_style(&self) -> IndentStyle {
+        self.indent_style
+    }
+
+    /// Indent with `indent_style` instead of what was detected, until the
+    /// indentation is detected again.
+    pub fn set_indent_style(&mut self, indent_style: IndentStyle) {
+        self.indent_style = indent_style;
+    }
+
     pub fn reset_edit_type(&mut self) {
         self.last_edit_type = EditType::Other;
     }
 
+    /// `None` never splits the edits that would be grouped because of a pause.
+    pub fn set_undo_group_timeout(&mut self, timeout: Option<Duration>) {
+        self.undo_group_timeout = timeout;
+    }
+
+    /// Start a new undo group with the next edit if the cursor was moved since
+    /// the last one.
+    pub fn reset_edit_type_if_moved(&mut self, cursor: &CursorMode) {
+        let moved = self
+            .revs
+            .last()
+            .map_or(true, |rev| rev.cursor_after.as_ref() != Some(cursor));
+        if moved {
+            self.reset_edit_type();
+        }
+    }
+
     pub fn edit<'a, I, E, S>(
         &mut self,
         edits: I,
@@ -375,14 +475,24 @@ fn apply_edit(
 
     fn calculate_undo_group(&mut self) -> usize {
         let has_undos = !self.live_undos.is_empty();
+        let now = Instant::now();
+        let paused = match (self.last_edit_time, self.undo_group_timeout) {
+            (Some(last), Some(timeout)) => now.duration_since(last) > timeout,
+            _ => false,
+        };
+        self.last_edit_time = Some(now);
         let is_unbroken_group =
-            !self.this_edit_type.breaks_undo_group(self.last_edit_type);
+            !paused && !self.this_edit_type.breaks_undo_group(self.last_edit_type);
 
         if has_undos && is_unbroken_group {
             *self.live_undos.last().unwrap()
         } else {
             let undo_group = self.undo_group_id;
             self.live_undos.truncate(self.cur_undo);
+            self.undo_groups.push(UndoGroup {
+                parent: self.live_undos.last().copied(),
+                time: now,
+            });
             self.live_undos.push(undo_group);
             self.cur_undo += 1;
             self.undo_group_id += 1;
@@ -473,6 +583,7 @@ fn mk_new_rev(
                 },
                 cursor_before: None,
                 cursor_after: None,
+                scroll: None,
             },
             new_text,
             new_tombstones,
@@ -624,6 +735,8 @@ fn compute_undo(&self, groups: &BTreeSet<usize>) -> (Revision, Subset) {
                 cursor.cloned()
             });
 
+        let scroll = self.revs.get(first_candidate).and_then(|rev| rev.scroll);
+
         let deletes_bitxor = self.deletes_from_union.bitxor(&deletes_from_union);
         let max_undo_so_far = self.revs.last().unwrap().max_undo_so_far;
         self.atomic_rev
@@ -638,6 +751,7 @@ fn compute_undo(&self, groups: &BTreeSet<usize>) -> (Revision, Subset) {
                 },
                 cursor_before,
                 cursor_after,
+                scroll,
             },
             deletes_from_union,
         )
@@ -718,6 +832,61 @@ pub fn do_redo(
         Some((delta, inval_lines, edits, cursor_after))
     }
 
+    /// Every undo group, indexed by its id, so the history can be shown as a tree.
+    pub fn undo_groups(&self) -> &[UndoGroup] {
+        &self.undo_groups
+    }
+
+    pub fn current_undo_group(&self) -> usize {
+        self.live_undos[self.cur_undo - 1]
+    }
+
+    pub fn pristine_undo_group(&self) -> usize {
+        self.pristine_undo_group
+    }
+
+    /// Go to the state right after the edits of an undo group were made, which
+    /// undoes and redoes the groups in between, also on other branches.
+    pub fn undo_to_group(
+        &mut self,
+        group: usize,
+    ) -> Option<(RopeDelta, InvalLines, SyntaxEdit, Option<CursorMode>)> {
+        if group >= self.undo_groups.len() || group == self.current_undo_group() {
+            return None;
+        }
+
+        let mut path = vec![group];
+        let mut current = group;
+        while let Some(parent) = self.undo_groups[current].parent {
+            path.push(parent);
+            current = parent;
+        }
+        path.reverse();
+
+        // Keep what can be redone when going back on the current branch
+        if !self.live_undos.starts_with(&path) {
+            self.live_undos = path.clone();
+        }
+        self.cur_undo = path.len();
+        self.undos = (0..self.undo_groups.len())
+            .filter(|group| !path.contains(group))
+            .collect();
+        self.last_edit_type = EditType::Undo;
+
+        let cursor = self
+            .revs
+            .iter()
+            .rev()
+            .find(|rev| match rev.edit {
+                Contents::Edit { undo_group, .. } => undo_group == group,
+                Contents::Undo { .. } => false,
+            })
+            .and_then(|rev| rev.cursor_after.clone());
+        let (delta, inval_lines, edits, _, _) = self.undo(self.undos.clone());
+
+        Some((delta, inval_lines, edits, cursor))
+    }
+
     pub fn move_word_forward(&self, offset: usize) -> usize {
         self.move_n_words_forward(offset, 1)
     }
@@ -736,6 +905,70 @@ pub fn char_at_offset(&self, offset: usize) -> Option<char> {
             .peek_next_codepoint()
     }
 
+    /// The edits that clean up the whitespace of the buffer before it's saved.
+    /// Trailing whitespace is removed from every line except `keep_line`, and
+    /// the end of the file is made to be a single newline.
+    pub fn whitespace_edits_on_save(
+        &self,
+        trim_trailing_whitespace: bool,
+        keep_line: Option<usize>,
+        insert_final_newline: bool,
+        trim_final_newlines: bool,
+    ) -> Vec<(Selection, &'static str)> {
+        let trailing_whitespace = |line: usize| {
+            let start = self.offset_of_line(line);
+            let end = self.line_end_offset(line, true);
+            let content = self.slice_to_cow(start..end);
+            let trimmed = content.trim_end_matches(|c: char| c == ' ' || c == '\t');
+            (start + trimmed.len(), end)
+        };
+        let trim_line =
+            |line: usize| trim_trailing_whitespace && keep_line != Some(line);
+
+        // The whitespace after the last line with content is replaced as a
+        // whole, so that it doesn't overlap with the trimming of each line.
+        let last_content_line = (0..self.num_lines())
+            .rev()
+            .find(|line| !self.is_line_whitespace(*line));
+        let tail = last_content_line.and_then(|line| {
+            let start = if trim_line(line) {
+                trailing_whitespace(line).0
+            } else {
+                self.line_end_offset(line, true)
+            };
+            let current = self.slice_to_cow(start..self.len());
+            let has_newline = current.contains('\n');
+            let new = if trim_final_newlines {
+                if has_newline || insert_final_newline {
+                    "\n"
+                } else {
+                    ""
+                }
+            } else if insert_final_newline && !has_newline {
+                "\n"
+            } else {
+                return None;
+            };
+            (current != new).then_some((line, start, new))
+        });
+
+        let last_line = match tail {
+            Some((line, _, _)) => line,
+            None => self.last_line() + 1,
+        };
+        let mut edits: Vec<(Selection, &'static str)> = (0..last_line)
+            .filter(|line| trim_line(*line))
+            .filter_map(|line| {
+                let (start, end) = trailing_whitespace(line);
+                (start < end).then(|| (Selection::region(start, end), ""))
+            })
+            .collect();
+        if let Some((_, start, new)) = tail {
+            edits.push((Selection::region(start, self.len()), new));
+        }
+        edits
+    }
+
     pub fn previous_unmatched(
         &self,
         syntax: &Syntax,
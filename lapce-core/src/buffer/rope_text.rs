@@ -2,6 +2,8 @@
 
 use lapce_xi_rope::{interval::IntervalBounds, rope::ChunkIter, Cursor, Rope};
 use lsp_types::Position;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar;
 
 use crate::{
     encoding::{offset_utf16_to_utf8, offset_utf8_to_utf16},
@@ -13,6 +15,15 @@
 pub trait RopeText {
     fn text(&self) -> &Rope;
 
+    /// Characters that are part of words besides letters, digits and `_`.
+    fn word_chars(&self) -> &str {
+        ""
+    }
+
+    fn word_cursor(&self, offset: usize) -> WordCursor {
+        WordCursor::new(self.text(), offset).with_word_chars(self.word_chars())
+    }
+
     fn len(&self) -> usize {
         self.text().len()
     }
@@ -94,6 +105,18 @@ fn offset_to_line_col(&self, offset: usize) -> (usize, usize) {
         (line, col)
     }
 
+    /// The column the offset is shown at on its line, which counts grapheme
+    /// clusters rather than bytes, with the wide ones like CJK characters taking
+    /// up two columns.
+    fn offset_to_display_col(&self, offset: usize) -> usize {
+        let offset = offset.min(self.len());
+        let line_start = self.offset_of_line(self.line_of_offset(offset));
+        self.slice_to_cow(line_start..offset)
+            .graphemes(true)
+            .map(grapheme_width)
+            .sum()
+    }
+
     fn offset_of_line_col(&self, line: usize, col: usize) -> usize {
         let mut pos = 0;
         let mut offset = self.offset_of_line(line);
@@ -210,16 +233,16 @@ fn next_grapheme_offset(
     }
 
     fn prev_code_boundary(&self, offset: usize) -> usize {
-        WordCursor::new(self.text(), offset).prev_code_boundary()
+        self.word_cursor(offset).prev_code_boundary()
     }
 
     fn next_code_boundary(&self, offset: usize) -> usize {
-        WordCursor::new(self.text(), offset).next_code_boundary()
+        self.word_cursor(offset).next_code_boundary()
     }
 
     /// Return the previous and end boundaries of the word under cursor.
     fn select_word(&self, offset: usize) -> (usize, usize) {
-        WordCursor::new(self.text(), offset).select_word()
+        self.word_cursor(offset).select_word()
     }
 
     /// Returns the offset of the first non-blank character on the given line.
@@ -368,7 +391,7 @@ fn find_nth_word<F>(
     where
         F: FnMut(&mut WordCursor) -> Option<usize>,
     {
-        let mut cursor = WordCursor::new(self.text(), offset);
+        let mut cursor = self.word_cursor(offset);
         let mut new_offset = offset;
         while count != 0 {
             // FIXME: wait for if-let-chain
@@ -412,21 +435,44 @@ fn move_n_words_backward(
     fn move_word_backward_deletion(&self, offset: usize) -> usize {
         self.find_nth_word(offset, 1, |cursor| cursor.prev_deletion_boundary())
     }
+
+    /// Move over `count` sub-words, the humps of camelCase words and the parts of
+    /// snake_case ones.
+    fn move_n_subwords_forward(&self, offset: usize, count: usize) -> usize {
+        self.find_nth_word(offset, count, |cursor| cursor.next_subword_boundary())
+    }
+
+    fn move_n_subwords_backward(&self, offset: usize, count: usize) -> usize {
+        self.find_nth_word(offset, count, |cursor| cursor.prev_subword_boundary())
+    }
 }
 
 #[derive(Clone)]
 pub struct RopeTextVal {
     pub text: Rope,
+    pub word_chars: String,
 }
 impl RopeTextVal {
     pub fn new(text: Rope) -> Self {
-        Self { text }
+        Self {
+            text,
+            word_chars: String::new(),
+        }
+    }
+
+    pub fn with_word_chars(mut self, word_chars: impl Into<String>) -> Self {
+        self.word_chars = word_chars.into();
+        self
     }
 }
 impl RopeText for RopeTextVal {
     fn text(&self) -> &Rope {
         &self.text
     }
+
+    fn word_chars(&self) -> &str {
+        &self.word_chars
+    }
 }
 impl From<Rope> for RopeTextVal {
     fn from(text: Rope) -> Self {
@@ -453,6 +499,18 @@ fn from(text: &'a Rope) -> Self {
     }
 }
 
+/// The number of columns a grapheme cluster takes up, which is that of its first
+/// character, so that combining marks and the rest of an emoji sequence don't
+/// add to it. Control characters like tabs take up one column.
+pub fn grapheme_width(grapheme: &str) -> usize {
+    grapheme
+        .chars()
+        .next()
+        .and_then(UnicodeWidthChar::width)
+        .unwrap_or(1)
+        .max(1)
+}
+
 /// Joins an iterator of iterators over char indices `(usize, char)` into one
 /// as if they were from a single long string
 /// Assumes the iterators end after the first `None` value
@@ -710,4 +768,19 @@ fn test_is_line_whitespace() {
         assert!(text.is_line_whitespace(0));
         assert!(!text.is_line_whitespace(1));
     }
+
+    #[test]
+    fn test_offset_to_display_col() {
+        let text = Rope::from("a\n中文e\u{301}👍🏽x");
+        let text = RopeTextVal::new(text);
+
+        assert_eq!(text.offset_to_display_col(0), 0);
+        assert_eq!(text.offset_to_display_col(2), 0);
+        // Each ideograph is two columns wide
+        assert_eq!(text.offset_to_display_col(8), 4);
+        // The combining accent is in the same column as the e
+        assert_eq!(text.offset_to_display_col(11), 5);
+        // The skin tone is part of the emoji before it
+        assert_eq!(text.offset_to_display_col(19), 7);
+    }
 }
@@ -1,10 +1,12 @@
 use super::{Buffer, RopeText};
 
 mod editing {
+    use std::time::Duration;
+
     use lapce_xi_rope::Rope;
 
     use super::*;
-    use crate::{editor::EditType, selection::Selection};
+    use crate::{cursor::CursorMode, editor::EditType, selection::Selection};
 
     #[test]
     fn is_pristine() {
@@ -16,6 +18,134 @@ fn is_pristine() {
         buffer.do_undo();
         assert!(buffer.is_pristine());
     }
+
+    #[test]
+    fn set_dirty() {
+        let mut buffer = Buffer::new("");
+        buffer.init_content(Rope::from("abc"));
+        buffer.set_dirty();
+        assert!(!buffer.is_pristine());
+        buffer.set_pristine();
+        assert!(buffer.is_pristine());
+    }
+
+    #[test]
+    fn append_content() {
+        let mut buffer = Buffer::new("");
+        buffer.init_content(Rope::from("abc\n"));
+        let inval_lines = buffer.append_content(Rope::from("def\nghi"));
+        assert_eq!(buffer.to_string(), "abc\ndef\nghi");
+        assert_eq!(buffer.rev(), 2);
+        assert_eq!(inval_lines.start_line, 1);
+        assert_eq!(inval_lines.new_count, 2);
+        assert!(buffer.is_pristine());
+        buffer.edit(&[(Selection::caret(0), "d")], EditType::InsertChars);
+        buffer.do_undo();
+        assert_eq!(buffer.to_string(), "abc\ndef\nghi");
+    }
+
+    fn apply_whitespace_edits(
+        text: &str,
+        keep_line: Option<usize>,
+        insert_final_newline: bool,
+        trim_final_newlines: bool,
+    ) -> String {
+        let mut buffer = Buffer::new(text);
+        let edits = buffer.whitespace_edits_on_save(
+            true,
+            keep_line,
+            insert_final_newline,
+            trim_final_newlines,
+        );
+        buffer.edit(&edits, EditType::Other);
+        buffer.to_string()
+    }
+
+    #[test]
+    fn whitespace_edits_on_save() {
+        assert_eq!(
+            apply_whitespace_edits("a  \nb\t\nc", None, false, false),
+            "a\nb\nc"
+        );
+        assert_eq!(
+            apply_whitespace_edits("a  \nb  \n", Some(1), false, false),
+            "a\nb  \n"
+        );
+        assert_eq!(
+            apply_whitespace_edits("a\nb  ", None, true, false),
+            "a\nb\n"
+        );
+        assert_eq!(
+            apply_whitespace_edits("a\nb \n\n  \n\n", None, false, true),
+            "a\nb\n"
+        );
+        assert_eq!(apply_whitespace_edits("a\nb", None, false, true), "a\nb");
+        assert_eq!(apply_whitespace_edits("a\nb\n", None, true, true), "a\nb\n");
+        assert_eq!(apply_whitespace_edits("", None, true, true), "");
+    }
+
+    fn type_char(buffer: &mut Buffer, offset: usize, c: &str) {
+        let cursor = CursorMode::Insert(Selection::caret(offset));
+        buffer.reset_edit_type_if_moved(&cursor);
+        buffer.edit(&[(Selection::caret(offset), c)], EditType::InsertChars);
+        buffer.set_cursor_before(cursor);
+        buffer.set_cursor_after(CursorMode::Insert(Selection::caret(offset + 1)));
+    }
+
+    #[test]
+    fn undo_groups_typing() {
+        let mut buffer = Buffer::new("");
+        type_char(&mut buffer, 0, "a");
+        type_char(&mut buffer, 1, "b");
+        // The cursor was moved back before typing on
+        type_char(&mut buffer, 0, "c");
+        type_char(&mut buffer, 1, "d");
+        assert_eq!(buffer.to_string(), "cdab");
+        buffer.do_undo();
+        assert_eq!(buffer.to_string(), "ab");
+        buffer.do_undo();
+        assert_eq!(buffer.to_string(), "");
+    }
+
+    #[test]
+    fn undo_group_timeout() {
+        let mut buffer = Buffer::new("");
+        buffer.set_undo_group_timeout(Some(Duration::from_millis(10)));
+        type_char(&mut buffer, 0, "a");
+        type_char(&mut buffer, 1, "b");
+        std::thread::sleep(Duration::from_millis(50));
+        type_char(&mut buffer, 2, "c");
+        buffer.do_undo();
+        assert_eq!(buffer.to_string(), "ab");
+    }
+
+    #[test]
+    fn undo_tree() {
+        let mut buffer = Buffer::new("");
+        buffer.edit(&[(Selection::caret(0), "a")], EditType::Other);
+        buffer.edit(&[(Selection::caret(1), "b")], EditType::Other);
+        buffer.set_pristine();
+        buffer.do_undo();
+        // Branches off after "a"
+        buffer.edit(&[(Selection::caret(1), "c")], EditType::Other);
+        let parents: Vec<_> = buffer
+            .undo_groups()
+            .iter()
+            .map(|group| group.parent)
+            .collect();
+        assert_eq!(parents, vec![None, Some(0), Some(1), Some(1)]);
+        assert_eq!(buffer.to_string(), "ac");
+
+        buffer.undo_to_group(buffer.pristine_undo_group());
+        assert_eq!(buffer.to_string(), "ab");
+        assert!(buffer.is_pristine());
+
+        buffer.undo_to_group(0);
+        assert_eq!(buffer.to_string(), "");
+        // What was undone on the current branch can still be redone
+        buffer.do_redo();
+        assert_eq!(buffer.to_string(), "a");
+    }
 }
 
 mod motion {
@@ -1,3 +1,5 @@
+use unicode_bidi::{bidi_class, BidiClass};
+
 /// Determine whether a character is a line ending.
 #[inline]
 pub fn char_is_line_ending(ch: char) -> bool {
@@ -32,3 +34,17 @@ pub fn char_is_whitespace(ch: char) -> bool {
         _ => false,
     }
 }
+
+/// Determine whether a character is of a right-to-left script, like Arabic
+/// and Hebrew, or forces the text after it to be laid out right to left.
+#[inline]
+pub fn char_is_rtl(ch: char) -> bool {
+    matches!(
+        bidi_class(ch),
+        BidiClass::R
+            | BidiClass::AL
+            | BidiClass::RLE
+            | BidiClass::RLO
+            | BidiClass::RLI
+    )
+}
@@ -0,0 +1,204 @@
+//! Colors written in the text as hex codes or `rgb()` calls, found to show a
+//! swatch for them and written back in the same notation once changed.
+
+use crate::transform::is_identifier_char;
+
+/// How a color is written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorNotation {
+    /// `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`
+    Hex,
+    /// `rgb(r, g, b)` or `rgba(r, g, b, a)`, with the alpha from 0 to 1
+    Rgb,
+}
+
+/// A color in the text, with the range it is written in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TextColor {
+    pub start: usize,
+    pub end: usize,
+    pub rgba: [u8; 4],
+    pub notation: ColorNotation,
+}
+
+/// The colors written in the text. A hex code has to stand on its own, so that
+/// things like `a#bad` or `#define` aren't taken for one, and a short one of
+/// only digits is more likely an issue number like `#123`.
+pub fn find_colors(text: &str) -> Vec<TextColor> {
+    let mut colors = Vec::new();
+    let mut i = 0;
+    while i < text.len() {
+        let rest = &text[i..];
+        let after_word = text[..i]
+            .chars()
+            .next_back()
+            .is_some_and(is_identifier_char);
+        let found = if after_word {
+            None
+        } else if rest.starts_with('#') {
+            parse_hex(rest).filter(|(len, _, _)| {
+                *len > 5 || !rest[1..*len].bytes().all(|b| b.is_ascii_digit())
+            })
+        } else if rest.starts_with("rgb") {
+            parse_rgb(rest)
+        } else {
+            None
+        };
+        if let Some((len, rgba, notation)) = found {
+            colors.push(TextColor {
+                start: i,
+                end: i + len,
+                rgba,
+                notation,
+            });
+            i += len;
+        } else {
+            i += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    colors
+}
+
+/// The color the whole text is, like the text of a color a language server
+/// found, with how it is written.
+pub fn parse_color(text: &str) -> Option<([u8; 4], ColorNotation)> {
+    let (len, rgba, notation) = parse_hex(text).or_else(|| parse_rgb(text))?;
+    (len == text.len()).then_some((rgba, notation))
+}
+
+fn parse_hex(text: &str) -> Option<(usize, [u8; 4], ColorNotation)> {
+    let digits = text.strip_prefix('#')?;
+    let len = digits
+        .find(|c: char| !c.is_ascii_hexdigit())
+        .unwrap_or(digits.len());
+    if digits[len..].starts_with(is_identifier_char) {
+        return None;
+    }
+    let digits = &digits[..len];
+    let channel = |i: usize, width: usize| {
+        let value = u8::from_str_radix(&digits[i * width..(i + 1) * width], 16);
+        value.map(|v| if width == 1 { v * 17 } else { v })
+    };
+    let rgba = match len {
+        3 | 4 => {
+            let alpha = if len == 4 { channel(3, 1).ok()? } else { 255 };
+            [
+                channel(0, 1).ok()?,
+                channel(1, 1).ok()?,
+                channel(2, 1).ok()?,
+                alpha,
+            ]
+        }
+        6 | 8 => {
+            let alpha = if len == 8 { channel(3, 2).ok()? } else { 255 };
+            [
+                channel(0, 2).ok()?,
+                channel(1, 2).ok()?,
+                channel(2, 2).ok()?,
+                alpha,
+            ]
+        }
+        _ => return None,
+    };
+    Some((len + 1, rgba, ColorNotation::Hex))
+}
+
+fn parse_rgb(text: &str) -> Option<(usize, [u8; 4], ColorNotation)> {
+    let args_start = if text.starts_with("rgba(") {
+        5
+    } else if text.starts_with("rgb(") {
+        4
+    } else {
+        return None;
+    };
+    let args_end = args_start + text[args_start..].find(')')?;
+    let args = text[args_start..args_end]
+        .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+        .filter(|arg| !arg.is_empty())
+        .collect::<Vec<_>>();
+    if args.len() != 3 && args.len() != 4 {
+        return None;
+    }
+
+    let mut rgba = [255; 4];
+    for (i, arg) in args.iter().enumerate() {
+        let (value, max) = match arg.strip_suffix('%') {
+            Some(percent) => (percent.parse::<f32>().ok()?, 100.0),
+            None if i == 3 => (arg.parse::<f32>().ok()?, 1.0),
+            None => (arg.parse::<f32>().ok()?, 255.0),
+        };
+        if !(0.0..=max).contains(&value) {
+            return None;
+        }
+        rgba[i] = (value / max * 255.0).round() as u8;
+    }
+    Some((args_end + 1, rgba, ColorNotation::Rgb))
+}
+
+/// Write the color in the notation, leaving out the alpha when it's opaque.
+pub fn format_color(rgba: [u8; 4], notation: ColorNotation) -> String {
+    let [r, g, b, a] = rgba;
+    match notation {
+        ColorNotation::Hex if a == 255 => format!("#{r:02x}{g:02x}{b:02x}"),
+        ColorNotation::Hex => format!("#{r:02x}{g:02x}{b:02x}{a:02x}"),
+        ColorNotation::Rgb if a == 255 => format!("rgb({r}, {g}, {b})"),
+        ColorNotation::Rgb => {
+            let alpha = format!("{:.2}", a as f32 / 255.0);
+            let alpha = alpha.trim_end_matches('0').trim_end_matches('.');
+            format!("rgba({r}, {g}, {b}, {alpha})")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_colors() {
+        let text = "a: #F80; b: rgba(255, 0, 0, 0.5); c#fff #12345 #define #123";
+        let colors = find_colors(text);
+        assert_eq!(
+            colors,
+            vec![
+                TextColor {
+                    start: 3,
+                    end: 7,
+                    rgba: [255, 136, 0, 255],
+                    notation: ColorNotation::Hex,
+                },
+                TextColor {
+                    start: 12,
+                    end: 32,
+                    rgba: [255, 0, 0, 128],
+                    notation: ColorNotation::Rgb,
+                },
+            ]
+        );
+        assert_eq!(
+            parse_color("#11223344"),
+            Some(([17, 34, 51, 68], ColorNotation::Hex))
+        );
+        assert_eq!(parse_color("rgb(1, 2, 3) "), None);
+    }
+
+    #[test]
+    fn test_format_color() {
+        assert_eq!(
+            format_color([255, 136, 0, 255], ColorNotation::Hex),
+            "#ff8800"
+        );
+        assert_eq!(
+            format_color([255, 136, 0, 128], ColorNotation::Hex),
+            "#ff880080"
+        );
+        assert_eq!(
+            format_color([1, 2, 3, 128], ColorNotation::Rgb),
+            "rgba(1, 2, 3, 0.5)"
+        );
+        assert_eq!(
+            format_color([1, 2, 3, 255], ColorNotation::Rgb),
+            "rgb(1, 2, 3)"
+        );
+    }
+}
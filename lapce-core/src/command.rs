@@ -1,6 +1,9 @@
 use strum_macros::{Display, EnumIter, EnumMessage, EnumString, IntoStaticStr};
 
-use crate::movement::{LinePosition, Movement};
+use crate::{
+    movement::{LinePosition, Movement},
+    syntax::SyntaxItem,
+};
 
 #[derive(
     Display,
@@ -14,8 +17,10 @@
     IntoStaticStr,
 )]
 pub enum EditCommand {
+    #[strum(message = "Move Line Up")]
     #[strum(serialize = "move_line_up")]
     MoveLineUp,
+    #[strum(message = "Move Line Down")]
     #[strum(serialize = "move_line_down")]
     MoveLineDown,
     #[strum(serialize = "insert_new_line")]
@@ -52,6 +57,39 @@ pub enum EditCommand {
     #[strum(message = "Join Lines")]
     #[strum(serialize = "join_lines")]
     JoinLines,
+    #[strum(message = "Transpose Characters")]
+    #[strum(serialize = "transpose_characters")]
+    TransposeCharacters,
+    #[strum(message = "Transform to Uppercase")]
+    #[strum(serialize = "transform_to_uppercase")]
+    TransformToUppercase,
+    #[strum(message = "Transform to Lowercase")]
+    #[strum(serialize = "transform_to_lowercase")]
+    TransformToLowercase,
+    #[strum(message = "Transform to Title Case")]
+    #[strum(serialize = "transform_to_title_case")]
+    TransformToTitleCase,
+    #[strum(message = "Transform to Snake Case")]
+    #[strum(serialize = "transform_to_snake_case")]
+    TransformToSnakeCase,
+    #[strum(message = "Transform to Camel Case")]
+    #[strum(serialize = "transform_to_camel_case")]
+    TransformToCamelCase,
+    #[strum(message = "Sort Lines Ascending")]
+    #[strum(serialize = "sort_lines_ascending")]
+    SortLinesAscending,
+    #[strum(message = "Sort Lines Descending")]
+    #[strum(serialize = "sort_lines_descending")]
+    SortLinesDescending,
+    #[strum(message = "Sort Lines and Remove Duplicates")]
+    #[strum(serialize = "sort_lines_unique")]
+    SortLinesUnique,
+    #[strum(message = "Reverse Lines")]
+    #[strum(serialize = "reverse_lines")]
+    ReverseLines,
+    #[strum(message = "Insert Number Sequence")]
+    #[strum(serialize = "insert_sequence")]
+    InsertSequence,
     #[strum(message = "Indent Line")]
     #[strum(serialize = "indent_line")]
     IndentLine,
@@ -65,6 +103,9 @@ pub enum EditCommand {
     Undo,
     #[strum(serialize = "redo")]
     Redo,
+    #[strum(message = "Undo to Last Save")]
+    #[strum(serialize = "undo_to_last_save")]
+    UndoToLastSave,
     #[strum(message = "Copy")]
     #[strum(serialize = "clipboard_copy")]
     ClipboardCopy,
@@ -74,6 +115,30 @@ pub enum EditCommand {
     #[strum(message = "Paste")]
     #[strum(serialize = "clipboard_paste")]
     ClipboardPaste,
+    #[strum(message = "Paste without Formatting")]
+    #[strum(serialize = "clipboard_paste_without_formatting")]
+    ClipboardPasteWithoutFormatting,
+    #[strum(message = "Kill Line")]
+    #[strum(serialize = "kill_line")]
+    KillLine,
+    #[strum(message = "Kill Region")]
+    #[strum(serialize = "kill_region")]
+    KillRegion,
+    #[strum(message = "Copy Region to Kill Ring")]
+    #[strum(serialize = "kill_ring_save")]
+    KillRingSave,
+    #[strum(message = "Yank from Kill Ring")]
+    #[strum(serialize = "kill_ring_yank")]
+    KillRingYank,
+    #[strum(message = "Replace Yank with Previous Kill")]
+    #[strum(serialize = "kill_ring_yank_pop")]
+    KillRingYankPop,
+    #[strum(message = "Set Mark")]
+    #[strum(serialize = "set_mark")]
+    SetMark,
+    #[strum(message = "Exchange Point and Mark")]
+    #[strum(serialize = "exchange_point_and_mark")]
+    ExchangePointAndMark,
     #[strum(serialize = "yank")]
     Yank,
     #[strum(serialize = "paste")]
@@ -97,8 +162,10 @@ pub enum EditCommand {
     ToggleLinewiseVisualMode,
     #[strum(serialize = "toggle_blockwise_visual_mode")]
     ToggleBlockwiseVisualMode,
+    #[strum(message = "Duplicate Line Up")]
     #[strum(serialize = "duplicate_line_up")]
     DuplicateLineUp,
+    #[strum(message = "Duplicate Line Down")]
     #[strum(serialize = "duplicate_line_down")]
     DuplicateLineDown,
 }
@@ -108,6 +175,9 @@ pub fn not_changing_buffer(&self) -> bool {
         matches!(
             self,
             &EditCommand::ClipboardCopy
+                | &EditCommand::KillRingSave
+                | &EditCommand::SetMark
+                | &EditCommand::ExchangePointAndMark
                 | &EditCommand::Yank
                 | &EditCommand::NormalMode
                 | &EditCommand::InsertMode
@@ -147,6 +217,12 @@ pub enum MoveCommand {
     WordForward,
     #[strum(serialize = "word_end_forward")]
     WordEndForward,
+    #[strum(message = "Sub-word Backward")]
+    #[strum(serialize = "subword_backward")]
+    SubwordBackward,
+    #[strum(message = "Sub-word Forward")]
+    #[strum(serialize = "subword_forward")]
+    SubwordForward,
     #[strum(message = "Document Start")]
     #[strum(serialize = "document_start")]
     DocumentStart,
@@ -179,6 +255,24 @@ pub enum MoveCommand {
     #[strum(message = "Paragraph backward")]
     #[strum(serialize = "paragraph_backward")]
     ParagraphBackward,
+    #[strum(message = "Go to Next Function")]
+    #[strum(serialize = "next_function")]
+    NextFunction,
+    #[strum(message = "Go to Previous Function")]
+    #[strum(serialize = "previous_function")]
+    PreviousFunction,
+    #[strum(message = "Go to Next Class")]
+    #[strum(serialize = "next_class")]
+    NextClass,
+    #[strum(message = "Go to Previous Class")]
+    #[strum(serialize = "previous_class")]
+    PreviousClass,
+    #[strum(message = "Go to Next Top-Level Item")]
+    #[strum(serialize = "next_top_level_item")]
+    NextTopLevelItem,
+    #[strum(message = "Go to Previous Top-Level Item")]
+    #[strum(serialize = "previous_top_level_item")]
+    PreviousTopLevelItem,
 }
 
 impl MoveCommand {
@@ -205,6 +299,8 @@ pub fn to_movement(&self, count: Option<usize>) -> Movement {
             WordBackward => Movement::WordBackward,
             WordForward => Movement::WordForward,
             WordEndForward => Movement::WordEndForward,
+            SubwordBackward => Movement::SubwordBackward,
+            SubwordForward => Movement::SubwordForward,
             MatchPairs => Movement::MatchPairs,
             NextUnmatchedRightBracket => Movement::NextUnmatched(')'),
             PreviousUnmatchedLeftBracket => Movement::PreviousUnmatched('('),
@@ -212,6 +308,12 @@ pub fn to_movement(&self, count: Option<usize>) -> Movement {
             PreviousUnmatchedLeftCurlyBracket => Movement::PreviousUnmatched('{'),
             ParagraphForward => Movement::ParagraphForward,
             ParagraphBackward => Movement::ParagraphBackward,
+            NextFunction => Movement::NextItem(SyntaxItem::Function),
+            PreviousFunction => Movement::PreviousItem(SyntaxItem::Function),
+            NextClass => Movement::NextItem(SyntaxItem::Class),
+            PreviousClass => Movement::PreviousItem(SyntaxItem::Class),
+            NextTopLevelItem => Movement::NextItem(SyntaxItem::TopLevel),
+            PreviousTopLevelItem => Movement::PreviousItem(SyntaxItem::TopLevel),
         }
     }
 }
@@ -228,20 +330,28 @@ pub fn to_movement(&self, count: Option<usize>) -> Movement {
     IntoStaticStr,
 )]
 pub enum FocusCommand {
+    #[strum(message = "Split Editor Right")]
     #[strum(serialize = "split_vertical")]
     SplitVertical,
+    #[strum(message = "Split Editor Down")]
     #[strum(serialize = "split_horizontal")]
     SplitHorizontal,
+    #[strum(message = "Exchange Split")]
     #[strum(serialize = "split_exchange")]
     SplitExchange,
+    #[strum(message = "Close Editor in Split")]
     #[strum(serialize = "split_close")]
     SplitClose,
+    #[strum(message = "Focus Split Right")]
     #[strum(serialize = "split_right")]
     SplitRight,
+    #[strum(message = "Focus Split Left")]
     #[strum(serialize = "split_left")]
     SplitLeft,
+    #[strum(message = "Focus Split Up")]
     #[strum(serialize = "split_up")]
     SplitUp,
+    #[strum(message = "Focus Split Down")]
     #[strum(serialize = "split_down")]
     SplitDown,
     #[strum(serialize = "search_whole_word_forward")]
@@ -250,8 +360,15 @@ pub enum FocusCommand {
     SearchForward,
     #[strum(serialize = "search_backward")]
     SearchBackward,
+    #[strum(serialize = "history_previous")]
+    HistoryPrevious,
+    #[strum(serialize = "history_next")]
+    HistoryNext,
     #[strum(serialize = "toggle_case_sensitive_search")]
     ToggleCaseSensitive,
+    #[strum(message = "Toggle Find in Selection")]
+    #[strum(serialize = "toggle_find_in_selection")]
+    ToggleFindInSelection,
     #[strum(serialize = "global_search_refresh")]
     GlobalSearchRefresh,
     #[strum(serialize = "clear_search")]
@@ -290,6 +407,22 @@ pub enum FocusCommand {
     BottomOfWindow,
     #[strum(serialize = "show_code_actions")]
     ShowCodeActions,
+    #[strum(message = "Organize Imports")]
+    #[strum(serialize = "organize_imports")]
+    OrganizeImports,
+    #[strum(message = "Extract Function")]
+    #[strum(serialize = "extract_function")]
+    ExtractFunction,
+    #[strum(message = "Extract Variable")]
+    #[strum(serialize = "extract_variable")]
+    ExtractVariable,
+    #[strum(message = "Inline")]
+    #[strum(serialize = "inline_symbol")]
+    InlineSymbol,
+    #[strum(message = "Pick Color")]
+    #[strum(serialize = "pick_color")]
+    PickColor,
+    #[strum(message = "Get Completion")]
     #[strum(serialize = "get_completion")]
     GetCompletion,
     #[strum(serialize = "get_signature")]
@@ -306,6 +439,18 @@ pub enum FocusCommand {
     #[strum(message = "Go to Type Definition")]
     #[strum(serialize = "goto_type_definition")]
     GotoTypeDefinition,
+    #[strum(message = "Peek Definition")]
+    #[strum(serialize = "peek_definition")]
+    PeekDefinition,
+    #[strum(serialize = "peek.next")]
+    PeekNext,
+    #[strum(serialize = "peek.previous")]
+    PeekPrevious,
+    #[strum(serialize = "peek.open")]
+    PeekOpen,
+    #[strum(message = "Find References")]
+    #[strum(serialize = "find_references")]
+    FindReferences,
     #[strum(message = "Show Hover")]
     #[strum(serialize = "show_hover")]
     ShowHover,
@@ -334,6 +479,21 @@ pub enum FocusCommand {
     InlineFindRight,
     #[strum(serialize = "inline_find_left")]
     InlineFindLeft,
+    #[strum(message = "Select Register")]
+    #[strum(serialize = "select_register")]
+    SelectRegister,
+    #[strum(message = "Surround With...")]
+    #[strum(serialize = "surround_add")]
+    SurroundAdd,
+    #[strum(message = "Change Surrounding Pair")]
+    #[strum(serialize = "surround_change")]
+    SurroundChange,
+    #[strum(message = "Delete Surrounding Pair")]
+    #[strum(serialize = "surround_delete")]
+    SurroundDelete,
+    /// `s` after `d` or `y` in Vim, to delete a pair or add one around a word.
+    #[strum(serialize = "motion_mode_surround")]
+    MotionModeSurround,
     #[strum(serialize = "create_mark")]
     CreateMark,
     #[strum(serialize = "go_to_mark")]
@@ -346,6 +506,9 @@ pub enum FocusCommand {
     #[strum(message = "Save Without Formatting")]
     #[strum(serialize = "save_without_format")]
     SaveWithoutFormatting,
+    #[strum(message = "Save As")]
+    #[strum(serialize = "save_as")]
+    SaveAs,
     #[strum(serialize = "save_and_exit")]
     SaveAndExit,
     #[strum(serialize = "force_exit")]
@@ -407,6 +570,10 @@ pub enum MultiSelectionCommand {
     InsertCursorEndOfLine,
     #[strum(serialize = "select_current_line")]
     SelectCurrentLine,
+    #[strum(message = "Split Selection into Lines")]
+    #[strum(serialize = "split_selection_into_lines")]
+    SplitSelectionIntoLines,
+    #[strum(message = "Select All Occurrences")]
     #[strum(serialize = "select_all_current")]
     SelectAllCurrent,
     #[strum(serialize = "select_next_current")]
@@ -415,4 +582,24 @@ pub enum MultiSelectionCommand {
     SelectSkipCurrent,
     #[strum(serialize = "select_all")]
     SelectAll,
+    #[strum(serialize = "expand_selection")]
+    ExpandSelection,
+    #[strum(serialize = "shrink_selection")]
+    ShrinkSelection,
+    #[strum(serialize = "select_around_function")]
+    SelectAroundFunction,
+    #[strum(serialize = "select_inside_function")]
+    SelectInsideFunction,
+    #[strum(serialize = "select_around_class")]
+    SelectAroundClass,
+    #[strum(serialize = "select_inside_class")]
+    SelectInsideClass,
+    #[strum(serialize = "select_around_argument")]
+    SelectAroundArgument,
+    #[strum(serialize = "select_inside_argument")]
+    SelectInsideArgument,
+    #[strum(serialize = "select_around_string")]
+    SelectAroundString,
+    #[strum(serialize = "select_inside_string")]
+    SelectInsideString,
 }
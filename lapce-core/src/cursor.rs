@@ -380,13 +380,21 @@ pub fn get_selection(&self) -> Option<(usize, usize)> {
         }
     }
 
+    /// The line, the column it's shown in and the offset of the cursor. The
+    /// column counts wide characters like CJK as two, the way they are shown.
     pub fn get_line_col_char(
         &self,
         buffer: &Buffer,
     ) -> Option<(usize, usize, usize)> {
+        let line_col = |offset: usize| {
+            (
+                buffer.line_of_offset(offset),
+                buffer.offset_to_display_col(offset),
+            )
+        };
         match &self.mode {
             CursorMode::Normal(offset) => {
-                let ln_col = buffer.offset_to_line_col(*offset);
+                let ln_col = line_col(*offset);
                 Some((ln_col.0, ln_col.1, *offset))
             }
             CursorMode::Visual {
@@ -394,7 +402,7 @@ pub fn get_line_col_char(
                 end,
                 mode: _,
             } => {
-                let v = buffer.offset_to_line_col(*start.min(end));
+                let v = line_col(*start.min(end));
                 Some((v.0, v.1, *start))
             }
             CursorMode::Insert(selection) => {
@@ -403,7 +411,7 @@ pub fn get_line_col_char(
                 }
 
                 let x = selection.regions().get(0).unwrap();
-                let v = buffer.offset_to_line_col(x.start);
+                let v = line_col(x.start);
 
                 Some((v.0, v.1, x.start))
             }
@@ -99,6 +99,19 @@ pub fn themes_directory() -> Option<PathBuf> {
             None
         }
     }
+    /// Get the path to the locales folder, with the catalogs of the text of the
+    /// UI in other languages
+    pub fn locales_directory() -> Option<PathBuf> {
+        if let Some(dir) = Self::data_local_directory() {
+            let dir = dir.join("locales");
+            if !dir.exists() {
+                let _ = std::fs::create_dir(&dir);
+            }
+            Some(dir)
+        } else {
+            None
+        }
+    }
     // Get the path to plugins directory
     // Each plugin has own directory that contains
     // metadata file and plugin wasm
@@ -144,6 +157,20 @@ pub fn updates_directory() -> Option<PathBuf> {
         }
     }
 
+    /// Get the path to the recovery directory
+    /// Unsaved changes of open files are backed up here, so they can be restored after a crash
+    pub fn recovery_directory() -> Option<PathBuf> {
+        if let Some(dir) = Self::data_local_directory() {
+            let dir = dir.join("recovery");
+            if !dir.exists() {
+                let _ = std::fs::create_dir(&dir);
+            }
+            Some(dir)
+        } else {
+            None
+        }
+    }
+
     pub fn queries_directory() -> Option<PathBuf> {
         if let Some(dir) = Self::config_directory() {
             let dir = dir.join("queries");
@@ -1,15 +1,22 @@
-use std::{collections::HashSet, iter};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashSet},
+    iter,
+    ops::Range,
+};
 
 use itertools::Itertools;
-use lapce_xi_rope::RopeDelta;
+use lapce_xi_rope::{RopeDelta, Transformer};
 
 use crate::{
     buffer::{rope_text::RopeText, Buffer, InvalLines},
     command::EditCommand,
     cursor::{get_first_selection_after, Cursor, CursorMode},
+    indent::reindent,
     mode::{Mode, MotionMode, VisualMode},
     register::{Clipboard, Register, RegisterData, RegisterKind},
     selection::{InsertDrift, SelRegion, Selection},
+    surround::{find_surrounding_pair, surround_pair, Surround},
     syntax::{
         edit::SyntaxEdit,
         util::{
@@ -18,6 +25,7 @@
         },
         Syntax,
     },
+    transform,
     word::{get_char_property, CharClassification},
 };
 
@@ -124,7 +132,8 @@ pub fn insert(
                         && auto_surround
                         && (matching_pair_type == Some(true)
                             || c == '"'
-                            || c == '\'')
+                            || c == '\''
+                            || c == '`')
                     {
                         edits.push((
                             Selection::region(region.min(), region.min()),
@@ -133,8 +142,7 @@ pub fn insert(
                         edits_after.push((
                             idx,
                             match c {
-                                '"' => '"',
-                                '\'' => '\'',
+                                '"' | '\'' | '`' => c,
                                 _ => matching_char(c).unwrap(),
                             },
                         ));
@@ -577,6 +585,61 @@ fn compute_paste_edit(
         }
     }
 
+    /// Reindent text from the clipboard for where it's pasted, when it's several
+    /// lines pasted at one place.
+    fn reindent_paste(cursor: &Cursor, buffer: &Buffer, data: &mut RegisterData) {
+        let selection = cursor.edit_selection(buffer);
+        let [region] = selection.regions() else {
+            return;
+        };
+        let indent_unit = buffer.indent_unit();
+        if data.mode != VisualMode::Normal {
+            // Whole lines go above the line of the cursor in insert mode and
+            // below it otherwise, inside the block the line before opens if it
+            // opens one
+            let line = buffer.line_of_offset(region.min());
+            let line = if cursor.is_normal() { line + 1 } else { line };
+            let indent = match line.checked_sub(1) {
+                Some(prev) => {
+                    let indent = buffer.indent_on_line(prev);
+                    if has_unmatched_pair(&buffer.line_content(prev)) {
+                        format!("{indent}{indent_unit}")
+                    } else {
+                        indent
+                    }
+                }
+                None => String::new(),
+            };
+            data.content = reindent(&data.content, &indent, indent_unit, true);
+            return;
+        }
+
+        if !data.content.contains('\n') {
+            return;
+        }
+        let offset = match cursor.mode {
+            CursorMode::Normal(offset) => {
+                (offset + 1).min(buffer.offset_line_end(offset, true))
+            }
+            CursorMode::Visual { .. } | CursorMode::Insert(_) => region.min(),
+        };
+        let line = buffer.line_of_offset(offset);
+        let before = buffer.slice_to_cow(buffer.offset_of_line(line)..offset);
+        data.content = if before.trim().is_empty() {
+            // The cursor is in the indentation, which the first line goes after
+            let content = data.content.trim_start_matches([' ', '\t']);
+            reindent(content, &before, indent_unit, false)
+        } else {
+            let indent = buffer.indent_on_line(line);
+            let indent = if has_unmatched_pair(&before) {
+                format!("{indent}{indent_unit}")
+            } else {
+                indent
+            };
+            reindent(&data.content, &indent, indent_unit, false)
+        };
+    }
+
     pub fn do_paste(
         cursor: &mut Cursor,
         buffer: &mut Buffer,
@@ -681,6 +744,60 @@ pub fn do_paste(
         deltas
     }
 
+    /// Remove the regions of the selection and put their text on the kill ring
+    /// and the clipboard.
+    fn kill<T: Clipboard>(
+        cursor: &mut Cursor,
+        buffer: &mut Buffer,
+        clipboard: &mut T,
+        register: &mut Register,
+        selection: Selection,
+    ) -> Vec<(RopeDelta, InvalLines, SyntaxEdit)> {
+        let content = selection
+            .regions()
+            .iter()
+            .map(|region| buffer.slice_to_cow(region.min()..region.max()))
+            .join("\n");
+        if content.is_empty() {
+            return vec![];
+        }
+        register.add_kill(selection.min_offset(), &content);
+        if let Some(killed) =
+            register.kill_ring_entry(register.kill_ring_len().saturating_sub(1))
+        {
+            clipboard.put_string(killed);
+        }
+
+        let (delta, inval_lines, edits) =
+            buffer.edit([(&selection, "")], EditType::Cut);
+        let selection = selection.apply_delta(&delta, true, InsertDrift::Default);
+        cursor.update_selection(buffer, selection);
+        vec![(delta, inval_lines, edits)]
+    }
+
+    /// Insert an entry of the kill ring in place of the selection, and remember
+    /// where it went so yank pop can replace it.
+    fn yank_kill(
+        cursor: &mut Cursor,
+        buffer: &mut Buffer,
+        register: &mut Register,
+        index: usize,
+    ) -> Vec<(RopeDelta, InvalLines, SyntaxEdit)> {
+        let Some(content) = register.kill_ring_entry(index).map(str::to_string)
+        else {
+            return vec![];
+        };
+        let selection = cursor.edit_selection(buffer);
+        let (delta, inval_lines, edits) =
+            buffer.edit([(&selection, content.as_str())], EditType::Paste);
+        let selection = selection.apply_delta(&delta, true, InsertDrift::Default);
+        if let [region] = selection.regions() {
+            register.set_kill_yank(region.end - content.len(), region.end, index);
+        }
+        cursor.mode = CursorMode::Insert(selection);
+        vec![(delta, inval_lines, edits)]
+    }
+
     fn do_indent(
         buffer: &mut Buffer,
         selection: Selection,
@@ -750,31 +867,89 @@ fn do_outdent(
         buffer.edit(&edits, EditType::Outdent)
     }
 
+    /// The lines the regions of the selection are on, sorted, with the ranges
+    /// of overlapping regions merged. A region that ends at the start of a line
+    /// doesn't take that line.
+    fn selected_line_ranges(
+        buffer: &Buffer,
+        selection: &Selection,
+    ) -> Vec<Range<usize>> {
+        let mut ranges = selection
+            .regions()
+            .iter()
+            .map(|region| {
+                let start_line = buffer.line_of_offset(region.min());
+                let mut end_line = buffer.line_of_offset(region.max());
+                if end_line > start_line
+                    && region.max() == buffer.offset_of_line(end_line)
+                {
+                    end_line -= 1;
+                }
+                start_line..end_line + 1
+            })
+            .collect::<Vec<_>>();
+        ranges.sort_by_key(|range| range.start);
+
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start < last.end => {
+                    last.end = last.end.max(range.end);
+                }
+                _ => merged.push(range),
+            }
+        }
+        merged
+    }
+
+    fn line_ending(line_content: &str) -> &'static str {
+        if line_content.ends_with("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        }
+    }
+
+    /// Move the offsets of the cursor over the edit, with the ones where text
+    /// was inserted going after it if `after`.
+    fn transform_cursor(cursor: &mut Cursor, delta: &RopeDelta, after: bool) {
+        let mut transformer = Transformer::new(delta);
+        cursor.mode = match &cursor.mode {
+            CursorMode::Normal(offset) => {
+                CursorMode::Normal(transformer.transform(*offset, after))
+            }
+            CursorMode::Visual { start, end, mode } => CursorMode::Visual {
+                start: transformer.transform(*start, after),
+                end: transformer.transform(*end, after),
+                mode: *mode,
+            },
+            CursorMode::Insert(selection) => CursorMode::Insert(
+                selection.apply_delta(delta, after, InsertDrift::Default),
+            ),
+        };
+    }
+
     fn duplicate_line(
         cursor: &mut Cursor,
         buffer: &mut Buffer,
         direction: DuplicateDirection,
     ) -> Vec<(RopeDelta, InvalLines, SyntaxEdit)> {
-        // TODO other modes
-        let selection = match cursor.mode {
-            CursorMode::Insert(ref mut sel) => sel,
-            _ => return vec![],
-        };
-
-        let mut line_ranges = HashSet::new();
-        for region in selection.regions_mut() {
-            let start_line = buffer.line_of_offset(region.start);
-            let end_line = buffer.line_of_offset(region.end) + 1;
-
-            line_ranges.insert(start_line..end_line);
-        }
+        let selection = cursor.edit_selection(buffer);
 
         let mut edits = vec![];
-        for range in line_ranges {
+        for range in Self::selected_line_ranges(buffer, &selection) {
             let start = buffer.offset_of_line(range.start);
             let end = buffer.offset_of_line(range.end);
 
-            let content = buffer.slice_to_cow(start..end).into_owned();
+            let mut content = buffer.slice_to_cow(start..end).into_owned();
+            // The last line has no line break to copy along with it
+            if !content.ends_with('\n') {
+                let line_ending = Self::line_ending(&buffer.line_content(0));
+                match direction {
+                    DuplicateDirection::Up => content.insert_str(0, line_ending),
+                    DuplicateDirection::Down => content.push_str(line_ending),
+                }
+            }
             edits.push((
                 match direction {
                     DuplicateDirection::Up => Selection::caret(end),
@@ -783,16 +958,599 @@ fn duplicate_line(
                 content,
             ));
         }
+        if edits.is_empty() {
+            return vec![];
+        }
+
+        let edits = edits
+            .iter()
+            .map(|(sel, content)| (sel, content.as_str()))
+            .collect::<Vec<_>>();
+
+        let (delta, inval_lines, edits) = buffer.edit(&edits, EditType::Other);
+
+        // The cursor stays on the copy in the direction of the duplication
+        Self::transform_cursor(
+            cursor,
+            &delta,
+            matches!(direction, DuplicateDirection::Down),
+        );
+
+        vec![(delta, inval_lines, edits)]
+    }
+
+    /// Add an indent unit to the start of the line, or take one off it, unless
+    /// it's blank. Returns the line and how far its text moved.
+    fn shift_line_indent<'a>(
+        content: &'a str,
+        change: isize,
+        indent_unit: &str,
+    ) -> (Cow<'a, str>, isize) {
+        if change == 0 || content.trim().is_empty() {
+            return (Cow::Borrowed(content), 0);
+        }
+        if change > 0 {
+            return (
+                Cow::Owned(format!("{indent_unit}{content}")),
+                indent_unit.len() as isize,
+            );
+        }
+        let removed = if content.starts_with('\t') {
+            1
+        } else {
+            content
+                .chars()
+                .take(indent_unit.len())
+                .take_while(|c| *c == ' ')
+                .count()
+        };
+        (Cow::Borrowed(&content[removed..]), -(removed as isize))
+    }
+
+    /// Swap the lines of each region with the line above or below them, all in
+    /// one edit. Lines that move into or out of a block, past the line that
+    /// opens or closes it, are indented or outdented to fit there.
+    fn move_lines(
+        cursor: &mut Cursor,
+        buffer: &mut Buffer,
+        up: bool,
+    ) -> Vec<(RopeDelta, InvalLines, SyntaxEdit)> {
+        let selection = cursor.edit_selection(buffer);
+        // Regions on neighbouring lines move together
+        let mut blocks: Vec<Range<usize>> = Vec::new();
+        for range in Self::selected_line_ranges(buffer, &selection) {
+            match blocks.last_mut() {
+                Some(last) if last.end == range.start => last.end = range.end,
+                _ => blocks.push(range),
+            }
+        }
+        // Nothing moves if one of the blocks can't, so that they keep their
+        // places relative to each other
+        let (Some(first), Some(last)) = (blocks.first(), blocks.last()) else {
+            return vec![];
+        };
+        if (up && first.start == 0) || (!up && last.end > buffer.last_line()) {
+            return vec![];
+        }
+
+        let indent_unit = buffer.indent_unit();
+        let mut edits = Vec::with_capacity(blocks.len());
+        // The offsets of the lines of the blocks before the edit, where they
+        // are after it, and how far their text moved within the line
+        let mut moved_lines: Vec<(usize, usize, isize)> = Vec::new();
+        let mut block_ends = Vec::with_capacity(blocks.len());
+        // How much the edits before change the length of the text
+        let mut shift = 0isize;
+        for block in &blocks {
+            let other_line = if up { block.start - 1 } else { block.end };
+            let other = buffer.line_content(other_line).into_owned();
+            let opens = has_unmatched_pair(&other);
+            let closes = other
+                .trim_start()
+                .starts_with(|c: char| matching_pair_direction(c) == Some(false));
+            let change: isize = match (opens, closes) {
+                (true, false) if up => -1,
+                (true, false) => 1,
+                (false, true) if up => 1,
+                (false, true) => -1,
+                _ => 0,
+            };
+
+            let mut text = String::new();
+            let mut lines = Vec::with_capacity(block.len());
+            for line in block.clone() {
+                let content = buffer.line_content(line);
+                let (content, col_shift) =
+                    Self::shift_line_indent(&content, change, indent_unit);
+                lines.push((buffer.offset_of_line(line), text.len(), col_shift));
+                text.push_str(&content);
+            }
+
+            let (start, end) = if up {
+                (
+                    buffer.offset_of_line(other_line),
+                    buffer.offset_of_line(block.end),
+                )
+            } else {
+                (
+                    buffer.offset_of_line(block.start),
+                    buffer.offset_of_line(other_line + 1),
+                )
+            };
+            let new_start = (start as isize + shift) as usize;
+            // Where the block starts in the new text, and how long it is there
+            let (new_text, block_start, block_len) = if up {
+                let block_len = text.len();
+                if text.ends_with('\n') {
+                    (text + &other, 0, block_len)
+                } else {
+                    // The block was the last line, which the other line is now
+                    let line_ending = Self::line_ending(&other);
+                    let other = other.strip_suffix(line_ending).unwrap_or(&other);
+                    (text + line_ending + other, 0, block_len)
+                }
+            } else if other.ends_with('\n') {
+                let block_start = other.len();
+                (other + &text, block_start, text.len())
+            } else {
+                let line_ending = Self::line_ending(&text);
+                let text = text.strip_suffix(line_ending).unwrap_or(&text);
+                let block_start = other.len() + line_ending.len();
+                (other + line_ending + text, block_start, text.len())
+            };
+
+            moved_lines.extend(lines.into_iter().map(|(old, new, col_shift)| {
+                (old, new_start + block_start + new, col_shift)
+            }));
+            block_ends.push((
+                buffer.offset_of_line(block.end),
+                new_start + block_start + block_len,
+            ));
+            shift += new_text.len() as isize - (end - start) as isize;
+            edits.push((Selection::region(start, end), new_text));
+        }
+
+        let map = |offset: usize| {
+            if let Some((_, new_end)) =
+                block_ends.iter().find(|(old_end, _)| *old_end == offset)
+            {
+                return *new_end;
+            }
+            let line_start = buffer.offset_of_line(buffer.line_of_offset(offset));
+            match moved_lines.iter().find(|(old, _, _)| *old == line_start) {
+                Some((old, new, col_shift)) => {
+                    new + ((offset - old) as isize + col_shift).max(0) as usize
+                }
+                None => offset,
+            }
+        };
+        let mode = match &cursor.mode {
+            CursorMode::Normal(offset) => CursorMode::Normal(map(*offset)),
+            CursorMode::Visual { start, end, mode } => CursorMode::Visual {
+                start: map(*start),
+                end: map(*end),
+                mode: *mode,
+            },
+            CursorMode::Insert(selection) => {
+                let mut new_selection = Selection::new();
+                for region in selection.regions() {
+                    new_selection.add_region(SelRegion::new(
+                        map(region.start),
+                        map(region.end),
+                        region.horiz,
+                    ));
+                }
+                CursorMode::Insert(new_selection)
+            }
+        };
+
+        let edits = edits
+            .iter()
+            .map(|(sel, content)| (sel, content.as_str()))
+            .collect::<Vec<_>>();
+        let (delta, inval_lines, edits) = buffer.edit(&edits, EditType::MoveLine);
+        cursor.mode = mode;
+        vec![(delta, inval_lines, edits)]
+    }
+
+    /// Join the line of each caret with the next one, and the lines of each
+    /// selection spanning more than one line together, with the indent of the
+    /// joined lines replaced by a space.
+    fn join_lines(
+        cursor: &mut Cursor,
+        buffer: &mut Buffer,
+    ) -> Vec<(RopeDelta, InvalLines, SyntaxEdit)> {
+        let selection = cursor.edit_selection(buffer);
+        let last_line = buffer.last_line();
+        let mut lines = BTreeSet::new();
+        for range in Self::selected_line_ranges(buffer, &selection) {
+            let end = if range.len() > 1 {
+                range.end - 1
+            } else {
+                range.end
+            };
+            lines.extend((range.start..end).filter(|line| *line < last_line));
+        }
+
+        let mut edits = Vec::with_capacity(lines.len());
+        for line in lines {
+            let content = buffer.line_content(line);
+            let content = content.trim_end_matches(['\r', '\n']);
+            let next = buffer.line_content(line + 1);
+            let next = next.trim();
+            // Blank lines have nothing after their indent
+            let start = (buffer.offset_of_line(line) + content.trim_end().len())
+                .max(buffer.first_non_blank_character_on_line(line));
+            let end = buffer.first_non_blank_character_on_line(line + 1);
+            let separator = if content.trim().is_empty()
+                || next.is_empty()
+                || next.starts_with(')')
+            {
+                ""
+            } else {
+                " "
+            };
+            edits.push((Selection::region(start, end), separator));
+        }
+        let Some(last_join) = edits.last().map(|(sel, _)| sel.min_offset()) else {
+            return vec![];
+        };
+
+        let edits = edits
+            .iter()
+            .map(|(sel, separator)| (sel, *separator))
+            .collect::<Vec<_>>();
+        let (delta, inval_lines, edits) = buffer.edit(&edits, EditType::Other);
+        if cursor.is_insert() {
+            cursor.apply_delta(&delta);
+        } else {
+            // Like in Vim, the cursor goes to where the last lines were joined
+            let mut transformer = Transformer::new(&delta);
+            cursor.mode =
+                CursorMode::Normal(transformer.transform(last_join, false));
+        }
+        vec![(delta, inval_lines, edits)]
+    }
+
+    /// Swap the characters before and after each caret and move it past them.
+    /// At the end of a line, the last two characters of it are swapped instead.
+    fn transpose_characters(
+        cursor: &mut Cursor,
+        buffer: &mut Buffer,
+    ) -> Vec<(RopeDelta, InvalLines, SyntaxEdit)> {
+        let CursorMode::Insert(selection) = &cursor.mode else {
+            return vec![];
+        };
+
+        let mut edits = Vec::new();
+        let mut new_selection = Selection::new();
+        let mut last_end = 0;
+        for region in selection.regions() {
+            if !region.is_caret() {
+                new_selection.add_region(*region);
+                continue;
+            }
+            let line = buffer.line_of_offset(region.start);
+            let line_start = buffer.offset_of_line(line);
+            let line_end = buffer.line_end_offset(line, true);
+            let end = if region.start >= line_end {
+                line_end
+            } else {
+                buffer.next_grapheme_offset(region.start, 1, line_end)
+            };
+            let middle = buffer.prev_grapheme_offset(end, 1, line_start);
+            let start = buffer.prev_grapheme_offset(middle, 1, line_start);
+            // Carets next to each other would swap the same characters
+            if start == middle || start < last_end {
+                new_selection.add_region(*region);
+                continue;
+            }
+            let swapped = format!(
+                "{}{}",
+                buffer.slice_to_cow(middle..end),
+                buffer.slice_to_cow(start..middle)
+            );
+            edits.push((Selection::region(start, end), swapped));
+            new_selection.add_region(SelRegion::caret(end));
+            last_end = end;
+        }
+        if edits.is_empty() {
+            return vec![];
+        }
 
         let edits = edits
             .iter()
             .map(|(sel, content)| (sel, content.as_str()))
             .collect::<Vec<_>>();
+        let (delta, inval_lines, edits) = buffer.edit(&edits, EditType::Other);
+        // The swapped text has the same length, so the offsets are still right
+        cursor.mode = CursorMode::Insert(new_selection);
+        vec![(delta, inval_lines, edits)]
+    }
+
+    /// Replace each of the ranges with its text after `f`, in one edit, and move
+    /// the cursor over it. Selections of a whole range still select all of it,
+    /// and other offsets in one keep their distance from its start, as far as
+    /// the new text goes.
+    fn replace_ranges(
+        cursor: &mut Cursor,
+        buffer: &mut Buffer,
+        ranges: Vec<(usize, usize)>,
+        f: impl Fn(&str) -> String,
+    ) -> Vec<(RopeDelta, InvalLines, SyntaxEdit)> {
+        let mut edits = Vec::with_capacity(ranges.len());
+        let mut replaced = Vec::with_capacity(ranges.len());
+        for (start, end) in ranges {
+            let text = buffer.slice_to_cow(start..end);
+            let new_text = f(&text);
+            if new_text != text {
+                replaced.push((start, end, new_text.len()));
+                edits.push((Selection::region(start, end), new_text));
+            }
+        }
+        if edits.is_empty() {
+            return vec![];
+        }
+
+        let map = |offset: usize, after: bool| {
+            let mut shift = 0isize;
+            for (start, end, new_len) in &replaced {
+                let new_start = (*start as isize + shift) as usize;
+                if offset < *start {
+                    break;
+                } else if offset == *end && after {
+                    return new_start + new_len;
+                } else if offset <= *end {
+                    return new_start + (offset - start).min(*new_len);
+                }
+                shift += *new_len as isize - (end - start) as isize;
+            }
+            (offset as isize + shift) as usize
+        };
+        let mode = match &cursor.mode {
+            CursorMode::Normal(offset) => CursorMode::Normal(map(*offset, false)),
+            CursorMode::Visual { start, end, .. } => {
+                CursorMode::Normal(map(*start.min(end), false))
+            }
+            CursorMode::Insert(selection) => {
+                let mut new_selection = Selection::new();
+                for region in selection.regions() {
+                    let forward = region.start < region.end;
+                    new_selection.add_region(SelRegion::new(
+                        map(region.start, !forward && !region.is_caret()),
+                        map(region.end, forward),
+                        None,
+                    ));
+                }
+                CursorMode::Insert(new_selection)
+            }
+        };
+
+        let edits = edits
+            .iter()
+            .map(|(sel, content)| (sel, content.as_str()))
+            .collect::<Vec<_>>();
+        let (delta, inval_lines, edits) = buffer.edit(&edits, EditType::Other);
+        cursor.mode = mode;
+        vec![(delta, inval_lines, edits)]
+    }
+
+    /// Transform the text of each selection, or the word of each caret.
+    fn transform_selection(
+        cursor: &mut Cursor,
+        buffer: &mut Buffer,
+        f: impl Fn(&str) -> String,
+    ) -> Vec<(RopeDelta, InvalLines, SyntaxEdit)> {
+        let selection = cursor.edit_selection(buffer);
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+        for region in selection.regions() {
+            let (start, end) = if region.is_caret() {
+                buffer.select_word(region.start)
+            } else {
+                (region.min(), region.max())
+            };
+            // Carets in the same word
+            if start == end || ranges.last().is_some_and(|(_, last)| start < *last) {
+                continue;
+            }
+            ranges.push((start, end));
+        }
+        Self::replace_ranges(cursor, buffer, ranges, f)
+    }
+
+    /// Apply `f` to the lines of each selection, or to all the lines when no
+    /// selection spans more than one.
+    fn transform_lines(
+        cursor: &mut Cursor,
+        buffer: &mut Buffer,
+        f: impl Fn(&mut Vec<&str>),
+    ) -> Vec<(RopeDelta, InvalLines, SyntaxEdit)> {
+        let selection = cursor.edit_selection(buffer);
+        let mut ranges = Self::selected_line_ranges(buffer, &selection);
+        if ranges.iter().all(|range| range.len() < 2) {
+            ranges = vec![0..buffer.last_line() + 1];
+        }
+        let ranges = ranges
+            .into_iter()
+            .map(|range| {
+                (
+                    buffer.offset_of_line(range.start),
+                    buffer.offset_of_line(range.end),
+                )
+            })
+            .collect();
+        Self::replace_ranges(cursor, buffer, ranges, |text| {
+            transform::transform_lines(text, &f)
+        })
+    }
+
+    /// Replace each region with a number, counting up from the number in the
+    /// first one, or from 1.
+    fn insert_sequence(
+        cursor: &mut Cursor,
+        buffer: &mut Buffer,
+    ) -> Vec<(RopeDelta, InvalLines, SyntaxEdit)> {
+        let CursorMode::Insert(selection) = &cursor.mode else {
+            return vec![];
+        };
+        let first = selection.regions().first().map(|region| {
+            buffer
+                .slice_to_cow(region.min()..region.max())
+                .trim()
+                .parse::<i64>()
+        });
+        let start = match first {
+            Some(Ok(start)) => start,
+            _ => 1,
+        };
+
+        let numbers = (start..)
+            .zip(selection.regions())
+            .map(|(n, region)| {
+                (Selection::region(region.min(), region.max()), n.to_string())
+            })
+            .collect::<Vec<_>>();
+        let edits = numbers
+            .iter()
+            .map(|(sel, number)| (sel, number.as_str()))
+            .collect::<Vec<_>>();
+        let (delta, inval_lines, edits) = buffer.edit(&edits, EditType::Other);
+        let selection = selection.apply_delta(&delta, true, InsertDrift::Default);
+        cursor.mode = CursorMode::Insert(selection);
+        vec![(delta, inval_lines, edits)]
+    }
+
+    /// Add, change or delete the pairs around the selections, in one edit. In
+    /// normal mode the pair is around the cursor, or added around its word.
+    pub fn surround(
+        cursor: &mut Cursor,
+        buffer: &mut Buffer,
+        syntax: &Syntax,
+        surround: Surround,
+    ) -> Vec<(RopeDelta, InvalLines, SyntaxEdit)> {
+        let selection = match cursor.mode {
+            CursorMode::Normal(offset) => Selection::caret(offset),
+            _ => cursor.edit_selection(buffer),
+        };
+
+        let mut edits = BTreeMap::new();
+        match surround {
+            Surround::Add(c) => {
+                let Some((open, close)) = surround_pair(c) else {
+                    return vec![];
+                };
+                let mut last_end = 0;
+                for region in selection.regions() {
+                    let (start, mut end) = if region.is_caret() {
+                        buffer.select_word(region.start)
+                    } else {
+                        (region.min(), region.max())
+                    };
+                    // Whole lines are wrapped without their last line break
+                    let end_line = buffer.line_of_offset(end);
+                    if end_line > buffer.line_of_offset(start)
+                        && end == buffer.offset_of_line(end_line)
+                    {
+                        end = buffer.line_end_offset(end_line - 1, true);
+                    }
+                    if start == end || start < last_end {
+                        continue;
+                    }
+                    // A pair can start where the one before ends
+                    edits.entry((start, start)).or_default().push(open);
+                    edits.insert((end, end), close.to_string());
+                    last_end = end;
+                }
+            }
+            Surround::Delete(target) | Surround::Change(target, _) => {
+                let (open, close) = match surround {
+                    Surround::Change(_, to) => match surround_pair(to) {
+                        Some((open, close)) => (open.to_string(), close.to_string()),
+                        None => return vec![],
+                    },
+                    _ => (String::new(), String::new()),
+                };
+                for region in selection.regions() {
+                    if let Some([open_range, close_range]) = find_surrounding_pair(
+                        buffer,
+                        syntax,
+                        region.min(),
+                        region.max(),
+                        target,
+                    ) {
+                        edits.insert(open_range, open.clone());
+                        edits.insert(close_range, close.clone());
+                    }
+                }
+            }
+        }
+        let Some(((first, _), _)) = edits.first_key_value() else {
+            return vec![];
+        };
+        let first = *first;
 
-        let (delta, inval_lines, edits) = buffer.edit(&edits, EditType::InsertChars);
+        let edits = edits
+            .iter()
+            .map(|((start, end), text)| (Selection::region(*start, *end), text))
+            .collect::<Vec<_>>();
+        let edits = edits
+            .iter()
+            .map(|(sel, text)| (sel, text.as_str()))
+            .collect::<Vec<_>>();
+        let (delta, inval_lines, edits) = buffer.edit(&edits, EditType::Other);
+        match cursor.mode {
+            // The selections still select the text that was wrapped
+            CursorMode::Insert(ref selection)
+                if matches!(surround, Surround::Add(_)) =>
+            {
+                let selection =
+                    selection.apply_delta(&delta, true, InsertDrift::Outside);
+                cursor.mode = CursorMode::Insert(selection);
+            }
+            CursorMode::Insert(_) => cursor.apply_delta(&delta),
+            // Like in Vim, the cursor goes to the start of the first pair
+            _ => cursor.mode = CursorMode::Normal(first),
+        }
+        vec![(delta, inval_lines, edits)]
+    }
 
-        *selection = selection.apply_delta(&delta, true, InsertDrift::Default);
+    /// Go to the state of the buffer right after the edits of an undo group.
+    pub fn undo_to_group(
+        cursor: &mut Cursor,
+        buffer: &mut Buffer,
+        group: usize,
+        modal: bool,
+    ) -> Vec<(RopeDelta, InvalLines, SyntaxEdit)> {
+        let undo = buffer.undo_to_group(group);
+        Self::apply_undo(cursor, buffer, undo, modal)
+    }
 
+    /// Put the cursor back to where it was when the undone or redone edits were
+    /// made.
+    fn apply_undo(
+        cursor: &mut Cursor,
+        buffer: &Buffer,
+        undo: Option<(RopeDelta, InvalLines, SyntaxEdit, Option<CursorMode>)>,
+        modal: bool,
+    ) -> Vec<(RopeDelta, InvalLines, SyntaxEdit)> {
+        let Some((delta, inval_lines, edits, cursor_mode)) = undo else {
+            return vec![];
+        };
+        if let Some(cursor_mode) = cursor_mode {
+            cursor.mode = if modal {
+                CursorMode::Normal(cursor_mode.offset())
+            } else if cursor.is_insert() {
+                cursor_mode
+            } else {
+                CursorMode::Insert(Selection::caret(cursor_mode.offset()))
+            };
+        } else if let Some(new_cursor) =
+            get_first_selection_after(cursor, buffer, &delta)
+        {
+            *cursor = new_cursor
+        } else {
+            cursor.apply_delta(&delta);
+        }
         vec![(delta, inval_lines, edits)]
     }
 
@@ -808,78 +1566,13 @@ pub fn do_edit<T: Clipboard>(
         smart_tab: bool,
     ) -> Vec<(RopeDelta, InvalLines, SyntaxEdit)> {
         use crate::command::EditCommand::*;
+        let kill_yank = register.take_kill_yank();
+        if !matches!(cmd, KillLine | KillRegion) {
+            register.break_kill();
+        }
         match cmd {
-            MoveLineUp => {
-                let mut deltas = Vec::new();
-                if let CursorMode::Insert(mut selection) = cursor.mode.clone() {
-                    for region in selection.regions_mut() {
-                        let start_line = buffer.line_of_offset(region.min());
-                        if start_line > 0 {
-                            let previous_line_len =
-                                buffer.line_content(start_line - 1).len();
-
-                            let end_line = buffer.line_of_offset(region.max());
-                            let start = buffer.offset_of_line(start_line);
-                            let end = buffer.offset_of_line(end_line + 1);
-                            let content =
-                                buffer.slice_to_cow(start..end).to_string();
-                            let (delta, inval_lines, edits) = buffer.edit(
-                                [
-                                    (&Selection::region(start, end), ""),
-                                    (
-                                        &Selection::caret(
-                                            buffer.offset_of_line(start_line - 1),
-                                        ),
-                                        &content,
-                                    ),
-                                ],
-                                EditType::MoveLine,
-                            );
-                            deltas.push((delta, inval_lines, edits));
-                            region.start -= previous_line_len;
-                            region.end -= previous_line_len;
-                        }
-                    }
-                    cursor.mode = CursorMode::Insert(selection);
-                }
-                deltas
-            }
-            MoveLineDown => {
-                let mut deltas = Vec::new();
-                if let CursorMode::Insert(mut selection) = cursor.mode.clone() {
-                    for region in selection.regions_mut().iter_mut().rev() {
-                        let last_line = buffer.last_line();
-                        let start_line = buffer.line_of_offset(region.min());
-                        let end_line = buffer.line_of_offset(region.max());
-                        if end_line < last_line {
-                            let next_line_len =
-                                buffer.line_content(end_line + 1).len();
-
-                            let start = buffer.offset_of_line(start_line);
-                            let end = buffer.offset_of_line(end_line + 1);
-                            let content =
-                                buffer.slice_to_cow(start..end).to_string();
-                            let (delta, inval_lines, edits) = buffer.edit(
-                                [
-                                    (
-                                        &Selection::caret(
-                                            buffer.offset_of_line(end_line + 2),
-                                        ),
-                                        content.as_str(),
-                                    ),
-                                    (&Selection::region(start, end), ""),
-                                ],
-                                EditType::MoveLine,
-                            );
-                            deltas.push((delta, inval_lines, edits));
-                            region.start += next_line_len;
-                            region.end += next_line_len;
-                        }
-                    }
-                    cursor.mode = CursorMode::Insert(selection);
-                }
-                deltas
-            }
+            MoveLineUp => Self::move_lines(cursor, buffer, true),
+            MoveLineDown => Self::move_lines(cursor, buffer, false),
             InsertNewLine => match cursor.mode.clone() {
                 CursorMode::Normal(offset) => {
                     Self::insert_new_line(buffer, cursor, Selection::caret(offset))
@@ -951,20 +1644,37 @@ pub fn do_edit<T: Clipboard>(
                 cursor.apply_delta(&delta);
                 vec![(delta, inval_lines, edits)]
             }
-            JoinLines => {
-                let offset = cursor.offset();
-                let (line, _col) = buffer.offset_to_line_col(offset);
-                if line < buffer.last_line() {
-                    let start = buffer.line_end_offset(line, true);
-                    let end = buffer.first_non_blank_character_on_line(line + 1);
-                    vec![buffer.edit(
-                        [(&Selection::region(start, end), " ")],
-                        EditType::Other,
-                    )]
-                } else {
-                    vec![]
-                }
+            JoinLines => Self::join_lines(cursor, buffer),
+            TransposeCharacters => Self::transpose_characters(cursor, buffer),
+            TransformToUppercase => {
+                Self::transform_selection(cursor, buffer, str::to_uppercase)
             }
+            TransformToLowercase => {
+                Self::transform_selection(cursor, buffer, str::to_lowercase)
+            }
+            TransformToTitleCase => {
+                Self::transform_selection(cursor, buffer, transform::to_title_case)
+            }
+            TransformToSnakeCase => {
+                Self::transform_selection(cursor, buffer, transform::to_snake_case)
+            }
+            TransformToCamelCase => {
+                Self::transform_selection(cursor, buffer, transform::to_camel_case)
+            }
+            SortLinesAscending => {
+                Self::transform_lines(cursor, buffer, |lines| lines.sort())
+            }
+            SortLinesDescending => Self::transform_lines(cursor, buffer, |lines| {
+                lines.sort_by(|a, b| b.cmp(a))
+            }),
+            SortLinesUnique => Self::transform_lines(cursor, buffer, |lines| {
+                lines.sort();
+                lines.dedup();
+            }),
+            ReverseLines => {
+                Self::transform_lines(cursor, buffer, |lines| lines.reverse())
+            }
+            InsertSequence => Self::insert_sequence(cursor, buffer),
             OutdentLine => {
                 let selection = cursor.edit_selection(buffer);
                 let (delta, inval_lines, edits) =
@@ -1042,60 +1752,21 @@ pub fn do_edit<T: Clipboard>(
                 vec![(delta, inval_lines, edits)]
             }
             Undo => {
-                if let Some((delta, inval_lines, edits, cursor_mode)) =
-                    buffer.do_undo()
-                {
-                    if let Some(cursor_mode) = cursor_mode {
-                        cursor.mode = if modal {
-                            CursorMode::Normal(cursor_mode.offset())
-                        } else if cursor.is_insert() {
-                            cursor_mode
-                        } else {
-                            CursorMode::Insert(Selection::caret(
-                                cursor_mode.offset(),
-                            ))
-                        };
-                    } else if let Some(new_cursor) =
-                        get_first_selection_after(cursor, buffer, &delta)
-                    {
-                        *cursor = new_cursor
-                    } else {
-                        cursor.apply_delta(&delta);
-                    }
-                    vec![(delta, inval_lines, edits)]
-                } else {
-                    vec![]
-                }
+                let undo = buffer.do_undo();
+                Self::apply_undo(cursor, buffer, undo, modal)
             }
             Redo => {
-                if let Some((delta, inval_lines, edits, cursor_mode)) =
-                    buffer.do_redo()
-                {
-                    if let Some(cursor_mode) = cursor_mode {
-                        cursor.mode = if modal {
-                            CursorMode::Normal(cursor_mode.offset())
-                        } else if cursor.is_insert() {
-                            cursor_mode
-                        } else {
-                            CursorMode::Insert(Selection::caret(
-                                cursor_mode.offset(),
-                            ))
-                        };
-                    } else if let Some(new_cursor) =
-                        get_first_selection_after(cursor, buffer, &delta)
-                    {
-                        *cursor = new_cursor
-                    } else {
-                        cursor.apply_delta(&delta);
-                    }
-                    vec![(delta, inval_lines, edits)]
-                } else {
-                    vec![]
-                }
+                let redo = buffer.do_redo();
+                Self::apply_undo(cursor, buffer, redo, modal)
+            }
+            UndoToLastSave => {
+                let group = buffer.pristine_undo_group();
+                Self::undo_to_group(cursor, buffer, group, modal)
             }
             ClipboardCopy => {
                 let data = cursor.yank(buffer);
-                clipboard.put_string(data.content);
+                clipboard.put_string(&data.content);
+                register.push_history(data);
 
                 match &cursor.mode {
                     CursorMode::Visual {
@@ -1114,7 +1785,8 @@ pub fn do_edit<T: Clipboard>(
             }
             ClipboardCut => {
                 let data = cursor.yank(buffer);
-                clipboard.put_string(data.content);
+                clipboard.put_string(&data.content);
+                register.push_history(data);
 
                 let selection =
                     if let CursorMode::Insert(mut selection) = cursor.mode.clone() {
@@ -1139,19 +1811,107 @@ pub fn do_edit<T: Clipboard>(
                 cursor.update_selection(buffer, selection);
                 vec![(delta, inval_lines, edits)]
             }
-            ClipboardPaste => {
+            ClipboardPaste | ClipboardPasteWithoutFormatting => {
                 if let Some(s) = clipboard.get_string() {
-                    let mode = if s.ends_with('\n') {
-                        VisualMode::Linewise
-                    } else {
-                        VisualMode::Normal
-                    };
-                    let data = RegisterData { content: s, mode };
+                    let mut data = RegisterData::from_clipboard(s);
+                    if *cmd == ClipboardPaste {
+                        Self::reindent_paste(cursor, buffer, &mut data);
+                    }
                     Self::do_paste(cursor, buffer, &data)
                 } else {
                     vec![]
                 }
             }
+            KillLine => {
+                let CursorMode::Insert(selection) = &cursor.mode else {
+                    return vec![];
+                };
+                let mut kill_selection = Selection::new();
+                for region in selection.regions() {
+                    let offset = region.end;
+                    let line = buffer.line_of_offset(offset);
+                    let line_end = buffer.line_end_offset(line, true);
+                    // At the end of a line it's the line break that is killed
+                    let end = if offset < line_end {
+                        line_end
+                    } else {
+                        buffer.offset_of_line(line + 1)
+                    };
+                    kill_selection.add_region(SelRegion::new(offset, end, None));
+                }
+                Self::kill(cursor, buffer, clipboard, register, kill_selection)
+            }
+            KillRegion => {
+                let CursorMode::Insert(selection) = &cursor.mode else {
+                    return vec![];
+                };
+                let selection = selection.clone();
+                Self::kill(cursor, buffer, clipboard, register, selection)
+            }
+            KillRingSave => {
+                let CursorMode::Insert(selection) = &cursor.mode else {
+                    return vec![];
+                };
+                if selection.is_caret() {
+                    return vec![];
+                }
+                let data = cursor.yank(buffer);
+                register.push_kill(&data.content);
+                clipboard.put_string(&data.content);
+
+                let mut carets = Selection::new();
+                for region in selection.regions() {
+                    carets.add_region(SelRegion::caret(region.end));
+                }
+                cursor.mode = CursorMode::Insert(carets);
+                vec![]
+            }
+            KillRingYank => {
+                if !cursor.is_insert() {
+                    return vec![];
+                }
+                // Text copied somewhere else is yanked before the older kills
+                if let Some(s) = clipboard.get_string() {
+                    register.push_kill(&s);
+                }
+                match register.kill_ring_len() {
+                    0 => vec![],
+                    len => Self::yank_kill(cursor, buffer, register, len - 1),
+                }
+            }
+            KillRingYankPop => {
+                let len = register.kill_ring_len();
+                let Some((start, end, index)) = kill_yank else {
+                    return vec![];
+                };
+                if len == 0 || !cursor.is_insert() || cursor.offset() != end {
+                    return vec![];
+                }
+                let index = if index == 0 { len - 1 } else { index - 1 };
+                cursor.mode = CursorMode::Insert(Selection::region(start, end));
+                Self::yank_kill(cursor, buffer, register, index)
+            }
+            SetMark => {
+                if cursor.is_insert() {
+                    let offset = cursor.offset();
+                    cursor.mode = CursorMode::Insert(Selection::caret(offset));
+                }
+                vec![]
+            }
+            ExchangePointAndMark => {
+                if let CursorMode::Insert(selection) = &cursor.mode {
+                    let mut exchanged = Selection::new();
+                    for region in selection.regions() {
+                        exchanged.add_region(SelRegion::new(
+                            region.end,
+                            region.start,
+                            None,
+                        ));
+                    }
+                    cursor.mode = CursorMode::Insert(exchanged);
+                }
+                vec![]
+            }
             Yank => {
                 match &cursor.mode {
                     CursorMode::Visual { start, end, .. } => {
@@ -1169,12 +1929,12 @@ pub fn do_edit<T: Clipboard>(
                 vec![]
             }
             Paste => {
-                let data = register.unnamed.clone();
+                let data = register.paste_data();
                 Self::do_paste(cursor, buffer, &data)
             }
             PasteBefore => {
                 let offset = cursor.offset();
-                let data = register.unnamed.clone();
+                let data = register.paste_data();
                 let mut local_cursor =
                     Cursor::new(CursorMode::Insert(Selection::new()), None, None);
                 local_cursor.set_offset(offset, false, false);
@@ -1642,12 +2402,29 @@ enum DuplicateDirection {
 mod test {
     use crate::{
         buffer::{rope_text::RopeText, Buffer},
+        command::EditCommand,
         cursor::{Cursor, CursorMode},
         editor::{DuplicateDirection, Editor},
+        mode::VisualMode,
+        register::{Clipboard, Register},
         selection::{SelRegion, Selection},
+        surround::Surround,
         syntax::Syntax,
     };
 
+    #[derive(Default)]
+    struct TestClipboard(Option<String>);
+
+    impl Clipboard for TestClipboard {
+        fn get_string(&mut self) -> Option<String> {
+            self.0.clone()
+        }
+
+        fn put_string(&mut self, s: impl AsRef<str>) {
+            self.0 = Some(s.as_ref().to_string());
+        }
+    }
+
     #[test]
     fn test_insert_simple() {
         let mut buffer = Buffer::new("abc");
@@ -1964,5 +2741,291 @@ fn check_multiple_cursor_match_insertion() {
         assert_eq!(cursor.mode, CursorMode::Insert(end_selection));
     }
 
-    // TODO(dbuga): add tests duplicating selections (multiple line blocks)
+    #[test]
+    fn kill_and_yank() {
+        let mut buffer = Buffer::new("first\nsecond\n");
+        let mut cursor =
+            Cursor::new(CursorMode::Insert(Selection::caret(0)), None, None);
+        let mut clipboard = TestClipboard::default();
+        let mut register = Register::default();
+        let mut edit = |cursor: &mut Cursor, buffer: &mut Buffer, cmd| {
+            Editor::do_edit(
+                cursor,
+                buffer,
+                &cmd,
+                &Syntax::plaintext(),
+                &mut clipboard,
+                false,
+                &mut register,
+                true,
+            );
+        };
+
+        // The second kill takes the line break and is added to the first
+        edit(&mut cursor, &mut buffer, EditCommand::KillLine);
+        edit(&mut cursor, &mut buffer, EditCommand::KillLine);
+        assert_eq!("second\n", buffer.slice_to_cow(0..buffer.len()));
+
+        cursor.mode = CursorMode::Insert(Selection::caret(7));
+        edit(&mut cursor, &mut buffer, EditCommand::KillRingYank);
+        assert_eq!("second\nfirst\n", buffer.slice_to_cow(0..buffer.len()));
+
+        cursor.mode = CursorMode::Insert(Selection::region(0, 6));
+        edit(&mut cursor, &mut buffer, EditCommand::KillRegion);
+        assert_eq!("\nfirst\n", buffer.slice_to_cow(0..buffer.len()));
+
+        cursor.mode = CursorMode::Insert(Selection::caret(7));
+        edit(&mut cursor, &mut buffer, EditCommand::KillRingYank);
+        assert_eq!("\nfirst\nsecond", buffer.slice_to_cow(0..buffer.len()));
+
+        edit(&mut cursor, &mut buffer, EditCommand::KillRingYankPop);
+        assert_eq!("\nfirst\nfirst\n", buffer.slice_to_cow(0..buffer.len()));
+        assert_eq!(cursor.offset(), 13);
+    }
+
+    #[test]
+    fn paste_reindents() {
+        // Copied from the middle of a line of a block that is indented more
+        let copied = "if x {\n            y();\n        }";
+        let paste = |cmd| {
+            let mut buffer = Buffer::new("fn a() {\n    \n}\n");
+            let mut cursor =
+                Cursor::new(CursorMode::Insert(Selection::caret(13)), None, None);
+            let mut clipboard = TestClipboard(Some(copied.to_string()));
+            Editor::do_edit(
+                &mut cursor,
+                &mut buffer,
+                &cmd,
+                &Syntax::plaintext(),
+                &mut clipboard,
+                false,
+                &mut Register::default(),
+                true,
+            );
+            buffer.slice_to_cow(0..buffer.len()).to_string()
+        };
+
+        assert_eq!(
+            paste(EditCommand::ClipboardPaste),
+            "fn a() {\n    if x {\n        y();\n    }\n}\n"
+        );
+        assert_eq!(
+            paste(EditCommand::ClipboardPasteWithoutFormatting),
+            "fn a() {\n    if x {\n            y();\n        }\n}\n"
+        );
+    }
+
+    fn run_edit(
+        cursor: &mut Cursor,
+        buffer: &mut Buffer,
+        cmd: EditCommand,
+        modal: bool,
+    ) {
+        Editor::do_edit(
+            cursor,
+            buffer,
+            &cmd,
+            &Syntax::plaintext(),
+            &mut TestClipboard::default(),
+            modal,
+            &mut Register::default(),
+            true,
+        );
+    }
+
+    #[test]
+    fn duplicate_selected_lines() {
+        let mut buffer = Buffer::new("a\nb\nc\n");
+        let mut cursor =
+            Cursor::new(CursorMode::Insert(Selection::region(0, 3)), None, None);
+        run_edit(
+            &mut cursor,
+            &mut buffer,
+            EditCommand::DuplicateLineDown,
+            false,
+        );
+        assert_eq!("a\nb\na\nb\nc\n", buffer.slice_to_cow(0..buffer.len()));
+
+        let mut buffer = Buffer::new("a\nb");
+        let mut cursor = Cursor::new(CursorMode::Normal(2), None, None);
+        run_edit(
+            &mut cursor,
+            &mut buffer,
+            EditCommand::DuplicateLineDown,
+            true,
+        );
+        assert_eq!("a\nb\nb", buffer.slice_to_cow(0..buffer.len()));
+        assert_eq!(cursor.offset(), 4);
+    }
+
+    #[test]
+    fn move_lines_in_one_step() {
+        let mut buffer = Buffer::new("a\nb\nc\nd");
+        let mut selection = Selection::new();
+        selection.add_region(SelRegion::caret(2));
+        selection.add_region(SelRegion::caret(7));
+        let mut cursor = Cursor::new(CursorMode::Insert(selection), None, None);
+
+        run_edit(&mut cursor, &mut buffer, EditCommand::MoveLineUp, false);
+        assert_eq!("b\na\nd\nc", buffer.slice_to_cow(0..buffer.len()));
+        let CursorMode::Insert(selection) = &cursor.mode else {
+            panic!("not in insert mode");
+        };
+        assert_eq!(
+            selection.regions(),
+            &[SelRegion::caret(0), SelRegion::caret(5)]
+        );
+
+        // The first line can't move up, so neither does the other one
+        run_edit(&mut cursor, &mut buffer, EditCommand::MoveLineUp, false);
+        assert_eq!("b\na\nd\nc", buffer.slice_to_cow(0..buffer.len()));
+
+        run_edit(&mut cursor, &mut buffer, EditCommand::Undo, false);
+        assert_eq!("a\nb\nc\nd", buffer.slice_to_cow(0..buffer.len()));
+    }
+
+    #[test]
+    fn move_line_reindents_across_blocks() {
+        let mut buffer = Buffer::new("fn a() {\n    x();\n}\ny();\n");
+        let mut cursor =
+            Cursor::new(CursorMode::Insert(Selection::caret(20)), None, None);
+
+        run_edit(&mut cursor, &mut buffer, EditCommand::MoveLineUp, false);
+        assert_eq!(
+            "fn a() {\n    x();\n    y();\n}\n",
+            buffer.slice_to_cow(0..buffer.len())
+        );
+        assert_eq!(cursor.offset(), 22);
+
+        run_edit(&mut cursor, &mut buffer, EditCommand::MoveLineDown, false);
+        assert_eq!(
+            "fn a() {\n    x();\n}\ny();\n",
+            buffer.slice_to_cow(0..buffer.len())
+        );
+        assert_eq!(cursor.offset(), 20);
+
+        cursor.mode = CursorMode::Insert(Selection::caret(13));
+        run_edit(&mut cursor, &mut buffer, EditCommand::MoveLineUp, false);
+        assert_eq!(
+            "x();\nfn a() {\n}\ny();\n",
+            buffer.slice_to_cow(0..buffer.len())
+        );
+        assert_eq!(cursor.offset(), 0);
+    }
+
+    #[test]
+    fn join_lines_of_each_caret() {
+        let mut buffer = Buffer::new("a\n  b\nc\n    d\n");
+        let mut selection = Selection::new();
+        selection.add_region(SelRegion::caret(0));
+        selection.add_region(SelRegion::caret(6));
+        let mut cursor = Cursor::new(CursorMode::Insert(selection), None, None);
+        run_edit(&mut cursor, &mut buffer, EditCommand::JoinLines, false);
+        assert_eq!("a b\nc d\n", buffer.slice_to_cow(0..buffer.len()));
+
+        let mut buffer = Buffer::new("x\n y\nz\n");
+        let mut cursor = Cursor::new(
+            CursorMode::Visual {
+                start: 0,
+                end: 5,
+                mode: VisualMode::Normal,
+            },
+            None,
+            None,
+        );
+        run_edit(&mut cursor, &mut buffer, EditCommand::JoinLines, true);
+        assert_eq!("x y z\n", buffer.slice_to_cow(0..buffer.len()));
+        assert_eq!(cursor.mode, CursorMode::Normal(3));
+    }
+
+    #[test]
+    fn transpose_characters() {
+        let mut buffer = Buffer::new("abc\nde\n");
+        let mut selection = Selection::new();
+        selection.add_region(SelRegion::caret(1));
+        selection.add_region(SelRegion::caret(6));
+        let mut cursor = Cursor::new(CursorMode::Insert(selection), None, None);
+
+        run_edit(
+            &mut cursor,
+            &mut buffer,
+            EditCommand::TransposeCharacters,
+            false,
+        );
+        assert_eq!("bac\ned\n", buffer.slice_to_cow(0..buffer.len()));
+        let CursorMode::Insert(selection) = &cursor.mode else {
+            panic!("not in insert mode");
+        };
+        assert_eq!(
+            selection.regions(),
+            &[SelRegion::caret(2), SelRegion::caret(6)]
+        );
+    }
+
+    #[test]
+    fn transform_word_and_lines() {
+        let mut buffer = Buffer::new("let fooBar = 1;\n");
+        let mut cursor =
+            Cursor::new(CursorMode::Insert(Selection::caret(6)), None, None);
+        run_edit(
+            &mut cursor,
+            &mut buffer,
+            EditCommand::TransformToSnakeCase,
+            false,
+        );
+        assert_eq!("let foo_bar = 1;\n", buffer.slice_to_cow(0..buffer.len()));
+        assert_eq!(cursor.offset(), 6);
+
+        // A caret sorts all the lines
+        let mut buffer = Buffer::new("c\na\nb\n");
+        let mut cursor =
+            Cursor::new(CursorMode::Insert(Selection::caret(1)), None, None);
+        run_edit(
+            &mut cursor,
+            &mut buffer,
+            EditCommand::SortLinesAscending,
+            false,
+        );
+        assert_eq!("a\nb\nc\n", buffer.slice_to_cow(0..buffer.len()));
+        assert_eq!(cursor.offset(), 1);
+    }
+
+    #[test]
+    fn insert_sequence() {
+        let mut buffer = Buffer::new("7\nx\nx\n");
+        let mut selection = Selection::new();
+        selection.add_region(SelRegion::new(0, 1, None));
+        selection.add_region(SelRegion::new(2, 3, None));
+        selection.add_region(SelRegion::new(4, 5, None));
+        let mut cursor = Cursor::new(CursorMode::Insert(selection), None, None);
+        run_edit(&mut cursor, &mut buffer, EditCommand::InsertSequence, false);
+        assert_eq!("7\n8\n9\n", buffer.slice_to_cow(0..buffer.len()));
+    }
+
+    #[test]
+    fn surround() {
+        let surround = |cursor: &mut Cursor, surround| {
+            let mut buffer = Buffer::new("foo(bar) x");
+            Editor::surround(cursor, &mut buffer, &Syntax::plaintext(), surround);
+            buffer.slice_to_cow(0..buffer.len()).to_string()
+        };
+
+        let mut cursor =
+            Cursor::new(CursorMode::Insert(Selection::caret(5)), None, None);
+        assert_eq!(surround(&mut cursor, Surround::Delete('(')), "foobar x");
+        assert_eq!(
+            surround(&mut cursor, Surround::Change('b', '[')),
+            "foo[bar] x"
+        );
+
+        let mut cursor =
+            Cursor::new(CursorMode::Insert(Selection::region(0, 3)), None, None);
+        assert_eq!(surround(&mut cursor, Surround::Add('"')), "\"foo\"(bar) x");
+        assert_eq!(cursor.mode, CursorMode::Insert(Selection::region(1, 4)));
+
+        // The word of the cursor in normal mode
+        let mut cursor = Cursor::new(CursorMode::Normal(9), None, None);
+        assert_eq!(surround(&mut cursor, Surround::Add(')')), "foo(bar) (x)");
+        assert_eq!(cursor.mode, CursorMode::Normal(9));
+    }
 }
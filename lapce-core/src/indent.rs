@@ -1,3 +1,5 @@
+use std::iter;
+
 use lapce_xi_rope::Rope;
 
 use crate::{
@@ -50,6 +52,14 @@ pub fn as_str(&self) -> &'static str {
             }
         }
     }
+
+    /// How the style is shown to the user, like `Spaces: 4`.
+    pub fn name(&self) -> String {
+        match self {
+            IndentStyle::Tabs => "Tabs".to_string(),
+            IndentStyle::Spaces(n) => format!("Spaces: {n}"),
+        }
+    }
 }
 
 pub fn create_edit<'s>(
@@ -87,6 +97,68 @@ pub fn create_outdent<'s>(
     Some((Selection::region(start, offset), ""))
 }
 
+/// Change the indentation of pasted text so that its least indented lines get
+/// `indent`, keeping how much more the other lines are indented than them.
+///
+/// Unless `whole_lines`, the first line is left alone, since it goes after what
+/// is before the cursor. It was then likely copied from the middle of a line, so
+/// it only counts towards the least indentation if it has some.
+pub fn reindent(
+    content: &str,
+    indent: &str,
+    indent_unit: &str,
+    whole_lines: bool,
+) -> String {
+    let tab_width = if indent_unit.starts_with('\t') {
+        4
+    } else {
+        indent_unit.len()
+    };
+    let width = |line: &str| {
+        line.chars()
+            .take_while(|c| *c == ' ' || *c == '\t')
+            .map(|c| if c == '\t' { tab_width } else { 1 })
+            .sum::<usize>()
+    };
+    let is_blank = |line: &str| line.trim().is_empty();
+
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let base = lines
+        .iter()
+        .enumerate()
+        .filter(|(i, line)| {
+            !is_blank(line)
+                && (*i > 0 || whole_lines || line.starts_with([' ', '\t']))
+        })
+        .map(|(_, line)| width(line))
+        .min()
+        .unwrap_or(0);
+
+    let mut result = String::with_capacity(content.len());
+    for (i, line) in lines.iter().enumerate() {
+        if i == 0 && !whole_lines {
+            result.push_str(line);
+            continue;
+        }
+        let text = line.trim_start_matches([' ', '\t']);
+        if is_blank(line) {
+            // Only the line break is kept, not trailing whitespace
+            result.push_str(&line[line.trim_end_matches(['\r', '\n']).len()..]);
+            continue;
+        }
+        let extra = width(line).saturating_sub(base);
+        result.push_str(indent);
+        if indent_unit.starts_with('\t') {
+            result.extend(iter::repeat('\t').take(extra / tab_width));
+            result.extend(iter::repeat(' ').take(extra % tab_width));
+        } else {
+            result.extend(iter::repeat(' ').take(extra));
+        }
+        result.push_str(text);
+    }
+    result
+}
+
 /// Attempts to detect the indentation style used in a document.
 ///
 /// Returns the indentation style if the auto-detect confidence is
@@ -206,3 +278,34 @@ pub fn auto_detect_indent_style(document_text: &Rope) -> Option<IndentStyle> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::reindent;
+
+    #[test]
+    fn test_reindent_whole_lines() {
+        let content = "    if x {\n        y();\n\n    }\n";
+        assert_eq!(
+            reindent(content, "\t", "    ", true),
+            "\tif x {\n\t    y();\n\n\t}\n"
+        );
+    }
+
+    #[test]
+    fn test_reindent_from_middle_of_line() {
+        // Copied from `if`, so the first line has lost its indentation
+        let content = "if x {\n        y();\n    }";
+        assert_eq!(
+            reindent(content, "  ", "  ", false),
+            "if x {\n      y();\n  }"
+        );
+    }
+
+    #[test]
+    fn test_reindent_with_tabs() {
+        let content = "a\n\t\tb\r\n\tc";
+        assert_eq!(reindent(content, "", "\t", false), "a\n\tb\r\nc");
+        assert_eq!(reindent("  a\n      b\n", "\t", "\t", true), "\ta\n\t\tb\n");
+    }
+}
@@ -1,23 +1,33 @@
 use std::{collections::HashSet, path::Path, str::FromStr};
 
 use anyhow::Result;
+use lapce_xi_rope::Rope;
 use once_cell::sync::Lazy;
+use serde::Deserialize;
 use strum_macros::{AsRefStr, AsStaticStr, Display, EnumMessage, EnumString};
 use tracing::{debug, error};
-use tree_sitter::TreeCursor;
+use tree_sitter::{Query, TreeCursor};
 
 use crate::{
+    buffer::rope_text::{RopeText, RopeTextRef},
     directory::Directory,
     syntax::highlight::{HighlightConfiguration, HighlightIssue},
 };
 
-pub static RUNTIME_LANGUAGES: Lazy<Vec<SyntaxProperties>> = Lazy::new(Vec::new);
+/// Languages that aren't built in, but added by `languages.toml` in the grammars
+/// directory.
+pub static RUNTIME_LANGUAGES: Lazy<Vec<RuntimeLanguage>> =
+    Lazy::new(load_runtime_languages);
+
+const RUNTIME_LANGUAGES_FILE_NAME: &str = "languages.toml";
 
 #[allow(dead_code)]
 const DEFAULT_CODE_LENS_LIST: &[&str] = &["source_file"];
 #[allow(dead_code)]
 const DEFAULT_CODE_LENS_IGNORE_LIST: &[&str] = &["source_file"];
 const DEFAULT_QUERIES: include_dir::Dir = include_dir::include_dir!("queries");
+/// How many lines at the start and the end of a file are searched for a modeline.
+const MODELINE_LINES: usize = 5;
 
 #[macro_export]
 macro_rules! comment_properties {
@@ -65,8 +75,7 @@ macro_rules! comment_properties {
 
 #[derive(Eq, PartialEq, Hash, Clone, Copy, Debug, PartialOrd, Ord, Default)]
 pub struct SyntaxProperties {
-    /// An extra check to make sure that the array elements are in the correct order.  
-    /// If this id does not match the enum value, a panic will happen with a debug assertion message.
+    /// The language these are the properties of.
     id: LapceLanguage,
 
     /// All tokens that can be used for comments in language
@@ -123,8 +132,9 @@ struct CommentProperties {
     multi_line_prefix: Option<&'static str>,
 }
 
-/// NOTE: Keep the enum variants "fieldless" so they can cast to usize as array
-/// indices into the LANGUAGES array.  See method `LapceLanguage::properties`.
+/// NOTE: Keep the enum variants of the built in languages "fieldless", only
+/// `Runtime` refers to the languages added at runtime by its index into
+/// `RUNTIME_LANGUAGES`.  See method `LapceLanguage::properties`.
 ///
 /// Do not assign values to the variants because the number of variants and
 /// number of elements in the LANGUAGES array change as different features
@@ -210,6 +220,8 @@ pub enum LapceLanguage {
     Latex,
     #[strum(message = "Lua")]
     Lua,
+    #[strum(message = "Makefile")]
+    Make,
     #[strum(message = "Markdown")]
     Markdown,
     #[strum(serialize = "markdown.inline")]
@@ -264,10 +276,14 @@ pub enum LapceLanguage {
     Yaml,
     #[strum(message = "Zig")]
     Zig,
+
+    /// A language added at runtime, by its index into `RUNTIME_LANGUAGES`.
+    #[strum(disabled)]
+    Runtime(usize),
 }
 
-/// NOTE: Elements in the array must be in the same order as the enum variants of
-/// `LapceLanguage` as they will be accessed using the enum variants as indices.
+/// NOTE: Keep the elements in the array in the same order as the enum variants of
+/// `LapceLanguage`, every variant besides `Runtime` needs one.
 const LANGUAGES: &[SyntaxProperties] = &[
     // Languages
     SyntaxProperties {
@@ -354,7 +370,7 @@ pub enum LapceLanguage {
         id: LapceLanguage::Cmake,
 
         indent: "  ",
-        files: &[],
+        files: &["cmakelists.txt"],
         extensions: &["cmake"],
 
         comment: comment_properties!("#"),
@@ -929,6 +945,17 @@ pub enum LapceLanguage {
         #[cfg(not(feature = "lang-lua"))]
         tree_sitter: None,
     },
+    SyntaxProperties {
+        id: LapceLanguage::Make,
+
+        indent: "\t",
+        files: &["makefile", "gnumakefile"],
+        extensions: &["mk", "mak"],
+
+        comment: comment_properties!("#"),
+
+        tree_sitter: None,
+    },
     SyntaxProperties {
         id: LapceLanguage::Markdown,
 
@@ -1520,6 +1547,7 @@ pub enum LapceLanguage {
 impl LapceLanguage {
     const HIGHLIGHTS_QUERIES_FILE_NAME: &'static str = "highlights.scm";
     const HIGHLIGHTS_INJECTIONS_FILE_NAME: &'static str = "injections.scm";
+    const TEXTOBJECTS_QUERIES_FILE_NAME: &'static str = "textobjects.scm";
     #[cfg(unix)]
     const SYSTEM_GRAMMARS_DIRECTORY: &'static str = "/usr/lib";
     #[cfg(unix)]
@@ -1530,22 +1558,129 @@ pub fn from_path(path: &Path) -> LapceLanguage {
     }
 
     fn from_path_raw(path: &Path) -> Option<LapceLanguage> {
-        let filename = path.file_stem()?.to_str()?.to_lowercase();
-        let extension = path.extension()?.to_str()?.to_lowercase();
+        let filename = path.file_name()?.to_str()?.to_lowercase();
+        let stem = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .map(|s| s.to_lowercase());
+        let extension = path
+            .extension()
+            .and_then(|s| s.to_str())
+            .map(|s| s.to_lowercase());
         // NOTE: This is a linear search.  It is assumed that this function
         // isn't called in any tight loop.
-        for properties in LANGUAGES {
-            if properties.files.contains(&filename.as_str())
-                || properties.extensions.contains(&extension.as_str())
-            {
-                return Some(properties.id);
-            }
+        // The whole file name wins over the extension, and the extension over
+        // the name without it, so `Dockerfile.dev` is found but `makefile.py`
+        // is still Python
+        Self::all_properties()
+            .find(|properties| properties.files.contains(&filename.as_str()))
+            .or_else(|| {
+                let extension = extension?;
+                Self::all_properties().find(|properties| {
+                    properties.extensions.contains(&extension.as_str())
+                })
+            })
+            .or_else(|| {
+                let stem = stem?;
+                Self::all_properties()
+                    .find(|properties| properties.files.contains(&stem.as_str()))
+            })
+            .map(|properties| properties.id)
+    }
+
+    /// Detect the language from the file's name and content. A modeline in the
+    /// content decides over the file name, while a shebang line is only used
+    /// when the file name doesn't say which language it is.
+    pub fn from_content(path: &Path, text: &Rope) -> LapceLanguage {
+        Self::from_modeline(text)
+            .or_else(|| Self::from_path_raw(path))
+            .or_else(|| Self::from_shebang(text))
+            .unwrap_or(LapceLanguage::Plaintext)
+    }
+
+    /// The language set by a Vim modeline such as `vim: set ft=python:` or an
+    /// Emacs one such as `-*- mode: python -*-`, in the first or last lines.
+    pub fn from_modeline(text: &Rope) -> Option<LapceLanguage> {
+        let text = RopeTextRef::new(text);
+        let last_line = text.last_line();
+        let head_end = MODELINE_LINES.min(last_line + 1);
+        let tail_start = (last_line + 1).saturating_sub(MODELINE_LINES);
+        (0..head_end)
+            .chain(tail_start.max(head_end)..=last_line)
+            .find_map(|line| {
+                let content = text.line_content(line);
+                emacs_modeline(&content)
+                    .or_else(|| vim_modeline(&content))
+                    .and_then(Self::from_alias)
+            })
+    }
+
+    /// The language of the interpreter in a shebang line like
+    /// `#!/usr/bin/env python3`.
+    pub fn from_shebang(text: &Rope) -> Option<LapceLanguage> {
+        let text = RopeTextRef::new(text);
+        let line = text.line_content(0);
+        let mut args = line.strip_prefix("#!")?.split_whitespace();
+        let mut interpreter = args.next()?.rsplit('/').next()?;
+        if interpreter == "env" {
+            interpreter = args.find(|arg| !arg.starts_with('-'))?;
         }
+        let interpreter = interpreter
+            .trim_end_matches(|c: char| c.is_ascii_digit() || c == '.')
+            .to_lowercase();
+        Some(match interpreter.as_str() {
+            "sh" | "dash" | "ksh" | "mksh" => LapceLanguage::Sh,
+            "bash" | "zsh" => LapceLanguage::Bash,
+            "python" | "pypy" => LapceLanguage::Python,
+            "node" | "nodejs" | "deno" | "bun" => LapceLanguage::Javascript,
+            "ts-node" => LapceLanguage::Typescript,
+            "ruby" | "jruby" => LapceLanguage::Ruby,
+            "php" => LapceLanguage::Php,
+            "lua" | "luajit" => LapceLanguage::Lua,
+            "elixir" => LapceLanguage::Elixir,
+            "escript" => LapceLanguage::Erlang,
+            "rscript" => LapceLanguage::R,
+            "julia" => LapceLanguage::Julia,
+            "runhaskell" | "runghc" => LapceLanguage::Haskell,
+            "guile" | "scheme" => LapceLanguage::Scheme,
+            "swift" => LapceLanguage::Swift,
+            "dart" => LapceLanguage::Dart,
+            "rust-script" => LapceLanguage::Rust,
+            "make" => LapceLanguage::Make,
+            _ => return None,
+        })
+    }
 
-        None
+    /// The language for a name used by other editors, like Vim's `cpp` or
+    /// Emacs' `c++`.
+    fn from_alias(name: &str) -> Option<LapceLanguage> {
+        let name = name.to_lowercase();
+        let name = name.strip_suffix("-mode").unwrap_or(&name);
+        Some(match name {
+            "c++" => LapceLanguage::Cpp,
+            "cs" | "c#" => LapceLanguage::Csharp,
+            "js" | "js2" => LapceLanguage::Javascript,
+            "ts" => LapceLanguage::Typescript,
+            "py" => LapceLanguage::Python,
+            "rb" => LapceLanguage::Ruby,
+            "md" => LapceLanguage::Markdown,
+            "tex" => LapceLanguage::Latex,
+            "yml" => LapceLanguage::Yaml,
+            "shell" | "shell-script" | "zsh" => LapceLanguage::Bash,
+            "makefile" | "makefile-gmake" => LapceLanguage::Make,
+            "docker" => LapceLanguage::Dockerfile,
+            name => return LapceLanguage::from_str(name).ok(),
+        })
     }
 
+    /// The language with the given id or display name, such as `cpp` or `C++`.
     pub fn from_name(name: &str) -> Option<LapceLanguage> {
+        if let Some(language) = Self::all_properties()
+            .map(|properties| properties.id)
+            .find(|language| language.name().eq_ignore_ascii_case(name))
+        {
+            return Some(language);
+        }
         match LapceLanguage::from_str(name.to_lowercase().as_str()) {
             Ok(v) => Some(v),
             Err(e) => {
@@ -1563,29 +1698,49 @@ pub fn languages() -> Vec<&'static str> {
                     .unwrap_or(strum::AsStaticRef::as_static(&l.id)),
             )
         }
+        for l in RUNTIME_LANGUAGES.iter() {
+            langs.push(l.name);
+        }
         langs
     }
 
-    // NOTE: Instead of using `&LANGUAGES[*self as usize]` directly, the
-    // `debug_assertion` gives better feedback should something has gone wrong
-    // badly.
-    fn properties(&self) -> &SyntaxProperties {
-        let i = *self as usize;
-        let l = &LANGUAGES[i];
-        debug_assert!(
-            l.id == *self,
-            "LANGUAGES[{i}]: Setting::id mismatch: {:?} != {:?}",
-            l.id,
-            self
-        );
-        l
+    // NOTE: The enum has a variant with a field, so it can't be cast to an
+    // index into `LANGUAGES`, and the properties are searched for instead.
+    fn properties(&self) -> &'static SyntaxProperties {
+        if let LapceLanguage::Runtime(i) = self {
+            return &RUNTIME_LANGUAGES[*i].properties;
+        }
+        let l = LANGUAGES.iter().find(|l| l.id == *self);
+        debug_assert!(l.is_some(), "LANGUAGES: no properties for {self:?}");
+        l.unwrap_or(&LANGUAGES[0])
+    }
+
+    /// The properties of the built in languages and the ones added at runtime,
+    /// where the runtime ones come first so they can take over file extensions.
+    fn all_properties() -> impl Iterator<Item = &'static SyntaxProperties> {
+        RUNTIME_LANGUAGES
+            .iter()
+            .map(|language| &language.properties)
+            .chain(LANGUAGES.iter())
     }
 
     pub fn name(&self) -> &'static str {
+        if let LapceLanguage::Runtime(i) = self {
+            return RUNTIME_LANGUAGES[*i].name;
+        }
         strum::EnumMessage::get_message(self)
             .unwrap_or(strum::AsStaticRef::as_static(self))
     }
 
+    /// The id language servers know a language added at runtime by, if it has
+    /// one.
+    pub fn runtime_language_id(&self) -> Option<&'static str> {
+        match self {
+            LapceLanguage::Runtime(i) => RUNTIME_LANGUAGES[*i].language_id,
+            _ => None,
+        }
+    }
+
     fn tree_sitter(&self) -> Option<TreeSitterProperties> {
         self.properties().tree_sitter
     }
@@ -1723,6 +1878,54 @@ fn get_grammar_query(&self) -> (String, String) {
         ("".to_string(), "".to_string())
     }
 
+    /// The `textobjects.scm` query of the language, looked up in the same
+    /// places as the highlights.
+    fn get_textobjects_query(&self) -> Option<String> {
+        let query_name = self.query_name();
+
+        if let Some(queries_dir) = Directory::queries_directory() {
+            let file = queries_dir
+                .join(&query_name)
+                .join(Self::TEXTOBJECTS_QUERIES_FILE_NAME);
+            if let Ok(s) = std::fs::read_to_string(file) {
+                return Some(s);
+            }
+        }
+
+        #[cfg(unix)]
+        {
+            let file = Path::new(Self::SYSTEM_QUERIES_DIRECTORY)
+                .join(&query_name)
+                .join(Self::TEXTOBJECTS_QUERIES_FILE_NAME);
+            if let Ok(s) = std::fs::read_to_string(file) {
+                return Some(s);
+            }
+        }
+
+        DEFAULT_QUERIES
+            .get_file(format!(
+                "{query_name}/{}",
+                Self::TEXTOBJECTS_QUERIES_FILE_NAME
+            ))
+            .and_then(|f| f.contents_utf8())
+            .map(|s| s.to_string())
+    }
+
+    pub(crate) fn new_textobjects_query(&self) -> Option<Query> {
+        let grammar = self.get_grammar()?;
+        let query = self.get_textobjects_query()?;
+        match Query::new(grammar, &query) {
+            Ok(query) => Some(query),
+            Err(err) => {
+                error!(
+                    "Encountered {err:?} while parsing the text objects of {}",
+                    self.name()
+                );
+                None
+            }
+        }
+    }
+
     pub(crate) fn new_highlight_config(
         &self,
     ) -> Result<HighlightConfiguration, HighlightIssue> {
@@ -1732,7 +1935,7 @@ pub(crate) fn new_highlight_config(
         match HighlightConfiguration::new(grammar, &query, &injection, "") {
             Ok(x) => Ok(x),
             Err(x) => {
-                let str = format!("Encountered {x:?} while trying to construct HighlightConfiguration for {}", strum::EnumMessage::get_message(self).unwrap_or(self.as_ref()));
+                let str = format!("Encountered {x:?} while trying to construct HighlightConfiguration for {}", self.name());
                 error!("{str}");
                 Err(HighlightIssue::Error(str))
             }
@@ -1825,3 +2028,151 @@ fn walk_tree(
         cursor.goto_parent();
     }
 }
+
+/// The language in an Emacs modeline, `-*- mode: python -*-` or `-*- python -*-`.
+fn emacs_modeline(line: &str) -> Option<&str> {
+    let (_, rest) = line.split_once("-*-")?;
+    let (vars, _) = rest.split_once("-*-")?;
+    let vars = vars.trim();
+    if !vars.contains(':') {
+        return Some(vars).filter(|mode| !mode.is_empty());
+    }
+    vars.split(';').find_map(|var| {
+        let (key, value) = var.split_once(':')?;
+        if key.trim().eq_ignore_ascii_case("mode") {
+            Some(value.trim())
+        } else {
+            None
+        }
+    })
+}
+
+/// The file type in a Vim modeline, such as `vim: set ft=python:` or
+/// `vi: filetype=sh`.
+fn vim_modeline(line: &str) -> Option<&str> {
+    let start = ["vim:", "vi:", "ex:"].iter().find_map(|prefix| {
+        line.match_indices(prefix)
+            .find(|(i, _)| {
+                *i == 0 || line[..*i].ends_with(|c: char| c.is_whitespace())
+            })
+            .map(|(i, _)| i + prefix.len())
+    })?;
+    line[start..]
+        .split(|c: char| c.is_whitespace() || c == ':')
+        .find_map(|option| {
+            let (key, value) = option.split_once('=')?;
+            match key {
+                "ft" | "filetype" | "syntax" | "syn" => Some(value),
+                _ => None,
+            }
+        })
+        .filter(|value| !value.is_empty())
+}
+
+/// A language added at runtime. Its grammar is loaded from a dynamic library in
+/// the grammars directory, and its highlight queries from the queries directory.
+#[derive(Debug, Clone)]
+pub struct RuntimeLanguage {
+    pub name: &'static str,
+    /// The id language servers know the language by.
+    pub language_id: Option<&'static str>,
+    properties: SyntaxProperties,
+}
+
+#[derive(Deserialize)]
+struct RuntimeLanguagesFile {
+    #[serde(default)]
+    language: Vec<RuntimeLanguageConfig>,
+}
+
+/// A language in `languages.toml`, for example
+///
+/// ```toml
+/// [[language]]
+/// name = "Gleam"
+/// extensions = ["gleam"]
+/// comment = "//"
+/// language-id = "gleam"
+/// ```
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct RuntimeLanguageConfig {
+    name: String,
+    /// The grammar is the `tree-sitter-{grammar}` library, which is the lowercase
+    /// name by default.
+    grammar: Option<String>,
+    /// The folder of the highlight queries, which is the grammar by default.
+    query: Option<String>,
+    #[serde(default)]
+    files: Vec<String>,
+    #[serde(default)]
+    extensions: Vec<String>,
+    comment: Option<String>,
+    indent: Option<String>,
+    language_id: Option<String>,
+}
+
+fn load_runtime_languages() -> Vec<RuntimeLanguage> {
+    let Some(path) = Directory::grammars_directory()
+        .map(|dir| dir.join(RUNTIME_LANGUAGES_FILE_NAME))
+    else {
+        return Vec::new();
+    };
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        return Vec::new();
+    };
+    let file: RuntimeLanguagesFile = match toml::from_str(&content) {
+        Ok(file) => file,
+        Err(err) => {
+            error!("Failed to parse {}: {err}", path.display());
+            return Vec::new();
+        }
+    };
+
+    // The languages live as long as the program, so their strings are leaked to
+    // fit the properties of the built in ones
+    fn leak(s: String) -> &'static str {
+        Box::leak(s.into_boxed_str())
+    }
+    fn leak_lowercase(list: Vec<String>) -> &'static [&'static str] {
+        let list: Vec<&'static str> =
+            list.into_iter().map(|s| leak(s.to_lowercase())).collect();
+        Box::leak(list.into_boxed_slice())
+    }
+
+    file.language
+        .into_iter()
+        .enumerate()
+        .map(|(i, config)| {
+            let grammar =
+                config.grammar.unwrap_or_else(|| config.name.to_lowercase());
+            let grammar = leak(grammar);
+            let query = config.query.map(leak).unwrap_or(grammar);
+            let comment = match config.comment {
+                Some(comment) => comment_properties!(leak(comment)),
+                None => comment_properties!(),
+            };
+            RuntimeLanguage {
+                name: leak(config.name),
+                language_id: config.language_id.map(leak),
+                properties: SyntaxProperties {
+                    id: LapceLanguage::Runtime(i),
+                    comment,
+                    indent: config.indent.map(leak).unwrap_or("    "),
+                    files: leak_lowercase(config.files),
+                    extensions: leak_lowercase(config.extensions),
+                    tree_sitter: Some(TreeSitterProperties {
+                        language: None,
+                        grammar: Some(grammar),
+                        query: Some(query),
+                        code_lens: (
+                            DEFAULT_CODE_LENS_LIST,
+                            DEFAULT_CODE_LENS_IGNORE_LIST,
+                        ),
+                        sticky_headers: &[],
+                    }),
+                },
+            }
+        })
+        .collect()
+}
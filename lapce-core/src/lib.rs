@@ -3,6 +3,7 @@
 pub mod buffer;
 pub mod char_buffer;
 pub mod chars;
+pub mod color;
 pub mod command;
 pub mod cursor;
 pub mod directory;
@@ -11,13 +12,18 @@
 pub mod indent;
 pub mod language;
 pub mod lens;
+pub mod link;
 pub mod meta;
 pub mod mode;
 pub mod movement;
 pub mod paragraph;
 pub mod register;
+pub mod search;
 pub mod selection;
 pub mod soft_tab;
+pub mod spell;
 pub mod style;
+pub mod surround;
 pub mod syntax;
+pub mod transform;
 pub mod word;
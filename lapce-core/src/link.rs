@@ -0,0 +1,195 @@
+//! Links in the text, which are URLs or the paths of files with the line and
+//! column to go to after them, like `src/main.rs:10:5`.
+
+/// The schemes of the URLs that are recognized.
+const SCHEMES: &[&str] = &["https://", "http://", "file://", "ftp://"];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LinkTarget {
+    Url(String),
+    /// A relative or absolute path, with the line and column after it counted
+    /// from 1
+    Path {
+        path: String,
+        line: Option<usize>,
+        col: Option<usize>,
+    },
+}
+
+/// A link in the text, with the range it is written in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextLink {
+    pub start: usize,
+    pub end: usize,
+    pub target: LinkTarget,
+}
+
+/// The link the character at `index` is in, if it is in something that looks
+/// like a URL or a path. Whether a path is of a file that exists is for the
+/// caller to check.
+pub fn link_at(text: &str, index: usize) -> Option<TextLink> {
+    if let Some((start, end)) = url_ranges(text)
+        .into_iter()
+        .find(|(start, end)| *start <= index && index < *end)
+    {
+        return Some(TextLink {
+            start,
+            end,
+            target: LinkTarget::Url(text[start..end].to_string()),
+        });
+    }
+
+    if !text.is_char_boundary(index) || !text[index..].starts_with(is_path_char) {
+        return None;
+    }
+    let start = text[..index]
+        .rfind(|c| !is_path_char(c))
+        .map_or(0, |i| i + 1);
+    let end = text[index..]
+        .find(|c| !is_path_char(c))
+        .map_or(text.len(), |i| index + i);
+    let token = text[start..end].trim_end_matches(['.', ',', ':']);
+    let (path, line, col) = split_line_col(token);
+    let path = path.trim_end_matches(['.', ',', ':']);
+    // Something like `a.b` or `a/b` at least, and not only punctuation
+    let looks_like_path = path
+        .trim_matches(|c: char| !c.is_alphanumeric())
+        .contains(['.', '/', '\\']);
+    if !looks_like_path || index >= start + token.len() {
+        return None;
+    }
+    Some(TextLink {
+        start,
+        end: start + token.len(),
+        target: LinkTarget::Path {
+            path: path.to_string(),
+            line,
+            col,
+        },
+    })
+}
+
+/// Split the line and column written after a path or URL, like `:10` or
+/// `:10:5`, off it.
+pub fn split_line_col(text: &str) -> (&str, Option<usize>, Option<usize>) {
+    let number = |text: &str| -> Option<(usize, usize)> {
+        let (rest, n) = text.rsplit_once(':')?;
+        Some((rest.len(), n.parse().ok()?))
+    };
+    match number(text) {
+        Some((len, last)) => match number(&text[..len]) {
+            Some((len, line)) => (&text[..len], Some(line), Some(last)),
+            None => (&text[..len], Some(last), None),
+        },
+        None => (text, None, None),
+    }
+}
+
+fn is_path_char(c: char) -> bool {
+    c.is_alphanumeric() || "_-./\\~+@:".contains(c)
+}
+
+/// The ranges of the URLs in the text. A URL ends at whitespace or a quote, and
+/// punctuation or a closing bracket after it are left out, unless the URL has
+/// the opening bracket.
+fn url_ranges(text: &str) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while i < text.len() {
+        let rest = &text[i..];
+        let after_word = text[..i]
+            .chars()
+            .next_back()
+            .is_some_and(char::is_alphanumeric);
+        let Some(scheme) = SCHEMES
+            .iter()
+            .find(|scheme| !after_word && rest.starts_with(**scheme))
+        else {
+            i += rest.chars().next().map_or(1, char::len_utf8);
+            continue;
+        };
+
+        let len = rest
+            .find(|c: char| c.is_whitespace() || "\"'`<>".contains(c))
+            .unwrap_or(rest.len());
+        let mut url = &rest[..len];
+        loop {
+            let trimmed = url.trim_end_matches(['.', ',', ';', ':', '!', '?']);
+            let trimmed = [('(', ')'), ('[', ']'), ('{', '}')].iter().fold(
+                trimmed,
+                |url, (open, close)| {
+                    if url.ends_with(*close)
+                        && url.matches(*open).count() < url.matches(*close).count()
+                    {
+                        &url[..url.len() - 1]
+                    } else {
+                        url
+                    }
+                },
+            );
+            if trimmed.len() == url.len() {
+                break;
+            }
+            url = trimmed;
+        }
+        if url.len() > scheme.len() {
+            ranges.push((i, i + url.len()));
+        }
+        i += len.max(1);
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url_at() {
+        let text = "see (https://lapce.dev/docs_(1)), or file:///tmp/a.rs:3.";
+        assert_eq!(
+            link_at(text, 10),
+            Some(TextLink {
+                start: 5,
+                end: 31,
+                target: LinkTarget::Url("https://lapce.dev/docs_(1)".to_string()),
+            })
+        );
+        assert_eq!(
+            link_at(text, 40).map(|link| link.target),
+            Some(LinkTarget::Url("file:///tmp/a.rs:3".to_string()))
+        );
+        assert_eq!(link_at(text, 1), None);
+    }
+
+    #[test]
+    fn test_path_at() {
+        let text = "error at src/main.rs:10:5: oops, self.x";
+        assert_eq!(
+            link_at(text, 12),
+            Some(TextLink {
+                start: 9,
+                end: 25,
+                target: LinkTarget::Path {
+                    path: "src/main.rs".to_string(),
+                    line: Some(10),
+                    col: Some(5),
+                },
+            })
+        );
+        assert_eq!(
+            link_at(text, 35).map(|link| link.target),
+            Some(LinkTarget::Path {
+                path: "self.x".to_string(),
+                line: None,
+                col: None,
+            })
+        );
+        assert_eq!(link_at(text, 2), None);
+        assert_eq!(link_at("a ... b", 3), None);
+        assert_eq!(
+            split_line_col("Cargo.toml:7"),
+            ("Cargo.toml", Some(7), None)
+        );
+    }
+}
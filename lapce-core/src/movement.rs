@@ -1,3 +1,5 @@
+use crate::syntax::SyntaxItem;
+
 #[derive(Clone, Debug)]
 pub enum LinePosition {
     First,
@@ -21,11 +23,15 @@ pub enum Movement {
     WordEndForward,
     WordForward,
     WordBackward,
+    SubwordForward,
+    SubwordBackward,
     NextUnmatched(char),
     PreviousUnmatched(char),
     MatchPairs,
     ParagraphForward,
     ParagraphBackward,
+    NextItem(SyntaxItem),
+    PreviousItem(SyntaxItem),
 }
 
 impl PartialEq for Movement {
@@ -61,6 +67,8 @@ pub fn is_jump(&self) -> bool {
                 | Movement::DocumentEnd
                 | Movement::ParagraphForward
                 | Movement::ParagraphBackward
+                | Movement::NextItem(_)
+                | Movement::PreviousItem(_)
         )
     }
 
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use crate::mode::VisualMode;
 
 pub trait Clipboard {
@@ -11,10 +13,83 @@ pub struct RegisterData {
     pub mode: VisualMode,
 }
 
+impl RegisterData {
+    /// Text from the system clipboard, which is pasted as whole lines if it ends
+    /// with a line break.
+    pub fn from_clipboard(content: String) -> Self {
+        let mode = if content.ends_with('\n') {
+            VisualMode::Linewise
+        } else {
+            VisualMode::Normal
+        };
+        Self { content, mode }
+    }
+}
+
+/// A register picked with `"` before a yank, delete or paste in modal editing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegisterName {
+    /// `a` to `z`. `A` to `Z` add to the end of the same registers instead of
+    /// replacing what's in them.
+    Named { name: char, append: bool },
+    /// `0`, the last yank.
+    LastYank,
+    /// `+` and `*`, the system clipboard.
+    Clipboard,
+    /// `_`, which keeps nothing.
+    BlackHole,
+    /// An entry of the clipboard history, the newest first.
+    History(usize),
+}
+
+impl RegisterName {
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            'a'..='z' => Some(Self::Named {
+                name: c,
+                append: false,
+            }),
+            'A'..='Z' => Some(Self::Named {
+                name: c.to_ascii_lowercase(),
+                append: true,
+            }),
+            '0' => Some(Self::LastYank),
+            '+' | '*' => Some(Self::Clipboard),
+            '_' => Some(Self::BlackHole),
+            _ => None,
+        }
+    }
+}
+
+/// How many kills the kill ring remembers.
+const KILL_RING_SIZE: usize = 60;
+/// How many yanks and copies the clipboard history remembers.
+const HISTORY_SIZE: usize = 30;
+
 #[derive(Clone, Default)]
 pub struct Register {
     pub unnamed: RegisterData,
     last_yank: RegisterData,
+    /// The text removed by the Emacs style kill commands, the newest last.
+    kill_ring: Vec<String>,
+    /// Where the last kill happened. Another kill at the same offset right after
+    /// it is added to that kill instead of starting a new one.
+    last_kill: Option<usize>,
+    /// The range the last yank from the kill ring inserted, and the entry of the
+    /// ring it was, so yank pop can replace it with the one before.
+    kill_yank: Option<(usize, usize, usize)>,
+    named: HashMap<char, RegisterData>,
+    /// The register the next yank, delete or paste uses instead of the unnamed
+    /// one.
+    selected: Option<RegisterName>,
+    /// The yanks, copies and cuts, the newest last.
+    history: Vec<RegisterData>,
+    /// What's on the system clipboard, loaded by the editor before a command
+    /// that uses the `+` register.
+    clipboard: Option<RegisterData>,
+    /// What a yank or delete put in the `+` register, for the editor to put on
+    /// the system clipboard.
+    clipboard_write: Option<String>,
 }
 
 pub enum RegisterKind {
@@ -31,11 +106,233 @@ pub fn add(&mut self, kind: RegisterKind, data: RegisterData) {
     }
 
     pub fn add_delete(&mut self, data: RegisterData) {
-        self.unnamed = data;
+        if self.store_selected(&data) {
+            self.unnamed = data;
+        }
     }
 
     pub fn add_yank(&mut self, data: RegisterData) {
+        if !self.store_selected(&data) {
+            return;
+        }
+        self.push_history(data.clone());
         self.unnamed = data.clone();
         self.last_yank = data;
     }
+
+    /// Use the register for the next yank, delete or paste.
+    pub fn select(&mut self, name: RegisterName) {
+        self.selected = Some(name);
+    }
+
+    pub fn clear_selected(&mut self) {
+        self.selected = None;
+    }
+
+    /// Whether the next yank, delete or paste uses the system clipboard, which
+    /// has to be loaded with [`Register::load_clipboard`] before it.
+    pub fn selects_clipboard(&self) -> bool {
+        self.selected == Some(RegisterName::Clipboard)
+    }
+
+    pub fn load_clipboard(&mut self, content: Option<String>) {
+        self.clipboard = content.map(RegisterData::from_clipboard);
+    }
+
+    /// What a yank or delete put in the `+` register since the last call.
+    pub fn take_clipboard_write(&mut self) -> Option<String> {
+        self.clipboard_write.take()
+    }
+
+    /// Put the data in the register that was selected, if one was. Returns
+    /// false if it was the black hole register, and the data shouldn't be kept
+    /// anywhere else either.
+    fn store_selected(&mut self, data: &RegisterData) -> bool {
+        match self.selected.take() {
+            Some(RegisterName::BlackHole) => return false,
+            Some(RegisterName::Named { name, append }) => {
+                let register = self.named.entry(name).or_default();
+                if append && !register.content.is_empty() {
+                    if data.mode == VisualMode::Linewise {
+                        if !register.content.ends_with('\n') {
+                            register.content.push('\n');
+                        }
+                        register.mode = VisualMode::Linewise;
+                    }
+                    register.content.push_str(&data.content);
+                } else {
+                    *register = data.clone();
+                }
+            }
+            Some(RegisterName::Clipboard) => {
+                self.clipboard = Some(data.clone());
+                self.clipboard_write = Some(data.content.clone());
+            }
+            Some(RegisterName::LastYank | RegisterName::History(_)) | None => {}
+        }
+        true
+    }
+
+    /// What a paste puts in, from the register that was selected or else the
+    /// unnamed one.
+    pub fn paste_data(&mut self) -> RegisterData {
+        match self.selected.take() {
+            None => self.unnamed.clone(),
+            Some(RegisterName::LastYank) => self.last_yank.clone(),
+            Some(RegisterName::Named { name, .. }) => {
+                self.named.get(&name).cloned().unwrap_or_default()
+            }
+            Some(RegisterName::Clipboard) => {
+                self.clipboard.clone().unwrap_or_default()
+            }
+            Some(RegisterName::BlackHole) => RegisterData::default(),
+            Some(RegisterName::History(index)) => self
+                .history
+                .iter()
+                .rev()
+                .nth(index)
+                .cloned()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Remember a yank or copy in the clipboard history, moving it to the top if
+    /// it's already in it.
+    pub fn push_history(&mut self, data: RegisterData) {
+        if data.content.is_empty() {
+            return;
+        }
+        self.history.retain(|d| d.content != data.content);
+        self.history.push(data);
+        if self.history.len() > HISTORY_SIZE {
+            self.history.remove(0);
+        }
+    }
+
+    /// The clipboard history, the newest first.
+    pub fn history(&self) -> impl Iterator<Item = &RegisterData> {
+        self.history.iter().rev()
+    }
+
+    pub fn add_kill(&mut self, offset: usize, content: &str) {
+        let last = self
+            .kill_ring
+            .last_mut()
+            .filter(|_| self.last_kill == Some(offset));
+        if let Some(last) = last {
+            last.push_str(content);
+        } else {
+            self.push_kill(content);
+        }
+        self.last_kill = Some(offset);
+    }
+
+    /// Put the text on the kill ring as the newest kill, unless it already is.
+    pub fn push_kill(&mut self, content: &str) {
+        if self.kill_ring.last().map(|s| s.as_str()) == Some(content) {
+            return;
+        }
+        self.kill_ring.push(content.to_string());
+        if self.kill_ring.len() > KILL_RING_SIZE {
+            self.kill_ring.remove(0);
+        }
+    }
+
+    /// The next kill starts a new entry even if it's at the same offset.
+    pub fn break_kill(&mut self) {
+        self.last_kill = None;
+    }
+
+    pub fn kill_ring_entry(&self, index: usize) -> Option<&str> {
+        self.kill_ring.get(index).map(|s| s.as_str())
+    }
+
+    pub fn kill_ring_len(&self) -> usize {
+        self.kill_ring.len()
+    }
+
+    pub fn set_kill_yank(&mut self, start: usize, end: usize, index: usize) {
+        self.kill_yank = Some((start, end, index));
+    }
+
+    pub fn take_kill_yank(&mut self) -> Option<(usize, usize, usize)> {
+        self.kill_yank.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Register, RegisterData, RegisterName};
+    use crate::mode::VisualMode;
+
+    fn data(content: &str, mode: VisualMode) -> RegisterData {
+        RegisterData {
+            content: content.to_string(),
+            mode,
+        }
+    }
+
+    #[test]
+    fn test_named_register() {
+        let mut register = Register::default();
+        register.select(RegisterName::from_char('a').unwrap());
+        register.add_yank(data("foo", VisualMode::Normal));
+        register.add_yank(data("bar", VisualMode::Normal));
+
+        register.select(RegisterName::from_char('a').unwrap());
+        assert_eq!(register.paste_data().content, "foo");
+        assert_eq!(register.paste_data().content, "bar");
+    }
+
+    #[test]
+    fn test_append_to_named_register() {
+        let mut register = Register::default();
+        register.select(RegisterName::from_char('a').unwrap());
+        register.add_yank(data("foo", VisualMode::Normal));
+        register.select(RegisterName::from_char('A').unwrap());
+        register.add_delete(data("bar\n", VisualMode::Linewise));
+
+        register.select(RegisterName::from_char('a').unwrap());
+        let pasted = register.paste_data();
+        assert_eq!(pasted.content, "foo\nbar\n");
+        assert_eq!(pasted.mode, VisualMode::Linewise);
+    }
+
+    #[test]
+    fn test_black_hole_register() {
+        let mut register = Register::default();
+        register.add_yank(data("foo", VisualMode::Normal));
+        register.select(RegisterName::BlackHole);
+        register.add_delete(data("bar", VisualMode::Normal));
+        assert_eq!(register.paste_data().content, "foo");
+    }
+
+    #[test]
+    fn test_clipboard_register() {
+        let mut register = Register::default();
+        register.select(RegisterName::Clipboard);
+        register.add_yank(data("foo", VisualMode::Normal));
+        assert_eq!(register.take_clipboard_write().as_deref(), Some("foo"));
+        assert_eq!(register.take_clipboard_write(), None);
+
+        register.select(RegisterName::Clipboard);
+        register.load_clipboard(Some("bar\n".to_string()));
+        let pasted = register.paste_data();
+        assert_eq!(pasted.content, "bar\n");
+        assert_eq!(pasted.mode, VisualMode::Linewise);
+    }
+
+    #[test]
+    fn test_history() {
+        let mut register = Register::default();
+        for content in ["foo", "bar", "foo"] {
+            register.add_yank(data(content, VisualMode::Normal));
+        }
+        let history: Vec<_> =
+            register.history().map(|d| d.content.as_str()).collect();
+        assert_eq!(history, vec!["foo", "bar"]);
+
+        register.select(RegisterName::History(1));
+        assert_eq!(register.paste_data().content, "bar");
+    }
 }
@@ -0,0 +1,356 @@
+//! Regex search that runs over the chunks of a rope, so that searching a large
+//! buffer doesn't copy it into a `String` first.
+//!
+//! A lazy DFA finds where a match ends going forward, and a reverse one then
+//! finds where it starts. Patterns a DFA can't handle on some text, like `\b`
+//! next to non-ASCII characters, make it give up, and the rest of the range is
+//! then searched as one string instead.
+
+use std::ops::Range;
+
+use anyhow::Result;
+use lapce_xi_rope::Rope;
+use regex_automata::{
+    hybrid::{
+        dfa::{Cache, DFA},
+        LazyStateID,
+    },
+    meta,
+    nfa::thompson,
+    util::syntax,
+    Anchored, Input, MatchKind,
+};
+
+/// The patterns come from the user, so how big they get compiled is limited.
+const SIZE_LIMIT: usize = 1_000_000;
+
+/// The lazy DFA gave up on the text, either because of a quit byte or because
+/// its cache was cleared too often.
+struct GaveUp;
+
+#[derive(Clone, Debug)]
+pub struct RopeRegex {
+    forward: DFA,
+    reverse: DFA,
+    fallback: meta::Regex,
+}
+
+impl RopeRegex {
+    /// `^` and `$` match at the start and end of every line, like when searching
+    /// line by line.
+    pub fn new(pattern: &str, case_insensitive: bool) -> Result<Self> {
+        let syntax = syntax::Config::new()
+            .case_insensitive(case_insensitive)
+            .multi_line(true)
+            .crlf(true);
+        let forward = DFA::builder()
+            .configure(DFA::config().unicode_word_boundary(true))
+            .syntax(syntax)
+            .thompson(thompson::Config::new().nfa_size_limit(Some(SIZE_LIMIT)))
+            .build(pattern)?;
+        let reverse = DFA::builder()
+            .configure(
+                DFA::config()
+                    .match_kind(MatchKind::All)
+                    .unicode_word_boundary(true),
+            )
+            .syntax(syntax)
+            .thompson(
+                thompson::Config::new()
+                    .reverse(true)
+                    .nfa_size_limit(Some(SIZE_LIMIT)),
+            )
+            .build(pattern)?;
+        let fallback = meta::Regex::builder()
+            .configure(meta::Regex::config().nfa_size_limit(Some(SIZE_LIMIT)))
+            .syntax(syntax)
+            .build(pattern)?;
+        Ok(Self {
+            forward,
+            reverse,
+            fallback,
+        })
+    }
+
+    /// The matches that start and end in `range`, in order. The text around the
+    /// range is still looked at for anchors and word boundaries.
+    pub fn find_iter<'a>(
+        &'a self,
+        text: &'a Rope,
+        range: Range<usize>,
+    ) -> RopeMatches<'a> {
+        RopeMatches {
+            regex: self,
+            text,
+            forward_cache: self.forward.create_cache(),
+            reverse_cache: self.reverse.create_cache(),
+            pos: range.start,
+            end: range.end.min(text.len()),
+            fallback: None,
+        }
+    }
+
+    /// The first match that starts at or after `start` and ends before `end`.
+    fn find_at(
+        &self,
+        forward_cache: &mut Cache,
+        reverse_cache: &mut Cache,
+        text: &Rope,
+        start: usize,
+        end: usize,
+    ) -> Result<Option<Range<usize>>, GaveUp> {
+        let Some(match_end) = self.find_end(forward_cache, text, start, end)? else {
+            return Ok(None);
+        };
+        let match_start = self
+            .find_start(reverse_cache, text, start, match_end)?
+            .ok_or(GaveUp)?;
+        Ok(Some(match_start..match_end))
+    }
+
+    fn find_end(
+        &self,
+        cache: &mut Cache,
+        text: &Rope,
+        start: usize,
+        end: usize,
+    ) -> Result<Option<usize>, GaveUp> {
+        let before = start.checked_sub(1).map(|i| text.byte_at(i));
+        let mut sid = start_state(&self.forward, cache, before, false)?;
+        let mut found = None;
+        let mut pos = start;
+        for chunk in text.iter_chunks(start..end) {
+            for &byte in chunk.as_bytes() {
+                sid = self
+                    .forward
+                    .next_state(cache, sid, byte)
+                    .map_err(|_| GaveUp)?;
+                // Matches are reported one byte late, so this one ends before `pos`
+                if check_state(sid, pos, &mut found)? {
+                    return Ok(found);
+                }
+                pos += 1;
+            }
+        }
+        sid = if end < text.len() {
+            self.forward.next_state(cache, sid, text.byte_at(end))
+        } else {
+            self.forward.next_eoi_state(cache, sid)
+        }
+        .map_err(|_| GaveUp)?;
+        check_state(sid, end, &mut found)?;
+        Ok(found)
+    }
+
+    /// Where the match that ends at `end` starts, going back no further than
+    /// `start`.
+    fn find_start(
+        &self,
+        cache: &mut Cache,
+        text: &Rope,
+        start: usize,
+        end: usize,
+    ) -> Result<Option<usize>, GaveUp> {
+        let after = (end < text.len()).then(|| text.byte_at(end));
+        let mut sid = start_state(&self.reverse, cache, after, true)?;
+        let mut found = None;
+        let mut pos = end;
+        while pos > start {
+            pos -= 1;
+            sid = self
+                .reverse
+                .next_state(cache, sid, text.byte_at(pos))
+                .map_err(|_| GaveUp)?;
+            if check_state(sid, pos + 1, &mut found)? {
+                return Ok(found);
+            }
+        }
+        sid = if start > 0 {
+            self.reverse.next_state(cache, sid, text.byte_at(start - 1))
+        } else {
+            self.reverse.next_eoi_state(cache, sid)
+        }
+        .map_err(|_| GaveUp)?;
+        check_state(sid, start, &mut found)?;
+        Ok(found)
+    }
+
+    /// Search the rest of the range as one string, for when the lazy DFA gave up.
+    fn find_all_fallback(
+        &self,
+        text: &Rope,
+        start: usize,
+        end: usize,
+    ) -> Vec<Range<usize>> {
+        let before = text.prev_codepoint_offset(start).unwrap_or(start);
+        let after = text.next_codepoint_offset(end).unwrap_or(end);
+        let haystack = text.slice_to_cow(before..after);
+        let input =
+            Input::new(haystack.as_bytes()).span(start - before..end - before);
+        self.fallback
+            .find_iter(input)
+            .map(|m| m.start() + before..m.end() + before)
+            .collect()
+    }
+}
+
+/// The start state, with the byte before the search, or after it for a reverse
+/// search, for the anchors and word boundaries to look at.
+fn start_state(
+    dfa: &DFA,
+    cache: &mut Cache,
+    around: Option<u8>,
+    reverse: bool,
+) -> Result<LazyStateID, GaveUp> {
+    let around = around.map(|byte| [byte]);
+    let haystack: &[u8] = around.as_ref().map_or(&[], |byte| byte.as_slice());
+    if reverse {
+        let input = Input::new(haystack).range(0..0).anchored(Anchored::Yes);
+        dfa.start_state_reverse(cache, &input)
+    } else {
+        let input = Input::new(haystack).range(haystack.len()..);
+        dfa.start_state_forward(cache, &input)
+    }
+    .map_err(|_| GaveUp)
+}
+
+/// Record a match at `pos` if the state is one. Returns whether the search is
+/// over.
+fn check_state(
+    sid: LazyStateID,
+    pos: usize,
+    found: &mut Option<usize>,
+) -> Result<bool, GaveUp> {
+    if !sid.is_tagged() {
+        return Ok(false);
+    }
+    if sid.is_match() {
+        *found = Some(pos);
+        Ok(false)
+    } else if sid.is_dead() {
+        Ok(true)
+    } else if sid.is_quit() {
+        Err(GaveUp)
+    } else {
+        Ok(false)
+    }
+}
+
+/// The matches of a [`RopeRegex`] in a range of a rope.
+pub struct RopeMatches<'a> {
+    regex: &'a RopeRegex,
+    text: &'a Rope,
+    forward_cache: Cache,
+    reverse_cache: Cache,
+    pos: usize,
+    end: usize,
+    /// The rest of the matches, once the lazy DFA gave up.
+    fallback: Option<std::vec::IntoIter<Range<usize>>>,
+}
+
+impl Iterator for RopeMatches<'_> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(fallback) = self.fallback.as_mut() {
+            return fallback.next();
+        }
+        if self.pos > self.end {
+            return None;
+        }
+
+        match self.regex.find_at(
+            &mut self.forward_cache,
+            &mut self.reverse_cache,
+            self.text,
+            self.pos,
+            self.end,
+        ) {
+            Ok(Some(m)) => {
+                // An empty match would be found again at the same place
+                self.pos = if m.is_empty() {
+                    self.text
+                        .next_codepoint_offset(m.end)
+                        .unwrap_or(self.end + 1)
+                } else {
+                    m.end
+                };
+                Some(m)
+            }
+            Ok(None) => {
+                self.pos = self.end + 1;
+                None
+            }
+            Err(GaveUp) => {
+                self.fallback = Some(
+                    self.regex
+                        .find_all_fallback(self.text, self.pos, self.end)
+                        .into_iter(),
+                );
+                self.next()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lapce_xi_rope::Rope;
+
+    use super::RopeRegex;
+
+    fn find_all(pattern: &str, text: &Rope) -> Vec<(usize, usize)> {
+        RopeRegex::new(pattern, false)
+            .unwrap()
+            .find_iter(text, 0..text.len())
+            .map(|m| (m.start, m.end))
+            .collect()
+    }
+
+    #[test]
+    fn test_find_across_chunks() {
+        // Long enough for the rope to be split into several leaves
+        let line = "fn foo() {}\n".repeat(200);
+        let text = Rope::from(format!("{line}needle\n{line}"));
+        let start = line.len();
+        assert_eq!(find_all("nee+dle", &text), vec![(start, start + 6)]);
+        assert_eq!(find_all(r"fn \w+", &text).len(), 400);
+    }
+
+    #[test]
+    fn test_find_lines() {
+        let text = Rope::from("ab\r\ncab\nab");
+        assert_eq!(find_all("^ab$", &text), vec![(0, 2), (8, 10)]);
+        assert_eq!(find_all(r"b\r?\nc", &text), vec![(1, 5)]);
+    }
+
+    #[test]
+    fn test_find_in_range() {
+        let text = Rope::from("foo foobar barfoo");
+        let regex = RopeRegex::new(r"\bfoo", false).unwrap();
+        let matches: Vec<_> = regex.find_iter(&text, 5..17).collect();
+        assert_eq!(matches, Vec::<std::ops::Range<usize>>::new());
+        let matches: Vec<_> = regex.find_iter(&text, 4..17).collect();
+        assert_eq!(matches, vec![4..7]);
+    }
+
+    #[test]
+    fn test_find_case_insensitive() {
+        let text = Rope::from("Foo fOO");
+        let regex = RopeRegex::new("foo", true).unwrap();
+        let matches: Vec<_> = regex.find_iter(&text, 0..text.len()).collect();
+        assert_eq!(matches, vec![0..3, 4..7]);
+    }
+
+    #[test]
+    fn test_find_unicode_word_boundary() {
+        let text = Rope::from("é foo éfoo");
+        assert_eq!(find_all(r"\bfoo\b", &text), vec![(3, 6)]);
+    }
+
+    #[test]
+    fn test_find_empty_matches() {
+        let text = Rope::from("a\nb");
+        assert_eq!(find_all("^", &text), vec![(0, 0), (2, 2)]);
+    }
+}
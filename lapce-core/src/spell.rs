@@ -0,0 +1,206 @@
+//! Spell checking of the prose in comments and strings against a word list.
+
+use std::collections::HashSet;
+
+use crate::transform::{identifier_words, is_identifier_char};
+
+/// Suggestions are the words at most this many edits away from the misspelling.
+const MAX_DISTANCE: usize = 2;
+
+/// The words that are spelled correctly, kept in lowercase so that a word is
+/// accepted however it is capitalized.
+#[derive(Clone, Debug, Default)]
+pub struct Dictionary {
+    words: HashSet<String>,
+}
+
+impl Dictionary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the words of a word list, which has one word on each line. Empty lines
+    /// and lines starting with `#` are skipped.
+    pub fn add_words(&mut self, list: &str) {
+        for line in list.lines() {
+            let word = line.trim();
+            if !word.is_empty() && !word.starts_with('#') {
+                self.words.insert(word.to_lowercase());
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Whether the word is spelled correctly, which a possessive is when the word
+    /// it is formed from is.
+    pub fn contains(&self, word: &str) -> bool {
+        let word = word.to_lowercase();
+        self.words.contains(&word)
+            || word
+                .strip_suffix("'s")
+                .is_some_and(|word| self.words.contains(word))
+    }
+
+    /// The closest words to a misspelled one, capitalized like it is.
+    pub fn suggestions(&self, word: &str, limit: usize) -> Vec<String> {
+        let lower = word.to_lowercase().chars().collect::<Vec<_>>();
+        let mut found = self
+            .words
+            .iter()
+            .filter_map(|candidate| {
+                let chars = candidate.chars().collect::<Vec<_>>();
+                if chars.len().abs_diff(lower.len()) > MAX_DISTANCE {
+                    return None;
+                }
+                let distance = edit_distance(&lower, &chars);
+                (distance <= MAX_DISTANCE).then_some((distance, candidate))
+            })
+            .collect::<Vec<_>>();
+        found.sort();
+        found
+            .into_iter()
+            .take(limit)
+            .map(|(_, candidate)| match_case(word, candidate))
+            .collect()
+    }
+}
+
+/// The number of characters to insert, delete, replace or swap with the next
+/// one to turn `a` into `b`.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut rows = vec![(0..=b.len()).collect::<Vec<_>>()];
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            row[j] = (rows[i - 1][j] + 1)
+                .min(row[j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+        rows.push(row);
+    }
+    rows[a.len()][b.len()]
+}
+
+fn match_case(word: &str, suggestion: &str) -> String {
+    if word.starts_with(char::is_uppercase) {
+        let mut chars = suggestion.chars();
+        chars
+            .next()
+            .map(|first| first.to_uppercase().chain(chars).collect())
+            .unwrap_or_default()
+    } else {
+        suggestion.to_string()
+    }
+}
+
+/// The words of the text worth checking, with their offsets. Identifiers are
+/// split into their words, and words in all capitals or with digits in them
+/// are left out, as are escape sequences and anything that looks like a link
+/// or an email address.
+pub fn words(text: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut token_start = None;
+    let mut escaped = false;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let next = chars.peek().map(|(_, c)| *c);
+        let in_token = !escaped
+            && (is_identifier_char(c)
+                || (c == '\''
+                    && token_start.is_some()
+                    && next.is_some_and(char::is_alphabetic)));
+        escaped = c == '\\' && !escaped;
+        match (in_token, token_start) {
+            (true, None) => token_start = Some(i),
+            (false, Some(start)) => {
+                add_token_words(text, start, i, &mut words);
+                token_start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(start) = token_start {
+        add_token_words(text, start, text.len(), &mut words);
+    }
+    words
+}
+
+fn add_token_words<'a>(
+    text: &'a str,
+    start: usize,
+    end: usize,
+    words: &mut Vec<(usize, &'a str)>,
+) {
+    // The whole run of text the token is in, to find links and addresses
+    let chunk_start = text[..start]
+        .rfind(char::is_whitespace)
+        .map_or(0, |i| i + 1);
+    let chunk_end = text[end..]
+        .find(char::is_whitespace)
+        .map_or(text.len(), |i| end + i);
+    let chunk = &text[chunk_start..chunk_end];
+    if chunk.contains("://") || chunk.contains('@') {
+        return;
+    }
+
+    let token = &text[start..end];
+    for word in identifier_words(token) {
+        let is_word = word.chars().count() > 1
+            && word.chars().all(|c| c.is_alphabetic() || c == '\'')
+            && !word.chars().all(|c| c.is_uppercase() || c == '\'');
+        if is_word {
+            let offset = start + (word.as_ptr() as usize - token.as_ptr() as usize);
+            words.push((offset, word));
+        }
+    }
+}
+
+/// The ranges of the words in the text that aren't in the dictionary.
+pub fn misspellings(dictionary: &Dictionary, text: &str) -> Vec<(usize, usize)> {
+    words(text)
+        .into_iter()
+        .filter(|(_, word)| !dictionary.contains(word))
+        .map(|(offset, word)| (offset, offset + word.len()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_words() {
+        let words = words("// parseHTTPRequest: don't\\nskip see https://x.io/a");
+        assert_eq!(
+            words,
+            vec![
+                (3, "parse"),
+                (12, "Request"),
+                (21, "don't"),
+                (28, "skip"),
+                (33, "see")
+            ]
+        );
+        assert_eq!(super::words("'quoted' v2 NOTE"), vec![(1, "quoted")]);
+    }
+
+    #[test]
+    fn test_misspellings() {
+        let mut dictionary = Dictionary::new();
+        dictionary.add_words("# comment\nthe\nword\nworld\nwords\n");
+        assert!(dictionary.contains("The"));
+        assert!(dictionary.contains("word's"));
+        assert_eq!(
+            misspellings(&dictionary, "the wrod wordl"),
+            vec![(4, 8), (9, 14)]
+        );
+        assert_eq!(dictionary.suggestions("Wrod", 2), vec!["Word", "Words"]);
+    }
+}
@@ -0,0 +1,85 @@
+//! Adding, changing and deleting the brackets, quotes or tags around text, like
+//! the Vim surround plugin.
+
+use crate::{
+    buffer::{rope_text::RopeText, Buffer},
+    syntax::{
+        util::{matching_char, matching_pair_direction},
+        Syntax,
+    },
+    word::WordCursor,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Surround {
+    /// Wrap each selection, or the word of each caret, in the pair of the
+    /// character.
+    Add(char),
+    Delete(char),
+    /// Replace the pair of the first character with the pair of the second.
+    Change(char, char),
+}
+
+/// The opening and closing characters of the pair a character stands for:
+/// either bracket of a pair, the letters Vim uses for them, or a quote.
+pub fn surround_pair(c: char) -> Option<(char, char)> {
+    let c = match c {
+        'b' => '(',
+        'B' => '{',
+        'r' => '[',
+        'a' | '<' | '>' => return Some(('<', '>')),
+        '"' | '\'' | '`' => return Some((c, c)),
+        c => c,
+    };
+    if matching_pair_direction(c)? {
+        Some((c, matching_char(c)?))
+    } else {
+        Some((matching_char(c)?, c))
+    }
+}
+
+/// The ranges of the opening and closing delimiters around `start..end` that
+/// `target` stands for, with `t` for the tags of an element. They are found in
+/// the syntax tree, or in the text for a language without one.
+pub fn find_surrounding_pair(
+    buffer: &Buffer,
+    syntax: &Syntax,
+    start: usize,
+    end: usize,
+    target: char,
+) -> Option<[(usize, usize); 2]> {
+    let pair = if target == 't' {
+        None
+    } else {
+        Some(surround_pair(target)?)
+    };
+    if syntax.layers.is_some() {
+        return syntax.find_surrounding_pair(start, end, pair);
+    }
+
+    let (open, close) = pair?;
+    if open != close {
+        let open_start =
+            WordCursor::new(buffer.text(), start).previous_unmatched(open)?;
+        let close_end = WordCursor::new(buffer.text(), end).next_unmatched(close)?;
+        return Some([
+            (open_start, open_start + open.len_utf8()),
+            (close_end - close.len_utf8(), close_end),
+        ]);
+    }
+
+    // Quotes don't nest, so the closest ones on the line are the pair
+    let line = buffer.line_of_offset(start);
+    let line_start = buffer.offset_of_line(line);
+    let line_end = buffer.line_end_offset(line, true);
+    if end > line_end {
+        return None;
+    }
+    let open_start =
+        line_start + buffer.slice_to_cow(line_start..start).rfind(open)?;
+    let close_start = end + buffer.slice_to_cow(end..line_end).find(close)?;
+    Some([
+        (open_start, open_start + open.len_utf8()),
+        (close_start, close_start + close.len_utf8()),
+    ])
+}
@@ -8,7 +8,7 @@
 
 use std::{
     cell::RefCell,
-    collections::{HashSet, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
     mem,
     path::Path,
     sync::{atomic::AtomicUsize, Arc},
@@ -22,7 +22,7 @@
 };
 use slotmap::{DefaultKey as LayerId, HopSlotMap};
 use thiserror::Error;
-use tree_sitter::{Node, Parser, Point, QueryCursor, Tree};
+use tree_sitter::{Node, Parser, Point, Query, QueryCursor, Tree};
 
 use self::{
     edit::SyntaxEdit,
@@ -43,6 +43,127 @@
 pub mod highlight;
 pub mod util;
 
+/// Node kinds that count as a function, across the bundled grammars.
+const FUNCTION_KINDS: &[&str] = &[
+    "function_item",
+    "function_definition",
+    "function_declaration",
+    "function_expression",
+    "function",
+    "arrow_function",
+    "generator_function_declaration",
+    "method_definition",
+    "method_declaration",
+    "method",
+    "constructor_declaration",
+    "closure_expression",
+    "func_literal",
+    "lambda",
+    "lambda_expression",
+];
+
+/// Node kinds that count as a class, or the closest thing a language has to one.
+const CLASS_KINDS: &[&str] = &[
+    "struct_item",
+    "enum_item",
+    "union_item",
+    "trait_item",
+    "impl_item",
+    "class_definition",
+    "class_declaration",
+    "class_specifier",
+    "struct_specifier",
+    "interface_declaration",
+    "enum_declaration",
+    "record_declaration",
+    "object_declaration",
+    "type_declaration",
+    "class",
+    "module",
+];
+
+/// Node kinds whose named children are arguments or parameters.
+const ARGUMENT_LIST_KINDS: &[&str] = &[
+    "arguments",
+    "argument_list",
+    "parameters",
+    "parameter_list",
+    "formal_parameters",
+    "lambda_parameters",
+    "closure_parameters",
+    "type_arguments",
+    "type_parameters",
+];
+
+/// A syntax node that can be selected as a whole ("around") or only by its
+/// contents ("inside"), like the text objects of Vim.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextObject {
+    Function,
+    Class,
+    Argument,
+    String,
+}
+
+impl TextObject {
+    fn is_node(&self, node: Node) -> bool {
+        match self {
+            TextObject::Function => FUNCTION_KINDS.contains(&node.kind()),
+            TextObject::Class => CLASS_KINDS.contains(&node.kind()),
+            TextObject::Argument => {
+                node.is_named()
+                    && !node.kind().contains("comment")
+                    && node
+                        .parent()
+                        .map(|p| ARGUMENT_LIST_KINDS.contains(&p.kind()))
+                        .unwrap_or(false)
+            }
+            TextObject::String => {
+                node.kind().contains("string")
+                    && !node
+                        .parent()
+                        .map(|p| p.kind().contains("string"))
+                        .unwrap_or(false)
+            }
+        }
+    }
+
+    /// The name the `textobjects.scm` queries capture the whole object with.
+    fn capture_name(&self) -> &'static str {
+        match self {
+            TextObject::Function => "function.around",
+            TextObject::Class => "class.around",
+            TextObject::Argument => "parameter.around",
+            TextObject::String => "string.around",
+        }
+    }
+}
+
+/// What the next and previous item motions jump between.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyntaxItem {
+    Function,
+    Class,
+    /// Anything at the top level of the file, like the functions, types and
+    /// imports of a module.
+    TopLevel,
+}
+
+thread_local! {
+    static TEXTOBJECTS_QUERIES: RefCell<HashMap<LapceLanguage, Option<Arc<Query>>>> =
+        Default::default();
+}
+
+fn get_textobjects_query(language: LapceLanguage) -> Option<Arc<Query>> {
+    TEXTOBJECTS_QUERIES.with(|queries| {
+        queries
+            .borrow_mut()
+            .entry(language)
+            .or_insert_with(|| language.new_textobjects_query().map(Arc::new))
+            .clone()
+    })
+}
+
 // Uses significant portions Helix's implementation, and on tree-sitter's highlighter implementation
 
 pub struct TsParser {
@@ -787,6 +908,152 @@ pub fn sticky_headers(&self, offset: usize) -> Option<Vec<usize>> {
         Some(offsets)
     }
 
+    /// The range of the smallest named node that contains `start..end` and is
+    /// bigger than it, which is what expanding a selection selects next.
+    pub fn expand_selection(
+        &self,
+        start: usize,
+        end: usize,
+    ) -> Option<(usize, usize)> {
+        let tree = self.layers.as_ref()?.try_tree()?;
+        let mut node = tree
+            .root_node()
+            .named_descendant_for_byte_range(start, end)?;
+        while node.start_byte() == start && node.end_byte() == end {
+            node = node.parent()?;
+        }
+        Some((node.start_byte(), node.end_byte()))
+    }
+
+    /// The range of the text object around `offset`. With `inside` only the
+    /// contents are selected, like the body of a function or the text of a string
+    /// without its quotes, otherwise the whole node is.
+    pub fn text_object(
+        &self,
+        offset: usize,
+        object: TextObject,
+        inside: bool,
+    ) -> Option<(usize, usize)> {
+        let tree = self.layers.as_ref()?.try_tree()?;
+        let node = tree.root_node().descendant_for_byte_range(offset, offset)?;
+        match object {
+            TextObject::Function | TextObject::Class => {
+                let node = ancestors(node).find(|n| object.is_node(*n))?;
+                if !inside {
+                    return Some((node.start_byte(), node.end_byte()));
+                }
+                let body = node.child_by_field_name("body").unwrap_or(node);
+                Some(inside_delimiters(body))
+            }
+            TextObject::String => {
+                // A string can have string parts in it, so the outermost one of
+                // the innermost run of string nodes is the whole string
+                let node = ancestors(node)
+                    .skip_while(|n| !n.kind().contains("string"))
+                    .take_while(|n| n.kind().contains("string"))
+                    .last()?;
+                if !inside {
+                    return Some((node.start_byte(), node.end_byte()));
+                }
+                if node.child_count() >= 2 {
+                    Some(inside_delimiters(node))
+                } else {
+                    let (start, end) = (node.start_byte(), node.end_byte());
+                    Some(if end - start >= 2 {
+                        (start + 1, end - 1)
+                    } else {
+                        (start, end)
+                    })
+                }
+            }
+            TextObject::Argument => {
+                let list = ancestors(node)
+                    .find(|n| ARGUMENT_LIST_KINDS.contains(&n.kind()))?;
+                let mut cursor = list.walk();
+                let args: Vec<(usize, usize)> = list
+                    .named_children(&mut cursor)
+                    .filter(|n| !n.kind().contains("comment"))
+                    .map(|n| (n.start_byte(), n.end_byte()))
+                    .collect();
+                // On a separator the argument before it is the one selected
+                let i = args
+                    .iter()
+                    .rposition(|(start, _)| *start <= offset)
+                    .unwrap_or(0);
+                let (start, end) = *args.get(i)?;
+                if inside {
+                    Some((start, end))
+                } else if let Some((next, _)) = args.get(i + 1) {
+                    Some((start, *next))
+                } else if let Some((_, prev)) = i.checked_sub(1).map(|i| args[i]) {
+                    Some((prev, end))
+                } else {
+                    Some((start, end))
+                }
+            }
+        }
+    }
+
+    /// The start of the next item after `offset`, or of the previous one before
+    /// it. Functions and classes are found with the `textobjects.scm` query of
+    /// the language, or by their node kinds if it doesn't have one.
+    pub fn find_item(
+        &self,
+        offset: usize,
+        item: SyntaxItem,
+        previous: bool,
+    ) -> Option<usize> {
+        let tree = self.layers.as_ref()?.try_tree()?;
+        let object = match item {
+            SyntaxItem::Function => TextObject::Function,
+            SyntaxItem::Class => TextObject::Class,
+            SyntaxItem::TopLevel => {
+                let root = tree.root_node();
+                let mut cursor = root.walk();
+                let starts = root
+                    .named_children(&mut cursor)
+                    .filter(|n| !n.kind().contains("comment"))
+                    .map(|n| n.start_byte());
+                return nearest(starts, offset, previous);
+            }
+        };
+
+        let query = get_textobjects_query(self.language).and_then(|query| {
+            let index = query.capture_index_for_name(object.capture_name())?;
+            Some((query, index))
+        });
+        if let Some((query, index)) = query {
+            let mut cursor = QueryCursor::new();
+            let starts = cursor
+                .captures(&query, tree.root_node(), RopeProvider(&self.text))
+                .map(|(m, i)| m.captures[i])
+                .filter(|capture| capture.index == index)
+                .map(|capture| capture.node.start_byte());
+            return nearest(starts, offset, previous);
+        }
+
+        let mut starts = Vec::new();
+        let mut cursor = tree.walk();
+        'walk: loop {
+            let node = cursor.node();
+            if object.is_node(node) {
+                starts.push(node.start_byte());
+            }
+            if cursor.goto_first_child() || cursor.goto_next_sibling() {
+                continue;
+            }
+            loop {
+                if !cursor.goto_parent() {
+                    break 'walk;
+                }
+                if cursor.goto_next_sibling() {
+                    break;
+                }
+            }
+        }
+        nearest(starts.into_iter(), offset, previous)
+    }
+
     pub fn find_enclosing_parentheses(
         &self,
         offset: usize,
@@ -822,6 +1089,92 @@ pub fn find_enclosing_parentheses(
         }
     }
 
+    /// The ranges of the opening and closing delimiters of the innermost node
+    /// around `start..end` that is delimited by `open` and `close`, like the
+    /// parentheses of a call or the quotes of a string, or by a start and an
+    /// end tag when there is no pair.
+    pub fn find_surrounding_pair(
+        &self,
+        start: usize,
+        end: usize,
+        pair: Option<(char, char)>,
+    ) -> Option<[(usize, usize); 2]> {
+        let tree = self.layers.as_ref()?.try_tree()?;
+        let node = tree.root_node().descendant_for_byte_range(start, end)?;
+        ancestors(node).find_map(|node| {
+            let count = node.child_count();
+            let Some((open, close)) = pair else {
+                let first = node.child(0)?;
+                let last = node.child(count.checked_sub(1)?)?;
+                let is_tag = first.kind().ends_with("start_tag")
+                    || first.kind().ends_with("opening_element");
+                let is_end_tag = last.kind().ends_with("end_tag")
+                    || last.kind().ends_with("closing_element");
+                return (count >= 2 && is_tag && is_end_tag).then(|| {
+                    [
+                        (first.start_byte(), first.end_byte()),
+                        (last.start_byte(), last.end_byte()),
+                    ]
+                });
+            };
+
+            // A string token can have no children, and the delimiters of a
+            // string can have a prefix, like `f"` in Python
+            let (first, last) = if count >= 2 {
+                let first = node.child(0)?;
+                let last = node.child(count - 1)?;
+                if !is_delimiter(&first) || !is_delimiter(&last) {
+                    return None;
+                }
+                (
+                    (first.start_byte(), first.end_byte()),
+                    (last.start_byte(), last.end_byte()),
+                )
+            } else if count == 0 && node.end_byte() - node.start_byte() >= 2 {
+                let (start, end) = (node.start_byte(), node.end_byte());
+                (
+                    (start, start + open.len_utf8()),
+                    (end - close.len_utf8(), end),
+                )
+            } else {
+                return None;
+            };
+            let first_text = self.text.slice_to_cow(first.0..first.1);
+            let last_text = self.text.slice_to_cow(last.0..last.1);
+            (first_text.ends_with(open) && last_text.starts_with(close)).then(|| {
+                [
+                    (first.1 - open.len_utf8(), first.1),
+                    (last.0, last.0 + close.len_utf8()),
+                ]
+            })
+        })
+    }
+
+    /// The ranges of the comments and strings in the document, taking only the
+    /// outermost node when they nest, like a string in a doc comment.
+    pub fn comment_and_string_ranges(&self) -> Vec<(usize, usize)> {
+        let mut ranges = Vec::new();
+        let Some(tree) = self.layers.as_ref().and_then(|l| l.try_tree()) else {
+            return ranges;
+        };
+        let mut cursor = tree.walk();
+        loop {
+            let node = cursor.node();
+            let kind = node.kind();
+            let is_prose = kind.contains("comment") || kind.contains("string");
+            if is_prose {
+                ranges.push((node.start_byte(), node.end_byte()));
+            } else if cursor.goto_first_child() {
+                continue;
+            }
+            while !cursor.goto_next_sibling() {
+                if !cursor.goto_parent() {
+                    return ranges;
+                }
+            }
+        }
+    }
+
     pub fn find_enclosing_pair(&self, offset: usize) -> Option<(usize, usize)> {
         if self.language == LapceLanguage::Markdown {
             // TODO: fix the issue that sometimes node.prev_sibling can stuck for markdown
@@ -860,6 +1213,44 @@ pub fn find_enclosing_pair(&self, offset: usize) -> Option<(usize, usize)> {
     }
 }
 
+/// The closest of `offsets` after `offset`, or before it for `previous`.
+fn nearest(
+    offsets: impl Iterator<Item = usize>,
+    offset: usize,
+    previous: bool,
+) -> Option<usize> {
+    if previous {
+        offsets.filter(|o| *o < offset).max()
+    } else {
+        offsets.filter(|o| *o > offset).min()
+    }
+}
+
+/// The node itself and then each of its parents.
+fn ancestors<'a>(node: Node<'a>) -> impl Iterator<Item = Node<'a>> {
+    std::iter::successors(Some(node), |n| n.parent())
+}
+
+fn is_delimiter(node: &Node) -> bool {
+    let kind = node.kind();
+    !node.is_named() || kind.ends_with("_start") || kind.ends_with("_end")
+}
+
+/// The range between the first and the last child of a node when those are
+/// delimiters, like the braces of a block or the quotes of a string. A block
+/// without them, like an indented body in Python, is taken as a whole.
+fn inside_delimiters(node: Node) -> (usize, usize) {
+    let count = node.child_count();
+    if count >= 2 {
+        if let (Some(first), Some(last)) = (node.child(0), node.child(count - 1)) {
+            if is_delimiter(&first) && is_delimiter(&last) {
+                return (first.end_byte(), last.start_byte());
+            }
+        }
+    }
+    (node.start_byte(), node.end_byte())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,168 @@
+//! Text transforms for the selection: changing the case of text and identifiers,
+//! and reordering lines.
+
+pub(crate) fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// The words of an identifier, split at underscores and where the case changes,
+/// like `parseHTTPRequest` into `parse`, `HTTP` and `Request`.
+pub(crate) fn identifier_words(identifier: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    for part in identifier.split('_').filter(|part| !part.is_empty()) {
+        let chars = part.char_indices().collect::<Vec<_>>();
+        let mut start = 0;
+        for i in 1..chars.len() {
+            let (index, c) = chars[i];
+            let prev = chars[i - 1].1;
+            let next = chars.get(i + 1).map(|(_, c)| *c);
+            let word_start = c.is_uppercase()
+                && (prev.is_lowercase()
+                    || prev.is_numeric()
+                    // The last capital of an acronym starts the next word
+                    || (prev.is_uppercase()
+                        && matches!(next, Some(next) if next.is_lowercase())));
+            if word_start {
+                words.push(&part[start..index]);
+                start = index;
+            }
+        }
+        words.push(&part[start..]);
+    }
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+/// Join the words of each identifier in the text with `join`, keeping the
+/// underscores it starts or ends with. The rest of the text is left as it is.
+fn convert_identifiers(text: &str, join: impl Fn(&[&str]) -> String) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(is_identifier_char) {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest
+            .find(|c: char| !is_identifier_char(c))
+            .unwrap_or(rest.len());
+        let identifier = &rest[..end];
+        rest = &rest[end..];
+
+        let inner = identifier.trim_matches('_');
+        if inner.is_empty() {
+            result.push_str(identifier);
+            continue;
+        }
+        let leading = identifier.len() - identifier.trim_start_matches('_').len();
+        result.push_str(&identifier[..leading]);
+        result.push_str(&join(&identifier_words(inner)));
+        result.push_str(&identifier[leading + inner.len()..]);
+    }
+    result.push_str(rest);
+    result
+}
+
+/// `foo_bar` for `fooBar`, `FooBar` or `FOO_BAR`.
+pub fn to_snake_case(text: &str) -> String {
+    convert_identifiers(text, |words| {
+        words
+            .iter()
+            .map(|word| word.to_lowercase())
+            .collect::<Vec<_>>()
+            .join("_")
+    })
+}
+
+/// `fooBar` for `foo_bar`, `FooBar` or `FOO_BAR`.
+pub fn to_camel_case(text: &str) -> String {
+    convert_identifiers(text, |words| {
+        let mut result = String::new();
+        for (i, word) in words.iter().enumerate() {
+            if i == 0 {
+                result.push_str(&word.to_lowercase());
+            } else {
+                result.push_str(&capitalize(word));
+            }
+        }
+        result
+    })
+}
+
+/// Lowercase the text, except for the first letter of each word. An apostrophe
+/// within a word, like in `don't`, doesn't start a new one.
+pub fn to_title_case(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut prev = None;
+    let mut before_prev: Option<char> = None;
+    for c in text.chars() {
+        let word_start = match prev {
+            None => true,
+            Some('\'') => !matches!(before_prev, Some(c) if c.is_alphabetic()),
+            Some(prev) => !prev.is_alphanumeric(),
+        };
+        if word_start {
+            result.extend(c.to_uppercase());
+        } else {
+            result.extend(c.to_lowercase());
+        }
+        before_prev = prev;
+        prev = Some(c);
+    }
+    result
+}
+
+/// Apply `f` to the lines of the text, without their line endings, and join
+/// them back with the line ending the text uses.
+pub fn transform_lines(text: &str, f: impl FnOnce(&mut Vec<&str>)) -> String {
+    let line_ending = if text.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut lines = text.lines().collect::<Vec<_>>();
+    f(&mut lines);
+    let mut result = lines.join(line_ending);
+    if text.ends_with('\n') {
+        result.push_str(line_ending);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identifier_case() {
+        assert_eq!(to_snake_case("parseHTTPRequest"), "parse_http_request");
+        assert_eq!(to_snake_case("FooBar2Baz"), "foo_bar2_baz");
+        assert_eq!(to_snake_case("let __fooBar = x;"), "let __foo_bar = x;");
+        assert_eq!(to_camel_case("parse_http_request"), "parseHttpRequest");
+        assert_eq!(to_camel_case("FOO_BAR_"), "fooBar_");
+        assert_eq!(to_camel_case("foo-bar baz_qux"), "foo-bar bazQux");
+    }
+
+    #[test]
+    fn test_title_case() {
+        assert_eq!(
+            to_title_case("don't STOP me-now o'neil"),
+            "Don't Stop Me-Now O'neil"
+        );
+    }
+
+    #[test]
+    fn test_transform_lines() {
+        let mut sorted = transform_lines("b\r\nc\r\na\r\n", |lines| lines.sort());
+        assert_eq!(sorted, "a\r\nb\r\nc\r\n");
+        sorted = transform_lines("b\na\nb", |lines| {
+            lines.sort();
+            lines.dedup();
+        });
+        assert_eq!(sorted, "a\nb");
+    }
+}
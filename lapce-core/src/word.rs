@@ -16,10 +16,65 @@ pub enum CharClassification {
     Space,
     /// Any punctuation character
     Punctuation,
-    /// Includes letters and all of non-ascii unicode
+    /// Han ideographs and hiragana, each of which is a word of its own
+    Ideographic,
+    /// Katakana, a run of which is one word
+    Katakana,
+    /// Includes letters and the rest of non-ascii unicode
     Other,
 }
 
+impl CharClassification {
+    /// Whether the character is part of a word, in any script.
+    pub fn is_word(self) -> bool {
+        matches!(
+            self,
+            CharClassification::Ideographic
+                | CharClassification::Katakana
+                | CharClassification::Other
+        )
+    }
+
+    fn is_word_or_punctuation(self) -> bool {
+        self.is_word() || self == CharClassification::Punctuation
+    }
+}
+
+/// How a character takes part in sub-words
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum SubwordClass {
+    Line,
+    /// Whitespace, `_` and the other word characters that split a word into
+    /// parts, like the `-` of kebab-case
+    Separator,
+    Punctuation,
+    Ideographic,
+    Upper,
+    Lower,
+    Digit,
+    Other,
+}
+
+impl SubwordClass {
+    /// Whether the character of class `next` belongs to the same sub-word as
+    /// the one of this class before it, going forward. The separators after
+    /// a sub-word are part of it.
+    fn joins(self, next: SubwordClass) -> bool {
+        use SubwordClass::*;
+        match (self, next) {
+            (Line, _) | (_, Line) => false,
+            (_, Separator) => true,
+            (Separator, _) => false,
+            (Upper, Upper | Lower)
+            | (Lower, Lower)
+            | (Digit, Digit)
+            | (Punctuation, Punctuation)
+            | (Other, Other) => true,
+            _ => false,
+        }
+    }
+}
+
 /// A word boundary can be the start of a word, its end or both for punctuation
 #[derive(PartialEq, Eq)]
 enum WordBoundary {
@@ -53,12 +108,77 @@ fn is_boundary(&self) -> bool {
 /// Boundaries can be the start of a word, its end, punctuation etc.
 pub struct WordCursor<'a> {
     pub(crate) inner: Cursor<'a, RopeInfo>,
+    /// Characters that are part of words besides letters, digits and `_`, like
+    /// `-` for CSS.
+    word_chars: &'a str,
 }
 
 impl<'a> WordCursor<'a> {
     pub fn new(text: &'a Rope, pos: usize) -> WordCursor<'a> {
         let inner = Cursor::new(text, pos);
-        WordCursor { inner }
+        WordCursor {
+            inner,
+            word_chars: "",
+        }
+    }
+
+    /// Take the characters as part of words too.
+    pub fn with_word_chars(mut self, word_chars: &'a str) -> Self {
+        self.word_chars = word_chars;
+        self
+    }
+
+    fn char_property(&self, c: char) -> CharClassification {
+        if self.word_chars.contains(c) {
+            CharClassification::Other
+        } else {
+            get_char_property(c)
+        }
+    }
+
+    /// The sub-word class of the character, by which camelCase humps and
+    /// snake_case parts are told apart.
+    fn subword_class(&self, c: char) -> SubwordClass {
+        match self.char_property(c) {
+            CharClassification::Cr | CharClassification::Lf => SubwordClass::Line,
+            CharClassification::Space => SubwordClass::Separator,
+            CharClassification::Punctuation => SubwordClass::Punctuation,
+            CharClassification::Ideographic => SubwordClass::Ideographic,
+            CharClassification::Katakana | CharClassification::Other => {
+                if c.is_uppercase() {
+                    SubwordClass::Upper
+                } else if c.is_lowercase() {
+                    SubwordClass::Lower
+                } else if c.is_numeric() {
+                    SubwordClass::Digit
+                } else if c == '_' || c.is_ascii_punctuation() {
+                    SubwordClass::Separator
+                } else {
+                    SubwordClass::Other
+                }
+            }
+        }
+    }
+
+    /// Get the first character of the grapheme cluster before the cursor, and
+    /// move the cursor to its start. Going by grapheme clusters keeps combining
+    /// marks and the rest of an emoji sequence with the character they belong to.
+    fn prev_grapheme_char(&mut self) -> Option<char> {
+        let end = self.inner.pos();
+        let start = self.inner.prev_grapheme()?;
+        self.inner.set(start);
+        let c = self.inner.peek_next_codepoint()?;
+        Some(grapheme_char(c, end - start))
+    }
+
+    /// Get the first character of the grapheme cluster after the cursor, and
+    /// move the cursor to its end.
+    fn next_grapheme_char(&mut self) -> Option<char> {
+        let start = self.inner.pos();
+        let c = self.inner.peek_next_codepoint()?;
+        let end = self.inner.next_grapheme()?;
+        self.inner.set(end);
+        Some(grapheme_char(c, end - start))
     }
 
     /// Get the previous start boundary of a word, and set the cursor position to the boundary found.
@@ -77,11 +197,11 @@ pub fn new(text: &'a Rope, pos: usize) -> WordCursor<'a> {
     /// assert_eq!(boundary, Some(0));
     ///```
     pub fn prev_boundary(&mut self, mode: Mode) -> Option<usize> {
-        if let Some(ch) = self.inner.prev_codepoint() {
-            let mut prop = get_char_property(ch);
+        if let Some(ch) = self.prev_grapheme_char() {
+            let mut prop = self.char_property(ch);
             let mut candidate = self.inner.pos();
-            while let Some(prev) = self.inner.prev_codepoint() {
-                let prop_prev = get_char_property(prev);
+            while let Some(prev) = self.prev_grapheme_char() {
+                let prop_prev = self.char_property(prev);
                 if classify_boundary(prop_prev, prop).is_start() {
                     break;
                 }
@@ -120,15 +240,15 @@ pub fn prev_boundary(&mut self, mode: Mode) -> Option<usize> {
     /// assert_eq!(&text[..position.unwrap()], "violet ");
     ///```
     pub fn prev_deletion_boundary(&mut self) -> Option<usize> {
-        if let Some(ch) = self.inner.prev_codepoint() {
-            let mut prop = get_char_property(ch);
+        if let Some(ch) = self.prev_grapheme_char() {
+            let mut prop = self.char_property(ch);
             let mut candidate = self.inner.pos();
 
             // Flag, determines if the word should be deleted or not
             // If not, erase only whitespace characters.
             let mut keep_word = false;
-            while let Some(prev) = self.inner.prev_codepoint() {
-                let prop_prev = get_char_property(prev);
+            while let Some(prev) = self.prev_grapheme_char() {
+                let prop_prev = self.char_property(prev);
 
                 // Stop if line beginning reached, without any non-whitespace characters
                 if prop_prev == CharClassification::Lf
@@ -150,10 +270,7 @@ pub fn prev_deletion_boundary(&mut self) -> Option<usize> {
                 }
 
                 // Skip word deletion if above conditions were met
-                if keep_word
-                    && (prop_prev == CharClassification::Punctuation
-                        || prop_prev == CharClassification::Other)
-                {
+                if keep_word && prop_prev.is_word_or_punctuation() {
                     break;
                 }
 
@@ -184,8 +301,8 @@ pub fn prev_deletion_boundary(&mut self) -> Option<usize> {
     ///```
     pub fn next_non_blank_char(&mut self) -> usize {
         let mut candidate = self.inner.pos();
-        while let Some(next) = self.inner.next_codepoint() {
-            let prop = get_char_property(next);
+        while let Some(next) = self.next_grapheme_char() {
+            let prop = self.char_property(next);
             if prop != CharClassification::Space {
                 break;
             }
@@ -207,11 +324,11 @@ pub fn next_non_blank_char(&mut self) -> usize {
     /// assert_eq!(boundary, Some(6));
     ///```
     pub fn next_boundary(&mut self) -> Option<usize> {
-        if let Some(ch) = self.inner.next_codepoint() {
-            let mut prop = get_char_property(ch);
+        if let Some(ch) = self.next_grapheme_char() {
+            let mut prop = self.char_property(ch);
             let mut candidate = self.inner.pos();
-            while let Some(next) = self.inner.next_codepoint() {
-                let prop_next = get_char_property(next);
+            while let Some(next) = self.next_grapheme_char() {
+                let prop_next = self.char_property(next);
                 if classify_boundary(prop, prop_next).is_start() {
                     break;
                 }
@@ -236,12 +353,12 @@ pub fn next_boundary(&mut self) -> Option<usize> {
     /// assert_eq!(end_boundary, Some(5));
     ///```
     pub fn end_boundary(&mut self) -> Option<usize> {
-        self.inner.next_codepoint();
-        if let Some(ch) = self.inner.next_codepoint() {
-            let mut prop = get_char_property(ch);
+        self.next_grapheme_char();
+        if let Some(ch) = self.next_grapheme_char() {
+            let mut prop = self.char_property(ch);
             let mut candidate = self.inner.pos();
-            while let Some(next) = self.inner.next_codepoint() {
-                let prop_next = get_char_property(next);
+            while let Some(next) = self.next_grapheme_char() {
+                let prop_next = self.char_property(next);
                 if classify_boundary(prop, prop_next).is_end() {
                     break;
                 }
@@ -254,7 +371,8 @@ pub fn end_boundary(&mut self) -> Option<usize> {
         None
     }
 
-    /// Get the first matching [`CharClassification::Other`] backward and set the cursor position to this location .
+    /// Get the start of the word before the cursor, made of word characters that
+    /// no word boundary falls between, and set the cursor position to it.
     /// **Example:**
     ///
     /// ```rust
@@ -268,17 +386,27 @@ pub fn end_boundary(&mut self) -> Option<usize> {
     ///```
     pub fn prev_code_boundary(&mut self) -> usize {
         let mut candidate = self.inner.pos();
-        while let Some(prev) = self.inner.prev_codepoint() {
-            let prop_prev = get_char_property(prev);
-            if prop_prev != CharClassification::Other {
+        let mut prop = self
+            .inner
+            .peek_next_codepoint()
+            .map(|c| self.char_property(c))
+            .filter(|prop| prop.is_word());
+        while let Some(prev) = self.prev_grapheme_char() {
+            let prop_prev = self.char_property(prev);
+            if !prop_prev.is_word()
+                || prop.is_some_and(|prop| {
+                    classify_boundary(prop_prev, prop) != WordBoundary::Interior
+                })
+            {
                 break;
             }
+            prop = Some(prop_prev);
             candidate = self.inner.pos();
         }
         candidate
     }
 
-    /// Get the first matching [`CharClassification::Other`] forward and set the cursor position to this location .
+    /// Get the end of the word after the cursor, and set the cursor position to it.
     /// **Example:**
     ///
     /// ```rust
@@ -292,16 +420,106 @@ pub fn prev_code_boundary(&mut self) -> usize {
     ///```
     pub fn next_code_boundary(&mut self) -> usize {
         let mut candidate = self.inner.pos();
-        while let Some(prev) = self.inner.next_codepoint() {
-            let prop_prev = get_char_property(prev);
-            if prop_prev != CharClassification::Other {
+        let mut prop = None;
+        while let Some(next) = self.next_grapheme_char() {
+            let prop_next = self.char_property(next);
+            if !prop_next.is_word()
+                || prop.is_some_and(|prop| {
+                    classify_boundary(prop, prop_next) != WordBoundary::Interior
+                })
+            {
                 break;
             }
+            prop = Some(prop_next);
             candidate = self.inner.pos();
         }
         candidate
     }
 
+    /// Get the start of the next sub-word, which is the next word or the next
+    /// hump of a camelCase word or part of a snake_case one, and set the cursor
+    /// position to it.
+    /// **Example:**
+    ///
+    /// ```rust
+    /// # use lapce_core::word::WordCursor;
+    /// # use lapce_xi_rope::Rope;
+    /// let rope = Rope::from("parseHTTPServer_name");
+    /// let mut cursor = WordCursor::new(&rope, 0);
+    /// assert_eq!(cursor.next_subword_boundary(), Some(5));
+    /// assert_eq!(cursor.next_subword_boundary(), Some(9));
+    /// assert_eq!(cursor.next_subword_boundary(), Some(16));
+    ///```
+    pub fn next_subword_boundary(&mut self) -> Option<usize> {
+        let start = self.inner.pos();
+        let mut class = self.subword_class(self.next_grapheme_char()?);
+        let mut uppers = usize::from(class == SubwordClass::Upper);
+        let mut last = start;
+        let mut candidate = self.inner.pos();
+        while let Some(next) = self.next_grapheme_char() {
+            let next = self.subword_class(next);
+            // The last capital of a run of them starts the next hump, like the
+            // `S` of `HTTPServer`
+            if class == SubwordClass::Upper
+                && next == SubwordClass::Lower
+                && uppers > 1
+            {
+                candidate = last;
+                break;
+            }
+            if !class.joins(next) {
+                break;
+            }
+            if next == SubwordClass::Upper {
+                uppers += 1;
+            }
+            class = next;
+            last = candidate;
+            candidate = self.inner.pos();
+        }
+        self.inner.set(candidate);
+        Some(candidate)
+    }
+
+    /// Get the start of the sub-word before the cursor, and set the cursor
+    /// position to it.
+    /// **Example:**
+    ///
+    /// ```rust
+    /// # use lapce_core::word::WordCursor;
+    /// # use lapce_xi_rope::Rope;
+    /// let rope = Rope::from("parseHTTPServer_name");
+    /// let mut cursor = WordCursor::new(&rope, 20);
+    /// assert_eq!(cursor.prev_subword_boundary(), Some(16));
+    /// assert_eq!(cursor.prev_subword_boundary(), Some(9));
+    /// assert_eq!(cursor.prev_subword_boundary(), Some(5));
+    ///```
+    pub fn prev_subword_boundary(&mut self) -> Option<usize> {
+        let mut class = self.subword_class(self.prev_grapheme_char()?);
+        let mut lowers = class == SubwordClass::Lower;
+        let mut candidate = self.inner.pos();
+        while let Some(prev) = self.prev_grapheme_char() {
+            let prev = self.subword_class(prev);
+            // Separators are skipped over to the sub-word before them
+            let joins = if class == SubwordClass::Separator {
+                prev != SubwordClass::Line
+            } else if class == SubwordClass::Upper && prev == SubwordClass::Upper {
+                // A hump ends at its capital
+                !lowers
+            } else {
+                prev != SubwordClass::Separator && prev.joins(class)
+            };
+            if !joins {
+                break;
+            }
+            lowers |= prev == SubwordClass::Lower;
+            class = prev;
+            candidate = self.inner.pos();
+        }
+        self.inner.set(candidate);
+        Some(candidate)
+    }
+
     /// Looks for a matching pair character, either forward for opening chars (ex: `(`) or
     /// backward for closing char (ex: `}`), and return the matched character position if found.
     /// Will return `None` if the character under cursor is not matchable (see [`crate::syntax::util::matching_char`]).
@@ -445,7 +663,19 @@ pub fn find_enclosing_pair(&mut self) -> Option<(usize, usize)> {
     }
 }
 
-/// Return the [`CharClassification`] of the input character
+/// The character a grapheme cluster is classified by, which is its first one,
+/// except for a CRLF line ending, which is one cluster taken as a line feed.
+fn grapheme_char(first: char, len: usize) -> char {
+    if first == '\r' && len > 1 {
+        '\n'
+    } else {
+        first
+    }
+}
+
+/// Return the [`CharClassification`] of the input character. Outside of ASCII,
+/// the classes follow the word boundary rules of Unicode (UAX #29) for the
+/// scripts that aren't written with spaces between words.
 pub fn get_char_property(codepoint: char) -> CharClassification {
     if codepoint <= ' ' {
         if codepoint == '\r' {
@@ -464,10 +694,56 @@ pub fn get_char_property(codepoint: char) -> CharClassification {
         if (0x7800000178000001u64 >> ((codepoint as u32) & 0x3f)) & 1 != 0 {
             return CharClassification::Punctuation;
         }
+    } else {
+        match codepoint {
+            '\u{a0}' | '\u{2000}'..='\u{200a}' | '\u{3000}' => {
+                return CharClassification::Space;
+            }
+            '\u{2010}'..='\u{2027}'
+            | '\u{2030}'..='\u{205e}'
+            | '\u{3001}'..='\u{3004}'
+            | '\u{3008}'..='\u{3020}'
+            | '\u{ff01}'..='\u{ff0f}'
+            | '\u{ff1a}'..='\u{ff20}'
+            | '\u{ff3b}'..='\u{ff40}'
+            | '\u{ff5b}'..='\u{ff65}' => return CharClassification::Punctuation,
+            '\u{3005}'..='\u{3007}'
+            | '\u{3040}'..='\u{309f}'
+            | '\u{3400}'..='\u{4dbf}'
+            | '\u{4e00}'..='\u{9fff}'
+            | '\u{f900}'..='\u{faff}'
+            | '\u{20000}'..='\u{3134f}' => return CharClassification::Ideographic,
+            '\u{30a0}'..='\u{30ff}'
+            | '\u{31f0}'..='\u{31ff}'
+            | '\u{ff66}'..='\u{ff9f}' => return CharClassification::Katakana,
+            _ => {}
+        }
     }
     CharClassification::Other
 }
 
+/// The start and end of each whole-word occurrence of the word in the text,
+/// with the `word_chars` taken as part of words too.
+pub fn word_occurrences(
+    text: &Rope,
+    word: &str,
+    word_chars: &str,
+) -> Vec<(usize, usize)> {
+    let is_word = |c: char| word_chars.contains(c) || get_char_property(c).is_word();
+    if word.is_empty() || !word.chars().all(is_word) {
+        return Vec::new();
+    }
+
+    let text = text.slice_to_cow(0..text.len());
+    text.match_indices(word)
+        .map(|(start, _)| (start, start + word.len()))
+        .filter(|(start, end)| {
+            !text[..*start].chars().next_back().is_some_and(is_word)
+                && !text[*end..].chars().next().is_some_and(is_word)
+        })
+        .collect()
+}
+
 fn classify_boundary(
     prev: CharClassification,
     next: CharClassification,
@@ -487,6 +763,9 @@ fn classify_boundary(
         (_, Lf) => End,
         (Punctuation, Other) => Both,
         (Other, Punctuation) => Both,
+        // Every ideograph is a word, while a run of katakana is one
+        (Katakana, Katakana) => Interior,
+        (Ideographic | Katakana, _) | (_, Ideographic | Katakana) => Both,
         _ => Interior,
     }
 }
@@ -495,7 +774,7 @@ fn classify_boundary(
 mod test {
     use lapce_xi_rope::Rope;
 
-    use super::WordCursor;
+    use super::{word_occurrences, WordCursor};
     use crate::mode::Mode;
 
     #[test]
@@ -664,6 +943,40 @@ fn select_word_should_return_word_boundaries() {
         assert_eq!(&text[start..end], "are");
     }
 
+    #[test]
+    fn select_word_should_take_extra_word_chars() {
+        let text = "color: font-size;";
+        let rope = Rope::from(text);
+        let (start, end) = WordCursor::new(&rope, 9).select_word();
+        assert_eq!(&text[start..end], "font");
+        let (start, end) =
+            WordCursor::new(&rope, 9).with_word_chars("-").select_word();
+        assert_eq!(&text[start..end], "font-size");
+    }
+
+    #[test]
+    fn subword_boundaries_should_stop_at_humps_and_underscores() {
+        let rope = Rope::from("foo_bar bazQux");
+        let mut cursor = WordCursor::new(&rope, 0);
+        assert_eq!(cursor.next_subword_boundary(), Some(4));
+        assert_eq!(cursor.next_subword_boundary(), Some(8));
+        assert_eq!(cursor.next_subword_boundary(), Some(11));
+        assert_eq!(cursor.next_subword_boundary(), Some(14));
+        assert_eq!(cursor.next_subword_boundary(), None);
+
+        assert_eq!(cursor.prev_subword_boundary(), Some(11));
+        assert_eq!(cursor.prev_subword_boundary(), Some(8));
+        assert_eq!(cursor.prev_subword_boundary(), Some(4));
+        assert_eq!(cursor.prev_subword_boundary(), Some(0));
+        assert_eq!(cursor.prev_subword_boundary(), None);
+
+        let rope = Rope::from("utf8Decode");
+        let mut cursor = WordCursor::new(&rope, 0);
+        assert_eq!(cursor.next_subword_boundary(), Some(3));
+        assert_eq!(cursor.next_subword_boundary(), Some(4));
+        assert_eq!(cursor.next_subword_boundary(), Some(10));
+    }
+
     #[test]
     fn should_get_deletion_boundary_backward() {
         let text = "violet are blue";
@@ -675,6 +988,31 @@ fn should_get_deletion_boundary_backward() {
         assert_eq!(&text[..position.unwrap()], "violet ");
     }
 
+    #[test]
+    fn every_ideograph_should_be_a_word() {
+        let rope = Rope::from("中文abc カタカナ漢字");
+        let mut cursor = WordCursor::new(&rope, 0);
+        assert_eq!(cursor.next_boundary(), Some(3));
+        assert_eq!(cursor.next_boundary(), Some(6));
+        assert_eq!(cursor.next_boundary(), Some(10));
+        assert_eq!(cursor.next_boundary(), Some(22));
+        let mut cursor = WordCursor::new(&rope, 3);
+        assert_eq!(cursor.select_word(), (3, 6));
+    }
+
+    #[test]
+    fn word_boundaries_should_not_split_graphemes() {
+        let text = "cafe\u{301}, x";
+        let rope = Rope::from(text);
+        let mut cursor = WordCursor::new(&rope, 0);
+        assert_eq!(cursor.select_word(), (0, 6));
+        let mut cursor = WordCursor::new(&rope, 0);
+        assert_eq!(cursor.end_boundary(), Some(6));
+        let rope = Rope::from("a\r\n    b");
+        let mut cursor = WordCursor::new(&rope, 7);
+        assert_eq!(cursor.prev_boundary(Mode::Insert), Some(3));
+    }
+
     #[test]
     fn find_pair_should_return_positions() {
         let text = "violet (are) blue";
@@ -710,4 +1048,15 @@ fn find_pair_should_return_none() {
         let positions = cursor.find_enclosing_pair();
         assert_eq!(positions, None);
     }
+
+    #[test]
+    fn word_occurrences_should_match_whole_words() {
+        let rope = Rope::from("foo foo_bar foo-bar (foo)");
+        assert_eq!(
+            word_occurrences(&rope, "foo", ""),
+            vec![(0, 3), (12, 15), (21, 24)]
+        );
+        assert_eq!(word_occurrences(&rope, "foo", "-"), vec![(0, 3), (21, 24)]);
+        assert!(word_occurrences(&rope, "(", "").is_empty());
+    }
 }
@@ -5,21 +5,38 @@
     fs::File,
     io::{Read, Write},
     path::{Path, PathBuf},
+    string::FromUtf8Error,
     time::SystemTime,
 };
 
 use anyhow::{anyhow, Result};
 use lapce_core::{
     buffer::rope_text::CharIndicesJoin, encoding::offset_utf8_to_utf16,
+    language::LapceLanguage,
 };
-use lapce_rpc::buffer::BufferId;
+use lapce_rpc::buffer::{BufferId, LineEnding};
 use lapce_xi_rope::{interval::IntervalBounds, rope::Rope, RopeDelta};
 use lsp_types::*;
 
+/// Files bigger than this (in bytes) are opened in large file mode, where the
+/// buffer isn't sent to language servers.
+pub const LARGE_FILE_SIZE: u64 = 64 * 1024 * 1024;
+/// The number of bytes of a binary file that are shown in its hex dump.
+pub const HEX_DUMP_LIMIT: usize = 256 * 1024;
+/// Large files are sent to the editor in chunks of about this size (in bytes),
+/// so no single message has to carry the whole file.
+pub const LARGE_FILE_CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
 #[derive(Clone)]
 pub struct Buffer {
     pub language_id: &'static str,
     pub read_only: bool,
+    /// Whether the content is the text of the file, rather than a hex dump of a
+    /// binary file or a message saying why it couldn't be read. Only then can the
+    /// buffer be made writable.
+    pub is_text: bool,
+    pub large_file: bool,
+    pub line_ending: LineEnding,
     pub id: BufferId,
     pub rope: Rope,
     pub path: PathBuf,
@@ -29,27 +46,36 @@ pub struct Buffer {
 
 impl Buffer {
     pub fn new(id: BufferId, path: PathBuf) -> Buffer {
-        let (s, read_only) = match load_file(&path) {
-            Ok(s) => (s, false),
+        let (s, read_only, is_text) = match load_file(&path) {
+            Ok(s) => (s, is_readonly_file(&path), true),
             Err(err) => match err.downcast_ref::<std::io::Error>() {
                 Some(err) => match err.kind() {
                     std::io::ErrorKind::PermissionDenied => {
-                        ("Permission Denied".to_string(), true)
+                        ("Permission Denied".to_string(), true, false)
                     }
-                    std::io::ErrorKind::NotFound => ("".to_string(), false),
-                    _ => ("Not Supported".to_string(), true),
+                    std::io::ErrorKind::NotFound => ("".to_string(), false, true),
+                    _ => ("Not Supported".to_string(), true, false),
+                },
+                None => match err.downcast_ref::<FromUtf8Error>() {
+                    // Binary files are shown as a read only hex dump
+                    Some(err) => (hex_dump(err.as_bytes()), true, false),
+                    None => ("Not Supported".to_string(), true, false),
                 },
-                None => ("Not Supported".to_string(), true),
             },
         };
-        let rope = Rope::from(s);
+        let line_ending = LineEnding::detect(&s);
+        let rope = Rope::from(LineEnding::normalize(s));
         let rev = u64::from(!rope.is_empty());
-        let language_id = language_id_from_path(&path).unwrap_or("");
+        let language_id = language_id_from_content(&path, &rope).unwrap_or("");
         let mod_time = get_mod_time(&path);
+        let large_file = rope.len() as u64 > LARGE_FILE_SIZE;
         Buffer {
             id,
             rope,
             read_only,
+            is_text,
+            large_file,
+            line_ending,
             path,
             language_id,
             rev,
@@ -57,7 +83,17 @@ pub fn new(id: BufferId, path: PathBuf) -> Buffer {
         }
     }
 
-    pub fn save(&mut self, rev: u64, create_parents: bool) -> Result<()> {
+    /// Save the buffer by writing it to a temporary file next to the target and
+    /// renaming it over the original, so the file on disk is never half written.
+    ///
+    /// If the path is a symlink and `write_through_symlink` is set, the file the
+    /// link points to is replaced, otherwise the link itself is replaced.
+    pub fn save(
+        &mut self,
+        rev: u64,
+        create_parents: bool,
+        write_through_symlink: bool,
+    ) -> Result<()> {
         if self.read_only {
             return Err(anyhow!("can't save to read only file"));
         }
@@ -65,25 +101,11 @@ pub fn save(&mut self, rev: u64, create_parents: bool) -> Result<()> {
         if self.rev != rev {
             return Err(anyhow!("not the right rev"));
         }
-        let bak_extension = self.path.extension().map_or_else(
-            || OsString::from("bak"),
-            |ext| {
-                let mut ext = ext.to_os_string();
-                ext.push(".bak");
-                ext
-            },
-        );
-        let path = if self.path.is_symlink() {
+        let path = if write_through_symlink && self.path.is_symlink() {
             self.path.canonicalize()?
         } else {
             self.path.clone()
         };
-        let new_file = !path.exists();
-
-        let bak_file_path = &path.with_extension(bak_extension);
-        if !new_file {
-            fs::copy(&path, bak_file_path)?;
-        }
 
         if create_parents {
             if let Some(parent) = path.parent() {
@@ -91,20 +113,47 @@ pub fn save(&mut self, rev: u64, create_parents: bool) -> Result<()> {
             }
         }
 
+        let metadata = fs::metadata(&path).ok();
+        let tmp_path = save_tmp_path(&path)?;
+        let result = self.write_to(&tmp_path).and_then(|_| {
+            if let Some(metadata) = metadata.as_ref() {
+                fs::set_permissions(&tmp_path, metadata.permissions())?;
+                copy_ownership(&tmp_path, metadata);
+            }
+            fs::rename(&tmp_path, &path)?;
+            Ok(())
+        });
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+        }
+        result?;
+        sync_parent_dir(&path);
+
+        self.mod_time = get_mod_time(&path);
+
+        Ok(())
+    }
+
+    /// Write the content to a new file and flush it to disk.
+    fn write_to(&self, path: &Path) -> Result<()> {
         let mut f = fs::OpenOptions::new()
-            .create(true)
             .write(true)
+            .create(true)
             .truncate(true)
-            .open(&path)?;
+            .open(path)?;
+        // Text pasted from elsewhere can already have `\r\n`, so only the bare
+        // `\n` are converted, also when the `\r` ended the previous chunk
+        let mut after_cr = false;
         for chunk in self.rope.iter_chunks(..self.rope.len()) {
-            f.write_all(chunk.as_bytes())?;
-        }
-
-        self.mod_time = get_mod_time(&path);
-        if !new_file {
-            fs::remove_file(bak_file_path)?;
+            match self.line_ending {
+                LineEnding::Lf => f.write_all(chunk.as_bytes())?,
+                LineEnding::CrLf => {
+                    f.write_all(to_crlf(chunk, after_cr).as_bytes())?;
+                    after_cr = chunk.ends_with('\r');
+                }
+            }
         }
-
+        f.sync_all()?;
         Ok(())
     }
 
@@ -128,6 +177,24 @@ pub fn update(
         )
     }
 
+    /// The chunk of a large file's content that starts at `offset`, and whether
+    /// there is more after it. A chunk ends after a line break when there is one
+    /// within the chunk size, so the editor doesn't have to lay out half lines.
+    pub fn content_chunk(&self, offset: usize) -> (String, bool) {
+        let len = self.rope.len();
+        let offset = offset.min(len);
+        let mut end = (offset + LARGE_FILE_CHUNK_SIZE).min(len);
+        if end < len {
+            let line_start = self.rope.offset_of_line(self.rope.line_of_offset(end));
+            end = if line_start > offset {
+                line_start
+            } else {
+                self.rope.at_or_next_codepoint_boundary(end).unwrap_or(len)
+            };
+        }
+        (self.rope.slice_to_cow(offset..end).into_owned(), end < len)
+    }
+
     pub fn get_document(&self) -> String {
         self.rope.to_string()
     }
@@ -182,6 +249,111 @@ pub fn is_empty(&self) -> bool {
     }
 }
 
+/// Turn every `\n` that doesn't follow a `\r` into `\r\n`. `after_cr` is whether
+/// the text before this one ended with a `\r`.
+fn to_crlf(text: &str, after_cr: bool) -> Cow<str> {
+    let bytes = text.as_bytes();
+    let is_bare = |i: usize| {
+        if i == 0 {
+            !after_cr
+        } else {
+            bytes[i - 1] != b'\r'
+        }
+    };
+    if !text.match_indices('\n').any(|(i, _)| is_bare(i)) {
+        return Cow::Borrowed(text);
+    }
+    let mut result = String::with_capacity(text.len() + text.len() / 32);
+    let mut start = 0;
+    for (i, _) in text.match_indices('\n') {
+        if is_bare(i) {
+            result.push_str(&text[start..i]);
+            result.push('\r');
+            start = i;
+        }
+    }
+    result.push_str(&text[start..]);
+    Cow::Owned(result)
+}
+
+/// Whether the file exists but we don't have permission to write to it, be it
+/// because of its mode, its owner or an ACL.
+#[cfg(unix)]
+pub fn is_readonly_file(path: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+
+    if !path.exists() {
+        return false;
+    }
+    match std::ffi::CString::new(path.as_os_str().as_bytes()) {
+        Ok(c_path) => unsafe { libc::access(c_path.as_ptr(), libc::W_OK) != 0 },
+        Err(_) => false,
+    }
+}
+
+/// Whether the file exists but we don't have permission to write to it. Opening
+/// it for writing is what tells, since that takes ACLs into account too.
+#[cfg(not(unix))]
+pub fn is_readonly_file(path: &Path) -> bool {
+    path.exists() && fs::OpenOptions::new().write(true).open(path).is_err()
+}
+
+/// Give the owner write permission on the file.
+pub fn make_writable(path: &Path) -> Result<()> {
+    let mut permissions = fs::metadata(path)?.permissions();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        permissions.set_mode(permissions.mode() | 0o200);
+    }
+    #[cfg(not(unix))]
+    permissions.set_readonly(false);
+    fs::set_permissions(path, permissions)?;
+    Ok(())
+}
+
+/// The temporary file a save is written to before being renamed over `path`.
+/// It lives in the same directory so the rename doesn't cross file systems.
+fn save_tmp_path(path: &Path) -> Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("{path:?} is not a file"))?;
+    let mut tmp_name = OsString::from(".");
+    tmp_name.push(file_name);
+    tmp_name.push(format!(".{}.lapce-save", std::process::id()));
+    Ok(path.with_file_name(tmp_name))
+}
+
+/// Give the file the same owner and group as the original. This only succeeds
+/// when we are allowed to, which is fine because a file we create is owned by
+/// us anyway.
+#[cfg(unix)]
+fn copy_ownership(path: &Path, metadata: &fs::Metadata) {
+    use std::os::unix::{ffi::OsStrExt, fs::MetadataExt};
+
+    if let Ok(c_path) = std::ffi::CString::new(path.as_os_str().as_bytes()) {
+        unsafe {
+            libc::chown(c_path.as_ptr(), metadata.uid(), metadata.gid());
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn copy_ownership(_path: &Path, _metadata: &fs::Metadata) {}
+
+/// Make sure the rename itself survives a crash by syncing the directory entry.
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) {
+    if let Some(parent) = path.parent() {
+        if let Ok(dir) = File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) {}
+
 pub fn load_file(path: &Path) -> Result<String> {
     read_path_to_string(path)
 }
@@ -200,6 +372,105 @@ pub fn read_path_to_string<P: AsRef<Path>>(path: P) -> Result<String> {
     Ok(contents.to_string())
 }
 
+/// Format the bytes like `hexdump -C`, with an offset column, sixteen hex bytes and
+/// their printable ASCII characters on each line. Only the first
+/// [`HEX_DUMP_LIMIT`] bytes are shown, with a notice of how many more there are.
+pub fn hex_dump(bytes: &[u8]) -> String {
+    let shown = &bytes[..bytes.len().min(HEX_DUMP_LIMIT)];
+    let mut s = String::with_capacity(shown.len() / 16 * 80 + 160);
+    for (i, line) in shown.chunks(16).enumerate() {
+        s.push_str(&format!("{:08x} ", i * 16));
+        for j in 0..16 {
+            if j == 8 {
+                s.push(' ');
+            }
+            match line.get(j) {
+                Some(b) => s.push_str(&format!(" {b:02x}")),
+                None => s.push_str("   "),
+            }
+        }
+        s.push_str("  |");
+        for b in line {
+            if b.is_ascii_graphic() || *b == b' ' {
+                s.push(*b as char);
+            } else {
+                s.push('.');
+            }
+        }
+        s.push_str("|\n");
+    }
+    if bytes.len() > shown.len() {
+        s.push_str(&format!(
+            "\n{} more bytes of the {} byte file aren't shown\n",
+            bytes.len() - shown.len(),
+            bytes.len()
+        ));
+    }
+    s
+}
+
+/// Like `language_id_from_path`, but a modeline in the content decides over the
+/// path, and file names and shebang lines it doesn't know are detected too.
+pub fn language_id_from_content(path: &Path, text: &Rope) -> Option<&'static str> {
+    LapceLanguage::from_modeline(text)
+        .and_then(language_id_from_language)
+        .or_else(|| language_id_from_path(path))
+        .or_else(|| {
+            language_id_from_language(LapceLanguage::from_content(path, text))
+        })
+}
+
+/// The language id language servers know a language picked in the editor by.
+pub fn language_id_from_language(language: LapceLanguage) -> Option<&'static str> {
+    Some(match language {
+        LapceLanguage::Bash | LapceLanguage::Sh => "shellscript",
+        LapceLanguage::C => "c",
+        LapceLanguage::Clojure => "clojure",
+        LapceLanguage::Cmake => "cmake",
+        LapceLanguage::Cpp => "cpp",
+        LapceLanguage::Csharp => "csharp",
+        LapceLanguage::Css => "css",
+        LapceLanguage::D => "dlang",
+        LapceLanguage::Dart => "dart",
+        LapceLanguage::Dockerfile => "dockerfile",
+        LapceLanguage::Elixir => "elixir",
+        LapceLanguage::Elm => "elm",
+        LapceLanguage::Erlang => "erlang",
+        LapceLanguage::Go => "go",
+        LapceLanguage::Haskell => "haskell",
+        LapceLanguage::Html => "html",
+        LapceLanguage::Java => "java",
+        LapceLanguage::Javascript => "javascript",
+        LapceLanguage::Json => "json",
+        LapceLanguage::Jsx => "javascriptreact",
+        LapceLanguage::Julia => "julia",
+        LapceLanguage::Kotlin => "kotlin",
+        LapceLanguage::Latex => "tex",
+        LapceLanguage::Lua => "lua",
+        LapceLanguage::Make => "makefile",
+        LapceLanguage::Markdown => "markdown",
+        LapceLanguage::Php => "php",
+        LapceLanguage::ProtoBuf => "proto",
+        LapceLanguage::Python => "python",
+        LapceLanguage::R => "r",
+        LapceLanguage::Ruby => "ruby",
+        LapceLanguage::Rust => "rust",
+        LapceLanguage::Scss => "scss",
+        LapceLanguage::Sql => "sql",
+        LapceLanguage::Svelte => "svelte",
+        LapceLanguage::Swift => "swift",
+        LapceLanguage::Toml => "toml",
+        LapceLanguage::Tsx => "typescriptreact",
+        LapceLanguage::Typescript => "typescript",
+        LapceLanguage::Vue => "vue",
+        LapceLanguage::Xml => "xml",
+        LapceLanguage::Yaml => "yaml",
+        LapceLanguage::Zig => "zig",
+        LapceLanguage::Runtime(_) => return language.runtime_language_id(),
+        _ => return None,
+    })
+}
+
 pub fn language_id_from_path(path: &Path) -> Option<&'static str> {
     // recommended language_id values
     // https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocumentItem
@@ -337,3 +608,26 @@ pub fn get_mod_time<P: AsRef<Path>>(path: P) -> Option<SystemTime> {
         .and_then(|meta| meta.modified())
         .ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{hex_dump, to_crlf, HEX_DUMP_LIMIT};
+
+    #[test]
+    fn test_to_crlf() {
+        assert_eq!(to_crlf("a\nb\n", false), "a\r\nb\r\n");
+        assert_eq!(to_crlf("a\r\nb\n", false), "a\r\nb\r\n");
+        assert_eq!(to_crlf("\na", true), "\na");
+        assert_eq!(to_crlf("\na", false), "\r\na");
+    }
+
+    #[test]
+    fn test_hex_dump_limit() {
+        let bytes = vec![0u8; HEX_DUMP_LIMIT + 100];
+        let dump = hex_dump(&bytes);
+        assert_eq!(dump.lines().count(), HEX_DUMP_LIMIT / 16 + 2);
+        assert!(
+            dump.ends_with("100 more bytes of the 262244 byte file aren't shown\n")
+        );
+    }
+}
@@ -24,6 +24,12 @@ pub fn parse_file_line_column(path: &str) -> Result<PathObject, Error> {
         });
     }
 
+    parse_position(path)
+}
+
+/// Like [`parse_file_line_column`], but a trailing `:line` or `:line:column` is
+/// read as the position even when a file is named like that.
+pub fn parse_position(path: &str) -> Result<PathObject, Error> {
     let pwd = std::env::current_dir().unwrap_or_default();
 
     let mut splits = path.rsplit(':').peekable();
@@ -67,7 +73,10 @@ pub fn parse_file_line_column(path: &str) -> Result<PathObject, Error> {
             )
         }
     } else {
-        (pwd.join(path), None)
+        let path = PathBuf::from(path)
+            .canonicalize()
+            .unwrap_or_else(|_| pwd.join(path));
+        (path, None)
     };
 
     Ok(PathObject {
@@ -94,7 +103,7 @@ pub fn try_open_in_existing_process(paths: &[PathObject]) -> Result<()> {
 mod tests {
     use std::{env, path::PathBuf};
 
-    use super::parse_file_line_column;
+    use super::{parse_file_line_column, parse_position};
     use crate::cli::PathObject;
 
     #[test]
@@ -216,4 +225,26 @@ fn test_relative_path_with_none() {
             ),
         );
     }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_file_named_like_position() {
+        let dir = env::temp_dir().join("lapce-cli-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let dir = dir.canonicalize().unwrap();
+        let file = dir.join("notes.txt:3");
+        std::fs::write(&file, "").unwrap();
+        let arg = file.to_str().unwrap();
+
+        assert_eq!(
+            parse_file_line_column(arg).unwrap(),
+            PathObject::from_path(file.clone(), false),
+        );
+        assert_eq!(
+            parse_position(arg).unwrap(),
+            PathObject::new(dir.join("notes.txt"), false, 3, 1),
+        );
+
+        std::fs::remove_file(&file).unwrap();
+    }
 }
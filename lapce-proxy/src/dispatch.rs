@@ -13,30 +13,40 @@
 use alacritty_terminal::{event::WindowSize, event_loop::Msg};
 use anyhow::{anyhow, Context, Result};
 use crossbeam_channel::Sender;
-use git2::{build::CheckoutBuilder, DiffOptions, Repository};
+use git2::{build::CheckoutBuilder, BranchType, DiffOptions, Repository};
 use grep_matcher::Matcher;
 use grep_regex::RegexMatcherBuilder;
 use grep_searcher::{sinks::UTF8, SearcherBuilder};
 use indexmap::IndexMap;
+use lapce_core::language::LapceLanguage;
 use lapce_rpc::{
+    buffer::LineEnding,
     core::{CoreNotification, CoreRpcHandler},
     file::FileNodeItem,
     proxy::{
         ProxyHandler, ProxyNotification, ProxyRequest, ProxyResponse,
-        ProxyRpcHandler, SearchMatch,
+        ProxyRpcHandler, SearchMatch, PATH_EXISTS_ERROR_CODE,
+        PROXY_PROTOCOL_VERSION,
     },
-    source_control::{DiffInfo, FileDiff},
+    source_control::{DiffInfo, FileCommit, FileDiff},
     style::{LineStyle, SemanticStyles},
     terminal::TermId,
     RequestId, RpcError,
 };
 use lapce_xi_rope::Rope;
-use lsp_types::{Position, Range, TextDocumentItem, Url};
+use lsp_types::{
+    MessageType, Position, Range, ShowMessageParams, TextDocumentItem, Url,
+};
 use parking_lot::Mutex;
 
 use crate::{
-    buffer::{get_mod_time, load_file, Buffer},
+    buffer::{
+        get_mod_time, language_id_from_language, load_file, make_writable, Buffer,
+    },
+    file_index::FileIndex,
     plugin::{catalog::PluginCatalog, remove_volt, PluginCatalogRpcHandler},
+    progress::{self, TaskProgress},
+    runnable,
     terminal::{Terminal, TerminalSender},
     watcher::{FileWatcher, Notify, WatchToken},
 };
@@ -46,12 +56,15 @@
 
 pub struct Dispatcher {
     workspace: Option<PathBuf>,
+    /// The other root folders of a multi-root workspace.
+    folders: Vec<PathBuf>,
     pub proxy_rpc: ProxyRpcHandler,
     core_rpc: CoreRpcHandler,
     catalog_rpc: PluginCatalogRpcHandler,
     buffers: HashMap<PathBuf, Buffer>,
     terminals: HashMap<TermId, TerminalSender>,
     file_watcher: FileWatcher,
+    file_index: Arc<Mutex<FileIndex>>,
     window_id: usize,
     tab_id: usize,
 }
@@ -62,29 +75,44 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
         match rpc {
             Initialize {
                 workspace,
+                folders,
                 disabled_volts,
+                granted_permissions,
                 plugin_configurations,
                 window_id,
                 tab_id,
+                protocol_version,
+                compression: _,
             } => {
+                self.core_rpc.proxy_handshake(PROXY_PROTOCOL_VERSION);
+                if protocol_version != PROXY_PROTOCOL_VERSION {
+                    // The editor tells the user, as whatever else it sends may
+                    // not be understood
+                    return;
+                }
                 self.window_id = window_id;
                 self.tab_id = tab_id;
                 self.workspace = workspace;
+                self.folders = folders;
+                *self.file_index.lock() = FileIndex::new(self.roots());
                 self.file_watcher.notify(FileWatchNotifier::new(
                     self.workspace.clone(),
                     self.core_rpc.clone(),
                     self.proxy_rpc.clone(),
+                    self.file_index.clone(),
                 ));
-                if let Some(workspace) = self.workspace.as_ref() {
-                    self.file_watcher
-                        .watch(workspace, true, WORKSPACE_EVENT_TOKEN);
+                for root in self.roots() {
+                    self.file_watcher.watch(&root, true, WORKSPACE_EVENT_TOKEN);
                 }
 
                 let plugin_rpc = self.catalog_rpc.clone();
+                plugin_rpc.set_granted_permissions(granted_permissions);
                 let workspace = self.workspace.clone();
+                let workspace_folders = self.roots();
                 thread::spawn(move || {
                     let mut plugin = PluginCatalog::new(
                         workspace,
+                        workspace_folders,
                         disabled_volts,
                         plugin_configurations,
                         plugin_rpc.clone(),
@@ -112,6 +140,7 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                         return;
                     }
                     if let Ok(content) = load_file(&buffer.path) {
+                        let content = LineEnding::normalize(content);
                         self.core_rpc.open_file_changed(path, content);
                     }
                 }
@@ -143,6 +172,9 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                 let buffer = self.buffers.get_mut(&path).unwrap();
                 let old_text = buffer.rope.clone();
                 buffer.update(&delta, rev);
+                if buffer.large_file {
+                    return;
+                }
                 self.catalog_rpc.did_change_text_document(
                     &path,
                     rev,
@@ -151,6 +183,57 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     buffer.rope.clone(),
                 );
             }
+            RestoreBuffers { buffers } => {
+                for restored in buffers {
+                    let mut buffer =
+                        Buffer::new(restored.buffer_id, restored.path.clone());
+                    buffer.rope = Rope::from(restored.content.as_str());
+                    buffer.rev = restored.rev;
+                    if !buffer.large_file {
+                        self.catalog_rpc.did_open_document(
+                            &restored.path,
+                            buffer.language_id,
+                            buffer.rev as i32,
+                            restored.content,
+                        );
+                    }
+                    self.file_watcher.watch(
+                        &restored.path,
+                        false,
+                        OPEN_FILE_EVENT_TOKEN,
+                    );
+                    self.buffers.insert(restored.path, buffer);
+                }
+            }
+            SetLineEnding { path, line_ending } => {
+                if let Some(buffer) = self.buffers.get_mut(&path) {
+                    buffer.line_ending = line_ending;
+                }
+            }
+            SetLanguage { path, language } => {
+                let Some(buffer) = self.buffers.get_mut(&path) else {
+                    return;
+                };
+                let language_id = LapceLanguage::from_name(&language)
+                    .and_then(language_id_from_language)
+                    .unwrap_or("");
+                if buffer.language_id == language_id {
+                    return;
+                }
+                let old_language_id =
+                    std::mem::replace(&mut buffer.language_id, language_id);
+                // The document is closed for the language servers of the old
+                // language, and opened again for the ones of the new language
+                if !buffer.large_file {
+                    self.catalog_rpc.did_close_document(&path, old_language_id);
+                    self.catalog_rpc.did_open_document(
+                        &path,
+                        language_id,
+                        buffer.rev as i32,
+                        buffer.rope.to_string(),
+                    );
+                }
+            }
             UpdatePluginConfigs { configs } => {
                 let _ = self.catalog_rpc.update_plugin_configs(configs);
             }
@@ -280,6 +363,26 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
             EnableVolt { volt } => {
                 let _ = self.catalog_rpc.enable_volt(volt);
             }
+            GrantVoltPermissions { volt } => {
+                let _ = self.catalog_rpc.grant_permissions(volt);
+            }
+            RevokeVoltPermissions { volt } => {
+                let _ = self.catalog_rpc.revoke_permissions(volt);
+            }
+            ExecutePluginCommand { volt, id } => {
+                let _ = self.catalog_rpc.execute_plugin_command(volt, id);
+            }
+            PluginQuickPickResult { id, index } => {
+                self.catalog_rpc.quick_pick_result(id, index);
+            }
+            PluginMessageActionResult { id, index } => {
+                self.catalog_rpc.message_action_result(id, index);
+            }
+            CancelWorkDoneProgress { token } => {
+                if !progress::cancel(&token) {
+                    self.catalog_rpc.cancel_work_done_progress(token);
+                }
+            }
             GitCommit { message, diffs } => {
                 if let Some(workspace) = self.workspace.as_ref() {
                     match git_commit(workspace, &message, diffs) {
@@ -296,6 +399,29 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     }
                 }
             }
+            GitCreateBranch { name } => {
+                if let Some(workspace) = self.workspace.as_ref() {
+                    if let Err(e) = git_create_branch(workspace, &name) {
+                        self.core_rpc.show_message(
+                            "Create Branch".to_string(),
+                            ShowMessageParams {
+                                typ: MessageType::ERROR,
+                                message: e.to_string(),
+                            },
+                        );
+                    }
+                }
+            }
+            GitPull {} => {
+                if let Some(workspace) = self.workspace.clone() {
+                    git_remote_command(self.core_rpc.clone(), workspace, "pull");
+                }
+            }
+            GitPush {} => {
+                if let Some(workspace) = self.workspace.clone() {
+                    git_remote_command(self.core_rpc.clone(), workspace, "push");
+                }
+            }
             GitDiscardFilesChanges { files } => {
                 if let Some(workspace) = self.workspace.as_ref() {
                     match git_discard_files_changes(
@@ -331,21 +457,52 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
         match rpc {
             NewBuffer { buffer_id, path } => {
                 let buffer = Buffer::new(buffer_id, path.clone());
-                let content = buffer.rope.to_string();
                 let read_only = buffer.read_only;
-                self.catalog_rpc.did_open_document(
-                    &path,
-                    buffer.language_id.to_string(),
-                    buffer.rev as i32,
-                    content.clone(),
-                );
+                let large_file = buffer.large_file;
+                let line_ending = buffer.line_ending;
+                // Language servers aren't told about large files, as syncing
+                // them would be too expensive. The editor gets their content a
+                // chunk at a time.
+                let (content, more) = if large_file {
+                    buffer.content_chunk(0)
+                } else {
+                    let content = buffer.rope.to_string();
+                    self.catalog_rpc.did_open_document(
+                        &path,
+                        buffer.language_id,
+                        buffer.rev as i32,
+                        content.clone(),
+                    );
+                    (content, false)
+                };
                 self.file_watcher.watch(&path, false, OPEN_FILE_EVENT_TOKEN);
                 self.buffers.insert(path, buffer);
                 self.respond_rpc(
                     id,
-                    Ok(ProxyResponse::NewBufferResponse { content, read_only }),
+                    Ok(ProxyResponse::NewBufferResponse {
+                        content,
+                        read_only,
+                        large_file,
+                        line_ending,
+                        more,
+                    }),
                 );
             }
+            BufferChunk { path, offset } => {
+                let result = match self.buffers.get_mut(&path) {
+                    Some(buffer) => {
+                        let (content, more) = buffer.content_chunk(offset);
+                        // The editor adds each chunk as a revision of its own
+                        buffer.rev += 1;
+                        Ok(ProxyResponse::BufferChunkResponse { content, more })
+                    }
+                    None => Err(RpcError {
+                        code: 0,
+                        message: format!("{path:?} isn't open"),
+                    }),
+                };
+                self.respond_rpc(id, result);
+            }
             BufferHead { path } => {
                 let result = if let Some(workspace) = self.workspace.as_ref() {
                     let result = file_get_head(workspace, &path);
@@ -368,6 +525,57 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 };
                 self.respond_rpc(id, result);
             }
+            GitFileHistory { path } => {
+                let Some(workspace) = self.workspace.clone() else {
+                    self.respond_rpc(
+                        id,
+                        Err(RpcError {
+                            code: 0,
+                            message: "no workspace set".to_string(),
+                        }),
+                    );
+                    return;
+                };
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = git_file_history(&workspace, &path)
+                        .map(|commits| ProxyResponse::GitFileHistoryResponse {
+                            commits,
+                        })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        });
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
+            GitFileRevision { path, revision } => {
+                let Some(workspace) = self.workspace.clone() else {
+                    self.respond_rpc(
+                        id,
+                        Err(RpcError {
+                            code: 0,
+                            message: "no workspace set".to_string(),
+                        }),
+                    );
+                    return;
+                };
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = file_get_revision(&workspace, &path, &revision)
+                        .map(|(_blob_id, content)| {
+                            ProxyResponse::BufferHeadResponse {
+                                version: revision,
+                                content,
+                            }
+                        })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        });
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
             GlobalSearch {
                 pattern,
                 case_sensitive,
@@ -377,7 +585,7 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 static WORKER_ID: AtomicU64 = AtomicU64::new(0);
                 let our_id = WORKER_ID.fetch_add(1, Ordering::SeqCst) + 1;
 
-                let workspace = self.workspace.clone();
+                let roots = self.roots();
                 let buffers = self
                     .buffers
                     .iter()
@@ -385,15 +593,22 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     .cloned()
                     .collect::<Vec<PathBuf>>();
                 let proxy_rpc = self.proxy_rpc.clone();
+                let core_rpc = self.core_rpc.clone();
 
                 // Perform the search on another thread to avoid blocking the proxy thread
                 thread::spawn(move || {
+                    let progress = TaskProgress::begin(
+                        &core_rpc,
+                        format!("Searching for \"{pattern}\""),
+                        true,
+                    );
                     proxy_rpc.handle_response(
                         id,
                         search_in_path(
                             our_id,
                             &WORKER_ID,
-                            workspace
+                            &progress,
+                            roots
                                 .iter()
                                 .flat_map(|w| ignore::Walk::new(w).flatten())
                                 .chain(
@@ -456,6 +671,21 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     },
                 );
             }
+            GetDocumentHighlights { path, position } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_document_highlights(
+                    &path,
+                    position,
+                    move |_, result| {
+                        let result = result.map(|highlights| {
+                            ProxyResponse::GetDocumentHighlightsResponse {
+                                highlights: highlights.unwrap_or_default(),
+                            }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
             GitGetRemoteFileUrl { file } => {
                 if let Some(workspace) = self.workspace.as_ref() {
                     match git_get_remote_file_url(workspace, &file) {
@@ -521,6 +751,58 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                         proxy_rpc.handle_response(id, result);
                     });
             }
+            GetDocumentColors { path } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc
+                    .get_document_colors(&path, move |_, result| {
+                        let result = result.map(|colors| {
+                            ProxyResponse::GetDocumentColors { colors }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    });
+            }
+            GetColorPresentations { path, color, range } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_color_presentations(
+                    &path,
+                    color,
+                    range,
+                    move |_, result| {
+                        let result = result.map(|presentations| {
+                            ProxyResponse::GetColorPresentations { presentations }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            GetDocumentLinks { path } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc
+                    .get_document_links(&path, move |_, result| {
+                        let result = result
+                            .map(|links| ProxyResponse::GetDocumentLinks { links });
+                        proxy_rpc.handle_response(id, result);
+                    });
+            }
+            GetRunnables { path } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_runnables(&path, move |_, result| {
+                    let result = result
+                        .map(|runnables| ProxyResponse::GetRunnables { runnables });
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
+            BuildRunnable { args } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = runnable::build_runnable(&args)
+                        .map(|executable| ProxyResponse::BuildRunnableResponse {
+                            executable,
+                        })
+                        .map_err(|message| RpcError { code: 0, message });
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
             GetSemanticTokens { path } => {
                 let buffer = self.buffers.get(&path).unwrap();
                 let text = buffer.rope.clone();
@@ -576,8 +858,32 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 let proxy_rpc = self.proxy_rpc.clone();
                 self.catalog_rpc.get_code_actions(
                     &path,
-                    position,
+                    Range {
+                        start: position,
+                        end: position,
+                    },
+                    diagnostics,
+                    None,
+                    move |plugin_id, result| {
+                        let result = result.map(|resp| {
+                            ProxyResponse::GetCodeActionsResponse { plugin_id, resp }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            GetRangeCodeActions {
+                path,
+                range,
+                diagnostics,
+                only,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_code_actions(
+                    &path,
+                    range,
                     diagnostics,
+                    only,
                     move |plugin_id, result| {
                         let result = result.map(|resp| {
                             ProxyResponse::GetCodeActionsResponse { plugin_id, resp }
@@ -645,43 +951,28 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 );
             }
             GetFiles { .. } => {
-                let workspace = self.workspace.clone();
+                let file_index = self.file_index.clone();
+                let core_rpc = self.core_rpc.clone();
                 let proxy_rpc = self.proxy_rpc.clone();
                 thread::spawn(move || {
-                    let result = if let Some(workspace) = workspace {
-                        let git_folder =
-                            ignore::overrides::OverrideBuilder::new(&workspace)
-                                .add("!.git/")
-                                .map(|git_folder| git_folder.build());
-
-                        let walker = match git_folder {
-                            Ok(Ok(git_folder)) => {
-                                ignore::WalkBuilder::new(&workspace)
-                                    .hidden(false)
-                                    .parents(false)
-                                    .require_git(false)
-                                    .overrides(git_folder)
-                                    .build()
-                            }
-                            _ => ignore::WalkBuilder::new(&workspace)
-                                .parents(false)
-                                .require_git(false)
-                                .build(),
-                        };
-
-                        let mut items = Vec::new();
-                        for path in walker.flatten() {
-                            if let Some(file_type) = path.file_type() {
-                                if file_type.is_file() {
-                                    items.push(path.into_path());
-                                }
-                            }
-                        }
-                        Ok(ProxyResponse::GetFilesResponse { items })
-                    } else {
-                        Ok(ProxyResponse::GetFilesResponse { items: Vec::new() })
-                    };
-                    proxy_rpc.handle_response(id, result);
+                    // The workspace is walked until the index has been built,
+                    // after that the index is given right away
+                    let mut progress = None;
+                    let mut found = 0;
+                    let items = FileIndex::files(&file_index, |items| {
+                        let progress = progress.get_or_insert_with(|| {
+                            TaskProgress::begin(&core_rpc, "Indexing files", false)
+                        });
+                        found += items.len();
+                        progress.report(Some(format!("{found} files")), None);
+                        core_rpc
+                            .notification(CoreNotification::IndexedFiles { items });
+                    });
+                    drop(progress);
+                    proxy_rpc.handle_response(
+                        id,
+                        Ok(ProxyResponse::GetFilesResponse { items }),
+                    );
                 });
             }
             GetOpenFilesContent {} => {
@@ -701,6 +992,7 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
             ReadDir { path } => {
                 let proxy_rpc = self.proxy_rpc.clone();
                 thread::spawn(move || {
+                    let repo = Repository::discover(&path).ok();
                     let result = fs::read_dir(path)
                         .map(|entries| {
                             let mut items = entries
@@ -708,6 +1000,11 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                                 .filter_map(|entry| {
                                     entry
                                         .map(|e| FileNodeItem {
+                                            ignored: repo.as_ref().is_some_and(
+                                                |repo| {
+                                                    git_is_ignored(repo, &e.path())
+                                                },
+                                            ),
                                             path: e.path(),
                                             is_dir: e.path().is_dir(),
                                             open: false,
@@ -730,17 +1027,39 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     proxy_rpc.handle_response(id, result);
                 });
             }
+            FindFile { paths } => {
+                let path = paths.into_iter().find(|path| path.is_file());
+                self.proxy_rpc.handle_response(
+                    id,
+                    Ok(ProxyResponse::FindFileResponse { path }),
+                );
+            }
+            ReadFile { path } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = fs::read(path)
+                        .map(|content| ProxyResponse::ReadFileResponse { content })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        });
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
             Save {
                 rev,
                 path,
                 create_parents,
+                write_through_symlink,
             } => {
                 let buffer = self.buffers.get_mut(&path).unwrap();
                 let result = buffer
-                    .save(rev, create_parents)
+                    .save(rev, create_parents, write_through_symlink)
                     .map(|_r| {
-                        self.catalog_rpc
-                            .did_save_text_document(&path, buffer.rope.clone());
+                        if !buffer.large_file {
+                            self.catalog_rpc
+                                .did_save_text_document(&path, buffer.rope.clone());
+                        }
                         ProxyResponse::SaveResponse {}
                     })
                     .map_err(|e| RpcError {
@@ -760,7 +1079,7 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 buffer.rope = Rope::from(content);
                 buffer.rev = rev;
                 let result = buffer
-                    .save(rev, create_parents)
+                    .save(rev, create_parents, true)
                     .map(|_| ProxyResponse::Success {})
                     .map_err(|e| RpcError {
                         code: 0,
@@ -804,6 +1123,30 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     });
                 self.respond_rpc(id, result);
             }
+            MakeWritable { path } => {
+                let is_text = self
+                    .buffers
+                    .get(&path)
+                    .map_or(true, |buffer| buffer.is_text);
+                let result = if is_text {
+                    make_writable(&path)
+                } else {
+                    // Saving a hex dump or an error message would overwrite the file
+                    Err(anyhow!("{path:?} can't be edited as text"))
+                };
+                let result = result
+                    .map(|_| {
+                        if let Some(buffer) = self.buffers.get_mut(&path) {
+                            buffer.read_only = false;
+                        }
+                        ProxyResponse::Success {}
+                    })
+                    .map_err(|e| RpcError {
+                        code: 0,
+                        message: e.to_string(),
+                    });
+                self.respond_rpc(id, result);
+            }
             DuplicatePath {
                 existing_path,
                 new_path,
@@ -853,6 +1196,28 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 };
                 self.respond_rpc(id, result);
             }
+            CopyPath {
+                from,
+                to,
+                overwrite,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = transfer_path(&from, &to, overwrite, copy_path);
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
+            MovePath {
+                from,
+                to,
+                overwrite,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = transfer_path(&from, &to, overwrite, move_path);
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
             GetSelectionRange { positions, path } => {
                 let proxy_rpc = self.proxy_rpc.clone();
                 self.catalog_rpc.get_selection_range(
@@ -921,17 +1286,28 @@ pub fn new(core_rpc: CoreRpcHandler, proxy_rpc: ProxyRpcHandler) -> Self {
 
         Self {
             workspace: None,
+            folders: Vec::new(),
             proxy_rpc,
             core_rpc,
             catalog_rpc: plugin_rpc,
             buffers: HashMap::new(),
             terminals: HashMap::new(),
             file_watcher,
+            file_index: Arc::new(Mutex::new(FileIndex::new(Vec::new()))),
             window_id: 1,
             tab_id: 1,
         }
     }
 
+    /// The workspace folder followed by the other root folders.
+    fn roots(&self) -> Vec<PathBuf> {
+        self.workspace
+            .iter()
+            .chain(self.folders.iter())
+            .cloned()
+            .collect()
+    }
+
     fn respond_rpc(&self, id: RequestId, result: Result<ProxyResponse, RpcError>) {
         self.proxy_rpc.handle_response(id, result);
     }
@@ -941,8 +1317,9 @@ struct FileWatchNotifier {
     core_rpc: CoreRpcHandler,
     proxy_rpc: ProxyRpcHandler,
     workspace: Option<PathBuf>,
-    workspace_fs_change_handler: Arc<Mutex<Option<Sender<bool>>>>,
+    workspace_fs_change_handler: Arc<Mutex<Option<Sender<Vec<PathBuf>>>>>,
     last_diff: Arc<Mutex<DiffInfo>>,
+    file_index: Arc<Mutex<FileIndex>>,
 }
 
 impl Notify for FileWatchNotifier {
@@ -956,6 +1333,7 @@ fn new(
         workspace: Option<PathBuf>,
         core_rpc: CoreRpcHandler,
         proxy_rpc: ProxyRpcHandler,
+        file_index: Arc<Mutex<FileIndex>>,
     ) -> Self {
         let notifier = Self {
             workspace,
@@ -963,6 +1341,7 @@ fn new(
             proxy_rpc,
             workspace_fs_change_handler: Arc::new(Mutex::new(None)),
             last_diff: Arc::new(Mutex::new(DiffInfo::default())),
+            file_index,
         };
 
         if let Some(workspace) = notifier.workspace.clone() {
@@ -1006,19 +1385,22 @@ fn handle_workspace_fs_event(&self, event: notify::Event) {
             notify::EventKind::Modify(_) => false,
             _ => return,
         };
+        if explorer_change {
+            self.file_index.lock().update(&event.paths);
+        }
 
         let mut handler = self.workspace_fs_change_handler.lock();
         if let Some(sender) = handler.as_mut() {
             if explorer_change {
-                // only send the value if we need to update file explorer as well
-                let _ = sender.send(explorer_change);
+                // only send the paths if we need to update file explorer as well
+                let _ = sender.send(event.paths);
             }
             return;
         }
         let (sender, receiver) = crossbeam_channel::unbounded();
         if explorer_change {
-            // only send the value if we need to update file explorer as well
-            let _ = sender.send(explorer_change);
+            // only send the paths if we need to update file explorer as well
+            let _ = sender.send(event.paths);
         }
 
         let local_handler = self.workspace_fs_change_handler.clone();
@@ -1032,15 +1414,12 @@ fn handle_workspace_fs_event(&self, event: notify::Event) {
                 local_handler.lock().take();
             }
 
-            let mut explorer_change = false;
-            for e in receiver {
-                if e {
-                    explorer_change = true;
-                    break;
-                }
+            let mut changed_paths = HashSet::new();
+            for paths in receiver {
+                changed_paths.extend(paths);
             }
-            if explorer_change {
-                core_rpc.workspace_file_change();
+            if !changed_paths.is_empty() {
+                core_rpc.workspace_file_change(changed_paths.into_iter().collect());
             }
             if let Some(diff) = git_diff_new(&workspace) {
                 let mut last_diff = last_diff.lock();
@@ -1110,12 +1489,113 @@ fn git_commit(
 
 fn git_checkout(workspace_path: &Path, reference: &str) -> Result<()> {
     let repo = Repository::discover(workspace_path)?;
+
+    // Checking out a remote branch creates a local branch tracking it, if there
+    // isn't one already.
+    if let Ok(remote_branch) = repo.find_branch(reference, BranchType::Remote) {
+        let name = reference
+            .split_once('/')
+            .map(|(_, name)| name)
+            .unwrap_or(reference);
+        if repo.find_branch(name, BranchType::Local).is_err() {
+            let commit = remote_branch.get().peel_to_commit()?;
+            let mut branch = repo.branch(name, &commit, false)?;
+            branch.set_upstream(Some(reference))?;
+        }
+        return git_checkout(workspace_path, name);
+    }
+
     let (object, reference) = repo.revparse_ext(reference)?;
     repo.checkout_tree(&object, None)?;
     repo.set_head(reference.unwrap().name().unwrap())?;
     Ok(())
 }
 
+/// Create a branch at the current HEAD and switch to it. The working tree stays
+/// as it is.
+fn git_create_branch(workspace_path: &Path, name: &str) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+    let commit = repo.head()?.peel_to_commit()?;
+    let branch = repo.branch(name, &commit, false)?;
+    let reference = branch
+        .get()
+        .name()
+        .ok_or_else(|| anyhow!("invalid branch name"))?;
+    repo.set_head(reference)?;
+    Ok(())
+}
+
+/// Run a git command that talks to the remote with the git executable, so the
+/// user's credential helpers and ssh setup are used, and report its progress.
+fn git_remote_command(
+    core_rpc: CoreRpcHandler,
+    workspace_path: PathBuf,
+    command: &'static str,
+) {
+    thread::spawn(move || {
+        let progress =
+            TaskProgress::begin(&core_rpc, format!("git {command}"), false);
+
+        let mut process = std::process::Command::new("git");
+        process
+            .arg(command)
+            .current_dir(&workspace_path)
+            .stdin(std::process::Stdio::null())
+            // Nobody can answer a prompt for credentials or an unknown host key,
+            // so git and ssh fail instead of waiting for one forever
+            .env("GIT_TERMINAL_PROMPT", "0");
+        if std::env::var_os("GIT_SSH_COMMAND").is_none() {
+            process.env("GIT_SSH_COMMAND", "ssh -o BatchMode=yes");
+        }
+        #[cfg(target_os = "windows")]
+        std::os::windows::process::CommandExt::creation_flags(
+            &mut process,
+            0x08000000,
+        );
+        let error = match process.output() {
+            Ok(output) if output.status.success() => None,
+            Ok(output) => {
+                let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+                if is_git_auth_error(&stderr) {
+                    Some(format!(
+                        "The remote needs credentials that git can't ask for here. \
+                         Set up a credential helper or an ssh key for it.\n\n{stderr}"
+                    ))
+                } else {
+                    Some(stderr)
+                }
+            }
+            Err(e) => Some(e.to_string()),
+        };
+
+        drop(progress);
+
+        if let Some(error) = error {
+            core_rpc.show_message(
+                format!("git {command} failed"),
+                ShowMessageParams {
+                    typ: MessageType::ERROR,
+                    message: error,
+                },
+            );
+        }
+    });
+}
+
+/// Whether git failed because it needed a password or had to confirm a host key.
+fn is_git_auth_error(stderr: &str) -> bool {
+    [
+        "terminal prompts disabled",
+        "could not read Username",
+        "could not read Password",
+        "Authentication failed",
+        "Permission denied (publickey",
+        "Host key verification failed",
+    ]
+    .iter()
+    .any(|pattern| stderr.contains(pattern))
+}
+
 fn git_discard_files_changes<'a>(
     workspace_path: &Path,
     files: impl Iterator<Item = &'a Path>,
@@ -1156,6 +1636,13 @@ fn git_discard_workspace_changes(workspace_path: &Path) -> Result<()> {
     Ok(())
 }
 
+fn git_is_ignored(repo: &Repository, path: &Path) -> bool {
+    let Some(path) = repo.workdir().and_then(|w| path.strip_prefix(w).ok()) else {
+        return false;
+    };
+    repo.is_path_ignored(path).unwrap_or(false)
+}
+
 fn git_delta_format(
     workspace_path: &Path,
     delta: &git2::DiffDelta,
@@ -1278,18 +1765,69 @@ fn git_diff_new(workspace_path: &Path) -> Option<DiffInfo> {
 }
 
 fn file_get_head(workspace_path: &Path, path: &Path) -> Result<(String, String)> {
+    file_get_revision(workspace_path, path, "HEAD")
+}
+
+fn file_get_revision(
+    workspace_path: &Path,
+    path: &Path,
+    revision: &str,
+) -> Result<(String, String)> {
     let repo = Repository::discover(workspace_path)?;
-    let head = repo.head()?;
-    let tree = head.peel_to_tree()?;
+    let tree = repo.revparse_single(revision)?.peel_to_tree()?;
     let tree_entry = tree.get_path(path.strip_prefix(workspace_path)?)?;
     let blob = repo.find_blob(tree_entry.id())?;
     let id = blob.id().to_string();
     let content = std::str::from_utf8(blob.content())
         .with_context(|| "content bytes to string")?
         .to_string();
+    // Buffers are normalized to `\n`, so the head needs to be as well for diffing
+    let content = LineEnding::normalize(content);
     Ok((id, content))
 }
 
+/// The most commits [`git_file_history`] returns.
+const FILE_HISTORY_LIMIT: usize = 1000;
+
+/// The commits reachable from HEAD in which the file differs from all of the
+/// commit's parents, newest first.
+fn git_file_history(workspace_path: &Path, path: &Path) -> Result<Vec<FileCommit>> {
+    let repo = Repository::discover(workspace_path)?;
+    let path = path.strip_prefix(workspace_path)?;
+    let entry_id = |commit: &git2::Commit| {
+        commit
+            .tree()
+            .ok()
+            .and_then(|tree| tree.get_path(path).ok())
+            .map(|entry| entry.id())
+    };
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_head()?;
+    revwalk.set_sorting(git2::Sort::TIME)?;
+
+    let mut commits = Vec::new();
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        let Some(id) = entry_id(&commit) else {
+            continue;
+        };
+        let changed = commit.parents().all(|parent| entry_id(&parent) != Some(id));
+        if changed {
+            commits.push(FileCommit {
+                id: commit.id().to_string(),
+                summary: commit.summary().unwrap_or("").to_string(),
+                author: commit.author().name().unwrap_or("").to_string(),
+                time: commit.time().seconds(),
+            });
+            if commits.len() >= FILE_HISTORY_LIMIT {
+                break;
+            }
+        }
+    }
+    Ok(commits)
+}
+
 fn git_get_remote_file_url(workspace_path: &Path, file: &Path) -> Result<String> {
     let repo = Repository::discover(workspace_path)?;
     let head = repo.head()?;
@@ -1335,9 +1873,14 @@ fn git_get_remote_file_url(workspace_path: &Path, file: &Path) -> Result<String>
     Ok(url)
 }
 
+/// How many files are searched between the reports of the progress.
+const SEARCH_PROGRESS_INTERVAL: usize = 500;
+
+#[allow(clippy::too_many_arguments)]
 fn search_in_path(
     id: u64,
     current_id: &AtomicU64,
+    progress: &TaskProgress,
     paths: impl Iterator<Item = PathBuf>,
     pattern: &str,
     case_sensitive: bool,
@@ -1358,13 +1901,22 @@ fn search_in_path(
     })?;
     let mut searcher = SearcherBuilder::new().build();
 
-    for path in paths {
+    for (i, path) in paths.enumerate() {
         if current_id.load(Ordering::SeqCst) != id {
             return Err(RpcError {
                 code: 0,
                 message: "expired search job".to_string(),
             });
         }
+        if progress.is_cancelled() {
+            return Err(RpcError {
+                code: 0,
+                message: "search cancelled".to_string(),
+            });
+        }
+        if i > 0 && i % SEARCH_PROGRESS_INTERVAL == 0 {
+            progress.report(Some(format!("{i} files")), None);
+        }
 
         if path.is_file() {
             let mut line_matches = Vec::new();
@@ -1416,3 +1968,109 @@ fn search_in_path(
 
     Ok(ProxyResponse::GlobalSearchResponse { matches })
 }
+
+/// Copy or move `from` to `to`. What is at the destination is only replaced with
+/// `overwrite`, otherwise the error has the `PATH_EXISTS_ERROR_CODE` code.
+fn transfer_path(
+    from: &Path,
+    to: &Path,
+    overwrite: bool,
+    transfer: fn(&Path, &Path) -> std::io::Result<()>,
+) -> Result<ProxyResponse, RpcError> {
+    let error = |e: std::io::Error| RpcError {
+        code: 0,
+        message: e.to_string(),
+    };
+    if to.starts_with(from) {
+        return Err(RpcError {
+            code: 0,
+            message: format!("{to:?} is inside {from:?}"),
+        });
+    }
+    // Replacing a folder the path is in would remove the path itself
+    if from.starts_with(to) {
+        return Err(RpcError {
+            code: 0,
+            message: format!("{from:?} is inside {to:?}"),
+        });
+    }
+    if to.exists() {
+        if !overwrite {
+            return Err(RpcError {
+                code: PATH_EXISTS_ERROR_CODE,
+                message: format!("{to:?} already exists"),
+            });
+        }
+        remove_path(to).map_err(error)?;
+    }
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent).map_err(error)?;
+    }
+    transfer(from, to)
+        .map(|_| ProxyResponse::Success {})
+        .map_err(error)
+}
+
+/// Symbolic links are copied as links rather than what they point to, so a link
+/// to a folder above can't make the copy go on forever.
+fn copy_path(from: &Path, to: &Path) -> std::io::Result<()> {
+    let metadata = fs::symlink_metadata(from)?;
+    if metadata.file_type().is_symlink() {
+        copy_symlink(from, to)
+    } else if metadata.is_dir() {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_path(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        fs::copy(from, to).map(|_| ())
+    }
+}
+
+#[cfg(unix)]
+fn copy_symlink(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(from)?, to)
+}
+
+#[cfg(windows)]
+fn copy_symlink(from: &Path, to: &Path) -> std::io::Result<()> {
+    let target = fs::read_link(from)?;
+    if from.is_dir() {
+        std::os::windows::fs::symlink_dir(target, to)
+    } else {
+        std::os::windows::fs::symlink_file(target, to)
+    }
+}
+
+/// Renaming fails when the destination is on another file system, and only then
+/// the path is copied over and removed.
+fn move_path(from: &Path, to: &Path) -> std::io::Result<()> {
+    match fs::rename(from, to) {
+        Err(e) if is_cross_device(&e) => {
+            copy_path(from, to)?;
+            remove_path(from)
+        }
+        result => result,
+    }
+}
+
+#[cfg(unix)]
+fn is_cross_device(e: &std::io::Error) -> bool {
+    e.raw_os_error() == Some(libc::EXDEV)
+}
+
+#[cfg(windows)]
+fn is_cross_device(e: &std::io::Error) -> bool {
+    // ERROR_NOT_SAME_DEVICE
+    e.raw_os_error() == Some(17)
+}
+
+fn remove_path(path: &Path) -> std::io::Result<()> {
+    if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
@@ -0,0 +1,180 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use parking_lot::Mutex;
+
+/// How many files are found before they are sent on while walking the workspace.
+const PROGRESS_BATCH_SIZE: usize = 5000;
+
+/// The files in the workspace that the palette searches. Every root folder of the
+/// workspace is walked once, respecting `.gitignore`, and after that the list is
+/// kept up to date from file system events.
+pub struct FileIndex {
+    roots: Vec<PathBuf>,
+    /// `None` until the workspace has been walked.
+    files: Option<BTreeSet<PathBuf>>,
+    /// The paths that changed while the workspace is walked, which the walk may
+    /// have passed already. `None` when no walk is going on, or when the one
+    /// going on is out of date because the ignore rules changed.
+    changed_while_walking: Option<Vec<PathBuf>>,
+}
+
+impl FileIndex {
+    pub fn new(roots: Vec<PathBuf>) -> Self {
+        Self {
+            roots,
+            files: None,
+            changed_while_walking: None,
+        }
+    }
+
+    /// All the files in the workspace. If the workspace hasn't been walked yet,
+    /// `progress` gets the files in batches as they are found. The walk doesn't
+    /// hold the lock of the index, so it can be kept up to date meanwhile.
+    pub fn files(
+        index: &Mutex<FileIndex>,
+        mut progress: impl FnMut(Vec<PathBuf>),
+    ) -> Vec<PathBuf> {
+        let roots = {
+            let mut index = index.lock();
+            if let Some(files) = index.files.as_ref() {
+                return files.iter().cloned().collect();
+            }
+            index.changed_while_walking.get_or_insert_with(Vec::new);
+            index.roots.clone()
+        };
+
+        let mut files = walk(&roots, &mut progress);
+
+        let mut index = index.lock();
+        if index.files.is_none() && index.roots == roots {
+            if let Some(changed) = index.changed_while_walking.take() {
+                update_files(&mut files, &roots, &changed);
+                index.files = Some(files.clone());
+            }
+        }
+        index
+            .files
+            .as_ref()
+            .unwrap_or(&files)
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Bring the index up to date for paths that were created, removed or
+    /// renamed. A path that no longer exists is removed with everything below
+    /// it, and one that does is walked again.
+    pub fn update(&mut self, paths: &[PathBuf]) {
+        let paths: Vec<PathBuf> = paths
+            .iter()
+            .filter(|path| !path.components().any(|c| c.as_os_str() == ".git"))
+            .cloned()
+            .collect();
+        if paths.iter().any(|path| {
+            path.file_name()
+                .map(|name| name == ".gitignore" || name == ".ignore")
+                .unwrap_or(false)
+        }) {
+            // The ignore rules changed, so the whole workspace is walked again
+            // the next time the files are needed.
+            self.files = None;
+            self.changed_while_walking = None;
+            return;
+        }
+
+        if let Some(files) = self.files.as_mut() {
+            update_files(files, &self.roots, &paths);
+        } else if let Some(changed) = self.changed_while_walking.as_mut() {
+            changed.extend(paths);
+        }
+    }
+}
+
+fn walk(
+    roots: &[PathBuf],
+    progress: &mut impl FnMut(Vec<PathBuf>),
+) -> BTreeSet<PathBuf> {
+    let mut files = BTreeSet::new();
+    let mut batch = Vec::new();
+    let entries = roots
+        .iter()
+        .flat_map(|root| walk_builder(root).build().flatten());
+    for entry in entries {
+        if entry.file_type().map(|t| t.is_file()).unwrap_or(false) {
+            let path = entry.into_path();
+            batch.push(path.clone());
+            files.insert(path);
+            if batch.len() >= PROGRESS_BATCH_SIZE {
+                progress(std::mem::take(&mut batch));
+            }
+        }
+    }
+    if !batch.is_empty() {
+        progress(batch);
+    }
+    files
+}
+
+/// Remove the paths from the files, with everything below them, and walk the
+/// ones that still exist again.
+fn update_files(
+    files: &mut BTreeSet<PathBuf>,
+    roots: &[PathBuf],
+    paths: &[PathBuf],
+) {
+    for path in paths {
+        remove_path(files, path);
+        let root = roots.iter().find(|root| path.starts_with(root));
+        if let Some(root) = root.filter(|_| path.exists()) {
+            add_path(files, root, path);
+        }
+    }
+}
+
+fn walk_builder(root: &Path) -> ignore::WalkBuilder {
+    let git_folder = ignore::overrides::OverrideBuilder::new(root)
+        .add("!.git/")
+        .map(|git_folder| git_folder.build());
+
+    let mut builder = ignore::WalkBuilder::new(root);
+    builder.parents(false).require_git(false);
+    if let Ok(Ok(git_folder)) = git_folder {
+        builder.hidden(false).overrides(git_folder);
+    }
+    builder
+}
+
+/// Remove the path and, if it was a directory, everything that was below it.
+fn remove_path(files: &mut BTreeSet<PathBuf>, path: &Path) {
+    // Paths are ordered by component, so the ones below `path` come right after it
+    let removed: Vec<PathBuf> = files
+        .range(path.to_path_buf()..)
+        .take_while(|p| p.starts_with(path))
+        .cloned()
+        .collect();
+    for p in removed {
+        files.remove(&p);
+    }
+}
+
+/// Add the files at or below the path that aren't ignored. The walk starts at the
+/// root folder so the ignore files of every directory on the way are applied, but
+/// it only goes into the directories that lead to the path.
+fn add_path(files: &mut BTreeSet<PathBuf>, root: &Path, path: &Path) {
+    let target = path.to_path_buf();
+    let walker = walk_builder(root)
+        .filter_entry(move |entry| {
+            target.starts_with(entry.path()) || entry.path().starts_with(&target)
+        })
+        .build();
+    for entry in walker.flatten() {
+        if entry.path().starts_with(path)
+            && entry.file_type().map(|t| t.is_file()).unwrap_or(false)
+        {
+            files.insert(entry.into_path());
+        }
+    }
+}
@@ -3,7 +3,10 @@
 pub mod buffer;
 pub mod cli;
 pub mod dispatch;
+pub mod file_index;
 pub mod plugin;
+pub mod progress;
+pub mod runnable;
 pub mod terminal;
 pub mod watcher;
 
@@ -11,7 +14,10 @@
     io::{stdin, stdout, BufReader},
     path::PathBuf,
     process::exit,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     thread,
 };
 
@@ -22,8 +28,10 @@
 use lapce_rpc::{
     core::{CoreRpc, CoreRpcHandler},
     file::PathObject,
-    proxy::{ProxyMessage, ProxyNotification, ProxyRpcHandler},
-    stdio::stdio_transport,
+    proxy::{
+        ProxyMessage, ProxyNotification, ProxyRpcHandler, PROXY_PROTOCOL_VERSION,
+    },
+    stdio::compressed_stdio_transport,
     RpcMessage,
 };
 use tracing::error;
@@ -58,7 +66,14 @@ pub fn mainloop() {
 
     let (writer_tx, writer_rx) = crossbeam_channel::unbounded();
     let (reader_tx, reader_rx) = crossbeam_channel::unbounded();
-    stdio_transport(stdout(), writer_rx, BufReader::new(stdin()), reader_tx);
+    let compress = Arc::new(AtomicBool::new(false));
+    compressed_stdio_transport(
+        stdout(),
+        writer_rx,
+        BufReader::new(stdin()),
+        reader_tx,
+        compress.clone(),
+    );
 
     let local_core_rpc = core_rpc.clone();
     let local_writer_tx = writer_tx.clone();
@@ -95,6 +110,20 @@ pub fn mainloop() {
                     });
                 }
                 RpcMessage::Notification(n) => {
+                    if let ProxyNotification::Initialize {
+                        protocol_version,
+                        compression,
+                        ..
+                    } = &n
+                    {
+                        // Only an editor that speaks the same protocol can read
+                        // compressed messages
+                        compress.store(
+                            *compression
+                                && *protocol_version == PROXY_PROTOCOL_VERSION,
+                            Ordering::Relaxed,
+                        );
+                    }
                     local_proxy_rpc.notification(n);
                 }
                 RpcMessage::Response(id, resp) => {
@@ -0,0 +1,140 @@
+//! The methods of the plugin API that are specific to Lapce, which plugins use
+//! next to the ones of the Language Server Protocol and of `psp-types`. Together
+//! they make up version [`PLUGIN_API_VERSION`] of the API, and a method is only
+//! ever added to a newer version, so a plugin that asks for a version can rely
+//! on everything listed for it here.
+//!
+//! Buffer text is changed with `workspace/applyEdit`, and notifications are shown
+//! with `window/showMessage`, or since version 2 with `window/showMessageRequest`
+//! when they have buttons, all from the Language Server Protocol.
+//!
+//! [`PLUGIN_API_VERSION`]: lapce_rpc::plugin::PLUGIN_API_VERSION
+
+use lapce_rpc::plugin::{PluginQuickPickItem, StatusItemAlignment};
+use lsp_types::{notification::Notification, request::Request, Url};
+use serde::{Deserialize, Serialize};
+
+/// Add a command to the palette, or rename one the plugin added before.
+///
+/// Since version 1.
+pub enum RegisterCommand {}
+
+impl Notification for RegisterCommand {
+    type Params = RegisterCommandParams;
+    const METHOD: &'static str = "lapce/registerCommand";
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterCommandParams {
+    pub id: String,
+    pub title: String,
+}
+
+/// Remove a command the plugin added to the palette.
+///
+/// Since version 1.
+pub enum UnregisterCommand {}
+
+impl Notification for UnregisterCommand {
+    type Params = UnregisterCommandParams;
+    const METHOD: &'static str = "lapce/unregisterCommand";
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnregisterCommandParams {
+    pub id: String,
+}
+
+/// Sent to the plugin when one of its commands is run, from the palette or from
+/// one of its status bar items.
+///
+/// Since version 1.
+pub enum ExecuteCommand {}
+
+impl Notification for ExecuteCommand {
+    type Params = ExecuteCommandParams;
+    const METHOD: &'static str = "lapce/executeCommand";
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecuteCommandParams {
+    pub id: String,
+}
+
+/// Show an item in the status bar, or update one the plugin showed before.
+///
+/// Since version 1, with `alignment` since version 2.
+pub enum SetStatusItem {}
+
+impl Notification for SetStatusItem {
+    type Params = SetStatusItemParams;
+    const METHOD: &'static str = "lapce/setStatusItem";
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetStatusItemParams {
+    pub id: String,
+    pub text: String,
+    /// One of the plugin's commands, which is run when the item is clicked.
+    pub command: Option<String>,
+    /// `"left"` or `"right"`, left when it's left out.
+    #[serde(default)]
+    pub alignment: StatusItemAlignment,
+}
+
+/// Remove an item the plugin showed in the status bar.
+///
+/// Since version 1.
+pub enum RemoveStatusItem {}
+
+impl Notification for RemoveStatusItem {
+    type Params = RemoveStatusItemParams;
+    const METHOD: &'static str = "lapce/removeStatusItem";
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoveStatusItemParams {
+    pub id: String,
+}
+
+/// Let the user pick one of the items in the palette. The result is `None` when
+/// the palette is closed without picking one.
+///
+/// Since version 1.
+pub enum ShowQuickPick {}
+
+impl Request for ShowQuickPick {
+    type Params = ShowQuickPickParams;
+    type Result = Option<usize>;
+    const METHOD: &'static str = "lapce/showQuickPick";
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShowQuickPickParams {
+    pub placeholder: Option<String>,
+    pub items: Vec<PluginQuickPickItem>,
+}
+
+/// The text of a document that is open in the editor, with the changes that
+/// haven't been saved yet. The result is `None` when the document isn't open.
+///
+/// Since version 1.
+pub enum GetBufferText {}
+
+impl Request for GetBufferText {
+    type Params = GetBufferTextParams;
+    type Result = Option<String>;
+    const METHOD: &'static str = "lapce/getBufferText";
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetBufferTextParams {
+    pub uri: Url,
+}
@@ -18,7 +18,8 @@
 };
 use lapce_xi_rope::{Rope, RopeDelta};
 use lsp_types::{
-    notification::DidOpenTextDocument, DidOpenTextDocumentParams, SemanticTokens,
+    notification::{DidCloseTextDocument, DidOpenTextDocument},
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, SemanticTokens,
     TextDocumentIdentifier, TextDocumentItem, VersionedTextDocumentIdentifier,
 };
 use parking_lot::Mutex;
@@ -26,6 +27,7 @@
 use serde_json::Value;
 
 use super::{
+    api::{self, ExecuteCommandParams},
     dap::{DapClient, DapRpcHandler, DebuggerData},
     psp::{ClonableCallback, PluginServerRpc, PluginServerRpcHandler, RpcCallback},
     wasi::{load_all_volts, start_volt},
@@ -37,6 +39,7 @@
 
 pub struct PluginCatalog {
     workspace: Option<PathBuf>,
+    workspace_folders: Vec<PathBuf>,
     plugin_rpc: PluginCatalogRpcHandler,
     plugins: HashMap<PluginId, PluginServerRpcHandler>,
     daps: HashMap<DapId, DapRpcHandler>,
@@ -49,12 +52,14 @@ pub struct PluginCatalog {
 impl PluginCatalog {
     pub fn new(
         workspace: Option<PathBuf>,
+        workspace_folders: Vec<PathBuf>,
         disabled_volts: Vec<VoltID>,
         plugin_configurations: HashMap<String, HashMap<String, serde_json::Value>>,
         plugin_rpc: PluginCatalogRpcHandler,
     ) -> Self {
         let plugin = Self {
             workspace,
+            workspace_folders,
             plugin_rpc: plugin_rpc.clone(),
             plugin_configurations,
             plugins: HashMap::new(),
@@ -177,12 +182,19 @@ pub fn handle_server_notification(
     fn start_unactivated_volts(&mut self, to_be_activated: Vec<VoltID>) {
         for id in to_be_activated.iter() {
             let workspace = self.workspace.clone();
+            let workspace_folders = self.workspace_folders.clone();
             if let Some(meta) = self.unactivated_volts.remove(id) {
                 let configurations =
                     self.plugin_configurations.get(&meta.name).cloned();
                 let plugin_rpc = self.plugin_rpc.clone();
                 thread::spawn(move || {
-                    let _ = start_volt(workspace, configurations, plugin_rpc, meta);
+                    let _ = start_volt(
+                        workspace,
+                        workspace_folders,
+                        configurations,
+                        plugin_rpc,
+                        meta,
+                    );
                 });
             }
         }
@@ -193,16 +205,10 @@ fn check_unactivated_volts(&mut self) {
             .unactivated_volts
             .iter()
             .filter_map(|(id, meta)| {
-                let contains = meta
-                    .activation
-                    .as_ref()
-                    .and_then(|a| a.language.as_ref())
-                    .map(|l| {
-                        self.open_files
-                            .iter()
-                            .any(|(_, language_id)| l.contains(language_id))
-                    })
-                    .unwrap_or(false);
+                let contains = self
+                    .open_files
+                    .values()
+                    .any(|language_id| meta.activated_by_language(language_id));
                 if contains {
                     return Some(id.clone());
                 }
@@ -248,18 +254,8 @@ pub fn handle_did_open_text_document(&mut self, document: TextDocumentItem) {
         let to_be_activated: Vec<VoltID> = self
             .unactivated_volts
             .iter()
-            .filter_map(|(id, meta)| {
-                let contains = meta
-                    .activation
-                    .as_ref()
-                    .and_then(|a| a.language.as_ref())
-                    .map(|l| l.contains(&document.language_id))?;
-                if contains {
-                    Some(id.clone())
-                } else {
-                    None
-                }
-            })
+            .filter(|(_, meta)| meta.activated_by_language(&document.language_id))
+            .map(|(id, _)| id.clone())
             .collect();
         self.start_unactivated_volts(to_be_activated);
 
@@ -277,6 +273,30 @@ pub fn handle_did_open_text_document(&mut self, document: TextDocumentItem) {
         }
     }
 
+    /// Close the document for the language servers of the language, like when
+    /// it is switched to another language.
+    pub fn handle_did_close_text_document(
+        &mut self,
+        language_id: String,
+        text_document: TextDocumentIdentifier,
+    ) {
+        let path = text_document.uri.to_file_path().ok();
+        if let Some(path) = path.as_ref() {
+            self.open_files.remove(path);
+        }
+        for (_, plugin) in self.plugins.iter() {
+            plugin.server_notification(
+                DidCloseTextDocument::METHOD,
+                DidCloseTextDocumentParams {
+                    text_document: text_document.clone(),
+                },
+                Some(language_id.clone()),
+                path.clone(),
+                true,
+            );
+        }
+    }
+
     pub fn handle_did_save_text_document(
         &mut self,
         language_id: String,
@@ -480,13 +500,19 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
             }
             InstallVolt(volt) => {
                 let workspace = self.workspace.clone();
+                let workspace_folders = self.workspace_folders.clone();
                 let configurations =
                     self.plugin_configurations.get(&volt.name).cloned();
                 let catalog_rpc = self.plugin_rpc.clone();
                 let _ = catalog_rpc.stop_volt(volt.clone());
                 thread::spawn(move || {
-                    let _ =
-                        install_volt(catalog_rpc, workspace, configurations, volt);
+                    let _ = install_volt(
+                        catalog_rpc,
+                        workspace,
+                        workspace_folders,
+                        configurations,
+                        volt,
+                    );
                 });
             }
             ReloadVolt(volt) => {
@@ -509,6 +535,23 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
                         plugin.shutdown();
                     }
                 }
+                let core_rpc = &self.plugin_rpc.core_rpc;
+                core_rpc.plugin_commands(volt_id.clone(), Vec::new());
+                core_rpc.plugin_status_items(volt_id, Vec::new());
+            }
+            ExecuteCommand { volt, id } => {
+                let params = ExecuteCommandParams { id };
+                for plugin in self.plugins.values() {
+                    if plugin.volt_id == volt && plugin.spawned_by.is_none() {
+                        plugin.server_notification(
+                            api::ExecuteCommand::METHOD,
+                            params.clone(),
+                            None,
+                            None,
+                            false,
+                        );
+                    }
+                }
             }
             EnableVolt(volt) => {
                 let volt_id = volt.id();
@@ -12,7 +12,7 @@
 use jsonrpc_lite::{Id, Params};
 use lapce_core::meta;
 use lapce_rpc::{
-    plugin::{PluginId, VoltID},
+    plugin::{LanguageServerStatus, PluginId, VoltID},
     style::LineStyle,
     RpcError,
 };
@@ -64,6 +64,7 @@ pub struct LspClient {
     workspace: Option<PathBuf>,
     host: PluginHostHandler,
     options: Option<Value>,
+    volt_display_name: String,
 }
 
 impl PluginServerHandler for LspClient {
@@ -167,6 +168,7 @@ fn new(
         plugin_rpc: PluginCatalogRpcHandler,
         document_selector: DocumentSelector,
         workspace: Option<PathBuf>,
+        workspace_folders: Vec<PathBuf>,
         volt_id: VoltID,
         volt_display_name: String,
         spawned_by: Option<PluginId>,
@@ -203,6 +205,11 @@ fn new(
             plugin_id,
             io_tx.clone(),
         );
+        plugin_rpc.core_rpc.language_server_status(
+            server_rpc.plugin_id,
+            volt_display_name.clone(),
+            LanguageServerStatus::Starting,
+        );
         thread::spawn(move || {
             for msg in io_rx {
                 if let Ok(msg) = serde_json::to_string(&msg) {
@@ -216,6 +223,7 @@ fn new(
 
         let local_server_rpc = server_rpc.clone();
         let core_rpc = plugin_rpc.core_rpc.clone();
+        let name = volt_display_name.clone();
         thread::spawn(move || {
             let mut reader = Box::new(BufReader::new(stdout));
             loop {
@@ -233,6 +241,11 @@ fn new(
                             tracing::Level::ERROR,
                             format!("lsp server {server} stopped!"),
                         );
+                        core_rpc.language_server_status(
+                            local_server_rpc.plugin_id,
+                            name,
+                            LanguageServerStatus::Stopped,
+                        );
                         return;
                     }
                 };
@@ -263,13 +276,16 @@ fn new(
 
         let host = PluginHostHandler::new(
             workspace.clone(),
+            workspace_folders,
             pwd,
             volt_id,
-            volt_display_name,
+            volt_display_name.clone(),
             document_selector,
             plugin_rpc.core_rpc.clone(),
             server_rpc.clone(),
             plugin_rpc.clone(),
+            // Only plugins can ask the editor to run programs
+            false,
         );
 
         Ok(Self {
@@ -279,6 +295,7 @@ fn new(
             workspace,
             host,
             options,
+            volt_display_name,
         })
     }
 
@@ -287,6 +304,7 @@ pub fn start(
         plugin_rpc: PluginCatalogRpcHandler,
         document_selector: DocumentSelector,
         workspace: Option<PathBuf>,
+        workspace_folders: Vec<PathBuf>,
         volt_id: VoltID,
         volt_display_name: String,
         spawned_by: Option<PluginId>,
@@ -300,6 +318,7 @@ pub fn start(
             plugin_rpc,
             document_selector,
             workspace,
+            workspace_folders,
             volt_id,
             volt_display_name,
             spawned_by,
@@ -323,19 +342,16 @@ fn initialize(&mut self) {
             .workspace
             .clone()
             .map(|p| Url::from_directory_path(p).unwrap());
+        let workspace_folders = self.host.lsp_workspace_folders();
         #[allow(deprecated)]
         let params = InitializeParams {
             process_id: Some(process::id()),
-            root_uri: root_uri.clone(),
+            root_uri,
             initialization_options: self.options.clone(),
             capabilities: client_capabilities(),
             trace: Some(TraceValue::Verbose),
-            workspace_folders: root_uri.map(|uri| {
-                vec![WorkspaceFolder {
-                    name: uri.as_str().to_string(),
-                    uri,
-                }]
-            }),
+            workspace_folders: (!workspace_folders.is_empty())
+                .then_some(workspace_folders),
             client_info: Some(ClientInfo {
                 name: meta::NAME.to_owned(),
                 version: Some(meta::VERSION.to_owned()),
@@ -366,7 +382,11 @@ fn initialize(&mut self) {
             {
                 self.server_rpc.shutdown();
                 self.shutdown();
+            } else {
+                self.set_status(LanguageServerStatus::Running);
             }
+        } else {
+            self.set_status(LanguageServerStatus::Stopped);
         }
         //     move |result| {
         //         if let Ok(value) = result {
@@ -380,6 +400,14 @@ fn initialize(&mut self) {
         // );
     }
 
+    fn set_status(&self, status: LanguageServerStatus) {
+        self.plugin_rpc.core_rpc.language_server_status(
+            self.server_rpc.plugin_id,
+            self.volt_display_name.clone(),
+            status,
+        );
+    }
+
     fn shutdown(&mut self) {
         let _ = self.process.kill();
         let _ = self.process.wait();
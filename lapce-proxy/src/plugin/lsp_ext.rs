@@ -0,0 +1,25 @@
+//! Requests that language servers add to the Language Server Protocol, which are
+//! sent to the servers that say they have them.
+
+use lapce_rpc::proxy::Runnable;
+use lsp_types::{request::Request, Position, TextDocumentIdentifier};
+use serde::{Deserialize, Serialize};
+
+/// The tests, benchmarks and binaries in a document that rust-analyzer can
+/// run. Servers that have it list `runnables` in their experimental
+/// capabilities.
+pub enum Runnables {}
+
+impl Request for Runnables {
+    type Params = RunnablesParams;
+    type Result = Vec<Runnable>;
+    const METHOD: &'static str = "experimental/runnables";
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunnablesParams {
+    pub text_document: TextDocumentIdentifier,
+    /// Only the runnables around the position, or all of them if there's none
+    pub position: Option<Position>,
+}
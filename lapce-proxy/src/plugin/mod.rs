@@ -1,6 +1,8 @@
+pub mod api;
 pub mod catalog;
 pub mod dap;
 pub mod lsp;
+pub mod lsp_ext;
 pub mod psp;
 pub mod wasi;
 
@@ -24,16 +26,21 @@
 use lapce_rpc::{
     core::CoreRpcHandler,
     dap_types::{self, DapId, RunDebugConfig, SourceBreakpoint, ThreadId},
-    plugin::{PluginId, VoltInfo, VoltMetadata},
-    proxy::ProxyRpcHandler,
+    plugin::{
+        PluginId, PluginQuickPickItem, VoltID, VoltInfo, VoltMetadata,
+        VoltPermissions,
+    },
+    proxy::{ProxyRpcHandler, Runnable},
     style::LineStyle,
     terminal::TermId,
     RequestId, RpcError,
 };
 use lapce_xi_rope::{Rope, RopeDelta};
 use lsp_types::{
+    notification::{Notification, WorkDoneProgressCancel},
     request::{
-        CodeActionRequest, CodeActionResolveRequest, Completion,
+        CodeActionRequest, CodeActionResolveRequest, ColorPresentationRequest,
+        Completion, DocumentColor, DocumentHighlightRequest, DocumentLinkRequest,
         DocumentSymbolRequest, Formatting, GotoDefinition, GotoTypeDefinition,
         GotoTypeDefinitionParams, GotoTypeDefinitionResponse, HoverRequest,
         InlayHintRequest, PrepareRenameRequest, References, Rename, Request,
@@ -43,25 +50,31 @@
     ClientCapabilities, CodeAction, CodeActionCapabilityResolveSupport,
     CodeActionClientCapabilities, CodeActionContext, CodeActionKind,
     CodeActionKindLiteralSupport, CodeActionLiteralSupport, CodeActionParams,
-    CodeActionResponse, CompletionClientCapabilities, CompletionItem,
+    CodeActionResponse, Color, ColorInformation, ColorPresentation,
+    ColorPresentationParams, CompletionClientCapabilities, CompletionItem,
     CompletionItemCapability, CompletionItemCapabilityResolveSupport,
-    CompletionParams, CompletionResponse, Diagnostic, DocumentFormattingParams,
+    CompletionParams, CompletionResponse, Diagnostic,
+    DocumentColorClientCapabilities, DocumentColorParams, DocumentFormattingParams,
+    DocumentHighlight, DocumentHighlightClientCapabilities, DocumentHighlightParams,
+    DocumentLink, DocumentLinkClientCapabilities, DocumentLinkParams,
     DocumentSymbolParams, DocumentSymbolResponse, FormattingOptions, GotoCapability,
     GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverClientCapabilities,
     HoverParams, InlayHint, InlayHintClientCapabilities, InlayHintParams, Location,
-    MarkupKind, MessageActionItemCapabilities, ParameterInformationSettings,
-    PartialResultParams, Position, PrepareRenameResponse,
+    MarkupKind, MessageActionItem, MessageActionItemCapabilities,
+    ParameterInformationSettings, PartialResultParams, Position,
+    PrepareRenameResponse, ProgressParams, ProgressParamsValue, ProgressToken,
     PublishDiagnosticsClientCapabilities, Range, ReferenceContext, ReferenceParams,
     RenameParams, SelectionRange, SelectionRangeParams, SemanticTokens,
-    SemanticTokensClientCapabilities, SemanticTokensParams,
-    ShowMessageRequestClientCapabilities, SignatureHelp,
+    SemanticTokensClientCapabilities, SemanticTokensParams, ShowMessageParams,
+    ShowMessageRequestClientCapabilities, ShowMessageRequestParams, SignatureHelp,
     SignatureHelpClientCapabilities, SignatureHelpParams,
     SignatureInformationSettings, SymbolInformation, TextDocumentClientCapabilities,
     TextDocumentIdentifier, TextDocumentItem, TextDocumentPositionParams,
     TextDocumentSyncClientCapabilities, TextEdit, Url,
-    VersionedTextDocumentIdentifier, WindowClientCapabilities,
-    WorkDoneProgressParams, WorkspaceClientCapabilities, WorkspaceEdit,
-    WorkspaceSymbolClientCapabilities, WorkspaceSymbolParams,
+    VersionedTextDocumentIdentifier, WindowClientCapabilities, WorkDoneProgress,
+    WorkDoneProgressCancelParams, WorkDoneProgressParams,
+    WorkspaceClientCapabilities, WorkspaceEdit, WorkspaceSymbolClientCapabilities,
+    WorkspaceSymbolParams,
 };
 use parking_lot::Mutex;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
@@ -71,10 +84,11 @@
 use self::{
     catalog::PluginCatalog,
     dap::DapRpcHandler,
-    psp::{ClonableCallback, PluginServerRpcHandler, RpcCallback},
+    lsp_ext::{Runnables, RunnablesParams},
+    psp::{ClonableCallback, PluginServerRpcHandler, ResponseSender, RpcCallback},
     wasi::{load_volt, start_volt},
 };
-use crate::buffer::language_id_from_path;
+use crate::{buffer::language_id_from_path, progress::TaskProgress};
 
 pub type PluginName = String;
 
@@ -122,6 +136,10 @@ pub enum PluginCatalogRpc {
     DidOpenTextDocument {
         document: TextDocumentItem,
     },
+    DidCloseTextDocument {
+        language_id: String,
+        document: TextDocumentIdentifier,
+    },
     DidChangeTextDocument {
         language_id: String,
         document: VersionedTextDocumentIdentifier,
@@ -146,6 +164,10 @@ pub enum PluginCatalogNotification {
     PluginServerLoaded(PluginServerRpcHandler),
     InstallVolt(VoltInfo),
     StopVolt(VoltInfo),
+    ExecuteCommand {
+        volt: VoltID,
+        id: String,
+    },
     EnableVolt(VoltInfo),
     ReloadVolt(VoltMetadata),
     DapLoaded(DapRpcHandler),
@@ -208,10 +230,24 @@ pub struct PluginCatalogRpcHandler {
     proxy_rpc: ProxyRpcHandler,
     plugin_tx: Sender<PluginCatalogRpc>,
     plugin_rx: Arc<Mutex<Option<Receiver<PluginCatalogRpc>>>>,
-    #[allow(dead_code)]
     id: Arc<AtomicU64>,
     #[allow(dead_code, clippy::type_complexity)]
     pending: Arc<Mutex<HashMap<u64, Sender<Result<Value, RpcError>>>>>,
+    /// The plugins that wait for the user to pick an item in the palette.
+    quick_picks: Arc<Mutex<HashMap<u64, ResponseSender>>>,
+    /// The plugins that wait for the user to click one of the actions of a
+    /// message, with the actions.
+    #[allow(clippy::type_complexity)]
+    message_requests:
+        Arc<Mutex<HashMap<u64, (ResponseSender, Vec<MessageActionItem>)>>>,
+    /// The progress of the language servers that the user can cancel, with the
+    /// server that reported it.
+    cancellable_progresses: Arc<Mutex<HashMap<ProgressToken, PluginId>>>,
+    /// The language ids of the open documents, which aren't always the one of
+    /// their path, as the content or the user can decide the language too.
+    language_ids: Arc<Mutex<HashMap<PathBuf, &'static str>>>,
+    /// The permissions the user has granted to each plugin.
+    granted_permissions: Arc<Mutex<HashMap<VoltID, VoltPermissions>>>,
 }
 
 impl PluginCatalogRpcHandler {
@@ -224,9 +260,29 @@ pub fn new(core_rpc: CoreRpcHandler, proxy_rpc: ProxyRpcHandler) -> Self {
             plugin_rx: Arc::new(Mutex::new(Some(plugin_rx))),
             id: Arc::new(AtomicU64::new(0)),
             pending: Arc::new(Mutex::new(HashMap::new())),
+            quick_picks: Arc::new(Mutex::new(HashMap::new())),
+            message_requests: Arc::new(Mutex::new(HashMap::new())),
+            cancellable_progresses: Arc::new(Mutex::new(HashMap::new())),
+            language_ids: Arc::new(Mutex::new(HashMap::new())),
+            granted_permissions: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
+    /// Whether the document was opened in the editor.
+    pub fn is_open(&self, path: &Path) -> bool {
+        self.language_ids.lock().contains_key(path)
+    }
+
+    fn language_id(&self, path: &Path) -> String {
+        self.language_ids
+            .lock()
+            .get(path)
+            .copied()
+            .or_else(|| language_id_from_path(path))
+            .unwrap_or("")
+            .to_string()
+    }
+
     #[allow(dead_code)]
     fn handle_response(&self, id: RequestId, result: Result<Value, RpcError>) {
         if let Some(chan) = { self.pending.lock().remove(&id) } {
@@ -290,6 +346,12 @@ pub fn mainloop(&self, plugin: &mut PluginCatalog) {
                 PluginCatalogRpc::DidOpenTextDocument { document } => {
                     plugin.handle_did_open_text_document(document);
                 }
+                PluginCatalogRpc::DidCloseTextDocument {
+                    language_id,
+                    document,
+                } => {
+                    plugin.handle_did_close_text_document(language_id, document);
+                }
                 PluginCatalogRpc::DidSaveTextDocument {
                     language_id,
                     path,
@@ -472,7 +534,7 @@ pub fn format_semantic_tokens(
     pub fn did_save_text_document(&self, path: &Path, text: Rope) {
         let text_document =
             TextDocumentIdentifier::new(Url::from_file_path(path).unwrap());
-        let language_id = language_id_from_path(path).unwrap_or("").to_string();
+        let language_id = self.language_id(path);
         let _ = self.plugin_tx.send(PluginCatalogRpc::DidSaveTextDocument {
             language_id,
             text_document,
@@ -493,7 +555,7 @@ pub fn did_change_text_document(
             Url::from_file_path(path).unwrap(),
             rev as i32,
         );
-        let language_id = language_id_from_path(path).unwrap_or("").to_string();
+        let language_id = self.language_id(path);
         let _ = self
             .plugin_tx
             .send(PluginCatalogRpc::DidChangeTextDocument {
@@ -525,8 +587,7 @@ pub fn get_definition(
             partial_result_params: PartialResultParams::default(),
         };
 
-        let language_id =
-            Some(language_id_from_path(path).unwrap_or("").to_string());
+        let language_id = Some(self.language_id(path));
         self.send_request_to_all_plugins(
             method,
             params,
@@ -556,8 +617,7 @@ pub fn get_type_definition(
             partial_result_params: PartialResultParams::default(),
         };
 
-        let language_id =
-            Some(language_id_from_path(path).unwrap_or("").to_string());
+        let language_id = Some(self.language_id(path));
         self.send_request_to_all_plugins(
             method,
             params,
@@ -590,8 +650,7 @@ pub fn get_references(
             },
         };
 
-        let language_id =
-            Some(language_id_from_path(path).unwrap_or("").to_string());
+        let language_id = Some(self.language_id(path));
         self.send_request_to_all_plugins(
             method,
             params,
@@ -601,11 +660,42 @@ pub fn get_references(
         );
     }
 
-    pub fn get_code_actions(
+    pub fn get_document_highlights(
         &self,
         path: &Path,
         position: Position,
+        cb: impl FnOnce(PluginId, Result<Option<Vec<DocumentHighlight>>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = DocumentHighlightRequest::METHOD;
+        let params = DocumentHighlightParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+
+        let language_id = Some(self.language_id(path));
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    pub fn get_code_actions(
+        &self,
+        path: &Path,
+        range: Range,
         diagnostics: Vec<Diagnostic>,
+        only: Option<Vec<CodeActionKind>>,
         cb: impl FnOnce(PluginId, Result<CodeActionResponse, RpcError>)
             + Clone
             + Send
@@ -615,19 +705,12 @@ pub fn get_code_actions(
         let method = CodeActionRequest::METHOD;
         let params = CodeActionParams {
             text_document: TextDocumentIdentifier { uri },
-            range: Range {
-                start: position,
-                end: position,
-            },
-            context: CodeActionContext {
-                diagnostics,
-                only: None,
-            },
+            range,
+            context: CodeActionContext { diagnostics, only },
             work_done_progress_params: WorkDoneProgressParams::default(),
             partial_result_params: PartialResultParams::default(),
         };
-        let language_id =
-            Some(language_id_from_path(path).unwrap_or("").to_string());
+        let language_id = Some(self.language_id(path));
         self.send_request_to_all_plugins(
             method,
             params,
@@ -653,8 +736,110 @@ pub fn get_inlay_hints(
             work_done_progress_params: WorkDoneProgressParams::default(),
             range,
         };
-        let language_id =
-            Some(language_id_from_path(path).unwrap_or("").to_string());
+        let language_id = Some(self.language_id(path));
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    pub fn get_document_colors(
+        &self,
+        path: &Path,
+        cb: impl FnOnce(PluginId, Result<Vec<ColorInformation>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = DocumentColor::METHOD;
+        let params = DocumentColorParams {
+            text_document: TextDocumentIdentifier { uri },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let language_id = Some(self.language_id(path));
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    pub fn get_color_presentations(
+        &self,
+        path: &Path,
+        color: Color,
+        range: Range,
+        cb: impl FnOnce(PluginId, Result<Vec<ColorPresentation>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = ColorPresentationRequest::METHOD;
+        let params = ColorPresentationParams {
+            text_document: TextDocumentIdentifier { uri },
+            color,
+            range,
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let language_id = Some(self.language_id(path));
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    pub fn get_document_links(
+        &self,
+        path: &Path,
+        cb: impl FnOnce(PluginId, Result<Vec<DocumentLink>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = DocumentLinkRequest::METHOD;
+        let params = DocumentLinkParams {
+            text_document: TextDocumentIdentifier { uri },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let language_id = Some(self.language_id(path));
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    pub fn get_runnables(
+        &self,
+        path: &Path,
+        cb: impl FnOnce(PluginId, Result<Vec<Runnable>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = Runnables::METHOD;
+        let params = RunnablesParams {
+            text_document: TextDocumentIdentifier { uri },
+            position: None,
+        };
+        let language_id = Some(self.language_id(path));
         self.send_request_to_all_plugins(
             method,
             params,
@@ -679,8 +864,7 @@ pub fn get_document_symbols(
             work_done_progress_params: WorkDoneProgressParams::default(),
             partial_result_params: PartialResultParams::default(),
         };
-        let language_id =
-            Some(language_id_from_path(path).unwrap_or("").to_string());
+        let language_id = Some(self.language_id(path));
         self.send_request_to_all_plugins(
             method,
             params,
@@ -726,8 +910,7 @@ pub fn get_document_formatting(
             },
             work_done_progress_params: WorkDoneProgressParams::default(),
         };
-        let language_id =
-            Some(language_id_from_path(path).unwrap_or("").to_string());
+        let language_id = Some(self.language_id(path));
         self.send_request_to_all_plugins(
             method,
             params,
@@ -752,8 +935,7 @@ pub fn prepare_rename(
             text_document: TextDocumentIdentifier { uri },
             position,
         };
-        let language_id =
-            Some(language_id_from_path(path).unwrap_or("").to_string());
+        let language_id = Some(self.language_id(path));
         self.send_request_to_all_plugins(
             method,
             params,
@@ -783,8 +965,7 @@ pub fn rename(
             new_name,
             work_done_progress_params: WorkDoneProgressParams::default(),
         };
-        let language_id =
-            Some(language_id_from_path(path).unwrap_or("").to_string());
+        let language_id = Some(self.language_id(path));
         self.send_request_to_all_plugins(
             method,
             params,
@@ -809,8 +990,7 @@ pub fn get_semantic_tokens(
             work_done_progress_params: WorkDoneProgressParams::default(),
             partial_result_params: PartialResultParams::default(),
         };
-        let language_id =
-            Some(language_id_from_path(path).unwrap_or("").to_string());
+        let language_id = Some(self.language_id(path));
         self.send_request_to_all_plugins(
             method,
             params,
@@ -837,8 +1017,7 @@ pub fn get_selection_range(
             work_done_progress_params: WorkDoneProgressParams::default(),
             partial_result_params: Default::default(),
         };
-        let language_id =
-            Some(language_id_from_path(path).unwrap_or("").to_string());
+        let language_id = Some(self.language_id(path));
         self.send_request_to_all_plugins(
             method,
             params,
@@ -863,8 +1042,7 @@ pub fn hover(
             },
             work_done_progress_params: WorkDoneProgressParams::default(),
         };
-        let language_id =
-            Some(language_id_from_path(path).unwrap_or("").to_string());
+        let language_id = Some(self.language_id(path));
 
         self.send_request_to_all_plugins(
             method,
@@ -895,8 +1073,7 @@ pub fn completion(
         };
 
         let core_rpc = self.core_rpc.clone();
-        let language_id =
-            Some(language_id_from_path(path).unwrap_or("").to_string());
+        let language_id = Some(self.language_id(path));
 
         self.send_request_to_all_plugins(
             method,
@@ -972,8 +1149,7 @@ pub fn signature_help(
         };
 
         let core_rpc = self.core_rpc.clone();
-        let language_id =
-            Some(language_id_from_path(path).unwrap_or("").to_string());
+        let language_id = Some(self.language_id(path));
         self.send_request(
             None,
             None,
@@ -1033,20 +1209,33 @@ pub fn action_resolve(
     pub fn did_open_document(
         &self,
         path: &Path,
-        language_id: String,
+        language_id: &'static str,
         version: i32,
         text: String,
     ) {
+        self.language_ids
+            .lock()
+            .insert(path.to_path_buf(), language_id);
         let _ = self.plugin_tx.send(PluginCatalogRpc::DidOpenTextDocument {
             document: TextDocumentItem::new(
                 Url::from_file_path(path).unwrap(),
-                language_id,
+                language_id.to_string(),
                 version,
                 text,
             ),
         });
     }
 
+    pub fn did_close_document(&self, path: &Path, language_id: &'static str) {
+        self.language_ids.lock().remove(path);
+        let _ = self.plugin_tx.send(PluginCatalogRpc::DidCloseTextDocument {
+            language_id: language_id.to_string(),
+            document: TextDocumentIdentifier::new(
+                Url::from_file_path(path).unwrap(),
+            ),
+        });
+    }
+
     pub fn unactivated_volts(&self, volts: Vec<VoltMetadata>) -> Result<()> {
         self.catalog_notification(PluginCatalogNotification::UnactivatedVolts(volts))
     }
@@ -1073,6 +1262,124 @@ pub fn install_volt(&self, volt: VoltInfo) -> Result<()> {
         self.catalog_notification(PluginCatalogNotification::InstallVolt(volt))
     }
 
+    pub fn execute_plugin_command(&self, volt: VoltID, id: String) -> Result<()> {
+        self.catalog_notification(PluginCatalogNotification::ExecuteCommand {
+            volt,
+            id,
+        })
+    }
+
+    /// Show the items of a plugin in the palette, and answer `resp` with the one
+    /// the user picks.
+    pub fn show_quick_pick(
+        &self,
+        placeholder: Option<String>,
+        items: Vec<PluginQuickPickItem>,
+        resp: ResponseSender,
+    ) {
+        let id = self.id.fetch_add(1, Ordering::Relaxed);
+        self.quick_picks.lock().insert(id, resp);
+        self.core_rpc.show_quick_pick(id, placeholder, items);
+    }
+
+    pub fn quick_pick_result(&self, id: u64, index: Option<usize>) {
+        if let Some(resp) = self.quick_picks.lock().remove(&id) {
+            resp.send(index);
+        }
+    }
+
+    /// Show a message of a plugin with its actions as buttons, and answer `resp`
+    /// with the one the user clicks.
+    pub fn show_message_request(
+        &self,
+        title: String,
+        params: ShowMessageRequestParams,
+        resp: ResponseSender,
+    ) {
+        let id = self.id.fetch_add(1, Ordering::Relaxed);
+        let actions = params.actions.unwrap_or_default();
+        let titles = actions.iter().map(|action| action.title.clone()).collect();
+        self.message_requests.lock().insert(id, (resp, actions));
+        let message = ShowMessageParams {
+            typ: params.typ,
+            message: params.message,
+        };
+        self.core_rpc
+            .show_message_request(id, title, message, titles);
+    }
+
+    pub fn message_action_result(&self, id: u64, index: Option<usize>) {
+        if let Some((resp, actions)) = self.message_requests.lock().remove(&id) {
+            resp.send(index.and_then(|index| actions.into_iter().nth(index)));
+        }
+    }
+
+    /// Forward the progress of a language server to the editor, and remember
+    /// which server to tell if the user cancels it.
+    pub fn work_done_progress(&self, plugin_id: PluginId, progress: ProgressParams) {
+        if let ProgressParamsValue::WorkDone(value) = &progress.value {
+            match value {
+                WorkDoneProgress::Begin(begin)
+                    if begin.cancellable == Some(true) =>
+                {
+                    self.cancellable_progresses
+                        .lock()
+                        .insert(progress.token.clone(), plugin_id);
+                }
+                WorkDoneProgress::End(_) => {
+                    self.cancellable_progresses.lock().remove(&progress.token);
+                }
+                _ => {}
+            }
+        }
+        self.core_rpc.work_done_progress(progress);
+    }
+
+    pub fn cancel_work_done_progress(&self, token: ProgressToken) {
+        let Some(plugin_id) = self.cancellable_progresses.lock().remove(&token)
+        else {
+            return;
+        };
+        self.send_notification(
+            Some(plugin_id),
+            WorkDoneProgressCancel::METHOD,
+            WorkDoneProgressCancelParams { token },
+            None,
+            None,
+            false,
+        );
+    }
+
+    pub fn set_granted_permissions(
+        &self,
+        granted: impl IntoIterator<Item = (VoltID, VoltPermissions)>,
+    ) {
+        *self.granted_permissions.lock() = granted.into_iter().collect();
+    }
+
+    /// Whether the user agreed to exactly these permissions for the plugin, so a
+    /// plugin that asks for more after an update has to ask again.
+    pub fn permissions_granted(
+        &self,
+        volt: &VoltID,
+        permissions: &VoltPermissions,
+    ) -> bool {
+        !permissions.needs_consent()
+            || self.granted_permissions.lock().get(volt) == Some(permissions)
+    }
+
+    pub fn grant_permissions(&self, volt: VoltMetadata) -> Result<()> {
+        self.granted_permissions
+            .lock()
+            .insert(volt.id(), volt.permissions());
+        self.reload_volt(volt)
+    }
+
+    pub fn revoke_permissions(&self, volt: VoltInfo) -> Result<()> {
+        self.granted_permissions.lock().remove(&volt.id());
+        self.stop_volt(volt)
+    }
+
     pub fn stop_volt(&self, volt: VoltInfo) -> Result<()> {
         self.catalog_notification(PluginCatalogNotification::StopVolt(volt))
     }
@@ -1308,9 +1615,15 @@ pub fn download_volt(volt: &VoltInfo) -> Result<VoltMetadata> {
 pub fn install_volt(
     catalog_rpc: PluginCatalogRpcHandler,
     workspace: Option<PathBuf>,
+    workspace_folders: Vec<PathBuf>,
     configurations: Option<HashMap<String, serde_json::Value>>,
     volt: VoltInfo,
 ) -> Result<()> {
+    let _progress = TaskProgress::begin(
+        &catalog_rpc.core_rpc,
+        format!("Installing {}", volt.display_name),
+        false,
+    );
     let download_volt_result = download_volt(&volt);
     if download_volt_result.is_err() {
         catalog_rpc
@@ -1321,7 +1634,13 @@ pub fn install_volt(
     let local_catalog_rpc = catalog_rpc.clone();
     let local_meta = meta.clone();
 
-    let _ = start_volt(workspace, configurations, local_catalog_rpc, local_meta);
+    let _ = start_volt(
+        workspace,
+        workspace_folders,
+        configurations,
+        local_catalog_rpc,
+        local_meta,
+    );
     let icon = volt_icon(&meta);
     catalog_rpc.core_rpc.volt_installed(meta, icon);
     Ok(())
@@ -1393,6 +1712,12 @@ fn client_capabilities() -> ClientCapabilities {
             inlay_hint: Some(InlayHintClientCapabilities {
                 ..Default::default()
             }),
+            color_provider: Some(DocumentColorClientCapabilities::default()),
+            document_highlight: Some(DocumentHighlightClientCapabilities::default()),
+            document_link: Some(DocumentLinkClientCapabilities {
+                tooltip_support: Some(true),
+                ..Default::default()
+            }),
             code_action: Some(CodeActionClientCapabilities {
                 data_support: Some(true),
                 resolve_support: Some(CodeActionCapabilityResolveSupport {
@@ -1446,10 +1771,12 @@ fn client_capabilities() -> ClientCapabilities {
             ..Default::default()
         }),
         workspace: Some(WorkspaceClientCapabilities {
+            apply_edit: Some(true),
             symbol: Some(WorkspaceSymbolClientCapabilities {
                 ..Default::default()
             }),
             configuration: Some(false),
+            workspace_folders: Some(true),
             ..Default::default()
         }),
         ..Default::default()
@@ -19,34 +19,39 @@
 };
 use lapce_rpc::{
     core::CoreRpcHandler,
-    plugin::{PluginId, VoltID},
+    plugin::{PluginCommand, PluginId, PluginStatusItem, VoltID},
+    proxy::{ProxyRequest, ProxyResponse},
     style::{LineStyle, Style},
     RpcError,
 };
 use lapce_xi_rope::{Rope, RopeDelta};
 use lsp_types::{
     notification::{
-        DidChangeTextDocument, DidOpenTextDocument, DidSaveTextDocument,
-        Initialized, LogMessage, Notification, Progress, PublishDiagnostics,
-        ShowMessage,
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+        DidSaveTextDocument, Initialized, LogMessage, Notification, Progress,
+        PublishDiagnostics, ShowMessage,
     },
     request::{
-        CodeActionRequest, CodeActionResolveRequest, Completion,
-        DocumentSymbolRequest, Formatting, GotoDefinition, GotoTypeDefinition,
-        HoverRequest, Initialize, InlayHintRequest, PrepareRenameRequest,
-        References, RegisterCapability, Rename, ResolveCompletionItem,
-        SelectionRangeRequest, SemanticTokensFullRequest, SignatureHelpRequest,
-        WorkDoneProgressCreate, WorkspaceSymbol,
+        ApplyWorkspaceEdit, CodeActionRequest, CodeActionResolveRequest,
+        ColorPresentationRequest, Completion, DocumentColor,
+        DocumentHighlightRequest, DocumentLinkRequest, DocumentSymbolRequest,
+        Formatting, GotoDefinition, GotoTypeDefinition, HoverRequest, Initialize,
+        InlayHintRequest, PrepareRenameRequest, References, RegisterCapability,
+        Rename, ResolveCompletionItem, SelectionRangeRequest,
+        SemanticTokensFullRequest, ShowMessageRequest, SignatureHelpRequest,
+        WorkDoneProgressCreate, WorkspaceFoldersRequest, WorkspaceSymbol,
     },
+    ApplyWorkspaceEditParams, ApplyWorkspaceEditResponse,
     CodeActionProviderCapability, DidChangeTextDocumentParams,
-    DidSaveTextDocumentParams, DocumentSelector, HoverProviderCapability,
-    InitializeResult, LogMessageParams, OneOf, ProgressParams,
-    PublishDiagnosticsParams, Range, Registration, RegistrationParams,
-    SemanticTokens, SemanticTokensLegend, SemanticTokensServerCapabilities,
-    ServerCapabilities, ShowMessageParams, TextDocumentContentChangeEvent,
+    DidSaveTextDocumentParams, DocumentChangeOperation, DocumentChanges,
+    DocumentSelector, HoverProviderCapability, InitializeResult, LogMessageParams,
+    OneOf, ProgressParams, PublishDiagnosticsParams, Range, Registration,
+    RegistrationParams, ResourceOp, SemanticTokens, SemanticTokensLegend,
+    SemanticTokensServerCapabilities, ServerCapabilities, ShowMessageParams,
+    ShowMessageRequestParams, TextDocumentContentChangeEvent,
     TextDocumentIdentifier, TextDocumentSaveRegistrationOptions,
     TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncSaveOptions,
-    VersionedTextDocumentIdentifier,
+    Url, VersionedTextDocumentIdentifier, WorkspaceEdit, WorkspaceFolder,
 };
 use parking_lot::Mutex;
 use psp_types::{
@@ -60,7 +65,14 @@
 use serde_json::Value;
 
 use super::{
+    api::{
+        GetBufferText, GetBufferTextParams, RegisterCommand, RegisterCommandParams,
+        RemoveStatusItem, RemoveStatusItemParams, SetStatusItem,
+        SetStatusItemParams, ShowQuickPick, ShowQuickPickParams, UnregisterCommand,
+        UnregisterCommandParams,
+    },
     lsp::{DocumentFilter, LspClient},
+    lsp_ext::Runnables,
     PluginCatalogRpcHandler,
 };
 
@@ -592,6 +604,8 @@ pub struct PluginHostHandler {
     volt_display_name: String,
     pwd: Option<PathBuf>,
     pub(crate) workspace: Option<PathBuf>,
+    /// Every root folder of the workspace, starting with `workspace`.
+    pub(crate) workspace_folders: Vec<PathBuf>,
     document_selector: Vec<DocumentFilter>,
     core_rpc: CoreRpcHandler,
     catalog_rpc: PluginCatalogRpcHandler,
@@ -602,12 +616,20 @@ pub struct PluginHostHandler {
     /// Language servers that this plugin has spawned.  
     /// Note that these plugin ids could be 'dead' if the LSP died/exited.  
     spawned_lsp: HashMap<PluginId, SpawnedLspInfo>,
+
+    /// The commands the plugin added to the palette.
+    commands: Vec<PluginCommand>,
+    /// The items the plugin shows in the status bar.
+    status_items: Vec<PluginStatusItem>,
+    /// Whether the user allowed the plugin to run programs.
+    allow_processes: bool,
 }
 
 impl PluginHostHandler {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         workspace: Option<PathBuf>,
+        workspace_folders: Vec<PathBuf>,
         pwd: Option<PathBuf>,
         volt_id: VoltID,
         volt_display_name: String,
@@ -615,6 +637,7 @@ pub fn new(
         core_rpc: CoreRpcHandler,
         server_rpc: PluginServerRpcHandler,
         catalog_rpc: PluginCatalogRpcHandler,
+        allow_processes: bool,
     ) -> Self {
         let document_selector = document_selector
             .iter()
@@ -623,6 +646,7 @@ pub fn new(
         Self {
             pwd,
             workspace,
+            workspace_folders,
             volt_id,
             volt_display_name,
             document_selector,
@@ -632,9 +656,27 @@ pub fn new(
             server_capabilities: ServerCapabilities::default(),
             server_registrations: ServerRegistrations::default(),
             spawned_lsp: HashMap::new(),
+            commands: Vec::new(),
+            status_items: Vec::new(),
+            allow_processes,
         }
     }
 
+    /// The root folders of the workspace, as they are given to language servers.
+    pub(crate) fn lsp_workspace_folders(&self) -> Vec<WorkspaceFolder> {
+        self.workspace_folders
+            .iter()
+            .filter_map(|path| {
+                let uri = Url::from_directory_path(path).ok()?;
+                let name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_else(|| uri.as_str().to_string());
+                Some(WorkspaceFolder { name, uri })
+            })
+            .collect()
+    }
+
     pub fn document_supported(
         &self,
         language_id: Option<&str>,
@@ -675,7 +717,7 @@ pub fn method_registered(&mut self, method: &str) -> bool {
                 .as_ref()
                 .and_then(|c| c.resolve_provider)
                 .unwrap_or(false),
-            DidOpenTextDocument::METHOD => {
+            DidOpenTextDocument::METHOD | DidCloseTextDocument::METHOD => {
                 match &self.server_capabilities.text_document_sync {
                     Some(TextDocumentSyncCapability::Kind(kind)) => {
                         kind != &TextDocumentSyncKind::NONE
@@ -736,6 +778,15 @@ pub fn method_registered(&mut self, method: &str) -> bool {
                     OneOf::Right(_) => true,
                 })
                 .unwrap_or(false),
+            DocumentHighlightRequest::METHOD => self
+                .server_capabilities
+                .document_highlight_provider
+                .as_ref()
+                .map(|r| match r {
+                    OneOf::Left(is_capable) => *is_capable,
+                    OneOf::Right(_) => true,
+                })
+                .unwrap_or(false),
             CodeActionRequest::METHOD => self
                 .server_capabilities
                 .code_action_provider
@@ -760,6 +811,17 @@ pub fn method_registered(&mut self, method: &str) -> bool {
             InlayHintRequest::METHOD => {
                 self.server_capabilities.inlay_hint_provider.is_some()
             }
+            DocumentColor::METHOD | ColorPresentationRequest::METHOD => {
+                self.server_capabilities.color_provider.is_some()
+            }
+            DocumentLinkRequest::METHOD => {
+                self.server_capabilities.document_link_provider.is_some()
+            }
+            Runnables::METHOD => {
+                self.server_capabilities.experimental.as_ref().is_some_and(
+                    |experimental| experimental.get("runnables").is_some(),
+                )
+            }
             DocumentSymbolRequest::METHOD => {
                 self.server_capabilities.document_symbol_provider.is_some()
             }
@@ -873,10 +935,29 @@ pub fn process_request(
         params: Params,
         resp: ResponseSender,
     ) -> Result<()> {
+        let spawns_process = matches!(
+            method.as_str(),
+            ExecuteProcess::METHOD
+                | RegisterDebuggerType::METHOD
+                | StartLspServer::METHOD
+        );
+        if spawns_process && !self.allow_processes {
+            return Err(anyhow!(
+                "{} isn't allowed to run programs",
+                self.volt_display_name
+            ));
+        }
+
         match method.as_str() {
             WorkDoneProgressCreate::METHOD => {
                 resp.send_null();
             }
+            WorkspaceFoldersRequest::METHOD => {
+                // The folders don't change while the server runs, so they're the
+                // ones it was initialized with, and null without a workspace
+                let folders = self.lsp_workspace_folders();
+                resp.send((!folders.is_empty()).then_some(folders));
+            }
             RegisterCapability::METHOD => {
                 let params: RegistrationParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
@@ -910,6 +991,7 @@ pub fn process_request(
                 let params: StartLspServerParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
                 let workspace = self.workspace.clone();
+                let workspace_folders = self.workspace_folders.clone();
                 let pwd = self.pwd.clone();
                 let catalog_rpc = self.catalog_rpc.clone();
                 let volt_id = self.volt_id.clone();
@@ -924,6 +1006,7 @@ pub fn process_request(
                         catalog_rpc,
                         params.document_selector,
                         workspace,
+                        workspace_folders,
                         volt_id,
                         volt_display_name,
                         Some(spawned_by),
@@ -995,6 +1078,76 @@ pub fn process_request(
                     },
                 )
             }
+            ShowMessageRequest::METHOD => {
+                let params: ShowMessageRequestParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                let title = format!("Plugin: {}", self.volt_display_name);
+                self.catalog_rpc.show_message_request(title, params, resp);
+            }
+            ShowQuickPick::METHOD => {
+                let params: ShowQuickPickParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                self.catalog_rpc.show_quick_pick(
+                    params.placeholder,
+                    params.items,
+                    resp,
+                );
+            }
+            GetBufferText::METHOD => {
+                let params: GetBufferTextParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                self.catalog_rpc.proxy_rpc.request_async(
+                    ProxyRequest::GetOpenFilesContent {},
+                    move |result| {
+                        let text = match result {
+                            Ok(ProxyResponse::GetOpenFilesContentResponse {
+                                items,
+                            }) => items
+                                .into_iter()
+                                .find(|item| item.uri == params.uri)
+                                .map(|item| item.text),
+                            _ => None,
+                        };
+                        resp.send(text);
+                    },
+                );
+            }
+            ApplyWorkspaceEdit::METHOD => {
+                let params: ApplyWorkspaceEditParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                // Plugins can only change the files of the workspace, or without
+                // a workspace the text of the documents that are open
+                let (edited, operated) = edited_uris(&params.edit);
+                let in_workspace = |path: &Path| {
+                    self.workspace_folders
+                        .iter()
+                        .any(|folder| path.starts_with(folder))
+                };
+                let no_workspace = self.workspace_folders.is_empty();
+                let outside = edited
+                    .into_iter()
+                    .find(|uri| {
+                        !uri.to_file_path().is_ok_and(|path| {
+                            in_workspace(&path)
+                                || (no_workspace && self.catalog_rpc.is_open(&path))
+                        })
+                    })
+                    .or_else(|| {
+                        operated.into_iter().find(|uri| {
+                            !uri.to_file_path().is_ok_and(|path| in_workspace(&path))
+                        })
+                    });
+                let failure_reason =
+                    outside.map(|uri| format!("{uri} is outside of the workspace"));
+                if failure_reason.is_none() {
+                    self.core_rpc.apply_workspace_edit(params.edit);
+                }
+                resp.send(ApplyWorkspaceEditResponse {
+                    applied: failure_reason.is_none(),
+                    failure_reason,
+                    failed_change: None,
+                });
+            }
             _ => return Err(anyhow!("request not supported")),
         }
 
@@ -1016,10 +1169,17 @@ pub fn handle_notification(
                         self.volt_display_name
                     ),
                 );
+                if !self.allow_processes {
+                    return Err(anyhow!(
+                        "{} isn't allowed to run programs",
+                        self.volt_display_name
+                    ));
+                }
 
                 let params: StartLspServerParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
                 let workspace = self.workspace.clone();
+                let workspace_folders = self.workspace_folders.clone();
                 let pwd = self.pwd.clone();
                 let catalog_rpc = self.catalog_rpc.clone();
                 let volt_id = self.volt_id.clone();
@@ -1029,6 +1189,7 @@ pub fn handle_notification(
                         catalog_rpc,
                         params.document_selector,
                         workspace,
+                        workspace_folders,
                         volt_id,
                         volt_display_name,
                         None,
@@ -1048,7 +1209,8 @@ pub fn handle_notification(
             Progress::METHOD => {
                 let progress: ProgressParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
-                self.catalog_rpc.core_rpc.work_done_progress(progress);
+                self.catalog_rpc
+                    .work_done_progress(self.server_rpc.plugin_id, progress);
             }
             ShowMessage::METHOD => {
                 let message: ShowMessageParams =
@@ -1061,6 +1223,56 @@ pub fn handle_notification(
                     serde_json::from_value(serde_json::to_value(params)?)?;
                 self.catalog_rpc.core_rpc.log_message(message);
             }
+            RegisterCommand::METHOD => {
+                let params: RegisterCommandParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                let command = PluginCommand {
+                    volt: self.volt_id.clone(),
+                    id: params.id,
+                    title: params.title,
+                };
+                match self.commands.iter_mut().find(|c| c.id == command.id) {
+                    Some(existing) => *existing = command,
+                    None => self.commands.push(command),
+                }
+                self.core_rpc
+                    .plugin_commands(self.volt_id.clone(), self.commands.clone());
+            }
+            UnregisterCommand::METHOD => {
+                let params: UnregisterCommandParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                self.commands.retain(|c| c.id != params.id);
+                self.core_rpc
+                    .plugin_commands(self.volt_id.clone(), self.commands.clone());
+            }
+            SetStatusItem::METHOD => {
+                let params: SetStatusItemParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                let item = PluginStatusItem {
+                    volt: self.volt_id.clone(),
+                    id: params.id,
+                    text: params.text,
+                    command: params.command,
+                    alignment: params.alignment,
+                };
+                match self.status_items.iter_mut().find(|i| i.id == item.id) {
+                    Some(existing) => *existing = item,
+                    None => self.status_items.push(item),
+                }
+                self.core_rpc.plugin_status_items(
+                    self.volt_id.clone(),
+                    self.status_items.clone(),
+                );
+            }
+            RemoveStatusItem::METHOD => {
+                let params: RemoveStatusItemParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                self.status_items.retain(|i| i.id != params.id);
+                self.core_rpc.plugin_status_items(
+                    self.volt_id.clone(),
+                    self.status_items.clone(),
+                );
+            }
             _ => {
                 eprintln!("host notificaton {method} not handled");
             }
@@ -1279,9 +1491,19 @@ fn format_semantic_styles(
         let end =
             start + offset_utf16_to_utf8(sub_text, semantic_token.length as usize);
 
-        let kind = semantic_legends.token_types[semantic_token.token_type as usize]
+        // The modifiers are added to the type as scope segments, such as
+        // `variable.mutable`, so themes can style them on their own
+        let mut kind = semantic_legends.token_types
+            [semantic_token.token_type as usize]
             .as_str()
             .to_string();
+        let modifiers = semantic_legends.token_modifiers.iter().take(32);
+        for (i, modifier) in modifiers.enumerate() {
+            if semantic_token.token_modifiers_bitset & (1 << i) != 0 {
+                kind.push('.');
+                kind.push_str(modifier.as_str());
+            }
+        }
         if start < last_start {
             continue;
         }
@@ -1310,3 +1532,40 @@ fn semantic_tokens_legend(
         ) => &options.semantic_tokens_options.legend,
     }
 }
+
+/// The documents that a workspace edit changes the text of, and the files it
+/// creates, renames or deletes.
+fn edited_uris(edit: &WorkspaceEdit) -> (Vec<&Url>, Vec<&Url>) {
+    let mut edited: Vec<&Url> = edit
+        .changes
+        .as_ref()
+        .map(|changes| changes.keys().collect())
+        .unwrap_or_default();
+    let mut operated = Vec::new();
+    match edit.document_changes.as_ref() {
+        Some(DocumentChanges::Edits(edits)) => {
+            edited.extend(edits.iter().map(|e| &e.text_document.uri));
+        }
+        Some(DocumentChanges::Operations(ops)) => {
+            for op in ops {
+                match op {
+                    DocumentChangeOperation::Edit(e) => {
+                        edited.push(&e.text_document.uri);
+                    }
+                    DocumentChangeOperation::Op(ResourceOp::Create(create)) => {
+                        operated.push(&create.uri);
+                    }
+                    DocumentChangeOperation::Op(ResourceOp::Rename(rename)) => {
+                        operated.push(&rename.old_uri);
+                        operated.push(&rename.new_uri);
+                    }
+                    DocumentChangeOperation::Op(ResourceOp::Delete(delete)) => {
+                        operated.push(&delete.uri);
+                    }
+                }
+            }
+        }
+        None => {}
+    }
+    (edited, operated)
+}
@@ -7,15 +7,22 @@
     io::{Read, Seek, Write},
     path::{Path, PathBuf},
     process,
-    sync::{Arc, RwLock},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
     thread,
+    time::Duration,
 };
 
 use anyhow::{anyhow, Result};
 use jsonrpc_lite::{Id, Params};
 use lapce_core::directory::Directory;
 use lapce_rpc::{
-    plugin::{PluginId, VoltID, VoltInfo, VoltMetadata},
+    plugin::{
+        PluginId, VoltID, VoltInfo, VoltLanguageServer, VoltMetadata,
+        VoltPermissions, PLUGIN_API_VERSION,
+    },
     style::LineStyle,
     RpcError,
 };
@@ -32,6 +39,7 @@
 
 use super::{
     client_capabilities,
+    lsp::LspClient,
     psp::{
         handle_plugin_server_message, PluginHandlerNotification, PluginHostHandler,
         PluginServerHandler, PluginServerRpc, ResponseSender, RpcCallback,
@@ -40,6 +48,16 @@
 };
 use crate::plugin::psp::PluginServerRpcHandler;
 
+/// How often the engine's epoch goes up, which is how the CPU time a plugin takes
+/// is measured.
+const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// The data a plugin's wasm store holds.
+struct VoltState {
+    wasi: wasmtime_wasi::WasiCtx,
+    limits: wasmtime::StoreLimits,
+}
+
 #[derive(Default)]
 pub struct WasiPipe {
     buffer: VecDeque<u8>,
@@ -237,9 +255,14 @@ pub fn load_all_volts(
     let volts = all_volts
         .into_iter()
         .filter_map(|meta| {
-            meta.wasm.as_ref()?;
-            let icon = volt_icon(&meta);
-            plugin_rpc.core_rpc.volt_installed(meta.clone(), icon);
+            if meta.wasm.is_none() && meta.language_servers.is_none() {
+                return None;
+            }
+            // The editor finds the plugins without wasm itself
+            if meta.wasm.is_some() {
+                let icon = volt_icon(&meta);
+                plugin_rpc.core_rpc.volt_installed(meta.clone(), icon);
+            }
             if disabled_volts.contains(&meta.id()) {
                 return None;
             }
@@ -306,7 +329,10 @@ pub fn find_all_volts() -> Vec<VoltMetadata> {
 ///         icon_themes: None,
 ///         dir: parent_path.canonicalize().ok(),
 ///         activation: None,
-///         config: None
+///         config: None,
+///         api_version: None,
+///         language_servers: None,
+///         permissions: None
 ///     }
 /// );
 /// let _ = std::fs::remove_file(parent_path.join("volt.toml"));
@@ -358,13 +384,167 @@ pub fn enable_volt(
     Ok(())
 }
 
+/// Start a language server that the plugin declares in its `volt.toml`. It gets
+/// the plugin's configuration as its initialization options.
+fn start_language_server(
+    workspace: Option<PathBuf>,
+    workspace_folders: Vec<PathBuf>,
+    configurations: Option<HashMap<String, serde_json::Value>>,
+    plugin_rpc: PluginCatalogRpcHandler,
+    meta: &VoltMetadata,
+    server: &VoltLanguageServer,
+) -> Result<()> {
+    let command = server
+        .command
+        .replace("${os}", std::env::consts::OS)
+        .replace("${arch}", std::env::consts::ARCH);
+    let bundled = meta
+        .dir
+        .as_ref()
+        .map(|dir| dir.join(&command))
+        .filter(|path| path.is_file());
+    let server_uri = match bundled {
+        Some(path) => Url::from_file_path(path)
+            .map_err(|_| anyhow!("can't convert server path to uri"))?,
+        None => Url::parse(&format!("urn:{command}"))?,
+    };
+    let document_selector = server
+        .languages
+        .iter()
+        .map(|language| DocumentFilter {
+            language: Some(language.clone()),
+            pattern: None,
+            scheme: None,
+        })
+        .collect();
+    let options = configurations.map(serde_json::to_value).transpose()?;
+
+    thread::spawn({
+        let volt_id = meta.id();
+        let volt_display_name = meta.display_name.clone();
+        let pwd = meta.dir.clone();
+        let args = server.args.clone();
+        move || {
+            let _ = LspClient::start(
+                plugin_rpc,
+                document_selector,
+                workspace,
+                workspace_folders,
+                volt_id,
+                volt_display_name,
+                None,
+                None,
+                pwd,
+                server_uri,
+                args,
+                options,
+            );
+        }
+    });
+    Ok(())
+}
+
+/// The hosts the plugin can send requests to, in the form the HTTP extension
+/// expects.
+fn allowed_hosts(permissions: &VoltPermissions) -> Vec<String> {
+    if permissions.network.iter().any(|host| host == "*") {
+        return vec!["insecure:allow-all".to_string()];
+    }
+    permissions
+        .network
+        .iter()
+        .map(|host| {
+            if host.contains("://") {
+                host.clone()
+            } else {
+                format!("https://{host}")
+            }
+        })
+        .collect()
+}
+
+/// The folders the plugin can use besides its own.
+fn permitted_dirs(
+    permissions: &VoltPermissions,
+    workspace_folders: &[PathBuf],
+) -> Vec<PathBuf> {
+    permissions
+        .filesystem
+        .iter()
+        .flat_map(|scope| {
+            if scope.contains("${workspace}") {
+                workspace_folders
+                    .iter()
+                    .map(|folder| {
+                        PathBuf::from(
+                            scope.replace("${workspace}", &folder.to_string_lossy()),
+                        )
+                    })
+                    .collect()
+            } else {
+                vec![PathBuf::from(scope)]
+            }
+        })
+        .filter(|dir| dir.is_dir())
+        .collect()
+}
+
 pub fn start_volt(
     workspace: Option<PathBuf>,
+    workspace_folders: Vec<PathBuf>,
     configurations: Option<HashMap<String, serde_json::Value>>,
     plugin_rpc: PluginCatalogRpcHandler,
     meta: VoltMetadata,
 ) -> Result<()> {
-    let engine = wasmtime::Engine::default();
+    if let Some(api_version) = meta.api_version.filter(|v| *v > PLUGIN_API_VERSION) {
+        let message = format!(
+            "{} needs version {api_version} of the plugin API, but this version \
+             of Lapce only has version {PLUGIN_API_VERSION}",
+            meta.display_name
+        );
+        plugin_rpc
+            .core_rpc
+            .log(tracing::Level::ERROR, message.clone());
+        return Err(anyhow!(message));
+    }
+
+    let permissions = meta.permissions();
+    if !plugin_rpc.permissions_granted(&meta.id(), &permissions) {
+        // It's started again once the user agrees
+        plugin_rpc.core_rpc.volt_permissions_requested(meta);
+        return Ok(());
+    }
+
+    for server in meta.language_servers.iter().flatten() {
+        let started = if permissions.process {
+            start_language_server(
+                workspace.clone(),
+                workspace_folders.clone(),
+                configurations.clone(),
+                plugin_rpc.clone(),
+                &meta,
+                server,
+            )
+        } else {
+            Err(anyhow!("the plugin isn't allowed to run programs"))
+        };
+        if let Err(err) = started {
+            plugin_rpc.core_rpc.log(
+                tracing::Level::ERROR,
+                format!(
+                    "{} can't start {}: {err}",
+                    meta.display_name, server.command
+                ),
+            );
+        }
+    }
+    if meta.wasm.is_none() {
+        return Ok(());
+    }
+
+    let mut config = wasmtime::Config::new();
+    config.epoch_interruption(permissions.cpu_time.is_some());
+    let engine = wasmtime::Engine::new(&config)?;
     let module = wasmtime::Module::from_file(
         &engine,
         meta.wasm
@@ -372,9 +552,10 @@ pub fn start_volt(
             .ok_or_else(|| anyhow!("no wasm in plugin"))?,
     )?;
     let mut linker = wasmtime::Linker::new(&engine);
-    wasmtime_wasi::add_to_linker(&mut linker, |s| s)?;
-    HttpState::new()?.add_to_linker(&mut linker, |_| HttpCtx {
-        allowed_hosts: Some(vec!["insecure:allow-all".to_string()]),
+    wasmtime_wasi::add_to_linker(&mut linker, |s: &mut VoltState| &mut s.wasi)?;
+    let allowed_hosts = allowed_hosts(&permissions);
+    HttpState::new()?.add_to_linker(&mut linker, move |_| HttpCtx {
+        allowed_hosts: Some(allowed_hosts.clone()),
         max_concurrent_requests: Some(100),
     })?;
 
@@ -409,11 +590,12 @@ pub fn start_volt(
     let stdin = Arc::new(RwLock::new(WasiPipe::new()));
     let stdout = Arc::new(RwLock::new(WasiPipe::new()));
     let stderr = Arc::new(RwLock::new(WasiPipe::new()));
-    let wasi = WasiCtxBuilder::new()
-        .inherit_env()?
+    let mut wasi = WasiCtxBuilder::new();
+    wasi.inherit_env()?
         .env("VOLT_OS", std::env::consts::OS)?
         .env("VOLT_ARCH", std::env::consts::ARCH)?
         .env("VOLT_LIBC", volt_libc)?
+        .env("VOLT_API_VERSION", &PLUGIN_API_VERSION.to_string())?
         .env(
             "VOLT_URI",
             Url::from_directory_path(volt_path)
@@ -435,9 +617,46 @@ pub fn start_volt(
                 wasmtime_wasi::ambient_authority(),
             )?,
             "/",
-        )?
-        .build();
-    let mut store = wasmtime::Store::new(&engine, wasi);
+        )?;
+    for dir in permitted_dirs(&permissions, &workspace_folders) {
+        wasi.preopened_dir(
+            wasmtime_wasi::Dir::open_ambient_dir(
+                &dir,
+                wasmtime_wasi::ambient_authority(),
+            )?,
+            &dir,
+        )?;
+    }
+    let running = Arc::new(AtomicBool::new(true));
+    let limits = match permissions.memory {
+        Some(megabytes) => wasmtime::StoreLimitsBuilder::new()
+            .memory_size(megabytes * 1024 * 1024)
+            .build(),
+        None => wasmtime::StoreLimits::default(),
+    };
+    let mut store = wasmtime::Store::new(
+        &engine,
+        VoltState {
+            wasi: wasi.build(),
+            limits,
+        },
+    );
+    store.limiter(|state| &mut state.limits);
+    // Every message gets `cpu_time` to be handled, counted in epoch ticks
+    let epoch_deadline = permissions
+        .cpu_time
+        .map(|ms| (ms / EPOCH_TICK.as_millis() as u64).max(1));
+    if let Some(deadline) = epoch_deadline {
+        store.set_epoch_deadline(deadline);
+        let engine = engine.clone();
+        let running = running.clone();
+        thread::spawn(move || {
+            while running.load(Ordering::Relaxed) {
+                thread::sleep(EPOCH_TICK);
+                engine.increment_epoch();
+            }
+        });
+    }
 
     let (io_tx, io_rx) = crossbeam_channel::unbounded();
     let rpc = PluginServerRpcHandler::new(meta.id(), None, None, io_tx);
@@ -459,6 +678,8 @@ pub fn start_volt(
         }
     })?;
     linker.module(&mut store, "", &module)?;
+    let volt = meta.info();
+    let catalog_rpc = plugin_rpc.clone();
     thread::spawn(move || {
         let instance = linker.instantiate(&mut store, &module).unwrap();
         let handle_rpc = instance
@@ -471,8 +692,27 @@ pub fn start_volt(
             if let Ok(msg) = serde_json::to_string(&msg) {
                 let _ = writeln!(stdin.write().unwrap(), "{msg}");
             }
-            let _ = handle_rpc.call(&mut store, ());
+            if let Some(deadline) = epoch_deadline {
+                store.set_epoch_deadline(deadline);
+            }
+            if let Err(err) = handle_rpc.call(&mut store, ()) {
+                if err.downcast_ref::<wasmtime::Trap>()
+                    == Some(&wasmtime::Trap::Interrupt)
+                {
+                    catalog_rpc.core_rpc.log(
+                        tracing::Level::ERROR,
+                        format!(
+                            "{} was stopped because it took too long to handle \
+                             a message",
+                            volt.display_name
+                        ),
+                    );
+                    let _ = catalog_rpc.stop_volt(volt);
+                    break;
+                }
+            }
         }
+        running.store(false, Ordering::Relaxed);
     });
 
     let id = PluginId::next();
@@ -480,6 +720,7 @@ pub fn start_volt(
         id,
         host: PluginHostHandler::new(
             workspace,
+            workspace_folders,
             meta.dir.clone(),
             meta.id(),
             meta.display_name.clone(),
@@ -507,6 +748,7 @@ pub fn start_volt(
             plugin_rpc.core_rpc.clone(),
             rpc.clone(),
             plugin_rpc.clone(),
+            permissions.process,
         ),
         configurations,
     };
@@ -0,0 +1,97 @@
+//! The progress of the long tasks of the proxy itself, like indexing the files
+//! of the workspace, which the editor shows the same way as the `$/progress` of
+//! a language server.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use lapce_rpc::core::CoreRpcHandler;
+use lsp_types::{
+    ProgressParams, ProgressParamsValue, ProgressToken, WorkDoneProgress,
+    WorkDoneProgressBegin, WorkDoneProgressEnd, WorkDoneProgressReport,
+};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+static NEXT_TOKEN: AtomicU64 = AtomicU64::new(0);
+/// Whether the user cancelled each task that can be cancelled. The tokens are
+/// unique in the process, so the proxies of other window tabs running in it
+/// share this.
+static CANCELLED: Lazy<Mutex<HashMap<ProgressToken, Arc<AtomicBool>>>> =
+    Lazy::new(Default::default);
+
+/// A task shown in the editor until it's dropped.
+pub struct TaskProgress {
+    core_rpc: CoreRpcHandler,
+    token: ProgressToken,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl TaskProgress {
+    pub fn begin(
+        core_rpc: &CoreRpcHandler,
+        title: impl Into<String>,
+        cancellable: bool,
+    ) -> Self {
+        let id = NEXT_TOKEN.fetch_add(1, Ordering::Relaxed);
+        let token = ProgressToken::String(format!("lapce/{id}"));
+        let cancelled = Arc::new(AtomicBool::new(false));
+        if cancellable {
+            CANCELLED.lock().insert(token.clone(), cancelled.clone());
+        }
+        let progress = Self {
+            core_rpc: core_rpc.clone(),
+            token,
+            cancelled,
+        };
+        progress.send(WorkDoneProgress::Begin(WorkDoneProgressBegin {
+            title: title.into(),
+            cancellable: Some(cancellable),
+            ..Default::default()
+        }));
+        progress
+    }
+
+    pub fn report(&self, message: Option<String>, percentage: Option<u32>) {
+        self.send(WorkDoneProgress::Report(WorkDoneProgressReport {
+            message,
+            percentage,
+            ..Default::default()
+        }));
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    fn send(&self, value: WorkDoneProgress) {
+        self.core_rpc.work_done_progress(ProgressParams {
+            token: self.token.clone(),
+            value: ProgressParamsValue::WorkDone(value),
+        });
+    }
+}
+
+impl Drop for TaskProgress {
+    fn drop(&mut self) {
+        CANCELLED.lock().remove(&self.token);
+        self.send(WorkDoneProgress::End(WorkDoneProgressEnd { message: None }));
+    }
+}
+
+/// Cancel the task of the token, if it's a task of the proxy that can be
+/// cancelled. Returns whether it was one.
+pub fn cancel(token: &ProgressToken) -> bool {
+    match CANCELLED.lock().get(token) {
+        Some(cancelled) => {
+            cancelled.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
@@ -0,0 +1,97 @@
+//! Building the executables of the runnables a language server lists, so that
+//! they can be debugged. The build runs where the workspace is, which is on the
+//! remote host for a remote workspace.
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+use std::process::{Command, Stdio};
+
+use lapce_rpc::proxy::RunnableArgs;
+use serde::Deserialize;
+
+/// A line of the JSON output of Cargo, of which only the artifacts with an
+/// executable are of interest.
+#[derive(Deserialize)]
+struct CargoMessage {
+    reason: String,
+    executable: Option<String>,
+}
+
+/// Build the executable of the runnable with Cargo, and return its path. The
+/// error is what Cargo gave when the build failed.
+pub fn build_runnable(args: &RunnableArgs) -> Result<String, String> {
+    let mut cmd = Command::new("cargo");
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    cmd.args(build_args(args)).stdin(Stdio::null());
+    if let Some(root) = args.workspace_root.as_ref() {
+        cmd.current_dir(root);
+    }
+    let output = cmd.output().map_err(|err| err.to_string())?;
+    built_executable(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| String::from_utf8_lossy(&output.stderr).trim().to_string())
+}
+
+/// The arguments to have Cargo build the executable of the runnable without
+/// running it, and list what it built as JSON.
+fn build_args(args: &RunnableArgs) -> Vec<String> {
+    let mut build_args = args.cargo_args.clone();
+    match build_args.first().map(String::as_str) {
+        Some("run") => build_args[0] = "build".to_string(),
+        Some("test") => build_args.push("--no-run".to_string()),
+        _ => {}
+    }
+    build_args.extend(args.cargo_extra_args.iter().cloned());
+    build_args.push("--message-format=json".to_string());
+    build_args
+}
+
+/// The executable Cargo built last, from its JSON output.
+fn built_executable(output: &str) -> Option<String> {
+    output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<CargoMessage>(line).ok())
+        .filter(|message| message.reason == "compiler-artifact")
+        .filter_map(|message| message.executable)
+        .last()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_executable() {
+        let args = RunnableArgs {
+            workspace_root: Some("/project".into()),
+            cargo_args: vec![
+                "test".to_string(),
+                "--package".to_string(),
+                "app".to_string(),
+            ],
+            cargo_extra_args: vec!["--release".to_string()],
+            executable_args: vec![
+                "tests::it_works".to_string(),
+                "--exact".to_string(),
+            ],
+        };
+        assert_eq!(
+            build_args(&args),
+            vec![
+                "test",
+                "--package",
+                "app",
+                "--no-run",
+                "--release",
+                "--message-format=json"
+            ]
+        );
+        let output = r#"{"reason":"compiler-artifact","executable":null}
+{"reason":"compiler-artifact","executable":"/project/target/debug/deps/app-1a2b"}
+{"reason":"build-finished","success":true}"#;
+        assert_eq!(
+            built_executable(output).as_deref(),
+            Some("/project/target/debug/deps/app-1a2b")
+        );
+    }
+}
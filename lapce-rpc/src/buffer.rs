@@ -22,3 +22,80 @@ pub struct BufferHeadResponse {
     pub version: String,
     pub content: String,
 }
+
+/// The line ending style of a file on disk.
+/// Buffers always use `\n` internally, the file's line ending is restored when
+/// saving.
+#[derive(
+    Eq, PartialEq, Hash, Copy, Clone, Debug, Default, Serialize, Deserialize,
+)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    /// Detect the most common line ending in the text, defaulting to `\n` when there
+    /// are no line breaks at all.
+    pub fn detect(text: &str) -> LineEnding {
+        let mut lf = 0;
+        let mut crlf = 0;
+        let bytes = text.as_bytes();
+        for (i, _) in text.match_indices('\n') {
+            if i > 0 && bytes[i - 1] == b'\r' {
+                crlf += 1;
+            } else {
+                lf += 1;
+            }
+        }
+        if crlf > lf {
+            LineEnding::CrLf
+        } else {
+            LineEnding::Lf
+        }
+    }
+
+    /// Convert every `\r\n` in the text into `\n`.
+    pub fn normalize(text: String) -> String {
+        if text.contains("\r\n") {
+            text.replace("\r\n", "\n")
+        } else {
+            text
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "LF",
+            LineEnding::CrLf => "CRLF",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LineEnding;
+
+    #[test]
+    fn test_detect_line_ending() {
+        assert_eq!(LineEnding::detect(""), LineEnding::Lf);
+        assert_eq!(LineEnding::detect("a\nb\n"), LineEnding::Lf);
+        assert_eq!(LineEnding::detect("a\r\nb\r\n"), LineEnding::CrLf);
+        assert_eq!(LineEnding::detect("a\r\nb\r\nc\n"), LineEnding::CrLf);
+        assert_eq!(LineEnding::detect("a\r\nb\nc\n"), LineEnding::Lf);
+    }
+
+    #[test]
+    fn test_normalize_line_ending() {
+        assert_eq!(LineEnding::normalize("a\r\nb\n".to_string()), "a\nb\n");
+        assert_eq!(LineEnding::normalize("a\nb".to_string()), "a\nb");
+    }
+}
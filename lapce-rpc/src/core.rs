@@ -10,7 +10,7 @@
 use crossbeam_channel::{Receiver, Sender};
 use lsp_types::{
     CompletionResponse, LogMessageParams, ProgressParams, PublishDiagnosticsParams,
-    ShowMessageParams, SignatureHelp,
+    ShowMessageParams, SignatureHelp, WorkspaceEdit,
 };
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
@@ -20,7 +20,10 @@
         self, DapId, RunDebugConfig, Scope, StackFrame, Stopped, ThreadId, Variable,
     },
     file::PathObject,
-    plugin::{PluginId, VoltInfo, VoltMetadata},
+    plugin::{
+        LanguageServerStatus, PluginCommand, PluginId, PluginQuickPickItem,
+        PluginStatusItem, VoltID, VoltInfo, VoltMetadata,
+    },
     proxy::ProxyStatus,
     source_control::DiffInfo,
     terminal::TermId,
@@ -40,6 +43,11 @@ pub enum CoreNotification {
     ProxyStatus {
         status: ProxyStatus,
     },
+    /// The first thing the proxy sends, with the version of the protocol it
+    /// speaks. It doesn't do anything else when that isn't the editor's.
+    ProxyHandshake {
+        protocol_version: u32,
+    },
     OpenFileChanged {
         path: PathBuf,
         content: String,
@@ -58,7 +66,19 @@ pub enum CoreNotification {
     OpenPaths {
         paths: Vec<PathObject>,
     },
-    WorkspaceFileChange,
+    /// Open a new untitled file with the text in it, like what was piped to
+    /// `lapce -`.
+    OpenUntitled {
+        content: String,
+    },
+    /// Files or folders in the workspace were created, removed or renamed.
+    WorkspaceFileChange {
+        paths: Vec<PathBuf>,
+    },
+    /// Files found so far while the workspace file list is first built.
+    IndexedFiles {
+        items: Vec<PathBuf>,
+    },
     PublishDiagnostics {
         diagnostics: PublishDiagnosticsParams,
     },
@@ -69,6 +89,14 @@ pub enum CoreNotification {
         title: String,
         message: ShowMessageParams,
     },
+    /// A message with buttons, where the title of the one that is clicked is
+    /// answered with the `id` in a `PluginMessageActionResult` notification.
+    ShowMessageRequest {
+        id: u64,
+        title: String,
+        message: ShowMessageParams,
+        actions: Vec<String>,
+    },
     LogMessage {
         message: LogMessageParams,
     },
@@ -91,6 +119,37 @@ pub enum CoreNotification {
         volt: VoltInfo,
         only_installing: bool,
     },
+    /// A plugin can't start until the user grants the permissions it asks for.
+    VoltPermissionsRequested {
+        volt: VoltMetadata,
+    },
+    /// All the commands a plugin has added to the palette.
+    PluginCommands {
+        volt: VoltID,
+        commands: Vec<PluginCommand>,
+    },
+    /// All the items a plugin shows in the status bar.
+    PluginStatusItems {
+        volt: VoltID,
+        items: Vec<PluginStatusItem>,
+    },
+    /// A language server was started, finished initializing or stopped. `name`
+    /// is the display name of the plugin that started it.
+    LanguageServerStatus {
+        plugin_id: PluginId,
+        name: String,
+        status: LanguageServerStatus,
+    },
+    /// A plugin asks the user to pick one of the items, which is answered with
+    /// the `id` in a `PluginQuickPickResult` notification.
+    ShowQuickPick {
+        id: u64,
+        placeholder: Option<String>,
+        items: Vec<PluginQuickPickItem>,
+    },
+    ApplyWorkspaceEdit {
+        edit: WorkspaceEdit,
+    },
     DiffInfo {
         diff: DiffInfo,
     },
@@ -225,8 +284,8 @@ pub fn notification(&self, notification: CoreNotification) {
         let _ = self.tx.send(CoreRpc::Notification(Box::new(notification)));
     }
 
-    pub fn workspace_file_change(&self) {
-        self.notification(CoreNotification::WorkspaceFileChange);
+    pub fn workspace_file_change(&self, paths: Vec<PathBuf>) {
+        self.notification(CoreNotification::WorkspaceFileChange { paths });
     }
 
     pub fn diff_info(&self, diff: DiffInfo) {
@@ -284,6 +343,48 @@ pub fn volt_removed(&self, volt: VoltInfo, only_installing: bool) {
         });
     }
 
+    pub fn volt_permissions_requested(&self, volt: VoltMetadata) {
+        self.notification(CoreNotification::VoltPermissionsRequested { volt });
+    }
+
+    pub fn plugin_commands(&self, volt: VoltID, commands: Vec<PluginCommand>) {
+        self.notification(CoreNotification::PluginCommands { volt, commands });
+    }
+
+    pub fn plugin_status_items(&self, volt: VoltID, items: Vec<PluginStatusItem>) {
+        self.notification(CoreNotification::PluginStatusItems { volt, items });
+    }
+
+    pub fn language_server_status(
+        &self,
+        plugin_id: PluginId,
+        name: String,
+        status: LanguageServerStatus,
+    ) {
+        self.notification(CoreNotification::LanguageServerStatus {
+            plugin_id,
+            name,
+            status,
+        });
+    }
+
+    pub fn show_quick_pick(
+        &self,
+        id: u64,
+        placeholder: Option<String>,
+        items: Vec<PluginQuickPickItem>,
+    ) {
+        self.notification(CoreNotification::ShowQuickPick {
+            id,
+            placeholder,
+            items,
+        });
+    }
+
+    pub fn apply_workspace_edit(&self, edit: WorkspaceEdit) {
+        self.notification(CoreNotification::ApplyWorkspaceEdit { edit });
+    }
+
     pub fn run_in_terminal(&self, config: RunDebugConfig) {
         self.notification(CoreNotification::RunInTerminal { config });
     }
@@ -307,6 +408,21 @@ pub fn show_message(&self, title: String, message: ShowMessageParams) {
         self.notification(CoreNotification::ShowMessage { title, message });
     }
 
+    pub fn show_message_request(
+        &self,
+        id: u64,
+        title: String,
+        message: ShowMessageParams,
+        actions: Vec<String>,
+    ) {
+        self.notification(CoreNotification::ShowMessageRequest {
+            id,
+            title,
+            message,
+            actions,
+        });
+    }
+
     pub fn log_message(&self, message: LogMessageParams) {
         self.notification(CoreNotification::LogMessage { message });
     }
@@ -365,6 +481,10 @@ pub fn dap_breakpoints_resp(
     pub fn home_dir(&self, path: PathBuf) {
         self.notification(CoreNotification::HomeDir { path });
     }
+
+    pub fn proxy_handshake(&self, protocol_version: u32) {
+        self.notification(CoreNotification::ProxyHandshake { protocol_version });
+    }
 }
 
 impl Default for CoreRpcHandler {
@@ -57,6 +57,10 @@ pub struct RunDebugConfig {
     pub debug_command: Option<Vec<String>>,
     #[serde(skip)]
     pub dap_id: DapId,
+    /// Keep the output of the process, to search it for problems once it
+    /// finishes.
+    #[serde(skip)]
+    pub collect_output: bool,
 }
 
 pub trait Request {
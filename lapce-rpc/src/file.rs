@@ -52,6 +52,7 @@ pub struct FileNodeViewData {
     pub path: PathBuf,
     pub is_dir: bool,
     pub open: bool,
+    pub ignored: bool,
     pub level: usize,
 }
 
@@ -61,6 +62,9 @@ pub struct FileNodeItem {
     pub is_dir: bool,
     pub read: bool,
     pub open: bool,
+    /// Whether version control ignores the path.
+    #[serde(default)]
+    pub ignored: bool,
     pub children: HashMap<PathBuf, FileNodeItem>,
     pub children_open_count: usize,
 }
@@ -119,6 +123,7 @@ pub fn sorted_children_mut(&mut self) -> Vec<&mut FileNodeItem> {
     /// #    is_dir: true,
     /// #    read: false,
     /// #    open: false,
+    /// #    ignored: false,
     /// #    children: HashMap::new(),
     /// #    children_open_count: 0,
     ///};
@@ -141,12 +146,36 @@ fn ancestors_rev<'a>(
         Some(ancestors.into_iter().rev())
     }
 
+    /// Whether this is the node above the root folders of a multi-root
+    /// workspace. It has an empty path, and its children are the root folders.
+    pub fn is_multi_root(&self) -> bool {
+        self.path.as_os_str().is_empty()
+    }
+
+    /// The root folder of a multi-root workspace that `path` is in. The deepest
+    /// one wins when the root folders are nested.
+    fn root_folder_path(&self, path: &Path) -> Option<PathBuf> {
+        self.children
+            .keys()
+            .filter(|root| path.starts_with(root))
+            .max_by_key(|root| root.components().count())
+            .cloned()
+    }
+
     pub fn get_file_node(&self, path: &Path) -> Option<&FileNodeItem> {
+        if self.is_multi_root() && !path.as_os_str().is_empty() {
+            let root = self.root_folder_path(path)?;
+            return self.children.get(&root)?.get_file_node(path);
+        }
         self.ancestors_rev(path)?
             .try_fold(self, |node, path| node.children.get(path))
     }
 
     pub fn get_file_node_mut(&mut self, path: &Path) -> Option<&mut FileNodeItem> {
+        if self.is_multi_root() && !path.as_os_str().is_empty() {
+            let root = self.root_folder_path(path)?;
+            return self.children.get_mut(&root)?.get_file_node_mut(path);
+        }
         self.ancestors_rev(path)?
             .try_fold(self, |node, path| node.children.get_mut(path))
     }
@@ -155,9 +184,7 @@ pub fn remove_child(&mut self, path: &Path) -> Option<FileNodeItem> {
         let parent = path.parent()?;
         let node = self.get_file_node_mut(parent)?;
         let node = node.children.remove(path)?;
-        for p in path.ancestors() {
-            self.update_node_count(p);
-        }
+        self.update_node_count_recursive(path);
 
         Some(node)
     }
@@ -172,13 +199,12 @@ pub fn add_child(&mut self, path: &Path, is_dir: bool) -> Option<()> {
                 is_dir,
                 read: false,
                 open: false,
+                ignored: false,
                 children: HashMap::new(),
                 children_open_count: 0,
             },
         );
-        for p in path.ancestors() {
-            self.update_node_count(p);
-        }
+        self.update_node_count_recursive(path);
 
         Some(())
     }
@@ -194,15 +220,17 @@ pub fn set_item_children(
             node.children = children;
         }
 
-        for p in path.ancestors() {
-            self.update_node_count(p);
-        }
+        self.update_node_count_recursive(path);
     }
 
     pub fn update_node_count_recursive(&mut self, path: &Path) {
         for current_path in path.ancestors() {
             self.update_node_count(current_path);
         }
+        if self.is_multi_root() {
+            // The ancestors of a path never include the empty path
+            self.update_node_count(Path::new(""));
+        }
     }
 
     pub fn update_node_count(&mut self, path: &Path) -> Option<()> {
@@ -241,6 +269,7 @@ pub fn append_view_slice(
                 path: self.path.clone(),
                 is_dir: self.is_dir,
                 open: self.open,
+                ignored: self.ignored,
                 level,
             });
         }
@@ -6,6 +6,12 @@
 
 use crate::counter::Counter;
 
+/// The version of the API plugins use to talk to the editor. It goes up when
+/// methods are added to it, and a plugin that needs a newer one than the editor
+/// has isn't started. Plugins get it in the `VOLT_API_VERSION` environment
+/// variable.
+pub const PLUGIN_API_VERSION: u32 = 2;
+
 #[derive(Eq, PartialEq, Hash, Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct PluginId(pub u64);
 
@@ -42,6 +48,77 @@ pub fn id(&self) -> VoltID {
     }
 }
 
+/// A language server that a plugin provides, started by the editor when a file of
+/// one of its languages is opened.
+#[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct VoltLanguageServer {
+    /// The program to run, either relative to the plugin's directory or the name
+    /// of one on the `PATH`. `${os}` and `${arch}` are replaced with the ones the
+    /// proxy runs on, so a plugin can bundle a binary for each platform.
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    pub languages: Vec<String>,
+}
+
+/// What a plugin is allowed to do outside of its own directory, from the
+/// `[permissions]` table of its `volt.toml`. The user is asked to grant them
+/// before the plugin runs for the first time.
+#[derive(Deserialize, Clone, Debug, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct VoltPermissions {
+    /// Folders the plugin can read and write. `${workspace}` stands for every
+    /// folder of the workspace.
+    #[serde(default)]
+    pub filesystem: Vec<String>,
+    /// The hosts the plugin can send requests to, or `*` for all of them.
+    #[serde(default)]
+    pub network: Vec<String>,
+    /// Whether the plugin can run programs, which includes language servers.
+    #[serde(default)]
+    pub process: bool,
+    /// The most memory the plugin can use, in megabytes.
+    pub memory: Option<usize>,
+    /// The longest the plugin can take to handle a message, in milliseconds.
+    pub cpu_time: Option<u64>,
+}
+
+impl VoltPermissions {
+    /// The permissions of plugins that don't declare any, which are the ones all
+    /// plugins had before permissions could be declared.
+    pub fn unrestricted() -> Self {
+        Self {
+            network: vec!["*".to_string()],
+            process: true,
+            ..Default::default()
+        }
+    }
+
+    /// Whether the plugin can do anything the user has to agree to.
+    pub fn needs_consent(&self) -> bool {
+        !self.filesystem.is_empty() || !self.network.is_empty() || self.process
+    }
+
+    /// Describes each permission, for asking the user to grant them.
+    pub fn describe(&self) -> Vec<String> {
+        let mut descriptions: Vec<String> = self
+            .filesystem
+            .iter()
+            .map(|scope| format!("Read and write files in {scope}"))
+            .collect();
+        if self.network.iter().any(|host| host == "*") {
+            descriptions.push("Connect to any host on the internet".to_string());
+        } else if !self.network.is_empty() {
+            descriptions.push(format!("Connect to {}", self.network.join(", ")));
+        }
+        if self.process {
+            descriptions.push("Run programs on your computer".to_string());
+        }
+        descriptions
+    }
+}
+
 #[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "kebab-case")]
 pub struct VoltActivation {
@@ -71,6 +148,10 @@ pub struct VoltMetadata {
     pub dir: Option<PathBuf>,
     pub activation: Option<VoltActivation>,
     pub config: Option<HashMap<String, VoltConfig>>,
+    /// The lowest [`PLUGIN_API_VERSION`] the plugin works with.
+    pub api_version: Option<u32>,
+    pub language_servers: Option<Vec<VoltLanguageServer>>,
+    pub permissions: Option<VoltPermissions>,
 }
 
 impl VoltMetadata {
@@ -90,6 +171,74 @@ pub fn info(&self) -> VoltInfo {
             updated_at_ts: 0,
         }
     }
+
+    /// The permissions the plugin asks for.
+    pub fn permissions(&self) -> VoltPermissions {
+        self.permissions
+            .clone()
+            .unwrap_or_else(VoltPermissions::unrestricted)
+    }
+
+    /// Whether opening a file of the language starts the plugin.
+    pub fn activated_by_language(&self, language_id: &str) -> bool {
+        let activation = self
+            .activation
+            .as_ref()
+            .and_then(|a| a.language.as_ref())
+            .is_some_and(|languages| languages.iter().any(|l| l == language_id));
+        activation
+            || self
+                .language_servers
+                .iter()
+                .flatten()
+                .any(|server| server.languages.iter().any(|l| l == language_id))
+    }
+}
+
+/// A command a plugin added to the palette.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginCommand {
+    pub volt: VoltID,
+    /// What the plugin is told to execute when the command is run.
+    pub id: String,
+    pub title: String,
+}
+
+/// An item a plugin shows in the status bar.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginStatusItem {
+    pub volt: VoltID,
+    pub id: String,
+    pub text: String,
+    /// The id of a command of the plugin that is run when the item is clicked.
+    pub command: Option<String>,
+    #[serde(default)]
+    pub alignment: StatusItemAlignment,
+}
+
+/// Which end of the status bar a plugin's item is shown at.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StatusItemAlignment {
+    #[default]
+    Left,
+    Right,
+}
+
+/// How far a language server a plugin started has got.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LanguageServerStatus {
+    Starting,
+    Running,
+    /// It exited, or couldn't be initialized.
+    Stopped,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginQuickPickItem {
+    pub label: String,
+    pub description: Option<String>,
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
@@ -142,7 +291,10 @@ fn from(volt: &VoltInfo) -> Self {
 
 #[cfg(test)]
 mod tests {
-    use super::{VoltID, VoltInfo, VoltMetadata};
+    use super::{
+        PluginStatusItem, StatusItemAlignment, VoltID, VoltInfo, VoltMetadata,
+        VoltPermissions,
+    };
 
     #[test]
     fn test_volt_metadata_id() {
@@ -160,6 +312,9 @@ fn test_volt_metadata_id() {
             dir: std::env::current_dir().unwrap().canonicalize().ok(),
             activation: None,
             config: None,
+            api_version: None,
+            language_servers: None,
+            permissions: None,
         };
         let volt_id = VoltID {
             author: "Author".to_string(),
@@ -198,6 +353,9 @@ fn test_volt_metadata_info() {
             dir: std::env::current_dir().unwrap().canonicalize().ok(),
             activation: None,
             config: None,
+            api_version: None,
+            language_servers: None,
+            permissions: None,
         };
         let volt_info = VoltInfo {
             name: "plugin".to_string(),
@@ -234,4 +392,51 @@ fn test_volt_info_id() {
         assert_eq!(<&VoltInfo as Into<VoltID>>::into(&volt_info), volt_id);
         assert_eq!(<VoltInfo as Into<VoltID>>::into(volt_info), volt_id);
     }
+
+    #[test]
+    fn test_volt_permissions() {
+        let permissions: VoltPermissions = serde_json::from_str(
+            r#"{
+                "filesystem": ["${workspace}"],
+                "network": ["api.github.com"],
+                "memory": 64
+            }"#,
+        )
+        .unwrap();
+        assert!(!permissions.process);
+        assert_eq!(permissions.memory, Some(64));
+        assert!(permissions.needs_consent());
+        assert_eq!(
+            permissions.describe(),
+            vec![
+                "Read and write files in ${workspace}".to_string(),
+                "Connect to api.github.com".to_string(),
+            ]
+        );
+
+        let limits_only = VoltPermissions {
+            cpu_time: Some(100),
+            ..Default::default()
+        };
+        assert!(!limits_only.needs_consent());
+        assert!(VoltPermissions::unrestricted().needs_consent());
+    }
+
+    #[test]
+    fn test_status_item_alignment() {
+        let item: PluginStatusItem = serde_json::from_str(
+            r#"{
+                "volt": {"author": "author", "name": "plugin"},
+                "id": "status",
+                "text": "Ready",
+                "command": null
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(item.alignment, StatusItemAlignment::Left);
+
+        let alignment: StatusItemAlignment =
+            serde_json::from_str(r#""right""#).unwrap();
+        assert_eq!(alignment, StatusItemAlignment::Right);
+    }
 }
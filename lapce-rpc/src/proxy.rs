@@ -11,21 +11,23 @@
 use indexmap::IndexMap;
 use lapce_xi_rope::RopeDelta;
 use lsp_types::{
-    request::GotoTypeDefinitionResponse, CodeAction, CodeActionResponse,
-    CompletionItem, Diagnostic, DocumentSymbolResponse, GotoDefinitionResponse,
-    Hover, InlayHint, Location, Position, PrepareRenameResponse, SelectionRange,
-    SymbolInformation, TextDocumentItem, TextEdit, WorkspaceEdit,
+    request::GotoTypeDefinitionResponse, CodeAction, CodeActionKind,
+    CodeActionResponse, Color, ColorInformation, ColorPresentation, CompletionItem,
+    Diagnostic, DocumentHighlight, DocumentLink, DocumentSymbolResponse,
+    GotoDefinitionResponse, Hover, InlayHint, Location, LocationLink, Position,
+    PrepareRenameResponse, ProgressToken, Range, SelectionRange, SymbolInformation,
+    TextDocumentItem, TextEdit, WorkspaceEdit,
 };
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 
 use super::plugin::VoltID;
 use crate::{
-    buffer::BufferId,
+    buffer::{BufferId, LineEnding},
     dap_types::{self, DapId, RunDebugConfig, SourceBreakpoint, ThreadId},
     file::{FileNodeItem, PathObject},
-    plugin::{PluginId, VoltInfo, VoltMetadata},
-    source_control::FileDiff,
+    plugin::{PluginId, VoltInfo, VoltMetadata, VoltPermissions},
+    source_control::{FileCommit, FileDiff},
     style::SemanticStyles,
     terminal::{TermId, TerminalProfile},
     RequestId, RpcError, RpcMessage,
@@ -38,13 +40,29 @@ pub enum ProxyRpc {
     Shutdown,
 }
 
+/// The version of the messages between the editor and the proxy. It goes up when
+/// they change in a way that an editor and a proxy of different versions wouldn't
+/// understand each other.
+pub const PROXY_PROTOCOL_VERSION: u32 = 1;
+
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum ProxyStatus {
     Connecting,
     Connected,
+    /// The connection to a remote proxy dropped, and it's being started again.
+    Reconnecting,
     Disconnected,
 }
 
+/// A document the editor has open, with the changes that haven't been saved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestoredBuffer {
+    pub buffer_id: BufferId,
+    pub path: PathBuf,
+    pub rev: u64,
+    pub content: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchMatch {
     pub line: usize,
@@ -53,6 +71,32 @@ pub struct SearchMatch {
     pub line_content: String,
 }
 
+/// The code of the error a copy or move fails with when something is already at
+/// the destination and `overwrite` isn't set.
+pub const PATH_EXISTS_ERROR_CODE: i64 = 1;
+
+/// Something rust-analyzer can run in the document, like a test or a `main`
+/// function, from its `experimental/runnables` extension.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Runnable {
+    pub label: String,
+    pub location: Option<LocationLink>,
+    /// `cargo` for the runnables that are run with Cargo
+    pub kind: String,
+    pub args: RunnableArgs,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RunnableArgs {
+    pub workspace_root: Option<PathBuf>,
+    pub cargo_args: Vec<String>,
+    pub cargo_extra_args: Vec<String>,
+    /// The arguments after `--`, like the name of the test to run
+    pub executable_args: Vec<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 #[serde(tag = "method", content = "params")]
@@ -61,9 +105,24 @@ pub enum ProxyRequest {
         buffer_id: BufferId,
         path: PathBuf,
     },
+    /// The next chunk of a large file, from `offset` on.
+    BufferChunk {
+        path: PathBuf,
+        offset: usize,
+    },
     BufferHead {
         path: PathBuf,
     },
+    GitFileHistory {
+        path: PathBuf,
+    },
+    GitFileRevision {
+        path: PathBuf,
+        revision: String,
+    },
+    ReadFile {
+        path: PathBuf,
+    },
     GlobalSearch {
         pattern: String,
         case_sensitive: bool,
@@ -98,6 +157,10 @@ pub enum ProxyRequest {
         path: PathBuf,
         position: Position,
     },
+    GetDocumentHighlights {
+        path: PathBuf,
+        position: Position,
+    },
     GetDefinition {
         request_id: usize,
         path: PathBuf,
@@ -111,6 +174,24 @@ pub enum ProxyRequest {
     GetInlayHints {
         path: PathBuf,
     },
+    GetDocumentColors {
+        path: PathBuf,
+    },
+    GetColorPresentations {
+        path: PathBuf,
+        color: Color,
+        range: Range,
+    },
+    GetDocumentLinks {
+        path: PathBuf,
+    },
+    GetRunnables {
+        path: PathBuf,
+    },
+    /// Build the executable of a runnable, to debug it.
+    BuildRunnable {
+        args: RunnableArgs,
+    },
     GetSemanticTokens {
         path: PathBuf,
     },
@@ -128,6 +209,13 @@ pub enum ProxyRequest {
         position: Position,
         diagnostics: Vec<Diagnostic>,
     },
+    /// The code actions for `range`, only of the kinds in `only` if given.
+    GetRangeCodeActions {
+        path: PathBuf,
+        range: Range,
+        diagnostics: Vec<Diagnostic>,
+        only: Option<Vec<CodeActionKind>>,
+    },
     GetDocumentSymbols {
         path: PathBuf,
     },
@@ -145,11 +233,18 @@ pub enum ProxyRequest {
     ReadDir {
         path: PathBuf,
     },
+    /// The first of the paths that is of a file.
+    FindFile {
+        paths: Vec<PathBuf>,
+    },
     Save {
         rev: u64,
         path: PathBuf,
         /// Whether to create the parent directories if they do not exist.
         create_parents: bool,
+        /// Whether to save to the target of a symlink rather than replacing the
+        /// link.
+        write_through_symlink: bool,
     },
     SaveBufferAs {
         buffer_id: BufferId,
@@ -168,6 +263,9 @@ pub enum ProxyRequest {
     TrashPath {
         path: PathBuf,
     },
+    MakeWritable {
+        path: PathBuf,
+    },
     DuplicatePath {
         existing_path: PathBuf,
         new_path: PathBuf,
@@ -176,6 +274,18 @@ pub enum ProxyRequest {
         from: PathBuf,
         to: PathBuf,
     },
+    /// Copy a file, or a folder with everything in it.
+    CopyPath {
+        from: PathBuf,
+        to: PathBuf,
+        overwrite: bool,
+    },
+    /// Move a file or a folder, also across file systems.
+    MovePath {
+        from: PathBuf,
+        to: PathBuf,
+        overwrite: bool,
+    },
     DapVariable {
         dap_id: DapId,
         reference: usize,
@@ -192,10 +302,22 @@ pub enum ProxyRequest {
 pub enum ProxyNotification {
     Initialize {
         workspace: Option<PathBuf>,
+        /// The root folders added to the workspace besides `workspace`.
+        #[serde(default)]
+        folders: Vec<PathBuf>,
         disabled_volts: Vec<VoltID>,
+        /// The permissions the user has granted to each plugin.
+        #[serde(default)]
+        granted_permissions: Vec<(VoltID, VoltPermissions)>,
         plugin_configurations: HashMap<String, HashMap<String, serde_json::Value>>,
         window_id: usize,
         tab_id: usize,
+        /// Proxies from before the protocol was versioned don't send this.
+        #[serde(default)]
+        protocol_version: u32,
+        /// Whether large messages should be compressed, for a remote proxy.
+        #[serde(default)]
+        compression: bool,
     },
     OpenFileChanged {
         path: PathBuf,
@@ -220,6 +342,20 @@ pub enum ProxyNotification {
         delta: RopeDelta,
         rev: u64,
     },
+    /// Sent to a proxy that was started again after the connection to it
+    /// dropped, with the documents the editor still has open.
+    RestoreBuffers {
+        buffers: Vec<RestoredBuffer>,
+    },
+    SetLineEnding {
+        path: PathBuf,
+        line_ending: LineEnding,
+    },
+    /// The language of the document was picked by the user.
+    SetLanguage {
+        path: PathBuf,
+        language: String,
+    },
     UpdatePluginConfigs {
         configs: HashMap<String, HashMap<String, serde_json::Value>>,
     },
@@ -242,6 +378,37 @@ pub enum ProxyNotification {
     EnableVolt {
         volt: VoltInfo,
     },
+    /// The user agreed to the permissions the plugin asks for, so it can start.
+    GrantVoltPermissions {
+        volt: VoltMetadata,
+    },
+    /// The plugin is stopped, and asks for its permissions again the next time
+    /// it would start.
+    RevokeVoltPermissions {
+        volt: VoltInfo,
+    },
+    /// A command a plugin added to the palette was run.
+    ExecutePluginCommand {
+        volt: VoltID,
+        id: String,
+    },
+    /// The answer to a `ShowQuickPick` notification, with the index of the
+    /// picked item.
+    PluginQuickPickResult {
+        id: u64,
+        index: Option<usize>,
+    },
+    /// The answer to a `ShowMessageRequest` notification, with the index of the
+    /// clicked action, or `None` when the message was closed.
+    PluginMessageActionResult {
+        id: u64,
+        index: Option<usize>,
+    },
+    /// The user cancelled a task shown with a `WorkDoneProgress` notification,
+    /// of a language server or of the proxy.
+    CancelWorkDoneProgress {
+        token: ProgressToken,
+    },
     GitCommit {
         message: String,
         diffs: Vec<FileDiff>,
@@ -249,6 +416,11 @@ pub enum ProxyNotification {
     GitCheckout {
         reference: String,
     },
+    GitCreateBranch {
+        name: String,
+    },
+    GitPull {},
+    GitPush {},
     GitDiscardFilesChanges {
         files: Vec<PathBuf>,
     },
@@ -322,14 +494,32 @@ pub enum ProxyResponse {
     NewBufferResponse {
         content: String,
         read_only: bool,
+        large_file: bool,
+        line_ending: LineEnding,
+        /// The content is only the first chunk of a large file, the rest is
+        /// fetched with `BufferChunk`.
+        more: bool,
+    },
+    BufferChunkResponse {
+        content: String,
+        more: bool,
     },
     BufferHeadResponse {
         version: String,
         content: String,
     },
+    GitFileHistoryResponse {
+        commits: Vec<FileCommit>,
+    },
+    ReadFileResponse {
+        content: Vec<u8>,
+    },
     ReadDirResponse {
         items: Vec<FileNodeItem>,
     },
+    FindFileResponse {
+        path: Option<PathBuf>,
+    },
     CompletionResolveResponse {
         item: Box<CompletionItem>,
     },
@@ -351,6 +541,9 @@ pub enum ProxyResponse {
     GetReferencesResponse {
         references: Vec<Location>,
     },
+    GetDocumentHighlightsResponse {
+        highlights: Vec<DocumentHighlight>,
+    },
     GetCodeActionsResponse {
         plugin_id: PluginId,
         resp: CodeActionResponse,
@@ -373,6 +566,21 @@ pub enum ProxyResponse {
     GetInlayHints {
         hints: Vec<InlayHint>,
     },
+    GetDocumentColors {
+        colors: Vec<ColorInformation>,
+    },
+    GetColorPresentations {
+        presentations: Vec<ColorPresentation>,
+    },
+    GetDocumentLinks {
+        links: Vec<DocumentLink>,
+    },
+    GetRunnables {
+        runnables: Vec<Runnable>,
+    },
+    BuildRunnableResponse {
+        executable: String,
+    },
     GetSemanticTokens {
         styles: SemanticStyles,
     },
@@ -527,6 +735,18 @@ pub fn git_checkout(&self, reference: String) {
         self.notification(ProxyNotification::GitCheckout { reference });
     }
 
+    pub fn git_create_branch(&self, name: String) {
+        self.notification(ProxyNotification::GitCreateBranch { name });
+    }
+
+    pub fn git_pull(&self) {
+        self.notification(ProxyNotification::GitPull {});
+    }
+
+    pub fn git_push(&self) {
+        self.notification(ProxyNotification::GitPush {});
+    }
+
     pub fn install_volt(&self, volt: VoltInfo) {
         self.notification(ProxyNotification::InstallVolt { volt });
     }
@@ -552,20 +772,28 @@ pub fn shutdown(&self) {
         let _ = self.tx.send(ProxyRpc::Shutdown);
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn initialize(
         &self,
         workspace: Option<PathBuf>,
+        folders: Vec<PathBuf>,
         disabled_volts: Vec<VoltID>,
+        granted_permissions: Vec<(VoltID, VoltPermissions)>,
         plugin_configurations: HashMap<String, HashMap<String, serde_json::Value>>,
         window_id: usize,
         tab_id: usize,
+        compression: bool,
     ) {
         self.notification(ProxyNotification::Initialize {
             workspace,
+            folders,
             disabled_volts,
+            granted_permissions,
             plugin_configurations,
             window_id,
             tab_id,
+            protocol_version: PROXY_PROTOCOL_VERSION,
+            compression,
         });
     }
 
@@ -626,10 +854,40 @@ pub fn new_buffer(
         self.request_async(ProxyRequest::NewBuffer { buffer_id, path }, f);
     }
 
+    pub fn buffer_chunk(
+        &self,
+        path: PathBuf,
+        offset: usize,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::BufferChunk { path, offset }, f);
+    }
+
     pub fn get_buffer_head(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
         self.request_async(ProxyRequest::BufferHead { path }, f);
     }
 
+    /// The commits that changed the file, newest first.
+    pub fn git_file_history(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitFileHistory { path }, f);
+    }
+
+    /// The content of the file at the given revision. Responds like
+    /// [`get_buffer_head`](Self::get_buffer_head).
+    pub fn git_file_revision(
+        &self,
+        path: PathBuf,
+        revision: String,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitFileRevision { path, revision }, f);
+    }
+
+    /// Read the raw bytes of a file, for content that can't be opened as a text buffer.
+    pub fn read_file(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::ReadFile { path }, f);
+    }
+
     pub fn create_file(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
         self.request_async(ProxyRequest::CreateFile { path }, f);
     }
@@ -642,6 +900,10 @@ pub fn trash_path(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
         self.request_async(ProxyRequest::TrashPath { path }, f);
     }
 
+    pub fn make_writable(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::MakeWritable { path }, f);
+    }
+
     pub fn duplicate_path(
         &self,
         existing_path: PathBuf,
@@ -666,6 +928,40 @@ pub fn rename_path(
         self.request_async(ProxyRequest::RenamePath { from, to }, f);
     }
 
+    pub fn copy_path(
+        &self,
+        from: PathBuf,
+        to: PathBuf,
+        overwrite: bool,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::CopyPath {
+                from,
+                to,
+                overwrite,
+            },
+            f,
+        );
+    }
+
+    pub fn move_path(
+        &self,
+        from: PathBuf,
+        to: PathBuf,
+        overwrite: bool,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::MovePath {
+                from,
+                to,
+                overwrite,
+            },
+            f,
+        );
+    }
+
     pub fn save_buffer_as(
         &self,
         buffer_id: BufferId,
@@ -711,6 +1007,7 @@ pub fn save(
         rev: u64,
         path: PathBuf,
         create_parents: bool,
+        write_through_symlink: bool,
         f: impl ProxyCallback + 'static,
     ) {
         self.request_async(
@@ -718,6 +1015,7 @@ pub fn save(
                 rev,
                 path,
                 create_parents,
+                write_through_symlink,
             },
             f,
         );
@@ -740,6 +1038,10 @@ pub fn read_dir(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
         self.request_async(ProxyRequest::ReadDir { path }, f);
     }
 
+    pub fn find_file(&self, paths: Vec<PathBuf>, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::FindFile { paths }, f);
+    }
+
     pub fn completion_resolve(
         &self,
         plugin_id: PluginId,
@@ -830,6 +1132,18 @@ pub fn get_references(
         self.request_async(ProxyRequest::GetReferences { path, position }, f);
     }
 
+    pub fn get_document_highlights(
+        &self,
+        path: PathBuf,
+        position: Position,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::GetDocumentHighlights { path, position },
+            f,
+        );
+    }
+
     pub fn get_code_actions(
         &self,
         path: PathBuf,
@@ -847,6 +1161,25 @@ pub fn get_code_actions(
         );
     }
 
+    pub fn get_range_code_actions(
+        &self,
+        path: PathBuf,
+        range: Range,
+        diagnostics: Vec<Diagnostic>,
+        only: Option<Vec<CodeActionKind>>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::GetRangeCodeActions {
+                path,
+                range,
+                diagnostics,
+                only,
+            },
+            f,
+        );
+    }
+
     pub fn get_document_formatting(
         &self,
         path: PathBuf,
@@ -917,10 +1250,90 @@ pub fn get_inlay_hints(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
         self.request_async(ProxyRequest::GetInlayHints { path }, f);
     }
 
+    pub fn get_document_colors(
+        &self,
+        path: PathBuf,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GetDocumentColors { path }, f);
+    }
+
+    pub fn get_color_presentations(
+        &self,
+        path: PathBuf,
+        color: Color,
+        range: Range,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::GetColorPresentations { path, color, range },
+            f,
+        );
+    }
+
+    pub fn get_document_links(
+        &self,
+        path: PathBuf,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GetDocumentLinks { path }, f);
+    }
+
+    pub fn get_runnables(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GetRunnables { path }, f);
+    }
+
+    pub fn build_runnable(
+        &self,
+        args: RunnableArgs,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::BuildRunnable { args }, f);
+    }
+
     pub fn update(&self, path: PathBuf, delta: RopeDelta, rev: u64) {
         self.notification(ProxyNotification::Update { path, delta, rev });
     }
 
+    pub fn restore_buffers(&self, buffers: Vec<RestoredBuffer>) {
+        self.notification(ProxyNotification::RestoreBuffers { buffers });
+    }
+
+    pub fn set_line_ending(&self, path: PathBuf, line_ending: LineEnding) {
+        self.notification(ProxyNotification::SetLineEnding { path, line_ending });
+    }
+
+    pub fn set_language(&self, path: PathBuf, language: String) {
+        self.notification(ProxyNotification::SetLanguage { path, language });
+    }
+
+    pub fn grant_volt_permissions(&self, volt: VoltMetadata) {
+        self.notification(ProxyNotification::GrantVoltPermissions { volt });
+    }
+
+    pub fn revoke_volt_permissions(&self, volt: VoltInfo) {
+        self.notification(ProxyNotification::RevokeVoltPermissions { volt });
+    }
+
+    pub fn execute_plugin_command(&self, volt: VoltID, id: String) {
+        self.notification(ProxyNotification::ExecutePluginCommand { volt, id });
+    }
+
+    pub fn plugin_quick_pick_result(&self, id: u64, index: Option<usize>) {
+        self.notification(ProxyNotification::PluginQuickPickResult { id, index });
+    }
+
+    pub fn plugin_message_action_result(&self, id: u64, index: Option<usize>) {
+        self.notification(ProxyNotification::PluginMessageActionResult {
+            id,
+            index,
+        });
+    }
+
+    pub fn cancel_work_done_progress(&self, token: ProgressToken) {
+        self.notification(ProxyNotification::CancelWorkDoneProgress { token });
+    }
+
     pub fn update_plugin_configs(
         &self,
         configs: HashMap<String, HashMap<String, serde_json::Value>>,
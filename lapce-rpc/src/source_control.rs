@@ -10,6 +10,16 @@ pub struct DiffInfo {
     pub diffs: Vec<FileDiff>,
 }
 
+/// A commit that changed a file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileCommit {
+    pub id: String,
+    pub summary: String,
+    pub author: String,
+    /// Seconds since the unix epoch.
+    pub time: i64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum FileDiff {
     Modified(PathBuf),
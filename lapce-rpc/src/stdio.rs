@@ -1,20 +1,71 @@
 use std::{
-    io::{self, BufRead, Write},
+    collections::HashMap,
+    io::{self, BufRead, Read, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     thread,
 };
 
 use anyhow::Result;
+use base64::{engine::general_purpose, Engine as _};
 use crossbeam_channel::{Receiver, Sender};
-use serde::{de::DeserializeOwned, Serialize};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::{json, Value};
 
 use crate::{RpcError, RpcMessage, RpcObject};
 
+/// Messages longer than this are compressed, on a transport that compresses.
+const COMPRESSION_THRESHOLD: usize = 16 * 1024;
+/// The most compressed data one line carries. Longer messages are split into
+/// several chunks.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// A line with part of a compressed message. The data of all the chunks with the
+/// same id, in order, is the message compressed with gzip, in base64.
+#[derive(Serialize, Deserialize)]
+struct Chunk {
+    chunk: u64,
+    data: String,
+    more: bool,
+}
+
 pub fn stdio_transport<W, R, Req1, Notif1, Resp1, Req2, Notif2, Resp2>(
+    writer: W,
+    writer_receiver: Receiver<RpcMessage<Req2, Notif2, Resp2>>,
+    reader: R,
+    reader_sender: Sender<RpcMessage<Req1, Notif1, Resp1>>,
+) where
+    W: 'static + Write + Send,
+    R: 'static + BufRead + Send,
+    Req1: 'static + Serialize + DeserializeOwned + Send + Sync,
+    Notif1: 'static + Serialize + DeserializeOwned + Send + Sync,
+    Resp1: 'static + Serialize + DeserializeOwned + Send + Sync,
+    Req2: 'static + Serialize + DeserializeOwned + Send + Sync,
+    Notif2: 'static + Serialize + DeserializeOwned + Send + Sync,
+    Resp2: 'static + Serialize + DeserializeOwned + Send + Sync,
+{
+    compressed_stdio_transport(
+        writer,
+        writer_receiver,
+        reader,
+        reader_sender,
+        Arc::new(AtomicBool::new(false)),
+    );
+}
+
+/// Like [`stdio_transport`], but large messages are compressed and sent in chunks
+/// while `compress` is set, which is for slow links such as to a remote proxy.
+/// All the chunks of a message are written before the next message, so messages
+/// arrive in the order they were sent. Compressed messages are always read.
+pub fn compressed_stdio_transport<W, R, Req1, Notif1, Resp1, Req2, Notif2, Resp2>(
     mut writer: W,
     writer_receiver: Receiver<RpcMessage<Req2, Notif2, Resp2>>,
     mut reader: R,
     reader_sender: Sender<RpcMessage<Req1, Notif1, Resp1>>,
+    compress: Arc<AtomicBool>,
 ) where
     W: 'static + Write + Send,
     R: 'static + BufRead + Send,
@@ -25,21 +76,77 @@ pub fn stdio_transport<W, R, Req1, Notif1, Resp1, Req2, Notif2, Resp2>(
     Notif2: 'static + Serialize + DeserializeOwned + Send + Sync,
     Resp2: 'static + Serialize + DeserializeOwned + Send + Sync,
 {
-    thread::spawn(move || {
-        for value in writer_receiver {
-            if write_msg(&mut writer, value).is_err() {
-                return;
-            };
+    thread::spawn(move || -> io::Result<()> {
+        let mut next_chunk = 0;
+        for msg in writer_receiver {
+            let line = encode_msg(msg)?;
+            if compress.load(Ordering::Relaxed) && line.len() > COMPRESSION_THRESHOLD
+            {
+                next_chunk += 1;
+                for chunk in compress_msg(&line, next_chunk)? {
+                    write_line(&mut writer, &chunk)?;
+                }
+            } else {
+                write_line(&mut writer, &line)?;
+            }
         }
+        Ok(())
     });
     thread::spawn(move || -> Result<()> {
+        let mut chunks: HashMap<u64, String> = HashMap::new();
         loop {
-            let msg = read_msg(&mut reader)?;
-            reader_sender.send(msg)?;
+            let mut buf = String::new();
+            let _s = reader.read_line(&mut buf)?;
+            let mut value: Value = serde_json::from_str(&buf)?;
+            if value.get("chunk").is_some() {
+                let chunk: Chunk = serde_json::from_value(value)?;
+                chunks.entry(chunk.chunk).or_default().push_str(&chunk.data);
+                if chunk.more {
+                    continue;
+                }
+                let data = chunks.remove(&chunk.chunk).unwrap_or_default();
+                value = decompress_msg(&data)?;
+            }
+            reader_sender.send(parse_msg(value)?)?;
         }
     });
 }
 
+/// Compress the line of a message, and split it into the lines of its chunks.
+fn compress_msg(line: &str, id: u64) -> io::Result<Vec<String>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+    encoder.write_all(line.as_bytes())?;
+    let data = general_purpose::STANDARD.encode(encoder.finish()?);
+
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < data.len() {
+        let end = (start + CHUNK_SIZE).min(data.len());
+        let chunk = Chunk {
+            chunk: id,
+            data: data[start..end].to_string(),
+            more: end < data.len(),
+        };
+        chunks.push(format!("{}\n", serde_json::to_string(&chunk)?));
+        start = end;
+    }
+    Ok(chunks)
+}
+
+fn decompress_msg(data: &str) -> io::Result<Value> {
+    let bytes = general_purpose::STANDARD
+        .decode(data)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let mut line = String::new();
+    GzDecoder::new(bytes.as_slice()).read_to_string(&mut line)?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+fn write_line<W: Write>(out: &mut W, line: &str) -> io::Result<()> {
+    out.write_all(line.as_bytes())?;
+    out.flush()
+}
+
 pub fn write_msg<W, Req, Notif, Resp>(
     out: &mut W,
     msg: RpcMessage<Req, Notif, Resp>,
@@ -49,6 +156,19 @@ pub fn write_msg<W, Req, Notif, Resp>(
     Req: Serialize,
     Notif: Serialize,
     Resp: Serialize,
+{
+    let msg = encode_msg(msg)?;
+    write_line(out, &msg)
+}
+
+/// The line a message is sent as.
+fn encode_msg<Req, Notif, Resp>(
+    msg: RpcMessage<Req, Notif, Resp>,
+) -> io::Result<String>
+where
+    Req: Serialize,
+    Notif: Serialize,
+    Resp: Serialize,
 {
     let value = match msg {
         RpcMessage::Request(id, req) => {
@@ -72,10 +192,7 @@ pub fn write_msg<W, Req, Notif, Resp>(
             })
         }
     };
-    let msg = format!("{}\n", serde_json::to_string(&value)?);
-    out.write_all(msg.as_bytes())?;
-    out.flush()?;
-    Ok(())
+    Ok(format!("{}\n", serde_json::to_string(&value)?))
 }
 
 pub fn read_msg<R, Req, Notif, Resp>(
@@ -90,6 +207,17 @@ pub fn read_msg<R, Req, Notif, Resp>(
     let mut buf = String::new();
     let _s = inp.read_line(&mut buf)?;
     let value: Value = serde_json::from_str(&buf)?;
+    parse_msg(value)
+}
+
+fn parse_msg<Req, Notif, Resp>(
+    value: Value,
+) -> io::Result<RpcMessage<Req, Notif, Resp>>
+where
+    Req: DeserializeOwned,
+    Notif: DeserializeOwned,
+    Resp: DeserializeOwned,
+{
     let object = RpcObject(value);
     let is_response = object.is_response();
     let msg = if is_response {
@@ -121,3 +249,38 @@ pub fn read_msg<R, Req, Notif, Resp>(
     };
     Ok(msg)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compressed_chunks() {
+        // Numbers that don't repeat, so they don't compress into a single chunk
+        let numbers: Vec<String> = (0..100_000u64)
+            .map(|i| (i.wrapping_mul(6364136223846793005) >> 33).to_string())
+            .collect();
+        let line = format!("{}\n", numbers.join(","));
+        let chunks = compress_msg(&line, 1).unwrap();
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|chunk| chunk.ends_with('\n')));
+
+        let mut data = String::new();
+        let mut more = true;
+        for chunk in chunks {
+            assert!(more);
+            let chunk: Chunk = serde_json::from_str(&chunk).unwrap();
+            assert_eq!(chunk.chunk, 1);
+            data.push_str(&chunk.data);
+            more = chunk.more;
+        }
+        assert!(!more);
+
+        let bytes = general_purpose::STANDARD.decode(data).unwrap();
+        let mut decompressed = String::new();
+        GzDecoder::new(bytes.as_slice())
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, line);
+    }
+}
@@ -4,7 +4,7 @@
 
 pub type LineStyles = HashMap<usize, Arc<Vec<LineStyle>>>;
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct LineStyle {
     pub start: usize,
     pub end: usize,